@@ -0,0 +1,210 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::utils::parse_emoji_id_or_public_key;
+use std::str::FromStr;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+
+/// The header row of a batch payout file. It is optional in the input file and always written to the report.
+pub const PAYOUT_FILE_HEADER: &str = "destination,amount,reference";
+
+/// A single payment read from a batch payout file
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayoutRow {
+    /// The line of the payout file the payment was read from
+    pub line: usize,
+    pub destination: CommsPublicKey,
+    pub amount: MicroTari,
+    /// Free text identifying the payment, e.g. a withdrawal id. It is used as the transaction message.
+    pub reference: String,
+}
+
+/// The outcome of a single payment of a batch payout
+#[derive(Clone, Debug)]
+pub enum PayoutOutcome {
+    /// The transaction was sent to the destination
+    Sent(u64),
+    /// The destination was not online and is being discovered on the network, the transaction is sent once it is found
+    DiscoveryInProgress(u64),
+    /// No transaction could be created for the payment
+    Failed(String),
+}
+
+/// Parse the contents of a batch payout file, which holds one `destination,amount,reference` row per line. The
+/// destination is a public key or emoji id and the amount is in µT unless it is suffixed with T. Blank lines, lines
+/// starting with `#` and a header row are skipped. Every row is validated and all of the errors are returned together,
+/// so that a file is only paid out once all of its rows are correct.
+pub fn parse_payout_file(contents: &str) -> Result<Vec<PayoutRow>, Vec<String>> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.eq_ignore_ascii_case(PAYOUT_FILE_HEADER) {
+            continue;
+        }
+        // The reference is the last column, so it may contain commas
+        let columns = line.splitn(3, ',').map(str::trim).collect::<Vec<_>>();
+        if columns.len() < 2 {
+            errors.push(format!(
+                "Line {}: expected `destination,amount,reference`, found `{}`",
+                line_number, line
+            ));
+            continue;
+        }
+        let destination = match parse_emoji_id_or_public_key(columns[0]) {
+            Some(destination) => destination,
+            None => {
+                errors.push(format!(
+                    "Line {}: `{}` is not a valid public key or emoji id",
+                    line_number, columns[0]
+                ));
+                continue;
+            },
+        };
+        let amount = match MicroTari::from_str(columns[1]) {
+            Ok(amount) if amount > MicroTari::from(0) => amount,
+            Ok(_) => {
+                errors.push(format!("Line {}: the amount must be greater than zero", line_number));
+                continue;
+            },
+            Err(e) => {
+                errors.push(format!(
+                    "Line {}: `{}` is not a valid amount: {}",
+                    line_number, columns[1], e
+                ));
+                continue;
+            },
+        };
+        rows.push(PayoutRow {
+            line: line_number,
+            destination,
+            amount,
+            reference: columns.get(2).map(|r| r.to_string()).unwrap_or_default(),
+        });
+    }
+
+    if rows.is_empty() && errors.is_empty() {
+        errors.push("The payout file does not contain any payments".to_string());
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Write the report of a batch payout as CSV, with the transaction id and status of every row of the payout file
+pub fn format_payout_report(results: &[(PayoutRow, PayoutOutcome)]) -> String {
+    let mut report = format!("{},tx_id,status\n", PAYOUT_FILE_HEADER);
+    for (row, outcome) in results {
+        let (tx_id, status) = match outcome {
+            PayoutOutcome::Sent(tx_id) => (tx_id.to_string(), "sent".to_string()),
+            PayoutOutcome::DiscoveryInProgress(tx_id) => (tx_id.to_string(), "discovery in progress".to_string()),
+            PayoutOutcome::Failed(reason) => (String::new(), format!("failed: {}", reason)),
+        };
+        report.push_str(&format!(
+            "{},{}uT,{},{},{}\n",
+            row.destination,
+            row.amount.as_micro_tari(),
+            csv_field(&row.reference),
+            tx_id,
+            csv_field(&status)
+        ));
+    }
+    report
+}
+
+// Quote a CSV field if it contains a separator or a quote
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+    use tari_wallet::util::emoji::EmojiId;
+
+    #[test]
+    fn parses_payout_file() {
+        let (_, key1) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, key2) = CommsPublicKey::random_keypair(&mut OsRng);
+        let emoji_id = EmojiId::from_pubkey(&key2).as_str().to_string();
+        let contents = format!(
+            "{}\n# Withdrawals\n{}, 1.5T, withdrawal 1, second part\n\n{},250,\n",
+            PAYOUT_FILE_HEADER, key1, emoji_id
+        );
+
+        let rows = parse_payout_file(&contents).unwrap();
+        assert_eq!(rows, vec![
+            PayoutRow {
+                line: 3,
+                destination: key1.clone(),
+                amount: MicroTari::from(1_500_000),
+                reference: "withdrawal 1, second part".to_string(),
+            },
+            PayoutRow {
+                line: 5,
+                destination: key2,
+                amount: MicroTari::from(250),
+                reference: "".to_string(),
+            },
+        ]);
+
+        let report = format_payout_report(&[
+            (rows[0].clone(), PayoutOutcome::Sent(7)),
+            (rows[1].clone(), PayoutOutcome::Failed("Not enough funds".to_string())),
+        ]);
+        assert_eq!(
+            report,
+            format!(
+                "destination,amount,reference,tx_id,status\n{},1500000uT,\"withdrawal 1, second \
+                 part\",7,sent\n{},250uT,,,failed: Not enough funds\n",
+                key1, rows[1].destination
+            )
+        );
+    }
+
+    #[test]
+    fn reports_every_invalid_row() {
+        let (_, key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let contents = format!("not a key,100,a\n{},0,b\n{},lots,c\n{}\n{},100,d", key, key, key, key);
+
+        let errors = parse_payout_file(&contents).unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].starts_with("Line 1:"));
+        assert!(errors[1].starts_with("Line 2:"));
+        assert!(errors[2].starts_with("Line 3:"));
+        assert!(errors[3].starts_with("Line 4:"));
+
+        assert_eq!(parse_payout_file("# Nothing to pay\n").unwrap_err(), vec![
+            "The payout file does not contain any payments".to_string()
+        ]);
+    }
+}
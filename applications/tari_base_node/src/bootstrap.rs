@@ -0,0 +1,132 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use futures::{future, FutureExt};
+use log::*;
+use std::{cmp, time::Duration};
+use tari_comms::{
+    backoff::{Backoff, ExponentialBackoff},
+    connection_manager::ConnectionManagerRequester,
+    peer_manager::NodeId,
+};
+use tari_comms_dht::DhtRequester;
+use tari_shutdown::ShutdownSignal;
+use tokio::time::delay_for;
+
+pub const LOG_TARGET: &str = "base_node::app::bootstrap";
+
+/// The longest time to wait between attempts to reach the seed peers
+const MAX_SEED_DIAL_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Dials the configured seed peers in the background until at least one of them can be reached, waiting an
+/// exponentially increasing amount of time between attempts. This allows the base node to start up and listen while
+/// none of its seed peers are reachable. Once a seed peer connects, a join message is sent so that the rest of the
+/// network learns about this node. Chain metadata from the new peers will move the state machine into block sync if
+/// their tip is ahead of ours.
+pub struct SeedPeerBootstrapper {
+    seed_peers: Vec<NodeId>,
+    connection_manager: ConnectionManagerRequester,
+    dht_requester: DhtRequester,
+    backoff: ExponentialBackoff,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl SeedPeerBootstrapper {
+    pub fn new(
+        seed_peers: Vec<NodeId>,
+        connection_manager: ConnectionManagerRequester,
+        dht_requester: DhtRequester,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            seed_peers,
+            connection_manager,
+            dht_requester,
+            backoff: ExponentialBackoff::default(),
+            shutdown_signal,
+        }
+    }
+
+    /// Runs until a seed peer has been reached or the shutdown signal is triggered
+    pub async fn run(mut self) {
+        if self.seed_peers.is_empty() {
+            info!(
+                target: LOG_TARGET,
+                "No seed peers are configured. The base node will wait for peers to connect to it."
+            );
+            return;
+        }
+
+        let mut shutdown_signal = self.shutdown_signal.clone().fuse();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let num_connected = self.dial_seed_peers().await;
+            if num_connected > 0 {
+                info!(
+                    target: LOG_TARGET,
+                    "Connected to {} of {} seed peer(s) after {} attempt(s)",
+                    num_connected,
+                    self.seed_peers.len(),
+                    attempts
+                );
+                if let Err(err) = self.dht_requester.send_join().await {
+                    warn!(target: LOG_TARGET, "Failed to send join message to seed peers: {}", err);
+                }
+                break;
+            }
+
+            // The first attempt has a backoff of zero, so the retries start from the second backoff period
+            let backoff = cmp::min(self.backoff.calculate_backoff(attempts + 1), MAX_SEED_DIAL_BACKOFF);
+            warn!(
+                target: LOG_TARGET,
+                "Unable to reach any of the {} seed peer(s). Retrying in {:.0?}",
+                self.seed_peers.len(),
+                backoff
+            );
+            futures::select! {
+                _ = delay_for(backoff).fuse() => {},
+                _ = shutdown_signal => {
+                    debug!(target: LOG_TARGET, "Seed peer bootstrapping stopped because of a shutdown signal");
+                    break;
+                },
+            }
+        }
+    }
+
+    /// Dial all the seed peers concurrently, returning the number of seed peers that were reached
+    async fn dial_seed_peers(&mut self) -> usize {
+        let dials = self.seed_peers.iter().map(|node_id| {
+            let mut connection_manager = self.connection_manager.clone();
+            let node_id = node_id.clone();
+            async move {
+                let result = connection_manager.dial_peer(node_id.clone()).await;
+                if let Err(err) = &result {
+                    debug!(target: LOG_TARGET, "Failed to dial seed peer '{}': {}", node_id, err);
+                }
+                result
+            }
+        });
+        future::join_all(dials).await.into_iter().filter(Result::is_ok).count()
+    }
+}
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::miner;
+use crate::{bootstrap::SeedPeerBootstrapper, miner};
 use futures::future;
 use log::*;
 use rand::rngs::OsRng;
@@ -33,6 +33,7 @@ use std::{
     },
     time::Duration,
 };
+use tari_broadcast_channel::Subscriber;
 use tari_common::{CommsTransport, DatabaseType, GlobalConfig, Network, SocksAuthentication, TorControlAuthentication};
 use tari_comms::{
     multiaddr::{Multiaddr, Protocol},
@@ -53,7 +54,11 @@ use tari_core::{
         service::{BaseNodeServiceConfig, BaseNodeServiceInitializer},
         BaseNodeStateMachine,
         BaseNodeStateMachineConfig,
+        DiskSpaceEvent,
+        DiskSpaceWatchdog,
+        DiskSpaceWatchdogConfig,
         LocalNodeCommsInterface,
+        MaintenanceModeHandle,
         OutboundNodeCommsInterface,
     },
     chain_storage::{
@@ -63,9 +68,10 @@ use tari_core::{
         BlockchainDatabaseConfig,
         LMDBDatabase,
         MemoryDatabase,
+        RefusedReorgHandle,
         Validators,
     },
-    consensus::{ConsensusManager, ConsensusManagerBuilder, Network as NetworkType},
+    consensus::{ConsensusConstants, ConsensusManager, ConsensusManagerBuilder, Network as NetworkType},
     mempool::{
         service::LocalMempoolService,
         Mempool,
@@ -78,6 +84,7 @@ use tari_core::{
     tari_utilities::{hex::Hex, message_format::MessageFormat},
     transactions::{
         crypto::keys::SecretKey as SK,
+        tari_amount::MicroTari,
         types::{CryptoFactories, HashDigest, PrivateKey, PublicKey},
     },
     validation::{
@@ -92,11 +99,15 @@ use tari_p2p::{
     initialization::{initialize_comms, CommsConfig},
     services::{
         comms_outbound::CommsOutboundServiceInitializer,
-        liveness::{LivenessConfig, LivenessInitializer},
+        liveness::{LivenessConfig, LivenessHandle, LivenessInitializer},
     },
     transport::{TorConfig, TransportType},
 };
-use tari_service_framework::{handles::ServiceHandles, StackBuilder};
+use tari_service_framework::{
+    handles::ServiceHandles,
+    watchdog::{Watchdog, WatchdogInitializer, DEFAULT_CHECK_INTERVAL},
+    StackBuilder,
+};
 use tari_shutdown::ShutdownSignal;
 use tari_wallet::{
     output_manager_service::{
@@ -105,7 +116,11 @@ use tari_wallet::{
         storage::sqlite_db::OutputManagerSqliteDatabase,
         OutputManagerServiceInitializer,
     },
-    storage::connection_manager::{run_migration_and_create_sqlite_connection, WalletDbConnection},
+    storage::{
+        connection_manager::{run_migration_and_create_sqlite_connection, WalletDbConnection},
+        database::WalletDatabase,
+        sqlite_db::WalletSqliteDatabase,
+    },
     transaction_service::{
         config::TransactionServiceConfig,
         handle::TransactionServiceHandle,
@@ -113,7 +128,7 @@ use tari_wallet::{
         TransactionServiceInitializer,
     },
 };
-use tokio::{runtime, stream::StreamExt, sync::broadcast, task, time::delay_for};
+use tokio::{runtime, stream::StreamExt, sync::broadcast, task};
 
 const LOG_TARGET: &str = "c::bn::initialization";
 
@@ -159,6 +174,12 @@ impl NodeContainer {
         using_backend!(self, ctx, ctx.local_mempool())
     }
 
+    /// Returns a handle to the base node liveness service. This function panics if it has not been registered
+    /// with the comms service
+    pub fn local_liveness(&self) -> LivenessHandle {
+        using_backend!(self, ctx, ctx.local_liveness())
+    }
+
     /// Returns the CommsNode.
     pub fn base_node_comms(&self) -> &CommsNode {
         using_backend!(self, ctx, &ctx.base_node_comms)
@@ -195,6 +216,16 @@ impl NodeContainer {
         using_backend!(self, ctx, ctx.wallet_transaction_service())
     }
 
+    /// Returns a handle to the chain reorg, if any, that was refused and is awaiting a decision from the node operator
+    pub fn refused_reorg_handle(&self) -> RefusedReorgHandle {
+        using_backend!(self, ctx, ctx.node.get_refused_reorg_handle())
+    }
+
+    /// Returns a handle used to put the node into, and take it out of, maintenance mode
+    pub fn maintenance_mode_handle(&self) -> MaintenanceModeHandle {
+        using_backend!(self, ctx, ctx.maintenance_mode.clone())
+    }
+
     async fn run_impl<B: BlockchainBackend + 'static>(mut ctx: BaseNodeContext<B>, rt: runtime::Handle) {
         info!(target: LOG_TARGET, "Tari base node has STARTED");
         let mut wallet_output_handle = ctx.output_manager();
@@ -210,12 +241,6 @@ impl NodeContainer {
                             target: LOG_TARGET,
                             "🤑💰🤑 Newly mined coinbase output added to wallet 🤑💰🤑"
                         );
-                        // TODO Remove this when the wallet monitors the UTXO's more intelligently
-                        let mut oms_handle_clone = wallet_output_handle.clone();
-                        tokio::spawn(async move {
-                            delay_for(Duration::from_secs(240)).await;
-                            oms_handle_clone.sync_with_base_node().await;
-                        });
                     },
                     Err(e) => warn!(target: LOG_TARGET, "Error adding output: {}", e),
                 }
@@ -226,6 +251,24 @@ impl NodeContainer {
             miner.mine().await;
             debug!(target: LOG_TARGET, "Miner has shutdown");
         });
+        if let Some(mut disk_space_events) = ctx.disk_space_events.take() {
+            rt.spawn(async move {
+                while let Some(event) = disk_space_events.next().await {
+                    match &*event {
+                        DiskSpaceEvent::LowDiskSpace { available, threshold } => println!(
+                            "WARNING: Only {} MiB of disk space is available, below the minimum of {} MiB. The node \
+                             has stopped storing new blocks until more space is available.",
+                            available / (1024 * 1024),
+                            threshold / (1024 * 1024)
+                        ),
+                        DiskSpaceEvent::DiskSpaceRecovered { available } => println!(
+                            "{} MiB of disk space is available. The node has resumed storing new blocks.",
+                            available / (1024 * 1024)
+                        ),
+                    }
+                }
+            });
+        }
         info!(
             target: LOG_TARGET,
             "Starting node - It will run until a fatal error occurs or until the stop flag is activated."
@@ -253,6 +296,8 @@ struct BaseNodeContext<B: BlockchainBackend> {
     pub node: BaseNodeStateMachine<B>,
     pub miner: Option<Miner>,
     pub miner_enabled: Arc<AtomicBool>,
+    pub maintenance_mode: MaintenanceModeHandle,
+    pub disk_space_events: Option<Subscriber<DiskSpaceEvent>>,
 }
 
 impl<B: BlockchainBackend> BaseNodeContext<B> {
@@ -277,6 +322,13 @@ impl<B: BlockchainBackend> BaseNodeContext<B> {
             .expect("Could not get local mempool interface handle")
     }
 
+    /// Returns the handle to the base node Liveness service
+    pub fn local_liveness(&self) -> LivenessHandle {
+        self.base_node_handles
+            .get_handle::<LivenessHandle>()
+            .expect("Could not get local liveness service handle")
+    }
+
     /// Return the handle to the Transaciton Service
     pub fn wallet_transaction_service(&self) -> TransactionServiceHandle {
         self.wallet_handles
@@ -464,20 +516,53 @@ where
         StatelessBlockValidator::new(&rules.consensus_constants()),
         AccumDifficultyValidator {},
     );
-    // TODO - make BlockchainDatabaseConfig configurable
-    let db = BlockchainDatabase::new(backend, &rules, validators, BlockchainDatabaseConfig::default())
-        .map_err(|e| e.to_string())?;
-    let mempool_validator =
-        MempoolValidators::new(FullTxValidator::new(factories.clone()), TxInputAndMaturityValidator {});
+    let db_config = BlockchainDatabaseConfig {
+        max_allowed_reorg_depth: config.max_allowed_reorg_depth,
+        max_orphan_age: config.max_orphan_age_secs.map(Duration::from_secs),
+        ..Default::default()
+    };
+    let db = BlockchainDatabase::new(backend, &rules, validators, db_config).map_err(|e| e.to_string())?;
+    let mempool_validator = MempoolValidators::new(
+        FullTxValidator::new(rules.clone(), factories.clone()),
+        TxInputAndMaturityValidator {},
+    );
     let mempool = Mempool::new(db.clone(), MempoolConfig::default(), mempool_validator);
+    let flush_db = db.clone();
+    let maintenance_mode = MaintenanceModeHandle::new(move || flush_db.flush());
     let handle = runtime::Handle::current();
 
+    // The blockchain database is made read-only when the disk holding the data directory is nearly full
+    let disk_space_events = config.min_free_disk_space_mb.map(|min_free_disk_space_mb| {
+        let min_free_space = min_free_disk_space_mb * 1024 * 1024;
+        let watchdog_config = DiskSpaceWatchdogConfig {
+            min_free_space,
+            resume_free_space: min_free_space + min_free_space / 10,
+            ..Default::default()
+        };
+        let data_dir = config.data_dir.clone();
+        let watchdog = DiskSpaceWatchdog::new(watchdog_config, db.safe_mode_handle(), move || {
+            fs2::available_space(&data_dir)
+        });
+        let events = watchdog.get_event_stream();
+        task::spawn(watchdog.run(interrupt_signal.clone()));
+        events
+    });
+
+    // The services of both the base node and the wallet stacks report to the same watchdog
+    let service_watchdog = Watchdog::default();
+    task::spawn(
+        service_watchdog
+            .clone()
+            .run(DEFAULT_CHECK_INTERVAL, interrupt_signal.clone()),
+    );
+
     //---------------------------------- Base Node --------------------------------------------//
 
     let (publisher, base_node_subscriptions) = pubsub_connector(handle.clone(), 100);
     let base_node_subscriptions = Arc::new(base_node_subscriptions);
     create_peer_db_folder(&config.peer_db_path)?;
-    let (base_node_comms, base_node_dht) = setup_base_node_comms(base_node_identity, config, publisher).await?;
+    let (base_node_comms, base_node_dht, seed_peers) =
+        setup_base_node_comms(base_node_identity, config, publisher).await?;
 
     debug!(target: LOG_TARGET, "Registering base node services");
     let base_node_handles = register_base_node_services(
@@ -487,6 +572,8 @@ where
         base_node_subscriptions.clone(),
         mempool,
         rules.clone(),
+        maintenance_mode.clone(),
+        service_watchdog.clone(),
     )
     .await;
     debug!(target: LOG_TARGET, "Base node service registration complete.");
@@ -503,6 +590,16 @@ where
     )
     .await?;
 
+    task::spawn(
+        SeedPeerBootstrapper::new(
+            seed_peers,
+            base_node_comms.connection_manager(),
+            base_node_dht.dht_requester(),
+            interrupt_signal.clone(),
+        )
+        .run(),
+    );
+
     task::spawn(sync_peers(
         base_node_comms.subscribe_connection_manager_events(),
         base_node_comms.peer_manager(),
@@ -517,6 +614,10 @@ where
     )?;
     let wallet_conn = run_migration_and_create_sqlite_connection(&config.wallet_db_file)
         .map_err(|e| format!("Could not create wallet: {:?}", e))?;
+    WalletDatabase::new(WalletSqliteDatabase::new(wallet_conn.clone()))
+        .check_network_genesis_hash(rules.get_genesis_block_hash().to_hex())
+        .await
+        .map_err(|e| format!("Could not open wallet {}: {}", config.wallet_db_file.display(), e))?;
 
     let wallet_handles = register_wallet_services(
         &wallet_comms,
@@ -524,6 +625,9 @@ where
         &wallet_conn,
         wallet_subscriptions,
         factories,
+        rules.consensus_constants(),
+        &config.wallet_recovery_scan_values,
+        service_watchdog,
     )
     .await;
 
@@ -559,12 +663,15 @@ where
     let node = BaseNodeStateMachine::new(
         &db,
         &outbound_interface,
+        rules.clone(),
         base_node_comms.peer_manager(),
         base_node_comms.connection_manager(),
         chain_metadata_service.get_event_stream(),
         state_machine_config,
         interrupt_signal,
-    );
+    )
+    .with_preferred_sync_peers(parse_preferred_sync_peers(&config.preferred_sync_peers))
+    .with_maintenance_mode(maintenance_mode.clone());
 
     //---------------------------------- Mining --------------------------------------------//
 
@@ -597,6 +704,8 @@ where
         node,
         miner: Some(miner),
         miner_enabled,
+        maintenance_mode,
+        disk_space_events,
     })
 }
 
@@ -697,6 +806,25 @@ fn parse_peer_seeds(seeds: &[String]) -> Vec<Peer> {
     result
 }
 
+/// Parse the public keys of the configured preferred sync peers into node ids, skipping any invalid entries
+fn parse_preferred_sync_peers(public_keys: &[String]) -> Vec<NodeId> {
+    public_keys
+        .iter()
+        .filter_map(|s| match PublicKey::from_hex(s.trim()) {
+            Ok(pk) => NodeId::from_key(&pk).ok(),
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "{} is not a valid preferred sync peer public key. {}",
+                    s,
+                    e.to_string()
+                );
+                None
+            },
+        })
+        .collect()
+}
+
 /// Creates a transport type from the given configuration
 /// /// ## Paramters
 /// `config` - The reference to the configuration in which to set up the comms stack, see [GlobalConfig]
@@ -785,15 +913,6 @@ fn setup_wallet_transport_type(config: &GlobalConfig) -> TransportType {
         "Wallet transport is set to '{:?}'", config.comms_transport
     );
 
-    let add_to_port = |addr: Multiaddr, n| -> Multiaddr {
-        addr.iter()
-            .map(|p| match p {
-                Protocol::Tcp(port) => Protocol::Tcp(port + n),
-                p => p,
-            })
-            .collect()
-    };
-
     match config.comms_transport.clone() {
         CommsTransport::Tcp {
             listener_address,
@@ -863,6 +982,17 @@ fn setup_wallet_transport_type(config: &GlobalConfig) -> TransportType {
     }
 }
 
+/// Increases the TCP port of an address, which is how the addresses of the base node's wallet are derived from those of
+/// the base node
+fn add_to_port(addr: Multiaddr, n: u16) -> Multiaddr {
+    addr.iter()
+        .map(|p| match p {
+            Protocol::Tcp(port) => Protocol::Tcp(port + n),
+            p => p,
+        })
+        .collect()
+}
+
 /// Converts one socks authentication struct into another
 /// ## Parameters
 /// `auth` - Socks authentication of type SocksAuthentication
@@ -938,12 +1068,13 @@ fn create_peer_db_folder<P: AsRef<Path>>(peer_db_path: P) -> Result<(), String>
 /// `config` - The reference to the configuration in which to set up the comms stack, see [GlobalConfig]
 /// `publisher` - The publisher for the publish-subscribe messaging system
 /// ## Returns
-/// A Result containing the commsnode and dht on success, string will indicate the reason on error
+/// A Result containing the commsnode, dht and the node ids of the seed peers on success, string will indicate the
+/// reason on error
 async fn setup_base_node_comms(
     node_identity: Arc<NodeIdentity>,
     config: &GlobalConfig,
     publisher: PubsubDomainConnector,
-) -> Result<(CommsNode, Dht), String>
+) -> Result<(CommsNode, Dht, Vec<NodeId>), String>
 {
     let comms_config = CommsConfig {
         node_identity,
@@ -961,6 +1092,8 @@ async fn setup_base_node_comms(
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: config.listener_liveness_whitelist_cidrs.clone(),
         listener_liveness_max_sessions: config.listnener_liveness_max_sessions,
+        additional_listener_addresses: config.additional_listener_addresses.clone(),
+        additional_public_addresses: config.additional_public_addresses.clone(),
     };
     let (comms, dht) = initialize_comms(comms_config, publisher)
         .await
@@ -975,9 +1108,15 @@ async fn setup_base_node_comms(
             .map_err(|e| format!("Failed to save tor identity: {:?}", e))?;
     }
 
-    add_peers_to_comms(&comms, parse_peer_seeds(&config.peer_seeds)).await?;
+    let seed_peers = parse_peer_seeds(&config.peer_seeds);
+    let seed_node_ids = seed_peers
+        .iter()
+        .filter(|p| &p.public_key != comms.node_identity().public_key())
+        .map(|p| p.node_id.clone())
+        .collect();
+    add_peers_to_comms(&comms, seed_peers).await?;
 
-    Ok((comms, dht))
+    Ok((comms, dht, seed_node_ids))
 }
 
 /// Asynchronously initializes comms for the base node's wallet
@@ -1012,6 +1151,19 @@ async fn setup_wallet_comms(
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        // The wallet listens one port above the base node on all of its addresses
+        additional_listener_addresses: config
+            .additional_listener_addresses
+            .iter()
+            .cloned()
+            .map(|addr| add_to_port(addr, 1))
+            .collect(),
+        additional_public_addresses: config
+            .additional_public_addresses
+            .iter()
+            .cloned()
+            .map(|addr| add_to_port(addr, 1))
+            .collect(),
     };
     let (comms, dht) = initialize_comms(comms_config, publisher)
         .await
@@ -1070,6 +1222,7 @@ async fn add_peers_to_comms(comms: &CommsNode, peers: Vec<Peer>) -> Result<(), S
 /// `mempool` - The mempool interface, for all transactions not yet included or recently included in a block
 /// `consensus_manager` - The consensus manager for the blockchain
 /// `factories` -  Cryptographic factory based on Pederson Commitments
+/// `watchdog` - The watchdog that the services report their activities to
 ///
 /// ## Returns
 /// A hashmap of handles wrapped in an atomic reference counter
@@ -1080,6 +1233,8 @@ async fn register_base_node_services<B>(
     subscription_factory: Arc<SubscriptionFactory>,
     mempool: Mempool<B>,
     consensus_manager: ConsensusManager,
+    maintenance_mode: MaintenanceModeHandle,
+    watchdog: Watchdog,
 ) -> Arc<ServiceHandles>
 where
     B: BlockchainBackend + 'static,
@@ -1087,19 +1242,22 @@ where
     let node_config = BaseNodeServiceConfig::default(); // TODO - make this configurable
     let mempool_config = MempoolServiceConfig::default(); // TODO - make this configurable
     StackBuilder::new(runtime::Handle::current(), comms.shutdown_signal())
+        .add_initializer(WatchdogInitializer::new(watchdog))
         .add_initializer(CommsOutboundServiceInitializer::new(dht.outbound_requester()))
-        .add_initializer(BaseNodeServiceInitializer::new(
-            subscription_factory.clone(),
-            db,
-            mempool.clone(),
-            consensus_manager,
-            node_config,
-        ))
-        .add_initializer(MempoolServiceInitializer::new(
-            subscription_factory.clone(),
-            mempool,
-            mempool_config,
-        ))
+        .add_initializer(
+            BaseNodeServiceInitializer::new(
+                subscription_factory.clone(),
+                db,
+                mempool.clone(),
+                consensus_manager,
+                node_config,
+            )
+            .with_maintenance_mode(maintenance_mode.clone()),
+        )
+        .add_initializer(
+            MempoolServiceInitializer::new(subscription_factory.clone(), mempool, mempool_config)
+                .with_maintenance_mode(maintenance_mode),
+        )
         .add_initializer(LivenessInitializer::new(
             LivenessConfig {
                 auto_ping_interval: Some(Duration::from_secs(30)),
@@ -1112,7 +1270,7 @@ where
             dht.dht_requester(),
             comms.connection_manager(),
         ))
-        .add_initializer(ChainMetadataServiceInitializer)
+        .add_initializer(ChainMetadataServiceInitializer::new(comms.node_identity()))
         .finish()
         .await
         .expect("Service initialization failed")
@@ -1125,6 +1283,9 @@ where
 /// `wallet_db_conn` - A reference to the sqlite database connection for the transaction and output manager services
 /// `subscription_factory` - The publish-subscribe messaging system, wrapped in an atomic reference counter
 /// `factories` -  Cryptographic factory based on Pederson Commitments
+/// `consensus_constants` - The consensus constants the wallet's fee and coinbase settings are taken from
+/// `recovery_scan_values` - The output values, in MicroTari, that a recovery scan of the wallet looks for
+/// `watchdog` - The watchdog that the services report their activities to
 ///
 /// ## Returns
 /// A hashmap of handles wrapped in an atomic reference counter
@@ -1134,9 +1295,13 @@ async fn register_wallet_services(
     wallet_db_conn: &WalletDbConnection,
     subscription_factory: Arc<SubscriptionFactory>,
     factories: CryptoFactories,
+    consensus_constants: &ConsensusConstants,
+    recovery_scan_values: &[u64],
+    watchdog: Watchdog,
 ) -> Arc<ServiceHandles>
 {
     StackBuilder::new(runtime::Handle::current(), wallet_comms.shutdown_signal())
+        .add_initializer(WatchdogInitializer::new(watchdog))
         .add_initializer(CommsOutboundServiceInitializer::new(wallet_dht.outbound_requester()))
         .add_initializer(LivenessInitializer::new(
             LivenessConfig{
@@ -1151,7 +1316,14 @@ async fn register_wallet_services(
     ))
         // Wallet services
         .add_initializer(OutputManagerServiceInitializer::new(
-            OutputManagerServiceConfig::default(),
+            OutputManagerServiceConfig {
+                min_output_value: consensus_constants.min_output_value(),
+                min_fee_per_gram: consensus_constants.min_fee_per_gram(),
+                coinbase_lock_height: consensus_constants.coinbase_lock_height(),
+                block_interval: Duration::from_secs(consensus_constants.get_target_block_interval()),
+                recovery_scan_values: recovery_scan_values.iter().map(|v| MicroTari::from(*v)).collect(),
+                ..Default::default()
+            },
             subscription_factory.clone(),
             OutputManagerSqliteDatabase::new(wallet_db_conn.clone()),
             factories.clone(),
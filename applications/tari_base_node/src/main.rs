@@ -77,6 +77,10 @@
 #[macro_use]
 mod table;
 
+/// Parsing of batch payout files and their reports for the `send-batch` command
+mod batch_payout;
+/// Background dialing of the seed peers until the node has joined the network
+mod bootstrap;
 /// Utilities and helpers for building the base node instance
 mod builder;
 /// The command line interface definition and configuration
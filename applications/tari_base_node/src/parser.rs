@@ -22,10 +22,11 @@
 
 use super::LOG_TARGET;
 use crate::{
+    batch_payout::{format_payout_report, parse_payout_file, PayoutOutcome},
     builder::NodeContainer,
     table::Table,
     utils,
-    utils::{format_duration_basic, format_naive_datetime},
+    utils::{format_duration_basic, format_naive_datetime, parse_emoji_id_or_public_key},
 };
 use chrono::Utc;
 use chrono_english::{parse_date_string, Dialect};
@@ -41,6 +42,7 @@ use rustyline::{
 };
 use rustyline_derive::{Helper, Highlighter, Validator};
 use std::{
+    fs,
     io::{self, Write},
     str::FromStr,
     string::ToString,
@@ -55,28 +57,37 @@ use strum_macros::{Display, EnumIter, EnumString};
 use tari_comms::{
     connection_manager::ConnectionManagerRequester,
     peer_manager::{PeerFeatures, PeerManager, PeerQuery},
-    types::CommsPublicKey,
     NodeIdentity,
 };
 use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester};
 use tari_core::{
-    base_node::LocalNodeCommsInterface,
+    base_node::{LocalNodeCommsInterface, MaintenanceModeHandle},
     blocks::BlockHeader,
+    chain_storage::RefusedReorgHandle,
+    consensus::{
+        emission_report::{self, EmissionComparison, DEFAULT_CHECKPOINT_YEARS},
+        ConsensusConstantsBuilder,
+        Network,
+    },
     mempool::service::LocalMempoolService,
-    tari_utilities::{hex::Hex, Hashable},
+    tari_utilities::{
+        hex::{from_hex, Hex},
+        Hashable,
+    },
     transactions::{
         tari_amount::{uT, MicroTari},
-        transaction::OutputFeatures,
+        transaction::{OutputFeatures, Transaction},
     },
 };
 use tari_crypto::ristretto::pedersen::PedersenCommitmentFactory;
+use tari_p2p::services::liveness::LivenessHandle;
 use tari_shutdown::Shutdown;
 use tari_wallet::{
     output_manager_service::{error::OutputManagerError, handle::OutputManagerHandle},
     transaction_service::{error::TransactionServiceError, handle::TransactionServiceHandle},
     util::emoji::EmojiId,
 };
-use tokio::{runtime, time};
+use tokio::{runtime, task, time};
 
 /// Enum representing commands used by the basenode
 #[derive(Clone, PartialEq, Debug, Display, EnumIter, EnumString)]
@@ -88,7 +99,10 @@ pub enum BaseNodeCommand {
     ListTransactions,
     ListCompletedTransactions,
     CancelTransaction,
+    GetRawTransaction,
+    SubmitRawTransaction,
     SendTari,
+    SendBatch,
     GetChainMetadata,
     ListPeers,
     ResetOfflinePeers,
@@ -98,14 +112,25 @@ pub enum BaseNodeCommand {
     ListHeaders,
     CheckDb,
     CalcTiming,
+    GetDifficultyStats,
     DiscoverPeer,
     GetBlock,
     GetMempoolStats,
     GetMempoolState,
     Whoami,
     ToggleMining,
+    ReorgStatus,
+    ApproveReorg,
+    RejectReorg,
+    EnterMaintenance,
+    ExitMaintenance,
     MakeItRain,
     CoinSplit,
+    CoinJoin,
+    BurnTari,
+    GetTotalBurned,
+    GetNetworkTimeOffset,
+    EmissionReport,
     Quit,
     Exit,
 }
@@ -125,8 +150,11 @@ pub struct Parser {
     wallet_output_service: OutputManagerHandle,
     node_service: LocalNodeCommsInterface,
     mempool_service: LocalMempoolService,
+    liveness_service: LivenessHandle,
     wallet_transaction_service: TransactionServiceHandle,
     enable_miner: Arc<AtomicBool>,
+    refused_reorg: RefusedReorgHandle,
+    maintenance_mode: MaintenanceModeHandle,
 }
 
 const MAKE_IT_RAIN_USAGE: &str = "\nmake-it-rain [Txs/s] [duration (s)] [start amount (uT)] [increment (uT)/Tx] \
@@ -135,6 +163,14 @@ const MAKE_IT_RAIN_USAGE: &str = "\nmake-it-rain [Txs/s] [duration (s)] [start a
                                   [increment (uT)/Tx] [\"start time (UTC)\" / 'now' for immediate start] --file \
                                   [\"path to file\" containing list of 'public key or emoji id' 'message']\n";
 
+const SEND_BATCH_USAGE: &str = "send-batch [\"path to payout file\"] [\"path to report file\"]";
+
+const EMISSION_REPORT_USAGE: &str = "emission-report [network]\n       or\nemission-report [network] [network to \
+                                     compare with]\n       or\nemission-report [network] [initial block reward] \
+                                     [decay] [tail block reward]\n";
+
+const DEFAULT_MAINTENANCE_RETRY_AFTER: Duration = Duration::from_secs(60);
+
 /// This will go through all instructions and look for potential matches
 impl Completer for Parser {
     type Candidate = String;
@@ -178,8 +214,11 @@ impl Parser {
             wallet_output_service: ctx.output_manager(),
             node_service: ctx.local_node(),
             mempool_service: ctx.local_mempool(),
+            liveness_service: ctx.local_liveness(),
             wallet_transaction_service: ctx.wallet_transaction_service(),
             enable_miner: ctx.miner_enabled(),
+            refused_reorg: ctx.refused_reorg_handle(),
+            maintenance_mode: ctx.maintenance_mode_handle(),
         }
     }
 
@@ -244,9 +283,18 @@ impl Parser {
             CancelTransaction => {
                 self.process_cancel_transaction(args);
             },
+            GetRawTransaction => {
+                self.process_get_raw_transaction(args);
+            },
+            SubmitRawTransaction => {
+                self.process_submit_raw_transaction(args);
+            },
             SendTari => {
                 self.process_send_tari(args);
             },
+            SendBatch => {
+                self.process_send_batch(del_arg_vec);
+            },
             GetChainMetadata => {
                 self.process_get_chain_meta();
             },
@@ -277,9 +325,27 @@ impl Parser {
             CalcTiming => {
                 self.process_calc_timing(args);
             },
+            GetDifficultyStats => {
+                self.process_get_difficulty_stats(args);
+            },
             ToggleMining => {
                 self.process_toggle_mining();
             },
+            ReorgStatus => {
+                self.process_reorg_status();
+            },
+            ApproveReorg => {
+                self.process_approve_reorg(true);
+            },
+            RejectReorg => {
+                self.process_approve_reorg(false);
+            },
+            EnterMaintenance => {
+                self.process_enter_maintenance(args);
+            },
+            ExitMaintenance => {
+                self.process_exit_maintenance();
+            },
             GetBlock => {
                 self.process_get_block(args);
             },
@@ -298,6 +364,21 @@ impl Parser {
             CoinSplit => {
                 self.process_coin_split(args);
             },
+            CoinJoin => {
+                self.process_coin_join(args);
+            },
+            BurnTari => {
+                self.process_burn_tari(args);
+            },
+            GetTotalBurned => {
+                self.process_get_total_burned();
+            },
+            GetNetworkTimeOffset => {
+                self.process_get_network_time_offset();
+            },
+            EmissionReport => {
+                self.process_emission_report(args);
+            },
             Exit | Quit => {
                 println!("Shutting down...");
                 info!(
@@ -336,12 +417,30 @@ impl Parser {
                 println!("Cancel a transaction");
                 println!("USAGE: cancel-transaction [transaction ID]");
             },
+            GetRawTransaction => {
+                println!("Prints a completed transaction as hex, so that it can be submitted to another base node");
+                println!("USAGE: get-raw-transaction [transaction ID]");
+            },
+            SubmitRawTransaction => {
+                println!("Submits a transaction exported with get-raw-transaction to this node's mempool");
+                println!("USAGE: submit-raw-transaction [transaction hex]");
+            },
             SendTari => {
                 println!("Sends an amount of Tari to a address call this command via:");
                 println!("send-tari [amount of tari to send] [destination public key or emoji id] [optional: msg]");
+                println!("The amount is in µT unless it is suffixed with T, e.g. `1.5T` or `1500000uT`");
+            },
+            SendBatch => {
+                println!("Pays out every row of a CSV file and writes a report with the transaction id of each row:");
+                println!("{}", SEND_BATCH_USAGE);
+                println!(
+                    "Each row of the file is `destination,amount,reference`, where the destination is a public key or \
+                     emoji id and the reference is used as the transaction message. Nothing is sent unless every row \
+                     is valid and the wallet can pay for all of them."
+                );
             },
             GetChainMetadata => {
-                println!("Gets your base node chain meta data");
+                println!("Gets your base node chain meta data and the height at which tail emission starts");
             },
             DiscoverPeer => {
                 println!("Attempt to discover a peer on the Tari network");
@@ -372,9 +471,39 @@ impl Parser {
             CalcTiming => {
                 println!("Calculates the time average time taken to mine a given range of blocks.");
             },
+            GetDifficultyStats => {
+                println!(
+                    "Displays the solve time, target and achieved difficulty and estimated hash rate of a range of \
+                     blocks, can be called in the following two ways: "
+                );
+                println!("get-difficulty-stats [first block height] [last block height]");
+                println!("get-difficulty-stats [number of blocks from chain tip]");
+            },
             ToggleMining => {
                 println!("Enable or disable the miner on this node, calling this command will toggle the state");
             },
+            ReorgStatus => {
+                println!(
+                    "Displays the chain reorg, if any, that was refused because it exceeded the maximum allowed depth"
+                );
+            },
+            ApproveReorg => {
+                println!("Approves the refused chain reorg, after which the node switches to the fork chain");
+            },
+            RejectReorg => {
+                println!("Rejects the refused chain reorg and keeps the current main chain");
+            },
+            EnterMaintenance => {
+                println!(
+                    "Puts the node into maintenance: inbound peer connections are refused, peer and wallet queries \
+                     are asked to retry later and the blockchain database is flushed to disk. Call this command via:"
+                );
+                println!("enter-maintenance [retry after seconds, default 60] [--check]");
+                println!("Passing --check also runs the check-db consistency check once the database is flushed");
+            },
+            ExitMaintenance => {
+                println!("Takes the node out of maintenance and resumes accepting connections and queries");
+            },
             GetBlock => {
                 println!("View a block of a height, call this command via:");
                 println!("get-block [height of the block]");
@@ -398,6 +527,33 @@ impl Parser {
             CoinSplit => {
                 println!("Constructs a transaction to split a small set of UTXOs into a large set of UTXOs");
             },
+            CoinJoin => {
+                println!("Constructs a transaction to consolidate the smallest UTXOs into a single UTXO:");
+                println!("coin-join [maximum number of UTXOs to join]");
+            },
+            BurnTari => {
+                println!("Permanently destroys an amount of Tari from the wallet by submitting a burn transaction:");
+                println!("burn-tari [amount of tari to burn]");
+            },
+            GetTotalBurned => {
+                println!("Displays the total amount of Tari that has been burned on the main chain");
+            },
+            GetNetworkTimeOffset => {
+                println!(
+                    "Displays the estimated offset of the local clock from network time, based on the timestamps in \
+                     pongs received from peers"
+                );
+            },
+            EmissionReport => {
+                println!(
+                    "Prints the emission curve of the consensus constants of a network: the block reward half-life, \
+                     the tail emission onset and the block reward and supply at checkpoints since the genesis block. \
+                     Passing a second network, or the emission amounts of a proposed schedule, compares the two \
+                     curves. Call this command via:"
+                );
+                println!("{}", EMISSION_REPORT_USAGE);
+                println!("Block rewards are in µT unless they are suffixed with T, e.g. `1T` or `1000000uT`");
+            },
             Exit | Quit => {
                 println!("Exits the base node");
             },
@@ -628,6 +784,63 @@ impl Parser {
         });
     }
 
+    /// Function to process the get-raw-transaction command
+    fn process_get_raw_transaction<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let mut transactions = self.wallet_transaction_service.clone();
+        let tx_id = match args.next().and_then(|s| s.parse::<u64>().ok()) {
+            Some(id) => id,
+            None => {
+                println!("Please enter a valid transaction ID");
+                println!("USAGE: get-raw-transaction [transaction id]");
+                return;
+            },
+        };
+
+        self.executor.spawn(async move {
+            match transactions.get_raw_transaction(tx_id).await {
+                Ok(bytes) => println!("{}", bytes.to_hex()),
+                Err(err) => {
+                    println!("Failed to get transaction: {:?}", err);
+                },
+            }
+        });
+    }
+
+    /// Function to process the submit-raw-transaction command
+    fn process_submit_raw_transaction<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let tx = match args
+            .next()
+            .ok_or_else(|| "No transaction provided".to_string())
+            .and_then(|s| from_hex(s.trim()).map_err(|e| e.to_string()))
+            .and_then(|bytes| Transaction::from_raw_bytes(&bytes))
+            .and_then(|tx| {
+                // The mempool identifies transactions by their first kernel
+                if tx.body.kernels().is_empty() {
+                    Err("The transaction has no kernels".to_string())
+                } else {
+                    Ok(tx)
+                }
+            }) {
+            Ok(tx) => tx,
+            Err(err) => {
+                println!("Please enter a valid transaction: {}", err);
+                println!("USAGE: submit-raw-transaction [transaction hex]");
+                return;
+            },
+        };
+
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            match handler.submit_transaction(tx).await {
+                Ok(response) => println!("Transaction submitted: {}", response),
+                Err(err) => {
+                    println!("Failed to submit transaction: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                },
+            };
+        });
+    }
+
     /// Function to process the get-chain-metadata command
     fn process_get_chain_meta(&mut self) {
         let mut handler = self.node_service.clone();
@@ -640,6 +853,13 @@ impl Parser {
                 },
                 Ok(data) => println!("{}", data),
             };
+            match handler.get_tail_emission_height().await {
+                Err(err) => {
+                    println!("Failed to retrieve the tail emission height: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with base node: {:?}", err);
+                },
+                Ok(height) => println!("Tail emission starts at height {}", height),
+            };
         });
     }
 
@@ -1008,6 +1228,54 @@ impl Parser {
         debug!(target: LOG_TARGET, "Mining state is now switched to {}", new_state);
     }
 
+    /// Function to process the reorg-status command
+    fn process_reorg_status(&self) {
+        match self.refused_reorg.get() {
+            Ok(Some(reorg)) => {
+                println!(
+                    "A chain reorg of depth {} from height {} to fork tip {} was refused.",
+                    reorg.depth,
+                    reorg.fork_height,
+                    reorg.fork_tip_hash.to_hex()
+                );
+                if reorg.approved {
+                    println!("The reorg has been approved and will be applied shortly.");
+                } else {
+                    println!("Use approve-reorg or reject-reorg to resume block synchronisation.");
+                }
+            },
+            Ok(None) => println!("There is no refused chain reorg awaiting approval"),
+            Err(e) => {
+                println!("Could not retrieve the refused chain reorg");
+                warn!(target: LOG_TARGET, "Error reading the refused chain reorg: {:?}", e);
+            },
+        }
+    }
+
+    /// Function to process the approve-reorg and reject-reorg commands
+    fn process_approve_reorg(&self, approve: bool) {
+        let result = if approve {
+            self.refused_reorg.approve()
+        } else {
+            self.refused_reorg.reject()
+        };
+        match result {
+            Ok(true) if approve => {
+                println!("Chain reorg approved");
+                info!(target: LOG_TARGET, "Refused chain reorg approved by the node operator");
+            },
+            Ok(true) => {
+                println!("Chain reorg rejected");
+                info!(target: LOG_TARGET, "Refused chain reorg rejected by the node operator");
+            },
+            Ok(false) => println!("There is no refused chain reorg awaiting approval"),
+            Err(e) => {
+                println!("Could not update the refused chain reorg");
+                warn!(target: LOG_TARGET, "Error updating the refused chain reorg: {:?}", e);
+            },
+        }
+    }
+
     /// Function to process the list-headers command
     fn process_list_headers<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
         let command_arg = args.map(|arg| arg.to_string()).take(4).collect::<Vec<String>>();
@@ -1103,6 +1371,125 @@ impl Parser {
         });
     }
 
+    /// Function to process the get-difficulty-stats command
+    fn process_get_difficulty_stats<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        let command_arg = args.map(|arg| arg.parse::<u64>()).take(3).collect::<Vec<_>>();
+        if command_arg.is_empty() || command_arg.len() > 2 || command_arg.iter().any(Result::is_err) {
+            println!("Command entered incorrectly, please use the following formats: ");
+            println!("get-difficulty-stats [first block height] [last block height]");
+            println!("get-difficulty-stats [number of blocks from chain tip]");
+            return;
+        }
+        let command_arg = command_arg.into_iter().filter_map(Result::ok).collect::<Vec<_>>();
+
+        let mut handler = self.node_service.clone();
+        self.executor.spawn(async move {
+            let (start_height, end_height) = if command_arg.len() == 2 {
+                (command_arg[0], command_arg[1])
+            } else {
+                match handler.get_metadata().await {
+                    Err(err) => {
+                        println!("Failed to retrieve chain height: {:?}", err);
+                        warn!(target: LOG_TARGET, "Error communicating with base node: {}", err);
+                        return;
+                    },
+                    Ok(data) => {
+                        let tip_height = data.height_of_longest_chain.unwrap_or(0);
+                        (tip_height.saturating_sub(command_arg[0].saturating_sub(1)), tip_height)
+                    },
+                }
+            };
+            match handler.get_difficulty_stats(start_height, end_height).await {
+                Err(err) => {
+                    println!("Failed to retrieve the difficulty stats: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with base node: {}", err);
+                },
+                Ok(stats) => {
+                    println!("height,pow_algo,timestamp,solve_time,target_difficulty,achieved_difficulty,hash_rate");
+                    for s in stats {
+                        println!(
+                            "{},{},{},{},{},{},{}",
+                            s.height,
+                            s.pow_algo,
+                            s.timestamp,
+                            s.solve_time,
+                            s.target_difficulty,
+                            s.achieved_difficulty,
+                            s.estimated_hash_rate
+                        );
+                    }
+                },
+            }
+        });
+    }
+
+    /// Function to process the enter-maintenance command
+    fn process_enter_maintenance<'a, I: Iterator<Item = &'a str>>(&mut self, args: I) {
+        let mut retry_after = DEFAULT_MAINTENANCE_RETRY_AFTER;
+        let mut check_db = false;
+        for arg in args {
+            if arg == "--check" {
+                check_db = true;
+                continue;
+            }
+            match arg.parse::<u64>() {
+                Ok(secs) => retry_after = Duration::from_secs(secs),
+                Err(_) => {
+                    println!("Invalid argument provided, please use the following format: ");
+                    println!("enter-maintenance [retry after seconds, default 60] [--check]");
+                    return;
+                },
+            }
+        }
+
+        let mut connection_manager = self.connection_manager.clone();
+        self.executor.spawn(async move {
+            if let Err(e) = connection_manager.set_inbound_connections_paused(true).await {
+                println!("Could not pause inbound peer connections");
+                warn!(target: LOG_TARGET, "Error pausing inbound connections: {:?}", e);
+            }
+        });
+        // Blocks until in-flight block processing has released the database and the flush has completed
+        match self.maintenance_mode.enter(retry_after) {
+            Ok(_) => {
+                println!(
+                    "Node is in maintenance, peers will be asked to retry after {}s",
+                    retry_after.as_secs()
+                );
+                info!(target: LOG_TARGET, "Maintenance mode entered by the node operator");
+            },
+            Err(e) => {
+                println!("Node is in maintenance, but the blockchain database could not be flushed");
+                warn!(target: LOG_TARGET, "Error flushing the blockchain database: {:?}", e);
+            },
+        }
+        if check_db {
+            self.process_check_db();
+        }
+    }
+
+    /// Function to process the exit-maintenance command
+    fn process_exit_maintenance(&self) {
+        let mut connection_manager = self.connection_manager.clone();
+        self.executor.spawn(async move {
+            if let Err(e) = connection_manager.set_inbound_connections_paused(false).await {
+                println!("Could not resume inbound peer connections");
+                warn!(target: LOG_TARGET, "Error resuming inbound connections: {:?}", e);
+            }
+        });
+        match self.maintenance_mode.exit() {
+            Ok(true) => {
+                println!("Node has left maintenance");
+                info!(target: LOG_TARGET, "Maintenance mode exited by the node operator");
+            },
+            Ok(false) => println!("Node is not in maintenance"),
+            Err(e) => {
+                println!("Could not take the node out of maintenance");
+                warn!(target: LOG_TARGET, "Error exiting maintenance mode: {:?}", e);
+            },
+        }
+    }
+
     /// Function to process the check-db command
     fn process_check_db(&mut self) {
         // Todo, add calls to ask peers for missing data
@@ -1166,14 +1553,14 @@ impl Parser {
 
     /// Function to process the coin split command
     fn process_coin_split<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
-        let amount_per_split = args.next().and_then(|v| v.parse::<u64>().ok());
+        let amount_per_split = args.next().and_then(|v| MicroTari::from_str(v).ok());
         let split_count = args.next().and_then(|v| v.parse::<usize>().ok());
         if amount_per_split.is_none() | split_count.is_none() {
             println!("Command entered incorrectly, please use the following format: ");
             println!("coin-split [amount of tari to allocated to each UTXO] [number of UTXOs to create]");
             return;
         }
-        let amount_per_split: MicroTari = amount_per_split.unwrap().into();
+        let amount_per_split = amount_per_split.unwrap();
         let split_count = split_count.unwrap();
 
         // Use output manager service to get utxo and create the coin split transaction
@@ -1209,14 +1596,204 @@ impl Parser {
         });
     }
 
-    /// Function to process the send transaction command
-    fn process_send_tari<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
-        let amount = args.next().and_then(|v| v.parse::<u64>().ok());
-        if amount.is_none() {
-            println!("Please enter a valid amount of tari");
+    /// Function to process the coin-join command
+    fn process_coin_join<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let max_inputs = match args.next().map(|v| v.parse::<usize>()) {
+            Some(Ok(v)) => v,
+            _ => {
+                println!("Command entered incorrectly, please use the following format: ");
+                println!("coin-join [maximum number of UTXOs to join]");
+                return;
+            },
+        };
+
+        let fee_per_gram = 25 * uT; // TODO: use configured fee per gram
+        let mut output_manager = self.wallet_output_service.clone();
+        let mut txn_service = self.wallet_transaction_service.clone();
+        self.executor.spawn(async move {
+            match output_manager.create_coin_join(max_inputs, fee_per_gram).await {
+                Ok((tx_id, tx, fee, amount)) => {
+                    match txn_service
+                        .submit_transaction(tx_id, tx, fee, amount, "Coin join".into())
+                        .await
+                    {
+                        Ok(_) => println!("Coin join transaction created with tx_id:\n{}", tx_id),
+                        Err(e) => {
+                            println!("Something went wrong creating a coin join transaction");
+                            println!("{:?}", e);
+                            warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                        },
+                    };
+                },
+                Err(e) => {
+                    println!("Something went wrong creating a coin join transaction");
+                    println!("{:?}", e);
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                },
+            };
+        });
+    }
+
+    /// Function to process the burn-tari command
+    fn process_burn_tari<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let amount = match args.next().map(MicroTari::from_str) {
+            Some(Ok(v)) => v,
+            Some(Err(e)) => {
+                println!("Please enter a valid amount of tari: {}", e);
+                return;
+            },
+            None => {
+                println!("Command entered incorrectly, please use the following format: ");
+                println!("burn-tari [amount of tari to burn]");
+                return;
+            },
+        };
+
+        let fee_per_gram = 25 * uT; // TODO: use configured fee per gram
+        let mut output_manager = self.wallet_output_service.clone();
+        let mut txn_service = self.wallet_transaction_service.clone();
+        self.executor.spawn(async move {
+            match output_manager.create_burn_transaction(amount, fee_per_gram, None).await {
+                Ok((tx_id, tx, fee, amount)) => {
+                    match txn_service
+                        .submit_transaction(tx_id, tx, fee, amount, "Burn".into())
+                        .await
+                    {
+                        Ok(_) => println!("Burn transaction created with tx_id:\n{}", tx_id),
+                        Err(e) => {
+                            println!("Something went wrong creating a burn transaction");
+                            println!("{:?}", e);
+                            warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                            return;
+                        },
+                    };
+                },
+                Err(e) => {
+                    println!("Something went wrong creating a burn transaction");
+                    println!("{:?}", e);
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+            };
+        });
+    }
+
+    /// Function to process the get-total-burned command
+    fn process_get_total_burned(&mut self) {
+        let mut handler = self.node_service.clone();
+        self.executor.spawn(async move {
+            match handler.get_total_burned().await {
+                Err(err) => {
+                    println!("Failed to retrieve the total burned: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with base node: {:?}", err);
+                    return;
+                },
+                Ok(total) => println!("Total burned: {}", total),
+            };
+        });
+    }
+
+    /// Function to process the get-network-time-offset command
+    fn process_get_network_time_offset(&mut self) {
+        let mut handler = self.liveness_service.clone();
+        self.executor.spawn(async move {
+            match handler.get_network_time_offset().await {
+                Err(err) => {
+                    println!("Failed to retrieve the network time offset: {:?}", err);
+                    warn!(
+                        target: LOG_TARGET,
+                        "Error communicating with liveness service: {:?}", err
+                    );
+                    return;
+                },
+                Ok(Some(offset)) if offset >= 0 => println!("Local clock is {}ms ahead of network time", offset),
+                Ok(Some(offset)) => println!("Local clock is {}ms behind network time", -offset),
+                Ok(None) => println!("Not enough peer timestamps have been received to estimate network time"),
+            };
+        });
+    }
+
+    /// Function to process the emission-report command
+    fn process_emission_report<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        let args = args.collect::<Vec<_>>();
+        let usage = || {
+            println!("Command entered incorrectly, please use the following formats: ");
+            println!("{}", EMISSION_REPORT_USAGE);
+        };
+        if args.is_empty() || args.len() == 3 || args.len() > 4 {
+            usage();
             return;
         }
-        let amount: MicroTari = amount.unwrap().into();
+        let network = match Network::from_str(args[0]) {
+            Ok(network) => network,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            },
+        };
+        let current = network.create_consensus_constants();
+        let proposed = match args.len() {
+            1 => None,
+            2 => match Network::from_str(args[1]) {
+                Ok(network) => Some(network.create_consensus_constants()),
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                },
+            },
+            _ => {
+                let (initial, decay, tail) = match (
+                    MicroTari::from_str(args[1]),
+                    args[2].parse::<f64>(),
+                    MicroTari::from_str(args[3]),
+                ) {
+                    (Ok(initial), Ok(decay), Ok(tail)) => (initial, decay, tail),
+                    _ => {
+                        println!("Invalid emission amounts provided.");
+                        usage();
+                        return;
+                    },
+                };
+                Some(
+                    ConsensusConstantsBuilder::new(network)
+                        .with_emission_amounts(initial, decay, tail)
+                        .build(),
+                )
+            },
+        };
+
+        // Summing up the emission curve to the tail emission onset can take a while
+        self.executor.spawn(async move {
+            let report = task::spawn_blocking(move || match proposed {
+                Some(proposed) => {
+                    EmissionComparison::new(&current, &proposed, &DEFAULT_CHECKPOINT_YEARS).map(|c| c.to_string())
+                },
+                None => {
+                    emission_report::EmissionReport::new(&current, &DEFAULT_CHECKPOINT_YEARS).map(|r| r.to_string())
+                },
+            })
+            .await;
+            match report {
+                Ok(Ok(report)) => println!("{}", report),
+                Ok(Err(e)) => println!("Invalid emission schedule: {}", e),
+                Err(e) => warn!(target: LOG_TARGET, "Error calculating the emission report: {}", e),
+            }
+        });
+    }
+
+    /// Function to process the send transaction command
+    fn process_send_tari<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let amount = match args.next().map(MicroTari::from_str) {
+            Some(Ok(v)) => v,
+            Some(Err(e)) => {
+                println!("Please enter a valid amount of tari: {}", e);
+                return;
+            },
+            None => {
+                println!("Please enter a valid amount of tari");
+                return;
+            },
+        };
 
         let key = match args.next() {
             Some(k) => k.to_string(),
@@ -1298,6 +1875,9 @@ impl Parser {
                 Err(TransactionServiceError::OutputManagerError(OutputManagerError::NotEnoughFunds)) => {
                     println!("Not enough funds to fulfill the transaction.");
                 },
+                Err(TransactionServiceError::OutputManagerError(OutputManagerError::FundsPendingMaturity)) => {
+                    println!("Not enough spendable funds to fulfill the transaction until time-locked outputs mature.");
+                },
                 Err(e) => {
                     println!("Something went wrong sending funds");
                     println!("{:?}", e);
@@ -1309,6 +1889,104 @@ impl Parser {
         });
     }
 
+    /// Function to process the send-batch command, which pays out a CSV file of payments with one transaction per row
+    fn process_send_batch(&mut self, command_arg: Vec<String>) {
+        if command_arg.len() != 2 {
+            println!("Command entered incorrectly, please use the following format:");
+            println!("{}", SEND_BATCH_USAGE);
+            return;
+        }
+        let report_path = command_arg[1].clone();
+        let contents = match fs::read_to_string(&command_arg[0]) {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("Could not read the payout file: {}", e);
+                return;
+            },
+        };
+        let rows = match parse_payout_file(&contents) {
+            Ok(rows) => rows,
+            Err(errors) => {
+                println!("The payout file is not valid, nothing was sent:");
+                for error in errors {
+                    println!("   {}", error);
+                }
+                return;
+            },
+        };
+
+        let fee_per_gram = 25 * uT; // TODO: use configured fee per gram
+        let mut output_manager = self.wallet_output_service.clone();
+        let mut txn_service = self.wallet_transaction_service.clone();
+        self.executor.spawn(async move {
+            // Check up front that the wallet can pay for every row, so that a batch is not left partially paid out
+            let mut total = MicroTari::from(0);
+            for row in rows.iter() {
+                match output_manager.estimate_fee(row.amount, fee_per_gram, 1).await {
+                    Ok((fee, _)) => total = total + row.amount + fee,
+                    Err(e) => {
+                        println!("Line {}: the payment cannot be made, nothing was sent: {}", row.line, e);
+                        return;
+                    },
+                }
+            }
+            match output_manager.get_balance().await {
+                Ok(balance) if balance.available_balance < total => {
+                    println!(
+                        "The batch needs {} including fees, but only {} is available. Nothing was sent.",
+                        total, balance.available_balance
+                    );
+                    return;
+                },
+                Ok(_) => (),
+                Err(e) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+            }
+
+            let mut results = Vec::with_capacity(rows.len());
+            for row in rows {
+                let outcome = match txn_service
+                    .send_transaction(row.destination.clone(), row.amount, fee_per_gram, row.reference.clone())
+                    .await
+                {
+                    Ok(tx_id) => PayoutOutcome::Sent(tx_id),
+                    Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => {
+                        PayoutOutcome::DiscoveryInProgress(tx_id)
+                    },
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Batch payment on line {} failed: {:?}", row.line, e);
+                        PayoutOutcome::Failed(e.to_string())
+                    },
+                };
+                results.push((row, outcome));
+            }
+
+            let failed = results
+                .iter()
+                .filter(|(_, outcome)| match outcome {
+                    PayoutOutcome::Failed(_) => true,
+                    _ => false,
+                })
+                .count();
+            println!(
+                "Sent {} of {} payments, {} failed",
+                results.len() - failed,
+                results.len(),
+                failed
+            );
+            match fs::write(&report_path, format_payout_report(&results)) {
+                Ok(()) => println!("The report was written to {}", report_path),
+                Err(e) => {
+                    println!("Could not write the report file: {}", e);
+                    println!("{}", format_payout_report(&results));
+                },
+            }
+        });
+    }
+
     // Function to process the make it rain transaction function
     fn process_make_it_rain(&mut self, command_arg: Vec<String>) {
         let command_error_msg =
@@ -1412,8 +2090,8 @@ impl Parser {
         // TODO: Start at specified time, must be non-blocking
         for i in 0..(tx_per_s * duration as f64) as usize {
             // `send-tari` commands: [amount of tari to send] [destination public key or emoji id] [optional: msg]
-            let command_str =
-                (start_amount.0 + amount_inc.0 * i as u64).to_string() + " " + &dest_pubkey.to_string() + " " + &msg;
+            let amount = start_amount + amount_inc * i as u64;
+            let command_str = format!("{}uT {} {}", amount.as_micro_tari(), dest_pubkey, msg);
             let args = command_str.split_whitespace();
             // Execute
             self.process_send_tari(args);
@@ -1421,13 +2099,6 @@ impl Parser {
     }
 }
 
-/// Returns a CommsPublicKey from either a emoji id or a public key
-fn parse_emoji_id_or_public_key(key: &str) -> Option<CommsPublicKey> {
-    EmojiId::str_to_pubkey(&key.trim().replace('|', ""))
-        .or_else(|_| CommsPublicKey::from_hex(key))
-        .ok()
-}
-
 /// Given a slice of headers (in reverse order), calculate the maximum, minimum and average periods between them
 fn timing_stats(headers: &[BlockHeader]) -> (u64, u64, f64) {
     let (max, min) = headers.windows(2).fold((0u64, std::u64::MAX), |(max, min), next| {
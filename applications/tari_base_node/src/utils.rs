@@ -23,7 +23,9 @@
 use chrono::NaiveDateTime;
 use futures::{Stream, StreamExt};
 use std::{sync::Arc, time::Duration};
-use tari_wallet::transaction_service::handle::TransactionEvent;
+use tari_comms::types::CommsPublicKey;
+use tari_core::tari_utilities::hex::Hex;
+use tari_wallet::{transaction_service::handle::TransactionEvent, util::emoji::EmojiId};
 use tokio::sync::broadcast::RecvError;
 
 pub const LOG_TARGET: &str = "base_node::app::utils";
@@ -79,6 +81,13 @@ pub fn format_naive_datetime(dt: &NaiveDateTime) -> String {
     dt.format("%Y-%m-%d %H:%M:%S").to_string()
 }
 
+/// Returns a CommsPublicKey from either a emoji id or a public key
+pub fn parse_emoji_id_or_public_key(key: &str) -> Option<CommsPublicKey> {
+    EmojiId::str_to_pubkey(&key.trim().replace('|', ""))
+        .or_else(|_| CommsPublicKey::from_hex(key))
+        .ok()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
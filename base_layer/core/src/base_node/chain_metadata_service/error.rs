@@ -24,6 +24,7 @@ use crate::base_node::comms_interface::CommsInterfaceError;
 use derive_error::Error;
 use prost::DecodeError;
 use tari_comms::message::MessageError;
+use tari_crypto::signatures::SchnorrSignatureError;
 use tari_p2p::services::liveness::error::LivenessError;
 
 #[derive(Debug, Error)]
@@ -32,6 +33,12 @@ pub enum ChainMetadataSyncError {
     DecodeError(DecodeError),
     /// Peer did not send any chain metadata
     NoChainMetadata,
+    /// The chain metadata signature is invalid
+    InvalidSignature,
+    /// The chain metadata was signed by a key that does not belong to the sending peer
+    SignerMismatch,
+    /// Failed to sign chain metadata
+    SignatureError(SchnorrSignatureError),
     LivenessError(LivenessError),
     CommsInterfaceError(CommsInterfaceError),
     MessageError(MessageError),
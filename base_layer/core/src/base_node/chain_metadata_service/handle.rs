@@ -30,6 +30,10 @@ use tari_comms::peer_manager::NodeId;
 pub struct PeerChainMetadata {
     pub node_id: NodeId,
     pub chain_metadata: ChainMetadata,
+    /// Whether the metadata is known to come from the peer, because it was signed by the peer or received in response
+    /// to a request sent to the peer. Unsigned metadata from peers that do not sign their metadata yet is only used if
+    /// no verified metadata is available.
+    pub verified: bool,
 }
 
 impl PeerChainMetadata {
@@ -37,6 +41,16 @@ impl PeerChainMetadata {
         Self {
             node_id,
             chain_metadata,
+            verified: true,
+        }
+    }
+
+    /// Chain metadata that was received without a signature
+    pub fn unsigned(node_id: NodeId, chain_metadata: ChainMetadata) -> Self {
+        Self {
+            node_id,
+            chain_metadata,
+            verified: false,
         }
     }
 }
@@ -51,6 +65,8 @@ impl Display for PeerChainMetadata {
 #[derive(Debug)]
 pub enum ChainMetadataEvent {
     PeerChainMetadataReceived(Vec<PeerChainMetadata>),
+    /// The offset in milliseconds of the local clock from network time has exceeded the maximum allowed offset
+    NetworkTimeOffsetExceeded(i64),
 }
 
 #[derive(Clone)]
@@ -20,21 +20,39 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{service::ChainMetadataService, LOG_TARGET};
+use super::{
+    service::{ChainMetadataService, SERVICE_NAME},
+    LOG_TARGET,
+};
 use crate::base_node::{chain_metadata_service::handle::ChainMetadataHandle, comms_interface::LocalNodeCommsInterface};
 use futures::{future, future::select, pin_mut};
 use log::*;
-use std::future::Future;
+use std::{future::Future, sync::Arc};
 use tari_broadcast_channel as broadcast_channel;
+use tari_comms::peer_manager::NodeIdentity;
 use tari_p2p::services::liveness::LivenessHandle;
-use tari_service_framework::{handles::ServiceHandlesFuture, ServiceInitializationError, ServiceInitializer};
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    watchdog::Watchdog,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
 use tari_shutdown::ShutdownSignal;
 use tokio::runtime;
 
 // Must be set to 1 to ensure outdated chain metadata is discarded.
 const BROADCAST_EVENT_BUFFER_SIZE: usize = 1;
 
-pub struct ChainMetadataServiceInitializer;
+pub struct ChainMetadataServiceInitializer {
+    node_identity: Arc<NodeIdentity>,
+}
+
+impl ChainMetadataServiceInitializer {
+    /// Create a new initializer. The node identity is used to sign the chain metadata sent to peers.
+    pub fn new(node_identity: Arc<NodeIdentity>) -> Self {
+        Self { node_identity }
+    }
+}
 
 impl ServiceInitializer for ChainMetadataServiceInitializer {
     type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
@@ -49,6 +67,7 @@ impl ServiceInitializer for ChainMetadataServiceInitializer {
         let (publisher, subscriber) = broadcast_channel::bounded(BROADCAST_EVENT_BUFFER_SIZE);
         let handle = ChainMetadataHandle::new(subscriber);
         handles_fut.register(handle);
+        let node_identity = Arc::clone(&self.node_identity);
 
         executor.spawn(async move {
             let handles = handles_fut.await;
@@ -61,7 +80,14 @@ impl ServiceInitializer for ChainMetadataServiceInitializer {
                 .get_handle::<LocalNodeCommsInterface>()
                 .expect("LocalNodeCommsInterface required to initialize ChainStateSyncService");
 
-            let service_run = ChainMetadataService::new(liveness, base_node, publisher).run();
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
+
+            let service_run = ChainMetadataService::new(liveness, base_node, node_identity, publisher)
+                .with_monitor(monitor)
+                .run();
             pin_mut!(service_run);
             select(service_run, shutdown).await;
             info!(target: LOG_TARGET, "ChainMetadataService has shut down");
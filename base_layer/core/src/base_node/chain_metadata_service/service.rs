@@ -33,17 +33,31 @@ use chrono::{NaiveDateTime, Utc};
 use futures::{stream::StreamExt, SinkExt};
 use log::*;
 use prost::Message;
+use rand::rngs::OsRng;
+use std::sync::Arc;
 use tari_broadcast_channel::Publisher;
 use tari_common::log_if_error;
-use tari_comms::{message::MessageExt, peer_manager::NodeId};
+use tari_comms::{
+    message::{MessageError, MessageExt},
+    peer_manager::{NodeId, NodeIdentity},
+    types::CommsPublicKey,
+    utils::signature,
+};
+use tari_crypto::tari_utilities::{message_format::MessageFormat, ByteArray};
 use tari_p2p::services::liveness::{LivenessEvent, LivenessHandle, Metadata, MetadataKey};
+use tari_service_framework::watchdog::{ServiceMonitor, Watchdog};
+
+/// The name the service is registered with at the watchdog
+pub(super) const SERVICE_NAME: &str = "Chain Metadata Service";
 
 pub(super) struct ChainMetadataService {
     liveness: LivenessHandle,
     base_node: LocalNodeCommsInterface,
+    node_identity: Arc<NodeIdentity>,
     peer_chain_metadata: Vec<PeerChainMetadata>,
     last_chainstate_flushed_at: NaiveDateTime,
     event_publisher: Publisher<ChainMetadataEvent>,
+    monitor: ServiceMonitor,
 }
 
 impl ChainMetadataService {
@@ -52,21 +66,31 @@ impl ChainMetadataService {
     /// ## Arguments
     /// `liveness` - the liveness service handle
     /// `base_node` - the base node service handle
+    /// `node_identity` - the identity used to sign the chain metadata sent to peers
     pub fn new(
         liveness: LivenessHandle,
         base_node: LocalNodeCommsInterface,
+        node_identity: Arc<NodeIdentity>,
         event_publisher: Publisher<ChainMetadataEvent>,
     ) -> Self
     {
         Self {
             liveness,
             base_node,
+            node_identity,
             peer_chain_metadata: Vec::new(),
             last_chainstate_flushed_at: Utc::now().naive_utc(),
             event_publisher,
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Report the events that are being handled to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     /// Run the service
     pub async fn run(mut self) {
         let mut liveness_event_stream = self.liveness.get_event_stream_fused();
@@ -81,6 +105,7 @@ impl ChainMetadataService {
         loop {
             futures::select! {
                 event = base_node_event_stream.select_next_some() => {
+                    let _activity = self.monitor.enter("block event");
                     log_if_error!(
                         level: debug,
                         target: LOG_TARGET,
@@ -90,6 +115,7 @@ impl ChainMetadataService {
                 },
 
                 liveness_event = liveness_event_stream.select_next_some() => {
+                    let _activity = self.monitor.enter("liveness event");
                     log_if_error!(
                         target: LOG_TARGET,
                         "Failed to handle liveness event because '{}'",
@@ -117,12 +143,16 @@ impl ChainMetadataService {
         Ok(())
     }
 
-    /// Send this node's metadata to
+    /// Sign this node's metadata and send it to neighbouring peers in liveness pongs. The unsigned metadata is sent
+    /// as well for peers that do not verify signed metadata yet.
     async fn update_liveness_chain_metadata(&mut self) -> Result<(), ChainMetadataSyncError> {
-        let chain_metadata = self.base_node.get_metadata().await?;
-        let bytes = proto::ChainMetadata::from(chain_metadata).to_encoded_bytes();
+        let chain_metadata = proto::ChainMetadata::from(self.base_node.get_metadata().await?);
+        let signed_bytes = sign_chain_metadata(&self.node_identity, chain_metadata.clone())?.to_encoded_bytes();
         self.liveness
-            .set_pong_metadata_entry(MetadataKey::ChainMetadata, bytes)
+            .set_pong_metadata_entry(MetadataKey::SignedChainMetadata, signed_bytes)
+            .await?;
+        self.liveness
+            .set_pong_metadata_entry(MetadataKey::ChainMetadata, chain_metadata.to_encoded_bytes())
             .await?;
         Ok(())
     }
@@ -158,6 +188,12 @@ impl ChainMetadataService {
                 // and have allocated space for their replies
                 self.resize_chainstate_buffer(*num_peers);
             },
+            LivenessEvent::NetworkTimeOffsetExceeded(offset) => {
+                self.event_publisher
+                    .send(ChainMetadataEvent::NetworkTimeOffsetExceeded(*offset))
+                    .await
+                    .map_err(|_| ChainMetadataSyncError::EventPublishFailed)?;
+            },
             _ => {},
         }
 
@@ -196,13 +232,33 @@ impl ChainMetadataService {
         metadata: &Metadata,
     ) -> Result<(), ChainMetadataSyncError>
     {
-        let chain_metadata_bytes = metadata
-            .get(MetadataKey::ChainMetadata)
-            .ok_or_else(|| ChainMetadataSyncError::NoChainMetadata)?;
-
-        let chain_metadata = proto::ChainMetadata::decode(chain_metadata_bytes.as_slice())?.into();
-        debug!(target: LOG_TARGET, "Received chain metadata from NodeId '{}'", node_id);
-        trace!(target: LOG_TARGET, "{}", chain_metadata);
+        // Signed metadata takes precedence. Metadata that fails verification is rejected rather than falling back to
+        // the unsigned metadata sent alongside it.
+        let peer_chain_metadata = match metadata.get(MetadataKey::SignedChainMetadata) {
+            Some(signed_metadata_bytes) => {
+                let signed_metadata = proto::SignedChainMetadata::decode(signed_metadata_bytes.as_slice())?;
+                let chain_metadata = verify_chain_metadata(node_id, &signed_metadata)?.into();
+                PeerChainMetadata::new(node_id.clone(), chain_metadata)
+            },
+            None => {
+                let chain_metadata_bytes = metadata
+                    .get(MetadataKey::ChainMetadata)
+                    .ok_or_else(|| ChainMetadataSyncError::NoChainMetadata)?;
+                let chain_metadata = proto::ChainMetadata::decode(chain_metadata_bytes.as_slice())?.into();
+                PeerChainMetadata::unsigned(node_id.clone(), chain_metadata)
+            },
+        };
+        debug!(
+            target: LOG_TARGET,
+            "Received {} chain metadata from NodeId '{}'",
+            if peer_chain_metadata.verified {
+                "signed"
+            } else {
+                "unsigned"
+            },
+            node_id
+        );
+        trace!(target: LOG_TARGET, "{}", peer_chain_metadata.chain_metadata);
 
         if let Some(pos) = self
             .peer_chain_metadata
@@ -212,19 +268,52 @@ impl ChainMetadataService {
             self.peer_chain_metadata.remove(pos);
         }
 
-        self.peer_chain_metadata
-            .push(PeerChainMetadata::new(node_id.clone(), chain_metadata));
+        self.peer_chain_metadata.push(peer_chain_metadata);
 
         Ok(())
     }
 }
 
+/// Encode the chain metadata and sign it with the comms secret key of this node
+fn sign_chain_metadata(
+    node_identity: &NodeIdentity,
+    chain_metadata: proto::ChainMetadata,
+) -> Result<proto::SignedChainMetadata, ChainMetadataSyncError>
+{
+    let metadata = chain_metadata.to_encoded_bytes();
+    let signature = signature::sign(&mut OsRng, node_identity.secret_key().clone(), &metadata)?;
+    Ok(proto::SignedChainMetadata {
+        metadata,
+        public_key: node_identity.public_key().to_vec(),
+        signature: signature.to_binary().map_err(MessageError::MessageFormatError)?,
+    })
+}
+
+/// Check that the signed chain metadata was signed by the peer that sent it and return the decoded metadata
+fn verify_chain_metadata(
+    node_id: &NodeId,
+    signed_metadata: &proto::SignedChainMetadata,
+) -> Result<proto::ChainMetadata, ChainMetadataSyncError>
+{
+    let public_key = CommsPublicKey::from_bytes(&signed_metadata.public_key)
+        .map_err(|_| ChainMetadataSyncError::InvalidSignature)?;
+    let signer_node_id = NodeId::from_key(&public_key).map_err(|_| ChainMetadataSyncError::InvalidSignature)?;
+    if &signer_node_id != node_id {
+        return Err(ChainMetadataSyncError::SignerMismatch);
+    }
+    if !signature::verify(&public_key, &signed_metadata.signature, &signed_metadata.metadata)? {
+        return Err(ChainMetadataSyncError::InvalidSignature);
+    }
+    Ok(proto::ChainMetadata::decode(signed_metadata.metadata.as_slice())?)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::base_node::comms_interface::{CommsInterfaceError, NodeCommsRequest, NodeCommsResponse};
     use std::convert::TryInto;
     use tari_broadcast_channel as broadcast_channel;
+    use tari_comms::peer_manager::PeerFeatures;
     use tari_p2p::services::liveness::{mock::create_p2p_liveness_mock, LivenessRequest, PongEvent};
     use tari_service_framework::reply_channel;
     use tari_test_utils::{runtime, unpack_enum};
@@ -241,11 +330,24 @@ mod test {
         (base_node, base_node_receiver)
     }
 
+    fn create_node_identity() -> Arc<NodeIdentity> {
+        Arc::new(
+            NodeIdentity::random(
+                &mut OsRng,
+                "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+                PeerFeatures::COMMUNICATION_NODE,
+            )
+            .unwrap(),
+        )
+    }
+
     fn create_sample_proto_chain_metadata() -> proto::ChainMetadata {
         proto::ChainMetadata {
             height_of_longest_chain: Some(1),
             best_block: Some(vec![]),
             pruning_horizon: 64,
+            effective_pruned_height: 0,
+            best_block_timestamp: Some(0),
             accumulated_difficulty: 1.into(),
         }
     }
@@ -260,7 +362,7 @@ mod test {
             let (base_node, mut base_node_receiver) = create_base_node_nci();
 
             let (publisher, _subscriber) = broadcast_channel::bounded(1);
-            let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+            let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
 
             let mut proto_chain_metadata = create_sample_proto_chain_metadata();
             proto_chain_metadata.height_of_longest_chain = Some(123);
@@ -276,10 +378,17 @@ mod test {
 
             rt.block_on(service.update_liveness_chain_metadata()).unwrap();
 
-            assert_eq!(liveness_mock_state.call_count(), 1);
+            assert_eq!(liveness_mock_state.call_count(), 2);
 
-            let last_call = liveness_mock_state.take_calls().remove(0);
-            unpack_enum!(LivenessRequest::SetPongMetadata(metadata_key, data) = last_call);
+            let mut calls = liveness_mock_state.take_calls();
+            unpack_enum!(LivenessRequest::SetPongMetadata(metadata_key, data) = calls.remove(0));
+            assert_eq!(metadata_key, MetadataKey::SignedChainMetadata);
+            let signed_metadata = proto::SignedChainMetadata::decode(data.as_slice()).unwrap();
+            assert_eq!(signed_metadata.public_key, service.node_identity.public_key().to_vec());
+            let chain_metadata = verify_chain_metadata(service.node_identity.node_id(), &signed_metadata).unwrap();
+            assert_eq!(chain_metadata.height_of_longest_chain, Some(123));
+
+            unpack_enum!(LivenessRequest::SetPongMetadata(metadata_key, data) = calls.remove(0));
             assert_eq!(metadata_key, MetadataKey::ChainMetadata);
             let chain_metadata = proto::ChainMetadata::decode(data.as_slice()).unwrap();
             assert_eq!(chain_metadata.height_of_longest_chain, Some(123));
@@ -291,9 +400,11 @@ mod test {
         let (liveness_handle, _) = create_p2p_liveness_mock(1);
         let mut metadata = Metadata::new();
         let proto_chain_metadata = create_sample_proto_chain_metadata();
-        metadata.insert(MetadataKey::ChainMetadata, proto_chain_metadata.to_encoded_bytes());
+        let peer_identity = create_node_identity();
+        let signed_metadata = sign_chain_metadata(&peer_identity, proto_chain_metadata.clone()).unwrap();
+        metadata.insert(MetadataKey::SignedChainMetadata, signed_metadata.to_encoded_bytes());
 
-        let node_id = NodeId::new();
+        let node_id = peer_identity.node_id().clone();
         let pong_event = PongEvent {
             is_neighbour: true,
             metadata,
@@ -305,7 +416,7 @@ mod test {
         let (base_node, _) = create_base_node_nci();
 
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
 
         // To prevent the chain metadata buffer being flushed after receiving a single pong event,
         // extend it's capacity to 2
@@ -319,6 +430,69 @@ mod test {
             metadata.chain_metadata.height_of_longest_chain,
             proto_chain_metadata.height_of_longest_chain
         );
+        assert!(metadata.verified);
+    }
+
+    #[tokio_macros::test]
+    async fn handle_liveness_event_unsigned_metadata() {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let mut metadata = Metadata::new();
+        let proto_chain_metadata = create_sample_proto_chain_metadata();
+        metadata.insert(MetadataKey::ChainMetadata, proto_chain_metadata.to_encoded_bytes());
+        let node_id = create_node_identity().node_id().clone();
+        let pong_event = PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: node_id.clone(),
+            latency: None,
+            is_monitored: false,
+        };
+
+        let (base_node, _) = create_base_node_nci();
+        let (publisher, _subscriber) = broadcast_channel::bounded(1);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
+
+        service.peer_chain_metadata.reserve_exact(2);
+        let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
+        service.handle_liveness_event(&sample_event).await.unwrap();
+        assert_eq!(service.peer_chain_metadata.len(), 1);
+        let metadata = service.peer_chain_metadata.remove(0);
+        assert_eq!(metadata.node_id, node_id);
+        assert_eq!(
+            metadata.chain_metadata.height_of_longest_chain,
+            proto_chain_metadata.height_of_longest_chain
+        );
+        assert!(!metadata.verified);
+    }
+
+    #[tokio_macros::test]
+    async fn handle_liveness_event_signed_metadata_preferred() {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let mut metadata = Metadata::new();
+        let peer_identity = create_node_identity();
+        let signed_metadata = sign_chain_metadata(&peer_identity, create_sample_proto_chain_metadata()).unwrap();
+        metadata.insert(MetadataKey::SignedChainMetadata, signed_metadata.to_encoded_bytes());
+        let mut unsigned_metadata = create_sample_proto_chain_metadata();
+        unsigned_metadata.height_of_longest_chain = Some(1_000_000);
+        metadata.insert(MetadataKey::ChainMetadata, unsigned_metadata.to_encoded_bytes());
+        let pong_event = PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: peer_identity.node_id().clone(),
+            latency: None,
+            is_monitored: false,
+        };
+
+        let (base_node, _) = create_base_node_nci();
+        let (publisher, _subscriber) = broadcast_channel::bounded(1);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
+
+        service.peer_chain_metadata.reserve_exact(2);
+        let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
+        service.handle_liveness_event(&sample_event).await.unwrap();
+        let metadata = service.peer_chain_metadata.remove(0);
+        assert_eq!(metadata.chain_metadata.height_of_longest_chain, Some(1));
+        assert!(metadata.verified);
     }
 
     #[tokio_macros::test]
@@ -336,7 +510,7 @@ mod test {
 
         let (base_node, _) = create_base_node_nci();
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
 
         let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
         let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
@@ -360,11 +534,79 @@ mod test {
 
         let (base_node, _) = create_base_node_nci();
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
 
         let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
         let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
         unpack_enum!(ChainMetadataSyncError::DecodeError(_err) = err);
         assert_eq!(service.peer_chain_metadata.len(), 0);
     }
+
+    #[tokio_macros::test]
+    async fn handle_liveness_event_signer_mismatch() {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let mut metadata = Metadata::new();
+        let signed_metadata =
+            sign_chain_metadata(&create_node_identity(), create_sample_proto_chain_metadata()).unwrap();
+        metadata.insert(MetadataKey::SignedChainMetadata, signed_metadata.to_encoded_bytes());
+        let pong_event = PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: create_node_identity().node_id().clone(),
+            latency: None,
+            is_monitored: false,
+        };
+
+        let (base_node, _) = create_base_node_nci();
+        let (publisher, _subscriber) = broadcast_channel::bounded(1);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
+
+        let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
+        let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
+        unpack_enum!(ChainMetadataSyncError::SignerMismatch = err);
+        assert_eq!(service.peer_chain_metadata.len(), 0);
+    }
+
+    #[tokio_macros::test]
+    async fn handle_liveness_event_invalid_signature() {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let mut metadata = Metadata::new();
+        let peer_identity = create_node_identity();
+        let mut signed_metadata = sign_chain_metadata(&peer_identity, create_sample_proto_chain_metadata()).unwrap();
+        // Claim a taller chain than the one that was signed
+        let mut spoofed_metadata = create_sample_proto_chain_metadata();
+        spoofed_metadata.height_of_longest_chain = Some(1_000_000);
+        signed_metadata.metadata = spoofed_metadata.to_encoded_bytes();
+        metadata.insert(MetadataKey::SignedChainMetadata, signed_metadata.to_encoded_bytes());
+        let pong_event = PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: peer_identity.node_id().clone(),
+            latency: None,
+            is_monitored: false,
+        };
+
+        let (base_node, _) = create_base_node_nci();
+        let (publisher, _subscriber) = broadcast_channel::bounded(1);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
+
+        let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
+        let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
+        unpack_enum!(ChainMetadataSyncError::InvalidSignature = err);
+        assert_eq!(service.peer_chain_metadata.len(), 0);
+    }
+
+    #[tokio_macros::test]
+    async fn handle_liveness_event_time_offset_exceeded() {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let (base_node, _) = create_base_node_nci();
+        let (publisher, mut subscriber) = broadcast_channel::bounded(1);
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, create_node_identity(), publisher);
+
+        let sample_event = LivenessEvent::NetworkTimeOffsetExceeded(-120_000);
+        service.handle_liveness_event(&sample_event).await.unwrap();
+        let event = subscriber.next().await.unwrap();
+        unpack_enum!(ChainMetadataEvent::NetworkTimeOffsetExceeded(offset) = &*event);
+        assert_eq!(*offset, -120_000);
+    }
 }
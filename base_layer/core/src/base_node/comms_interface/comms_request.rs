@@ -24,10 +24,11 @@ use crate::{
     blocks::NewBlockTemplate,
     chain_storage::MmrTree,
     proof_of_work::PowAlgorithm,
-    transactions::types::HashOutput,
+    transactions::types::{Commitment, HashOutput},
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
+use tari_crypto::tari_utilities::hex::Hex;
 
 /// A container for the parameters required for a FetchMmrState request.
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,15 +43,21 @@ pub struct MmrStateRequest {
 pub enum NodeCommsRequest {
     GetChainMetadata,
     FetchKernels(Vec<HashOutput>),
+    FetchKernelByExcess(Commitment),
     FetchHeaders(Vec<u64>),
     FetchHeadersWithHashes(Vec<HashOutput>),
     FetchHeadersAfter(Vec<HashOutput>, HashOutput),
     FetchUtxos(Vec<HashOutput>),
     FetchBlocks(Vec<u64>),
     FetchBlocksWithHashes(Vec<HashOutput>),
+    FetchBlockWithUtxo(Commitment),
+    FetchBlockWithKernel(Commitment),
     GetNewBlockTemplate,
     GetNewBlock(NewBlockTemplate),
     GetTargetDifficulty(PowAlgorithm),
+    GetTotalBurned,
+    GetTailEmissionHeight,
+    FetchDifficultyStats(u64, u64),
 }
 
 impl Display for NodeCommsRequest {
@@ -58,15 +65,29 @@ impl Display for NodeCommsRequest {
         match self {
             NodeCommsRequest::GetChainMetadata => f.write_str("GetChainMetadata"),
             NodeCommsRequest::FetchKernels(v) => f.write_str(&format!("FetchKernels (n={})", v.len())),
+            NodeCommsRequest::FetchKernelByExcess(excess) => {
+                f.write_str(&format!("FetchKernelByExcess ({})", excess.to_hex()))
+            },
             NodeCommsRequest::FetchHeaders(v) => f.write_str(&format!("FetchHeaders (n={})", v.len())),
             NodeCommsRequest::FetchHeadersWithHashes(v) => f.write_str(&format!("FetchHeaders (n={})", v.len())),
             NodeCommsRequest::FetchHeadersAfter(v, _hash) => f.write_str(&format!("FetchHeadersAfter (n={})", v.len())),
             NodeCommsRequest::FetchUtxos(v) => f.write_str(&format!("FetchUtxos (n={})", v.len())),
             NodeCommsRequest::FetchBlocks(v) => f.write_str(&format!("FetchBlocks (n={})", v.len())),
             NodeCommsRequest::FetchBlocksWithHashes(v) => f.write_str(&format!("FetchBlocks (n={})", v.len())),
+            NodeCommsRequest::FetchBlockWithUtxo(commitment) => {
+                f.write_str(&format!("FetchBlockWithUtxo ({})", commitment.to_hex()))
+            },
+            NodeCommsRequest::FetchBlockWithKernel(excess) => {
+                f.write_str(&format!("FetchBlockWithKernel ({})", excess.to_hex()))
+            },
             NodeCommsRequest::GetNewBlockTemplate => f.write_str("GetNewBlockTemplate"),
             NodeCommsRequest::GetNewBlock(b) => f.write_str(&format!("GetNewBlock (Block Height={})", b.header.height)),
             NodeCommsRequest::GetTargetDifficulty(algo) => f.write_str(&format!("GetTargetDifficulty ({})", algo)),
+            NodeCommsRequest::GetTotalBurned => f.write_str("GetTotalBurned"),
+            NodeCommsRequest::GetTailEmissionHeight => f.write_str("GetTailEmissionHeight"),
+            NodeCommsRequest::FetchDifficultyStats(start, end) => {
+                f.write_str(&format!("FetchDifficultyStats ({}..={})", start, end))
+            },
         }
     }
 }
@@ -23,10 +23,14 @@
 use crate::{
     blocks::{blockheader::BlockHeader, Block, NewBlockTemplate},
     chain_storage::{ChainMetadata, HistoricalBlock},
-    proof_of_work::Difficulty,
-    transactions::transaction::{TransactionKernel, TransactionOutput},
+    proof_of_work::{BlockDifficultyStats, Difficulty},
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::{TransactionKernel, TransactionOutput},
+    },
 };
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// API Response enum
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,4 +44,9 @@ pub enum NodeCommsResponse {
     NewBlock(Block),
     TargetDifficulty(Difficulty),
     FetchHeadersAfterResponse(Vec<BlockHeader>),
+    TotalBurned(MicroTari),
+    TailEmissionHeight(u64),
+    DifficultyStats(Vec<BlockDifficultyStats>),
+    /// The node is in maintenance and did not handle the request, which should be retried after this period
+    RetryAfter(Duration),
 }
@@ -43,7 +43,7 @@ use log::*;
 use std::sync::Arc;
 use strum_macros::Display;
 use tari_broadcast_channel::Publisher;
-use tari_comms::types::CommsPublicKey;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
 use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex};
 use tokio::sync::RwLock;
 
@@ -105,6 +105,13 @@ where T: BlockchainBackend + 'static
                 }
                 Ok(NodeCommsResponse::TransactionKernels(kernels))
             },
+            NodeCommsRequest::FetchKernelByExcess(excess) => {
+                let kernels = async_db::fetch_kernel_with_excess(self.blockchain_db.clone(), excess.clone())
+                    .await?
+                    .into_iter()
+                    .collect();
+                Ok(NodeCommsResponse::TransactionKernels(kernels))
+            },
             NodeCommsRequest::FetchHeaders(block_nums) => {
                 let mut block_headers = Vec::<BlockHeader>::new();
                 for block_num in block_nums {
@@ -202,6 +209,20 @@ where T: BlockchainBackend + 'static
                 }
                 Ok(NodeCommsResponse::HistoricalBlocks(blocks))
             },
+            NodeCommsRequest::FetchBlockWithUtxo(commitment) => {
+                let blocks = async_db::fetch_block_with_utxo(self.blockchain_db.clone(), commitment.clone())
+                    .await?
+                    .into_iter()
+                    .collect();
+                Ok(NodeCommsResponse::HistoricalBlocks(blocks))
+            },
+            NodeCommsRequest::FetchBlockWithKernel(excess) => {
+                let blocks = async_db::fetch_block_with_kernel(self.blockchain_db.clone(), excess.clone())
+                    .await?
+                    .into_iter()
+                    .collect();
+                Ok(NodeCommsResponse::HistoricalBlocks(blocks))
+            },
             NodeCommsRequest::GetNewBlockTemplate => {
                 let metadata = async_db::get_metadata(self.blockchain_db.clone()).await?;
                 let best_block_hash = metadata
@@ -210,7 +231,10 @@ where T: BlockchainBackend + 'static
                 let best_block_header =
                     async_db::fetch_header_with_block_hash(self.blockchain_db.clone(), best_block_hash).await?;
                 let mut header = BlockHeader::from_previous(&best_block_header);
-                header.version = self.consensus_manager.consensus_constants().blockchain_version();
+                header.version = {
+                    let db = &self.blockchain_db.db_read_access()?;
+                    self.consensus_manager.get_block_version(&**db, header.height)?
+                };
 
                 let transactions = async_mempool::retrieve(
                     self.mempool.clone(),
@@ -239,6 +263,19 @@ where T: BlockchainBackend + 'static
                     self.consensus_manager.get_target_difficulty(&**db, *pow_algo)?,
                 ))
             },
+            NodeCommsRequest::GetTotalBurned => Ok(NodeCommsResponse::TotalBurned(
+                async_db::fetch_total_burned(self.blockchain_db.clone()).await?,
+            )),
+            NodeCommsRequest::GetTailEmissionHeight => Ok(NodeCommsResponse::TailEmissionHeight(
+                self.consensus_manager.emission_schedule().tail_start_height(),
+            )),
+            NodeCommsRequest::FetchDifficultyStats(start_height, end_height) => {
+                let db = &self.blockchain_db.db_read_access()?;
+                Ok(NodeCommsResponse::DifficultyStats(
+                    self.consensus_manager
+                        .get_difficulty_stats(&**db, *start_height..=*end_height)?,
+                ))
+            },
         }
     }
 
@@ -276,6 +313,17 @@ where T: BlockchainBackend + 'static
             .send(block_event)
             .await
             .map_err(|_| CommsInterfaceError::EventStreamError)?;
+        // Remember the peers that sent orphans, so that the node can re-sync from them if the parents were missed
+        if let (Ok(BlockAddResult::OrphanBlock), Some(peer)) = (&add_block_result, source_peer.as_ref()) {
+            if let Ok(node_id) = NodeId::from_key(peer) {
+                if let Err(e) = self.blockchain_db.stalled_orphans_handle().add_source_peer(node_id) {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Could not record the source peer of an orphan block: {}", e
+                    );
+                }
+            }
+        }
         // Propagate verified block to remote nodes
         if let Ok(add_block_result) = add_block_result {
             let propagate = match add_block_result {
@@ -283,6 +331,7 @@ where T: BlockchainBackend + 'static
                 BlockAddResult::BlockExists => false,
                 BlockAddResult::OrphanBlock => false,
                 BlockAddResult::ChainReorg(_) => true,
+                BlockAddResult::ReorgRefused(_) => false,
             };
             if propagate {
                 debug!(
@@ -24,7 +24,8 @@ use crate::{
     base_node::comms_interface::{error::CommsInterfaceError, BlockEvent, NodeCommsRequest, NodeCommsResponse},
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{ChainMetadata, HistoricalBlock},
-    proof_of_work::{Difficulty, PowAlgorithm},
+    proof_of_work::{BlockDifficultyStats, Difficulty, PowAlgorithm},
+    transactions::tari_amount::MicroTari,
 };
 use futures::{stream::Fuse, StreamExt};
 use tari_broadcast_channel::Subscriber;
@@ -135,6 +136,44 @@ impl LocalNodeCommsInterface {
         }
     }
 
+    /// Request the total value burned by the burn kernels on the main chain from the base node service.
+    pub async fn get_total_burned(&mut self) -> Result<MicroTari, CommsInterfaceError> {
+        match self.request_sender.call(NodeCommsRequest::GetTotalBurned).await?? {
+            NodeCommsResponse::TotalBurned(total_burned) => Ok(total_burned),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Request the height from which the block reward is only the tail emission from the base node service.
+    pub async fn get_tail_emission_height(&mut self) -> Result<u64, CommsInterfaceError> {
+        match self
+            .request_sender
+            .call(NodeCommsRequest::GetTailEmissionHeight)
+            .await??
+        {
+            NodeCommsResponse::TailEmissionHeight(height) => Ok(height),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Request the difficulty statistics of the blocks from `start_height` to `end_height`, inclusive, from the base
+    /// node service.
+    pub async fn get_difficulty_stats(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<Vec<BlockDifficultyStats>, CommsInterfaceError>
+    {
+        match self
+            .request_sender
+            .call(NodeCommsRequest::FetchDifficultyStats(start_height, end_height))
+            .await??
+        {
+            NodeCommsResponse::DifficultyStats(stats) => Ok(stats),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
     /// Submit a block to the base node service.
     pub async fn submit_block(&mut self, block: Block) -> Result<(), CommsInterfaceError> {
         self.block_sender.call(block).await?
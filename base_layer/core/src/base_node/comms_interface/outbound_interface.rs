@@ -26,7 +26,7 @@ use crate::{
     chain_storage::{ChainMetadata, HistoricalBlock},
     transactions::{
         transaction::{TransactionKernel, TransactionOutput},
-        types::HashOutput,
+        types::{Commitment, HashOutput},
     },
 };
 use futures::channel::mpsc::UnboundedSender;
@@ -266,6 +266,42 @@ impl OutboundNodeCommsInterface {
         }
     }
 
+    /// Fetch the main chain block that created the unspent output with the provided commitment from remote base
+    /// nodes. The returned list is empty if the output is not in the UTXO set of the remote chain.
+    pub async fn fetch_block_with_utxo(
+        &mut self,
+        commitment: Commitment,
+    ) -> Result<Vec<HistoricalBlock>, CommsInterfaceError>
+    {
+        if let NodeCommsResponse::HistoricalBlocks(blocks) = self
+            .request_sender
+            .call((NodeCommsRequest::FetchBlockWithUtxo(commitment), None))
+            .await??
+        {
+            Ok(blocks)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
+    /// Fetch the main chain block that contains the transaction kernel with the provided excess from remote base
+    /// nodes. The returned list is empty if the kernel has not been mined.
+    pub async fn fetch_block_with_kernel(
+        &mut self,
+        excess: Commitment,
+    ) -> Result<Vec<HistoricalBlock>, CommsInterfaceError>
+    {
+        if let NodeCommsResponse::HistoricalBlocks(blocks) = self
+            .request_sender
+            .call((NodeCommsRequest::FetchBlockWithKernel(excess), None))
+            .await??
+        {
+            Ok(blocks)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
     /// Transmit a block to remote base nodes, excluding the provided peers.
     pub async fn propagate_block(
         &mut self,
@@ -26,3 +26,9 @@ use std::time::Duration;
 pub const BASE_NODE_SERVICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
 /// The fraction of responses that need to be received for a corresponding service request to be finalize.
 pub const BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION: f32 = 0.6;
+/// The maximum number of responses to repeated queries from remote nodes that are cached.
+pub const BASE_NODE_SERVICE_RESPONSE_CACHE_CAPACITY: usize = 1000;
+/// The Time-to-live of a cached response to a repeated query from remote nodes.
+pub const BASE_NODE_SERVICE_RESPONSE_CACHE_TTL: Duration = Duration::from_secs(5);
+/// The interval between checks of the free disk space under the data directory of the base node.
+pub const BASE_NODE_DISK_SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
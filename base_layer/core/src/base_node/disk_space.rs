@@ -0,0 +1,226 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{base_node::consts::BASE_NODE_DISK_SPACE_CHECK_INTERVAL, chain_storage::SafeModeHandle};
+use futures::{FutureExt, SinkExt, StreamExt};
+use log::*;
+use std::{io, time::Duration};
+use tari_broadcast_channel::{bounded, Publisher, Subscriber};
+use tari_shutdown::ShutdownSignal;
+use tokio::time;
+
+const LOG_TARGET: &str = "c::bn::disk_space";
+
+/// Configuration for the [DiskSpaceWatchdog].
+#[derive(Clone, Debug)]
+pub struct DiskSpaceWatchdogConfig {
+    /// The interval between checks of the free disk space
+    pub check_interval: Duration,
+    /// The blockchain database is put into safe mode when the free disk space, in bytes, falls below this threshold
+    pub min_free_space: u64,
+    /// The blockchain database is taken out of safe mode once the free disk space, in bytes, is at least this much.
+    /// It should be above `min_free_space` so that the node does not keep entering and leaving safe mode.
+    pub resume_free_space: u64,
+}
+
+impl Default for DiskSpaceWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: BASE_NODE_DISK_SPACE_CHECK_INTERVAL,
+            min_free_space: 1024 * 1024 * 1024,
+            resume_free_space: 1024 * 1024 * 1024 + 100 * 1024 * 1024,
+        }
+    }
+}
+
+/// The events published by the [DiskSpaceWatchdog].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiskSpaceEvent {
+    /// The free disk space fell below the threshold and the blockchain database entered safe mode
+    LowDiskSpace { available: u64, threshold: u64 },
+    /// Enough disk space was freed and the blockchain database left safe mode
+    DiskSpaceRecovered { available: u64 },
+}
+
+/// Periodically checks the free disk space under the data directory of the base node. When it falls below the
+/// configured threshold the blockchain database is put into read-only safe mode, so that the node stops storing new
+/// blocks before a write fails half way through and leaves the backend in an inconsistent state. The node keeps
+/// answering queries while in safe mode, and the database leaves safe mode once enough space has been freed.
+pub struct DiskSpaceWatchdog<F> {
+    config: DiskSpaceWatchdogConfig,
+    safe_mode: SafeModeHandle,
+    available_space: F,
+    event_publisher: Publisher<DiskSpaceEvent>,
+    event_subscriber: Subscriber<DiskSpaceEvent>,
+    // Set while the database is in safe mode because of low disk space, so that a safe mode that was entered for
+    // another reason is left alone
+    in_safe_mode: bool,
+}
+
+impl<F> DiskSpaceWatchdog<F>
+where F: Fn() -> io::Result<u64> + Send + 'static
+{
+    /// Create a watchdog that calls `available_space` to get the number of bytes available under the data directory.
+    pub fn new(config: DiskSpaceWatchdogConfig, safe_mode: SafeModeHandle, available_space: F) -> Self {
+        let (event_publisher, event_subscriber) = bounded(10);
+        Self {
+            config,
+            safe_mode,
+            available_space,
+            event_publisher,
+            event_subscriber,
+            in_safe_mode: false,
+        }
+    }
+
+    /// Returns a stream of the low disk space events.
+    pub fn get_event_stream(&self) -> Subscriber<DiskSpaceEvent> {
+        self.event_subscriber.clone()
+    }
+
+    /// Checks the free disk space at the configured interval until the shutdown signal is triggered.
+    pub async fn run(mut self, shutdown_signal: ShutdownSignal) {
+        let mut shutdown_signal = shutdown_signal.fuse();
+        let mut interval = time::interval(self.config.check_interval).fuse();
+        loop {
+            futures::select! {
+                _ = interval.select_next_some() => {
+                    if let Some(event) = self.check() {
+                        let _ = self.event_publisher.send(event).await;
+                    }
+                },
+                _ = shutdown_signal => {
+                    debug!(target: LOG_TARGET, "Disk space watchdog stopped because of a shutdown signal");
+                    break;
+                },
+            }
+        }
+    }
+
+    // Check the free disk space once, entering or leaving safe mode if the free space has crossed a threshold
+    fn check(&mut self) -> Option<DiskSpaceEvent> {
+        let available = match (self.available_space)() {
+            Ok(available) => available,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Unable to determine the free disk space: {}", e);
+                return None;
+            },
+        };
+        trace!(target: LOG_TARGET, "{} bytes of disk space available", available);
+
+        if !self.in_safe_mode && available < self.config.min_free_space {
+            let reason = format!(
+                "Only {} MiB of disk space is available, below the minimum of {} MiB",
+                available / (1024 * 1024),
+                self.config.min_free_space / (1024 * 1024)
+            );
+            if let Err(e) = self.safe_mode.enter(reason.clone()) {
+                error!(target: LOG_TARGET, "Unable to enter safe mode: {}", e);
+                return None;
+            }
+            error!(
+                target: LOG_TARGET,
+                "{}. The blockchain database is read-only until more space is available.", reason
+            );
+            self.in_safe_mode = true;
+            return Some(DiskSpaceEvent::LowDiskSpace {
+                available,
+                threshold: self.config.min_free_space,
+            });
+        }
+
+        if self.in_safe_mode && available >= self.config.resume_free_space {
+            if let Err(e) = self.safe_mode.exit() {
+                error!(target: LOG_TARGET, "Unable to exit safe mode: {}", e);
+                return None;
+            }
+            info!(
+                target: LOG_TARGET,
+                "{} MiB of disk space is available. The blockchain database has left safe mode.",
+                available / (1024 * 1024)
+            );
+            self.in_safe_mode = false;
+            return Some(DiskSpaceEvent::DiskSpaceRecovered { available });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn enters_and_leaves_safe_mode() {
+        let available = Arc::new(AtomicU64::new(2000));
+        let available_clone = available.clone();
+        let safe_mode = SafeModeHandle::default();
+        let config = DiskSpaceWatchdogConfig {
+            check_interval: Duration::from_secs(1),
+            min_free_space: 1000,
+            resume_free_space: 1500,
+        };
+        let mut watchdog = DiskSpaceWatchdog::new(config, safe_mode.clone(), move || {
+            Ok(available_clone.load(Ordering::SeqCst))
+        });
+
+        assert_eq!(watchdog.check(), None);
+        assert!(!safe_mode.is_active());
+
+        available.store(999, Ordering::SeqCst);
+        assert_eq!(
+            watchdog.check(),
+            Some(DiskSpaceEvent::LowDiskSpace {
+                available: 999,
+                threshold: 1000
+            })
+        );
+        assert!(safe_mode.is_active());
+        assert_eq!(watchdog.check(), None);
+
+        // Safe mode is only left once the free space reaches the resume threshold
+        available.store(1200, Ordering::SeqCst);
+        assert_eq!(watchdog.check(), None);
+        assert!(safe_mode.is_active());
+        available.store(1500, Ordering::SeqCst);
+        assert_eq!(
+            watchdog.check(),
+            Some(DiskSpaceEvent::DiskSpaceRecovered { available: 1500 })
+        );
+        assert!(!safe_mode.is_active());
+    }
+
+    #[test]
+    fn leaves_other_safe_mode_alone() {
+        let safe_mode = SafeModeHandle::default();
+        safe_mode.enter("Operator request".to_string()).unwrap();
+        let mut watchdog = DiskSpaceWatchdog::new(DiskSpaceWatchdogConfig::default(), safe_mode.clone(), || {
+            Ok(u64::max_value())
+        });
+        assert_eq!(watchdog.check(), None);
+        assert_eq!(safe_mode.reason().unwrap(), Some("Operator request".to_string()));
+    }
+}
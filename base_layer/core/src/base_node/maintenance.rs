@@ -0,0 +1,121 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::chain_storage::ChainStorageError;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// A cloneable handle to the maintenance mode of the base node. While the node is in maintenance it answers requests
+/// from peers and wallets with a `RetryAfter` response, ignores propagated blocks and transactions and does not sync
+/// blocks, so that the blockchain database stays quiescent while the host is being maintained.
+#[derive(Clone)]
+pub struct MaintenanceModeHandle {
+    retry_after: Arc<RwLock<Option<Duration>>>,
+    flush: Arc<dyn Fn() -> Result<(), ChainStorageError> + Send + Sync>,
+}
+
+impl MaintenanceModeHandle {
+    /// Create a handle that calls `flush` to persist the blockchain database when maintenance mode is entered.
+    pub fn new<F>(flush: F) -> Self
+    where F: Fn() -> Result<(), ChainStorageError> + Send + Sync + 'static {
+        Self {
+            retry_after: Arc::new(RwLock::new(None)),
+            flush: Arc::new(flush),
+        }
+    }
+
+    /// Put the node into maintenance. Requests received from now on are answered with the given retry period. This
+    /// returns once in-flight block processing has completed and the blockchain database has been flushed.
+    pub fn enter(&self, retry_after: Duration) -> Result<(), ChainStorageError> {
+        *self
+            .retry_after
+            .write()
+            .map_err(|_| ChainStorageError::AccessError("Write lock on maintenance mode failed".into()))? =
+            Some(retry_after);
+        (self.flush)()
+    }
+
+    /// Resume normal operation. Returns false if the node was not in maintenance.
+    pub fn exit(&self) -> Result<bool, ChainStorageError> {
+        Ok(self
+            .retry_after
+            .write()
+            .map_err(|_| ChainStorageError::AccessError("Write lock on maintenance mode failed".into()))?
+            .take()
+            .is_some())
+    }
+
+    /// Returns the period after which requests should be retried if the node is in maintenance, otherwise `None`.
+    pub fn retry_after(&self) -> Result<Option<Duration>, ChainStorageError> {
+        Ok(*self
+            .retry_after
+            .read()
+            .map_err(|_| ChainStorageError::AccessError("Read lock on maintenance mode failed".into()))?)
+    }
+
+    /// Returns true if the node is in maintenance. A poisoned lock is treated as not being in maintenance.
+    pub fn is_active(&self) -> bool {
+        self.retry_after().ok().and_then(|r| r).is_some()
+    }
+}
+
+impl Default for MaintenanceModeHandle {
+    fn default() -> Self {
+        Self::new(|| Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MaintenanceModeHandle;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[test]
+    fn enter_and_exit() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let flushes_clone = flushes.clone();
+        let handle = MaintenanceModeHandle::new(move || {
+            flushes_clone.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        assert!(!handle.is_active());
+        assert!(!handle.exit().unwrap());
+
+        handle.enter(Duration::from_secs(30)).unwrap();
+        let shared = handle.clone();
+        assert!(shared.is_active());
+        assert_eq!(shared.retry_after().unwrap(), Some(Duration::from_secs(30)));
+        assert_eq!(flushes.load(Ordering::SeqCst), 1);
+
+        assert!(shared.exit().unwrap());
+        assert!(!handle.is_active());
+        assert_eq!(handle.retry_after().unwrap(), None);
+    }
+}
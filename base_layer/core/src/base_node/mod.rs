@@ -39,6 +39,10 @@ pub mod comms_interface;
 #[cfg(feature = "base_node")]
 pub mod consts;
 #[cfg(feature = "base_node")]
+mod disk_space;
+#[cfg(feature = "base_node")]
+mod maintenance;
+#[cfg(feature = "base_node")]
 pub mod service;
 #[cfg(feature = "base_node")]
 mod state_machine;
@@ -48,6 +52,10 @@ pub mod states;
 #[cfg(feature = "base_node")]
 pub use comms_interface::{LocalNodeCommsInterface, OutboundNodeCommsInterface};
 #[cfg(feature = "base_node")]
+pub use disk_space::{DiskSpaceEvent, DiskSpaceWatchdog, DiskSpaceWatchdogConfig};
+#[cfg(feature = "base_node")]
+pub use maintenance::MaintenanceModeHandle;
+#[cfg(feature = "base_node")]
 pub use state_machine::{BaseNodeStateMachine, BaseNodeStateMachineConfig};
 
 #[cfg(any(feature = "base_node", feature = "base_node_proto"))]
@@ -33,6 +33,8 @@ impl From<proto::ChainMetadata> for ChainMetadata {
             height_of_longest_chain: metadata.height_of_longest_chain,
             best_block: metadata.best_block,
             pruning_horizon: metadata.pruning_horizon,
+            effective_pruned_height: metadata.effective_pruned_height,
+            best_block_timestamp: metadata.best_block_timestamp,
             accumulated_difficulty,
         }
     }
@@ -48,6 +50,8 @@ impl From<ChainMetadata> for proto::ChainMetadata {
             height_of_longest_chain: metadata.height_of_longest_chain,
             best_block: metadata.best_block,
             pruning_horizon: metadata.pruning_horizon,
+            effective_pruned_height: metadata.effective_pruned_height,
+            best_block_timestamp: metadata.best_block_timestamp,
             accumulated_difficulty,
         }
     }
@@ -36,4 +36,4 @@ pub mod request;
 #[cfg(feature = "base_node")]
 pub mod response;
 #[cfg(feature = "base_node")]
-pub use base_node::{BaseNodeServiceRequest, BaseNodeServiceResponse, ChainMetadata};
+pub use base_node::{BaseNodeServiceRequest, BaseNodeServiceResponse, ChainMetadata, SignedChainMetadata};
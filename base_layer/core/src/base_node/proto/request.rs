@@ -25,8 +25,13 @@ use super::base_node::{
     BlockHeights,
     FetchHeadersAfter as ProtoFetchHeadersAfter,
     HashOutputs,
+    HeightRange,
+};
+use crate::{
+    base_node::comms_interface as ci,
+    proof_of_work::PowAlgorithm,
+    transactions::types::{Commitment, HashOutput},
 };
-use crate::{base_node::comms_interface as ci, proof_of_work::PowAlgorithm, transactions::types::HashOutput};
 use std::convert::{TryFrom, TryInto};
 
 //---------------------------------- BaseNodeRequest --------------------------------------------//
@@ -39,6 +44,9 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             // Field was not specified
             GetChainMetadata(_) => ci::NodeCommsRequest::GetChainMetadata,
             FetchKernels(hash_outputs) => ci::NodeCommsRequest::FetchKernels(hash_outputs.outputs),
+            FetchKernelByExcess(excess) => {
+                ci::NodeCommsRequest::FetchKernelByExcess(Commitment::try_from(excess).map_err(|e| e.to_string())?)
+            },
             FetchHeaders(block_heights) => ci::NodeCommsRequest::FetchHeaders(block_heights.heights),
             FetchHeadersWithHashes(block_hashes) => ci::NodeCommsRequest::FetchHeadersWithHashes(block_hashes.outputs),
             FetchHeadersAfter(request) => {
@@ -47,11 +55,22 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             FetchUtxos(hash_outputs) => ci::NodeCommsRequest::FetchUtxos(hash_outputs.outputs),
             FetchBlocks(block_heights) => ci::NodeCommsRequest::FetchBlocks(block_heights.heights),
             FetchBlocksWithHashes(block_hashes) => ci::NodeCommsRequest::FetchBlocksWithHashes(block_hashes.outputs),
+            FetchBlockWithUtxo(commitment) => {
+                ci::NodeCommsRequest::FetchBlockWithUtxo(Commitment::try_from(commitment).map_err(|e| e.to_string())?)
+            },
+            FetchBlockWithKernel(excess) => {
+                ci::NodeCommsRequest::FetchBlockWithKernel(Commitment::try_from(excess).map_err(|e| e.to_string())?)
+            },
             GetNewBlockTemplate(_) => ci::NodeCommsRequest::GetNewBlockTemplate,
             GetNewBlock(block_template) => ci::NodeCommsRequest::GetNewBlock(block_template.try_into()?),
             GetTargetDifficulty(pow_algo) => {
                 ci::NodeCommsRequest::GetTargetDifficulty(PowAlgorithm::try_from(pow_algo)?)
             },
+            GetTotalBurned(_) => ci::NodeCommsRequest::GetTotalBurned,
+            GetTailEmissionHeight(_) => ci::NodeCommsRequest::GetTailEmissionHeight,
+            FetchDifficultyStats(range) => {
+                ci::NodeCommsRequest::FetchDifficultyStats(range.start_height, range.end_height)
+            },
         };
         Ok(request)
     }
@@ -63,6 +82,9 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
         match request {
             GetChainMetadata => ProtoNodeCommsRequest::GetChainMetadata(true),
             FetchKernels(hash_outputs) => ProtoNodeCommsRequest::FetchKernels(hash_outputs.into()),
+            FetchKernelByExcess(excess) => ProtoNodeCommsRequest::FetchKernelByExcess(excess.into()),
+            GetTotalBurned => ProtoNodeCommsRequest::GetTotalBurned(true),
+            GetTailEmissionHeight => ProtoNodeCommsRequest::GetTailEmissionHeight(true),
             FetchHeaders(block_heights) => ProtoNodeCommsRequest::FetchHeaders(block_heights.into()),
             FetchHeadersWithHashes(block_hashes) => ProtoNodeCommsRequest::FetchHeadersWithHashes(block_hashes.into()),
             FetchHeadersAfter(hashes, stopping_hash) => {
@@ -71,9 +93,17 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             FetchUtxos(hash_outputs) => ProtoNodeCommsRequest::FetchUtxos(hash_outputs.into()),
             FetchBlocks(block_heights) => ProtoNodeCommsRequest::FetchBlocks(block_heights.into()),
             FetchBlocksWithHashes(block_hashes) => ProtoNodeCommsRequest::FetchBlocksWithHashes(block_hashes.into()),
+            FetchBlockWithUtxo(commitment) => ProtoNodeCommsRequest::FetchBlockWithUtxo(commitment.into()),
+            FetchBlockWithKernel(excess) => ProtoNodeCommsRequest::FetchBlockWithKernel(excess.into()),
             GetNewBlockTemplate => ProtoNodeCommsRequest::GetNewBlockTemplate(true),
             GetNewBlock(block_template) => ProtoNodeCommsRequest::GetNewBlock(block_template.into()),
             GetTargetDifficulty(pow_algo) => ProtoNodeCommsRequest::GetTargetDifficulty(pow_algo as u64),
+            FetchDifficultyStats(start_height, end_height) => {
+                ProtoNodeCommsRequest::FetchDifficultyStats(HeightRange {
+                    start_height,
+                    end_height,
+                })
+            },
         }
     }
 }
@@ -22,20 +22,26 @@
 
 pub use super::base_node::base_node_service_response::Response as ProtoNodeCommsResponse;
 use super::base_node::{
+    BlockDifficultyStats as ProtoBlockDifficultyStats,
     BlockHeaders as ProtoBlockHeaders,
+    DifficultyStats as ProtoDifficultyStats,
     HistoricalBlocks as ProtoHistoricalBlocks,
     TransactionKernels as ProtoTransactionKernels,
     TransactionOutputs as ProtoTransactionOutputs,
 };
 use crate::{
     base_node::comms_interface as ci,
-    proof_of_work::Difficulty,
+    proof_of_work::{BlockDifficultyStats, Difficulty, PowAlgorithm},
     proto::core as core_proto_types,
-    transactions::proto::{types as transactions_proto, utils::try_convert_all},
+    transactions::{
+        proto::{types as transactions_proto, utils::try_convert_all},
+        tari_amount::MicroTari,
+    },
 };
 use std::{
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
     iter::{FromIterator, Iterator},
+    time::Duration,
 };
 
 impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
@@ -68,6 +74,13 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             NewBlockTemplate(block_template) => ci::NodeCommsResponse::NewBlockTemplate(block_template.try_into()?),
             NewBlock(block) => ci::NodeCommsResponse::NewBlock(block.try_into()?),
             TargetDifficulty(difficulty) => ci::NodeCommsResponse::TargetDifficulty(Difficulty::from(difficulty)),
+            TotalBurned(total_burned) => ci::NodeCommsResponse::TotalBurned(MicroTari::from(total_burned)),
+            TailEmissionHeight(height) => ci::NodeCommsResponse::TailEmissionHeight(height),
+            DifficultyStats(stats) => {
+                let stats = try_convert_all(stats.stats)?;
+                ci::NodeCommsResponse::DifficultyStats(stats)
+            },
+            RetryAfter(secs) => ci::NodeCommsResponse::RetryAfter(Duration::from_secs(secs)),
         };
 
         Ok(response)
@@ -102,6 +115,13 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             NewBlockTemplate(block_template) => ProtoNodeCommsResponse::NewBlockTemplate(block_template.into()),
             NewBlock(block) => ProtoNodeCommsResponse::NewBlock(block.into()),
             TargetDifficulty(difficulty) => ProtoNodeCommsResponse::TargetDifficulty(difficulty.as_u64()),
+            TotalBurned(total_burned) => ProtoNodeCommsResponse::TotalBurned(total_burned.into()),
+            TailEmissionHeight(height) => ProtoNodeCommsResponse::TailEmissionHeight(height),
+            DifficultyStats(stats) => {
+                let stats = stats.into_iter().map(Into::into).collect();
+                ProtoNodeCommsResponse::DifficultyStats(stats)
+            },
+            RetryAfter(period) => ProtoNodeCommsResponse::RetryAfter(period.as_secs()),
         }
     }
 }
@@ -141,3 +161,43 @@ impl FromIterator<core_proto_types::HistoricalBlock> for ProtoHistoricalBlocks {
         }
     }
 }
+
+impl FromIterator<ProtoBlockDifficultyStats> for ProtoDifficultyStats {
+    fn from_iter<T: IntoIterator<Item = ProtoBlockDifficultyStats>>(iter: T) -> Self {
+        Self {
+            stats: iter.into_iter().collect(),
+        }
+    }
+}
+
+//---------------------------------- BlockDifficultyStats --------------------------------------------//
+
+impl TryFrom<ProtoBlockDifficultyStats> for BlockDifficultyStats {
+    type Error = String;
+
+    fn try_from(stats: ProtoBlockDifficultyStats) -> Result<Self, Self::Error> {
+        Ok(Self {
+            height: stats.height,
+            pow_algo: PowAlgorithm::try_from(stats.pow_algo)?,
+            timestamp: stats.timestamp.into(),
+            solve_time: stats.solve_time,
+            target_difficulty: Difficulty::from(stats.target_difficulty),
+            achieved_difficulty: Difficulty::from(stats.achieved_difficulty),
+            estimated_hash_rate: stats.estimated_hash_rate,
+        })
+    }
+}
+
+impl From<BlockDifficultyStats> for ProtoBlockDifficultyStats {
+    fn from(stats: BlockDifficultyStats) -> Self {
+        Self {
+            height: stats.height,
+            pow_algo: stats.pow_algo as u64,
+            timestamp: stats.timestamp.as_u64(),
+            solve_time: stats.solve_time,
+            target_difficulty: stats.target_difficulty.as_u64(),
+            achieved_difficulty: stats.achieved_difficulty.as_u64(),
+            estimated_hash_rate: stats.estimated_hash_rate,
+        }
+    }
+}
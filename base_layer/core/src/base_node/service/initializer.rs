@@ -24,7 +24,8 @@ use crate::{
     base_node::{
         comms_interface::{InboundNodeCommsHandlers, LocalNodeCommsInterface, OutboundNodeCommsInterface},
         proto,
-        service::service::{BaseNodeService, BaseNodeServiceConfig, BaseNodeStreams},
+        service::service::{BaseNodeService, BaseNodeServiceConfig, BaseNodeStreams, SERVICE_NAME},
+        MaintenanceModeHandle,
     },
     blocks::Block,
     chain_storage::{BlockchainBackend, BlockchainDatabase},
@@ -47,6 +48,7 @@ use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -64,6 +66,7 @@ where T: BlockchainBackend
     mempool: Mempool<T>,
     consensus_manager: ConsensusManager,
     config: BaseNodeServiceConfig,
+    maintenance_mode: MaintenanceModeHandle,
 }
 
 impl<T> BaseNodeServiceInitializer<T>
@@ -84,9 +87,16 @@ where T: BlockchainBackend
             mempool,
             consensus_manager,
             config,
+            maintenance_mode: MaintenanceModeHandle::default(),
         }
     }
 
+    /// Share the given maintenance mode handle with the service, so that the node operator can put it into maintenance
+    pub fn with_maintenance_mode(mut self, maintenance_mode: MaintenanceModeHandle) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
+    }
+
     /// Get a stream for inbound Base Node request messages
     fn inbound_request_stream(&self) -> impl Stream<Item = DomainMessage<proto::BaseNodeServiceRequest>> {
         self.inbound_message_subscription_factory
@@ -180,6 +190,7 @@ where T: BlockchainBackend + 'static
             outbound_nci.clone(),
         );
         let config = self.config;
+        let maintenance_mode = self.maintenance_mode.clone();
 
         // Register handle to OutboundNodeCommsInterface before waiting for handles to be ready
         handles_fut.register(outbound_nci);
@@ -191,6 +202,10 @@ where T: BlockchainBackend + 'static
             let outbound_message_service = handles
                 .get_handle::<OutboundMessageRequester>()
                 .expect("OutboundMessageRequester handle required for BaseNodeService");
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
 
             let streams = BaseNodeStreams::new(
                 outbound_request_stream,
@@ -201,7 +216,10 @@ where T: BlockchainBackend + 'static
                 local_request_stream,
                 local_block_stream,
             );
-            let service = BaseNodeService::new(outbound_message_service, inbound_nch, config).start(streams);
+            let service = BaseNodeService::new(outbound_message_service, inbound_nch, config)
+                .with_maintenance_mode(maintenance_mode)
+                .with_monitor(monitor)
+                .start(streams);
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
             info!(target: LOG_TARGET, "Base Node Service shutdown");
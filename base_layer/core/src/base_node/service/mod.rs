@@ -22,6 +22,7 @@
 
 mod error;
 mod initializer;
+mod response_cache;
 #[allow(clippy::module_inception)]
 mod service;
 mod service_request;
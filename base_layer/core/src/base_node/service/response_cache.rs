@@ -0,0 +1,149 @@
+//  Copyright 2019 The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::base_node::comms_interface::{NodeCommsRequest, NodeCommsResponse};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use ttl_cache::TtlCache;
+
+/// The ResponseCache keeps the responses to frequently repeated requests from remote nodes for a short
+/// Time-to-live, so that many wallets polling the same node for its chain metadata, recent headers or the status of
+/// the same UTXOs are served from memory instead of each causing a database read. Cached responses are keyed by the
+/// serialized request. When the capacity of the cache has been reached the oldest responses are removed. A cache
+/// with a capacity or Time-to-live of zero is disabled.
+#[derive(Clone)]
+pub struct ResponseCache {
+    responses: Arc<RwLock<TtlCache<Vec<u8>, NodeCommsResponse>>>,
+    ttl: Duration,
+    enabled: bool,
+}
+
+impl ResponseCache {
+    /// Create a new ResponseCache that holds up to `capacity` responses for the specified Time-to-live
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            responses: Arc::new(RwLock::new(TtlCache::new(capacity))),
+            ttl,
+            enabled: capacity > 0 && ttl > Duration::from_secs(0),
+        }
+    }
+
+    /// Only queries that are cheap to serve slightly stale and that many clients repeat are cached
+    fn is_cacheable(request: &NodeCommsRequest) -> bool {
+        match request {
+            NodeCommsRequest::GetChainMetadata |
+            NodeCommsRequest::FetchHeaders(_) |
+            NodeCommsRequest::FetchHeadersWithHashes(_) |
+            NodeCommsRequest::FetchHeadersAfter(_, _) |
+            NodeCommsRequest::FetchUtxos(_) => true,
+            _ => false,
+        }
+    }
+
+    fn key(request: &NodeCommsRequest) -> Option<Vec<u8>> {
+        if Self::is_cacheable(request) {
+            bincode::serialize(request).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cached response to the request, if a response to an identical request was cached within the
+    /// Time-to-live
+    pub fn get(&self, request: &NodeCommsRequest) -> Option<NodeCommsResponse> {
+        if !self.enabled {
+            return None;
+        }
+        let key = Self::key(request)?;
+        self.responses.write().ok()?.get(&key).cloned()
+    }
+
+    /// Cache the response to the request if the request is one of the cacheable queries
+    pub fn insert(&self, request: &NodeCommsRequest, response: NodeCommsResponse) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(key) = Self::key(request) {
+            if let Ok(mut responses) = self.responses.write() {
+                let _ = responses.insert(key, response, self.ttl);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chain_storage::ChainMetadata;
+    use std::thread;
+
+    fn chain_metadata_response(height: u64) -> NodeCommsResponse {
+        NodeCommsResponse::ChainMetadata(ChainMetadata {
+            height_of_longest_chain: Some(height),
+            ..Default::default()
+        })
+    }
+
+    fn cached_height(cache: &ResponseCache) -> Option<u64> {
+        match cache.get(&NodeCommsRequest::GetChainMetadata) {
+            Some(NodeCommsResponse::ChainMetadata(metadata)) => metadata.height_of_longest_chain,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_cached_responses_expire() {
+        let cache = ResponseCache::new(10, Duration::from_millis(50));
+        assert!(cache.get(&NodeCommsRequest::GetChainMetadata).is_none());
+
+        cache.insert(&NodeCommsRequest::GetChainMetadata, chain_metadata_response(5));
+        assert_eq!(cached_height(&cache), Some(5));
+
+        // Requests are keyed by their content
+        cache.insert(
+            &NodeCommsRequest::FetchHeaders(vec![1, 2]),
+            NodeCommsResponse::BlockHeaders(Vec::new()),
+        );
+        assert!(cache.get(&NodeCommsRequest::FetchHeaders(vec![1, 2])).is_some());
+        assert!(cache.get(&NodeCommsRequest::FetchHeaders(vec![1, 3])).is_none());
+
+        thread::sleep(Duration::from_millis(51));
+        assert!(cache.get(&NodeCommsRequest::GetChainMetadata).is_none());
+        assert!(cache.get(&NodeCommsRequest::FetchHeaders(vec![1, 2])).is_none());
+    }
+
+    #[test]
+    fn test_only_cacheable_requests_are_cached() {
+        let cache = ResponseCache::new(10, Duration::from_secs(60));
+        cache.insert(
+            &NodeCommsRequest::FetchBlocks(vec![1]),
+            NodeCommsResponse::HistoricalBlocks(Vec::new()),
+        );
+        assert!(cache.get(&NodeCommsRequest::FetchBlocks(vec![1])).is_none());
+
+        let disabled_cache = ResponseCache::new(0, Duration::from_secs(60));
+        disabled_cache.insert(&NodeCommsRequest::GetChainMetadata, chain_metadata_response(5));
+        assert_eq!(cached_height(&disabled_cache), None);
+    }
+}
@@ -23,10 +23,16 @@
 use crate::{
     base_node::{
         comms_interface::{CommsInterfaceError, InboundNodeCommsHandlers, NodeCommsRequest, NodeCommsResponse},
-        consts::{BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION, BASE_NODE_SERVICE_REQUEST_TIMEOUT},
+        consts::{
+            BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION,
+            BASE_NODE_SERVICE_REQUEST_TIMEOUT,
+            BASE_NODE_SERVICE_RESPONSE_CACHE_CAPACITY,
+            BASE_NODE_SERVICE_RESPONSE_CACHE_TTL,
+        },
         generate_request_key,
         proto,
-        service::error::BaseNodeServiceError,
+        service::{error::BaseNodeServiceError, response_cache::ResponseCache},
+        MaintenanceModeHandle,
         RequestKey,
         WaitingRequests,
     },
@@ -55,10 +61,15 @@ use tari_comms_dht::{
 };
 use tari_crypto::ristretto::RistrettoPublicKey;
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
-use tari_service_framework::RequestContext;
+use tari_service_framework::{
+    watchdog::{ServiceMonitor, Watchdog},
+    RequestContext,
+};
 use tokio::task;
 
 const LOG_TARGET: &str = "c::bn::base_node_service::service";
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Base Node Service";
 
 /// Configuration for the BaseNodeService.
 #[derive(Clone, Copy)]
@@ -67,6 +78,10 @@ pub struct BaseNodeServiceConfig {
     pub request_timeout: Duration,
     /// The fraction of responses that need to be received for a corresponding service request to be finalize.
     pub desired_response_fraction: f32,
+    /// The maximum number of responses to repeated queries from remote nodes that are cached. Zero disables caching.
+    pub response_cache_capacity: usize,
+    /// The time for which the response to a repeated query from remote nodes is served from the cache.
+    pub response_cache_ttl: Duration,
 }
 
 impl Default for BaseNodeServiceConfig {
@@ -74,6 +89,8 @@ impl Default for BaseNodeServiceConfig {
         Self {
             request_timeout: BASE_NODE_SERVICE_REQUEST_TIMEOUT,
             desired_response_fraction: BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION,
+            response_cache_capacity: BASE_NODE_SERVICE_RESPONSE_CACHE_CAPACITY,
+            response_cache_ttl: BASE_NODE_SERVICE_RESPONSE_CACHE_TTL,
         }
     }
 }
@@ -132,6 +149,9 @@ pub struct BaseNodeService<B: BlockchainBackend + 'static> {
     timeout_sender: Sender<RequestKey>,
     timeout_receiver_stream: Option<Receiver<RequestKey>>,
     config: BaseNodeServiceConfig,
+    maintenance_mode: MaintenanceModeHandle,
+    response_cache: ResponseCache,
+    monitor: ServiceMonitor,
 }
 
 impl<B> BaseNodeService<B>
@@ -151,9 +171,25 @@ where B: BlockchainBackend + 'static
             timeout_sender,
             timeout_receiver_stream: Some(timeout_receiver),
             config,
+            maintenance_mode: MaintenanceModeHandle::default(),
+            response_cache: ResponseCache::new(config.response_cache_capacity, config.response_cache_ttl),
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Share the maintenance mode of the node with this service, which stops handling requests and blocks from peers
+    /// while it is active
+    pub fn with_maintenance_mode(mut self, maintenance_mode: MaintenanceModeHandle) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
+    }
+
+    /// Report the requests and blocks that are being handled to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     pub async fn start<SOutReq, SInReq, SInRes, SBlockIn, SLocalReq, SLocalBlock>(
         mut self,
         streams: BaseNodeStreams<SOutReq, SInReq, SInRes, SBlockIn, SLocalReq, SLocalBlock>,
@@ -251,7 +287,9 @@ where B: BlockchainBackend + 'static
         let waiting_requests = self.waiting_requests.clone();
         let timeout_sender = self.timeout_sender.clone();
         let config = self.config;
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("outbound request");
             let ((request, node_id), reply_tx) = request_context.split();
             let _ = handle_outbound_request(
                 outbound_message_service,
@@ -275,7 +313,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_outbound_block(&self, block_context: (Block, Vec<RistrettoPublicKey>)) {
         let outbound_message_service = self.outbound_message_service.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("outbound block");
             let (block, excluded_peers) = block_context;
             let _ = handle_outbound_block(outbound_message_service, block, excluded_peers)
                 .await
@@ -289,22 +329,34 @@ where B: BlockchainBackend + 'static
     fn spawn_handle_incoming_request(&self, domain_msg: DomainMessage<proto::base_node::BaseNodeServiceRequest>) {
         let inbound_nch = self.inbound_nch.clone();
         let outbound_message_service = self.outbound_message_service.clone();
+        let maintenance_mode = self.maintenance_mode.clone();
+        let response_cache = self.response_cache.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
-            let _ = handle_incoming_request(inbound_nch, outbound_message_service, domain_msg)
-                .await
-                .or_else(|err| {
-                    error!(
-                        target: LOG_TARGET,
-                        "Failed to handle incoming request message: {:?}", err
-                    );
-                    Err(err)
-                });
+            let _activity = monitor.enter("incoming request");
+            let _ = handle_incoming_request(
+                inbound_nch,
+                outbound_message_service,
+                maintenance_mode,
+                response_cache,
+                domain_msg,
+            )
+            .await
+            .or_else(|err| {
+                error!(
+                    target: LOG_TARGET,
+                    "Failed to handle incoming request message: {:?}", err
+                );
+                Err(err)
+            });
         });
     }
 
     fn spawn_handle_incoming_response(&self, domain_msg: DomainMessage<proto::base_node::BaseNodeServiceResponse>) {
         let waiting_requests = self.waiting_requests.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("incoming response");
             let _ = handle_incoming_response(waiting_requests, domain_msg.into_inner())
                 .await
                 .or_else(|err| {
@@ -319,7 +371,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_request_timeout(&self, timeout_request_key: u64) {
         let waiting_requests = self.waiting_requests.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("request timeout");
             let _ = handle_request_timeout(waiting_requests, timeout_request_key)
                 .await
                 .or_else(|err| {
@@ -330,8 +384,17 @@ where B: BlockchainBackend + 'static
     }
 
     fn spawn_handle_incoming_block(&self, block_msg: DomainMessage<Block>) {
+        if self.maintenance_mode.is_active() {
+            debug!(
+                target: LOG_TARGET,
+                "Ignoring block from {} because the node is in maintenance", block_msg.source_peer.public_key
+            );
+            return;
+        }
         let inbound_nch = self.inbound_nch.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("incoming block");
             let _ = handle_incoming_block(inbound_nch, block_msg).await.or_else(|err| {
                 error!(target: LOG_TARGET, "Failed to handle incoming block message: {:?}", err);
                 Err(err)
@@ -345,7 +408,9 @@ where B: BlockchainBackend + 'static
     )
     {
         let inbound_nch = self.inbound_nch.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("local request");
             let (request, reply_tx) = request_context.split();
             let _ = reply_tx
                 .send(inbound_nch.handle_request(&request).await)
@@ -361,7 +426,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_local_block(&self, block_context: RequestContext<Block, Result<(), CommsInterfaceError>>) {
         let mut inbound_nch = self.inbound_nch.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("local block");
             let (block, reply_tx) = block_context.split();
             let _ = reply_tx
                 .send(inbound_nch.handle_block(&block, None).await)
@@ -379,6 +446,8 @@ where B: BlockchainBackend + 'static
 async fn handle_incoming_request<B: BlockchainBackend + 'static>(
     inbound_nch: InboundNodeCommsHandlers<B>,
     mut outbound_message_service: OutboundMessageRequester,
+    maintenance_mode: MaintenanceModeHandle,
+    response_cache: ResponseCache,
     domain_request_msg: DomainMessage<proto::BaseNodeServiceRequest>,
 ) -> Result<(), BaseNodeServiceError>
 {
@@ -389,9 +458,31 @@ async fn handle_incoming_request<B: BlockchainBackend + 'static>(
         .request
         .ok_or_else(|| BaseNodeServiceError::InvalidRequest("Received invalid base node request".to_string()))?;
 
-    let response = inbound_nch
-        .handle_request(&request.try_into().map_err(BaseNodeServiceError::InvalidRequest)?)
-        .await?;
+    let response = match maintenance_mode.retry_after().ok().and_then(|r| r) {
+        Some(retry_after) => {
+            debug!(
+                target: LOG_TARGET,
+                "Asking {} to retry request after {}s because the node is in maintenance",
+                origin_public_key,
+                retry_after.as_secs()
+            );
+            NodeCommsResponse::RetryAfter(retry_after)
+        },
+        None => {
+            let request: NodeCommsRequest = request.try_into().map_err(BaseNodeServiceError::InvalidRequest)?;
+            match response_cache.get(&request) {
+                Some(response) => {
+                    trace!(target: LOG_TARGET, "Serving {} from the response cache", request);
+                    response
+                },
+                None => {
+                    let response = inbound_nch.handle_request(&request).await?;
+                    response_cache.insert(&request, response.clone());
+                    response
+                },
+            }
+        },
+    };
 
     let message = proto::BaseNodeServiceResponse {
         request_key: inner_msg.request_key,
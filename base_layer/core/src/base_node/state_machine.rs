@@ -26,14 +26,19 @@ use crate::{
         comms_interface::OutboundNodeCommsInterface,
         states,
         states::{BaseNodeState, BlockSyncConfig, StateEvent},
+        MaintenanceModeHandle,
     },
-    chain_storage::{BlockchainBackend, BlockchainDatabase},
+    chain_storage::{BlockchainBackend, BlockchainDatabase, RefusedReorgHandle},
+    consensus::ConsensusManager,
 };
 use futures::{future, future::Either, SinkExt};
 use log::*;
 use std::{future::Future, sync::Arc};
 use tari_broadcast_channel::{bounded, Publisher, Subscriber};
-use tari_comms::{connection_manager::ConnectionManagerRequester, PeerManager};
+use tari_comms::{
+    connection_manager::ConnectionManagerRequester,
+    peer_manager::{NodeId, PeerManager},
+};
 use tari_shutdown::ShutdownSignal;
 
 const LOG_TARGET: &str = "c::bn::base_node";
@@ -62,10 +67,13 @@ impl Default for BaseNodeStateMachineConfig {
 pub struct BaseNodeStateMachine<B: BlockchainBackend> {
     pub(super) db: BlockchainDatabase<B>,
     pub(super) comms: OutboundNodeCommsInterface,
+    pub(super) rules: ConsensusManager,
     pub(super) peer_manager: Arc<PeerManager>,
     pub(super) connection_manager: ConnectionManagerRequester,
     pub(super) metadata_event_stream: Subscriber<ChainMetadataEvent>,
     pub(super) config: BaseNodeStateMachineConfig,
+    pub(super) preferred_sync_peers: Vec<NodeId>,
+    pub(super) maintenance_mode: MaintenanceModeHandle,
     event_sender: Publisher<StateEvent>,
     event_receiver: Subscriber<StateEvent>,
     interrupt_signal: ShutdownSignal,
@@ -76,6 +84,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
     pub fn new(
         db: &BlockchainDatabase<B>,
         comms: &OutboundNodeCommsInterface,
+        rules: ConsensusManager,
         peer_manager: Arc<PeerManager>,
         connection_manager: ConnectionManagerRequester,
         metadata_event_stream: Subscriber<ChainMetadataEvent>,
@@ -87,16 +96,32 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
         Self {
             db: db.clone(),
             comms: comms.clone(),
+            rules,
             peer_manager,
             connection_manager,
             metadata_event_stream,
             interrupt_signal: shutdown_signal,
             config,
+            preferred_sync_peers: Vec::new(),
+            maintenance_mode: MaintenanceModeHandle::default(),
             event_sender,
             event_receiver,
         }
     }
 
+    /// Sets the trusted peers that are asked for headers and blocks before any other sync peer. Other peers are only
+    /// used once a request to the preferred peers has failed.
+    pub fn with_preferred_sync_peers(mut self, preferred_sync_peers: Vec<NodeId>) -> Self {
+        self.preferred_sync_peers = preferred_sync_peers;
+        self
+    }
+
+    /// Share the maintenance mode of the node with the state machine, which does not sync blocks while it is active
+    pub fn with_maintenance_mode(mut self, maintenance_mode: MaintenanceModeHandle) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
+    }
+
     /// Describe the Finite State Machine for the base node. This function describes _every possible_ state
     /// transition for the node given its current state and an event that gets triggered.
     pub fn transition(&self, state: BaseNodeState, event: StateEvent) -> BaseNodeState {
@@ -109,6 +134,9 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
                 BlockSync(self.config.block_sync_config.sync_strategy, network_tip, sync_peers)
             },
             (Waiting(s), Continue) => Listening(s.into()),
+            (Listening(_), ReorgRefused(r)) | (BlockSync(_, _, _), ReorgRefused(r)) => AwaitingReorgApproval(r.into()),
+            (AwaitingReorgApproval(s), Continue) => Listening(s.into()),
+            (Listening(s), NetworkTimeOffsetWarning(_)) => Listening(s),
             (_, FatalError(s)) => Shutdown(states::Shutdown::with_reason(s)),
             (_, UserQuit) => Shutdown(states::Shutdown::with_reason("Shutdown initiated by user".to_string())),
             (s, e) => {
@@ -128,6 +156,12 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
         self.event_receiver.clone()
     }
 
+    /// Returns a handle to the chain reorg, if any, that was refused because it was deeper than the configured
+    /// `max_allowed_reorg_depth`. The handle is used by the node operator to approve or reject the reorg.
+    pub fn get_refused_reorg_handle(&self) -> RefusedReorgHandle {
+        self.db.refused_reorg_handle()
+    }
+
     /// Start the base node runtime.
     pub async fn run(mut self) {
         use crate::base_node::states::BaseNodeState::*;
@@ -165,6 +199,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
             BlockSync(s, network_tip, sync_peers) => s.next_event(shared_state, network_tip, sync_peers).await,
             Listening(s) => s.next_event(shared_state).await,
             Waiting(s) => s.next_event().await,
+            AwaitingReorgApproval(s) => s.next_event(shared_state).await,
             Shutdown(_) => unreachable!("called get_next_state_event while in Shutdown state"),
         }
     }
@@ -30,7 +30,7 @@ use crate::{
         blockheader::{BlockHash, BlockHeader},
         Block,
     },
-    chain_storage::{async_db, BlockchainBackend, ChainMetadata, ChainStorageError},
+    chain_storage::{async_db, BlockAddResult, BlockchainBackend, ChainMetadata, ChainStorageError},
 };
 use core::cmp::min;
 use derive_error::Error;
@@ -158,6 +158,8 @@ pub enum BlockSyncError {
     EmptyBlockchain,
     EmptyNetworkBestBlock,
     NoSyncPeers,
+    ReorgRefused,
+    MaintenanceMode,
     ChainStorageError(ChainStorageError),
     PeerManagerError(PeerManagerError),
     ConnectionManagerError(ConnectionManagerError),
@@ -214,6 +216,23 @@ impl BestChainMetadataBlockSyncInfo {
                 warn!(target: LOG_TARGET, "An empty network best block hash was received.",);
                 StateEvent::BlockSyncFailure
             },
+            Err(BlockSyncError::ReorgRefused) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Block sync stopped because a deep chain reorg was refused."
+                );
+                match shared.db.refused_reorg_handle().get() {
+                    Ok(Some(reorg)) => StateEvent::ReorgRefused(reorg),
+                    _ => StateEvent::BlockSyncFailure,
+                }
+            },
+            Err(BlockSyncError::MaintenanceMode) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Block sync stopped because the node is in maintenance."
+                );
+                StateEvent::BlockSyncFailure
+            },
             Err(BlockSyncError::CommsInterfaceError(e)) => {
                 warn!(target: LOG_TARGET, "Unable to perform network queries: {}", e);
                 StateEvent::BlockSyncFailure
@@ -229,6 +248,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
     sync_peers: &mut Vec<NodeId>,
 ) -> Result<(), BlockSyncError>
 {
+    check_maintenance_mode(shared)?;
     let local_metadata = shared.db.get_metadata()?;
     if let Some(local_block_hash) = local_metadata.best_block.clone() {
         if let Some(network_block_hash) = network_metadata.best_block.clone() {
@@ -347,6 +367,14 @@ async fn find_chain_split_height<B: BlockchainBackend + 'static>(
     Err(BlockSyncError::ForkChainNotLinked)
 }
 
+// Stops block sync from adding blocks to the local blockchain while the node is in maintenance.
+fn check_maintenance_mode<B: BlockchainBackend>(shared: &BaseNodeStateMachine<B>) -> Result<(), BlockSyncError> {
+    if shared.maintenance_mode.is_active() {
+        return Err(BlockSyncError::MaintenanceMode);
+    }
+    Ok(())
+}
+
 // Request a block from a remote sync peer and attempt to add it to the local blockchain.
 async fn request_and_add_blocks<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
@@ -358,8 +386,19 @@ async fn request_and_add_blocks<B: BlockchainBackend + 'static>(
     for attempt in 0..config.max_add_block_retry_attempts {
         let (blocks, sync_peer) = request_blocks(shared, sync_peers, block_nums.clone()).await?;
         for block in blocks {
+            check_maintenance_mode(shared)?;
             let block_hash = block.hash();
             match shared.db.add_block(block.clone()) {
+                Ok(BlockAddResult::ReorgRefused(reorg)) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Block #{} ({}) triggered a chain reorg of depth {} that was refused",
+                        block.header.height,
+                        block_hash.to_hex(),
+                        reorg.depth
+                    );
+                    return Err(BlockSyncError::ReorgRefused);
+                },
                 Ok(_) => {
                     info!(
                         target: LOG_TARGET,
@@ -417,7 +456,7 @@ async fn request_blocks<B: BlockchainBackend + 'static>(
 {
     let config = shared.config.block_sync_config;
     for attempt in 1..=config.max_block_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+        let sync_peer = select_sync_peer(&config, &shared.preferred_sync_peers, sync_peers, attempt)?;
         trace!(
             target: LOG_TARGET,
             "Requesting blocks {:?} from {}.",
@@ -502,7 +541,7 @@ async fn request_headers<B: BlockchainBackend + 'static>(
 {
     let config = shared.config.block_sync_config;
     for attempt in 1..=config.max_header_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+        let sync_peer = select_sync_peer(&config, &shared.preferred_sync_peers, sync_peers, attempt)?;
         trace!(target: LOG_TARGET, "Requesting headers from {}.", sync_peer);
         match shared
             .comms
@@ -567,7 +606,7 @@ async fn request_network_tip_height<B: BlockchainBackend + 'static>(
 {
     let config = shared.config.block_sync_config;
     for attempt in 1..=config.max_metadata_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+        let sync_peer = select_sync_peer(&config, &shared.preferred_sync_peers, sync_peers, attempt)?;
         trace!(target: LOG_TARGET, "Requesting updated metadata from {}.", sync_peer);
         match shared.comms.request_metadata_from_peer(Some(sync_peer.clone())).await {
             Ok(metadata) => {
@@ -592,12 +631,29 @@ async fn request_network_tip_height<B: BlockchainBackend + 'static>(
 }
 
 // Selects the first sync peer or a random peer from the set of sync peers that have the current network tip depending
-// on the selected configuration.
-fn select_sync_peer(config: &BlockSyncConfig, sync_peers: &[NodeId]) -> Result<NodeId, BlockSyncError> {
+// on the selected configuration. The first attempt of a request is restricted to the preferred sync peers, if any of
+// them have the current network tip, and the remaining sync peers are only used once that attempt has failed.
+fn select_sync_peer(
+    config: &BlockSyncConfig,
+    preferred_sync_peers: &[NodeId],
+    sync_peers: &[NodeId],
+    attempt: usize,
+) -> Result<NodeId, BlockSyncError>
+{
+    let preferred_peers = sync_peers
+        .iter()
+        .filter(|p| preferred_sync_peers.contains(p))
+        .cloned()
+        .collect::<Vec<_>>();
+    let candidates = if attempt <= 1 && !preferred_peers.is_empty() {
+        preferred_peers.as_slice()
+    } else {
+        sync_peers
+    };
     if config.random_sync_peer_with_chain {
-        sync_peers.choose(&mut rand::thread_rng())
+        candidates.choose(&mut rand::thread_rng())
     } else {
-        sync_peers.first()
+        candidates.first()
     }
     .map(Clone::clone)
     .ok_or(BlockSyncError::NoSyncPeers)
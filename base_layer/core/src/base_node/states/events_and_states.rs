@@ -21,8 +21,8 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::states::{BlockSyncStrategy, ListeningInfo, Shutdown, Starting, Waiting},
-    chain_storage::ChainMetadata,
+    base_node::states::{AwaitingReorgApproval, BlockSyncStrategy, ListeningInfo, Shutdown, Starting, Waiting},
+    chain_storage::{ChainMetadata, RefusedReorg},
     proof_of_work::Difficulty,
 };
 use std::fmt::{Display, Error, Formatter};
@@ -36,6 +36,8 @@ pub enum BaseNodeState {
     Listening(ListeningInfo),
     // We're in a paused state, and will return to Listening after a timeout
     Waiting(Waiting),
+    // A deep chain reorg was refused, waiting for the node operator to approve or reject it
+    AwaitingReorgApproval(AwaitingReorgApproval),
     Shutdown(Shutdown),
 }
 
@@ -47,6 +49,9 @@ pub enum StateEvent {
     BlockSyncFailure,
     FallenBehind(SyncStatus),
     NetworkSilence,
+    ReorgRefused(RefusedReorg),
+    // The local clock differs from network time by more than the allowed offset, given in milliseconds
+    NetworkTimeOffsetWarning(i64),
     FatalError(String),
     Continue,
     UserQuit,
@@ -89,6 +94,12 @@ impl Display for StateEvent {
             BlockSyncFailure => f.write_str("Block Synchronization Failure"),
             FallenBehind(s) => write!(f, "Fallen behind main chain - {}", s),
             NetworkSilence => f.write_str("Network Silence"),
+            ReorgRefused(r) => write!(
+                f,
+                "Chain reorg of depth {} from height {} refused, awaiting operator approval",
+                r.depth, r.fork_height
+            ),
+            NetworkTimeOffsetWarning(offset) => write!(f, "Local clock differs from network time by {}ms", offset),
             Continue => f.write_str("Continuing"),
             FatalError(e) => write!(f, "Fatal Error - {}", e),
             UserQuit => f.write_str("User Termination"),
@@ -104,6 +115,7 @@ impl Display for BaseNodeState {
             Self::Listening(_) => "Listening",
             Self::Shutdown(_) => "Shutting down",
             Self::Waiting(_) => "Waiting",
+            Self::AwaitingReorgApproval(_) => "Awaiting reorg approval",
         };
         f.write_str(s)
     }
@@ -72,11 +72,15 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
 ) -> Result<StateEvent, String>
 {
     let mut sync_nodes = Vec::from(sync_nodes);
+    let preferred_sync_nodes = shared.preferred_sync_peers.clone();
     let tip = shared.db.fetch_tip_header().map_err(|e| e.to_string())?;
     let mut from_headers = fetch_headers_to_send::<B>(&tip, &shared.db);
-    let mut sync_node = next_sync_node(&mut sync_nodes);
+    let mut sync_node = next_sync_node(&mut sync_nodes, &preferred_sync_nodes);
 
     loop {
+        if let Some(event) = check_maintenance_mode(shared) {
+            return Ok(event);
+        }
         if sync_node == None {
             return Err("No more valid nodes to sync to".to_string());
         }
@@ -99,7 +103,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
                     target: LOG_TARGET,
                     "Could not sync with node '{}':{}", sync_node_string, e
                 );
-                sync_node = next_sync_node(&mut sync_nodes);
+                sync_node = next_sync_node(&mut sync_nodes, &preferred_sync_nodes);
                 continue;
             },
             Ok(headers) => {
@@ -140,7 +144,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
                             sync_node_string,
                             first_header.prev_hash.to_hex()
                         );
-                        sync_node = next_sync_node(&mut sync_nodes);
+                        sync_node = next_sync_node(&mut sync_nodes, &preferred_sync_nodes);
                         continue;
                     }
                 } else {
@@ -148,7 +152,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
                         target: LOG_TARGET,
                         "Could not sync with node '{}': Node did not return headers", sync_node_string
                     );
-                    sync_node = sync_nodes.pop().map(|n| n);
+                    sync_node = next_sync_node(&mut sync_nodes, &preferred_sync_nodes);
                     continue;
                 }
 
@@ -170,6 +174,9 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
 
                     let mut attempts = 0;
                     loop {
+                        if let Some(event) = check_maintenance_mode(shared) {
+                            return Ok(event);
+                        }
                         if download_blocks(curr_headers.clone(), shared).await? {
                             break;
                         }
@@ -190,14 +197,32 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
     Ok(StateEvent::BlocksSynchronized)
 }
 
-fn next_sync_node(sync_nodes: &mut Vec<NodeId>) -> Option<NodeId> {
+// Removes and returns the next node to sync from. Preferred sync nodes are used before any of the other nodes, which
+// are selected at random.
+fn next_sync_node(sync_nodes: &mut Vec<NodeId>, preferred_sync_nodes: &[NodeId]) -> Option<NodeId> {
     if sync_nodes.is_empty() {
         return None;
     }
-    let index = OsRng.gen_range(0, sync_nodes.len());
+    let index = sync_nodes
+        .iter()
+        .position(|n| preferred_sync_nodes.contains(n))
+        .unwrap_or_else(|| OsRng.gen_range(0, sync_nodes.len()));
     Some(sync_nodes.remove(index))
 }
 
+// Returns a `BlockSyncFailure` event that stops the sync if the node is in maintenance, so that no blocks are added
+// to the local blockchain until it resumes.
+fn check_maintenance_mode<B: BlockchainBackend>(shared: &BaseNodeStateMachine<B>) -> Option<StateEvent> {
+    if shared.maintenance_mode.is_active() {
+        info!(
+            target: LOG_TARGET,
+            "Block sync stopped because the node is in maintenance"
+        );
+        return Some(StateEvent::BlockSyncFailure);
+    }
+    None
+}
+
 fn fetch_headers_to_send<B: BlockchainBackend + 'static>(
     most_recent_header: &BlockHeader,
     db: &BlockchainDatabase<B>,
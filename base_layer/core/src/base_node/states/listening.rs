@@ -26,12 +26,16 @@ use crate::{
         states::{StateEvent, StateEvent::FatalError, SyncStatus},
         BaseNodeStateMachine,
     },
+    blocks::BlockHeader,
     chain_storage::{BlockchainBackend, ChainMetadata},
+    consensus::ConsensusManager,
     proof_of_work::Difficulty,
+    validation::check_pow_algorithm_supported,
 };
 use futures::stream::StreamExt;
 use log::*;
 use tari_comms::peer_manager::NodeId;
+use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 
 const LOG_TARGET: &str = "c::bn::states::listening";
 
@@ -44,7 +48,25 @@ pub struct ListeningInfo;
 impl ListeningInfo {
     pub async fn next_event<B: BlockchainBackend>(&mut self, shared: &mut BaseNodeStateMachine<B>) -> StateEvent {
         info!(target: LOG_TARGET, "Listening for chain metadata updates");
+        if let Some(event) = check_refused_reorg(shared) {
+            return event;
+        }
         while let Some(metadata_event) = shared.metadata_event_stream.next().await {
+            // Blocks received while listening could have triggered a refused reorg
+            if let Some(event) = check_refused_reorg(shared) {
+                return event;
+            }
+            // Blocks are not synced while the node is in maintenance, the stalled orphans are left for when it resumes
+            if shared.maintenance_mode.is_active() {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ignoring chain metadata update because the node is in maintenance"
+                );
+                continue;
+            }
+            if let Some(event) = check_stalled_orphans(shared).await {
+                return event;
+            }
             match &*metadata_event {
                 ChainMetadataEvent::PeerChainMetadataReceived(ref peer_metadata_list) => {
                     if !peer_metadata_list.is_empty() {
@@ -56,16 +78,36 @@ impl ListeningInfo {
                                 return FatalError(msg);
                             },
                         };
-                        // Find the best network metadata and set of sync peers with the best tip.
-                        let best_metadata = best_metadata(peer_metadata_list.as_slice());
-                        let sync_peers = find_sync_peers(&best_metadata, &peer_metadata_list);
+                        // Find the best network metadata and set of sync peers with the best tip that still hold the
+                        // blocks we need.
+                        let local_height = local.height_of_longest_chain.unwrap_or(0);
+                        let (best_metadata, sync_peers) =
+                            select_sync_peers(shared, peer_metadata_list, local_height).await;
                         if let SyncStatus::Lagging(network_tip, sync_peers) =
                             determine_sync_mode(&local, best_metadata, sync_peers, LOG_TARGET)
                         {
+                            if sync_peers.is_empty() {
+                                warn!(
+                                    target: LOG_TARGET,
+                                    "We are behind the network but all peers with the best chain have pruned the \
+                                     blocks after our tip at height #{} or could not prove their claimed tip",
+                                    local_height
+                                );
+                                continue;
+                            }
                             return StateEvent::FallenBehind(SyncStatus::Lagging(network_tip, sync_peers));
                         }
                     }
                 },
+                ChainMetadataEvent::NetworkTimeOffsetExceeded(offset) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "The local clock differs from network time by {}ms. Blocks with timestamps from this node may \
+                         be rejected by peers, and peer blocks may be rejected by this node.",
+                        offset
+                    );
+                    return StateEvent::NetworkTimeOffsetWarning(*offset);
+                },
             }
         }
 
@@ -77,32 +119,203 @@ impl ListeningInfo {
     }
 }
 
-// Finds the set of sync peers that have the best tip on their main chain.
-fn find_sync_peers(best_metadata: &ChainMetadata, peer_metadata_list: &Vec<PeerChainMetadata>) -> Vec<NodeId> {
+// Returns a `ReorgRefused` event if a chain reorg is waiting for a decision from the node operator.
+fn check_refused_reorg<B: BlockchainBackend>(shared: &BaseNodeStateMachine<B>) -> Option<StateEvent> {
+    match shared.db.refused_reorg_handle().get() {
+        Ok(reorg) => reorg.map(StateEvent::ReorgRefused),
+        Err(e) => Some(FatalError(format!("Could not read the refused chain reorg. {}", e))),
+    }
+}
+
+// Returns a `FallenBehind` event that syncs from the peers that sent orphan blocks, if orphans have kept accumulating
+// for longer than the configured `max_orphan_age` without the chain tip advancing. The parents of the orphans were
+// probably missed, and the peers that sent the orphans are the ones known to have them.
+async fn check_stalled_orphans<B: BlockchainBackend>(shared: &mut BaseNodeStateMachine<B>) -> Option<StateEvent> {
+    let orphans = match shared.db.take_stalled_orphans() {
+        Ok(Some(orphans)) => orphans,
+        Ok(None) => return None,
+        Err(e) => return Some(FatalError(format!("Could not read the stalled orphan blocks. {}", e))),
+    };
+    warn!(
+        target: LOG_TARGET,
+        "{} orphan blocks were received from {} peers without the chain tip advancing from height #{}, re-syncing \
+         from these peers",
+        orphans.orphan_count,
+        orphans.source_peers.len(),
+        orphans.tip_height
+    );
+    let mut peer_metadata_list = Vec::with_capacity(orphans.source_peers.len());
+    for node_id in orphans.source_peers {
+        match shared.comms.request_metadata_from_peer(Some(node_id.clone())).await {
+            Ok(chain_metadata) => peer_metadata_list.push(PeerChainMetadata::new(node_id, chain_metadata)),
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Failed to fetch the chain metadata of orphan source peer {}: {}", node_id, e
+            ),
+        }
+    }
+    if peer_metadata_list.is_empty() {
+        return None;
+    }
+
+    let local = match shared.db.get_metadata() {
+        Ok(m) => m,
+        Err(e) => return Some(FatalError(format!("Could not get local blockchain metadata. {}", e))),
+    };
+    let local_height = local.height_of_longest_chain.unwrap_or(0);
+    let (best_metadata, sync_peers) = select_sync_peers(shared, &peer_metadata_list, local_height).await;
+    match determine_sync_mode(&local, best_metadata, sync_peers, LOG_TARGET) {
+        SyncStatus::Lagging(network_tip, sync_peers) if !sync_peers.is_empty() => {
+            Some(StateEvent::FallenBehind(SyncStatus::Lagging(network_tip, sync_peers)))
+        },
+        _ => None,
+    }
+}
+
+// Determines the best network metadata and the verified sync peers that have it. Peers that cannot prove their claimed
+// tip are dropped and the best metadata is determined again from the remaining peers, so that a dishonest peer claiming
+// the best tip cannot stop the node from syncing from honest peers.
+async fn select_sync_peers<B: BlockchainBackend>(
+    shared: &mut BaseNodeStateMachine<B>,
+    peer_metadata_list: &[PeerChainMetadata],
+    local_height: u64,
+) -> (ChainMetadata, Vec<NodeId>)
+{
+    let mut candidates = peer_metadata_list.to_vec();
+    loop {
+        let best_metadata = best_metadata(candidates.as_slice());
+        let sync_peers = find_sync_peers(&best_metadata, &candidates, local_height);
+        if sync_peers.is_empty() {
+            return (best_metadata, sync_peers);
+        }
+        let (verified_peers, unverified_peers) = verify_sync_peers(shared, &best_metadata, sync_peers).await;
+        if !verified_peers.is_empty() || unverified_peers.is_empty() {
+            return (best_metadata, verified_peers);
+        }
+        candidates.retain(|peer_metadata| !unverified_peers.contains(&peer_metadata.node_id));
+    }
+}
+
+// Finds the set of sync peers that have the best tip on their main chain and that have not pruned the blocks following
+// our local tip at `local_height`.
+fn find_sync_peers(
+    best_metadata: &ChainMetadata,
+    peer_metadata_list: &Vec<PeerChainMetadata>,
+    local_height: u64,
+) -> Vec<NodeId>
+{
     let mut sync_peers = Vec::<NodeId>::new();
     for peer_metadata in peer_metadata_list {
-        if peer_metadata.chain_metadata == *best_metadata {
+        let metadata = &peer_metadata.chain_metadata;
+        if metadata.best_block == best_metadata.best_block &&
+            metadata.accumulated_difficulty == best_metadata.accumulated_difficulty &&
+            metadata.has_block_data(local_height)
+        {
             sync_peers.push(peer_metadata.node_id.clone());
         }
     }
     sync_peers
 }
 
-/// Determine the best metadata from a set of metadata received from the network.
-fn best_metadata(metadata_list: &[PeerChainMetadata]) -> ChainMetadata {
-    // TODO: Use heuristics to weed out outliers / dishonest nodes.
-    metadata_list.iter().fold(ChainMetadata::default(), |best, current| {
-        if current
-            .chain_metadata
-            .accumulated_difficulty
-            .unwrap_or_else(Difficulty::min) >=
-            best.accumulated_difficulty.unwrap_or_else(|| 0.into())
+// Cross-checks the claimed tip of each sync peer by requesting the header at the claimed tip height. The header must
+// hash to the claimed best block, be at the claimed height, meet the minimum proof of work and account for the claimed
+// accumulated difficulty. Returns the verified peers and the peers that could not prove their claimed tip.
+async fn verify_sync_peers<B: BlockchainBackend>(
+    shared: &mut BaseNodeStateMachine<B>,
+    best_metadata: &ChainMetadata,
+    sync_peers: Vec<NodeId>,
+) -> (Vec<NodeId>, Vec<NodeId>)
+{
+    let (tip_height, tip_hash) = match (best_metadata.height_of_longest_chain, best_metadata.best_block.as_ref()) {
+        (Some(height), Some(hash)) => (height, hash),
+        _ => return (sync_peers, Vec::new()),
+    };
+    let mut verified_peers = Vec::with_capacity(sync_peers.len());
+    let mut unverified_peers = Vec::new();
+    for sync_peer in sync_peers {
+        match shared
+            .comms
+            .request_headers_from_peer(vec![tip_height], Some(sync_peer.clone()))
+            .await
         {
-            current.chain_metadata.clone()
-        } else {
-            best
+            Ok(headers) => match check_claimed_tip(&shared.rules, &headers, best_metadata) {
+                Ok(()) => verified_peers.push(sync_peer),
+                Err(reason) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Peer {} could not prove its claimed tip {} at height #{}: {}",
+                        sync_peer,
+                        tip_hash.to_hex(),
+                        tip_height,
+                        reason
+                    );
+                    unverified_peers.push(sync_peer);
+                },
+            },
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to fetch the claimed tip header from peer {}: {}", sync_peer, e
+                );
+                unverified_peers.push(sync_peer);
+            },
         }
-    })
+    }
+    (verified_peers, unverified_peers)
+}
+
+// Checks that the headers returned for the claimed tip height consist of the single header described by the claimed
+// chain metadata.
+fn check_claimed_tip(rules: &ConsensusManager, headers: &[BlockHeader], claimed: &ChainMetadata) -> Result<(), String> {
+    let header = match headers {
+        [header] => header,
+        _ => return Err(format!("expected a single header, received {}", headers.len())),
+    };
+    if Some(header.hash()) != claimed.best_block {
+        return Err(format!("the header hash {} does not match", header.hash().to_hex()));
+    }
+    if Some(header.height) != claimed.height_of_longest_chain {
+        return Err(format!("the header is at height #{}", header.height));
+    }
+    check_pow_algorithm_supported(header).map_err(|e| e.to_string())?;
+    let achieved = header.achieved_difficulty();
+    let min_difficulty = rules.consensus_constants_at_height(header.height).min_pow_difficulty();
+    if achieved < min_difficulty {
+        return Err(format!(
+            "the achieved difficulty {} is below the minimum of {}",
+            achieved, min_difficulty
+        ));
+    }
+    let accumulated_difficulty = header.total_accumulated_difficulty_inclusive();
+    if Some(accumulated_difficulty) != claimed.accumulated_difficulty {
+        return Err(format!(
+            "the header accounts for an accumulated difficulty of {}",
+            accumulated_difficulty
+        ));
+    }
+    Ok(())
+}
+
+/// Determine the best metadata from a set of metadata received from the network. Unsigned metadata is only considered
+/// when none of the peers sent verified metadata.
+fn best_metadata(metadata_list: &[PeerChainMetadata]) -> ChainMetadata {
+    // TODO: Use heuristics to weed out outliers / dishonest nodes.
+    let has_verified = metadata_list.iter().any(|peer_metadata| peer_metadata.verified);
+    metadata_list
+        .iter()
+        .filter(|peer_metadata| peer_metadata.verified || !has_verified)
+        .fold(ChainMetadata::default(), |best, current| {
+            if current
+                .chain_metadata
+                .accumulated_difficulty
+                .unwrap_or_else(Difficulty::min) >=
+                best.accumulated_difficulty.unwrap_or_else(|| 0.into())
+            {
+                current.chain_metadata.clone()
+            } else {
+                best
+            }
+        })
 }
 
 /// Given a local and the network chain state respectively, figure out what synchronisation state we should be in.
@@ -152,3 +365,53 @@ fn determine_sync_mode(
         },
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{best_metadata, check_claimed_tip};
+    use crate::{
+        base_node::chain_metadata_service::PeerChainMetadata,
+        blocks::BlockHeader,
+        chain_storage::ChainMetadata,
+        consensus::{ConsensusManagerBuilder, Network},
+        proof_of_work::Difficulty,
+    };
+    use tari_comms::peer_manager::NodeId;
+    use tari_crypto::tari_utilities::Hashable;
+
+    #[test]
+    fn claimed_tip_must_match_header() {
+        let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+        let mut header = BlockHeader::new(1);
+        header.height = 5;
+        let accumulated_difficulty = header.total_accumulated_difficulty_inclusive();
+        let claimed = ChainMetadata::new(5, header.hash(), 0, accumulated_difficulty, 0);
+        assert!(check_claimed_tip(&rules, &[header.clone()], &claimed).is_ok());
+        assert!(check_claimed_tip(&rules, &[], &claimed).is_err());
+        assert!(check_claimed_tip(&rules, &[header.clone(), header.clone()], &claimed).is_err());
+
+        // The header hashes to the claimed tip, but the claimed height or accumulated difficulty is not accounted for
+        let wrong_height = ChainMetadata::new(6, header.hash(), 0, accumulated_difficulty, 0);
+        assert!(check_claimed_tip(&rules, &[header.clone()], &wrong_height).is_err());
+        let inflated_difficulty =
+            ChainMetadata::new(5, header.hash(), 0, accumulated_difficulty + Difficulty::from(1), 0);
+        assert!(check_claimed_tip(&rules, &[header.clone()], &inflated_difficulty).is_err());
+
+        let mut other_header = header.clone();
+        other_header.nonce += 1;
+        assert!(check_claimed_tip(&rules, &[other_header], &claimed).is_err());
+    }
+
+    #[test]
+    fn best_metadata_prefers_verified_metadata() {
+        let verified = ChainMetadata::new(10, vec![1], 0, Difficulty::from(100), 0);
+        let unsigned = ChainMetadata::new(20, vec![2], 0, Difficulty::from(200), 0);
+        let peers = vec![
+            PeerChainMetadata::new(NodeId::new(), verified.clone()),
+            PeerChainMetadata::unsigned(NodeId::new(), unsigned.clone()),
+        ];
+        assert_eq!(best_metadata(&peers), verified);
+        // Unsigned metadata is used when no peer sent verified metadata
+        assert_eq!(best_metadata(&peers[1..]), unsigned);
+    }
+}
@@ -51,6 +51,11 @@
 //!
 //! Full blocks received while in this state can be stored in the orphan pool until they are needed.
 //!
+//! ## AwaitingReorgApproval
+//!
+//! A chain reorganisation deeper than the configured `max_allowed_reorg_depth` was refused. Block synchronisation is
+//! suspended until the node operator approves or rejects the reorg, after which the node returns to `Listening`.
+//!
 //! ## Shutdown
 //!
 //! Reject all new requests with a `Shutdown` message, complete current validations / tasks, flush all state if
@@ -60,6 +65,7 @@ mod block_sync;
 mod events_and_states;
 mod forward_block_sync;
 mod listening;
+mod reorg_approval;
 mod shutdown_state;
 mod starting_state;
 mod waiting;
@@ -68,6 +74,7 @@ pub use block_sync::{BestChainMetadataBlockSyncInfo, BlockSyncConfig, BlockSyncS
 pub use events_and_states::{BaseNodeState, StateEvent, SyncStatus};
 pub use forward_block_sync::ForwardBlockSyncInfo;
 pub use listening::ListeningInfo;
+pub use reorg_approval::AwaitingReorgApproval;
 pub use shutdown_state::Shutdown;
 pub use starting_state::Starting;
 pub use waiting::Waiting;
@@ -0,0 +1,115 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    base_node::{
+        states::{ListeningInfo, StateEvent},
+        BaseNodeStateMachine,
+    },
+    chain_storage::{BlockchainBackend, RefusedReorg},
+};
+use log::*;
+use std::time::Duration;
+use tari_crypto::tari_utilities::hex::Hex;
+use tokio::time::delay_for;
+
+const LOG_TARGET: &str = "c::bn::states::reorg_approval";
+
+// The interval at which the refused reorg is checked for a decision by the node operator.
+const REORG_APPROVAL_POLL_INTERVAL: Duration = Duration::from_secs(5);
+// The number of polls between repeated alerts while the decision is outstanding (5 minutes).
+const REORG_ALERT_INTERVAL_POLLS: usize = 60;
+
+/// A safe state that the base node enters when a chain reorganisation deeper than the configured
+/// `max_allowed_reorg_depth` was refused. Block synchronisation is suspended until the node operator approves or
+/// rejects the reorg, after which the node returns to the `Listening` state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AwaitingReorgApproval {
+    reorg: RefusedReorg,
+}
+
+impl AwaitingReorgApproval {
+    pub async fn next_event<B: BlockchainBackend>(&mut self, shared: &mut BaseNodeStateMachine<B>) -> StateEvent {
+        let handle = shared.db.refused_reorg_handle();
+        let mut polls = 0;
+        loop {
+            match handle.get() {
+                Ok(Some(ref reorg)) if reorg.approved => {
+                    info!(
+                        target: LOG_TARGET,
+                        "Chain reorg to fork tip ({}) was approved by the node operator. Applying reorg.",
+                        reorg.fork_tip_hash.to_hex()
+                    );
+                    return match shared.db.apply_approved_reorg() {
+                        Ok(result) => {
+                            info!(
+                                target: LOG_TARGET,
+                                "Approved chain reorg completed. Result: {:?}", result
+                            );
+                            StateEvent::Continue
+                        },
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Approved chain reorg could not be applied: {}", e);
+                            StateEvent::Continue
+                        },
+                    };
+                },
+                Ok(Some(reorg)) => {
+                    if polls % REORG_ALERT_INTERVAL_POLLS == 0 {
+                        error!(
+                            target: LOG_TARGET,
+                            "ALERT: A chain reorg of depth {} from height {} to fork tip ({}) was refused. Block sync \
+                             is suspended until the reorg is approved or rejected by the node operator.",
+                            reorg.depth,
+                            reorg.fork_height,
+                            reorg.fork_tip_hash.to_hex()
+                        );
+                    }
+                    self.reorg = reorg;
+                },
+                Ok(None) => {
+                    info!(
+                        target: LOG_TARGET,
+                        "Chain reorg to fork tip ({}) was rejected by the node operator. Resuming normal operations",
+                        self.reorg.fork_tip_hash.to_hex()
+                    );
+                    return StateEvent::Continue;
+                },
+                Err(e) => return StateEvent::FatalError(format!("Could not read the refused chain reorg. {}", e)),
+            }
+            polls += 1;
+            delay_for(REORG_APPROVAL_POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl From<RefusedReorg> for AwaitingReorgApproval {
+    fn from(reorg: RefusedReorg) -> Self {
+        AwaitingReorgApproval { reorg }
+    }
+}
+
+impl From<AwaitingReorgApproval> for ListeningInfo {
+    fn from(_: AwaitingReorgApproval) -> Self {
+        ListeningInfo
+    }
+}
@@ -65,6 +65,12 @@ pub enum BlockValidationError {
     NoCutThrough,
     // The block weight is above the maximum
     BlockTooLarge,
+    // The block contains more than one input with the same commitment
+    DuplicateInput,
+    // The block contains more than one output with the same commitment
+    DuplicateOutput,
+    // The block contains an output that already exists in the UTXO set
+    OutputExistsInUtxoSet,
 }
 
 /// A Tari block. Blocks are linked together into a blockchain.
@@ -76,6 +76,8 @@ pub enum BlockHeaderValidationError {
     ProofOfWorkError(PowError),
     // Mismatched MMR roots
     MismatchedMmrRoots,
+    // The header's blockchain version is newer than the consensus blockchain version
+    InvalidVersion,
 }
 
 /// The BlockHeader contains all the metadata for the block, including proof of work, a link to the previous block
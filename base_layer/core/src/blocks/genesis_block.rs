@@ -93,6 +93,7 @@ pub fn get_rincewind_genesis_block_raw() -> Block {
             lock_height: 0,
             meta_info: None,
             linked_kernel: None,
+            burned_value: MicroTari(0),
             excess: Commitment::from_hex(
                 "d811169b90cf749d056416121ba34bf8b435e1c1549c446433c233289fc1372c",
             )
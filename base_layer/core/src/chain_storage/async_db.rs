@@ -32,8 +32,9 @@ use crate::{
         MmrTree,
     },
     transactions::{
+        tari_amount::MicroTari,
         transaction::{TransactionKernel, TransactionOutput},
-        types::HashOutput,
+        types::{Commitment, HashOutput},
     },
 };
 use log::*;
@@ -92,6 +93,8 @@ macro_rules! make_async {
 
 make_async!(get_metadata() -> ChainMetadata, "get_metadata");
 make_async!(fetch_kernel(hash: HashOutput) -> TransactionKernel, "fetch_kernel");
+make_async!(fetch_kernel_with_excess(excess: Commitment) -> Option<TransactionKernel>, "fetch_kernel_with_excess");
+make_async!(fetch_total_burned() -> MicroTari, "fetch_total_burned");
 make_async!(fetch_header_with_block_hash(hash: HashOutput) -> BlockHeader, "fetch_header_with_block_hash");
 make_async!(fetch_header(block_num: u64) -> BlockHeader, "fetch_header");
 make_async!(fetch_utxo(hash: HashOutput) -> TransactionOutput, "fetch_utxo");
@@ -107,5 +110,7 @@ make_async!(calculate_mmr_roots(template: NewBlockTemplate) -> Block, "calculate
 
 make_async!(fetch_block(height: u64) -> HistoricalBlock, "fetch_block");
 make_async!(fetch_block_with_hash(hash: HashOutput) -> Option<HistoricalBlock>, "fetch_block_with_hash");
+make_async!(fetch_block_with_utxo(commitment: Commitment) -> Option<HistoricalBlock>, "fetch_block_with_utxo");
+make_async!(fetch_block_with_kernel(excess: Commitment) -> Option<HistoricalBlock>, "fetch_block_with_kernel");
 make_async!(rewind_to_height(height: u64) -> Vec<Block>, "rewind_to_height");
 make_async!(fetch_mmr_proof(tree: MmrTree, pos: usize) -> MerkleProof, "fetch_mmr_proof");
@@ -23,7 +23,7 @@
 use crate::{
     blocks::{blockheader::BlockHash, Block, BlockHeader, NewBlockTemplate},
     chain_storage::{
-        consts::BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY,
+        consts::{BLOCKCHAIN_DATABASE_MAX_ORPHAN_AGE_SECS, BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY},
         db_transaction::{DbKey, DbKeyValuePair, DbTransaction, DbValue, MetadataKey, MetadataValue, MmrTree},
         error::ChainStorageError,
         ChainMetadata,
@@ -32,6 +32,7 @@ use crate::{
     consensus::ConsensusManager,
     proof_of_work::{Difficulty, ProofOfWork},
     transactions::{
+        tari_amount::MicroTari,
         transaction::{TransactionInput, TransactionKernel, TransactionOutput},
         types::{Commitment, HashOutput},
     },
@@ -42,9 +43,12 @@ use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::VecDeque,
+    ops::RangeInclusive,
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
 };
 use strum_macros::Display;
+use tari_comms::peer_manager::NodeId;
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 use tari_mmr::{Hash, MerkleCheckPoint, MerkleProof, MutableMmrLeafNodes};
 
@@ -54,12 +58,20 @@ const LOG_TARGET: &str = "c::cs::database";
 #[derive(Clone, Copy)]
 pub struct BlockchainDatabaseConfig {
     pub orphan_storage_capacity: usize,
+    /// Chain reorganisations that would remove more than this number of blocks from the main chain are refused until
+    /// they are approved by the node operator. `None` disables the check.
+    pub max_allowed_reorg_depth: Option<u64>,
+    /// Orphan blocks that keep accumulating for longer than this without the chain tip advancing are assumed to be
+    /// missing their parents, and the base node re-syncs from the peers that sent them. `None` disables the check.
+    pub max_orphan_age: Option<Duration>,
 }
 
 impl Default for BlockchainDatabaseConfig {
     fn default() -> Self {
         Self {
             orphan_storage_capacity: BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY,
+            max_allowed_reorg_depth: None,
+            max_orphan_age: Some(Duration::from_secs(BLOCKCHAIN_DATABASE_MAX_ORPHAN_AGE_SECS)),
         }
     }
 }
@@ -70,6 +82,201 @@ pub enum BlockAddResult {
     BlockExists,
     OrphanBlock,
     ChainReorg((Box<Vec<Block>>, Box<Vec<Block>>)), // Set of removed blocks and set of added blocks
+    ReorgRefused(RefusedReorg),
+}
+
+/// A chain reorganisation that was refused because it was deeper than the configured `max_allowed_reorg_depth`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RefusedReorg {
+    /// The hash of the strongest orphan chain tip
+    pub fork_tip_hash: BlockHash,
+    /// The height of the main chain block that the fork links to
+    pub fork_height: u64,
+    /// The number of main chain blocks that would be removed by the reorg
+    pub depth: u64,
+    /// Set once the node operator has approved the reorg
+    pub approved: bool,
+}
+
+/// A cloneable handle to the refused reorg, if any, that is waiting for a decision from the node operator.
+///
+/// The refused reorg is only held in memory and is dropped when the node restarts. The blocks of the fork stay in the
+/// orphan pool, so the reorg is refused again, and has to be approved again, when the next block of the fork arrives.
+#[derive(Clone, Default)]
+pub struct RefusedReorgHandle {
+    inner: Arc<RwLock<Option<RefusedReorg>>>,
+}
+
+impl RefusedReorgHandle {
+    /// Returns the refused reorg that is waiting for a decision, if any.
+    pub fn get(&self) -> Result<Option<RefusedReorg>, ChainStorageError> {
+        Ok(self
+            .inner
+            .read()
+            .map_err(|_| ChainStorageError::AccessError("Read lock on refused reorg failed".into()))?
+            .clone())
+    }
+
+    /// Approve the refused reorg. The reorg will be applied by the base node state machine. Returns false if there
+    /// is no refused reorg.
+    pub fn approve(&self) -> Result<bool, ChainStorageError> {
+        let mut lock = self.write_lock()?;
+        match lock.as_mut() {
+            Some(reorg) => {
+                reorg.approved = true;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Reject the refused reorg and keep the current main chain. Returns false if there is no refused reorg.
+    pub fn reject(&self) -> Result<bool, ChainStorageError> {
+        Ok(self.write_lock()?.take().is_some())
+    }
+
+    fn set(&self, reorg: RefusedReorg) -> Result<(), ChainStorageError> {
+        *self.write_lock()? = Some(reorg);
+        Ok(())
+    }
+
+    fn take(&self) -> Result<Option<RefusedReorg>, ChainStorageError> {
+        Ok(self.write_lock()?.take())
+    }
+
+    fn write_lock(&self) -> Result<RwLockWriteGuard<Option<RefusedReorg>>, ChainStorageError> {
+        self.inner
+            .write()
+            .map_err(|_| ChainStorageError::AccessError("Write lock on refused reorg failed".into()))
+    }
+}
+
+/// Orphan blocks that were added to the orphan pool since the main chain tip last advanced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StalledOrphans {
+    /// The height of the main chain tip when the first of the orphans was added
+    pub tip_height: u64,
+    /// When the first of the orphans was added
+    pub since: Instant,
+    /// The number of orphans added since the tip last advanced
+    pub orphan_count: usize,
+    /// The peers the orphans were received from
+    pub source_peers: Vec<NodeId>,
+}
+
+/// A cloneable handle to the orphan blocks, if any, that have accumulated while the main chain tip did not advance.
+/// The base node state machine uses it to detect that the parents of the orphans were missed and to re-sync from the
+/// peers that sent them.
+#[derive(Clone, Default)]
+pub struct StalledOrphansHandle {
+    inner: Arc<RwLock<Option<StalledOrphans>>>,
+}
+
+impl StalledOrphansHandle {
+    /// Returns the orphans that have accumulated since the main chain tip last advanced, if any.
+    pub fn get(&self) -> Result<Option<StalledOrphans>, ChainStorageError> {
+        Ok(self
+            .inner
+            .read()
+            .map_err(|_| ChainStorageError::AccessError("Read lock on stalled orphans failed".into()))?
+            .clone())
+    }
+
+    /// Record the peer an orphan block was received from. Has no effect if no orphans have accumulated.
+    pub fn add_source_peer(&self, peer: NodeId) -> Result<(), ChainStorageError> {
+        if let Some(orphans) = self.write_lock()?.as_mut() {
+            if !orphans.source_peers.contains(&peer) {
+                orphans.source_peers.push(peer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the accumulated orphans if the first of them was added longer than `max_age` ago. Orphans added after
+    /// this start a new period.
+    pub fn take_if_older_than(&self, max_age: Duration) -> Result<Option<StalledOrphans>, ChainStorageError> {
+        let mut lock = self.write_lock()?;
+        match lock.as_ref() {
+            Some(orphans) if orphans.since.elapsed() > max_age => Ok(lock.take()),
+            _ => Ok(None),
+        }
+    }
+
+    fn orphan_added(&self, tip_height: u64) -> Result<(), ChainStorageError> {
+        let mut lock = self.write_lock()?;
+        match lock.as_mut() {
+            Some(orphans) if orphans.tip_height == tip_height => orphans.orphan_count += 1,
+            _ => {
+                *lock = Some(StalledOrphans {
+                    tip_height,
+                    since: Instant::now(),
+                    orphan_count: 1,
+                    source_peers: Vec::new(),
+                })
+            },
+        }
+        Ok(())
+    }
+
+    fn tip_advanced(&self) -> Result<(), ChainStorageError> {
+        *self.write_lock()? = None;
+        Ok(())
+    }
+
+    fn write_lock(&self) -> Result<RwLockWriteGuard<Option<StalledOrphans>>, ChainStorageError> {
+        self.inner
+            .write()
+            .map_err(|_| ChainStorageError::AccessError("Write lock on stalled orphans failed".into()))
+    }
+}
+
+/// A cloneable handle to the safe mode of the blockchain database. While in safe mode the database is read-only: new
+/// blocks, reorgs, rewinds and transactions are refused with `ChainStorageError::SafeMode`, e.g. so that the backend
+/// is not left with a partial write when the host runs out of disk space. Queries are answered as normal.
+#[derive(Clone, Default)]
+pub struct SafeModeHandle {
+    reason: Arc<RwLock<Option<String>>>,
+}
+
+impl SafeModeHandle {
+    /// Put the database into safe mode for the given reason. Writes that are in progress are completed, later writes
+    /// are refused. Returns false if the database was already in safe mode, in which case the reason is updated.
+    pub fn enter(&self, reason: String) -> Result<bool, ChainStorageError> {
+        Ok(self.write_lock()?.replace(reason).is_none())
+    }
+
+    /// Take the database out of safe mode. Returns false if the database was not in safe mode.
+    pub fn exit(&self) -> Result<bool, ChainStorageError> {
+        Ok(self.write_lock()?.take().is_some())
+    }
+
+    /// Returns the reason the database is in safe mode, otherwise `None`.
+    pub fn reason(&self) -> Result<Option<String>, ChainStorageError> {
+        Ok(self
+            .reason
+            .read()
+            .map_err(|_| ChainStorageError::AccessError("Read lock on safe mode failed".into()))?
+            .clone())
+    }
+
+    /// Returns true if the database is in safe mode. A poisoned lock is treated as being in safe mode.
+    pub fn is_active(&self) -> bool {
+        self.reason().map(|r| r.is_some()).unwrap_or(true)
+    }
+
+    // Returns an error if the database is in safe mode, so that a write is not started
+    fn check_writable(&self) -> Result<(), ChainStorageError> {
+        match self.reason()? {
+            Some(reason) => Err(ChainStorageError::SafeMode(reason)),
+            None => Ok(()),
+        }
+    }
+
+    fn write_lock(&self) -> Result<RwLockWriteGuard<Option<String>>, ChainStorageError> {
+        self.reason
+            .write()
+            .map_err(|_| ChainStorageError::AccessError("Write lock on safe mode failed".into()))
+    }
 }
 
 /// MutableMmrState provides the total number of leaf nodes in the base MMR and the requested leaf nodes.
@@ -155,6 +362,10 @@ pub trait BlockchainBackend: Send + Sync {
     fn fetch_checkpoint(&self, tree: MmrTree, height: u64) -> Result<MerkleCheckPoint, ChainStorageError>;
     /// Fetches the leaf node hash and its deletion status for the nth leaf node in the given MMR tree.
     fn fetch_mmr_node(&self, tree: MmrTree, pos: u32) -> Result<(Hash, bool), ChainStorageError>;
+    /// Fetches the leaf index of the given hash in the UTXO or kernel MMR tree, if the hash was added to the tree.
+    fn fetch_mmr_leaf_index(&self, tree: MmrTree, hash: &HashOutput) -> Result<Option<usize>, ChainStorageError>;
+    /// Fetches the number of leaf nodes in the UTXO or kernel MMR tree once the block at the given height was added.
+    fn fetch_mmr_leaf_count(&self, tree: MmrTree, height: u64) -> Result<usize, ChainStorageError>;
     /// Performs the function F for each orphan block in the orphan pool.
     fn for_each_orphan<F>(&self, f: F) -> Result<(), ChainStorageError>
     where
@@ -167,8 +378,9 @@ pub trait BlockchainBackend: Send + Sync {
     where
         Self: Sized,
         F: FnMut(Result<(HashOutput, TransactionKernel), ChainStorageError>);
-    /// Performs the function F for each block header.
-    fn for_each_header<F>(&self, f: F) -> Result<(), ChainStorageError>
+    /// Performs the function F for each block header in the given height range, in ascending height order. A header
+    /// that is missing from the range is passed to F as a `ValueNotFound` error.
+    fn for_each_header<F>(&self, range: RangeInclusive<u64>, f: F) -> Result<(), ChainStorageError>
     where
         Self: Sized,
         F: FnMut(Result<(u64, BlockHeader), ChainStorageError>);
@@ -181,6 +393,10 @@ pub trait BlockchainBackend: Send + Sync {
     fn fetch_last_header(&self) -> Result<Option<BlockHeader>, ChainStorageError>;
     /// Returns the stored chain metadata.
     fn fetch_metadata(&self) -> Result<ChainMetadata, ChainStorageError>;
+    /// Flush all committed data to persistent storage. Backends that do not buffer writes have nothing to do.
+    fn flush(&self) -> Result<(), ChainStorageError> {
+        Ok(())
+    }
 }
 
 // Private macro that pulls out all the boiler plate of extracting a DB query result from its variants
@@ -230,6 +446,9 @@ where T: BlockchainBackend
     db: Arc<RwLock<T>>,
     validators: Validators<T>,
     config: BlockchainDatabaseConfig,
+    refused_reorg: RefusedReorgHandle,
+    stalled_orphans: StalledOrphansHandle,
+    safe_mode: SafeModeHandle,
 }
 
 impl<T> BlockchainDatabase<T>
@@ -247,10 +466,24 @@ where T: BlockchainBackend
             db: Arc::new(RwLock::new(db)),
             validators,
             config,
+            refused_reorg: RefusedReorgHandle::default(),
+            stalled_orphans: StalledOrphansHandle::default(),
+            safe_mode: SafeModeHandle::default(),
         };
         if blockchain_db.get_height()?.is_none() {
             let genesis_block = consensus_manager.get_genesis_block();
             blockchain_db.store_new_block(genesis_block)?;
+        } else {
+            // An existing database that was created for another network would otherwise be extended with blocks of
+            // the configured network
+            let expected = consensus_manager.get_genesis_block_hash();
+            let found = blockchain_db.fetch_header(0)?.hash();
+            if found != expected {
+                return Err(ChainStorageError::GenesisBlockMismatch {
+                    expected: expected.to_hex(),
+                    found: found.to_hex(),
+                });
+            }
         }
         Ok(blockchain_db)
     }
@@ -306,12 +539,30 @@ where T: BlockchainBackend
         fetch_kernel(&*db, hash)
     }
 
+    /// Returns the transaction kernel with the given excess, if it has been stored.
+    pub fn fetch_kernel_with_excess(&self, excess: Commitment) -> Result<Option<TransactionKernel>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_kernel_with_excess(&*db, excess)
+    }
+
+    /// Returns the total value that has been burned by the burn kernels on the main chain.
+    pub fn fetch_total_burned(&self) -> Result<MicroTari, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_total_burned(&*db)
+    }
+
     /// Returns the block header at the given block height.
     pub fn fetch_header(&self, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
         let db = self.db_read_access()?;
         fetch_header(&*db, block_num)
     }
 
+    /// Returns the heights, up to the chain tip, of the block headers that are missing from the database.
+    pub fn fetch_missing_header_heights(&self) -> Result<Vec<u64>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_missing_header_heights(&*db)
+    }
+
     /// Returns the block header corresponding` to the provided BlockHash
     pub fn fetch_header_with_block_hash(&self, hash: HashOutput) -> Result<BlockHeader, ChainStorageError> {
         let db = self.db_read_access()?;
@@ -415,20 +666,99 @@ where T: BlockchainBackend
     ///   * `Ok`: The block was added and all validation checks passed
     ///   * `OrphanBlock`: The block did not form part of the main chain and was added as an orphan.
     ///   * `ChainReorg`: The block was added, which resulted in a chain-reorg.
+    ///   * `ReorgRefused`: The block was added as an orphan, but the resulting chain-reorg was deeper than
+    ///     `max_allowed_reorg_depth` and requires approval from the node operator.
     ///
-    /// If an error does occur while writing the new block parts, all changes are reverted before returning.
+    /// If an error does occur while writing the new block parts, all changes are reverted before returning. The block
+    /// is refused with `ChainStorageError::SafeMode` while the database is in safe mode.
     pub fn add_block(&self, block: Block) -> Result<BlockAddResult, ChainStorageError> {
         // Perform orphan block validation.
         self.validators.orphan.validate(&block)?;
 
         let mut db = self.db_write_access()?;
-        add_block(
+        // Checked once the write lock is held, so that no block is written after safe mode has been entered
+        self.safe_mode.check_writable()?;
+        let block_add_result = add_block(
             &mut db,
             &self.validators.block,
             &self.validators.accum_difficulty,
             block,
             self.config.orphan_storage_capacity,
-        )
+            self.config.max_allowed_reorg_depth,
+        )?;
+        match &block_add_result {
+            BlockAddResult::Ok | BlockAddResult::ChainReorg(_) => self.stalled_orphans.tip_advanced()?,
+            BlockAddResult::OrphanBlock => {
+                let tip_height = db.fetch_metadata()?.height_of_longest_chain.unwrap_or(0);
+                self.stalled_orphans.orphan_added(tip_height)?;
+            },
+            BlockAddResult::ReorgRefused(reorg) => self.refused_reorg.set(reorg.clone())?,
+            BlockAddResult::BlockExists => {},
+        }
+        Ok(block_add_result)
+    }
+
+    /// Flush the backend to persistent storage. The write lock is held while flushing, so this waits for any block
+    /// that is being added to complete first.
+    pub fn flush(&self) -> Result<(), ChainStorageError> {
+        let db = self.db_write_access()?;
+        db.flush()
+    }
+
+    /// Returns a handle to the refused reorg that is waiting for a decision from the node operator.
+    pub fn refused_reorg_handle(&self) -> RefusedReorgHandle {
+        self.refused_reorg.clone()
+    }
+
+    /// Returns a handle to the orphan blocks that have accumulated while the main chain tip did not advance.
+    pub fn stalled_orphans_handle(&self) -> StalledOrphansHandle {
+        self.stalled_orphans.clone()
+    }
+
+    /// Take the orphan blocks that have accumulated without the main chain tip advancing for longer than the
+    /// configured `max_orphan_age`, if any. Returns `None` if the check is disabled.
+    pub fn take_stalled_orphans(&self) -> Result<Option<StalledOrphans>, ChainStorageError> {
+        match self.config.max_orphan_age {
+            Some(max_age) => self.stalled_orphans.take_if_older_than(max_age),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns a handle used to put the database into, and take it out of, read-only safe mode.
+    pub fn safe_mode_handle(&self) -> SafeModeHandle {
+        self.safe_mode.clone()
+    }
+
+    /// Apply the refused reorg once it has been approved by the node operator. The depth limit is not enforced for
+    /// an approved reorg. Returns `None` if there is no approved reorg.
+    pub fn apply_approved_reorg(&self) -> Result<Option<BlockAddResult>, ChainStorageError> {
+        match self.refused_reorg.get()? {
+            Some(ref reorg) if reorg.approved => {},
+            _ => return Ok(None),
+        }
+        let reorg = match self.refused_reorg.take()? {
+            Some(reorg) => reorg,
+            None => return Ok(None),
+        };
+        let mut db = self.db_write_access()?;
+        if let Err(e) = self.safe_mode.check_writable() {
+            // Keep the approved reorg so that it is applied once the database leaves safe mode
+            self.refused_reorg.set(reorg)?;
+            return Err(e);
+        }
+        let fork_tip_block = fetch_orphan(&**db, reorg.fork_tip_hash)?;
+        let block_add_result = handle_reorg(
+            &mut db,
+            &self.validators.block,
+            &self.validators.accum_difficulty,
+            fork_tip_block,
+            None,
+        )?;
+        if let BlockAddResult::ChainReorg(_) = block_add_result {
+            cleanup_orphans_comprehensive(&mut db, self.config.orphan_storage_capacity)?;
+            self.stalled_orphans.tip_advanced()?;
+        }
+        Ok(Some(block_add_result))
     }
 
     fn store_new_block(&self, block: Block) -> Result<(), ChainStorageError> {
@@ -460,9 +790,23 @@ where T: BlockchainBackend
         fetch_block_with_hash(&*db, hash)
     }
 
+    /// Returns the main chain block that created the unspent output with the given commitment, if the output is in
+    /// the current UTXO set.
+    pub fn fetch_block_with_utxo(&self, commitment: Commitment) -> Result<Option<HistoricalBlock>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_block_with_utxo(&*db, commitment)
+    }
+
+    /// Returns the main chain block that contains the transaction kernel with the given excess, if it has been mined.
+    pub fn fetch_block_with_kernel(&self, excess: Commitment) -> Result<Option<HistoricalBlock>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_block_with_kernel(&*db, excess)
+    }
+
     /// Atomically commit the provided transaction to the database backend. This function does not update the metadata.
     pub fn commit(&self, txn: DbTransaction) -> Result<(), ChainStorageError> {
         let mut db = self.db_write_access()?;
+        self.safe_mode.check_writable()?;
         commit(&mut db, txn)
     }
 
@@ -473,6 +817,7 @@ where T: BlockchainBackend
     /// * The block height is before pruning horizon
     pub fn rewind_to_height(&self, height: u64) -> Result<Vec<Block>, ChainStorageError> {
         let mut db = self.db_write_access()?;
+        self.safe_mode.check_writable()?;
         rewind_to_height(&mut db, height)
     }
 }
@@ -487,6 +832,32 @@ fn fetch_kernel<T: BlockchainBackend>(db: &T, hash: HashOutput) -> Result<Transa
     fetch!(db, hash, TransactionKernel)
 }
 
+fn fetch_kernel_with_excess<T: BlockchainBackend>(
+    db: &T,
+    excess: Commitment,
+) -> Result<Option<TransactionKernel>, ChainStorageError>
+{
+    let key = DbKey::KernelExcess(excess);
+    match db.fetch(&key)? {
+        Some(DbValue::KernelExcess(kernel)) => Ok(Some(*kernel)),
+        Some(other) => unexpected_result(key, other),
+        None => Ok(None),
+    }
+}
+
+// The running total is kept in the chain metadata, it is updated whenever blocks are added or rewound.
+fn fetch_total_burned<T: BlockchainBackend>(db: &T) -> Result<MicroTari, ChainStorageError> {
+    Ok(
+        if let Some(DbValue::Metadata(MetadataValue::TotalBurned(total_burned))) =
+            db.fetch(&DbKey::Metadata(MetadataKey::TotalBurned))?
+        {
+            total_burned
+        } else {
+            MicroTari::from(0)
+        },
+    )
+}
+
 pub fn fetch_header<T: BlockchainBackend>(db: &T, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
     fetch!(db, block_num, BlockHeader)
 }
@@ -503,6 +874,26 @@ pub fn fetch_headers<T: BlockchainBackend>(
     Ok(headers)
 }
 
+fn fetch_missing_header_heights<T: BlockchainBackend>(db: &T) -> Result<Vec<u64>, ChainStorageError> {
+    let tip_height = match db.fetch_metadata()?.height_of_longest_chain {
+        Some(height) => height,
+        None => return Ok(Vec::new()),
+    };
+    let mut missing = Vec::new();
+    let mut storage_error = None;
+    db.for_each_header(0..=tip_height, |pair| match pair {
+        Ok(_) => {},
+        Err(ChainStorageError::ValueNotFound(DbKey::BlockHeader(height))) => missing.push(height),
+        Err(e) => {
+            storage_error.get_or_insert(e);
+        },
+    })?;
+    match storage_error {
+        Some(e) => Err(e),
+        None => Ok(missing),
+    }
+}
+
 fn fetch_header_with_block_hash<T: BlockchainBackend>(
     db: &T,
     hash: HashOutput,
@@ -581,19 +972,28 @@ fn add_block<T: BlockchainBackend>(
     accum_difficulty_validator: &Arc<Validator<Difficulty, T>>,
     block: Block,
     orphan_storage_capacity: usize,
+    max_allowed_reorg_depth: Option<u64>,
 ) -> Result<BlockAddResult, ChainStorageError>
 {
     let block_hash = block.hash();
     if db.contains(&DbKey::BlockHash(block_hash))? {
         return Ok(BlockAddResult::BlockExists);
     }
-    let block_add_result = handle_possible_reorg(db, block_validator, accum_difficulty_validator, block)?;
+    let block_add_result = handle_possible_reorg(
+        db,
+        block_validator,
+        accum_difficulty_validator,
+        block,
+        max_allowed_reorg_depth,
+    )?;
     // Cleanup orphan block pool
     match block_add_result {
         BlockAddResult::Ok => {},
         BlockAddResult::BlockExists => {},
         BlockAddResult::OrphanBlock => cleanup_orphans_single(db, orphan_storage_capacity)?,
         BlockAddResult::ChainReorg(_) => cleanup_orphans_comprehensive(db, orphan_storage_capacity)?,
+        // The fork chain must remain in the orphan pool until the node operator has made a decision
+        BlockAddResult::ReorgRefused(_) => {},
     }
     Ok(block_add_result)
 }
@@ -606,6 +1006,7 @@ fn store_new_block<T: BlockchainBackend>(db: &mut RwLockWriteGuard<T>, block: Bl
     let accumulated_difficulty =
         ProofOfWork::new_from_difficulty(&header.pow, ProofOfWork::achieved_difficulty(&header))
             .total_accumulated_difficulty();
+    let total_burned = fetch_total_burned(&**db)? + kernels.iter().map(|k| k.burned_value).sum::<MicroTari>();
     // Build all the DB queries needed to add the block and the add it atomically
     let mut txn = DbTransaction::new();
     // Update metadata
@@ -621,6 +1022,10 @@ fn store_new_block<T: BlockchainBackend>(db: &mut RwLockWriteGuard<T>, block: Bl
         MetadataKey::AccumulatedWork,
         MetadataValue::AccumulatedWork(Some(accumulated_difficulty)),
     ));
+    txn.insert(DbKeyValuePair::Metadata(
+        MetadataKey::TotalBurned,
+        MetadataValue::TotalBurned(total_burned),
+    ));
     // Insert block
     txn.insert_header(header);
     txn.spend_inputs(&inputs);
@@ -664,6 +1069,61 @@ fn fetch_block_with_hash<T: BlockchainBackend>(
     Ok(None)
 }
 
+fn fetch_block_with_utxo<T: BlockchainBackend>(
+    db: &T,
+    commitment: Commitment,
+) -> Result<Option<HistoricalBlock>, ChainStorageError>
+{
+    let key = DbKey::UtxoCommitment(commitment);
+    match db.fetch(&key)? {
+        Some(DbValue::UtxoCommitment(utxo)) => fetch_block_with_mmr_leaf(db, MmrTree::Utxo, &utxo.hash()),
+        Some(other) => unexpected_result(key, other),
+        None => Ok(None),
+    }
+}
+
+fn fetch_block_with_kernel<T: BlockchainBackend>(
+    db: &T,
+    excess: Commitment,
+) -> Result<Option<HistoricalBlock>, ChainStorageError>
+{
+    match fetch_kernel_with_excess(db, excess)? {
+        Some(kernel) => fetch_block_with_mmr_leaf(db, MmrTree::Kernel, &kernel.hash()),
+        None => Ok(None),
+    }
+}
+
+// Finds the main chain block that added the given leaf hash to the MMR. The per-height MMR leaf counts are binary
+// searched for the first block whose MMR includes the leaf index of the hash.
+fn fetch_block_with_mmr_leaf<T: BlockchainBackend>(
+    db: &T,
+    tree: MmrTree,
+    hash: &HashOutput,
+) -> Result<Option<HistoricalBlock>, ChainStorageError>
+{
+    let leaf_index = match db.fetch_mmr_leaf_index(tree.clone(), hash)? {
+        Some(leaf_index) => leaf_index,
+        None => return Ok(None),
+    };
+    let tip_height = match db.fetch_metadata()?.height_of_longest_chain {
+        Some(height) => height,
+        None => return Ok(None),
+    };
+    if db.fetch_mmr_leaf_count(tree.clone(), tip_height)? <= leaf_index {
+        return Ok(None);
+    }
+    let (mut low, mut high) = (0, tip_height);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if db.fetch_mmr_leaf_count(tree.clone(), mid)? > leaf_index {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    fetch_block(db, low).map(Some)
+}
+
 fn check_for_valid_height<T: BlockchainBackend>(db: &T, height: u64) -> Result<u64, ChainStorageError> {
     let db_height = db.fetch_metadata()?.height_of_longest_chain.unwrap_or(0);
     if height > db_height {
@@ -748,11 +1208,14 @@ fn rewind_to_height<T: BlockchainBackend>(
         return Ok(removed_blocks); // Rewind unnecessary, already on correct height
     }
     let steps_back = (chain_height - height) as usize;
+    let mut total_burned = fetch_total_burned(&**db)?;
     let mut txn = DbTransaction::new();
     // Rewind operation must be performed in reverse from tip to height+1.
     for rewind_height in ((height + 1)..=chain_height).rev() {
         // Reconstruct block at height and add to orphan block pool
         let orphaned_block = fetch_block(&**db, rewind_height)?.block().clone();
+        let burned = orphaned_block.body.kernels().iter().map(|k| k.burned_value).sum();
+        total_burned = total_burned.checked_sub(burned).unwrap_or_default();
         removed_blocks.push(orphaned_block.clone());
         txn.insert_orphan(orphaned_block);
 
@@ -801,6 +1264,10 @@ fn rewind_to_height<T: BlockchainBackend>(
         MetadataKey::AccumulatedWork,
         MetadataValue::AccumulatedWork(Some(accumulated_work)),
     ));
+    txn.insert(DbKeyValuePair::Metadata(
+        MetadataKey::TotalBurned,
+        MetadataValue::TotalBurned(total_burned),
+    ));
     commit(db, txn)?;
 
     Ok(removed_blocks)
@@ -813,6 +1280,7 @@ fn handle_possible_reorg<T: BlockchainBackend>(
     block_validator: &Arc<Validator<Block, T>>,
     accum_difficulty_validator: &Arc<Validator<Difficulty, T>>,
     block: Block,
+    max_allowed_reorg_depth: Option<u64>,
 ) -> Result<BlockAddResult, ChainStorageError>
 {
     let db_height = db
@@ -836,7 +1304,13 @@ fn handle_possible_reorg<T: BlockchainBackend>(
     trace!(target: LOG_TARGET, "{}", block);
     // Trigger a reorg check for all blocks in the orphan block pool
     debug!(target: LOG_TARGET, "Checking for chain re-org.");
-    handle_reorg(db, block_validator, accum_difficulty_validator, block)
+    handle_reorg(
+        db,
+        block_validator,
+        accum_difficulty_validator,
+        block,
+        max_allowed_reorg_depth,
+    )
 }
 
 // The handle_reorg function is triggered by the adding of orphaned blocks. Reorg chains are constructed by
@@ -844,12 +1318,14 @@ fn handle_possible_reorg<T: BlockchainBackend>(
 // orphan block and then building a chain from the strongest orphan tip back to the main chain. The newly added
 // orphan block is considered to be a orphan tip if no better tips can be found that link to it. When a valid
 // reorg chain is constructed with a higher accumulated difficulty, then the main chain is rewound and updated
-// with the newly un-orphaned blocks from the reorg chain.
+// with the newly un-orphaned blocks from the reorg chain. Reorgs that would remove more than `max_allowed_reorg_depth`
+// blocks from the main chain are refused.
 fn handle_reorg<T: BlockchainBackend>(
     db: &mut RwLockWriteGuard<T>,
     block_validator: &Arc<Validator<Block, T>>,
     accum_difficulty_validator: &Arc<Validator<Difficulty, T>>,
     new_block: Block,
+    max_allowed_reorg_depth: Option<u64>,
 ) -> Result<BlockAddResult, ChainStorageError>
 {
     // We can assume that the new block is part of the re-org chain if it exists, otherwise the re-org would have
@@ -896,6 +1372,26 @@ fn handle_reorg<T: BlockchainBackend>(
             .header
             .height -
             1;
+        let reorg_depth = tip_header.height.saturating_sub(fork_height);
+        if let Some(max_depth) = max_allowed_reorg_depth {
+            if reorg_depth > max_depth {
+                error!(
+                    target: LOG_TARGET,
+                    "Refused chain reorg of depth {} from height {} to fork tip ({}). The maximum allowed reorg depth \
+                     is {}. The reorg must be approved by the node operator.",
+                    reorg_depth,
+                    fork_height,
+                    fork_tip_hash.to_hex(),
+                    max_depth
+                );
+                return Ok(BlockAddResult::ReorgRefused(RefusedReorg {
+                    fork_tip_hash,
+                    fork_height,
+                    depth: reorg_depth,
+                    approved: false,
+                }));
+            }
+        }
         let removed_blocks = reorganize_chain(db, block_validator, fork_height, reorg_chain)?;
         if removed_blocks.is_empty() {
             return Ok(BlockAddResult::Ok);
@@ -1217,6 +1713,9 @@ where T: BlockchainBackend
             db: self.db.clone(),
             validators: self.validators.clone(),
             config: self.config.clone(),
+            refused_reorg: self.refused_reorg.clone(),
+            stalled_orphans: self.stalled_orphans.clone(),
+            safe_mode: self.safe_mode.clone(),
         }
     }
 }
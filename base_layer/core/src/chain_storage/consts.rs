@@ -22,3 +22,6 @@
 
 /// The maximum number of orphans that can be stored in the Orphan block pool.
 pub const BLOCKCHAIN_DATABASE_ORPHAN_STORAGE_CAPACITY: usize = 720;
+/// The time, in seconds, that orphan blocks can keep accumulating without the chain tip advancing before their parents
+/// are assumed to have been missed.
+pub const BLOCKCHAIN_DATABASE_MAX_ORPHAN_AGE_SECS: u64 = 600;
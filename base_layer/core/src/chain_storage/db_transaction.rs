@@ -24,14 +24,15 @@ use crate::{
     blocks::{blockheader::BlockHash, Block, BlockHeader},
     proof_of_work::Difficulty,
     transactions::{
+        tari_amount::MicroTari,
         transaction::{TransactionInput, TransactionKernel, TransactionOutput},
-        types::HashOutput,
+        types::{Commitment, HashOutput},
     },
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
 use strum_macros::Display;
-use tari_crypto::tari_utilities::{hex::to_hex, Hashable};
+use tari_crypto::tari_utilities::{hex::to_hex, ByteArray, Hashable};
 
 #[derive(Debug)]
 pub struct DbTransaction {
@@ -202,6 +203,7 @@ pub enum MetadataKey {
     BestBlock,
     AccumulatedWork,
     PruningHorizon,
+    TotalBurned,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -210,6 +212,7 @@ pub enum MetadataValue {
     BestBlock(Option<BlockHash>),
     AccumulatedWork(Option<Difficulty>),
     PruningHorizon(u64),
+    TotalBurned(MicroTari),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -218,8 +221,10 @@ pub enum DbKey {
     BlockHeader(u64),
     BlockHash(BlockHash),
     UnspentOutput(HashOutput),
+    UtxoCommitment(Commitment),
     SpentOutput(HashOutput),
     TransactionKernel(HashOutput),
+    KernelExcess(Commitment),
     OrphanBlock(HashOutput),
 }
 
@@ -229,8 +234,10 @@ pub enum DbValue {
     BlockHeader(Box<BlockHeader>),
     BlockHash(Box<BlockHeader>),
     UnspentOutput(Box<TransactionOutput>),
+    UtxoCommitment(Box<TransactionOutput>),
     SpentOutput(Box<TransactionOutput>),
     TransactionKernel(Box<TransactionKernel>),
+    KernelExcess(Box<TransactionKernel>),
     OrphanBlock(Box<Block>),
 }
 
@@ -241,11 +248,14 @@ impl Display for DbValue {
             DbValue::Metadata(MetadataValue::AccumulatedWork(_)) => f.write_str("Total accumulated work"),
             DbValue::Metadata(MetadataValue::PruningHorizon(_)) => f.write_str("Pruning horizon"),
             DbValue::Metadata(MetadataValue::BestBlock(_)) => f.write_str("Chain tip block hash"),
+            DbValue::Metadata(MetadataValue::TotalBurned(_)) => f.write_str("Total burned value"),
             DbValue::BlockHeader(_) => f.write_str("Block header"),
             DbValue::BlockHash(_) => f.write_str("Block hash"),
             DbValue::UnspentOutput(_) => f.write_str("Unspent output"),
+            DbValue::UtxoCommitment(_) => f.write_str("Unspent output commitment"),
             DbValue::SpentOutput(_) => f.write_str("Spent output"),
             DbValue::TransactionKernel(_) => f.write_str("Transaction kernel"),
+            DbValue::KernelExcess(_) => f.write_str("Transaction kernel excess"),
             DbValue::OrphanBlock(_) => f.write_str("Orphan block"),
         }
     }
@@ -258,11 +268,14 @@ impl Display for DbKey {
             DbKey::Metadata(MetadataKey::AccumulatedWork) => f.write_str("Total accumulated work"),
             DbKey::Metadata(MetadataKey::PruningHorizon) => f.write_str("Pruning horizon"),
             DbKey::Metadata(MetadataKey::BestBlock) => f.write_str("Chain tip block hash"),
+            DbKey::Metadata(MetadataKey::TotalBurned) => f.write_str("Total burned value"),
             DbKey::BlockHeader(v) => f.write_str(&format!("Block header (#{})", v)),
             DbKey::BlockHash(v) => f.write_str(&format!("Block hash (#{})", to_hex(v))),
             DbKey::UnspentOutput(v) => f.write_str(&format!("Unspent output ({})", to_hex(v))),
+            DbKey::UtxoCommitment(v) => f.write_str(&format!("Unspent output commitment ({})", to_hex(v.as_bytes()))),
             DbKey::SpentOutput(v) => f.write_str(&format!("Spent output ({})", to_hex(v))),
             DbKey::TransactionKernel(v) => f.write_str(&format!("Transaction kernel ({})", to_hex(v))),
+            DbKey::KernelExcess(v) => f.write_str(&format!("Transaction kernel excess ({})", to_hex(v.as_bytes()))),
             DbKey::OrphanBlock(v) => f.write_str(&format!("Orphan block hash ({})", to_hex(v))),
         }
     }
@@ -78,4 +78,11 @@ pub enum ChainStorageError {
     BlockingTaskSpawnError(String),
     #[error("A request was out of range")]
     OutOfRange,
+    #[error("The blockchain database is read-only while in safe mode: {0}")]
+    SafeMode(String),
+    #[error(
+        "The blockchain database belongs to a different network. Its genesis block is {found} instead of {expected}; \
+         use a different data directory for each network"
+    )]
+    GenesisBlockMismatch { expected: String, found: String },
 }
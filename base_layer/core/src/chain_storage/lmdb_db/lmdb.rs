@@ -171,6 +171,38 @@ where
     Ok(())
 }
 
+/// Looks up each of the given keys inside a single read transaction and executes the function `f` with the value, if
+/// any, stored under the key. Integer keys are serialized in little-endian byte order, so a cursor walk would not
+/// visit them in numeric order; looking the keys up directly keeps the requested order without materializing the
+/// range.
+pub fn lmdb_for_each_key<F, K, V, I>(
+    env: &Environment,
+    db: &Database,
+    keys: I,
+    mut f: F,
+) -> Result<(), ChainStorageError>
+where
+    F: FnMut(Result<(K, Option<V>), ChainStorageError>),
+    K: Serialize,
+    V: DeserializeOwned,
+    I: IntoIterator<Item = K>,
+{
+    let txn = ReadTransaction::new(env).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    let access = txn.access();
+    for key in keys {
+        let key_buf = serialize(&key)?;
+        let val = match access.get(&db, &key_buf).to_opt() {
+            Ok(None) => Ok(None),
+            Ok(Some(v)) => deserialize(v)
+                .map(Some)
+                .map_err(|e| ChainStorageError::AccessError(e.to_string())),
+            Err(e) => Err(ChainStorageError::AccessError(e.to_string())),
+        };
+        f(val.map(|val| (key, val)));
+    }
+    Ok(())
+}
+
 pub fn lmdb_clear_db(txn: &WriteTransaction, db: &Database) -> Result<(), ChainStorageError> {
     txn.access()
         .clear_db(&db)
@@ -39,19 +39,33 @@ use crate::{
         },
         error::ChainStorageError,
         lmdb_db::{
-            lmdb::{lmdb_delete, lmdb_exists, lmdb_for_each, lmdb_get, lmdb_insert, lmdb_len, lmdb_replace},
+            lmdb::{
+                lmdb_delete,
+                lmdb_exists,
+                lmdb_for_each,
+                lmdb_for_each_key,
+                lmdb_get,
+                lmdb_insert,
+                lmdb_len,
+                lmdb_replace,
+            },
             LMDBVec,
             LMDB_DB_BLOCK_HASHES,
             LMDB_DB_HEADERS,
             LMDB_DB_KERNELS,
+            LMDB_DB_KERNELS_HASH_TO_INDEX,
+            LMDB_DB_KERNEL_EXCESSES,
             LMDB_DB_KERNEL_MMR_CP_BACKEND,
+            LMDB_DB_KERNEL_MMR_LEAF_COUNTS,
             LMDB_DB_METADATA,
             LMDB_DB_ORPHANS,
             LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND,
             LMDB_DB_STXOS,
             LMDB_DB_TXOS_HASH_TO_INDEX,
             LMDB_DB_UTXOS,
+            LMDB_DB_UTXO_COMMITMENTS,
             LMDB_DB_UTXO_MMR_CP_BACKEND,
+            LMDB_DB_UTXO_MMR_LEAF_COUNTS,
         },
         memory_db::MemDbVec,
         ChainMetadata,
@@ -66,7 +80,7 @@ use croaring::Bitmap;
 use digest::Digest;
 use lmdb_zero::{Database, Environment, WriteTransaction};
 use log::*;
-use std::{path::Path, sync::Arc};
+use std::{ops::RangeInclusive, path::Path, sync::Arc};
 use tari_crypto::tari_utilities::hash::Hashable;
 use tari_mmr::{
     functions::{prune_mutable_mmr, PrunedMutableMmr},
@@ -94,9 +108,12 @@ where D: Digest
     headers_db: DatabaseRef,
     block_hashes_db: DatabaseRef,
     utxos_db: DatabaseRef,
+    utxo_commitments_db: DatabaseRef,
     stxos_db: DatabaseRef,
     txos_hash_to_index_db: DatabaseRef,
     kernels_db: DatabaseRef,
+    kernel_excesses_db: DatabaseRef,
+    kernels_hash_to_index_db: DatabaseRef,
     orphans_db: DatabaseRef,
     utxo_mmr: MmrCache<D, MemDbVec<MmrHash>, LMDBVec<MerkleCheckPoint>>,
     utxo_checkpoints: LMDBVec<MerkleCheckPoint>,
@@ -107,6 +124,8 @@ where D: Digest
     range_proof_mmr: MmrCache<D, MemDbVec<MmrHash>, LMDBVec<MerkleCheckPoint>>,
     range_proof_checkpoints: LMDBVec<MerkleCheckPoint>,
     curr_range_proof_checkpoint: MerkleCheckPoint,
+    utxo_leaf_counts: LMDBVec<usize>,
+    kernel_leaf_counts: LMDBVec<usize>,
 }
 
 impl<D> LMDBDatabase<D>
@@ -139,6 +158,22 @@ where D: Digest + Send + Sync
                 .db()
                 .clone(),
         );
+        let utxo_leaf_counts = LMDBVec::new(
+            store.env(),
+            store
+                .get_handle(LMDB_DB_UTXO_MMR_LEAF_COUNTS)
+                .ok_or_else(|| ChainStorageError::CriticalError("Could not create UTXO MMR leaf counts".to_string()))?
+                .db()
+                .clone(),
+        );
+        let kernel_leaf_counts = LMDBVec::new(
+            store.env(),
+            store
+                .get_handle(LMDB_DB_KERNEL_MMR_LEAF_COUNTS)
+                .ok_or_else(|| ChainStorageError::CriticalError("Could not create kernel MMR leaf counts".to_string()))?
+                .db()
+                .clone(),
+        );
         // Restore memory metadata
         let env = store.env();
         let metadata_db = store
@@ -146,21 +181,17 @@ where D: Digest + Send + Sync
             .ok_or_else(|| ChainStorageError::CriticalError("Could not create metadata backend".to_string()))?
             .db()
             .clone();
-        let metadata = ChainMetadata {
-            height_of_longest_chain: fetch_chain_height(&env, &metadata_db)?,
-            best_block: fetch_best_block(&env, &metadata_db)?,
-            pruning_horizon: fetch_pruning_horizon(&env, &metadata_db)?,
-            accumulated_difficulty: fetch_accumulated_work(&env, &metadata_db)?,
-        };
+        let headers_db = store
+            .get_handle(LMDB_DB_HEADERS)
+            .ok_or_else(|| ChainStorageError::CriticalError("Could not get handle to headers DB".to_string()))?
+            .db()
+            .clone();
+        let metadata = fetch_metadata(&env, &metadata_db, &headers_db)?;
 
-        Ok(Self {
+        let mut db = Self {
             metadata_db,
             mem_metadata: metadata,
-            headers_db: store
-                .get_handle(LMDB_DB_HEADERS)
-                .ok_or_else(|| ChainStorageError::CriticalError("Could not get handle to headers DB".to_string()))?
-                .db()
-                .clone(),
+            headers_db,
             block_hashes_db: store
                 .get_handle(LMDB_DB_BLOCK_HASHES)
                 .ok_or_else(|| {
@@ -173,6 +204,13 @@ where D: Digest + Send + Sync
                 .ok_or_else(|| ChainStorageError::CriticalError("Could not create handle to UTXOs DB".to_string()))?
                 .db()
                 .clone(),
+            utxo_commitments_db: store
+                .get_handle(LMDB_DB_UTXO_COMMITMENTS)
+                .ok_or_else(|| {
+                    ChainStorageError::CriticalError("Could not create handle to UTXO commitments DB".to_string())
+                })?
+                .db()
+                .clone(),
             stxos_db: store
                 .get_handle(LMDB_DB_STXOS)
                 .ok_or_else(|| ChainStorageError::CriticalError("Could not create handle to STXOs DB".to_string()))?
@@ -188,6 +226,20 @@ where D: Digest + Send + Sync
                 .ok_or_else(|| ChainStorageError::CriticalError("Could not create handle to kernels DB".to_string()))?
                 .db()
                 .clone(),
+            kernel_excesses_db: store
+                .get_handle(LMDB_DB_KERNEL_EXCESSES)
+                .ok_or_else(|| {
+                    ChainStorageError::CriticalError("Could not create handle to kernel excesses DB".to_string())
+                })?
+                .db()
+                .clone(),
+            kernels_hash_to_index_db: store
+                .get_handle(LMDB_DB_KERNELS_HASH_TO_INDEX)
+                .ok_or_else(|| {
+                    ChainStorageError::CriticalError("Could not create handle to kernel indices DB".to_string())
+                })?
+                .db()
+                .clone(),
             orphans_db: store
                 .get_handle(LMDB_DB_ORPHANS)
                 .ok_or_else(|| ChainStorageError::CriticalError("Could not create handle to orphans DB".to_string()))?
@@ -202,8 +254,107 @@ where D: Digest + Send + Sync
             range_proof_mmr: MmrCache::new(MemDbVec::new(), range_proof_checkpoints.clone(), mmr_cache_config)?,
             range_proof_checkpoints,
             curr_range_proof_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
+            utxo_leaf_counts,
+            kernel_leaf_counts,
             env,
-        })
+        };
+        db.rebuild_kernel_excess_index()?;
+        db.rebuild_utxo_commitment_index()?;
+        db.rebuild_kernel_leaf_index()?;
+        db.rebuild_leaf_counts()?;
+        Ok(db)
+    }
+
+    // Databases created before the kernel excess index was introduced have an empty index while the kernels DB is
+    // populated, so the index is rebuilt from the stored kernels when the database is opened.
+    fn rebuild_kernel_excess_index(&self) -> Result<(), ChainStorageError> {
+        if lmdb_len(&self.env, &self.kernel_excesses_db)? > 0 || lmdb_len(&self.env, &self.kernels_db)? == 0 {
+            return Ok(());
+        }
+        let mut entries = Vec::new();
+        let mut result = Ok(());
+        lmdb_for_each::<_, HashOutput, TransactionKernel>(&self.env, &self.kernels_db, |pair| match pair {
+            Ok((hash, kernel)) => entries.push((kernel.excess, hash)),
+            Err(e) => result = Err(e),
+        })?;
+        result?;
+        info!(
+            target: LOG_TARGET,
+            "Rebuilding the kernel excess index from {} stored kernels",
+            entries.len()
+        );
+        let txn = WriteTransaction::new(self.env.clone()).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        for (excess, hash) in entries.iter() {
+            lmdb_replace(&txn, &self.kernel_excesses_db, excess, hash)?;
+        }
+        txn.commit().map_err(|e| ChainStorageError::AccessError(e.to_string()))
+    }
+
+    // Rebuilds the commitment index of the unspent outputs for databases created before the index was introduced.
+    fn rebuild_utxo_commitment_index(&self) -> Result<(), ChainStorageError> {
+        if lmdb_len(&self.env, &self.utxo_commitments_db)? > 0 || lmdb_len(&self.env, &self.utxos_db)? == 0 {
+            return Ok(());
+        }
+        let mut entries = Vec::new();
+        let mut result = Ok(());
+        lmdb_for_each::<_, HashOutput, TransactionOutput>(&self.env, &self.utxos_db, |pair| match pair {
+            Ok((hash, utxo)) => entries.push((utxo.commitment, hash)),
+            Err(e) => result = Err(e),
+        })?;
+        result?;
+        info!(
+            target: LOG_TARGET,
+            "Rebuilding the UTXO commitment index from {} stored UTXOs",
+            entries.len()
+        );
+        let txn = WriteTransaction::new(self.env.clone()).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        for (commitment, hash) in entries.iter() {
+            lmdb_replace(&txn, &self.utxo_commitments_db, commitment, hash)?;
+        }
+        txn.commit().map_err(|e| ChainStorageError::AccessError(e.to_string()))
+    }
+
+    // Rebuilds the kernel hash to MMR leaf index mapping from the kernel MMR checkpoints for databases created before
+    // the index was introduced. The leaves of every checkpoint follow on from the leaves of the previous checkpoints.
+    fn rebuild_kernel_leaf_index(&self) -> Result<(), ChainStorageError> {
+        if lmdb_len(&self.env, &self.kernels_hash_to_index_db)? > 0 ||
+            self.kernel_checkpoints
+                .is_empty()
+                .map_err(|e| ChainStorageError::AccessError(e.to_string()))?
+        {
+            return Ok(());
+        }
+        let mut entries = Vec::new();
+        let mut result = Ok(());
+        self.kernel_checkpoints
+            .for_each(|cp| match cp {
+                Ok(cp) => entries.extend(cp.nodes_added().iter().cloned()),
+                Err(e) => result = Err(ChainStorageError::AccessError(e.to_string())),
+            })
+            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        result?;
+        info!(
+            target: LOG_TARGET,
+            "Rebuilding the kernel MMR leaf index from {} kernel MMR leaves",
+            entries.len()
+        );
+        let txn = WriteTransaction::new(self.env.clone()).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        for (leaf_index, hash) in entries.iter().enumerate() {
+            lmdb_replace(&txn, &self.kernels_hash_to_index_db, hash, &leaf_index)?;
+        }
+        txn.commit().map_err(|e| ChainStorageError::AccessError(e.to_string()))
+    }
+
+    // Recomputes the UTXO and kernel MMR leaf counts when they do not cover every checkpoint, as is the case for
+    // databases created before the leaf counts were recorded.
+    fn rebuild_leaf_counts(&mut self) -> Result<(), ChainStorageError> {
+        if rebuild_leaf_counts_from_checkpoints(&self.utxo_checkpoints, &mut self.utxo_leaf_counts)? {
+            info!(target: LOG_TARGET, "Rebuilt the UTXO MMR leaf counts");
+        }
+        if rebuild_leaf_counts_from_checkpoints(&self.kernel_checkpoints, &mut self.kernel_leaf_counts)? {
+            info!(target: LOG_TARGET, "Rebuilt the kernel MMR leaf counts");
+        }
+        Ok(())
     }
 
     // Perform the RewindMmr and CreateMmrCheckpoint operations after MMR txns and storage txns have been applied.
@@ -220,6 +371,13 @@ where D: Digest + Send + Sync
                         self.kernel_checkpoints
                             .truncate(rewind_checkpoint_index(cp_count, steps_back))
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        let leaf_count_len = self
+                            .kernel_leaf_counts
+                            .len()
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        self.kernel_leaf_counts
+                            .truncate(rewind_checkpoint_index(leaf_count_len, steps_back))
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
                         self.kernel_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
@@ -233,6 +391,13 @@ where D: Digest + Send + Sync
                         self.utxo_checkpoints
                             .truncate(rewind_checkpoint_index(cp_count, steps_back))
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        let leaf_count_len = self
+                            .utxo_leaf_counts
+                            .len()
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        self.utxo_leaf_counts
+                            .truncate(rewind_checkpoint_index(leaf_count_len, steps_back))
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
                         self.utxo_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
@@ -262,6 +427,9 @@ where D: Digest + Send + Sync
                         self.kernel_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        self.kernel_leaf_counts
+                            .push(self.kernel_mmr.get_leaf_count())
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
                     },
                     MmrTree::Utxo => {
                         let curr_checkpoint = self.curr_utxo_checkpoint.clone();
@@ -273,6 +441,9 @@ where D: Digest + Send + Sync
                         self.utxo_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        self.utxo_leaf_counts
+                            .push(self.utxo_mmr.get_leaf_count())
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
                     },
                     MmrTree::RangeProof => {
                         let curr_checkpoint = self.curr_range_proof_checkpoint.clone();
@@ -334,6 +505,7 @@ where D: Digest + Send + Sync
                                 self.curr_range_proof_checkpoint.push_addition(proof_hash.clone());
                             }
                             if let Some(index) = self.find_range_proof_leaf_index(proof_hash)? {
+                                lmdb_replace(&txn, &self.utxo_commitments_db, &v.commitment, &k)?;
                                 lmdb_insert(&txn, &self.utxos_db, &k, &v)?;
                                 lmdb_insert(&txn, &self.txos_hash_to_index_db, &k, &index)?;
                             }
@@ -344,7 +516,12 @@ where D: Digest + Send + Sync
                             }
                             if *update_mmr {
                                 self.curr_kernel_checkpoint.push_addition(k.clone());
+                                let leaf_index = self.kernel_mmr.get_leaf_count() +
+                                    self.curr_kernel_checkpoint.nodes_added().len() -
+                                    1;
+                                lmdb_insert(&txn, &self.kernels_hash_to_index_db, &k, &leaf_index)?;
                             }
+                            lmdb_insert(&txn, &self.kernel_excesses_db, &v.excess, &k)?;
                             lmdb_insert(&txn, &self.kernels_db, &k, &v)?;
                         },
                         DbKeyValuePair::OrphanBlock(k, v) => {
@@ -369,15 +546,45 @@ where D: Digest + Send + Sync
                             }
                         },
                         DbKey::UnspentOutput(k) => {
+                            let val: Option<TransactionOutput> = lmdb_get(&self.env, &self.utxos_db, &k)?;
+                            if let Some(v) = val {
+                                self.delete_utxo_commitment(&txn, &k, &v)?;
+                            }
                             lmdb_delete(&txn, &self.utxos_db, &k)?;
                             lmdb_delete(&txn, &self.txos_hash_to_index_db, &k)?;
                         },
+                        DbKey::UtxoCommitment(commitment) => {
+                            let result: Option<HashOutput> =
+                                lmdb_get(&self.env, &self.utxo_commitments_db, &commitment)?;
+                            if let Some(k) = result {
+                                lmdb_delete(&txn, &self.utxo_commitments_db, &commitment)?;
+                                lmdb_delete(&txn, &self.utxos_db, &k)?;
+                                lmdb_delete(&txn, &self.txos_hash_to_index_db, &k)?;
+                            }
+                        },
                         DbKey::SpentOutput(k) => {
                             lmdb_delete(&txn, &self.stxos_db, &k)?;
                             lmdb_delete(&txn, &self.txos_hash_to_index_db, &k)?;
                         },
                         DbKey::TransactionKernel(k) => {
-                            lmdb_delete(&txn, &self.kernels_db, &k)?;
+                            let val: Option<TransactionKernel> = lmdb_get(&self.env, &self.kernels_db, &k)?;
+                            if let Some(v) = val {
+                                if lmdb_exists(&self.env, &self.kernels_hash_to_index_db, &k)? {
+                                    lmdb_delete(&txn, &self.kernels_hash_to_index_db, &k)?;
+                                }
+                                lmdb_delete(&txn, &self.kernel_excesses_db, &v.excess)?;
+                                lmdb_delete(&txn, &self.kernels_db, &k)?;
+                            }
+                        },
+                        DbKey::KernelExcess(excess) => {
+                            let result: Option<HashOutput> = lmdb_get(&self.env, &self.kernel_excesses_db, &excess)?;
+                            if let Some(k) = result {
+                                if lmdb_exists(&self.env, &self.kernels_hash_to_index_db, &k)? {
+                                    lmdb_delete(&txn, &self.kernels_hash_to_index_db, &k)?;
+                                }
+                                lmdb_delete(&txn, &self.kernel_excesses_db, &excess)?;
+                                lmdb_delete(&txn, &self.kernels_db, &k)?;
+                            }
                         },
                         DbKey::OrphanBlock(k) => {
                             lmdb_delete(&txn, &self.orphans_db, &k)?;
@@ -396,6 +603,7 @@ where D: Digest + Send + Sync
                             let utxo_result: Option<TransactionOutput> = lmdb_get(&self.env, &self.utxos_db, &hash)?;
                             match utxo_result {
                                 Some(utxo) => {
+                                    self.delete_utxo_commitment(&txn, &hash, &utxo)?;
                                     lmdb_delete(&txn, &self.utxos_db, &hash)?;
                                     lmdb_insert(&txn, &self.stxos_db, &hash, &utxo)?;
                                 },
@@ -410,6 +618,7 @@ where D: Digest + Send + Sync
                             match stxo_result {
                                 Some(stxo) => {
                                     lmdb_delete(&txn, &self.stxos_db, &hash)?;
+                                    lmdb_replace(&txn, &self.utxo_commitments_db, &stxo.commitment, &hash)?;
                                     lmdb_insert(&txn, &self.utxos_db, &hash, &stxo)?;
                                 },
                                 None => return Err(ChainStorageError::UnspendError),
@@ -425,12 +634,22 @@ where D: Digest + Send + Sync
             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
 
         if update_mem_metadata {
-            self.mem_metadata = ChainMetadata {
-                height_of_longest_chain: fetch_chain_height(&self.env, &self.metadata_db)?,
-                best_block: fetch_best_block(&self.env, &self.metadata_db)?,
-                pruning_horizon: fetch_pruning_horizon(&self.env, &self.metadata_db)?,
-                accumulated_difficulty: fetch_accumulated_work(&self.env, &self.metadata_db)?,
-            };
+            self.mem_metadata = fetch_metadata(&self.env, &self.metadata_db, &self.headers_db)?;
+        }
+        Ok(())
+    }
+
+    // Removes the commitment index entry of the unspent output, provided that the entry still refers to that output.
+    fn delete_utxo_commitment(
+        &self,
+        txn: &WriteTransaction,
+        hash: &HashOutput,
+        utxo: &TransactionOutput,
+    ) -> Result<(), ChainStorageError>
+    {
+        let indexed_hash: Option<HashOutput> = lmdb_get(&self.env, &self.utxo_commitments_db, &utxo.commitment)?;
+        if indexed_hash.as_ref() == Some(hash) {
+            lmdb_delete(txn, &self.utxo_commitments_db, &utxo.commitment)?;
         }
         Ok(())
     }
@@ -508,18 +727,23 @@ pub fn create_lmdb_database(
     let lmdb_store = LMDBBuilder::new()
         .set_path(path.to_str().unwrap())
         .set_environment_size(50000)
-        .set_max_number_of_databases(15)
+        .set_max_number_of_databases(20)
         .add_database(LMDB_DB_METADATA, flags)
         .add_database(LMDB_DB_HEADERS, flags)
         .add_database(LMDB_DB_BLOCK_HASHES, flags)
         .add_database(LMDB_DB_UTXOS, flags)
+        .add_database(LMDB_DB_UTXO_COMMITMENTS, flags)
         .add_database(LMDB_DB_STXOS, flags)
         .add_database(LMDB_DB_TXOS_HASH_TO_INDEX, flags)
         .add_database(LMDB_DB_KERNELS, flags)
+        .add_database(LMDB_DB_KERNEL_EXCESSES, flags)
+        .add_database(LMDB_DB_KERNELS_HASH_TO_INDEX, flags)
         .add_database(LMDB_DB_ORPHANS, flags)
         .add_database(LMDB_DB_UTXO_MMR_CP_BACKEND, flags)
         .add_database(LMDB_DB_KERNEL_MMR_CP_BACKEND, flags)
         .add_database(LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND, flags)
+        .add_database(LMDB_DB_UTXO_MMR_LEAF_COUNTS, flags)
+        .add_database(LMDB_DB_KERNEL_MMR_LEAF_COUNTS, flags)
         .build()
         .map_err(|err| ChainStorageError::CriticalError(format!("Could not create LMDB store:{}", err)))?;
     LMDBDatabase::<HashDigest>::new(lmdb_store, mmr_cache_config)
@@ -562,6 +786,16 @@ where D: Digest + Send + Sync
                 let val: Option<TransactionOutput> = lmdb_get(&self.env, &self.utxos_db, k)?;
                 val.map(|val| DbValue::UnspentOutput(Box::new(val)))
             },
+            DbKey::UtxoCommitment(commitment) => {
+                let k: Option<HashOutput> = lmdb_get(&self.env, &self.utxo_commitments_db, commitment)?;
+                match k {
+                    Some(k) => {
+                        let val: Option<TransactionOutput> = lmdb_get(&self.env, &self.utxos_db, &k)?;
+                        val.map(|val| DbValue::UtxoCommitment(Box::new(val)))
+                    },
+                    None => None,
+                }
+            },
             DbKey::SpentOutput(k) => {
                 let val: Option<TransactionOutput> = lmdb_get(&self.env, &self.stxos_db, k)?;
                 val.map(|val| DbValue::SpentOutput(Box::new(val)))
@@ -570,6 +804,16 @@ where D: Digest + Send + Sync
                 let val: Option<TransactionKernel> = lmdb_get(&self.env, &self.kernels_db, k)?;
                 val.map(|val| DbValue::TransactionKernel(Box::new(val)))
             },
+            DbKey::KernelExcess(excess) => {
+                let k: Option<HashOutput> = lmdb_get(&self.env, &self.kernel_excesses_db, excess)?;
+                match k {
+                    Some(k) => {
+                        let val: Option<TransactionKernel> = lmdb_get(&self.env, &self.kernels_db, &k)?;
+                        val.map(|val| DbValue::KernelExcess(Box::new(val)))
+                    },
+                    None => None,
+                }
+            },
             DbKey::OrphanBlock(k) => {
                 let val: Option<Block> = lmdb_get(&self.env, &self.orphans_db, k)?;
                 val.map(|val| DbValue::OrphanBlock(Box::new(val)))
@@ -583,8 +827,10 @@ where D: Digest + Send + Sync
             DbKey::BlockHeader(k) => lmdb_exists(&self.env, &self.headers_db, k)?,
             DbKey::BlockHash(h) => lmdb_exists(&self.env, &self.block_hashes_db, h)?,
             DbKey::UnspentOutput(k) => lmdb_exists(&self.env, &self.utxos_db, k)?,
+            DbKey::UtxoCommitment(commitment) => lmdb_exists(&self.env, &self.utxo_commitments_db, commitment)?,
             DbKey::SpentOutput(k) => lmdb_exists(&self.env, &self.stxos_db, k)?,
             DbKey::TransactionKernel(k) => lmdb_exists(&self.env, &self.kernels_db, k)?,
+            DbKey::KernelExcess(excess) => lmdb_exists(&self.env, &self.kernel_excesses_db, excess)?,
             DbKey::OrphanBlock(k) => lmdb_exists(&self.env, &self.orphans_db, k)?,
         })
     }
@@ -654,6 +900,30 @@ where D: Digest + Send + Sync
         Ok((hash, deleted))
     }
 
+    fn fetch_mmr_leaf_index(&self, tree: MmrTree, hash: &HashOutput) -> Result<Option<usize>, ChainStorageError> {
+        match tree {
+            MmrTree::Utxo => lmdb_get(&self.env, &self.txos_hash_to_index_db, hash),
+            MmrTree::Kernel => lmdb_get(&self.env, &self.kernels_hash_to_index_db, hash),
+            MmrTree::RangeProof => Err(ChainStorageError::InvalidOperation(
+                "Leaf indices are only kept for the UTXO and kernel MMRs".into(),
+            )),
+        }
+    }
+
+    fn fetch_mmr_leaf_count(&self, tree: MmrTree, height: u64) -> Result<usize, ChainStorageError> {
+        match tree {
+            MmrTree::Utxo => self.utxo_leaf_counts.get(height as usize),
+            MmrTree::Kernel => self.kernel_leaf_counts.get(height as usize),
+            MmrTree::RangeProof => {
+                return Err(ChainStorageError::InvalidOperation(
+                    "Leaf counts are only kept for the UTXO and kernel MMRs".into(),
+                ))
+            },
+        }
+        .map_err(|e| ChainStorageError::AccessError(format!("Leaf count error: {}", e.to_string())))?
+        .ok_or_else(|| ChainStorageError::OutOfRange)
+    }
+
     /// Iterate over all the stored orphan blocks and execute the function `f` for each block.
     fn for_each_orphan<F>(&self, f: F) -> Result<(), ChainStorageError>
     where F: FnMut(Result<(HashOutput, Block), ChainStorageError>) {
@@ -671,10 +941,16 @@ where D: Digest + Send + Sync
         lmdb_for_each::<F, HashOutput, TransactionKernel>(&self.env, &self.kernels_db, f)
     }
 
-    /// Iterate over all the stored block headers and execute the function `f` for each header.
-    fn for_each_header<F>(&self, f: F) -> Result<(), ChainStorageError>
+    /// Iterate over the stored block headers in the given height range and execute the function `f` for each header.
+    fn for_each_header<F>(&self, range: RangeInclusive<u64>, mut f: F) -> Result<(), ChainStorageError>
     where F: FnMut(Result<(u64, BlockHeader), ChainStorageError>) {
-        lmdb_for_each::<F, u64, BlockHeader>(&self.env, &self.headers_db, f)
+        lmdb_for_each_key::<_, u64, BlockHeader, _>(&self.env, &self.headers_db, range, |pair| {
+            f(pair.and_then(|(height, header)| {
+                header
+                    .map(|header| (height, header))
+                    .ok_or_else(|| ChainStorageError::ValueNotFound(DbKey::BlockHeader(height)))
+            }))
+        })
     }
 
     /// Iterate over all the stored unspent transaction outputs and execute the function `f` for each kernel.
@@ -698,6 +974,40 @@ where D: Digest + Send + Sync
     fn fetch_metadata(&self) -> Result<ChainMetadata, ChainStorageError> {
         Ok(self.mem_metadata.clone())
     }
+
+    /// Force the LMDB environment to be synced to disk.
+    fn flush(&self) -> Result<(), ChainStorageError> {
+        self.env
+            .sync(true)
+            .map_err(|e| ChainStorageError::AccessError(format!("Could not sync the LMDB environment: {}", e)))
+    }
+}
+
+// Fetches the chain metadata from the provided metadata db, the tip timestamp is read from the provided headers db.
+fn fetch_metadata(
+    env: &Environment,
+    metadata_db: &Database,
+    headers_db: &Database,
+) -> Result<ChainMetadata, ChainStorageError>
+{
+    let height_of_longest_chain = fetch_chain_height(env, metadata_db)?;
+    let best_block_timestamp = match height_of_longest_chain {
+        Some(height) => {
+            let header: Option<BlockHeader> = lmdb_get(env, headers_db, &height)?;
+            header.map(|header| header.timestamp.as_u64())
+        },
+        None => None,
+    };
+    let mut metadata = ChainMetadata {
+        height_of_longest_chain,
+        best_block: fetch_best_block(env, metadata_db)?,
+        pruning_horizon: fetch_pruning_horizon(env, metadata_db)?,
+        effective_pruned_height: 0,
+        best_block_timestamp,
+        accumulated_difficulty: fetch_accumulated_work(env, metadata_db)?,
+    };
+    metadata.effective_pruned_height = metadata.horizon_block(height_of_longest_chain.unwrap_or(0));
+    Ok(metadata)
 }
 
 // Fetches the chain height from the provided metadata db.
@@ -764,3 +1074,43 @@ fn rewind_checkpoint_index(cp_count: usize, steps_back: usize) -> usize {
         1
     }
 }
+
+// Replaces the leaf counts with the running total of the leaves added by each checkpoint if the leaf counts do not
+// match the checkpoints. Returns true if the leaf counts were rebuilt.
+fn rebuild_leaf_counts_from_checkpoints(
+    checkpoints: &LMDBVec<MerkleCheckPoint>,
+    leaf_counts: &mut LMDBVec<usize>,
+) -> Result<bool, ChainStorageError>
+{
+    let cp_count = checkpoints
+        .len()
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    let leaf_count_len = leaf_counts
+        .len()
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    if cp_count == leaf_count_len {
+        return Ok(false);
+    }
+    let mut counts = Vec::with_capacity(cp_count);
+    let mut leaf_count = 0;
+    let mut result = Ok(());
+    checkpoints
+        .for_each(|cp| match cp {
+            Ok(cp) => {
+                leaf_count += cp.nodes_added().len();
+                counts.push(leaf_count);
+            },
+            Err(e) => result = Err(ChainStorageError::AccessError(e.to_string())),
+        })
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    result?;
+    leaf_counts
+        .clear()
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    for count in counts {
+        leaf_counts
+            .push(count)
+            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    }
+    Ok(true)
+}
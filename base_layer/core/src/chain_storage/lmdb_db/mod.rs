@@ -33,10 +33,15 @@ pub const LMDB_DB_METADATA: &str = "metadata";
 pub const LMDB_DB_HEADERS: &str = "headers";
 pub const LMDB_DB_BLOCK_HASHES: &str = "block_hashes";
 pub const LMDB_DB_UTXOS: &str = "utxos";
+pub const LMDB_DB_UTXO_COMMITMENTS: &str = "utxo_commitments";
 pub const LMDB_DB_TXOS_HASH_TO_INDEX: &str = "txos_hash_to_index";
 pub const LMDB_DB_STXOS: &str = "stxos";
 pub const LMDB_DB_KERNELS: &str = "kernels";
+pub const LMDB_DB_KERNEL_EXCESSES: &str = "kernel_excesses";
+pub const LMDB_DB_KERNELS_HASH_TO_INDEX: &str = "kernels_hash_to_index";
 pub const LMDB_DB_ORPHANS: &str = "orphans";
 pub const LMDB_DB_UTXO_MMR_CP_BACKEND: &str = "utxo_mmr_cp_backend";
 pub const LMDB_DB_KERNEL_MMR_CP_BACKEND: &str = "kernel_mmr_cp_backend";
 pub const LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND: &str = "range_proof_mmr_cp_backend";
+pub const LMDB_DB_UTXO_MMR_LEAF_COUNTS: &str = "utxo_mmr_leaf_counts";
+pub const LMDB_DB_KERNEL_MMR_LEAF_COUNTS: &str = "kernel_mmr_leaf_counts";
@@ -50,9 +50,10 @@ use croaring::Bitmap;
 use digest::Digest;
 use std::{
     collections::HashMap,
+    ops::RangeInclusive,
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
-use tari_crypto::tari_utilities::hash::Hashable;
+use tari_crypto::tari_utilities::{hash::Hashable, ByteArray};
 use tari_mmr::{
     functions::{prune_mutable_mmr, PrunedMutableMmr},
     ArrayLike,
@@ -80,8 +81,14 @@ where D: Digest
     headers: HashMap<u64, BlockHeader>,
     block_hashes: HashMap<HashOutput, u64>,
     utxos: HashMap<HashOutput, MerkleNode<TransactionOutput>>,
+    // Maps the commitment bytes of each unspent output to the output hash
+    utxo_commitments: HashMap<Vec<u8>, HashOutput>,
     stxos: HashMap<HashOutput, MerkleNode<TransactionOutput>>,
     kernels: HashMap<HashOutput, TransactionKernel>,
+    // Maps the excess bytes of each stored kernel to the kernel hash
+    kernel_excesses: HashMap<Vec<u8>, HashOutput>,
+    // Maps the hash of each kernel that was added to the kernel MMR to its leaf index
+    kernel_leaf_indices: HashMap<HashOutput, usize>,
     orphans: HashMap<HashOutput, Block>,
     // Define MMRs to use both a memory-backed base and a memory-backed pruned MMR
     utxo_mmr: MmrCache<D, MemDbVec<MmrHash>, MemDbVec<MerkleCheckPoint>>,
@@ -93,6 +100,9 @@ where D: Digest
     range_proof_mmr: MmrCache<D, MemDbVec<MmrHash>, MemDbVec<MerkleCheckPoint>>,
     range_proof_checkpoints: MemDbVec<MerkleCheckPoint>,
     curr_range_proof_checkpoint: MerkleCheckPoint,
+    // The number of leaf nodes in the UTXO and kernel MMRs once the block at each height was added
+    utxo_leaf_counts: Vec<usize>,
+    kernel_leaf_counts: Vec<usize>,
 }
 
 /// A memory-backed blockchain database. The data is stored in RAM; and so all data will be lost when the program
@@ -123,8 +133,11 @@ where D: Digest + Send + Sync
                 headers: HashMap::default(),
                 block_hashes: HashMap::default(),
                 utxos: HashMap::default(),
+                utxo_commitments: HashMap::default(),
                 stxos: HashMap::default(),
                 kernels: HashMap::default(),
+                kernel_excesses: HashMap::default(),
+                kernel_leaf_indices: HashMap::default(),
                 orphans: HashMap::default(),
                 utxo_mmr,
                 utxo_checkpoints,
@@ -135,6 +148,8 @@ where D: Digest + Send + Sync
                 range_proof_mmr,
                 range_proof_checkpoints,
                 curr_range_proof_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
+                utxo_leaf_counts: Vec::new(),
+                kernel_leaf_counts: Vec::new(),
             })),
         }
     }
@@ -232,6 +247,7 @@ where D: Digest + Send + Sync
                             db.curr_range_proof_checkpoint.push_addition(proof_hash.clone());
                         }
                         if let Some(index) = find_range_proof_leaf_index(&mut db, proof_hash)? {
+                            db.utxo_commitments.insert(v.commitment.as_bytes().to_vec(), k.clone());
                             let v = MerkleNode { index, value: *v };
                             db.utxos.insert(k, v);
                         }
@@ -242,7 +258,11 @@ where D: Digest + Send + Sync
                         }
                         if update_mmr {
                             db.curr_kernel_checkpoint.push_addition(k.clone());
+                            let leaf_index =
+                                db.kernel_mmr.get_leaf_count() + db.curr_kernel_checkpoint.nodes_added().len() - 1;
+                            db.kernel_leaf_indices.insert(k.clone(), leaf_index);
                         }
+                        db.kernel_excesses.insert(v.excess.as_bytes().to_vec(), k.clone());
                         db.kernels.insert(k, *v);
                     },
                     DbKeyValuePair::OrphanBlock(k, v) => {
@@ -258,13 +278,29 @@ where D: Digest + Send + Sync
                         db.block_hashes.remove(&hash).and_then(|i| db.headers.remove(&i));
                     },
                     DbKey::UnspentOutput(k) => {
-                        db.utxos.remove(&k);
+                        if let Some(utxo) = db.utxos.remove(&k) {
+                            remove_utxo_commitment(&mut db, &k, &utxo.value);
+                        }
+                    },
+                    DbKey::UtxoCommitment(commitment) => {
+                        db.utxo_commitments
+                            .remove(commitment.as_bytes())
+                            .and_then(|k| db.utxos.remove(&k));
                     },
                     DbKey::SpentOutput(k) => {
                         db.stxos.remove(&k);
                     },
                     DbKey::TransactionKernel(k) => {
-                        db.kernels.remove(&k);
+                        db.kernel_leaf_indices.remove(&k);
+                        db.kernels
+                            .remove(&k)
+                            .and_then(|v| db.kernel_excesses.remove(v.excess.as_bytes()));
+                    },
+                    DbKey::KernelExcess(excess) => {
+                        db.kernel_excesses.remove(excess.as_bytes()).and_then(|k| {
+                            db.kernel_leaf_indices.remove(&k);
+                            db.kernels.remove(&k)
+                        });
                     },
                     DbKey::OrphanBlock(k) => {
                         db.orphans.remove(&k);
@@ -296,7 +332,9 @@ where D: Digest + Send + Sync
 
                         db.kernel_mmr
                             .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        let leaf_count = db.kernel_mmr.get_leaf_count();
+                        db.kernel_leaf_counts.push(leaf_count);
                     },
                     MmrTree::Utxo => {
                         let curr_checkpoint = db.curr_utxo_checkpoint.clone();
@@ -305,7 +343,9 @@ where D: Digest + Send + Sync
 
                         db.utxo_mmr
                             .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?
+                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+                        let leaf_count = db.utxo_mmr.get_leaf_count();
+                        db.utxo_leaf_counts.push(leaf_count);
                     },
                     MmrTree::RangeProof => {
                         let curr_checkpoint = db.curr_range_proof_checkpoint.clone();
@@ -323,6 +363,9 @@ where D: Digest + Send + Sync
                         let cp_count = db.kernel_checkpoints.len()?;
                         db.kernel_checkpoints
                             .truncate(rewind_checkpoint_index(cp_count, steps_back))?;
+                        let leaf_count_len = db.kernel_leaf_counts.len();
+                        db.kernel_leaf_counts
+                            .truncate(rewind_checkpoint_index(leaf_count_len, steps_back));
                         db.kernel_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
@@ -332,6 +375,9 @@ where D: Digest + Send + Sync
                         let cp_count = db.utxo_checkpoints.len()?;
                         db.utxo_checkpoints
                             .truncate(rewind_checkpoint_index(cp_count, steps_back))?;
+                        let leaf_count_len = db.utxo_leaf_counts.len();
+                        db.utxo_leaf_counts
+                            .truncate(rewind_checkpoint_index(leaf_count_len, steps_back));
                         db.utxo_mmr
                             .update()
                             .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
@@ -368,11 +414,21 @@ where D: Digest + Send + Sync
                 .utxos
                 .get(k)
                 .map(|v| DbValue::UnspentOutput(Box::new(v.value.clone()))),
+            DbKey::UtxoCommitment(commitment) => db
+                .utxo_commitments
+                .get(commitment.as_bytes())
+                .and_then(|k| db.utxos.get(k))
+                .map(|v| DbValue::UtxoCommitment(Box::new(v.value.clone()))),
             DbKey::SpentOutput(k) => db.stxos.get(k).map(|v| DbValue::SpentOutput(Box::new(v.value.clone()))),
             DbKey::TransactionKernel(k) => db
                 .kernels
                 .get(k)
                 .map(|v| DbValue::TransactionKernel(Box::new(v.clone()))),
+            DbKey::KernelExcess(excess) => db
+                .kernel_excesses
+                .get(excess.as_bytes())
+                .and_then(|k| db.kernels.get(k))
+                .map(|v| DbValue::KernelExcess(Box::new(v.clone()))),
             DbKey::OrphanBlock(k) => db.orphans.get(k).map(|v| DbValue::OrphanBlock(Box::new(v.clone()))),
         };
         Ok(result)
@@ -385,8 +441,10 @@ where D: Digest + Send + Sync
             DbKey::BlockHeader(k) => db.headers.contains_key(k),
             DbKey::BlockHash(h) => db.block_hashes.contains_key(h),
             DbKey::UnspentOutput(k) => db.utxos.contains_key(k),
+            DbKey::UtxoCommitment(commitment) => db.utxo_commitments.contains_key(commitment.as_bytes()),
             DbKey::SpentOutput(k) => db.stxos.contains_key(k),
             DbKey::TransactionKernel(k) => db.kernels.contains_key(k),
+            DbKey::KernelExcess(excess) => db.kernel_excesses.contains_key(excess.as_bytes()),
             DbKey::OrphanBlock(k) => db.orphans.contains_key(k),
         };
         Ok(result)
@@ -463,6 +521,34 @@ where D: Digest + Send + Sync
         Ok((hash, deleted))
     }
 
+    fn fetch_mmr_leaf_index(&self, tree: MmrTree, hash: &HashOutput) -> Result<Option<usize>, ChainStorageError> {
+        let db = self.db_access()?;
+        match tree {
+            MmrTree::Utxo => Ok(db.utxos.get(hash).or_else(|| db.stxos.get(hash)).map(|node| node.index)),
+            MmrTree::Kernel => Ok(db.kernel_leaf_indices.get(hash).cloned()),
+            MmrTree::RangeProof => Err(ChainStorageError::InvalidOperation(
+                "Leaf indices are only kept for the UTXO and kernel MMRs".into(),
+            )),
+        }
+    }
+
+    fn fetch_mmr_leaf_count(&self, tree: MmrTree, height: u64) -> Result<usize, ChainStorageError> {
+        let db = self.db_access()?;
+        let leaf_counts = match tree {
+            MmrTree::Utxo => &db.utxo_leaf_counts,
+            MmrTree::Kernel => &db.kernel_leaf_counts,
+            MmrTree::RangeProof => {
+                return Err(ChainStorageError::InvalidOperation(
+                    "Leaf counts are only kept for the UTXO and kernel MMRs".into(),
+                ))
+            },
+        };
+        leaf_counts
+            .get(height as usize)
+            .cloned()
+            .ok_or_else(|| ChainStorageError::OutOfRange)
+    }
+
     /// Iterate over all the stored orphan blocks and execute the function `f` for each block.
     fn for_each_orphan<F>(&self, mut f: F) -> Result<(), ChainStorageError>
     where F: FnMut(Result<(HashOutput, Block), ChainStorageError>) {
@@ -489,12 +575,15 @@ where D: Digest + Send + Sync
         Ok(())
     }
 
-    /// Iterate over all the stored block headers and execute the function `f` for each header.
-    fn for_each_header<F>(&self, mut f: F) -> Result<(), ChainStorageError>
+    /// Iterate over the stored block headers in the given height range and execute the function `f` for each header.
+    fn for_each_header<F>(&self, range: RangeInclusive<u64>, mut f: F) -> Result<(), ChainStorageError>
     where F: FnMut(Result<(u64, BlockHeader), ChainStorageError>) {
         let db = self.db_access()?;
-        for (key, val) in db.headers.iter() {
-            f(Ok((*key, val.clone())));
+        for height in range {
+            match db.headers.get(&height) {
+                Some(header) => f(Ok((height, header.clone()))),
+                None => f(Err(ChainStorageError::ValueNotFound(DbKey::BlockHeader(height)))),
+            }
         }
         Ok(())
     }
@@ -523,12 +612,16 @@ where D: Digest + Send + Sync
 
     /// Returns the metadata of the chain.
     fn fetch_metadata(&self) -> Result<ChainMetadata, ChainStorageError> {
-        Ok(ChainMetadata {
+        let mut metadata = ChainMetadata {
             height_of_longest_chain: self.fetch_chain_height()?,
             best_block: self.fetch_best_block()?,
             pruning_horizon: self.fetch_pruning_horizon()?,
+            effective_pruned_height: 0,
+            best_block_timestamp: self.fetch_last_header()?.map(|header| header.timestamp.as_u64()),
             accumulated_difficulty: self.fetch_accumulated_work()?,
-        })
+        };
+        metadata.effective_pruned_height = metadata.horizon_block(metadata.height_of_longest_chain.unwrap_or(0));
+        Ok(metadata)
     }
 }
 
@@ -558,8 +651,11 @@ where D: Digest
             headers: HashMap::default(),
             block_hashes: HashMap::default(),
             utxos: HashMap::default(),
+            utxo_commitments: HashMap::default(),
             stxos: HashMap::default(),
             kernels: HashMap::default(),
+            kernel_excesses: HashMap::default(),
+            kernel_leaf_indices: HashMap::default(),
             orphans: HashMap::default(),
             utxo_mmr,
             utxo_checkpoints,
@@ -570,6 +666,8 @@ where D: Digest
             range_proof_mmr,
             range_proof_checkpoints,
             curr_range_proof_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
+            utxo_leaf_counts: Vec::new(),
+            kernel_leaf_counts: Vec::new(),
         }
     }
 }
@@ -579,6 +677,7 @@ fn spend_utxo<D: Digest>(db: &mut RwLockWriteGuard<InnerDatabase<D>>, hash: Hash
     match db.utxos.remove(&hash) {
         None => false,
         Some(utxo) => {
+            remove_utxo_commitment(db, &hash, &utxo.value);
             db.curr_utxo_checkpoint.push_deletion(utxo.index as u32);
             db.stxos.insert(hash, utxo);
             true
@@ -593,12 +692,28 @@ fn unspend_stxo<D: Digest>(db: &mut RwLockWriteGuard<InnerDatabase<D>>, hash: Ha
     match db.stxos.remove(&hash) {
         None => false,
         Some(stxo) => {
+            db.utxo_commitments
+                .insert(stxo.value.commitment.as_bytes().to_vec(), hash.clone());
             db.utxos.insert(hash, stxo);
             true
         },
     }
 }
 
+// This is a private helper function. When it is called, we are guaranteed to have a write lock on self.db. The
+// commitment index entry is only removed if it still refers to the given output.
+fn remove_utxo_commitment<D: Digest>(
+    db: &mut RwLockWriteGuard<InnerDatabase<D>>,
+    hash: &HashOutput,
+    utxo: &TransactionOutput,
+)
+{
+    let commitment = utxo.commitment.as_bytes();
+    if db.utxo_commitments.get(commitment) == Some(hash) {
+        db.utxo_commitments.remove(commitment);
+    }
+}
+
 // Returns the leaf index of the hash. If the hash is in the newly added hashes it returns the future MMR index for that
 // hash, this index is only valid if the change history is Committed.
 fn find_range_proof_leaf_index<D: Digest>(
@@ -34,18 +34,34 @@ pub struct ChainMetadata {
     /// The number of blocks back from the tip that this database tracks. A value of 0 indicates that all blocks are
     /// tracked (i.e. the database is in full archival mode).
     pub pruning_horizon: u64,
+    /// The height of the earliest block for which this node still holds the full block data. This is zero for archival
+    /// nodes and for pruned nodes that have not yet reached their pruning horizon.
+    pub effective_pruned_height: u64,
+    /// The timestamp of the current tip of the longest valid chain, or `None` for an empty chain
+    pub best_block_timestamp: Option<u64>,
     /// The geamotric mean of the proof of work of the longest chain, none if the chain is empty
     pub accumulated_difficulty: Option<Difficulty>,
 }
 
 impl ChainMetadata {
-    pub fn new(height: u64, hash: BlockHash, horizon: u64, accumulated_difficulty: Difficulty) -> ChainMetadata {
-        ChainMetadata {
+    pub fn new(
+        height: u64,
+        hash: BlockHash,
+        horizon: u64,
+        accumulated_difficulty: Difficulty,
+        best_block_timestamp: u64,
+    ) -> ChainMetadata
+    {
+        let mut metadata = ChainMetadata {
             height_of_longest_chain: Some(height),
             best_block: Some(hash),
             pruning_horizon: horizon,
+            effective_pruned_height: 0,
+            best_block_timestamp: Some(best_block_timestamp),
             accumulated_difficulty: Some(accumulated_difficulty),
-        }
+        };
+        metadata.effective_pruned_height = metadata.horizon_block(height);
+        metadata
     }
 
     /// The block height at the pruning horizon, given the chain height of the network. Typically database backends
@@ -62,6 +78,11 @@ impl ChainMetadata {
         }
     }
 
+    /// Returns true if this node still holds the full block data for the block at the given height
+    pub fn has_block_data(&self, height: u64) -> bool {
+        height >= self.effective_pruned_height
+    }
+
     /// Set the pruning horizon to indicate that the chain is in archival mode (i.e. a pruning horizon of zero)
     pub fn archival_mode(&mut self) {
         self.pruning_horizon = 0;
@@ -74,6 +95,8 @@ impl Default for ChainMetadata {
             height_of_longest_chain: None,
             best_block: None,
             pruning_horizon: 2880,
+            effective_pruned_height: 0,
+            best_block_timestamp: None,
             accumulated_difficulty: None,
         }
     }
@@ -94,7 +117,12 @@ impl Display for ChainMetadata {
             accumulated_difficulty
         ))?;
         fmt.write_str(&format!("Best_block : {}\n", best_block))?;
-        fmt.write_str(&format!("Pruning horizon : {}\n", self.pruning_horizon))
+        fmt.write_str(&format!("Pruning horizon : {}\n", self.pruning_horizon))?;
+        fmt.write_str(&format!("Effective pruned height : {}\n", self.effective_pruned_height))?;
+        match self.best_block_timestamp {
+            Some(timestamp) => fmt.write_str(&format!("Best block timestamp : {}\n", timestamp)),
+            None => fmt.write_str("Best block timestamp : None\n"),
+        }
     }
 }
 
@@ -117,6 +145,18 @@ mod test {
         assert_eq!(metadata.horizon_block(2881), 1);
     }
 
+    #[test]
+    fn effective_pruned_height() {
+        let metadata = ChainMetadata::new(100, vec![0u8], 0, 1.into(), 0);
+        assert_eq!(metadata.effective_pruned_height, 0);
+        assert!(metadata.has_block_data(0));
+
+        let metadata = ChainMetadata::new(100, vec![0u8], 60, 1.into(), 0);
+        assert_eq!(metadata.effective_pruned_height, 40);
+        assert!(!metadata.has_block_data(39));
+        assert!(metadata.has_block_data(40));
+    }
+
     #[test]
     fn archival_node() {
         let mut metadata = ChainMetadata::default();
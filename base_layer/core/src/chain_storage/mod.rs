@@ -50,6 +50,11 @@ pub use blockchain_database::{
     BlockchainDatabase,
     BlockchainDatabaseConfig,
     MutableMmrState,
+    RefusedReorg,
+    RefusedReorgHandle,
+    SafeModeHandle,
+    StalledOrphans,
+    StalledOrphansHandle,
     Validators,
 };
 pub use db_transaction::{DbKey, DbKeyValuePair, DbTransaction, DbValue, MetadataKey, MetadataValue, MmrTree};
@@ -61,13 +66,18 @@ pub use lmdb_db::{
     LMDB_DB_BLOCK_HASHES,
     LMDB_DB_HEADERS,
     LMDB_DB_KERNELS,
+    LMDB_DB_KERNELS_HASH_TO_INDEX,
+    LMDB_DB_KERNEL_EXCESSES,
     LMDB_DB_KERNEL_MMR_CP_BACKEND,
+    LMDB_DB_KERNEL_MMR_LEAF_COUNTS,
     LMDB_DB_METADATA,
     LMDB_DB_ORPHANS,
     LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND,
     LMDB_DB_STXOS,
     LMDB_DB_UTXOS,
+    LMDB_DB_UTXO_COMMITMENTS,
     LMDB_DB_UTXO_MMR_CP_BACKEND,
+    LMDB_DB_UTXO_MMR_LEAF_COUNTS,
 };
 pub use memory_db::MemoryDatabase;
 pub use metadata::ChainMetadata;
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    consensus::network::Network,
+    consensus::{network::Network, version_bits::Deployment},
     proof_of_work::Difficulty,
     transactions::tari_amount::{uT, MicroTari, T},
 };
@@ -32,6 +32,8 @@ use tari_crypto::tari_utilities::epoch_time::EpochTime;
 /// This is the inner struct used to control all consensus values.
 #[derive(Clone)]
 pub struct ConsensusConstants {
+    /// The height from which these constants are in effect. Used to schedule consensus changes at fork heights.
+    effective_from_height: u64,
     /// The min height maturity a coinbase utxo must have
     coinbase_lock_height: u64,
     /// Current version of the blockchain
@@ -59,6 +61,16 @@ pub struct ConsensusConstants {
     pub(in crate::consensus) emission_tail: MicroTari,
     /// This is the initial min difficulty for the difficulty adjustment
     min_pow_difficulty: Difficulty,
+    /// The smallest value an output may carry. Outputs below this value cost more in weight than they are worth.
+    min_output_value: MicroTari,
+    /// The smallest fee per gram that the mempool accepts and relays. Transactions paying less are not propagated.
+    min_fee_per_gram: MicroTari,
+    /// The number of blocks over which version bits signalling is tallied
+    version_bits_window: u64,
+    /// The number of blocks in a window that must signal for a deployment to lock in
+    version_bits_threshold: u64,
+    /// The soft-fork deployments that miners can signal support for
+    deployments: Vec<Deployment>,
 }
 // The target time used by the difficulty adjustment algorithms, their target time is the target block interval * PoW
 // algorithm count
@@ -73,6 +85,11 @@ impl ConsensusConstants {
         self.coinbase_lock_height
     }
 
+    /// The height from which these constants are in effect.
+    pub fn effective_from_height(&self) -> u64 {
+        self.effective_from_height
+    }
+
     /// Current version of the blockchain.
     pub fn blockchain_version(&self) -> u16 {
         self.blockchain_version
@@ -94,6 +111,11 @@ impl ConsensusConstants {
         Utc::now().add(Duration::seconds(self.future_time_limit as i64))
     }
 
+    /// The Future Time Limit (FTL) of the blockchain in seconds.
+    pub fn get_future_time_limit(&self) -> u64 {
+        self.future_time_limit
+    }
+
     /// This is the our target time in seconds between blocks.
     pub fn get_target_block_interval(&self) -> u64 {
         self.target_block_interval
@@ -136,11 +158,43 @@ impl ConsensusConstants {
         self.min_pow_difficulty
     }
 
+    /// The smallest value an output may carry (the dust limit). Every output other than a coinbase must prove, with its
+    /// range proof, that its value is at least this amount.
+    pub fn min_output_value(&self) -> MicroTari {
+        self.min_output_value
+    }
+
+    /// The smallest fee per gram that the mempool accepts and relays.
+    pub fn min_fee_per_gram(&self) -> MicroTari {
+        self.min_fee_per_gram
+    }
+
+    /// The number of blocks over which version bits signalling is tallied.
+    pub fn version_bits_window(&self) -> u64 {
+        self.version_bits_window
+    }
+
+    /// The number of blocks in a window that must signal for a deployment to lock in.
+    pub fn version_bits_threshold(&self) -> u64 {
+        self.version_bits_threshold
+    }
+
+    /// The soft-fork deployments that miners can signal support for.
+    pub fn deployments(&self) -> &[Deployment] {
+        &self.deployments
+    }
+
+    /// Returns the deployment with the given name, if it is defined.
+    pub fn deployment(&self, name: &str) -> Option<&Deployment> {
+        self.deployments.iter().find(|d| d.name == name)
+    }
+
     #[allow(clippy::identity_op)]
     pub fn rincewind() -> Self {
         let target_block_interval = 120;
         let difficulty_block_window = 90;
         ConsensusConstants {
+            effective_from_height: 0,
             coinbase_lock_height: 60,
             blockchain_version: 1,
             future_time_limit: target_block_interval * difficulty_block_window / 20,
@@ -154,6 +208,13 @@ impl ConsensusConstants {
             emission_decay: 0.999_999_560_409_038_5,
             emission_tail: 1 * T,
             min_pow_difficulty: 60_000_000.into(),
+            // Outputs already on the Rincewind chain carry range proofs over their full value, so the minimum cannot
+            // be raised on this network
+            min_output_value: 0 * uT,
+            min_fee_per_gram: 5 * uT,
+            version_bits_window: 1008,
+            version_bits_threshold: 756,
+            deployments: Vec::new(),
         }
     }
 
@@ -161,6 +222,7 @@ impl ConsensusConstants {
         let target_block_interval = 120;
         let difficulty_block_window = 90;
         ConsensusConstants {
+            effective_from_height: 0,
             coinbase_lock_height: 1,
             blockchain_version: 1,
             future_time_limit: target_block_interval * difficulty_block_window / 20,
@@ -174,6 +236,11 @@ impl ConsensusConstants {
             emission_decay: 0.999,
             emission_tail: 100.into(),
             min_pow_difficulty: 1.into(),
+            min_output_value: 0 * uT,
+            min_fee_per_gram: 0 * uT,
+            version_bits_window: 144,
+            version_bits_threshold: 108,
+            deployments: Vec::new(),
         }
     }
 
@@ -182,6 +249,7 @@ impl ConsensusConstants {
         let target_block_interval = 120;
         let difficulty_block_window = 90;
         ConsensusConstants {
+            effective_from_height: 0,
             coinbase_lock_height: 1,
             blockchain_version: 1,
             future_time_limit: target_block_interval * difficulty_block_window / 20,
@@ -195,6 +263,11 @@ impl ConsensusConstants {
             emission_decay: 0.999,
             emission_tail: 100.into(),
             min_pow_difficulty: 500_000_000.into(),
+            min_output_value: 100 * uT,
+            min_fee_per_gram: 5 * uT,
+            version_bits_window: 2016,
+            version_bits_threshold: 1916,
+            deployments: Vec::new(),
         }
     }
 }
@@ -216,6 +289,50 @@ impl ConsensusConstantsBuilder {
         self
     }
 
+    /// Sets the height from which these constants are in effect.
+    pub fn with_effective_from_height(mut self, height: u64) -> ConsensusConstantsBuilder {
+        self.consensus.effective_from_height = height;
+        self
+    }
+
+    /// Sets the Future Time Limit (FTL) in seconds.
+    pub fn with_future_time_limit(mut self, seconds: u64) -> ConsensusConstantsBuilder {
+        self.consensus.future_time_limit = seconds;
+        self
+    }
+
+    /// Sets the amount of blocks used to calculate the median timestamp.
+    pub fn with_median_timestamp_count(mut self, count: usize) -> ConsensusConstantsBuilder {
+        self.consensus.median_timestamp_count = count;
+        self
+    }
+
+    /// Sets the smallest value an output may carry.
+    pub fn with_min_output_value(mut self, value: MicroTari) -> ConsensusConstantsBuilder {
+        self.consensus.min_output_value = value;
+        self
+    }
+
+    /// Sets the smallest fee per gram that the mempool accepts and relays.
+    pub fn with_min_fee_per_gram(mut self, fee_per_gram: MicroTari) -> ConsensusConstantsBuilder {
+        self.consensus.min_fee_per_gram = fee_per_gram;
+        self
+    }
+
+    /// Sets the number of blocks over which version bits signalling is tallied and the number of those blocks that must
+    /// signal for a deployment to lock in.
+    pub fn with_version_bits_window(mut self, window: u64, threshold: u64) -> ConsensusConstantsBuilder {
+        self.consensus.version_bits_window = window;
+        self.consensus.version_bits_threshold = threshold;
+        self
+    }
+
+    /// Adds a soft-fork deployment that miners can signal support for.
+    pub fn with_deployment(mut self, deployment: Deployment) -> ConsensusConstantsBuilder {
+        self.consensus.deployments.push(deployment);
+        self
+    }
+
     pub fn with_emission_amounts(
         mut self,
         intial_amount: MicroTari,
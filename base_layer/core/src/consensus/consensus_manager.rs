@@ -29,14 +29,28 @@ use crate::{
             get_rincewind_genesis_block,
         },
         Block,
+        BlockHeader,
+    },
+    chain_storage::{BlockchainBackend, ChainStorageError},
+    consensus::{
+        emission::EmissionSchedule,
+        network::Network,
+        version_bits::{signals_bit, with_signal_bit, Deployment, DeploymentState},
+        ConsensusConstants,
+    },
+    proof_of_work::{
+        get_median_timestamp,
+        BlockDifficultyStats,
+        Difficulty,
+        DifficultyAdjustmentError,
+        DifficultyStatsCalculator,
+        PowAlgorithm,
+        TargetDifficultyCalculator,
     },
-    chain_storage::{fetch_headers, BlockchainBackend, ChainStorageError},
-    consensus::{emission::EmissionSchedule, network::Network, ConsensusConstants},
-    proof_of_work::{get_median_timestamp, get_target_difficulty, Difficulty, DifficultyAdjustmentError, PowAlgorithm},
     transactions::tari_amount::MicroTari,
 };
 use derive_error::Error;
-use std::sync::Arc;
+use std::{cmp, ops::RangeInclusive, sync::Arc};
 use tari_crypto::tari_utilities::{epoch_time::EpochTime, hash::Hashable};
 
 #[derive(Debug, Error, Clone, PartialEq)]
@@ -52,6 +66,15 @@ pub enum ConsensusManagerError {
     PoisonedAccess(String),
     /// No Difficulty adjustment manager present
     MissingDifficultyAdjustmentManager,
+    /// The named deployment is not defined in the consensus constants
+    #[error(non_std, no_from)]
+    UnknownDeployment(String),
+    /// The emission schedule of the consensus constants is not well formed
+    #[error(msg_embedded, non_std, no_from)]
+    InvalidEmissionSchedule(String),
+    /// The version bits window or threshold of the consensus constants is not well formed
+    #[error(msg_embedded, non_std, no_from)]
+    InvalidVersionBits(String),
 }
 
 /// This is the consensus manager struct. This manages all state-full consensus code.
@@ -85,9 +108,19 @@ impl ConsensusManager {
         &self.inner.emission
     }
 
-    /// Get a pointer to the consensus constants
+    /// Get a pointer to the consensus constants that are in effect from the genesis block
     pub fn consensus_constants(&self) -> &ConsensusConstants {
-        &self.inner.consensus_constants
+        &self.inner.consensus_constants[0]
+    }
+
+    /// Get a pointer to the consensus constants that are in effect at the provided height
+    pub fn consensus_constants_at_height(&self, height: u64) -> &ConsensusConstants {
+        self.inner
+            .consensus_constants
+            .iter()
+            .rev()
+            .find(|c| c.effective_from_height() <= height)
+            .unwrap_or_else(|| self.consensus_constants())
     }
 
     /// Returns the estimated target difficulty for the specified PoW algorithm at the chain tip.
@@ -115,19 +148,77 @@ impl ConsensusManager {
         // TODO: store and use the target difficulty at horizon height as the initial difficulty for the
         // LinearWeightedMovingAverage, then only the header set from horizon_height+1 to height need to be
         // requested and processed.
-        let block_nums = (0..=height).collect();
-        let headers = fetch_headers(db, block_nums)?;
-        Ok(get_target_difficulty(
-            headers,
-            pow_algo,
-            self.inner.consensus_constants.get_difficulty_block_window() as usize,
-            self.inner.consensus_constants.get_diff_target_block_interval(),
-            self.inner.consensus_constants.get_difficulty_max_block_interval(),
-            self.inner.consensus_constants.min_pow_difficulty(),
-        )?)
-    }
-
-    /// Returns the median timestamp of the past 11 blocks at the chain tip.
+        let constants = self.consensus_constants();
+        let mut calculator = TargetDifficultyCalculator::new(
+            constants.get_difficulty_block_window() as usize,
+            constants.get_diff_target_block_interval(),
+            constants.get_difficulty_max_block_interval(),
+            constants.min_pow_difficulty(),
+        );
+        let mut error = None;
+        db.for_each_header(0..=height, |pair| {
+            if error.is_some() {
+                return;
+            }
+            if let Err(e) = pair
+                .map_err(ConsensusManagerError::from)
+                .and_then(|(_, header)| calculator.add_header(&header).map_err(ConsensusManagerError::from))
+            {
+                error = Some(e);
+            }
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(calculator.get_difficulty(pow_algo)),
+        }
+    }
+
+    /// Returns the difficulty statistics of the blocks in the provided height range, up to the chain tip. The
+    /// difficulty adjustment is replayed from the genesis block so that the target difficulties match the ones the
+    /// blocks were validated against.
+    pub fn get_difficulty_stats<B: BlockchainBackend>(
+        &self,
+        db: &B,
+        range: RangeInclusive<u64>,
+    ) -> Result<Vec<BlockDifficultyStats>, ConsensusManagerError>
+    {
+        let tip_height = db
+            .fetch_metadata()?
+            .height_of_longest_chain
+            .ok_or_else(|| ConsensusManagerError::EmptyBlockchain)?;
+        let end_height = cmp::min(*range.end(), tip_height);
+        if *range.start() > end_height {
+            return Ok(Vec::new());
+        }
+        let constants = self.consensus_constants();
+        let mut calculator = DifficultyStatsCalculator::new(
+            constants.get_difficulty_block_window() as usize,
+            constants.get_diff_target_block_interval(),
+            constants.get_difficulty_max_block_interval(),
+            constants.min_pow_difficulty(),
+        );
+        let mut stats = Vec::with_capacity((end_height - *range.start() + 1) as usize);
+        let mut error = None;
+        db.for_each_header(0..=end_height, |pair| {
+            if error.is_some() {
+                return;
+            }
+            match pair
+                .map_err(ConsensusManagerError::from)
+                .and_then(|(_, header)| calculator.add_header(&header).map_err(ConsensusManagerError::from))
+            {
+                Ok(block_stats) if range.contains(&block_stats.height) => stats.push(block_stats),
+                Ok(_) => {},
+                Err(e) => error = Some(e),
+            }
+        })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(stats),
+        }
+    }
+
+    /// Returns the median timestamp of the past `median_timestamp_count` blocks at the chain tip.
     pub fn get_median_timestamp<B: BlockchainBackend>(&self, db: &B) -> Result<EpochTime, ConsensusManagerError> {
         let height = db
             .fetch_metadata()?
@@ -136,24 +227,128 @@ impl ConsensusManager {
         self.get_median_timestamp_at_height(db, height)
     }
 
-    /// Returns the median timestamp of the past 11 blocks at the provided height.
+    /// Returns the median timestamp of the past `median_timestamp_count` blocks at the provided height, using the
+    /// consensus constants in effect at that height.
     pub fn get_median_timestamp_at_height<B: BlockchainBackend>(
         &self,
         db: &B,
         height: u64,
     ) -> Result<EpochTime, ConsensusManagerError>
     {
-        let median_timestamp_count = self.inner.consensus_constants.get_median_timestamp_count();
+        let median_timestamp_count = self.consensus_constants_at_height(height).get_median_timestamp_count();
         let min_height = if height > median_timestamp_count as u64 {
             height - median_timestamp_count as u64
         } else {
             0
         };
-        let block_nums = (min_height..=height).collect();
-        let headers = fetch_headers(db, block_nums)?;
+        let mut headers = Vec::<BlockHeader>::with_capacity(median_timestamp_count + 1);
+        let mut error = None;
+        db.for_each_header(min_height..=height, |pair| match pair {
+            Ok((_, header)) => headers.push(header),
+            Err(e) => {
+                error.get_or_insert(e);
+            },
+        })?;
+        if let Some(e) = error {
+            return Err(e.into());
+        }
         get_median_timestamp(headers).ok_or_else(|| ConsensusManagerError::EmptyBlockchain)
     }
 
+    /// Returns the activation state of the named deployment for the block at the provided height. The state is fixed
+    /// for the whole version bits window that contains the height and is determined by the signalling in the windows
+    /// before it, so only the headers below the start of that window are required.
+    pub fn get_deployment_state<B: BlockchainBackend>(
+        &self,
+        db: &B,
+        name: &str,
+        height: u64,
+    ) -> Result<DeploymentState, ConsensusManagerError>
+    {
+        let constants = self.consensus_constants_at_height(height);
+        let deployment = constants
+            .deployment(name)
+            .ok_or_else(|| ConsensusManagerError::UnknownDeployment(name.to_string()))?;
+        self.deployment_state(db, constants, deployment, height)
+    }
+
+    /// Returns the number of blocks that signal support for the named deployment in the version bits window that
+    /// contains the provided height, counting from the start of the window up to and including the height.
+    pub fn get_deployment_signal_count<B: BlockchainBackend>(
+        &self,
+        db: &B,
+        name: &str,
+        height: u64,
+    ) -> Result<u64, ConsensusManagerError>
+    {
+        let constants = self.consensus_constants_at_height(height);
+        let deployment = constants
+            .deployment(name)
+            .ok_or_else(|| ConsensusManagerError::UnknownDeployment(name.to_string()))?;
+        let window_start = height - height % constants.version_bits_window();
+        count_signals(db, deployment.bit, window_start, height)
+    }
+
+    /// Returns the header version a new block at the provided height should use: the blockchain version, with the
+    /// signalling bits set for every deployment that is still being tallied or is locked in.
+    pub fn get_block_version<B: BlockchainBackend>(&self, db: &B, height: u64) -> Result<u16, ConsensusManagerError> {
+        let constants = self.consensus_constants_at_height(height);
+        let mut version = constants.blockchain_version();
+        for deployment in constants.deployments() {
+            match self.deployment_state(db, constants, deployment, height)? {
+                DeploymentState::Started | DeploymentState::LockedIn => {
+                    version = with_signal_bit(version, deployment.bit);
+                },
+                _ => {},
+            }
+        }
+        Ok(version)
+    }
+
+    // Walks the deployment through the version bits windows up to the window containing the provided height. The
+    // walk starts at the first window that can move the deployment out of the defined state, as all windows before
+    // it leave the state untouched. The signalling is only tallied for the windows in which the deployment was started.
+    fn deployment_state<B: BlockchainBackend>(
+        &self,
+        db: &B,
+        constants: &ConsensusConstants,
+        deployment: &Deployment,
+        height: u64,
+    ) -> Result<DeploymentState, ConsensusManagerError>
+    {
+        let window = constants.version_bits_window();
+        let current_window_start = height - height % window;
+        let mut state = DeploymentState::Defined;
+        let first_transition = cmp::min(deployment.start_height, deployment.timeout_height);
+        let mut window_start = match first_transition.checked_add(window - 1) {
+            Some(rounded_up) => rounded_up - rounded_up % window,
+            None => return Ok(state),
+        };
+        while window_start <= current_window_start {
+            state = match state {
+                DeploymentState::Defined if window_start >= deployment.timeout_height => DeploymentState::Failed,
+                DeploymentState::Defined if window_start >= deployment.start_height => DeploymentState::Started,
+                DeploymentState::Started => {
+                    let count = count_signals(db, deployment.bit, window_start - window, window_start - 1)?;
+                    if count >= constants.version_bits_threshold() {
+                        DeploymentState::LockedIn
+                    } else if window_start >= deployment.timeout_height {
+                        DeploymentState::Failed
+                    } else {
+                        DeploymentState::Started
+                    }
+                },
+                DeploymentState::LockedIn => DeploymentState::Active,
+                state => state,
+            };
+            if state == DeploymentState::Active || state == DeploymentState::Failed {
+                break;
+            }
+            window_start += window;
+        }
+        Ok(state)
+    }
+
     /// Creates a total_coinbase offset containing all fees for the validation from block
     pub fn calculate_coinbase_and_fees(&self, block: &Block) -> MicroTari {
         let coinbase = self.emission_schedule().block_reward(block.header.height);
@@ -166,6 +361,47 @@ impl ConsensusManager {
     }
 }
 
+// Counts the headers from `from` to `to` inclusive that signal with the given version bit
+fn count_signals<B: BlockchainBackend>(db: &B, bit: u8, from: u64, to: u64) -> Result<u64, ConsensusManagerError> {
+    let mut count = 0;
+    let mut error = None;
+    db.for_each_header(from..=to, |pair| match pair {
+        Ok((_, header)) => {
+            if signals_bit(header.version, bit) {
+                count += 1;
+            }
+        },
+        Err(e) => {
+            error.get_or_insert(e);
+        },
+    })?;
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok(count),
+    }
+}
+
+// The version bits window is used as a divisor and the threshold has to be reachable within a single window
+fn validate_version_bits(constants: &ConsensusConstants) -> Result<(), String> {
+    let window = constants.version_bits_window();
+    let threshold = constants.version_bits_threshold();
+    if window == 0 {
+        return Err(format!(
+            "Version bits window effective from height {} must not be zero",
+            constants.effective_from_height()
+        ));
+    }
+    if threshold > window {
+        return Err(format!(
+            "Version bits threshold {} effective from height {} exceeds the window of {} blocks",
+            threshold,
+            constants.effective_from_height(),
+            window
+        ));
+    }
+    Ok(())
+}
+
 impl Clone for ConsensusManager {
     fn clone(&self) -> Self {
         Self {
@@ -176,8 +412,9 @@ impl Clone for ConsensusManager {
 
 /// This is the used to control all consensus values.
 struct ConsensusManagerInner {
-    /// This is the inner struct used to control all consensus values.
-    pub consensus_constants: ConsensusConstants,
+    /// This is the inner struct used to control all consensus values, ordered by the height from which they are in
+    /// effect. The first entry is always in effect from the genesis block.
+    pub consensus_constants: Vec<ConsensusConstants>,
     /// The configured chain network.
     pub network: Network,
     /// The configuration for the emission schedule.
@@ -189,7 +426,7 @@ struct ConsensusManagerInner {
 /// Constructor for the consensus manager struct
 pub struct ConsensusManagerBuilder {
     /// This is the inner struct used to control all consensus values.
-    pub consensus_constants: Option<ConsensusConstants>,
+    pub consensus_constants: Vec<ConsensusConstants>,
     /// The configured chain network.
     pub network: Network,
     /// This allows the user to set a custom Genesis block
@@ -200,15 +437,16 @@ impl ConsensusManagerBuilder {
     /// Creates a new ConsensusManagerBuilder with the specified network
     pub fn new(network: Network) -> Self {
         ConsensusManagerBuilder {
-            consensus_constants: None,
+            consensus_constants: Vec::new(),
             network,
             gen_block: None,
         }
     }
 
-    /// Adds in a custom consensus constants to be used
+    /// Adds in a custom consensus constants to be used. This can be called more than once to schedule consensus
+    /// changes at the height returned by `effective_from_height`.
     pub fn with_consensus_constants(mut self, consensus_constants: ConsensusConstants) -> Self {
-        self.consensus_constants = Some(consensus_constants);
+        self.consensus_constants.push(consensus_constants);
         self
     }
 
@@ -218,23 +456,41 @@ impl ConsensusManagerBuilder {
         self
     }
 
-    /// Builds a consensus manager
-    #[allow(clippy::or_fun_call)]
+    /// Builds a consensus manager. This panics if the consensus constants are not well formed, use `try_build` to
+    /// handle the error instead.
     pub fn build(self) -> ConsensusManager {
-        let consensus_constants = self
-            .consensus_constants
-            .unwrap_or(self.network.create_consensus_constants());
+        self.try_build().expect("Invalid consensus constants")
+    }
+
+    /// Builds a consensus manager, checking that the consensus constants produce a well formed emission schedule and
+    /// version bits windows
+    pub fn try_build(self) -> Result<ConsensusManager, ConsensusManagerError> {
+        let mut consensus_constants = self.consensus_constants;
+        consensus_constants.sort_by_key(|c| c.effective_from_height());
+        if consensus_constants
+            .first()
+            .map(|c| c.effective_from_height() > 0)
+            .unwrap_or(true)
+        {
+            consensus_constants.insert(0, self.network.create_consensus_constants());
+        }
         let emission = EmissionSchedule::new(
-            consensus_constants.emission_initial,
-            consensus_constants.emission_decay,
-            consensus_constants.emission_tail,
+            consensus_constants[0].emission_initial,
+            consensus_constants[0].emission_decay,
+            consensus_constants[0].emission_tail,
         );
+        emission
+            .validate()
+            .map_err(ConsensusManagerError::InvalidEmissionSchedule)?;
+        for constants in &consensus_constants {
+            validate_version_bits(constants).map_err(ConsensusManagerError::InvalidVersionBits)?;
+        }
         let inner = ConsensusManagerInner {
             consensus_constants,
             network: self.network,
             emission,
             gen_block: self.gen_block,
         };
-        ConsensusManager { inner: Arc::new(inner) }
+        Ok(ConsensusManager { inner: Arc::new(inner) })
     }
 }
@@ -53,13 +53,59 @@ impl EmissionSchedule {
 
     /// Calculate the block reward for the given block height, in µTari
     pub fn block_reward(&self, block: u64) -> MicroTari {
-        let base = if block < std::i32::MAX as u64 {
+        self.decaying_reward(block) + self.tail
+    }
+
+    /// The decaying part of the block reward. It is cut off to zero at heights that don't fit in an `i32`.
+    fn decaying_reward(&self, block: u64) -> MicroTari {
+        if block < std::i32::MAX as u64 {
             let base_f = (f64::from(self.initial) * self.decay.powi(block as i32)).trunc();
             MicroTari::from(base_f as u64)
         } else {
             MicroTari::from(0)
+        }
+    }
+
+    /// The first block height at which the block reward is only the tail emission. The decaying reward reaches zero
+    /// once $$A_0 r^n < 1$$, i.e. from $$n > -\ln A_0 / \ln r$$.
+    pub fn tail_start_height(&self) -> u64 {
+        let cutoff = std::i32::MAX as u64;
+        if self.decay.is_nan() || self.decay < 0.0 || self.decay >= 1.0 {
+            return cutoff;
+        }
+        let estimate = (-f64::from(self.initial).ln() / self.decay.ln()).floor();
+        let mut height = if estimate >= cutoff as f64 {
+            cutoff
+        } else {
+            estimate.max(0.0) as u64
         };
-        base + self.tail
+        // Correct for any floating point error in the estimate
+        while height > 0 && self.decaying_reward(height - 1) == MicroTari::from(0) {
+            height -= 1;
+        }
+        while height < cutoff && self.decaying_reward(height) > MicroTari::from(0) {
+            height += 1;
+        }
+        height
+    }
+
+    /// Check that the emission schedule is well formed. The decay must be at least 0 and less than 1, so that the
+    /// block reward never increases, and the decaying reward must reach zero on its own rather than being cut off at
+    /// the largest height it is calculated for.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.decay.is_nan() || self.decay < 0.0 || self.decay >= 1.0 {
+            return Err(format!(
+                "The emission decay must be at least 0 and less than 1, but is {}",
+                self.decay
+            ));
+        }
+        if self.decaying_reward(std::i32::MAX as u64 - 1) > MicroTari::from(0) {
+            return Err(format!(
+                "The block reward of {} with a decay of {} does not decay to the tail emission",
+                self.initial, self.decay
+            ));
+        }
+        Ok(())
     }
 
     /// Calculate the exact emitted supply after the given block, in µTari. The value is calculated by summing up the
@@ -124,7 +170,10 @@ impl<'a> Iterator for EmissionValues<'a> {
 
 #[cfg(test)]
 mod test {
-    use crate::{consensus::emission::EmissionSchedule, transactions::tari_amount::MicroTari};
+    use crate::{
+        consensus::{emission::EmissionSchedule, Network},
+        transactions::tari_amount::MicroTari,
+    };
     #[test]
     fn schedule() {
         let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.999, MicroTari::from(100));
@@ -146,6 +195,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn tail_start_height() {
+        let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.999, MicroTari::from(100));
+        let height = schedule.tail_start_height();
+        assert!(height > 0);
+        assert!(schedule.block_reward(height - 1) > MicroTari::from(100));
+        assert_eq!(schedule.block_reward(height), MicroTari::from(100));
+        assert_eq!(schedule.block_reward(height + 1000), MicroTari::from(100));
+
+        let schedule = EmissionSchedule::new(MicroTari::from(0), 0.999, MicroTari::from(100));
+        assert_eq!(schedule.tail_start_height(), 0);
+        let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.0, MicroTari::from(100));
+        assert_eq!(schedule.tail_start_height(), 1);
+    }
+
+    #[test]
+    fn validate_schedule() {
+        let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.999, MicroTari::from(100));
+        assert!(schedule.validate().is_ok());
+        let schedule = EmissionSchedule::new(MicroTari::from(1e21 as u64), 0.999_9999, MicroTari::from(100));
+        assert!(schedule.validate().is_ok());
+        for decay in &[1.0, 1.001, -0.5, std::f64::NAN] {
+            let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), *decay, MicroTari::from(100));
+            assert!(schedule.validate().is_err());
+        }
+        // The reward is still decaying when it is cut off
+        let schedule = EmissionSchedule::new(MicroTari::from(1e21 as u64), 0.999_999_999, MicroTari::from(100));
+        assert!(schedule.validate().is_err());
+    }
+
+    #[test]
+    fn network_emission_schedules_are_valid() {
+        for network in &[Network::MainNet, Network::Rincewind, Network::LocalNet] {
+            let (initial, decay, tail) = network.create_consensus_constants().emission_amounts();
+            let schedule = EmissionSchedule::new(initial, decay, tail);
+            assert!(schedule.validate().is_ok());
+            assert!(schedule.tail_start_height() < std::i32::MAX as u64);
+        }
+    }
+
     #[test]
     fn generate_emission_schedule_as_iterator() {
         let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.999, MicroTari::from(100));
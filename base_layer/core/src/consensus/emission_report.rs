@@ -0,0 +1,354 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Dry runs of the emission schedule of a set of consensus constants, used to review the emission curve before the
+//! constants of a new network are settled on. An [EmissionReport] lists the block reward and the total supply at
+//! checkpoints given in years since the genesis block, and an [EmissionComparison] lists them for two sets of
+//! constants side by side.
+//!
+//! [EmissionReport]: ./struct.EmissionReport.html
+//! [EmissionComparison]: ./struct.EmissionComparison.html
+
+use crate::{
+    consensus::{emission::EmissionSchedule, ConsensusConstants},
+    transactions::tari_amount::{MicroTari, Tari},
+};
+use std::{
+    cmp::max,
+    fmt::{Display, Error, Formatter},
+};
+
+/// The checkpoints, in years since the genesis block, that the supply is reported at by default
+pub const DEFAULT_CHECKPOINT_YEARS: [u64; 8] = [1, 2, 3, 4, 5, 10, 20, 50];
+
+const SECONDS_PER_YEAR: u64 = 31_557_600;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The block reward and total supply at a checkpoint
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmissionCheckpoint {
+    pub years: u64,
+    pub height: u64,
+    pub block_reward: MicroTari,
+    pub supply: MicroTari,
+}
+
+/// The emission curve of a set of consensus constants
+#[derive(Clone, Debug)]
+pub struct EmissionReport {
+    pub initial: MicroTari,
+    pub decay: f64,
+    pub tail: MicroTari,
+    pub target_block_interval: u64,
+    /// The number of blocks it takes for the decaying part of the block reward to halve
+    pub half_life: u64,
+    /// The first height at which the block reward is only the tail emission
+    pub tail_start_height: u64,
+    /// The supply emitted by the blocks before the tail emission starts
+    pub supply_before_tail: MicroTari,
+    pub checkpoints: Vec<EmissionCheckpoint>,
+}
+
+impl EmissionReport {
+    /// Calculate the emission curve of the consensus constants up to the tail emission onset, and the block reward
+    /// and supply at each of the checkpoints given in years since the genesis block. An error is returned when the
+    /// emission schedule is not well formed.
+    pub fn new(constants: &ConsensusConstants, checkpoint_years: &[u64]) -> Result<Self, String> {
+        let (initial, decay, tail) = constants.emission_amounts();
+        let schedule = EmissionSchedule::new(initial, decay, tail);
+        schedule.validate()?;
+        let target_block_interval = max(constants.get_target_block_interval(), 1);
+        let tail_start_height = schedule.tail_start_height();
+
+        let mut checkpoint_heights = checkpoint_years
+            .iter()
+            .map(|years| (*years, years * SECONDS_PER_YEAR / target_block_interval))
+            .collect::<Vec<_>>();
+        checkpoint_heights.sort_by_key(|(years, _)| *years);
+        checkpoint_heights.dedup_by_key(|(years, _)| *years);
+
+        // Once the tail emission starts the supply grows by the tail emission every block, so the curve only needs to
+        // be summed up to the onset
+        let mut supply_before_tail = MicroTari::from(0);
+        let mut checkpoints = Vec::with_capacity(checkpoint_heights.len());
+        let mut pending = checkpoint_heights.iter().peekable();
+        for (height, reward, supply) in schedule.iter().take(tail_start_height as usize) {
+            while let Some((years, checkpoint_height)) = pending.peek() {
+                if *checkpoint_height != height {
+                    break;
+                }
+                checkpoints.push(EmissionCheckpoint {
+                    years: *years,
+                    height,
+                    block_reward: reward,
+                    supply,
+                });
+                pending.next();
+            }
+            supply_before_tail = supply;
+        }
+        for (years, height) in pending {
+            let tail_blocks = height - tail_start_height + 1;
+            checkpoints.push(EmissionCheckpoint {
+                years: *years,
+                height: *height,
+                block_reward: tail,
+                supply: MicroTari::from(
+                    u64::from(supply_before_tail).saturating_add(tail_blocks.saturating_mul(u64::from(tail))),
+                ),
+            });
+        }
+
+        Ok(Self {
+            initial,
+            decay,
+            tail,
+            target_block_interval,
+            half_life: (0.5f64.ln() / decay.ln()).ceil().max(1.0) as u64,
+            tail_start_height,
+            supply_before_tail,
+            checkpoints,
+        })
+    }
+
+    /// The time it takes to mine the given number of blocks at the target block interval, in days
+    fn days(&self, blocks: u64) -> f64 {
+        blocks as f64 * self.target_block_interval as f64 / SECONDS_PER_DAY
+    }
+}
+
+impl Display for EmissionReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        writeln!(f, "Initial block reward: {}", Tari::from(self.initial))?;
+        writeln!(f, "Decay: {}", self.decay)?;
+        writeln!(f, "Tail block reward: {}", Tari::from(self.tail))?;
+        writeln!(f, "Target block interval: {} s", self.target_block_interval)?;
+        writeln!(
+            f,
+            "Block reward half-life: {} blocks (~{:.1} days)",
+            self.half_life,
+            self.days(self.half_life)
+        )?;
+        writeln!(
+            f,
+            "Tail emission starts at height {} (~{:.1} years), after a supply of {}",
+            self.tail_start_height,
+            self.days(self.tail_start_height) / 365.25,
+            Tari::from(self.supply_before_tail)
+        )?;
+        writeln!(
+            f,
+            "{:>6} {:>12} {:>22} {:>28}",
+            "Years", "Height", "Block reward", "Supply"
+        )?;
+        for checkpoint in &self.checkpoints {
+            writeln!(
+                f,
+                "{:>6} {:>12} {:>22} {:>28}",
+                checkpoint.years,
+                checkpoint.height,
+                Tari::from(checkpoint.block_reward).to_string(),
+                Tari::from(checkpoint.supply).to_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The emission curves of two sets of consensus constants, compared at the same checkpoints
+#[derive(Clone, Debug)]
+pub struct EmissionComparison {
+    pub current: EmissionReport,
+    pub proposed: EmissionReport,
+}
+
+impl EmissionComparison {
+    /// Compare the emission curve of the proposed consensus constants to that of the current constants
+    pub fn new(
+        current: &ConsensusConstants,
+        proposed: &ConsensusConstants,
+        checkpoint_years: &[u64],
+    ) -> Result<Self, String>
+    {
+        Ok(Self {
+            current: EmissionReport::new(current, checkpoint_years)?,
+            proposed: EmissionReport::new(proposed, checkpoint_years)?,
+        })
+    }
+}
+
+/// Formats the change from `current` to `proposed` in Tari, with a sign
+fn difference(current: MicroTari, proposed: MicroTari) -> String {
+    if proposed >= current {
+        format!("+{}", Tari::from(proposed - current))
+    } else {
+        format!("-{}", Tari::from(current - proposed))
+    }
+}
+
+impl Display for EmissionComparison {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let (current, proposed) = (&self.current, &self.proposed);
+        writeln!(f, "{:<24} {:>26} {:>26}", "", "Current", "Proposed")?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Initial block reward",
+            Tari::from(current.initial).to_string(),
+            Tari::from(proposed.initial).to_string()
+        )?;
+        writeln!(f, "{:<24} {:>26} {:>26}", "Decay", current.decay, proposed.decay)?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Tail block reward",
+            Tari::from(current.tail).to_string(),
+            Tari::from(proposed.tail).to_string()
+        )?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Target block interval (s)", current.target_block_interval, proposed.target_block_interval
+        )?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Half-life (blocks)", current.half_life, proposed.half_life
+        )?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Tail emission height", current.tail_start_height, proposed.tail_start_height
+        )?;
+        writeln!(
+            f,
+            "{:<24} {:>26} {:>26}",
+            "Supply before tail",
+            Tari::from(current.supply_before_tail).to_string(),
+            Tari::from(proposed.supply_before_tail).to_string()
+        )?;
+        writeln!(
+            f,
+            "{:>6} {:>28} {:>28} {:>28}",
+            "Years", "Current supply", "Proposed supply", "Difference"
+        )?;
+        for (c, p) in current.checkpoints.iter().zip(proposed.checkpoints.iter()) {
+            writeln!(
+                f,
+                "{:>6} {:>28} {:>28} {:>28}",
+                c.years,
+                Tari::from(c.supply).to_string(),
+                Tari::from(p.supply).to_string(),
+                difference(c.supply, p.supply)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::consensus::{ConsensusConstantsBuilder, Network};
+
+    #[test]
+    fn report_matches_schedule() {
+        let constants = Network::LocalNet.create_consensus_constants();
+        let (initial, decay, tail) = constants.emission_amounts();
+        let schedule = EmissionSchedule::new(initial, decay, tail);
+        let report = EmissionReport::new(&constants, &[50, 1, 1]).unwrap();
+
+        assert_eq!(report.tail_start_height, schedule.tail_start_height());
+        assert_eq!(
+            report.supply_before_tail,
+            schedule.supply_at_block(report.tail_start_height - 1)
+        );
+        // The checkpoints are sorted and deduplicated
+        assert_eq!(report.checkpoints.len(), 2);
+        assert_eq!(report.checkpoints[0].years, 1);
+        assert_eq!(report.checkpoints[1].years, 50);
+        for checkpoint in &report.checkpoints {
+            assert_eq!(
+                checkpoint.height,
+                checkpoint.years * SECONDS_PER_YEAR / constants.get_target_block_interval()
+            );
+            assert_eq!(checkpoint.block_reward, schedule.block_reward(checkpoint.height));
+        }
+        // The supply after the tail emission onset is extrapolated from the tail emission
+        let checkpoint = &report.checkpoints[1];
+        assert!(checkpoint.height > report.tail_start_height);
+        assert_eq!(
+            checkpoint.supply,
+            report.supply_before_tail + (checkpoint.height - report.tail_start_height + 1) * tail
+        );
+        assert_eq!(report.half_life, 693);
+    }
+
+    #[test]
+    fn checkpoint_before_tail_emission() {
+        let constants = ConsensusConstantsBuilder::new(Network::LocalNet)
+            .with_emission_amounts(MicroTari::from(10_000_000), 0.999_999, MicroTari::from(100))
+            .build();
+        let schedule = EmissionSchedule::new(MicroTari::from(10_000_000), 0.999_999, MicroTari::from(100));
+        let report = EmissionReport::new(&constants, &[1]).unwrap();
+        let checkpoint = &report.checkpoints[0];
+        assert!(checkpoint.height < report.tail_start_height);
+        assert_eq!(checkpoint.supply, schedule.supply_at_block(checkpoint.height));
+    }
+
+    #[test]
+    fn invalid_schedule() {
+        let constants = ConsensusConstantsBuilder::new(Network::LocalNet)
+            .with_emission_amounts(MicroTari::from(10_000_000), 1.0, MicroTari::from(100))
+            .build();
+        assert!(EmissionReport::new(&constants, &DEFAULT_CHECKPOINT_YEARS).is_err());
+        assert!(EmissionComparison::new(
+            &Network::LocalNet.create_consensus_constants(),
+            &constants,
+            &DEFAULT_CHECKPOINT_YEARS
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn compare_constants() {
+        let current = Network::LocalNet.create_consensus_constants();
+        let proposed = ConsensusConstantsBuilder::new(Network::LocalNet)
+            .with_emission_amounts(MicroTari::from(10_000_000), 0.999, MicroTari::from(200))
+            .build();
+        let comparison = EmissionComparison::new(&current, &proposed, &[1]).unwrap();
+        assert_eq!(
+            comparison.current.tail_start_height,
+            comparison.proposed.tail_start_height
+        );
+        let (c, p) = (&comparison.current.checkpoints[0], &comparison.proposed.checkpoints[0]);
+        assert_eq!(p.supply - c.supply, (c.height + 1) * MicroTari::from(100));
+        assert_eq!(
+            difference(c.supply, p.supply),
+            format!("+{}", Tari::from(p.supply - c.supply))
+        );
+        assert_eq!(
+            difference(p.supply, c.supply),
+            format!("-{}", Tari::from(p.supply - c.supply))
+        );
+    }
+}
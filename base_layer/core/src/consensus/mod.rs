@@ -25,7 +25,10 @@ mod consensus_manager;
 mod network;
 
 pub mod emission;
+pub mod emission_report;
+pub mod version_bits;
 
 pub use consensus_constants::{ConsensusConstants, ConsensusConstantsBuilder};
 pub use consensus_manager::{ConsensusManager, ConsensusManagerBuilder, ConsensusManagerError};
 pub use network::Network;
+pub use version_bits::{Deployment, DeploymentState};
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::consensus_constants::ConsensusConstants;
+use std::str::FromStr;
 
 /// Specifies the configured chain network.
 #[derive(Copy, Clone)]
@@ -43,3 +44,19 @@ impl Network {
         }
     }
 }
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Network::MainNet),
+            "rincewind" => Ok(Network::Rincewind),
+            "localnet" => Ok(Network::LocalNet),
+            invalid => Err(format!(
+                "Unrecognized network '{}'. Available networks are: mainnet, rincewind, localnet",
+                invalid
+            )),
+        }
+    }
+}
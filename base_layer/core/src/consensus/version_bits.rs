@@ -0,0 +1,113 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Version bits signalling for soft-fork deployments
+//!
+//! The lower byte of a block header version holds the blockchain version and the upper byte holds eight signalling
+//! bits. Miners signal support for a [Deployment] by setting its bit. Signalling is tallied over consecutive windows of
+//! `version_bits_window` blocks; once `version_bits_threshold` blocks in a window signal, the deployment locks in and
+//! it becomes active one window later. A deployment that has not locked in by its timeout height fails.
+
+use std::fmt::{Display, Error, Formatter};
+
+/// The number of bits the signalling bits are shifted by in the block header version
+const SIGNAL_BITS_SHIFT: u16 = 8;
+/// The mask of the blockchain version part of a block header version
+const BASE_VERSION_MASK: u16 = 0x00ff;
+/// The highest bit a deployment can signal with
+pub const MAX_DEPLOYMENT_BIT: u8 = 7;
+
+/// A soft-fork that miners signal support for with a version bit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deployment {
+    /// The name used to look up the deployment
+    pub name: &'static str,
+    /// The version bit used to signal support, from 0 to `MAX_DEPLOYMENT_BIT`
+    pub bit: u8,
+    /// The height from which signalling is counted
+    pub start_height: u64,
+    /// The height at which the deployment fails if it has not locked in
+    pub timeout_height: u64,
+}
+
+/// The activation state of a deployment. The state of a block is fixed for the window that contains it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeploymentState {
+    /// The start height has not been reached
+    Defined,
+    /// Signalling is being tallied
+    Started,
+    /// Enough blocks signalled; the deployment activates at the start of the next window
+    LockedIn,
+    /// The deployment's rules are in effect
+    Active,
+    /// The deployment timed out before it locked in
+    Failed,
+}
+
+impl Display for DeploymentState {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let state = match self {
+            DeploymentState::Defined => "Defined",
+            DeploymentState::Started => "Started",
+            DeploymentState::LockedIn => "Locked in",
+            DeploymentState::Active => "Active",
+            DeploymentState::Failed => "Failed",
+        };
+        fmt.write_str(state)
+    }
+}
+
+/// Returns the blockchain version part of a block header version, without the signalling bits.
+pub fn base_version(version: u16) -> u16 {
+    version & BASE_VERSION_MASK
+}
+
+/// Returns true if the block header version signals support with the given bit.
+pub fn signals_bit(version: u16, bit: u8) -> bool {
+    bit <= MAX_DEPLOYMENT_BIT && version & (1 << (SIGNAL_BITS_SHIFT + u16::from(bit))) != 0
+}
+
+/// Returns the block header version with the given signalling bit set.
+pub fn with_signal_bit(version: u16, bit: u8) -> u16 {
+    if bit > MAX_DEPLOYMENT_BIT {
+        return version;
+    }
+    version | (1 << (SIGNAL_BITS_SHIFT + u16::from(bit)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn signal_bits() {
+        let version = with_signal_bit(with_signal_bit(1, 0), 7);
+        assert_eq!(version, 0x8101);
+        assert_eq!(base_version(version), 1);
+        assert!(signals_bit(version, 0));
+        assert!(!signals_bit(version, 1));
+        assert!(signals_bit(version, 7));
+        assert!(!signals_bit(version, 8));
+        assert_eq!(with_signal_bit(1, 8), 1);
+    }
+}
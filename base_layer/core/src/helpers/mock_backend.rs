@@ -28,6 +28,7 @@ use crate::{
         types::HashOutput,
     },
 };
+use std::ops::RangeInclusive;
 use tari_mmr::{Hash, MerkleCheckPoint, MerkleProof};
 
 // This is a test backend. This is used so that the ConsensusManager can be called without actually having a backend.
@@ -77,6 +78,14 @@ impl BlockchainBackend for MockBackend {
         unimplemented!()
     }
 
+    fn fetch_mmr_leaf_index(&self, _tree: MmrTree, _hash: &HashOutput) -> Result<Option<usize>, ChainStorageError> {
+        unimplemented!()
+    }
+
+    fn fetch_mmr_leaf_count(&self, _tree: MmrTree, _height: u64) -> Result<usize, ChainStorageError> {
+        unimplemented!()
+    }
+
     fn for_each_orphan<F>(&self, _f: F) -> Result<(), ChainStorageError>
     where
         Self: Sized,
@@ -97,7 +106,7 @@ impl BlockchainBackend for MockBackend {
         unimplemented!()
     }
 
-    fn for_each_header<F>(&self, _f: F) -> Result<(), ChainStorageError>
+    fn for_each_header<F>(&self, _range: RangeInclusive<u64>, _f: F) -> Result<(), ChainStorageError>
     where
         Self: Sized,
         F: FnMut(Result<(u64, BlockHeader), ChainStorageError>),
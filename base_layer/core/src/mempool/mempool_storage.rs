@@ -33,6 +33,7 @@ use crate::{
         MempoolConfig,
         StateResponse,
         StatsResponse,
+        TxRejectionReason,
         TxStorageResponse,
     },
     transactions::{transaction::Transaction, types::Signature},
@@ -102,7 +103,20 @@ where T: BlockchainBackend
                 self.pending_pool.insert(tx)?;
                 Ok(TxStorageResponse::PendingPool)
             },
-            _ => Ok(TxStorageResponse::NotStored),
+            Err(ValidationError::FeeTooLow) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Transaction fee is below the minimum fee per gram, not storing"
+                );
+                Ok(TxStorageResponse::Rejected(TxRejectionReason::FeeTooLow))
+            },
+            Err(ValidationError::TransactionError(_)) | Err(ValidationError::InvalidAccountingBalance) => {
+                Ok(TxStorageResponse::Rejected(TxRejectionReason::InvalidTransaction))
+            },
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Transaction failed validation, not storing: {}", e);
+                Ok(TxStorageResponse::Rejected(TxRejectionReason::ValidationFailed))
+            },
         }
     }
 
@@ -130,6 +130,8 @@ pub enum TxStorageResponse {
     PendingPool,
     ReorgPool,
     NotStored,
+    /// The transaction failed validation and was rejected by the mempool
+    Rejected(TxRejectionReason),
 }
 
 impl Display for TxStorageResponse {
@@ -140,7 +142,30 @@ impl Display for TxStorageResponse {
             TxStorageResponse::PendingPool => "Pending pool",
             TxStorageResponse::ReorgPool => "Reorg pool",
             TxStorageResponse::NotStored => "Not stored",
+            TxStorageResponse::Rejected(reason) => return write!(fmt, "Rejected ({})", reason),
         };
         fmt.write_str(&storage.to_string())
     }
 }
+
+/// The reason code returned to the submitter of a transaction that was rejected by the mempool
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxRejectionReason {
+    /// The transaction fee is below the minimum fee per gram accepted for relay
+    FeeTooLow,
+    /// The transaction is not internally consistent
+    InvalidTransaction,
+    /// The transaction failed validation for any other reason
+    ValidationFailed,
+}
+
+impl Display for TxRejectionReason {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let reason = match self {
+            TxRejectionReason::FeeTooLow => "Fee too low",
+            TxRejectionReason::InvalidTransaction => "Invalid transaction",
+            TxRejectionReason::ValidationFailed => "Validation failed",
+        };
+        fmt.write_str(reason)
+    }
+}
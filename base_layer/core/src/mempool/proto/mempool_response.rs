@@ -24,9 +24,11 @@ use super::mempool::mempool_service_response::Response as ProtoMempoolResponse;
 use crate::mempool::{
     proto::mempool::{
         MempoolServiceResponse as ProtoMempoolServiceResponse,
+        TxRejectionReason as ProtoTxRejectionReason,
         TxStorageResponse as ProtoTxStorageResponse,
     },
     service::{MempoolResponse, MempoolServiceResponse},
+    TxStorageResponse,
 };
 use std::convert::{TryFrom, TryInto};
 
@@ -43,6 +45,11 @@ impl TryInto<MempoolResponse> for ProtoMempoolResponse {
                     .ok_or_else(|| "Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
                 MempoolResponse::TxStorage(tx_storage_response.try_into()?)
             },
+            TxRejected(reason) => {
+                let reason = ProtoTxRejectionReason::from_i32(reason)
+                    .ok_or_else(|| "Invalid or unrecognised `TxRejectionReason` enum".to_string())?;
+                MempoolResponse::TxStorage(TxStorageResponse::Rejected(reason.try_into()?))
+            },
         };
         Ok(response)
     }
@@ -68,6 +75,10 @@ impl From<MempoolResponse> for ProtoMempoolResponse {
         match response {
             Stats(stats_response) => ProtoMempoolResponse::Stats(stats_response.into()),
             State(state_response) => ProtoMempoolResponse::State(state_response.into()),
+            TxStorage(TxStorageResponse::Rejected(reason)) => {
+                let reason: ProtoTxRejectionReason = reason.into();
+                ProtoMempoolResponse::TxRejected(reason.into())
+            },
             TxStorage(tx_storage_response) => {
                 let tx_storage_response: ProtoTxStorageResponse = tx_storage_response.into();
                 ProtoMempoolResponse::TxStorage(tx_storage_response.into())
@@ -20,7 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{proto::mempool::TxStorageResponse as ProtoTxStorageResponse, TxStorageResponse};
+use crate::mempool::{
+    proto::mempool::{TxRejectionReason as ProtoTxRejectionReason, TxStorageResponse as ProtoTxStorageResponse},
+    TxRejectionReason,
+    TxStorageResponse,
+};
 use std::convert::TryFrom;
 
 impl TryFrom<ProtoTxStorageResponse> for TxStorageResponse {
@@ -47,7 +51,33 @@ impl From<TxStorageResponse> for ProtoTxStorageResponse {
             OrphanPool => ProtoTxStorageResponse::OrphanPool,
             PendingPool => ProtoTxStorageResponse::PendingPool,
             ReorgPool => ProtoTxStorageResponse::ReorgPool,
-            NotStored => ProtoTxStorageResponse::NotStored,
+            // Rejections are sent as a `TxRejectionReason` so that the reason code reaches the submitter
+            NotStored | Rejected(_) => ProtoTxStorageResponse::NotStored,
+        }
+    }
+}
+
+impl TryFrom<ProtoTxRejectionReason> for TxRejectionReason {
+    type Error = String;
+
+    fn try_from(reason: ProtoTxRejectionReason) -> Result<Self, Self::Error> {
+        use ProtoTxRejectionReason::*;
+        Ok(match reason {
+            None => return Err("TxRejectionReason not provided".to_string()),
+            FeeTooLow => TxRejectionReason::FeeTooLow,
+            InvalidTransaction => TxRejectionReason::InvalidTransaction,
+            ValidationFailed => TxRejectionReason::ValidationFailed,
+        })
+    }
+}
+
+impl From<TxRejectionReason> for ProtoTxRejectionReason {
+    fn from(reason: TxRejectionReason) -> Self {
+        use TxRejectionReason::*;
+        match reason {
+            FeeTooLow => ProtoTxRejectionReason::FeeTooLow,
+            InvalidTransaction => ProtoTxRejectionReason::InvalidTransaction,
+            ValidationFailed => ProtoTxRejectionReason::ValidationFailed,
         }
     }
 }
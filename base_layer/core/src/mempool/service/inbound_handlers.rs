@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::comms_interface::BlockEvent,
+    base_node::{comms_interface::BlockEvent, MaintenanceModeHandle},
     chain_storage::{BlockAddResult, BlockchainBackend},
     mempool::{
         async_mempool,
@@ -45,6 +45,7 @@ where T: BlockchainBackend + 'static
 {
     mempool: Mempool<T>,
     outbound_nmi: OutboundMempoolServiceInterface,
+    maintenance_mode: MaintenanceModeHandle,
 }
 
 impl<T> MempoolInboundHandlers<T>
@@ -52,7 +53,18 @@ where T: BlockchainBackend + 'static
 {
     /// Construct the MempoolInboundHandlers.
     pub fn new(mempool: Mempool<T>, outbound_nmi: OutboundMempoolServiceInterface) -> Self {
-        Self { mempool, outbound_nmi }
+        Self {
+            mempool,
+            outbound_nmi,
+            maintenance_mode: MaintenanceModeHandle::default(),
+        }
+    }
+
+    /// Share the maintenance mode of the node with the handlers, which do not store or propagate transactions while it
+    /// is active.
+    pub fn with_maintenance_mode(mut self, maintenance_mode: MaintenanceModeHandle) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
     }
 
     /// Handle inbound Mempool service requests from remote nodes and local services.
@@ -107,6 +119,14 @@ where T: BlockchainBackend + 'static
     ) -> Result<TxStorageResponse, MempoolServiceError>
     {
         trace!(target: LOG_TARGET, "Transaction: {}.", tx);
+        if self.maintenance_mode.is_active() {
+            debug!(
+                target: LOG_TARGET,
+                "Transaction ({}) not stored because the node is in maintenance",
+                tx.body.kernels()[0].excess_sig.get_signature().to_hex()
+            );
+            return Ok(TxStorageResponse::NotStored);
+        }
         let tx_storage =
             async_mempool::has_tx_with_excess_sig(self.mempool.clone(), tx.body.kernels()[0].excess_sig.clone())
                 .await?;
@@ -125,6 +145,7 @@ where T: BlockchainBackend + 'static
                         TxStorageResponse::PendingPool => true,
                         TxStorageResponse::ReorgPool => false,
                         TxStorageResponse::NotStored => false,
+                        TxStorageResponse::Rejected(_) => false,
                     };
                     if propagate {
                         debug!(
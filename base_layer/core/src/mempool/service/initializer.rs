@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::comms_interface::LocalNodeCommsInterface,
+    base_node::{comms_interface::LocalNodeCommsInterface, MaintenanceModeHandle},
     chain_storage::BlockchainBackend,
     mempool::{
         mempool::Mempool,
@@ -30,7 +30,7 @@ use crate::{
             inbound_handlers::MempoolInboundHandlers,
             local_service::LocalMempoolService,
             outbound_interface::OutboundMempoolServiceInterface,
-            service::{MempoolService, MempoolStreams},
+            service::{MempoolService, MempoolStreams, SERVICE_NAME},
         },
         MempoolServiceConfig,
     },
@@ -50,6 +50,7 @@ use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -65,6 +66,7 @@ where T: BlockchainBackend
     inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
     mempool: Mempool<T>,
     config: MempoolServiceConfig,
+    maintenance_mode: MaintenanceModeHandle,
 }
 
 impl<T> MempoolServiceInitializer<T>
@@ -81,9 +83,17 @@ where T: BlockchainBackend
             inbound_message_subscription_factory,
             mempool,
             config,
+            maintenance_mode: MaintenanceModeHandle::default(),
         }
     }
 
+    /// Share the maintenance mode of the node with the Mempool service, which does not accept transactions while it is
+    /// active.
+    pub fn with_maintenance_mode(mut self, maintenance_mode: MaintenanceModeHandle) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
+    }
+
     /// Get a stream for inbound Mempool service request messages
     fn inbound_request_stream(&self) -> impl Stream<Item = DomainMessage<proto::MempoolServiceRequest>> {
         self.inbound_message_subscription_factory
@@ -164,7 +174,8 @@ where T: BlockchainBackend + 'static
         let local_mp_interface = LocalMempoolService::new(local_request_sender_service);
         let config = self.config;
         let mempool = self.mempool.clone();
-        let inbound_handlers = MempoolInboundHandlers::new(mempool, outbound_mp_interface.clone());
+        let inbound_handlers = MempoolInboundHandlers::new(mempool, outbound_mp_interface.clone())
+            .with_maintenance_mode(self.maintenance_mode.clone());
 
         // Register handle to OutboundMempoolServiceInterface before waiting for handles to be ready
         handles_fut.register(outbound_mp_interface);
@@ -190,7 +201,13 @@ where T: BlockchainBackend + 'static
                 local_request_stream,
                 base_node.get_block_event_stream(),
             );
-            let service = MempoolService::new(outbound_message_service, inbound_handlers, config).start(streams);
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
+            let service = MempoolService::new(outbound_message_service, inbound_handlers, config)
+                .with_monitor(monitor)
+                .start(streams);
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
             info!(target: LOG_TARGET, "Mempool Service shutdown");
@@ -20,10 +20,14 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{
-    service::{MempoolRequest, MempoolResponse, MempoolServiceError},
-    StateResponse,
-    StatsResponse,
+use crate::{
+    mempool::{
+        service::{MempoolRequest, MempoolResponse, MempoolServiceError},
+        StateResponse,
+        StatsResponse,
+        TxStorageResponse,
+    },
+    transactions::transaction::Transaction,
 };
 use tari_service_framework::reply_channel::{Receiver, SenderService};
 use tower_service::Service;
@@ -68,18 +72,36 @@ impl LocalMempoolService {
             _ => Err(MempoolServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Submit a transaction to the mempool, which propagates it to the network if it is accepted. Resolves to where
+    /// the mempool stored the transaction, or why it was rejected.
+    pub async fn submit_transaction(&mut self, tx: Transaction) -> Result<TxStorageResponse, MempoolServiceError> {
+        match self
+            .request_sender
+            .call(MempoolRequest::SubmitTransaction(tx))
+            .await??
+        {
+            MempoolResponse::TxStorage(s) => Ok(s),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::mempool::{
-        service::{
-            local_service::{LocalMempoolRequestStream, LocalMempoolService},
-            MempoolRequest,
-            MempoolResponse,
+    use crate::{
+        mempool::{
+            service::{
+                local_service::{LocalMempoolRequestStream, LocalMempoolService},
+                MempoolRequest,
+                MempoolResponse,
+            },
+            MempoolServiceError,
+            StatsResponse,
+            TxRejectionReason,
+            TxStorageResponse,
         },
-        MempoolServiceError,
-        StatsResponse,
+        transactions::{transaction::Transaction, types::BlindingFactor},
     };
     use futures::StreamExt;
     use tari_service_framework::reply_channel::unbounded;
@@ -101,6 +123,9 @@ mod test {
             let (req, reply_channel) = req.split();
             let res = match req {
                 MempoolRequest::GetStats => Ok(MempoolResponse::Stats(request_stats())),
+                MempoolRequest::SubmitTransaction(_) => Ok(MempoolResponse::TxStorage(TxStorageResponse::Rejected(
+                    TxRejectionReason::FeeTooLow,
+                ))),
                 _ => Err(MempoolServiceError::UnexpectedApiResponse),
             };
             reply_channel.send(res).unwrap();
@@ -130,4 +155,17 @@ mod test {
         let stats = stats.expect("get_mempool_stats should have succeeded");
         assert_eq!(stats, request_stats());
     }
+
+    #[tokio_macros::test]
+    async fn submit_transaction_rejected() {
+        let (tx, rx) = unbounded();
+        let mut service = LocalMempoolService::new(tx);
+        task::spawn(mock_handler(rx));
+        let transaction = Transaction::new(vec![], vec![], vec![], BlindingFactor::default());
+        let tx_storage = service
+            .submit_transaction(transaction)
+            .await
+            .expect("submit_transaction should have succeeded");
+        assert_eq!(tx_storage, TxStorageResponse::Rejected(TxRejectionReason::FeeTooLow));
+    }
 }
@@ -100,4 +100,23 @@ impl OutboundMempoolServiceInterface {
             Err(MempoolServiceError::UnexpectedApiResponse)
         }
     }
+
+    /// Submit a transaction to the mempool of a remote base node. The remote mempool validates the transaction before
+    /// responding with the pool it was stored in, or with the reason it was rejected.
+    pub async fn submit_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<TxStorageResponse, MempoolServiceError>
+    {
+        if let MempoolResponse::TxStorage(tx_storage_response) = self
+            .request_sender
+            .call(MempoolRequest::SubmitTransaction(transaction))
+            .await??
+        {
+            trace!(target: LOG_TARGET, "Transaction submitted: {}", tx_storage_response);
+            Ok(tx_storage_response)
+        } else {
+            Err(MempoolServiceError::UnexpectedApiResponse)
+        }
+    }
 }
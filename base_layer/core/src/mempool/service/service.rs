@@ -57,10 +57,15 @@ use tari_comms_dht::{
 };
 use tari_crypto::{ristretto::RistrettoPublicKey, tari_utilities::hex::Hex};
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
-use tari_service_framework::RequestContext;
+use tari_service_framework::{
+    watchdog::{ServiceMonitor, Watchdog},
+    RequestContext,
+};
 use tokio::task;
 
 const LOG_TARGET: &str = "c::mempool::service::service";
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Mempool Service";
 
 /// A convenience struct to hold all the Mempool service streams
 pub struct MempoolStreams<SOutReq, SInReq, SInRes, STxIn, SLocalReq> {
@@ -112,6 +117,7 @@ pub struct MempoolService<B: BlockchainBackend + 'static> {
     timeout_sender: Sender<RequestKey>,
     timeout_receiver_stream: Option<Receiver<RequestKey>>,
     config: MempoolServiceConfig,
+    monitor: ServiceMonitor,
 }
 
 impl<B> MempoolService<B>
@@ -131,9 +137,16 @@ where B: BlockchainBackend + 'static
             timeout_sender,
             timeout_receiver_stream: Some(timeout_receiver),
             config,
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Report the requests and transactions that are being handled to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     pub async fn start<SOutReq, SInReq, SInRes, STxIn, SLocalReq>(
         mut self,
         streams: MempoolStreams<SOutReq, SInReq, SInRes, STxIn, SLocalReq>,
@@ -225,7 +238,9 @@ where B: BlockchainBackend + 'static
         let waiting_requests = self.waiting_requests.clone();
         let timeout_sender = self.timeout_sender.clone();
         let config = self.config;
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("outbound request");
             let (request, reply_tx) = request_context.split();
             let _ = handle_outbound_request(
                 outbound_message_service,
@@ -248,7 +263,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_outbound_tx(&self, tx_context: (Transaction, Vec<RistrettoPublicKey>)) {
         let outbound_message_service = self.outbound_message_service.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("outbound transaction");
             let (tx, excluded_peers) = tx_context;
             let _ = handle_outbound_tx(outbound_message_service, tx, excluded_peers)
                 .await
@@ -262,7 +279,9 @@ where B: BlockchainBackend + 'static
     fn spawn_handle_incoming_request(&self, domain_msg: DomainMessage<proto::mempool::MempoolServiceRequest>) {
         let inbound_handlers = self.inbound_handlers.clone();
         let outbound_message_service = self.outbound_message_service.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("incoming request");
             let _ = handle_incoming_request(inbound_handlers, outbound_message_service, domain_msg)
                 .await
                 .or_else(|err| {
@@ -277,7 +296,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_incoming_response(&self, domain_msg: DomainMessage<proto::mempool::MempoolServiceResponse>) {
         let waiting_requests = self.waiting_requests.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("incoming response");
             let _ = handle_incoming_response(waiting_requests, domain_msg.into_inner())
                 .await
                 .or_else(|err| {
@@ -292,7 +313,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_incoming_tx(&self, tx_msg: DomainMessage<Transaction>) {
         let inbound_handlers = self.inbound_handlers.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("incoming transaction");
             let _ = handle_incoming_tx(inbound_handlers, tx_msg).await.or_else(|err| {
                 error!(
                     target: LOG_TARGET,
@@ -309,7 +332,9 @@ where B: BlockchainBackend + 'static
     )
     {
         let mut inbound_handlers = self.inbound_handlers.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("local request");
             let (request, reply_tx) = request_context.split();
             let _ = reply_tx
                 .send(inbound_handlers.handle_request(&request).await)
@@ -325,7 +350,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_block_event(&self, block_event: Arc<BlockEvent>) {
         let inbound_handlers = self.inbound_handlers.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("block event");
             let _ = handle_block_event(inbound_handlers, &block_event).await.or_else(|err| {
                 error!(target: LOG_TARGET, "Failed to handle base node block event: {:?}", err);
                 Err(err)
@@ -335,7 +362,9 @@ where B: BlockchainBackend + 'static
 
     fn spawn_handle_request_timeout(&self, timeout_request_key: u64) {
         let waiting_requests = self.waiting_requests.clone();
+        let monitor = self.monitor.clone();
         task::spawn(async move {
+            let _activity = monitor.enter("request timeout");
             let _ = handle_request_timeout(waiting_requests, timeout_request_key)
                 .await
                 .or_else(|err| {
@@ -266,6 +266,11 @@ impl Miner {
                             start_mining = false;
                             wait_for_miner = true;
                         },
+                        ReorgRefused(_) => {
+                            info!(target: LOG_TARGET, "A deep chain reorg was refused. Pausing miner");
+                            start_mining = false;
+                            wait_for_miner = true;
+                        },
                         _ => {wait_for_miner = true;},
                     }
                 },
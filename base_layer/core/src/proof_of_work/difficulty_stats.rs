@@ -0,0 +1,166 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::blockheader::BlockHeader,
+    proof_of_work::{
+        error::DifficultyAdjustmentError,
+        Difficulty,
+        PowAlgorithm,
+        ProofOfWork,
+        TargetDifficultyCalculator,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use tari_crypto::tari_utilities::epoch_time::EpochTime;
+
+/// The difficulty statistics of a single block, which explorers use to chart the difficulty adjustment
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockDifficultyStats {
+    pub height: u64,
+    pub pow_algo: PowAlgorithm,
+    pub timestamp: EpochTime,
+    /// The seconds since the previous block mined with the same PoW algorithm, limited to the maximum block interval
+    /// as in the difficulty adjustment. It is zero for the first block of the algorithm.
+    pub solve_time: u64,
+    /// The difficulty the block had to achieve, as calculated by the LWMA over the preceding blocks
+    pub target_difficulty: Difficulty,
+    /// The difficulty actually achieved by the proof of work of the block
+    pub achieved_difficulty: Difficulty,
+    /// The exponential moving average of the hash rate of the PoW algorithm, in hashes per second
+    pub estimated_hash_rate: u64,
+}
+
+// The running state of a single PoW algorithm
+#[derive(Default)]
+struct AlgorithmStats {
+    last_timestamp: Option<EpochTime>,
+    hash_rate: Option<f64>,
+}
+
+/// Accumulates block headers, in ascending height order, to calculate the difficulty statistics of each block. The
+/// target difficulty is the one the LWMA yields before the block is added, so that the statistics match the
+/// difficulty adjustment of the chain.
+pub struct DifficultyStatsCalculator {
+    target_difficulty: TargetDifficultyCalculator,
+    max_block_time: u64,
+    // The smoothing factor of the hash rate moving average, derived from the difficulty block window
+    smoothing: f64,
+    monero: AlgorithmStats,
+    blake: AlgorithmStats,
+}
+
+impl DifficultyStatsCalculator {
+    pub fn new(block_window: usize, target_time: u64, max_block_time: u64, min_pow_difficulty: Difficulty) -> Self {
+        Self {
+            target_difficulty: TargetDifficultyCalculator::new(
+                block_window,
+                target_time,
+                max_block_time,
+                min_pow_difficulty,
+            ),
+            max_block_time,
+            smoothing: 2.0 / (block_window as f64 + 1.0),
+            monero: AlgorithmStats::default(),
+            blake: AlgorithmStats::default(),
+        }
+    }
+
+    /// Add the next header of the chain and return its difficulty statistics.
+    pub fn add_header(&mut self, header: &BlockHeader) -> Result<BlockDifficultyStats, DifficultyAdjustmentError> {
+        let pow_algo = header.pow.pow_algo;
+        let target_difficulty = self.target_difficulty.get_difficulty(pow_algo);
+        self.target_difficulty.add_header(header)?;
+
+        let max_block_time = self.max_block_time;
+        let smoothing = self.smoothing;
+        let stats = match pow_algo {
+            PowAlgorithm::Monero => &mut self.monero,
+            PowAlgorithm::Blake => &mut self.blake,
+        };
+        let solve_time = match stats.last_timestamp {
+            Some(last) if header.timestamp > last => cmp::min((header.timestamp - last).as_u64(), max_block_time),
+            _ => 0,
+        };
+        stats.last_timestamp = Some(header.timestamp);
+        // The difficulty is the expected number of hashes needed to solve the block. A solve time of zero carries no
+        // information about the hash rate, so it does not move the average.
+        if solve_time > 0 {
+            let hash_rate = target_difficulty.as_u64() as f64 / solve_time as f64;
+            stats.hash_rate = Some(match stats.hash_rate {
+                Some(average) => average + smoothing * (hash_rate - average),
+                None => hash_rate,
+            });
+        }
+
+        Ok(BlockDifficultyStats {
+            height: header.height,
+            pow_algo,
+            timestamp: header.timestamp,
+            solve_time,
+            target_difficulty,
+            achieved_difficulty: ProofOfWork::achieved_difficulty(header),
+            estimated_hash_rate: stats.hash_rate.unwrap_or(0.0).round() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn difficulty_stats() {
+        let mut calculator = DifficultyStatsCalculator::new(5, 60, 360, 6000.into());
+        let mut header = BlockHeader::new(0);
+        header.pow.pow_algo = PowAlgorithm::Blake;
+        header.timestamp = 60.into();
+        let first = calculator.add_header(&header).unwrap();
+        assert_eq!(first.solve_time, 0);
+        assert_eq!(first.target_difficulty, 6000.into());
+        assert_eq!(first.estimated_hash_rate, 0);
+
+        header.height = 1;
+        header.timestamp = 120.into();
+        let second = calculator.add_header(&header).unwrap();
+        assert_eq!(second.height, 1);
+        assert_eq!(second.solve_time, 60);
+        assert_eq!(second.target_difficulty, 6000.into());
+        assert_eq!(second.estimated_hash_rate, 100);
+
+        // A Monero block does not affect the solve time of the Blake blocks
+        header.height = 2;
+        header.timestamp = 150.into();
+        header.pow.pow_algo = PowAlgorithm::Monero;
+        assert_eq!(calculator.add_header(&header).unwrap().solve_time, 0);
+
+        // Solve times are limited to the maximum block interval and the hash rate is smoothed over the block window
+        header.height = 3;
+        header.timestamp = 1_000.into();
+        header.pow.pow_algo = PowAlgorithm::Blake;
+        let fourth = calculator.add_header(&header).unwrap();
+        assert_eq!(fourth.solve_time, 360);
+        assert_eq!(fourth.target_difficulty, 6000.into());
+        assert_eq!(fourth.estimated_hash_rate, 72);
+    }
+}
@@ -28,6 +28,8 @@ pub enum PowError {
     InvalidProofOfWork,
     // Target difficulty not achieved
     AchievedDifficultyTooLow,
+    // Merge mined proof of work cannot be verified without the merge_mining feature
+    MergeMiningNotSupported,
 }
 
 #[derive(Debug, Error, Clone, PartialEq)]
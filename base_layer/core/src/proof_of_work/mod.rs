@@ -22,8 +22,10 @@
 
 mod blake_pow;
 mod difficulty;
+mod difficulty_stats;
 mod error;
 mod median_timestamp;
+#[cfg(feature = "merge_mining")]
 #[allow(clippy::enum_variant_names)]
 mod monero_rx;
 #[allow(clippy::module_inception)]
@@ -37,8 +39,10 @@ pub mod lwma_diff;
 
 pub use blake_pow::{blake_difficulty, blake_difficulty_with_hash};
 pub use difficulty::{Difficulty, DifficultyAdjustment};
+pub use difficulty_stats::{BlockDifficultyStats, DifficultyStatsCalculator};
 pub use error::{DifficultyAdjustmentError, PowError};
 pub use median_timestamp::get_median_timestamp;
+#[cfg(feature = "merge_mining")]
 pub use monero_rx::monero_difficulty;
 pub use proof_of_work::{PowAlgorithm, ProofOfWork};
-pub use target_difficulty::get_target_difficulty;
+pub use target_difficulty::{get_target_difficulty, TargetDifficultyCalculator};
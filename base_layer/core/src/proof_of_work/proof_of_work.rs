@@ -20,9 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "merge_mining")]
+use crate::proof_of_work::monero_rx::monero_difficulty;
 use crate::{
     blocks::BlockHeader,
-    proof_of_work::{blake_pow::blake_difficulty, monero_rx::monero_difficulty, Difficulty},
+    proof_of_work::{blake_pow::blake_difficulty, Difficulty},
 };
 use bytes::{self, BufMut};
 use serde::{Deserialize, Serialize};
@@ -111,10 +113,14 @@ impl ProofOfWork {
     /// (as a u256)
     ///
     /// If there are any problems with calculating a difficulty (e.g. an invalid header), then the function returns a
-    /// difficulty of one.
+    /// difficulty of one. The same applies to merge mined headers when the `merge_mining` feature is disabled; block
+    /// validation rejects those headers with `PowError::MergeMiningNotSupported`.
     pub fn achieved_difficulty(header: &BlockHeader) -> Difficulty {
         match header.pow.pow_algo {
+            #[cfg(feature = "merge_mining")]
             PowAlgorithm::Monero => monero_difficulty(header),
+            #[cfg(not(feature = "merge_mining"))]
+            PowAlgorithm::Monero => 1.into(),
             PowAlgorithm::Blake => blake_difficulty(header),
         }
     }
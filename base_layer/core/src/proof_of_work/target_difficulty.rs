@@ -45,31 +45,63 @@ pub fn get_target_difficulty(
     min_pow_difficulty: Difficulty,
 ) -> Result<Difficulty, DifficultyAdjustmentError>
 {
-    let height = headers.last().expect("Header set should not be empty").height;
-    debug!(target: LOG_TARGET, "Calculating target difficulty to height:{}", height);
-    let mut monero_lwma =
-        LinearWeightedMovingAverage::new(block_window, target_time, min_pow_difficulty, max_block_time);
-    let mut blake_lwma =
-        LinearWeightedMovingAverage::new(block_window, target_time, min_pow_difficulty, max_block_time);
+    let mut calculator = TargetDifficultyCalculator::new(block_window, target_time, max_block_time, min_pow_difficulty);
+    for header in &headers {
+        calculator.add_header(header)?;
+    }
+    Ok(calculator.get_difficulty(pow_algo))
+}
+
+/// Accumulates block headers, in ascending height order, to estimate the target difficulty of each PoW algorithm
+/// without requiring the whole header set to be held in memory.
+pub struct TargetDifficultyCalculator {
+    monero_lwma: LinearWeightedMovingAverage,
+    blake_lwma: LinearWeightedMovingAverage,
+    min_pow_difficulty: Difficulty,
+    height: Option<u64>,
+}
 
-    // TODO: Store the target difficulty so that we don't have to calculate it for the whole chain
-    for header in headers {
+impl TargetDifficultyCalculator {
+    pub fn new(block_window: usize, target_time: u64, max_block_time: u64, min_pow_difficulty: Difficulty) -> Self {
+        Self {
+            monero_lwma: LinearWeightedMovingAverage::new(
+                block_window,
+                target_time,
+                min_pow_difficulty,
+                max_block_time,
+            ),
+            blake_lwma: LinearWeightedMovingAverage::new(block_window, target_time, min_pow_difficulty, max_block_time),
+            min_pow_difficulty,
+            height: None,
+        }
+    }
+
+    /// Add the next header of the chain to the difficulty calculation.
+    pub fn add_header(&mut self, header: &BlockHeader) -> Result<(), DifficultyAdjustmentError> {
+        // TODO: Store the target difficulty so that we don't have to calculate it for the whole chain
         match header.pow.pow_algo {
-            PowAlgorithm::Monero => monero_lwma.add(header.timestamp, monero_lwma.get_difficulty())?,
-            PowAlgorithm::Blake => blake_lwma.add(
+            PowAlgorithm::Monero => self
+                .monero_lwma
+                .add(header.timestamp, self.monero_lwma.get_difficulty())?,
+            PowAlgorithm::Blake => self.blake_lwma.add(
                 header.timestamp,
-                cmp::max(min_pow_difficulty, blake_lwma.get_difficulty()),
+                cmp::max(self.min_pow_difficulty, self.blake_lwma.get_difficulty()),
             )?,
         }
+        self.height = Some(header.height);
+        Ok(())
     }
 
-    let target_difficulty = match pow_algo {
-        PowAlgorithm::Monero => monero_lwma.get_difficulty(),
-        PowAlgorithm::Blake => cmp::max(min_pow_difficulty, blake_lwma.get_difficulty()),
-    };
-    debug!(
-        target: LOG_TARGET,
-        "Target difficulty:{} at height:{} for PoW:{}", target_difficulty, height, pow_algo
-    );
-    Ok(target_difficulty)
+    /// Returns the estimated target difficulty for the specified PoW algorithm given the headers added so far.
+    pub fn get_difficulty(&self, pow_algo: PowAlgorithm) -> Difficulty {
+        let target_difficulty = match pow_algo {
+            PowAlgorithm::Monero => self.monero_lwma.get_difficulty(),
+            PowAlgorithm::Blake => cmp::max(self.min_pow_difficulty, self.blake_lwma.get_difficulty()),
+        };
+        debug!(
+            target: LOG_TARGET,
+            "Target difficulty:{} at height:{:?} for PoW:{}", target_difficulty, self.height, pow_algo
+        );
+        target_difficulty
+    }
 }
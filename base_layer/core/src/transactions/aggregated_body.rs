@@ -193,8 +193,25 @@ impl AggregateBody {
         fee
     }
 
+    /// Returns the total value burned by the burn kernels in this body
+    pub fn get_total_burned(&self) -> MicroTari {
+        self.kernels.iter().map(|k| k.burned_value).sum()
+    }
+
+    /// Verify that value is only burned by burn kernels, and that every burn kernel burns a non-zero value.
+    pub fn verify_kernel_burned_values(&self) -> Result<(), TransactionError> {
+        for kernel in self.kernels.iter() {
+            kernel.verify_burned_value().or_else(|e| {
+                warn!(target: LOG_TARGET, "Kernel ({}) burned value invalid {:?}.", kernel, e);
+                Err(e)
+            })?;
+        }
+        Ok(())
+    }
+
     /// Validate this transaction by checking the following:
-    /// 1. The sum of inputs, outputs and fees equal the (public excess value + offset)
+    /// 1. The sum of inputs, outputs, fees and burned value equal the (public excess value + offset)
+    /// 1. Only burn kernels burn value
     /// 1. The signature signs the canonical message with the private excess
     /// 1. Range proofs of the outputs are valid
     ///
@@ -206,12 +223,27 @@ impl AggregateBody {
         reward: MicroTari,
         factories: &CryptoFactories,
     ) -> Result<(), TransactionError>
+    {
+        self.validate_internal_consistency_with_min_output_value(offset, reward, MicroTari(0), factories)
+    }
+
+    /// Validate this body like `validate_internal_consistency`, and also check that the range proof of every output
+    /// that is not a coinbase output proves a value of at least `min_output_value`. The value of a coinbase output is
+    /// determined by the block reward, so it is exempt.
+    pub fn validate_internal_consistency_with_min_output_value(
+        &self,
+        offset: &BlindingFactor,
+        reward: MicroTari,
+        min_output_value: MicroTari,
+        factories: &CryptoFactories,
+    ) -> Result<(), TransactionError>
     {
         let total_offset = factories.commitment.commit_value(&offset, reward.0);
 
         self.verify_kernel_signatures()?;
+        self.verify_kernel_burned_values()?;
         self.validate_kernel_sum(total_offset, &factories.commitment)?;
-        self.validate_range_proofs(&factories.range_proof)
+        self.validate_range_proofs(&factories.range_proof, min_output_value)
     }
 
     pub fn dissolve(self) -> (Vec<TransactionInput>, Vec<TransactionOutput>, Vec<TransactionKernel>) {
@@ -226,16 +258,19 @@ impl AggregateBody {
         &(sum_outputs - sum_inputs) + &fee_commitment
     }
 
-    /// Calculate the sum of the kernels, taking into account the provided offset, and their constituent fees
+    /// Calculate the sum of the kernels, taking into account the provided offset, and their constituent fees and
+    /// burned value
     fn sum_kernels(&self, offset: PedersenCommitment) -> KernelSum {
-        // Sum all kernel excesses and fees
+        // Sum all kernel excesses, fees and burned value
         self.kernels.iter().fold(
             KernelSum {
                 fees: MicroTari(0),
+                burned: MicroTari(0),
                 sum: offset,
             },
             |acc, val| KernelSum {
                 fees: acc.fees + val.fee,
+                burned: acc.burned + val.burned_value,
                 sum: &acc.sum + &val.excess,
             },
         )
@@ -245,21 +280,32 @@ impl AggregateBody {
     fn validate_kernel_sum(&self, offset: Commitment, factory: &CommitmentFactory) -> Result<(), TransactionError> {
         trace!(target: LOG_TARGET, "Checking kernel total");
         let kernel_sum = self.sum_kernels(offset);
-        let sum_io = self.sum_commitments(kernel_sum.fees.into(), factory);
+        // Burned value has no corresponding output, so it is accounted for in the same way as the fees
+        let sum_io = self.sum_commitments((kernel_sum.fees + kernel_sum.burned).into(), factory);
 
         if kernel_sum.sum != sum_io {
             return Err(TransactionError::ValidationError(
-                "Sum of inputs and outputs did not equal sum of kernels with fees".into(),
+                "Sum of inputs and outputs did not equal sum of kernels with fees and burned value".into(),
             ));
         }
 
         Ok(())
     }
 
-    fn validate_range_proofs(&self, range_proof_service: &RangeProofService) -> Result<(), TransactionError> {
+    fn validate_range_proofs(
+        &self,
+        range_proof_service: &RangeProofService,
+        min_output_value: MicroTari,
+    ) -> Result<(), TransactionError>
+    {
         trace!(target: LOG_TARGET, "Checking range proofs");
         for o in &self.outputs {
-            if !o.verify_range_proof(&range_proof_service)? {
+            let min_value = if o.features.flags.contains(OutputFlags::COINBASE_OUTPUT) {
+                MicroTari(0)
+            } else {
+                min_output_value
+            };
+            if !o.verify_range_proof_with_min_value(&range_proof_service, min_value)? {
                 return Err(TransactionError::ValidationError(
                     "Range proof could not be verified".into(),
                 ));
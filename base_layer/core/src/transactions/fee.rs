@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{tari_amount::*, transaction::MINIMUM_TRANSACTION_FEE};
+use std::fmt::Debug;
 
 pub struct Fee {}
 
@@ -58,3 +59,35 @@ impl Fee {
             WEIGHT_PER_OUTPUT * num_outputs as u64
     }
 }
+
+/// Calculates the absolute fee of a transaction from its fee-per-gram and size. Transaction builders and UTXO
+/// selection take a fee model rather than calling `Fee::calculate` directly so that alternative fee rules can be
+/// tried out without rewriting them.
+pub trait FeeModel: Debug + Send + Sync {
+    /// Computes the absolute transaction fee given the fee-per-gram, and the size of the transaction
+    fn calculate(
+        &self,
+        fee_per_gram: MicroTari,
+        num_kernels: usize,
+        num_inputs: usize,
+        num_outputs: usize,
+    ) -> MicroTari;
+}
+
+/// The fee model of the current consensus rules, where the fee is the fee-per-gram times the transaction weight. See
+/// `Fee::calculate`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WeightedFeeModel;
+
+impl FeeModel for WeightedFeeModel {
+    fn calculate(
+        &self,
+        fee_per_gram: MicroTari,
+        num_kernels: usize,
+        num_inputs: usize,
+        num_outputs: usize,
+    ) -> MicroTari
+    {
+        Fee::calculate(fee_per_gram, num_kernels, num_inputs, num_outputs)
+    }
+}
@@ -113,6 +113,7 @@ pub fn create_random_signature(fee: MicroTari, lock_height: u64) -> (PublicKey,
         lock_height,
         meta_info: None,
         linked_kernel: None,
+        burned_value: MicroTari(0),
     };
     let e = build_challenge(&PublicKey::from_secret_key(&r), &tx_meta);
     (p, Signature::sign(k, r, &e).unwrap())
@@ -133,6 +134,7 @@ pub fn create_random_signature_from_s_key(
         lock_height,
         meta_info: None,
         linked_kernel: None,
+        burned_value: MicroTari(0),
     };
     let e = build_challenge(&PublicKey::from_secret_key(&r), &tx_meta);
     (p, Signature::sign(s_key, r, &e).unwrap())
@@ -179,7 +181,8 @@ macro_rules! txn_schema {
             to: $outputs.clone(),
             fee: $fee,
             lock_height: $lock,
-            features: $features
+            features: $features,
+            min_output_value: $crate::transactions::tari_amount::MicroTari(0),
         }
     }};
 
@@ -212,6 +215,8 @@ pub struct TransactionSchema {
     pub fee: MicroTari,
     pub lock_height: u64,
     pub features: OutputFeatures,
+    /// The consensus minimum output value that the range proofs of the outputs are constructed for
+    pub min_output_value: MicroTari,
 }
 
 /// Create a random transaction input for the given amount and maturity period. The input and its unblinded
@@ -302,6 +307,7 @@ pub fn spend_utxos(schema: TransactionSchema) -> (Transaction, Vec<UnblindedOutp
     stx_builder
         .with_lock_height(schema.lock_height)
         .with_fee_per_gram(schema.fee)
+        .with_min_output_value(schema.min_output_value)
         .with_offset(test_params.offset.clone())
         .with_private_nonce(test_params.nonce.clone())
         .with_change_secret(test_params.change_key.clone());
@@ -39,6 +39,7 @@ use crate::transactions::{
     },
     types::{BlindingFactor, Commitment},
 };
+use prost::Message;
 use std::convert::{TryFrom, TryInto};
 use tari_crypto::tari_utilities::{ByteArray, ByteArrayError};
 
@@ -71,6 +72,7 @@ impl TryFrom<proto::TransactionKernel> for TransactionKernel {
             linked_kernel: kernel.linked_kernel.map(Into::into),
             lock_height: kernel.lock_height,
             meta_info: kernel.meta_info.map(Into::into),
+            burned_value: MicroTari::from(kernel.burned_value),
         })
     }
 }
@@ -85,6 +87,7 @@ impl From<TransactionKernel> for proto::TransactionKernel {
             linked_kernel: kernel.linked_kernel.map(Into::into),
             lock_height: kernel.lock_height,
             meta_info: kernel.meta_info.map(Into::into),
+            burned_value: kernel.burned_value.into(),
         }
     }
 }
@@ -231,3 +234,21 @@ impl From<Transaction> for proto::Transaction {
         }
     }
 }
+
+impl Transaction {
+    /// Encode the transaction in its canonical binary form, which is the protobuf encoding used to send transactions
+    /// between nodes. This allows a transaction to be exported and submitted to a base node by other means.
+    pub fn to_raw_bytes(&self) -> Vec<u8> {
+        let tx = proto::Transaction::from(self.clone());
+        let mut buf = Vec::with_capacity(tx.encoded_len());
+        // Encoding into a Vec can only fail if there is insufficient capacity, which a Vec will grow to provide
+        let _ = tx.encode(&mut buf);
+        buf
+    }
+
+    /// Decode a transaction from the binary form produced by `to_raw_bytes`
+    pub fn from_raw_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let tx = proto::Transaction::decode(bytes).map_err(|e| e.to_string())?;
+        Transaction::try_from(tx)
+    }
+}
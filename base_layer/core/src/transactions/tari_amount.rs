@@ -25,10 +25,28 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
 
 use std::{
+    convert::TryFrom,
     iter::Sum,
     ops::{Add, Mul},
+    str::FromStr,
 };
 use tari_crypto::ristretto::RistrettoSecretKey;
+use thiserror::Error as ThisError;
+
+/// The amount of MicroTari in a single Tari
+const MICRO_TARI_PER_TARI: u64 = 1_000_000;
+
+#[derive(Debug, Clone, ThisError, PartialEq)]
+pub enum MicroTariError {
+    #[error("The Tari amount is negative or not a finite number")]
+    InvalidAmount,
+    #[error("The Tari amount has more than 6 decimal places and cannot be represented in MicroTari")]
+    ExcessPrecision,
+    #[error("The amount is too large to be represented in MicroTari")]
+    Overflow,
+    #[error("Could not parse the amount: {0}")]
+    ParseError(String),
+}
 
 /// All calculations using Tari amounts should use these newtypes to prevent bugs related to rounding errors, unit
 /// conversion errors etc.
@@ -77,9 +95,29 @@ impl MicroTari {
         }
         None
     }
+
+    pub fn checked_add(self, v: MicroTari) -> Option<MicroTari> {
+        self.0.checked_add(v.0).map(MicroTari)
+    }
+
+    pub fn checked_mul(self, v: u64) -> Option<MicroTari> {
+        self.0.checked_mul(v).map(MicroTari)
+    }
+
+    /// Creates a MicroTari value from a whole number of Tari, failing if the result would overflow.
+    pub fn checked_from_tari(tari: u64) -> Result<MicroTari, MicroTariError> {
+        tari.checked_mul(MICRO_TARI_PER_TARI)
+            .map(MicroTari)
+            .ok_or(MicroTariError::Overflow)
+    }
+
+    /// Returns the value of this amount in MicroTari. Prefer this over accessing the inner value directly at API
+    /// boundaries so that the denomination is explicit.
+    pub fn as_micro_tari(self) -> u64 {
+        self.0
+    }
 }
 
-#[allow(clippy::identity_op)]
 impl Display for MicroTari {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         if *self < 1 * T {
@@ -90,6 +128,63 @@ impl Display for MicroTari {
     }
 }
 
+/// Parses an amount with an explicit denomination, e.g. `1.5 T`, `1.5T`, `1500000 uT` or `1500000 µT`. An amount
+/// without a denomination is interpreted as MicroTari.
+impl FromStr for MicroTari {
+    type Err = MicroTariError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(value) = strip_suffix(s, "µT").or_else(|| strip_suffix(s, "uT")) {
+            parse_micro_tari(value)
+        } else if let Some(value) = strip_suffix(s, "T") {
+            parse_tari(value)
+        } else {
+            parse_micro_tari(s)
+        }
+    }
+}
+
+fn strip_suffix<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    if s.ends_with(suffix) {
+        Some(s[..s.len() - suffix.len()].trim_end())
+    } else {
+        None
+    }
+}
+
+fn parse_micro_tari(value: &str) -> Result<MicroTari, MicroTariError> {
+    value
+        .parse::<u64>()
+        .map(MicroTari)
+        .map_err(|e| MicroTariError::ParseError(e.to_string()))
+}
+
+/// Parses a decimal Tari value into MicroTari without going through a floating point representation.
+fn parse_tari(value: &str) -> Result<MicroTari, MicroTariError> {
+    let mut parts = value.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default().trim_end_matches('0');
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(MicroTariError::ParseError(format!(
+            "'{}' is not a valid Tari amount",
+            value
+        )));
+    }
+    if fraction.len() > 6 {
+        return Err(MicroTariError::ExcessPrecision);
+    }
+    let parse_digits = |digits: &str| -> Result<u64, MicroTariError> {
+        if digits.is_empty() {
+            return Ok(0);
+        }
+        parse_micro_tari(digits).map(u64::from)
+    };
+    let whole = MicroTari::checked_from_tari(parse_digits(whole)?)?;
+    let fraction = parse_digits(fraction)? * 10u64.pow(6 - fraction.len() as u32);
+    whole.checked_add(MicroTari(fraction)).ok_or(MicroTariError::Overflow)
+}
+
 impl From<MicroTari> for u64 {
     fn from(v: MicroTari) -> Self {
         v.0
@@ -158,9 +253,30 @@ impl From<MicroTari> for Tari {
     }
 }
 
+/// Converts Tari to MicroTari, failing if the value is negative, too large or has more precision than a MicroTari.
+impl TryFrom<Tari> for MicroTari {
+    type Error = MicroTariError;
+
+    fn try_from(v: Tari) -> Result<Self, Self::Error> {
+        if !v.0.is_finite() || v.0 < 0.0 {
+            return Err(MicroTariError::InvalidAmount);
+        }
+        let micro_tari = (v.0 * MICRO_TARI_PER_TARI as f64).round();
+        if micro_tari >= u64::max_value() as f64 {
+            return Err(MicroTariError::Overflow);
+        }
+        let tolerance = (micro_tari * std::f64::EPSILON * 4.0).max(1e-3);
+        if (micro_tari - v.0 * MICRO_TARI_PER_TARI as f64).abs() > tolerance {
+            return Err(MicroTariError::ExcessPrecision);
+        }
+        Ok(MicroTari(micro_tari as u64))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::transactions::tari_amount::{MicroTari, Tari};
+    use crate::transactions::tari_amount::{MicroTari, MicroTariError, Tari};
+    use std::{convert::TryFrom, str::FromStr};
 
     #[test]
     fn micro_tari_arithmetic() {
@@ -212,4 +328,51 @@ mod test {
         let s = format!("{}", Tari::from(1.234));
         assert_eq!(s, "1.234000 T");
     }
+
+    #[test]
+    fn micro_tari_checked_conversions() {
+        assert_eq!(MicroTari::try_from(Tari::from(1.5)), Ok(MicroTari::from(1_500_000)));
+        assert_eq!(MicroTari::try_from(Tari::from(0.000_001)), Ok(MicroTari::from(1)));
+        assert_eq!(
+            MicroTari::try_from(Tari::from(0.000_000_1)),
+            Err(MicroTariError::ExcessPrecision)
+        );
+        assert_eq!(
+            MicroTari::try_from(Tari::from(-1.0)),
+            Err(MicroTariError::InvalidAmount)
+        );
+        assert_eq!(
+            MicroTari::try_from(Tari::from(std::f64::NAN)),
+            Err(MicroTariError::InvalidAmount)
+        );
+        assert_eq!(MicroTari::try_from(Tari::from(1e20)), Err(MicroTariError::Overflow));
+        assert_eq!(MicroTari::checked_from_tari(2), Ok(MicroTari::from(2_000_000)));
+        assert_eq!(
+            MicroTari::checked_from_tari(u64::max_value()),
+            Err(MicroTariError::Overflow)
+        );
+        assert_eq!(MicroTari::from(u64::max_value()).checked_add(MicroTari::from(1)), None);
+        assert_eq!(MicroTari::from(u64::max_value()).checked_mul(2), None);
+        assert_eq!(MicroTari::from(5).as_micro_tari(), 5);
+    }
+
+    #[test]
+    fn micro_tari_from_str() {
+        assert_eq!(MicroTari::from_str("1.5 T"), Ok(MicroTari::from(1_500_000)));
+        assert_eq!(MicroTari::from_str("2T"), Ok(MicroTari::from(2_000_000)));
+        assert_eq!(MicroTari::from_str("1500 uT"), Ok(MicroTari::from(1500)));
+        assert_eq!(MicroTari::from_str("1500µT"), Ok(MicroTari::from(1500)));
+        assert_eq!(MicroTari::from_str("1500"), Ok(MicroTari::from(1500)));
+        assert!(MicroTari::from_str("1.5 uT").is_err());
+        assert!(MicroTari::from_str("abc T").is_err());
+        assert_eq!(MicroTari::from_str("0.0000001 T"), Err(MicroTariError::ExcessPrecision));
+    }
+
+    #[test]
+    fn micro_tari_display_round_trip() {
+        for v in &[0, 1, 999_999, 1_000_000, 1_500_001, 123_456_789_012] {
+            let amount = MicroTari::from(*v);
+            assert_eq!(MicroTari::from_str(&amount.to_string()), Ok(amount));
+        }
+    }
 }
@@ -35,6 +35,7 @@ use crate::transactions::{
         HashDigest,
         HashOutput,
         MessageHash,
+        PrivateKey,
         RangeProof,
         RangeProofService,
         Signature,
@@ -71,6 +72,8 @@ bitflags! {
     pub struct KernelFeatures: u8 {
         /// Coinbase transaction
         const COINBASE_KERNEL = 1u8;
+        /// Burn transaction. The kernel's `burned_value` is removed from circulation and has no corresponding output
+        const BURN_KERNEL = 2u8;
     }
 }
 
@@ -78,6 +81,10 @@ impl KernelFeatures {
     pub fn create_coinbase() -> KernelFeatures {
         KernelFeatures::COINBASE_KERNEL
     }
+
+    pub fn create_burn() -> KernelFeatures {
+        KernelFeatures::BURN_KERNEL
+    }
 }
 
 /// Options for UTXO's
@@ -195,6 +202,20 @@ impl UnblindedOutput {
     }
 
     pub fn as_transaction_output(&self, factories: &CryptoFactories) -> Result<TransactionOutput, TransactionError> {
+        self.as_transaction_output_with_min_value(factories, MicroTari(0))
+    }
+
+    /// Commits an UnblindedOutput into a Transaction output whose range proof also proves that the value is at least
+    /// `min_value`. See `TransactionOutput::verify_range_proof_with_min_value`.
+    pub fn as_transaction_output_with_min_value(
+        &self,
+        factories: &CryptoFactories,
+        min_value: MicroTari,
+    ) -> Result<TransactionOutput, TransactionError>
+    {
+        let proven_value = self.value.checked_sub(min_value).ok_or_else(|| {
+            TransactionError::ValidationError("Output value is less than the minimum output value".into())
+        })?;
         let commitment = factories.commitment.commit(&self.spending_key, &self.value.into());
         let output = TransactionOutput {
             features: self.features.clone(),
@@ -202,12 +223,12 @@ impl UnblindedOutput {
             proof: RangeProof::from_bytes(
                 &factories
                     .range_proof
-                    .construct_proof(&self.spending_key, self.value.into())?,
+                    .construct_proof(&self.spending_key, proven_value.into())?,
             )
             .map_err(|_| TransactionError::RangeProofError(RangeProofError::ProofConstructionError))?,
         };
         // A range proof can be constructed for an invalid value so we should confirm that the proof can be verified.
-        if !output.verify_range_proof(&factories.range_proof)? {
+        if !output.verify_range_proof_with_min_value(&factories.range_proof, min_value)? {
             return Err(TransactionError::ValidationError(
                 "Range proof could not be verified".into(),
             ));
@@ -347,6 +368,22 @@ impl TransactionOutput {
         Ok(prover.verify(&self.proof.to_vec(), &self.commitment))
     }
 
+    /// Verify that the range proof is valid and that the value of the output is at least `min_value`. The value is
+    /// hidden by the commitment, so the range proof of such an output is constructed for `value - min_value` and is
+    /// verified against the commitment less `min_value`. A `min_value` of zero is the same as `verify_range_proof`.
+    pub fn verify_range_proof_with_min_value(
+        &self,
+        prover: &RangeProofService,
+        min_value: MicroTari,
+    ) -> Result<bool, TransactionError>
+    {
+        if min_value == MicroTari(0) {
+            return self.verify_range_proof(prover);
+        }
+        let min_value_commitment = CommitmentFactory::default().commit_value(&PrivateKey::default(), min_value.into());
+        Ok(prover.verify(&self.proof.to_vec(), &(&self.commitment - &min_value_commitment)))
+    }
+
     /// This will check if the input and the output is the same commitment by looking at the commitment and features.
     /// This will ignore the output rangeproof
     pub fn is_equal_to(&self, output: &TransactionInput) -> bool {
@@ -414,6 +451,10 @@ pub struct TransactionKernel {
     /// This is an optional field and is the hash of the kernel this kernel is linked to.
     /// This field is for example for relative time-locked transactions
     pub linked_kernel: Option<HashOutput>,
+    /// The value that is provably burned by this kernel. This is only non-zero for kernels with the `BURN_KERNEL`
+    /// feature. Burned value is accounted for like the fee when summing the kernels, but is never claimed.
+    #[serde(default)]
+    pub burned_value: MicroTari,
     /// Remainder of the sum of all transaction commitments. If the transaction
     /// is well formed, amounts components should sum to zero and the excess
     /// is hence a valid public key.
@@ -430,6 +471,7 @@ pub struct KernelBuilder {
     lock_height: u64,
     meta_info: Option<MessageHash>,
     linked_kernel: Option<MessageHash>,
+    burned_value: MicroTari,
     excess: Option<Commitment>,
     excess_sig: Option<Signature>,
 }
@@ -481,6 +523,12 @@ impl KernelBuilder {
         self
     }
 
+    /// Build a transaction kernel that burns the provided value
+    pub fn with_burned_value(mut self, burned_value: MicroTari) -> KernelBuilder {
+        self.burned_value = burned_value;
+        self
+    }
+
     pub fn build(self) -> Result<TransactionKernel, TransactionError> {
         if self.excess.is_none() || self.excess_sig.is_none() {
             return Err(TransactionError::NoSignatureError);
@@ -491,6 +539,7 @@ impl KernelBuilder {
             lock_height: self.lock_height,
             linked_kernel: self.linked_kernel,
             meta_info: self.meta_info,
+            burned_value: self.burned_value,
             excess: self.excess.unwrap(),
             excess_sig: self.excess_sig.unwrap(),
         })
@@ -505,6 +554,7 @@ impl Default for KernelBuilder {
             lock_height: 0,
             linked_kernel: None,
             meta_info: None,
+            burned_value: MicroTari::from(0),
             excess: None,
             excess_sig: None,
         }
@@ -520,6 +570,7 @@ impl TransactionKernel {
             fee: self.fee,
             meta_info: None,
             linked_kernel: None,
+            burned_value: self.burned_value,
         };
         let c = build_challenge(r, &m);
         if self.excess_sig.verify_challenge(excess, &c) {
@@ -528,13 +579,32 @@ impl TransactionKernel {
             Err(TransactionError::InvalidSignatureError)
         }
     }
+
+    /// Returns true if this kernel burns value
+    pub fn is_burn(&self) -> bool {
+        self.features.contains(KernelFeatures::BURN_KERNEL)
+    }
+
+    /// Checks that only kernels with the `BURN_KERNEL` feature burn value, and that they burn a non-zero amount.
+    pub fn verify_burned_value(&self) -> Result<(), TransactionError> {
+        match (self.is_burn(), self.burned_value > MicroTari::from(0)) {
+            (true, false) => Err(TransactionError::ValidationError(
+                "Burn kernel does not burn any value".into(),
+            )),
+            (false, true) => Err(TransactionError::ValidationError(
+                "Only burn kernels may burn value".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Hashable for TransactionKernel {
     /// Produce a canonical hash for a transaction kernel. The hash is given by
     /// $$ H(feature_bits | fee | lock_height | P_excess | R_sum | s_sum)
+    /// The burned value is appended for burn kernels only, so that the hashes of all other kernels are unchanged.
     fn hash(&self) -> Vec<u8> {
-        HashDigest::new()
+        let hasher = HashDigest::new()
             .chain(&[self.features.bits])
             .chain(u64::from(self.fee).to_le_bytes())
             .chain(self.lock_height.to_le_bytes())
@@ -542,20 +612,27 @@ impl Hashable for TransactionKernel {
             .chain(self.excess_sig.get_public_nonce().as_bytes())
             .chain(self.excess_sig.get_signature().as_bytes())
             .chain(self.meta_info.as_ref().unwrap_or(&vec![0]))
-            .chain(self.linked_kernel.as_ref().unwrap_or(&vec![0]))
-            .result()
-            .to_vec()
+            .chain(self.linked_kernel.as_ref().unwrap_or(&vec![0]));
+        if self.is_burn() {
+            hasher
+                .chain(u64::from(self.burned_value).to_le_bytes())
+                .result()
+                .to_vec()
+        } else {
+            hasher.result().to_vec()
+        }
     }
 }
 
 impl Display for TransactionKernel {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         let msg = format!(
-            "Fee: {}\nLock height: {}\nFeatures: {:?}\nExcess: {}\nExcess signature: {}\nMeta_info: \
+            "Fee: {}\nLock height: {}\nFeatures: {:?}\nBurned: {}\nExcess: {}\nExcess signature: {}\nMeta_info: \
              {}\nLinked_kernel: {}\n",
             self.fee,
             self.lock_height,
             self.features,
+            self.burned_value,
             self.excess.to_hex(),
             self.excess_sig
                 .to_json()
@@ -574,10 +651,11 @@ impl Display for TransactionKernel {
 }
 
 /// This struct holds the result of calculating the sum of the kernels in a Transaction
-/// and returns the summed commitments and the total fees
+/// and returns the summed commitments, the total fees and the total burned value
 pub struct KernelSum {
     pub sum: Commitment,
     pub fees: MicroTari,
+    pub burned: MicroTari,
 }
 
 //----------------------------------------      Transaction       ----------------------------------------------------//
@@ -622,9 +700,23 @@ impl Transaction {
         factories: &CryptoFactories,
         reward: Option<MicroTari>,
     ) -> Result<(), TransactionError>
+    {
+        self.validate_internal_consistency_with_min_output_value(factories, reward, 0 * uT)
+    }
+
+    /// Validate this transaction like `validate_internal_consistency`, and also check that every output that is not a
+    /// coinbase output proves a value of at least `min_output_value` with its range proof
+    #[allow(clippy::erasing_op)] // This is for 0 * uT
+    pub fn validate_internal_consistency_with_min_output_value(
+        &self,
+        factories: &CryptoFactories,
+        reward: Option<MicroTari>,
+        min_output_value: MicroTari,
+    ) -> Result<(), TransactionError>
     {
         let reward = reward.unwrap_or_else(|| 0 * uT);
-        self.body.validate_internal_consistency(&self.offset, reward, factories)
+        self.body
+            .validate_internal_consistency_with_min_output_value(&self.offset, reward, min_output_value, factories)
     }
 
     pub fn get_body(&self) -> &AggregateBody {
@@ -752,6 +844,12 @@ impl TransactionBuilder {
         self
     }
 
+    /// Add a kernel to an existing transaction, e.g. one of the kernels of a transaction to several recipients
+    pub fn add_kernel(&mut self, kernel: TransactionKernel) -> &mut Self {
+        self.body.add_kernel(kernel);
+        self
+    }
+
     pub fn with_reward(&mut self, reward: MicroTari) -> &mut Self {
         self.reward = Some(reward);
         self
@@ -978,4 +1076,12 @@ mod test {
         assert_eq!(tx3.body.outputs().len(), 4);
         assert_eq!(tx3.body.kernels().len(), 2);
     }
+    #[test]
+    fn raw_transaction_round_trip() {
+        let (tx, _, _) = create_tx(5000.into(), 15.into(), 1, 2, 1, 4);
+
+        let bytes = tx.to_raw_bytes();
+        assert_eq!(Transaction::from_raw_bytes(&bytes).unwrap(), tx);
+        assert!(Transaction::from_raw_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
 }
@@ -140,16 +140,26 @@ pub struct TransactionMetadata {
     /// This is an optional field and is the hash of the kernel this kernel is linked to.
     /// This field is for example for relative time-locked transactions
     pub linked_kernel: Option<HashOutput>,
+    /// The value burned by the transaction. Only burn transactions have a non-zero burned value
+    #[serde(default)]
+    pub burned_value: MicroTari,
 }
 
-/// Convenience function that calculates the challenge for the Schnorr signatures
+/// Convenience function that calculates the challenge for the Schnorr signatures. The burned value is only committed
+/// to for burn transactions, so that the challenge of all other transactions is unchanged.
 pub fn build_challenge(sum_public_nonces: &PublicKey, metadata: &TransactionMetadata) -> MessageHash {
-    Challenge::new()
+    let challenge = Challenge::new()
         .chain(sum_public_nonces.as_bytes())
         .chain(&u64::from(metadata.fee).to_le_bytes())
         .chain(&metadata.lock_height.to_le_bytes())
         .chain(metadata.meta_info.as_ref().unwrap_or(&vec![0]))
-        .chain(metadata.linked_kernel.as_ref().unwrap_or(&vec![0]))
-        .result()
-        .to_vec()
+        .chain(metadata.linked_kernel.as_ref().unwrap_or(&vec![0]));
+    if metadata.burned_value > MicroTari::from(0) {
+        challenge
+            .chain(&u64::from(metadata.burned_value).to_le_bytes())
+            .result()
+            .to_vec()
+    } else {
+        challenge.result().to_vec()
+    }
 }
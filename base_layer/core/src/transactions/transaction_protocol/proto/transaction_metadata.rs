@@ -31,6 +31,7 @@ impl From<proto::TransactionMetadata> for TransactionMetadata {
             lock_height: metadata.lock_height,
             meta_info: metadata.meta_info.map(Into::into),
             linked_kernel: metadata.linked_kernel.map(Into::into),
+            burned_value: metadata.burned_value.into(),
         }
     }
 }
@@ -47,6 +48,8 @@ impl From<TransactionMetadata> for proto::TransactionMetadata {
             // This is an optional field and is the hash of the kernel this kernel is linked to.
             // This field is for example for relative time-locked transactions
             linked_kernel: metadata.linked_kernel.map(Into::into),
+            // The value burned by the transaction
+            burned_value: metadata.burned_value.into(),
         }
     }
 }
@@ -104,6 +104,7 @@ impl TryFrom<proto::SingleRoundSenderData> for SingleRoundSenderData {
             public_nonce,
             metadata,
             message,
+            min_output_value: data.min_output_value.into(),
         })
     }
 }
@@ -119,6 +120,7 @@ impl From<SingleRoundSenderData> for proto::SingleRoundSenderData {
             public_nonce: sender_data.public_nonce.to_vec(),
             metadata: Some(sender_data.metadata.into()),
             message: sender_data.message,
+            min_output_value: sender_data.min_output_value.into(),
         }
     }
 }
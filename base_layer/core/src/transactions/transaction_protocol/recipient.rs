@@ -205,6 +205,7 @@ mod test {
             lock_height: 0,
             meta_info: None,
             linked_kernel: None,
+            burned_value: MicroTari(0),
         };
         let msg = SingleRoundSenderData {
             tx_id: 15,
@@ -213,6 +214,7 @@ mod test {
             public_nonce: PublicKey::from_secret_key(&p.change_key), // any random key will do
             metadata: m.clone(),
             message: "".to_string(),
+            min_output_value: MicroTari(0),
         };
         let sender_info = TransactionSenderMessage::Single(Box::new(msg.clone()));
         let pubkey = PublicKey::from_secret_key(&p.spend_key);
@@ -28,6 +28,7 @@ use crate::transactions::{
         Transaction,
         TransactionBuilder,
         TransactionInput,
+        TransactionKernel,
         TransactionOutput,
         MAX_TRANSACTION_INPUTS,
         MAX_TRANSACTION_OUTPUTS,
@@ -59,6 +60,15 @@ pub(super) struct RawTransactionInfo {
     pub ids: Vec<u64>,
     pub amounts: Vec<MicroTari>,
     pub change: MicroTari,
+    // The value of each change output, in the order of the change secrets they were created with
+    #[serde(default)]
+    pub change_amounts: Vec<MicroTari>,
+    // The smallest fee this transaction may pay
+    #[serde(default = "default_min_fee")]
+    pub min_fee: MicroTari,
+    // The smallest value that the range proof of each output proves
+    #[serde(default)]
+    pub min_output_value: MicroTari,
     pub metadata: TransactionMetadata,
     pub inputs: Vec<TransactionInput>,
     pub outputs: Vec<TransactionOutput>,
@@ -76,12 +86,35 @@ pub(super) struct RawTransactionInfo {
     pub recipient_info: RecipientInfo,
     pub signatures: Vec<Signature>,
     pub message: String,
+    // One kernel per recipient when there are several recipients, empty otherwise
+    #[serde(default)]
+    pub recipient_kernels: Vec<RecipientKernelInfo>,
+}
+
+/// The kernel negotiated with one of the recipients of a transaction to several recipients. Each kernel commits to a
+/// share of the sender's excess and of the fee, so that every recipient can complete its kernel in a single round
+/// without learning about the other recipients.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub(super) struct RecipientKernelInfo {
+    pub tx_id: u64,
+    pub amount: MicroTari,
+    pub metadata: TransactionMetadata,
+    // The sender's share of the excess blinding factor for this kernel
+    pub excess_blinding_factor: BlindingFactor,
+    pub public_excess: PublicKey,
+    pub private_nonce: PrivateKey,
+    pub public_nonce: PublicKey,
+    pub reply: Option<RecipientSignedMessage>,
+}
+
+fn default_min_fee() -> MicroTari {
+    MINIMUM_TRANSACTION_FEE
 }
 
 impl RawTransactionInfo {
     pub fn calculate_total_amount(&self) -> MicroTari {
         let to_others: MicroTari = self.amounts.iter().sum();
-        to_others + self.amount_to_self
+        to_others + self.amount_to_self + self.metadata.burned_value
     }
 }
 
@@ -99,6 +132,9 @@ pub struct SingleRoundSenderData {
     pub metadata: TransactionMetadata,
     /// Plain text message to receiver
     pub message: String,
+    /// The smallest value the range proof of the recipient's output must prove, i.e. the consensus dust limit
+    #[serde(default)]
+    pub min_output_value: MicroTari,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +166,14 @@ impl SenderTransactionProtocol {
         }
     }
 
+    /// Convenience method to check whether we're receiving the data of several recipients
+    pub fn is_collecting_multiple_signatures(&self) -> bool {
+        match &self.state {
+            SenderState::CollectingMultipleSignatures(_) => true,
+            _ => false,
+        }
+    }
+
     /// Convenience method to check whether we're ready to send a message to a single recipient
     pub fn is_single_round_message_ready(&self) -> bool {
         match &self.state {
@@ -192,7 +236,8 @@ impl SenderTransactionProtocol {
         match &self.state {
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => info.ids[0] == tx_id,
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => info.ids[0] == tx_id,
             _ => false,
         }
     }
@@ -201,7 +246,8 @@ impl SenderTransactionProtocol {
         match &self.state {
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.ids[0]),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.ids[0]),
             _ => Err(TPE::InvalidStateError),
         }
     }
@@ -211,7 +257,8 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.amounts.iter().sum()),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.amounts.iter().sum()),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -223,7 +270,8 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.amount_to_self),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.amount_to_self),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -235,7 +283,22 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.change),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.change),
+            SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
+            SenderState::Failed(_) => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// This function will return the value of each change output, in the order of the change secrets that were given to
+    /// the builder
+    pub fn get_change_amounts(&self) -> Result<Vec<MicroTari>, TPE> {
+        match &self.state {
+            SenderState::Initializing(info) |
+            SenderState::Finalizing(info) |
+            SenderState::SingleRoundMessageReady(info) |
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.change_amounts.clone()),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -247,7 +310,8 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.metadata.fee),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.metadata.fee),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -264,6 +328,7 @@ impl SenderTransactionProtocol {
                     public_excess: info.public_excess.clone(),
                     metadata: info.metadata.clone(),
                     message: info.message.clone(),
+                    min_output_value: info.min_output_value,
                 };
                 self.state = SenderState::CollectingSingleSignature(info.clone());
                 Ok(result)
@@ -272,6 +337,23 @@ impl SenderTransactionProtocol {
         }
     }
 
+    /// Rebuild the single round message that was sent to the recipient so that it can be sent again, e.g. when the
+    /// recipient never received it. This does not change the state of the protocol.
+    pub fn get_single_round_message(&self) -> Result<SingleRoundSenderData, TPE> {
+        match &self.state {
+            SenderState::CollectingSingleSignature(info) => Ok(SingleRoundSenderData {
+                tx_id: info.ids[0],
+                amount: self.get_total_amount()?,
+                public_nonce: info.public_nonce.clone(),
+                public_excess: info.public_excess.clone(),
+                metadata: info.metadata.clone(),
+                message: info.message.clone(),
+                min_output_value: info.min_output_value,
+            }),
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
     /// Add the signed transaction from the recipient and move to the next state
     pub fn add_single_recipient_info(
         &mut self,
@@ -281,7 +363,10 @@ impl SenderTransactionProtocol {
     {
         match &mut self.state {
             SenderState::CollectingSingleSignature(info) => {
-                if !rec.output.verify_range_proof(prover)? {
+                if !rec
+                    .output
+                    .verify_range_proof_with_min_value(prover, info.min_output_value)?
+                {
                     return Err(TPE::ValidationError(
                         "Recipient output range proof failed to verify".into(),
                     ));
@@ -299,6 +384,93 @@ impl SenderTransactionProtocol {
         }
     }
 
+    /// The messages for the recipients of a transaction to several recipients, in the order of their amounts. Each
+    /// recipient is sent its own message and replies to it as in the single-round protocol. The messages can be
+    /// rebuilt for resending at any time while the replies are being collected.
+    pub fn get_multi_recipient_messages(&self) -> Result<Vec<SingleRoundSenderData>, TPE> {
+        match &self.state {
+            SenderState::CollectingMultipleSignatures(info) => Ok(info
+                .recipient_kernels
+                .iter()
+                .map(|k| SingleRoundSenderData {
+                    tx_id: k.tx_id,
+                    amount: k.amount,
+                    public_nonce: k.public_nonce.clone(),
+                    public_excess: k.public_excess.clone(),
+                    metadata: k.metadata.clone(),
+                    message: info.message.clone(),
+                    min_output_value: info.min_output_value,
+                })
+                .collect()),
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// Add the signed data of one of the recipients of a transaction to several recipients. Once every recipient has
+    /// replied the protocol moves to the Finalizing state.
+    pub fn add_multi_recipient_info(
+        &mut self,
+        rec: RecipientSignedMessage,
+        prover: &RangeProofService,
+    ) -> Result<(), TPE>
+    {
+        match &mut self.state {
+            SenderState::CollectingMultipleSignatures(info) => {
+                let index = info
+                    .recipient_kernels
+                    .iter()
+                    .position(|k| k.tx_id == rec.tx_id)
+                    .ok_or_else(|| {
+                        TPE::ValidationError("The reply is not from a recipient of this transaction".into())
+                    })?;
+                if info.recipient_kernels[index].reply.is_some() {
+                    return Err(TPE::ValidationError(
+                        "A reply was already received from this recipient".into(),
+                    ));
+                }
+                if !rec
+                    .output
+                    .verify_range_proof_with_min_value(prover, info.min_output_value)?
+                {
+                    return Err(TPE::ValidationError(
+                        "Recipient output range proof failed to verify".into(),
+                    ));
+                }
+                info.outputs.push(rec.output.clone());
+                info.public_excess = &info.public_excess + &rec.public_spend_key;
+                info.recipient_kernels[index].reply = Some(rec);
+                if info.recipient_kernels.iter().all(|k| k.reply.is_some()) {
+                    self.state = SenderState::Finalizing(info.clone());
+                }
+                Ok(())
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// Build a kernel with the given metadata. Kernels that burn value get the burn feature.
+    fn build_kernel(
+        metadata: &TransactionMetadata,
+        features: KernelFeatures,
+        excess: &PedersenCommitment,
+        signature: &Signature,
+    ) -> Result<TransactionKernel, TPE>
+    {
+        let features = if metadata.burned_value > MicroTari(0) {
+            features | KernelFeatures::BURN_KERNEL
+        } else {
+            features
+        };
+        Ok(KernelBuilder::new()
+            .with_fee(metadata.fee)
+            .with_burned_value(metadata.burned_value)
+            .with_features(features)
+            .with_lock_height(metadata.lock_height)
+            .with_excess(excess)
+            .with_signature(signature)
+            .build()?)
+    }
+
     /// Attempts to build the final transaction.
     fn build_transaction(
         info: &RawTransactionInfo,
@@ -315,17 +487,23 @@ impl SenderTransactionProtocol {
             tx_builder.add_output(o.clone());
         }
         tx_builder.add_offset(info.offset.clone());
-        let mut s_agg = info.signatures[0].clone();
-        info.signatures.iter().skip(1).for_each(|s| s_agg = &s_agg + s);
-        let excess = PedersenCommitment::from_public_key(&info.public_excess);
-        let kernel = KernelBuilder::new()
-            .with_fee(info.metadata.fee)
-            .with_features(features)
-            .with_lock_height(info.metadata.lock_height)
-            .with_excess(&excess)
-            .with_signature(&s_agg)
-            .build()?;
-        tx_builder.with_kernel(kernel);
+        if info.recipient_kernels.is_empty() {
+            let mut s_agg = info.signatures[0].clone();
+            info.signatures.iter().skip(1).for_each(|s| s_agg = &s_agg + s);
+            let excess = PedersenCommitment::from_public_key(&info.public_excess);
+            tx_builder.with_kernel(Self::build_kernel(&info.metadata, features, &excess, &s_agg)?);
+        } else {
+            for (kernel_info, s) in info.recipient_kernels.iter().zip(info.signatures.iter()) {
+                let reply = kernel_info
+                    .reply
+                    .as_ref()
+                    .ok_or_else(|| TPE::IncompleteStateError("A recipient has not replied".into()))?;
+                let s_agg = s + &reply.partial_signature;
+                let excess =
+                    PedersenCommitment::from_public_key(&(&kernel_info.public_excess + &reply.public_spend_key));
+                tx_builder.add_kernel(Self::build_kernel(&kernel_info.metadata, features, &excess, &s_agg)?);
+            }
+        }
         tx_builder.build(factories).map_err(TPE::from)
     }
 
@@ -341,7 +519,7 @@ impl SenderTransactionProtocol {
                 return Err(TPE::ValidationError("Fee is greater than amount".into()));
             }
             // The fee must be greater than MIN_FEE to prevent spam attacks
-            if fee < MINIMUM_TRANSACTION_FEE {
+            if fee < info.min_fee {
                 return Err(TPE::ValidationError("Fee is less than the minimum".into()));
             }
             // Prevent overflow attacks by imposing sane limits on some key parameters
@@ -354,7 +532,13 @@ impl SenderTransactionProtocol {
             if info.inputs.is_empty() {
                 return Err(TPE::ValidationError("A transaction cannot have zero inputs".into()));
             }
-            if info.signatures.len() != 1 + info.num_recipients {
+            // The recipients' partial signatures are kept with their kernels when there are several recipients
+            let num_signatures = if info.recipient_kernels.is_empty() {
+                1 + info.num_recipients
+            } else {
+                info.num_recipients
+            };
+            if info.signatures.len() != num_signatures {
                 return Err(TPE::ValidationError(format!(
                     "Incorrect number of signatures ({})",
                     info.signatures.len()
@@ -369,7 +553,7 @@ impl SenderTransactionProtocol {
     /// Produce the sender's partial signature
     fn sign(&mut self) -> Result<(), TPE> {
         match &mut self.state {
-            SenderState::Finalizing(info) => {
+            SenderState::Finalizing(info) if info.recipient_kernels.is_empty() => {
                 let e = build_challenge(&info.public_nonce_sum, &info.metadata);
                 let k = info.offset_blinding_factor.clone();
                 let r = info.private_nonce.clone();
@@ -377,6 +561,22 @@ impl SenderTransactionProtocol {
                 info.signatures.push(s);
                 Ok(())
             },
+            SenderState::Finalizing(info) => {
+                let mut signatures = Vec::with_capacity(info.recipient_kernels.len());
+                for kernel in &info.recipient_kernels {
+                    let reply = kernel
+                        .reply
+                        .as_ref()
+                        .ok_or_else(|| TPE::IncompleteStateError("A recipient has not replied".into()))?;
+                    let public_nonce_sum = &kernel.public_nonce + reply.partial_signature.get_public_nonce();
+                    let e = build_challenge(&public_nonce_sum, &kernel.metadata);
+                    let s = Signature::sign(kernel.excess_blinding_factor.clone(), kernel.private_nonce.clone(), &e)
+                        .map_err(TPE::SigningError)?;
+                    signatures.push(s);
+                }
+                info.signatures = signatures;
+                Ok(())
+            },
             _ => Err(TPE::InvalidStateError),
         }
     }
@@ -412,7 +612,7 @@ impl SenderTransactionProtocol {
                 }
                 let transaction = result.unwrap();
                 let result = transaction
-                    .validate_internal_consistency(factories, None)
+                    .validate_internal_consistency_with_min_output_value(factories, None, info.min_output_value)
                     .map_err(TPE::TransactionBuildError);
                 if let Err(e) = result {
                     self.state = SenderState::Failed(e);
@@ -435,6 +635,7 @@ impl SenderTransactionProtocol {
                 let data = serde_json::to_string(s).map_err(|_| TPE::SerializationError)?;
                 Ok(data)
             },
+            SenderState::CollectingMultipleSignatures(_) => Err(TPE::InvalidStateError),
             SenderState::Finalizing(_) => Err(TPE::InvalidStateError),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
@@ -483,6 +684,8 @@ pub(super) enum SenderState {
     SingleRoundMessageReady(Box<RawTransactionInfo>),
     /// Waiting for the signed transaction data in the single-round protocol
     CollectingSingleSignature(Box<RawTransactionInfo>),
+    /// Waiting for the signed transaction data of each recipient of a transaction to several recipients
+    CollectingMultipleSignatures(Box<RawTransactionInfo>),
     /// The final transaction state is being validated - it will automatically transition to Failed or Finalized from
     /// here
     Finalizing(Box<RawTransactionInfo>),
@@ -500,9 +703,7 @@ impl SenderState {
             SenderState::Initializing(info) => match info.num_recipients {
                 0 => Ok(SenderState::Finalizing(info)),
                 1 => Ok(SenderState::SingleRoundMessageReady(info)),
-                _ => Ok(SenderState::Failed(TPE::UnsupportedError(
-                    "Multiple recipients are not supported yet".into(),
-                ))),
+                _ => Ok(SenderState::CollectingMultipleSignatures(info)),
             },
             _ => Err(TPE::InvalidTransitionError),
         }
@@ -531,6 +732,12 @@ impl fmt::Display for SenderState {
                 info.inputs.len(),
                 info.outputs.len()
             ),
+            CollectingMultipleSignatures(info) => write!(
+                f,
+                "CollectingMultipleSignatures({} input(s), {} output(s))",
+                info.inputs.len(),
+                info.outputs.len()
+            ),
             Finalizing(info) => write!(
                 f,
                 "Finalizing({} input(s), {} output(s))",
@@ -594,6 +801,53 @@ mod test {
         assert_eq!(tx.offset, p.offset);
     }
 
+    #[test]
+    fn burn_transaction() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(1200), &factories.commitment);
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(MicroTari(10))
+            .with_offset(p.offset.clone())
+            .with_private_nonce(p.nonce.clone())
+            .with_change_secret(p.change_key.clone())
+            .with_input(utxo, input)
+            .with_burned_value(MicroTari(500));
+        let mut sender = builder.build::<Blake256>(&factories).unwrap();
+        assert!(sender.is_finalizing());
+        match sender.finalize(KernelFeatures::empty(), &factories) {
+            Ok(true) => (),
+            Ok(false) => panic!("{:?}", sender.failure_reason()),
+            Err(e) => panic!("{:?}", e),
+        }
+        let tx = sender.get_transaction().unwrap().clone();
+        let kernel = &tx.body.kernels()[0];
+        assert!(kernel.features.contains(KernelFeatures::BURN_KERNEL));
+        assert_eq!(kernel.burned_value, MicroTari(500));
+        assert_eq!(tx.body.get_total_burned(), MicroTari(500));
+        // Only the change output is created, the burned value has no corresponding output
+        assert_eq!(tx.body.outputs().len(), 1);
+        let fee = Fee::calculate(MicroTari(10), 1, 1, 1);
+        assert_eq!(kernel.fee, fee);
+        assert!(tx.validate_internal_consistency(&factories, None).is_ok());
+
+        // The burned value can't be reduced to claim the difference
+        let mut tampered = tx.clone();
+        let mut kernel = tampered.body.kernels()[0].clone();
+        kernel.burned_value = MicroTari(400);
+        tampered.body.set_kernel(kernel);
+        assert!(tampered.validate_internal_consistency(&factories, None).is_err());
+
+        // Only burn kernels may burn value
+        let mut tampered = tx.clone();
+        let mut kernel = tampered.body.kernels()[0].clone();
+        kernel.features = KernelFeatures::empty();
+        tampered.body.set_kernel(kernel);
+        assert!(tampered.validate_internal_consistency(&factories, None).is_err());
+    }
+
     #[test]
     fn single_recipient_no_change() {
         let factories = CryptoFactories::default();
@@ -621,6 +875,9 @@ mod test {
         // Test serializing the current state to be sent and resuming from that serialized data
         let ser = alice.save_pending_transaction_to_be_sent().unwrap();
         let mut alice = SenderTransactionProtocol::load_pending_transaction_to_be_sent(ser).unwrap();
+        // The message can be rebuilt for resending while waiting for the response
+        assert_eq!(alice.get_single_round_message().unwrap(), msg);
+        assert!(alice.is_collecting_single_signature());
 
         // Receiver gets message, deserializes it etc, and creates his response
         let bob_info = SingleReceiverTransactionProtocol::create(
@@ -726,6 +983,85 @@ mod test {
         assert!(tx.clone().validate_internal_consistency(&factories, None).is_ok());
     }
 
+    #[test]
+    fn multiple_recipients_with_change() {
+        let factories = CryptoFactories::default();
+        // Alice's parameters
+        let a = TestParams::new();
+        // Bob's and Carol's parameters
+        let b = TestParams::new();
+        let c = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(5000), &factories.commitment);
+        let mut builder = SenderTransactionProtocol::builder(2);
+        let fee = Fee::calculate(MicroTari(20), 2, 1, 3);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(MicroTari(20))
+            .with_offset(a.offset.clone())
+            .with_private_nonce(a.nonce.clone())
+            .with_change_secret(a.change_key.clone())
+            .with_input(utxo.clone(), input)
+            .with_amount(0, MicroTari(1000))
+            .with_amount(1, MicroTari(1500));
+        let mut alice = builder.build::<Blake256>(&factories).unwrap();
+        assert!(alice.is_collecting_multiple_signatures());
+        assert_eq!(alice.get_total_amount().unwrap(), MicroTari(2500));
+        assert_eq!(alice.get_fee_amount().unwrap(), fee);
+        assert_eq!(alice.get_change_amount().unwrap(), MicroTari(2500) - fee);
+        let msgs = alice.get_multi_recipient_messages().unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].tx_id, alice.get_tx_id().unwrap());
+        assert_ne!(msgs[0].tx_id, msgs[1].tx_id);
+        assert_eq!(msgs[0].amount, MicroTari(1000));
+        assert_eq!(msgs[1].amount, MicroTari(1500));
+        assert_eq!(msgs[0].metadata.fee + msgs[1].metadata.fee, fee);
+
+        let bob_info = SingleReceiverTransactionProtocol::create(
+            &msgs[0],
+            b.nonce,
+            b.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .unwrap();
+        let carol_info = SingleReceiverTransactionProtocol::create(
+            &msgs[1],
+            c.nonce,
+            c.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .unwrap();
+        // The replies can arrive in any order
+        alice
+            .add_multi_recipient_info(carol_info.clone(), &factories.range_proof)
+            .unwrap();
+        assert!(alice.is_collecting_multiple_signatures());
+        // A second reply from the same recipient is rejected
+        assert!(alice
+            .add_multi_recipient_info(carol_info.clone(), &factories.range_proof)
+            .is_err());
+        alice
+            .add_multi_recipient_info(bob_info.clone(), &factories.range_proof)
+            .unwrap();
+        assert!(alice.is_finalizing());
+        match alice.finalize(KernelFeatures::empty(), &factories) {
+            Ok(true) => (),
+            Ok(false) => panic!("{:?}", alice.failure_reason()),
+            Err(e) => panic!("{:?}", e),
+        };
+        let tx = alice.get_transaction().unwrap();
+        assert_eq!(tx.offset, a.offset);
+        assert_eq!(tx.body.kernels().len(), 2);
+        assert_eq!(tx.body.get_total_fee(), fee);
+        assert_eq!(tx.body.inputs().len(), 1);
+        assert_eq!(tx.body.inputs()[0], utxo);
+        // Bob's output, Carol's output and a single change output
+        assert_eq!(tx.body.outputs().len(), 3);
+        assert!(tx.body.outputs().contains(&bob_info.output));
+        assert!(tx.body.outputs().contains(&carol_info.output));
+    }
+
     #[test]
     fn single_recipient_range_proof_fail() {
         let factories = CryptoFactories::new(32);
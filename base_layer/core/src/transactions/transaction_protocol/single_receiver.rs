@@ -87,9 +87,15 @@ impl SingleReceiverTransactionProtocol {
         let commitment = factories
             .commitment
             .commit_value(&spending_key, sender_info.amount.into());
+        // The range proof proves that the amount is at least the minimum output value, see
+        // `TransactionOutput::verify_range_proof_with_min_value`
+        let proven_amount = sender_info
+            .amount
+            .checked_sub(sender_info.min_output_value)
+            .ok_or_else(|| TPE::ValidationError("Amount is less than the minimum output value".into()))?;
         let proof = factories
             .range_proof
-            .construct_proof(&spending_key, sender_info.amount.into())?;
+            .construct_proof(&spending_key, proven_amount.into())?;
         Ok(TransactionOutput::new(
             features,
             commitment,
@@ -151,6 +157,7 @@ mod test {
             lock_height: 0,
             meta_info: None,
             linked_kernel: None,
+            burned_value: MicroTari(0),
         };
         let info = SingleRoundSenderData {
             tx_id: 500,
@@ -159,6 +166,7 @@ mod test {
             public_nonce: pub_rs.clone(),
             metadata: m.clone(),
             message: "".to_string(),
+            min_output_value: MicroTari(0),
         };
         let prot = SingleReceiverTransactionProtocol::create(&info, r, k.clone(), of, &factories).unwrap();
         assert_eq!(prot.tx_id, 500, "tx_id is incorrect");
@@ -181,4 +189,38 @@ mod test {
         );
         assert!(out.features.flags.is_empty(), "Output features flags have changed");
     }
+
+    #[test]
+    fn range_proof_proves_min_output_value() {
+        let factories = CryptoFactories::default();
+        let (_xs, pub_xs) = PublicKey::random_keypair(&mut OsRng);
+        let (_rs, pub_rs) = PublicKey::random_keypair(&mut OsRng);
+        let (r, k, of) = generate_output_parms();
+        let mut info = SingleRoundSenderData {
+            tx_id: 500,
+            amount: MicroTari(1500),
+            public_excess: pub_xs,
+            public_nonce: pub_rs,
+            metadata: TransactionMetadata::default(),
+            message: "".to_string(),
+            min_output_value: MicroTari(100),
+        };
+        let prot =
+            SingleReceiverTransactionProtocol::create(&info, r.clone(), k.clone(), of.clone(), &factories).unwrap();
+        let out = &prot.output;
+        assert!(factories.commitment.open_value(&k, info.amount.into(), &out.commitment));
+        assert!(out
+            .verify_range_proof_with_min_value(&factories.range_proof, MicroTari(100))
+            .unwrap());
+        // The proof is for the amount less the minimum, so it does not verify against the commitment itself
+        assert!(!out.verify_range_proof(&factories.range_proof).unwrap());
+
+        info.amount = MicroTari(99);
+        match SingleReceiverTransactionProtocol::create(&info, r, k, of, &factories) {
+            Err(TransactionProtocolError::ValidationError(s)) => {
+                assert_eq!(s, "Amount is less than the minimum output value")
+            },
+            _ => panic!("An amount below the minimum output value should fail"),
+        }
+    }
 }
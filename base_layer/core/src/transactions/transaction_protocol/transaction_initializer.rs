@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
-    fee::Fee,
+    fee::{FeeModel, WeightedFeeModel},
     tari_amount::*,
     transaction::{
         TransactionInput,
@@ -32,18 +32,27 @@ use crate::transactions::{
     },
     transaction_protocol::{
         recipient::RecipientInfo,
-        sender::{calculate_tx_id, RawTransactionInfo, SenderState, SenderTransactionProtocol},
+        sender::{calculate_tx_id, RawTransactionInfo, RecipientKernelInfo, SenderState, SenderTransactionProtocol},
         TransactionMetadata,
     },
     types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey},
 };
 use digest::Digest;
+use rand::rngs::OsRng;
+use rayon::prelude::*;
 use std::{
     cmp::max,
     collections::HashMap,
     fmt::{Debug, Error, Formatter},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tari_crypto::{
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+    tari_utilities::fixed_set::FixedSet,
 };
-use tari_crypto::{keys::PublicKey as PublicKeyTrait, tari_utilities::fixed_set::FixedSet};
 
 /// The SenderTransactionInitializer is a Builder that helps set up the initial state for the Sender party of a new
 /// transaction Typically you don't instantiate this object directly. Rather use
@@ -59,10 +68,14 @@ pub struct SenderTransactionInitializer {
     amounts: FixedSet<MicroTari>,
     lock_height: Option<u64>,
     fee_per_gram: Option<MicroTari>,
+    fee_model: Arc<dyn FeeModel>,
     inputs: Vec<TransactionInput>,
     unblinded_inputs: Vec<UnblindedOutput>,
     outputs: Vec<UnblindedOutput>,
-    change_secret: Option<BlindingFactor>,
+    change_secrets: Vec<BlindingFactor>,
+    min_output_value: MicroTari,
+    min_fee: MicroTari,
+    burned_value: MicroTari,
     offset: Option<BlindingFactor>,
     excess_blinding_factor: BlindingFactor,
     private_nonce: Option<PrivateKey>,
@@ -87,10 +100,14 @@ impl SenderTransactionInitializer {
             amounts: FixedSet::new(num_recipients),
             lock_height: None,
             fee_per_gram: None,
+            fee_model: Arc::new(WeightedFeeModel),
             inputs: Vec::new(),
             unblinded_inputs: Vec::new(),
             outputs: Vec::new(),
-            change_secret: None,
+            change_secrets: Vec::new(),
+            min_output_value: MicroTari(0),
+            min_fee: MINIMUM_TRANSACTION_FEE,
+            burned_value: MicroTari(0),
             offset: None,
             private_nonce: None,
             excess_blinding_factor: BlindingFactor::default(),
@@ -105,6 +122,13 @@ impl SenderTransactionInitializer {
         self
     }
 
+    /// Set the model used to calculate the absolute fee from the fee-per-gram. The default is the `WeightedFeeModel`
+    /// of the current consensus rules.
+    pub fn with_fee_model(&mut self, fee_model: Arc<dyn FeeModel>) -> &mut Self {
+        self.fee_model = fee_model;
+        self
+    }
+
     /// Set the amount to pay to the ith recipient. This method will silently fail if `receiver_index` >= num_receivers.
     pub fn with_amount(&mut self, receiver_index: usize, amount: MicroTari) -> &mut Self {
         self.amounts.set_item(receiver_index, amount);
@@ -142,7 +166,38 @@ impl SenderTransactionInitializer {
     /// Provide a blinding factor for the change output. The amount of change will automatically be calculated when
     /// the transaction is built.
     pub fn with_change_secret(&mut self, blinding_factor: BlindingFactor) -> &mut Self {
-        self.change_secret = Some(blinding_factor);
+        self.change_secrets = vec![blinding_factor];
+        self
+    }
+
+    /// Provide a blinding factor for each of several change outputs. The change is split into equal outputs, one per
+    /// blinding factor, when it can pay for all of them. Otherwise fewer change outputs are created, using the first
+    /// blinding factors.
+    pub fn with_change_secrets(&mut self, blinding_factors: Vec<BlindingFactor>) -> &mut Self {
+        self.change_secrets = blinding_factors;
+        self
+    }
+
+    /// Set the smallest value an output may carry (the consensus dust limit). Recipient amounts and outputs below this
+    /// value are rejected, and change below this value is added to the fee instead of creating a change output. The
+    /// range proofs of the outputs, including the recipients' outputs, prove that their values are at least this
+    /// value, so it must match the consensus `min_output_value` of the network.
+    pub fn with_min_output_value(&mut self, min_output_value: MicroTari) -> &mut Self {
+        self.min_output_value = min_output_value;
+        self
+    }
+
+    /// Set the smallest absolute fee the transaction may pay. This defaults to `MINIMUM_TRANSACTION_FEE` and should
+    /// only be lowered on networks that accept zero-fee transactions.
+    pub fn with_min_fee(&mut self, min_fee: MicroTari) -> &mut Self {
+        self.min_fee = min_fee;
+        self
+    }
+
+    /// Burn the provided value. The value is removed from circulation by the transaction kernel and has no
+    /// corresponding output.
+    pub fn with_burned_value(&mut self, burned_value: MicroTari) -> &mut Self {
+        self.burned_value = burned_value;
         self
     }
 
@@ -158,46 +213,114 @@ impl SenderTransactionInitializer {
         self
     }
 
-    /// Tries to make a change output with the given transaction parameters and add it to the set of outputs. The total
-    /// fee, including the additional change output (if any) is returned along with the amount of change.
-    /// The change output **always has default output features**.
-    fn add_change_if_required(&mut self) -> Result<(MicroTari, MicroTari), String> {
-        // The number of outputs excluding a possible residual change output
+    /// Tries to make the change outputs with the given transaction parameters and add them to the set of outputs. The
+    /// total fee, including the additional change outputs (if any) is returned along with the amount of each change
+    /// output. The change is split over as many of the change secrets as it can pay for, with every change output
+    /// above the minimum output value. The change outputs **always have default output features**.
+    fn add_change_if_required(&mut self) -> Result<(MicroTari, Vec<MicroTari>), String> {
+        // The number of outputs excluding possible residual change outputs
         let num_outputs = self.outputs.len() + self.num_recipients;
         let num_inputs = self.inputs.len();
+        // Every recipient of a transaction to several recipients gets its own kernel
+        let num_kernels = max(1, self.num_recipients);
         let total_being_spent = self.unblinded_inputs.iter().map(|i| i.value).sum::<MicroTari>();
         let total_to_self = self.outputs.iter().map(|o| o.value).sum::<MicroTari>();
         let total_amount = self.amounts.sum().ok_or_else(|| "Not all amounts have been provided")?;
         let fee_per_gram = self.fee_per_gram.ok_or_else(|| "Fee per gram was not provided")?;
-        let fee_without_change = Fee::calculate(fee_per_gram, 1, num_inputs, num_outputs);
-        let fee_with_change = Fee::calculate(fee_per_gram, 1, num_inputs, num_outputs + 1);
-        let extra_fee = fee_with_change - fee_without_change;
+        let fee_without_change = self
+            .fee_model
+            .calculate(fee_per_gram, num_kernels, num_inputs, num_outputs);
         // Subtract with a check on going negative
-        let change_amount = total_being_spent.checked_sub(total_to_self + total_amount + fee_without_change);
+        let change_amount =
+            total_being_spent.checked_sub(total_to_self + total_amount + self.burned_value + fee_without_change);
         match change_amount {
             None => Err("You are spending more than you're providing".into()),
-            Some(MicroTari(0)) => Ok((fee_without_change, MicroTari(0))),
+            Some(MicroTari(0)) => Ok((fee_without_change, Vec::new())),
             Some(v) => {
-                let change_amount = v.checked_sub(extra_fee);
-                match change_amount {
-                    // You can't win. Just add the change to the fee (which is less than the cost of adding another
-                    // output and go without a change output
-                    None => Ok((fee_without_change + v, MicroTari(0))),
-                    Some(MicroTari(0)) => Ok((fee_without_change + v, MicroTari(0))),
-                    Some(v) => {
-                        let change_key = self
-                            .change_secret
-                            .as_ref()
-                            .ok_or_else(|| "Change spending key was not provided")?;
-                        let change_key = change_key.clone();
-                        self.with_output(UnblindedOutput::new(v, change_key, None));
-                        Ok((fee_with_change, v))
-                    },
+                for num_change in (1..=max(1, self.change_secrets.len())).rev() {
+                    let fee_with_change =
+                        self.fee_model
+                            .calculate(fee_per_gram, num_kernels, num_inputs, num_outputs + num_change);
+                    let extra_fee = fee_with_change - fee_without_change;
+                    let change = match v.checked_sub(extra_fee) {
+                        Some(change) => change,
+                        // You can't win. This many change outputs cost more than the change is worth
+                        None => continue,
+                    };
+                    // Change outputs that would be dust are better off being added to the fee
+                    let part = MicroTari(change.0 / num_change as u64);
+                    if part == MicroTari(0) || part < self.min_output_value {
+                        continue;
+                    }
+                    if self.change_secrets.is_empty() {
+                        return Err("Change spending key was not provided".into());
+                    }
+                    // The first change output takes the remainder of the split
+                    let mut amounts = vec![part; num_change];
+                    amounts[0] = change - MicroTari(part.0 * (num_change as u64 - 1));
+                    let change_keys = self.change_secrets[..num_change].to_vec();
+                    for (amount, change_key) in amounts.iter().zip(change_keys) {
+                        self.with_output(UnblindedOutput::new(*amount, change_key, None));
+                    }
+                    return Ok((fee_with_change, amounts));
                 }
+                // Not even a single change output is worth it, so the change is added to the fee
+                Ok((fee_without_change + v, Vec::new()))
             },
         }
     }
 
+    /// Split the sender's excess, the fee and any burned value over one kernel per recipient. The first kernel uses the
+    /// sender's nonce and takes the remainder of the fee split, the other kernels use fresh nonces.
+    fn build_recipient_kernels(
+        ids: &[u64],
+        amounts: &[MicroTari],
+        excess_blinding_factor: &BlindingFactor,
+        private_nonce: &PrivateKey,
+        metadata: &TransactionMetadata,
+    ) -> Vec<RecipientKernelInfo>
+    {
+        let num_kernels = amounts.len() as u64;
+        let fee_part = MicroTari(metadata.fee.0 / num_kernels);
+        let mut remaining_excess = excess_blinding_factor.clone();
+        let mut kernels = Vec::with_capacity(amounts.len());
+        for (i, (tx_id, amount)) in ids.iter().zip(amounts).enumerate() {
+            let is_first = i == 0;
+            // The excess shares must add up to the sender's excess, so the last kernel takes what is left
+            let excess_share = if i + 1 == amounts.len() {
+                remaining_excess.clone()
+            } else {
+                PrivateKey::random(&mut OsRng)
+            };
+            remaining_excess = &remaining_excess - &excess_share;
+            let nonce = if is_first {
+                private_nonce.clone()
+            } else {
+                PrivateKey::random(&mut OsRng)
+            };
+            let kernel_metadata = TransactionMetadata {
+                fee: if is_first {
+                    metadata.fee - MicroTari(fee_part.0 * (num_kernels - 1))
+                } else {
+                    fee_part
+                },
+                burned_value: if is_first { metadata.burned_value } else { MicroTari(0) },
+                ..metadata.clone()
+            };
+            kernels.push(RecipientKernelInfo {
+                tx_id: *tx_id,
+                amount: *amount,
+                metadata: kernel_metadata,
+                public_excess: PublicKey::from_secret_key(&excess_share),
+                excess_blinding_factor: excess_share,
+                public_nonce: PublicKey::from_secret_key(&nonce),
+                private_nonce: nonce,
+                reply: None,
+            });
+        }
+        kernels
+    }
+
     fn check_value<T>(name: &str, val: &Option<T>, vec: &mut Vec<String>) {
         if val.is_none() {
             vec.push(name.to_string());
@@ -216,7 +339,19 @@ impl SenderTransactionInitializer {
     /// error (so that you can continue building) along with a string listing the missing fields.
     /// If all the input data is present, but one or more fields are invalid, the function will return a
     /// `SenderTransactionProtocol` instance in the Failed state.
-    pub fn build<D: Digest>(mut self, factories: &CryptoFactories) -> Result<SenderTransactionProtocol, BuildError> {
+    pub fn build<D: Digest>(self, factories: &CryptoFactories) -> Result<SenderTransactionProtocol, BuildError> {
+        self.build_with_progress::<D>(factories, &|_, _| {})
+    }
+
+    /// Construct a `SenderTransactionProtocol` instance like `build`. The range proofs of the outputs are generated in
+    /// parallel, and `progress` is called with the number of completed outputs and the total number of outputs each
+    /// time the range proof of an output is done.
+    pub fn build_with_progress<D: Digest>(
+        mut self,
+        factories: &CryptoFactories,
+        progress: &(dyn Fn(usize, usize) + Sync),
+    ) -> Result<SenderTransactionProtocol, BuildError>
+    {
         // Compile a list of all data that is missing
         let mut message = Vec::new();
         Self::check_value("Missing Lock Height", &self.lock_height, &mut message);
@@ -236,21 +371,34 @@ impl SenderTransactionInitializer {
         if !message.is_empty() {
             return self.build_err(&message.join(","));
         }
+        if (0..self.amounts.size())
+            .filter_map(|i| self.amounts.get_item(i))
+            .any(|a| *a < self.min_output_value) ||
+            self.outputs.iter().any(|o| o.value < self.min_output_value)
+        {
+            return self.build_err("Output value is less than the minimum output value");
+        }
         // Everything is here. Let's send some Tari!
         // Calculate the fee based on whether we need to add a residual change output or not
-        let (total_fee, change) = match self.add_change_if_required() {
-            Ok((fee, change)) => (fee, change),
+        let (total_fee, change_amounts) = match self.add_change_if_required() {
+            Ok((fee, change_amounts)) => (fee, change_amounts),
             Err(e) => return self.build_err(&e),
         };
         // Some checks on the fee
-        if total_fee < MINIMUM_TRANSACTION_FEE {
+        if total_fee < self.min_fee {
             return self.build_err("Fee is less than the minimum");
         }
 
+        let total_outputs = self.outputs.len();
+        let completed_outputs = AtomicUsize::new(0);
         let outputs = match self
             .outputs
-            .iter()
-            .map(|o| o.as_transaction_output(factories))
+            .par_iter()
+            .map(|o| {
+                let output = o.as_transaction_output_with_min_value(factories, self.min_output_value);
+                progress(completed_outputs.fetch_add(1, Ordering::SeqCst) + 1, total_outputs);
+                output
+            })
             .collect::<Result<Vec<TransactionOutput>, _>>()
         {
             Ok(o) => o,
@@ -277,18 +425,29 @@ impl SenderTransactionInitializer {
         for i in 0..num_ids {
             ids.push(calculate_tx_id::<D>(&public_nonce, i));
         }
+        let amounts = self.amounts.into_vec();
+        let metadata = TransactionMetadata {
+            fee: total_fee,
+            lock_height: self.lock_height.unwrap(),
+            meta_info: None,
+            linked_kernel: None,
+            burned_value: self.burned_value,
+        };
+        let recipient_kernels = if self.num_recipients > 1 {
+            Self::build_recipient_kernels(&ids, &amounts, &offset_blinding_factor, &nonce, &metadata)
+        } else {
+            Vec::new()
+        };
         let sender_info = RawTransactionInfo {
             num_recipients: self.num_recipients,
             amount_to_self,
             ids,
-            amounts: self.amounts.into_vec(),
-            change,
-            metadata: TransactionMetadata {
-                fee: total_fee,
-                lock_height: self.lock_height.unwrap(),
-                meta_info: None,
-                linked_kernel: None,
-            },
+            amounts,
+            change: change_amounts.iter().sum(),
+            change_amounts,
+            min_fee: self.min_fee,
+            min_output_value: self.min_output_value,
+            metadata,
             inputs: self.inputs,
             outputs,
             offset,
@@ -300,6 +459,7 @@ impl SenderTransactionInitializer {
             recipient_info,
             signatures: Vec::new(),
             message: self.message.unwrap_or_else(|| "".to_string()),
+            recipient_kernels,
         };
         let state = SenderState::Initializing(Box::new(sender_info));
         let state = state
@@ -314,19 +474,16 @@ impl SenderTransactionInitializer {
 #[cfg(test)]
 mod test {
     use crate::transactions::{
-        fee::{Fee, KERNEL_WEIGHT, WEIGHT_PER_INPUT, WEIGHT_PER_OUTPUT},
+        fee::{Fee, FeeModel, KERNEL_WEIGHT, WEIGHT_PER_INPUT, WEIGHT_PER_OUTPUT},
         helpers::{make_input, TestParams},
         tari_amount::*,
         transaction::{UnblindedOutput, MAX_TRANSACTION_INPUTS},
-        transaction_protocol::{
-            sender::SenderState,
-            transaction_initializer::SenderTransactionInitializer,
-            TransactionProtocolError,
-        },
-        types::CryptoFactories,
+        transaction_protocol::{sender::SenderState, transaction_initializer::SenderTransactionInitializer},
+        types::{CryptoFactories, PrivateKey},
     };
     use rand::rngs::OsRng;
-    use tari_crypto::common::Blake256;
+    use std::sync::{Arc, Mutex};
+    use tari_crypto::{common::Blake256, keys::SecretKey};
 
     /// One input, 2 outputs
     #[test]
@@ -408,6 +565,231 @@ mod test {
         }
     }
 
+    #[test]
+    fn custom_fee_model() {
+        /// Charges the same fee for every transaction regardless of its size
+        #[derive(Debug)]
+        struct FlatFeeModel;
+        impl FeeModel for FlatFeeModel {
+            fn calculate(&self, _: MicroTari, _: usize, _: usize, _: usize) -> MicroTari {
+                MicroTari(1000)
+            }
+        }
+
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(5000), &factories.commitment);
+        let output = UnblindedOutput::new(MicroTari(2000), p.spend_key, None);
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(output)
+            .with_input(utxo, input)
+            .with_change_secret(p.change_key)
+            .with_fee_per_gram(MicroTari(20))
+            .with_fee_model(Arc::new(FlatFeeModel));
+        let result = builder.build::<Blake256>(&factories).unwrap();
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.metadata.fee, MicroTari(1000), "Fee");
+            assert_eq!(info.outputs.len(), 2, "There should be 2 outputs");
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
+    /// The range proofs of the outputs are generated in parallel and each completed output is reported
+    #[test]
+    fn build_with_progress() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(50_000), &factories.commitment);
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_input(utxo, input)
+            .with_change_secret(p.change_key)
+            .with_fee_per_gram(MicroTari(20));
+        for _ in 0..4 {
+            builder.with_output(UnblindedOutput::new(
+                MicroTari(1_000),
+                PrivateKey::random(&mut OsRng),
+                None,
+            ));
+        }
+        let progress = Mutex::new(Vec::new());
+        let result = builder
+            .build_with_progress::<Blake256>(&factories, &|completed, total| {
+                progress.lock().unwrap().push((completed, total))
+            })
+            .unwrap();
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+        // The change output is included in the progress
+        assert_eq!(progress, vec![(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)]);
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.outputs.len(), 5, "There should be 5 outputs");
+            assert!(info
+                .outputs
+                .iter()
+                .all(|o| o.verify_range_proof(&factories.range_proof).unwrap()));
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
+    /// Change below the minimum output value is added to the fee
+    #[test]
+    fn dust_change_is_added_to_fee() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(5_000), &factories.commitment);
+        let fee_with_change = Fee::calculate(MicroTari(20), 1, 1, 2);
+        // Leave exactly 99 µT of change after paying for the change output
+        let output = UnblindedOutput::new(MicroTari(5_000) - fee_with_change - MicroTari(99), p.spend_key, None);
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(output)
+            .with_input(utxo, input)
+            .with_change_secret(p.change_key)
+            .with_min_output_value(MicroTari(100))
+            .with_fee_per_gram(MicroTari(20));
+        let result = builder.build::<Blake256>(&factories).unwrap();
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.metadata.fee, fee_with_change + MicroTari(99), "Fee");
+            assert_eq!(info.change, MicroTari(0), "Change");
+            assert_eq!(info.outputs.len(), 1, "There should be 1 output");
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
+    /// Change is split into one equal output per change secret, and the fee pays for every change output
+    #[test]
+    fn change_split_into_several_outputs() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(10_000), &factories.commitment);
+        let change_keys = vec![
+            p.change_key,
+            PrivateKey::random(&mut OsRng),
+            PrivateKey::random(&mut OsRng),
+        ];
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(UnblindedOutput::new(MicroTari(1_000), p.spend_key, None))
+            .with_input(utxo, input)
+            .with_change_secrets(change_keys)
+            .with_fee_per_gram(MicroTari(20));
+        let result = builder.build::<Blake256>(&factories).unwrap();
+        let expected_fee = Fee::calculate(MicroTari(20), 1, 1, 4);
+        let change = MicroTari(10_000) - MicroTari(1_000) - expected_fee;
+        let change_amounts = result.get_change_amounts().unwrap();
+        assert_eq!(change_amounts.len(), 3);
+        assert_eq!(change_amounts.iter().sum::<MicroTari>(), change);
+        assert!(change_amounts
+            .iter()
+            .all(|a| a.0 >= change.0 / 3 && a.0 <= change.0 / 3 + 2));
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.metadata.fee, expected_fee, "Fee");
+            assert_eq!(info.change, change, "Change");
+            assert_eq!(info.outputs.len(), 4, "There should be 4 outputs");
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
+    /// Fewer change outputs are created when the change can't cover several outputs above the minimum output value
+    #[test]
+    fn change_split_falls_back_to_fewer_outputs() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let fee_with_one_change = Fee::calculate(MicroTari(20), 1, 1, 2);
+        // Leave 150 µT of change after paying for a single change output, which can't be split into two outputs of
+        // at least 100 µT
+        let value = MicroTari(1_000) + fee_with_one_change + MicroTari(150);
+        let (utxo, input) = make_input(&mut OsRng, value, &factories.commitment);
+        let change_keys = vec![p.change_key, PrivateKey::random(&mut OsRng)];
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(UnblindedOutput::new(MicroTari(1_000), p.spend_key, None))
+            .with_input(utxo, input)
+            .with_change_secrets(change_keys)
+            .with_min_output_value(MicroTari(100))
+            .with_fee_per_gram(MicroTari(20));
+        let result = builder.build::<Blake256>(&factories).unwrap();
+        assert_eq!(result.get_change_amounts().unwrap(), vec![MicroTari(150)]);
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.metadata.fee, fee_with_one_change, "Fee");
+            assert_eq!(info.outputs.len(), 2, "There should be 2 outputs");
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
+    /// Outputs and amounts below the minimum output value are rejected
+    #[test]
+    fn below_min_output_value() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(5_000), &factories.commitment);
+        let mut builder = SenderTransactionInitializer::new(1);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_amount(0, MicroTari(99))
+            .with_input(utxo, input)
+            .with_change_secret(p.change_key)
+            .with_min_output_value(MicroTari(100))
+            .with_fee_per_gram(MicroTari(20));
+        let err = builder.build::<Blake256>(&factories).unwrap_err();
+        assert_eq!(err.message, "Output value is less than the minimum output value");
+        let mut builder = err.builder;
+        builder.with_amount(0, MicroTari(100));
+        assert!(builder.build::<Blake256>(&factories).is_ok());
+    }
+
+    /// A zero fee is rejected unless the minimum fee has been waived
+    #[test]
+    fn zero_fee_requires_waived_minimum() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(5_000), &factories.commitment);
+        let output = UnblindedOutput::new(MicroTari(5_000), p.spend_key, None);
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(output)
+            .with_input(utxo, input)
+            .with_fee_per_gram(MicroTari(0));
+        let err = builder.build::<Blake256>(&factories).unwrap_err();
+        assert_eq!(err.message, "Fee is less than the minimum");
+        let mut builder = err.builder;
+        builder.with_min_fee(MicroTari(0));
+        let result = builder.build::<Blake256>(&factories).unwrap();
+        if let SenderState::Finalizing(info) = result.state {
+            assert_eq!(info.metadata.fee, MicroTari(0), "Fee");
+            assert_eq!(info.min_fee, MicroTari(0), "Minimum fee");
+        } else {
+            panic!("There were no recipients, so we should be finalizing");
+        }
+    }
+
     /// Hit the edge case where our change isn't enough to cover the cost of an extra output
     #[test]
     fn change_edge_case() {
@@ -529,10 +911,21 @@ mod test {
             .with_fee_per_gram(MicroTari(20));
         let result = builder.build::<Blake256>(&factories).unwrap();
         // Peek inside and check the results
-        if let SenderState::Failed(TransactionProtocolError::UnsupportedError(s)) = result.state {
-            assert_eq!(s, "Multiple recipients are not supported yet")
+        if let SenderState::CollectingMultipleSignatures(info) = result.state {
+            assert_eq!(info.num_recipients, 2, "Number of receivers");
+            assert_eq!(info.recipient_kernels.len(), 2, "One kernel per receiver");
+            assert_eq!(info.recipient_kernels[0].tx_id, info.ids[0]);
+            assert_eq!(info.recipient_kernels[1].tx_id, info.ids[1]);
+            // Two kernels, one input, two recipient outputs, one output to self and one change output
+            let expected_fee = Fee::calculate(MicroTari(20), 2, 1, 4);
+            assert_eq!(info.metadata.fee, expected_fee, "Fee");
+            let kernel_fees = info.recipient_kernels.iter().map(|k| k.metadata.fee).sum::<MicroTari>();
+            assert_eq!(kernel_fees, expected_fee, "Kernel fees");
+            // The sender's excess is split over the kernels
+            let excess_sum = &info.recipient_kernels[0].public_excess + &info.recipient_kernels[1].public_excess;
+            assert_eq!(excess_sum, info.public_excess);
         } else {
-            panic!("We should not allow multiple recipients at this time");
+            panic!("There should be a kernel for each of the receivers");
         }
     }
 
@@ -28,7 +28,7 @@ use crate::{
         NewBlockTemplate,
     },
     chain_storage::{calculate_mmr_roots, is_utxo, BlockchainBackend},
-    consensus::{ConsensusConstants, ConsensusManager},
+    consensus::{version_bits, ConsensusConstants, ConsensusManager},
     transactions::{transaction::OutputFlags, types::CryptoFactories},
     validation::{
         helpers::{check_achieved_difficulty, check_median_timestamp},
@@ -38,7 +38,8 @@ use crate::{
     },
 };
 use log::*;
-use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex};
+use std::collections::HashSet;
+use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex, ByteArray};
 
 pub const LOG_TARGET: &str = "c::val::block_validators";
 
@@ -60,10 +61,12 @@ impl StatelessValidation<Block> for StatelessBlockValidator {
     /// The consensus checks that are done (in order of cheapest to verify to most expensive):
     /// 1. Is there precisely one Coinbase output and is it correctly defined?
     /// 1. Is the accounting correct?
+    /// 1. Are the input and output commitments unique within the block?
     /// 1. Are all inputs allowed to be spent (Are the feature flags satisfied)
     fn validate(&self, block: &Block) -> Result<(), ValidationError> {
         check_coinbase_output(block, &self.consensus_constants)?;
         check_block_weight(block, &self.consensus_constants)?;
+        check_duplicate_inputs_and_outputs(block)?;
         // Check that the inputs are are allowed to be spent
         block.check_stxo_rules().map_err(BlockValidationError::from)?;
         check_cut_through(block)?;
@@ -87,9 +90,12 @@ impl FullConsensusValidator {
 impl<B: BlockchainBackend> Validation<Block, B> for FullConsensusValidator {
     /// The consensus checks that are done (in order of cheapest to verify to most expensive):
     /// 1. Does the block satisfy the stateless checks?
+    /// 1. Are all outputs new to the UTXO set?
+    /// 1. Is the accounting correct and does every non-coinbase output meet the minimum output value?
     /// 1. Are all inputs currently in the UTXO set?
     /// 1. Are the block header MMR roots valid?
     /// 1. Is the block header timestamp less than the ftl?
+    /// 1. Is the block header version supported?
     /// 1. Is the block header timestamp greater than the median timestamp?
     /// 1. Is the Proof of Work valid?
     /// 1. Is the achieved difficulty of this block >= the target difficulty for this block?
@@ -102,12 +108,15 @@ impl<B: BlockchainBackend> Validation<Block, B> for FullConsensusValidator {
         );
         check_coinbase_output(block, &self.rules.consensus_constants())?;
         check_block_weight(block, &self.rules.consensus_constants())?;
+        check_duplicate_inputs_and_outputs(block)?;
         check_cut_through(block)?;
         block.check_stxo_rules().map_err(BlockValidationError::from)?;
+        check_outputs_are_not_utxos(block, db)?;
         check_accounting_balance(block, self.rules.clone(), &self.factories)?;
         check_inputs_are_utxos(block, db)?;
         check_mmr_roots(block, db)?;
         check_timestamp_ftl(&block.header, &self.rules)?;
+        check_header_version(&block.header, &self.rules)?;
         let tip_height = db
             .fetch_metadata()
             .map_err(|e| ValidationError::CustomError(e.to_string()))?
@@ -133,9 +142,12 @@ fn check_accounting_balance(
     );
     let offset = &block.header.total_kernel_offset;
     let total_coinbase = rules.calculate_coinbase_and_fees(block);
+    let min_output_value = rules
+        .consensus_constants_at_height(block.header.height)
+        .min_output_value();
     block
         .body
-        .validate_internal_consistency(&offset, total_coinbase, factories)
+        .validate_internal_consistency_with_min_output_value(&offset, total_coinbase, min_output_value, factories)
         .map_err(|err| {
             warn!(
                 target: LOG_TARGET,
@@ -191,6 +203,21 @@ fn check_inputs_are_utxos<B: BlockchainBackend>(block: &Block, db: &B) -> Result
     Ok(())
 }
 
+/// This function checks that none of the outputs in the block already exist in the UTXO set
+fn check_outputs_are_not_utxos<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), ValidationError> {
+    trace!(target: LOG_TARGET, "Checking output UTXOs do not exist",);
+    for output in block.body.outputs() {
+        if is_utxo(db, output.hash()).map_err(|e| ValidationError::CustomError(e.to_string()))? {
+            warn!(
+                target: LOG_TARGET,
+                "Block validation failed because the block has an output that is already in the UTXO set: {}", output
+            );
+            return Err(ValidationError::BlockError(BlockValidationError::OutputExistsInUtxoSet));
+        }
+    }
+    Ok(())
+}
+
 /// This function tests that the block timestamp is less than the ftl.
 fn check_timestamp_ftl(
     block_header: &BlockHeader,
@@ -201,7 +228,10 @@ fn check_timestamp_ftl(
         target: LOG_TARGET,
         "Checking timestamp is not too far in the future (FTL)",
     );
-    if block_header.timestamp > consensus_manager.consensus_constants().ftl() {
+    let ftl = consensus_manager
+        .consensus_constants_at_height(block_header.height)
+        .ftl();
+    if block_header.timestamp > ftl {
         warn!(
             target: LOG_TARGET,
             "Invalid Future Time Limit on block:{}",
@@ -214,6 +244,32 @@ fn check_timestamp_ftl(
     Ok(())
 }
 
+/// This function tests that the blockchain version of the header, ignoring the version bits used to signal for
+/// deployments, is not newer than the consensus blockchain version.
+fn check_header_version(
+    block_header: &BlockHeader,
+    consensus_manager: &ConsensusManager,
+) -> Result<(), ValidationError>
+{
+    trace!(target: LOG_TARGET, "Checking header version");
+    let blockchain_version = consensus_manager
+        .consensus_constants_at_height(block_header.height)
+        .blockchain_version();
+    if version_bits::base_version(block_header.version) > blockchain_version {
+        warn!(
+            target: LOG_TARGET,
+            "Block header version {} is newer than the blockchain version {} for block:{}",
+            block_header.version,
+            blockchain_version,
+            block_header.hash().to_hex()
+        );
+        return Err(ValidationError::BlockHeaderError(
+            BlockHeaderValidationError::InvalidVersion,
+        ));
+    }
+    Ok(())
+}
+
 fn check_mmr_roots<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), ValidationError> {
     trace!(target: LOG_TARGET, "Checking MMR roots match",);
     let template = NewBlockTemplate::from(block.clone());
@@ -263,3 +319,37 @@ fn check_cut_through(block: &Block) -> Result<(), ValidationError> {
     }
     Ok(())
 }
+
+/// This function checks that no two inputs and no two outputs in the block share a commitment
+fn check_duplicate_inputs_and_outputs(block: &Block) -> Result<(), ValidationError> {
+    trace!(
+        target: LOG_TARGET,
+        "Checking for duplicate inputs and outputs on block with hash {}",
+        block.hash().to_hex()
+    );
+    let mut commitments = HashSet::with_capacity(block.body.inputs().len());
+    for input in block.body.inputs() {
+        if !commitments.insert(input.commitment.as_bytes()) {
+            warn!(
+                target: LOG_TARGET,
+                "Block validation for {} failed: duplicate input {}",
+                block.hash().to_hex(),
+                input
+            );
+            return Err(ValidationError::BlockError(BlockValidationError::DuplicateInput));
+        }
+    }
+    let mut commitments = HashSet::with_capacity(block.body.outputs().len());
+    for output in block.body.outputs() {
+        if !commitments.insert(output.commitment.as_bytes()) {
+            warn!(
+                target: LOG_TARGET,
+                "Block validation for {} failed: duplicate output {}",
+                block.hash().to_hex(),
+                output
+            );
+            return Err(ValidationError::BlockError(BlockValidationError::DuplicateOutput));
+        }
+    }
+    Ok(())
+}
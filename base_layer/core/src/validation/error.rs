@@ -34,6 +34,8 @@ pub enum ValidationError {
     MaturityError,
     // Contains unknown inputs
     UnknownInputs,
+    // The transaction fee per gram is below the consensus minimum
+    FeeTooLow,
     // The transaction has some transaction error
     TransactionError(TransactionError),
     /// Custom error with string message
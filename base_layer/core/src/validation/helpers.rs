@@ -24,7 +24,7 @@ use crate::{
     blocks::blockheader::{BlockHeader, BlockHeaderValidationError},
     chain_storage::BlockchainBackend,
     consensus::ConsensusManager,
-    proof_of_work::PowError,
+    proof_of_work::{PowAlgorithm, PowError},
     validation::ValidationError,
 };
 use log::*;
@@ -67,6 +67,22 @@ pub fn check_median_timestamp<B: BlockchainBackend>(
     Ok(())
 }
 
+/// Merge mined proof of work can only be verified when the `merge_mining` feature is enabled. Without it, headers
+/// that claim to be merge mined are rejected rather than accepted with an unverified difficulty.
+pub fn check_pow_algorithm_supported(block_header: &BlockHeader) -> Result<(), ValidationError> {
+    if block_header.pow.pow_algo == PowAlgorithm::Monero && !cfg!(feature = "merge_mining") {
+        warn!(
+            target: LOG_TARGET,
+            "Merge mined block header {} can not be verified without the merge_mining feature",
+            block_header.hash().to_hex()
+        );
+        return Err(ValidationError::BlockHeaderError(
+            BlockHeaderValidationError::ProofOfWorkError(PowError::MergeMiningNotSupported),
+        ));
+    }
+    Ok(())
+}
+
 /// Calculates the achieved and target difficulties at the specified height and compares them.
 pub fn check_achieved_difficulty<B: BlockchainBackend>(
     db: &B,
@@ -79,6 +95,7 @@ pub fn check_achieved_difficulty<B: BlockchainBackend>(
         target: LOG_TARGET,
         "Checking block has acheived the required difficulty",
     );
+    check_pow_algorithm_supported(block_header)?;
     let achieved = block_header.achieved_difficulty();
     let mut target = 1.into();
     if block_header.height > 0 || rules.get_genesis_block_hash() != block_header.hash() {
@@ -108,3 +125,26 @@ pub fn check_achieved_difficulty<B: BlockchainBackend>(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge_mined_header_requires_merge_mining() {
+        let mut header = BlockHeader::new(1);
+        assert!(check_pow_algorithm_supported(&header).is_ok());
+        header.pow.pow_algo = PowAlgorithm::Monero;
+        let result = check_pow_algorithm_supported(&header);
+        if cfg!(feature = "merge_mining") {
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(
+                result,
+                Err(ValidationError::BlockHeaderError(
+                    BlockHeaderValidationError::ProofOfWorkError(PowError::MergeMiningNotSupported)
+                ))
+            );
+        }
+    }
+}
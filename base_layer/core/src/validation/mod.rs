@@ -34,6 +34,7 @@ mod traits;
 pub mod block_validators;
 pub mod mocks;
 pub use error::ValidationError;
+pub(crate) use helpers::check_pow_algorithm_supported;
 pub use traits::{StatelessValidation, StatelessValidator, Validation, Validator};
 pub mod accum_difficulty_validators;
 pub mod transaction_validators;
@@ -22,7 +22,8 @@
 
 use crate::{
     chain_storage::{is_stxo, is_utxo, BlockchainBackend},
-    transactions::{transaction::Transaction, types::CryptoFactories},
+    consensus::ConsensusManager,
+    transactions::{tari_amount::MicroTari, transaction::Transaction, types::CryptoFactories},
     validation::{StatelessValidation, Validation, ValidationError},
 };
 use log::*;
@@ -33,43 +34,58 @@ pub const LOG_TARGET: &str = "c::val::transaction_validators";
 /// This validator will only check that a transaction is internally consistent. It requires no state information.
 pub struct StatelessTxValidator {
     factories: CryptoFactories,
+    min_output_value: MicroTari,
 }
 
 impl StatelessTxValidator {
     pub fn new(factories: CryptoFactories) -> Self {
-        Self { factories }
+        Self {
+            factories,
+            min_output_value: MicroTari(0),
+        }
+    }
+
+    /// Also check that every output proves a value of at least the consensus `min_output_value`. This validator has no
+    /// access to the consensus rules, so the minimum is not checked unless it is provided here.
+    pub fn with_min_output_value(mut self, min_output_value: MicroTari) -> Self {
+        self.min_output_value = min_output_value;
+        self
     }
 }
 
 impl StatelessValidation<Transaction> for StatelessTxValidator {
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
-        verify_tx(tx, &self.factories)?;
+        verify_tx(tx, &self.factories, self.min_output_value)?;
         Ok(())
     }
 }
 
 /// This validator will perform a full verification of the transaction. In order the following will be checked:
-/// Transaction integrity, All inputs exist in the backend, All timelocks (kernel lock heights and output maturities)
-/// have passed
+/// Transaction integrity, Every output value meets the consensus minimum, The fee per gram meets the consensus minimum,
+/// All inputs exist in the backend, All timelocks
+/// (kernel lock heights and output maturities) have passed
 pub struct FullTxValidator {
+    rules: ConsensusManager,
     factories: CryptoFactories,
 }
 
 impl FullTxValidator {
-    pub fn new(factories: CryptoFactories) -> Self {
-        Self { factories }
+    pub fn new(rules: ConsensusManager, factories: CryptoFactories) -> Self {
+        Self { rules, factories }
     }
 }
 
 impl<B: BlockchainBackend> Validation<Transaction, B> for FullTxValidator {
     fn validate(&self, tx: &Transaction, db: &B) -> Result<(), ValidationError> {
-        verify_tx(tx, &self.factories)?;
-        verify_inputs(tx, db)?;
         let tip_height = db
             .fetch_metadata()
             .map_err(|e| ValidationError::CustomError(e.to_string()))?
             .height_of_longest_chain
             .unwrap_or(0);
+        let consensus_constants = self.rules.consensus_constants_at_height(tip_height + 1);
+        verify_tx(tx, &self.factories, consensus_constants.min_output_value())?;
+        verify_min_fee(tx, consensus_constants.min_fee_per_gram())?;
+        verify_inputs(tx, db)?;
         verify_timelocks(tx, tip_height)?;
         Ok(())
     }
@@ -117,13 +133,28 @@ impl<B: BlockchainBackend> Validation<Transaction, B> for TimeLockTxValidator {
     }
 }
 
-// This function verifies that the provided transaction is internally sound and that no funds were created in the
-// transaction.
-fn verify_tx(tx: &Transaction, factories: &CryptoFactories) -> Result<(), ValidationError> {
-    tx.validate_internal_consistency(factories, None)
+// This function verifies that the provided transaction is internally sound, that no funds were created in the
+// transaction and that every output proves a value of at least `min_output_value`.
+fn verify_tx(
+    tx: &Transaction,
+    factories: &CryptoFactories,
+    min_output_value: MicroTari,
+) -> Result<(), ValidationError>
+{
+    tx.validate_internal_consistency_with_min_output_value(factories, None, min_output_value)
         .map_err(ValidationError::TransactionError)
 }
 
+// This function checks that the fee paid by the provided transaction is at least the minimum fee per gram for its
+// weight.
+fn verify_min_fee(tx: &Transaction, min_fee_per_gram: MicroTari) -> Result<(), ValidationError> {
+    let min_fee = MicroTari::from(tx.calculate_weight() * u64::from(min_fee_per_gram));
+    if tx.body.get_total_fee() < min_fee {
+        return Err(ValidationError::FeeTooLow);
+    }
+    Ok(())
+}
+
 // This function checks that all the timelocks in the provided transaction pass. It checks kernel lock heights and
 // input maturities
 fn verify_timelocks(tx: &Transaction, current_height: u64) -> Result<(), ValidationError> {
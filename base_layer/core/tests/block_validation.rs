@@ -20,13 +20,28 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::block_builders::{chain_block_with_coinbase, create_coinbase, create_genesis_block_with_utxos};
 use tari_core::{
+    blocks::{BlockHeader, BlockValidationError},
     chain_storage::{BlockchainDatabase, BlockchainDatabaseConfig, MemoryDatabase, Validators},
-    consensus::{ConsensusManagerBuilder, Network},
-    transactions::types::{CryptoFactories, HashDigest},
+    consensus::{ConsensusConstantsBuilder, ConsensusManagerBuilder, Network},
+    helpers::create_mem_db,
+    transactions::{
+        helpers::{create_utxo, spend_utxos},
+        tari_amount::{uT, MicroTari},
+        transaction::{OutputFeatures, OutputFlags, TransactionError, TransactionInput, TransactionOutput},
+        types::{CryptoFactories, HashDigest},
+    },
+    txn_schema,
     validation::{
         accum_difficulty_validators::AccumDifficultyValidator,
         block_validators::{FullConsensusValidator, StatelessBlockValidator},
+        StatelessValidation,
+        Validation,
+        ValidationError,
     },
 };
 
@@ -46,3 +61,117 @@ fn test_genesis_block() {
     let result = db.add_block(block);
     assert!(result.is_ok());
 }
+
+fn non_coinbase_output(outputs: &[TransactionOutput]) -> TransactionOutput {
+    outputs
+        .iter()
+        .find(|o| !o.features.flags.contains(OutputFlags::COINBASE_OUTPUT))
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn test_duplicate_outputs_rejected() {
+    let factories = CryptoFactories::default();
+    let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+    let validator = StatelessBlockValidator::new(rules.consensus_constants());
+    let (mut block, _) =
+        create_genesis_block_with_utxos(&factories, &[MicroTari::from(10_000)], rules.consensus_constants());
+    assert!(validator.validate(&block).is_ok());
+
+    let output = non_coinbase_output(block.body.outputs());
+    block.body.add_output(output);
+    assert_eq!(
+        validator.validate(&block),
+        Err(ValidationError::BlockError(BlockValidationError::DuplicateOutput))
+    );
+}
+
+#[test]
+fn test_duplicate_inputs_rejected() {
+    let factories = CryptoFactories::default();
+    let rules = ConsensusManagerBuilder::new(Network::LocalNet).build();
+    let validator = StatelessBlockValidator::new(rules.consensus_constants());
+    let (mut block, _) =
+        create_genesis_block_with_utxos(&factories, &[MicroTari::from(10_000)], rules.consensus_constants());
+
+    let (utxo, _) = create_utxo(MicroTari::from(5_000), &factories, None);
+    let input = TransactionInput::new(OutputFeatures::default(), utxo.commitment);
+    block.body.add_input(input.clone());
+    assert!(validator.validate(&block).is_ok());
+
+    block.body.add_input(input);
+    assert_eq!(
+        validator.validate(&block),
+        Err(ValidationError::BlockError(BlockValidationError::DuplicateInput))
+    );
+}
+
+#[test]
+fn test_output_in_utxo_set_rejected() {
+    let factories = CryptoFactories::default();
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusManagerBuilder::new(network)
+        .build()
+        .consensus_constants()
+        .clone();
+    let (block0, _) = create_genesis_block_with_utxos(&factories, &[MicroTari::from(10_000)], &consensus_constants);
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants.clone())
+        .with_block(block0.clone())
+        .build();
+    let db = create_mem_db(&rules);
+    let validator = FullConsensusValidator::new(rules.clone(), factories.clone());
+
+    let (coinbase_utxo, coinbase_kernel, _) = create_coinbase(
+        &factories,
+        rules.emission_schedule().block_reward(1),
+        1 + consensus_constants.coinbase_lock_height(),
+    );
+    let mut block1 = BlockHeader::from_previous(&block0.header)
+        .into_builder()
+        .with_coinbase_utxo(coinbase_utxo, coinbase_kernel)
+        .build();
+    block1.body.add_output(non_coinbase_output(block0.body.outputs()));
+
+    let backend = db.db_read_access().unwrap();
+    assert_eq!(
+        validator.validate(&block1, &*backend),
+        Err(ValidationError::BlockError(BlockValidationError::OutputExistsInUtxoSet))
+    );
+}
+
+#[test]
+fn test_output_below_min_output_value_rejected() {
+    let factories = CryptoFactories::default();
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_min_output_value(100 * uT)
+        .build();
+    let (block0, outputs) =
+        create_genesis_block_with_utxos(&factories, &[MicroTari::from(10_000)], &consensus_constants);
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants.clone())
+        .with_block(block0.clone())
+        .build();
+    let db = create_mem_db(&rules);
+    let validator = FullConsensusValidator::new(rules.clone(), factories.clone());
+
+    // The range proofs of this transaction are constructed for a minimum output value of zero
+    let (tx, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1].clone()], to: vec![500 * uT]));
+    let (coinbase_utxo, coinbase_kernel, _) = create_coinbase(
+        &factories,
+        rules.emission_schedule().block_reward(1) + tx.body.get_total_fee(),
+        1 + consensus_constants.coinbase_lock_height(),
+    );
+    let template = chain_block_with_coinbase(&block0, vec![tx], coinbase_utxo, coinbase_kernel, &consensus_constants);
+    let block1 = db.calculate_mmr_roots(template).unwrap();
+
+    let backend = db.db_read_access().unwrap();
+    assert_eq!(
+        validator.validate(&block1, &*backend),
+        Err(ValidationError::TransactionError(TransactionError::ValidationError(
+            "Range proof could not be verified".into()
+        )))
+    );
+}
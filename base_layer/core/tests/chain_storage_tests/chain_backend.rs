@@ -21,11 +21,14 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use croaring::Bitmap;
+use lmdb_zero::{db, WriteTransaction};
+use std::path::Path;
 use tari_core::{
     blocks::BlockHeader,
     chain_storage::{
         create_lmdb_database,
         BlockchainBackend,
+        ChainStorageError,
         DbKey,
         DbKeyValuePair,
         DbTransaction,
@@ -34,6 +37,11 @@ use tari_core::{
         MetadataKey,
         MetadataValue,
         MmrTree,
+        LMDB_DB_KERNELS_HASH_TO_INDEX,
+        LMDB_DB_KERNEL_EXCESSES,
+        LMDB_DB_KERNEL_MMR_LEAF_COUNTS,
+        LMDB_DB_UTXO_COMMITMENTS,
+        LMDB_DB_UTXO_MMR_LEAF_COUNTS,
     },
     consensus::{ConsensusConstants, Network},
     helpers::create_orphan_block,
@@ -46,6 +54,7 @@ use tari_core::{
 };
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 use tari_mmr::{MmrCacheConfig, MutableMmr};
+use tari_storage::lmdb_store::LMDBBuilder;
 use tari_test_utils::paths::create_temporary_data_path;
 
 fn insert_contains_delete_and_fetch_header<T: BlockchainBackend>(mut db: T) {
@@ -674,6 +683,7 @@ fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>
     let utxo_hash1 = utxo1.hash();
     let kernel_hash1 = kernel1.hash();
     let rp_hash1 = utxo1.proof.hash();
+    let commitment1 = utxo1.commitment.clone();
 
     let mut txn = DbTransaction::new();
     txn.insert_utxo(utxo1, true);
@@ -688,6 +698,7 @@ fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>
     let utxo_hash2 = utxo2.hash();
     let kernel_hash2 = kernel2.hash();
     let rp_hash2 = utxo2.proof.hash();
+    let commitment2 = utxo2.commitment.clone();
 
     let mut txn = DbTransaction::new();
     txn.insert_utxo(utxo2, true);
@@ -719,6 +730,13 @@ fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>
     assert_eq!(db.contains(&DbKey::TransactionKernel(kernel_hash2.clone())), Ok(true));
     assert_eq!(db.contains(&DbKey::BlockHeader(0)), Ok(true));
     assert_eq!(db.contains(&DbKey::BlockHeader(1)), Ok(true));
+    assert_eq!(db.contains(&DbKey::UtxoCommitment(commitment1.clone())), Ok(false));
+    assert_eq!(db.contains(&DbKey::UtxoCommitment(commitment2.clone())), Ok(true));
+    assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Utxo, &utxo_hash1), Ok(Some(0)));
+    assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Utxo, &utxo_hash2), Ok(Some(1)));
+    assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Kernel, &kernel_hash2), Ok(Some(1)));
+    assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Utxo, 0), Ok(1));
+    assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Kernel, 1), Ok(2));
 
     let mut txn = DbTransaction::new();
     txn.delete(DbKey::BlockHeader(1));
@@ -740,6 +758,11 @@ fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>
     assert!(db.fetch_checkpoint(MmrTree::Utxo, 1).is_err());
     assert!(db.fetch_checkpoint(MmrTree::Kernel, 1).is_err());
     assert!(db.fetch_checkpoint(MmrTree::RangeProof, 1).is_err());
+    assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Kernel, &kernel_hash2), Ok(None));
+    assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Kernel, 0), Ok(1));
+    assert!(db.fetch_mmr_leaf_count(MmrTree::Kernel, 1).is_err());
+    assert_eq!(db.contains(&DbKey::UtxoCommitment(commitment1)), Ok(true));
+    assert_eq!(db.contains(&DbKey::UtxoCommitment(commitment2)), Ok(false));
 
     assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash1.clone())), Ok(true));
     assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash2.clone())), Ok(false));
@@ -908,6 +931,59 @@ fn lmdb_for_each_kernel() {
     }
 }
 
+fn insert_fetch_and_delete_kernel_excess<T: BlockchainBackend>(mut db: T) {
+    let kernel1 = create_test_kernel(100.into(), 0);
+    let kernel2 = create_test_kernel(200.into(), 1);
+    let hash1 = kernel1.hash();
+    assert_eq!(db.contains(&DbKey::KernelExcess(kernel1.excess.clone())), Ok(false));
+
+    let mut txn = DbTransaction::new();
+    txn.insert_kernel(kernel1.clone(), false);
+    txn.insert_kernel(kernel2.clone(), false);
+    assert!(db.write(txn).is_ok());
+    assert_eq!(db.contains(&DbKey::KernelExcess(kernel1.excess.clone())), Ok(true));
+    assert_eq!(db.contains(&DbKey::KernelExcess(kernel2.excess.clone())), Ok(true));
+    if let Some(DbValue::KernelExcess(retrieved_kernel)) =
+        db.fetch(&DbKey::KernelExcess(kernel1.excess.clone())).unwrap()
+    {
+        assert_eq!(*retrieved_kernel, kernel1);
+    } else {
+        assert!(false);
+    }
+
+    // Deleting the kernel also removes it from the excess index
+    let mut txn = DbTransaction::new();
+    txn.delete(DbKey::TransactionKernel(hash1));
+    txn.delete(DbKey::KernelExcess(kernel2.excess.clone()));
+    assert!(db.write(txn).is_ok());
+    assert_eq!(db.contains(&DbKey::KernelExcess(kernel1.excess)), Ok(false));
+    assert_eq!(db.contains(&DbKey::KernelExcess(kernel2.excess)), Ok(false));
+    assert_eq!(db.contains(&DbKey::TransactionKernel(kernel2.hash())), Ok(false));
+}
+
+#[test]
+fn memory_insert_fetch_and_delete_kernel_excess() {
+    let db = MemoryDatabase::<HashDigest>::default();
+    insert_fetch_and_delete_kernel_excess(db);
+}
+
+#[test]
+fn lmdb_insert_fetch_and_delete_kernel_excess() {
+    // Create temporary test folder
+    let temp_path = create_temporary_data_path();
+
+    // Perform test
+    {
+        let db = create_lmdb_database(&temp_path, MmrCacheConfig::default()).unwrap();
+        insert_fetch_and_delete_kernel_excess(db);
+    }
+
+    // Cleanup test data - in Windows the LMBD `set_mapsize` sets file size equals to map size; Linux use sparse files
+    if std::path::Path::new(&temp_path).exists() {
+        std::fs::remove_dir_all(&temp_path).unwrap();
+    }
+}
+
 fn for_each_header<T: BlockchainBackend>(mut db: T) {
     let header1 = BlockHeader::new(0);
     let header2 = BlockHeader::from_previous(&header1);
@@ -925,22 +1001,30 @@ fn for_each_header<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.contains(&DbKey::BlockHeader(key2)), Ok(true));
     assert_eq!(db.contains(&DbKey::BlockHeader(key3)), Ok(true));
 
-    let mut header1_found = false;
-    let mut header2_found = false;
-    let mut header3_found = false;
+    let mut headers = Vec::new();
     assert!(db
-        .for_each_header(|pair| {
-            let (key, header) = pair.unwrap();
-            if (key == key1) && (header == header1) {
-                header1_found = true;
-            } else if (key == key2) && (header == header2) {
-                header2_found = true;
-            } else if (key == key3) && (header == header3) {
-                header3_found = true;
-            }
+        .for_each_header(key1..=key3, |pair| {
+            headers.push(pair.unwrap());
         })
         .is_ok());
-    assert!(header1_found & header2_found & header3_found);
+    assert_eq!(headers, vec![
+        (key1, header1),
+        (key2, header2.clone()),
+        (key3, header3.clone())
+    ]);
+
+    let mut headers = Vec::new();
+    let mut missing = Vec::new();
+    assert!(db
+        .for_each_header(key2..=key3 + 1, |pair| match pair {
+            Ok(pair) => headers.push(pair),
+            Err(e) => missing.push(e),
+        })
+        .is_ok());
+    assert_eq!(headers, vec![(key2, header2), (key3, header3)]);
+    assert_eq!(missing, vec![ChainStorageError::ValueNotFound(DbKey::BlockHeader(
+        key3 + 1
+    ))]);
 }
 
 #[test]
@@ -1375,3 +1459,119 @@ fn lmdb_fetch_last_header() {
         std::fs::remove_dir_all(&temp_path).unwrap();
     }
 }
+
+// Empties the named LMDB databases to reproduce a store created before those databases were introduced.
+fn clear_lmdb_databases(path: &Path, names: &[&str]) {
+    let mut builder = LMDBBuilder::new()
+        .set_path(path.to_str().unwrap())
+        .set_environment_size(50000)
+        .set_max_number_of_databases(20);
+    for name in names {
+        builder = builder.add_database(name, db::CREATE);
+    }
+    let store = builder.build().unwrap();
+    let txn = WriteTransaction::new(store.env()).unwrap();
+    for name in names {
+        txn.access().clear_db(store.get_handle(name).unwrap().db()).unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn lmdb_rebuild_kernel_excess_index() {
+    // Create temporary test folder
+    let temp_path = create_temporary_data_path();
+
+    // Perform test
+    let kernel = create_test_kernel(5.into(), 0);
+    let hash = kernel.hash();
+    {
+        let mut db = create_lmdb_database(&temp_path, MmrCacheConfig::default()).unwrap();
+        let mut txn = DbTransaction::new();
+        txn.insert_kernel(kernel.clone(), true);
+        assert!(db.write(txn).is_ok());
+    }
+    clear_lmdb_databases(&temp_path, &[LMDB_DB_KERNEL_EXCESSES]);
+    {
+        let db = create_lmdb_database(&temp_path, MmrCacheConfig::default()).unwrap();
+        assert_eq!(db.contains(&DbKey::TransactionKernel(hash.clone())), Ok(true));
+        assert_eq!(db.contains(&DbKey::KernelExcess(kernel.excess.clone())), Ok(true));
+        if let Some(DbValue::KernelExcess(retrieved_kernel)) =
+            db.fetch(&DbKey::KernelExcess(kernel.excess.clone())).unwrap()
+        {
+            assert_eq!(*retrieved_kernel, kernel);
+        } else {
+            assert!(false);
+        }
+    }
+
+    // Cleanup test data - in Windows the LMBD `set_mapsize` sets file size equals to map size; Linux use sparse files
+    if std::path::Path::new(&temp_path).exists() {
+        std::fs::remove_dir_all(&temp_path).unwrap();
+    }
+}
+
+#[test]
+fn lmdb_rebuild_mmr_indexes_and_leaf_counts() {
+    // Create temporary test folder
+    let temp_path = create_temporary_data_path();
+
+    // Perform test
+    let factories = CryptoFactories::default();
+    let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
+    let (utxo2, _) = create_utxo(MicroTari(15_000), &factories, None);
+    let (utxo3, _) = create_utxo(MicroTari(20_000), &factories, None);
+    let kernel1 = create_test_kernel(100.into(), 0);
+    let kernel2 = create_test_kernel(200.into(), 0);
+    let header1 = BlockHeader::new(0);
+    let header2 = BlockHeader::from_previous(&header1);
+    let utxo_hash3 = utxo3.hash();
+    let kernel_hash1 = kernel1.hash();
+    let kernel_hash2 = kernel2.hash();
+    let commitments = vec![
+        utxo1.commitment.clone(),
+        utxo2.commitment.clone(),
+        utxo3.commitment.clone(),
+    ];
+    {
+        let mut db = create_lmdb_database(&temp_path, MmrCacheConfig::default()).unwrap();
+        let mut txn = DbTransaction::new();
+        txn.insert_utxo(utxo1, true);
+        txn.insert_kernel(kernel1, true);
+        txn.insert_header(header1);
+        txn.commit_block();
+        assert!(db.write(txn).is_ok());
+
+        let mut txn = DbTransaction::new();
+        txn.insert_utxo(utxo2, true);
+        txn.insert_utxo(utxo3, true);
+        txn.insert_kernel(kernel2, true);
+        txn.insert_header(header2);
+        txn.commit_block();
+        assert!(db.write(txn).is_ok());
+    }
+    clear_lmdb_databases(&temp_path, &[
+        LMDB_DB_UTXO_COMMITMENTS,
+        LMDB_DB_KERNELS_HASH_TO_INDEX,
+        LMDB_DB_UTXO_MMR_LEAF_COUNTS,
+        LMDB_DB_KERNEL_MMR_LEAF_COUNTS,
+    ]);
+    {
+        let db = create_lmdb_database(&temp_path, MmrCacheConfig::default()).unwrap();
+        for commitment in commitments {
+            assert_eq!(db.contains(&DbKey::UtxoCommitment(commitment)), Ok(true));
+        }
+        assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Utxo, &utxo_hash3), Ok(Some(2)));
+        assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Kernel, &kernel_hash1), Ok(Some(0)));
+        assert_eq!(db.fetch_mmr_leaf_index(MmrTree::Kernel, &kernel_hash2), Ok(Some(1)));
+        assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Utxo, 0), Ok(1));
+        assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Utxo, 1), Ok(3));
+        assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Kernel, 0), Ok(1));
+        assert_eq!(db.fetch_mmr_leaf_count(MmrTree::Kernel, 1), Ok(2));
+    }
+
+    // Cleanup test data - in Windows the LMBD `set_mapsize` sets file size equals to map size; Linux use sparse files
+    if std::path::Path::new(&temp_path).exists() {
+        std::fs::remove_dir_all(&temp_path).unwrap();
+    }
+}
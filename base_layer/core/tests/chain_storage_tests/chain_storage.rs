@@ -32,7 +32,7 @@ use crate::helpers::{
 };
 use croaring::Bitmap;
 use env_logger;
-use std::thread;
+use std::{thread, time::Duration};
 use tari_core::{
     blocks::{genesis_block, Block, BlockHash, BlockHeader},
     chain_storage::{
@@ -43,8 +43,11 @@ use tari_core::{
         BlockchainDatabaseConfig,
         ChainStorageError,
         DbKey,
+        DbKeyValuePair,
         DbTransaction,
         MemoryDatabase,
+        MetadataKey,
+        MetadataValue,
         MmrTree,
         Validators,
     },
@@ -54,6 +57,7 @@ use tari_core::{
     transactions::{
         helpers::{create_test_kernel, create_utxo, spend_utxos},
         tari_amount::{uT, MicroTari, T},
+        transaction::OutputFeatures,
         types::{CryptoFactories, HashDigest},
     },
     tx,
@@ -128,6 +132,25 @@ fn insert_and_fetch_header() {
     assert_eq!(store.fetch_header(42), Ok(header));
 }
 
+#[test]
+fn fetch_missing_header_heights() {
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let store = create_mem_db(&consensus_manager);
+    assert_eq!(store.fetch_missing_header_heights(), Ok(Vec::new()));
+
+    let mut header = BlockHeader::new(0);
+    header.height = 3;
+    let mut txn = DbTransaction::new();
+    txn.insert_header(header);
+    txn.insert(DbKeyValuePair::Metadata(
+        MetadataKey::ChainHeight,
+        MetadataValue::ChainHeight(Some(3)),
+    ));
+    assert!(store.commit(txn).is_ok());
+    assert_eq!(store.fetch_missing_header_heights(), Ok(vec![1, 2]));
+}
+
 #[test]
 fn insert_and_fetch_utxo() {
     let factories = CryptoFactories::default();
@@ -430,6 +453,42 @@ fn add_multiple_blocks() {
     assert_eq!(metadata.best_block.unwrap(), hash);
 }
 
+#[test]
+fn safe_mode_refuses_writes() {
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let store = create_mem_db(&consensus_manager);
+    let block0 = store.fetch_block(0).unwrap().block().clone();
+
+    let safe_mode = store.safe_mode_handle();
+    assert!(safe_mode.enter("Low disk space".to_string()).unwrap());
+    let result = append_block(
+        &store,
+        &block0,
+        vec![],
+        &consensus_manager.consensus_constants(),
+        1.into(),
+    );
+    assert_eq!(result, Err(ChainStorageError::SafeMode("Low disk space".to_string())));
+    assert_eq!(
+        store.rewind_to_height(0),
+        Err(ChainStorageError::SafeMode("Low disk space".to_string()))
+    );
+    // Queries are still answered
+    assert_eq!(store.get_metadata().unwrap().height_of_longest_chain, Some(0));
+
+    assert!(safe_mode.exit().unwrap());
+    append_block(
+        &store,
+        &block0,
+        vec![],
+        &consensus_manager.consensus_constants(),
+        1.into(),
+    )
+    .unwrap();
+    assert_eq!(store.get_metadata().unwrap().height_of_longest_chain, Some(1));
+}
+
 #[test]
 fn test_checkpoints() {
     let network = Network::LocalNet;
@@ -853,6 +912,55 @@ fn restore_metadata() {
     }
 }
 
+#[test]
+fn refuse_database_of_other_network() {
+    let path = create_temporary_data_path();
+    {
+        let validators = Validators::new(
+            MockValidator::new(true),
+            MockValidator::new(true),
+            MockAccumDifficultyValidator {},
+        );
+        let network = Network::LocalNet;
+        let rules = ConsensusManagerBuilder::new(network).build();
+        {
+            let db = create_lmdb_database(&path, MmrCacheConfig::default()).unwrap();
+            BlockchainDatabase::new(db, &rules, validators.clone(), BlockchainDatabaseConfig::default()).unwrap();
+        }
+
+        // A network with another genesis block may not use the same database
+        let factories = CryptoFactories::default();
+        let consensus_constants = ConsensusConstantsBuilder::new(network).build();
+        let (block0, _) = create_genesis_block(&factories, &consensus_constants);
+        let other_rules = ConsensusManagerBuilder::new(network)
+            .with_consensus_constants(consensus_constants)
+            .with_block(block0.clone())
+            .build();
+        let db = create_lmdb_database(&path, MmrCacheConfig::default()).unwrap();
+        match BlockchainDatabase::new(
+            db,
+            &other_rules,
+            validators.clone(),
+            BlockchainDatabaseConfig::default(),
+        ) {
+            Err(ChainStorageError::GenesisBlockMismatch { expected, found }) => {
+                assert_eq!(expected, block0.hash().to_hex());
+                assert_eq!(found, rules.get_genesis_block_hash().to_hex());
+            },
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("The database of another network was opened"),
+        }
+
+        // The network it was created for can still open it
+        let db = create_lmdb_database(&path, MmrCacheConfig::default()).unwrap();
+        assert!(BlockchainDatabase::new(db, &rules, validators, BlockchainDatabaseConfig::default()).is_ok());
+    }
+
+    if std::path::Path::new(&path).exists() {
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
+
 #[test]
 fn invalid_block() {
     let temp_path = create_temporary_data_path();
@@ -996,6 +1104,8 @@ fn orphan_cleanup_on_block_add() {
     let db = MemoryDatabase::<HashDigest>::default();
     let config = BlockchainDatabaseConfig {
         orphan_storage_capacity: 3,
+        max_allowed_reorg_depth: None,
+        max_orphan_age: None,
     };
     let store = BlockchainDatabase::new(db, &consensus_manager, validators, config).unwrap();
 
@@ -1031,6 +1141,60 @@ fn orphan_cleanup_on_block_add() {
     assert_eq!(store.fetch_orphan(orphan7_hash), Ok(orphan7));
 }
 
+#[test]
+fn stalled_orphans() {
+    let network = Network::LocalNet;
+    let factories = CryptoFactories::default();
+    let consensus_constants = ConsensusConstantsBuilder::new(network).build();
+    let (block0, output) = create_genesis_block(&factories, &consensus_constants);
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants)
+        .with_block(block0.clone())
+        .build();
+    let validators = Validators::new(
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockAccumDifficultyValidator {},
+    );
+    let db = MemoryDatabase::<HashDigest>::default();
+    let config = BlockchainDatabaseConfig {
+        max_orphan_age: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let mut store = BlockchainDatabase::new(db, &consensus_manager, validators, config).unwrap();
+    let mut blocks = vec![block0];
+    let mut outputs = vec![vec![output]];
+    let handle = store.stalled_orphans_handle();
+
+    let orphan1 = create_orphan_block(500, vec![], &consensus_manager.consensus_constants());
+    let orphan2 = create_orphan_block(5, vec![], &consensus_manager.consensus_constants());
+    assert_eq!(store.add_block(orphan1), Ok(BlockAddResult::OrphanBlock));
+    assert_eq!(store.add_block(orphan2), Ok(BlockAddResult::OrphanBlock));
+    let orphans = handle.get().unwrap().unwrap();
+    assert_eq!(orphans.tip_height, 0);
+    assert_eq!(orphans.orphan_count, 2);
+    // The orphans are only reported once they are older than the maximum orphan age
+    assert_eq!(store.take_stalled_orphans(), Ok(None));
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(store.take_stalled_orphans(), Ok(Some(orphans)));
+    assert_eq!(handle.get(), Ok(None));
+
+    // Orphans are no longer stalled once the chain tip advances
+    let orphan3 = create_orphan_block(30, vec![], &consensus_manager.consensus_constants());
+    assert_eq!(store.add_block(orphan3), Ok(BlockAddResult::OrphanBlock));
+    assert_eq!(handle.get().unwrap().unwrap().orphan_count, 1);
+    assert!(generate_new_block_with_achieved_difficulty(
+        &mut store,
+        &mut blocks,
+        &mut outputs,
+        vec![],
+        Difficulty::from(2),
+        &consensus_manager.consensus_constants()
+    )
+    .is_ok());
+    assert_eq!(handle.get(), Ok(None));
+}
+
 #[test]
 fn orphan_cleanup_on_reorg() {
     // Create Main Chain
@@ -1050,6 +1214,8 @@ fn orphan_cleanup_on_reorg() {
     let db = MemoryDatabase::<HashDigest>::default();
     let config = BlockchainDatabaseConfig {
         orphan_storage_capacity: 3,
+        max_allowed_reorg_depth: None,
+        max_orphan_age: None,
     };
     let mut store = BlockchainDatabase::new(db, &consensus_manager, validators, config).unwrap();
     let mut blocks = vec![block0];
@@ -1158,3 +1324,153 @@ fn orphan_cleanup_on_reorg() {
     assert_eq!(store.fetch_orphan(blocks[3].hash()), Ok(blocks[3].clone()));
     assert_eq!(store.fetch_orphan(blocks[4].hash()), Ok(blocks[4].clone()));
 }
+
+#[test]
+fn refuse_and_approve_deep_reorg() {
+    // Create Main Chain
+    let network = Network::LocalNet;
+    let factories = CryptoFactories::default();
+    let consensus_constants = ConsensusConstantsBuilder::new(network).build();
+    let (block0, output) = create_genesis_block(&factories, &consensus_constants);
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants)
+        .with_block(block0.clone())
+        .build();
+    let validators = Validators::new(
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockAccumDifficultyValidator {},
+    );
+    let db = MemoryDatabase::<HashDigest>::default();
+    let config = BlockchainDatabaseConfig {
+        max_allowed_reorg_depth: Some(2),
+        ..Default::default()
+    };
+    let mut store = BlockchainDatabase::new(db, &consensus_manager, validators, config).unwrap();
+    let mut blocks = vec![block0];
+    let mut outputs = vec![vec![output]];
+    // Block A1 to A4
+    for difficulty in &[2, 3, 3, 3] {
+        assert!(generate_new_block_with_achieved_difficulty(
+            &mut store,
+            &mut blocks,
+            &mut outputs,
+            vec![],
+            Difficulty::from(*difficulty),
+            &consensus_manager.consensus_constants()
+        )
+        .is_ok());
+    }
+
+    // Create Forked Chain
+    let consensus_manager_fork = ConsensusManagerBuilder::new(network)
+        .with_block(blocks[0].clone())
+        .build();
+    let mut orphan_store = create_mem_db(&consensus_manager_fork);
+    let mut orphan_blocks = vec![blocks[0].clone()];
+    let mut orphan_outputs = vec![outputs[0].clone()];
+    // Block B1 and B2
+    for difficulty in &[2, 10] {
+        assert!(generate_new_block_with_achieved_difficulty(
+            &mut orphan_store,
+            &mut orphan_blocks,
+            &mut orphan_outputs,
+            vec![],
+            Difficulty::from(*difficulty),
+            &consensus_manager_fork.consensus_constants()
+        )
+        .is_ok());
+    }
+
+    // The reorg from GB->A1->A2->A3->A4 to GB->B1->B2 removes 4 blocks and must be refused.
+    assert_eq!(
+        store.add_block(orphan_blocks[1].clone()),
+        Ok(BlockAddResult::OrphanBlock)
+    );
+    let refused_reorg = match store.add_block(orphan_blocks[2].clone()) {
+        Ok(BlockAddResult::ReorgRefused(reorg)) => reorg,
+        result => panic!("Expected the reorg to be refused, got {:?}", result),
+    };
+    assert_eq!(refused_reorg.fork_tip_hash, orphan_blocks[2].hash());
+    assert_eq!(refused_reorg.fork_height, 0);
+    assert_eq!(refused_reorg.depth, 4);
+    assert_eq!(store.get_height(), Ok(Some(4)));
+    assert_eq!(store.apply_approved_reorg(), Ok(None));
+
+    // Once approved by the node operator the reorg is applied
+    let handle = store.refused_reorg_handle();
+    assert_eq!(handle.get(), Ok(Some(refused_reorg)));
+    assert_eq!(handle.approve(), Ok(true));
+    match store.apply_approved_reorg() {
+        Ok(Some(BlockAddResult::ChainReorg(_))) => {},
+        result => panic!("Expected the approved reorg to be applied, got {:?}", result),
+    }
+    assert_eq!(store.get_height(), Ok(Some(2)));
+    assert_eq!(store.fetch_tip_header().unwrap().hash(), orphan_blocks[2].hash());
+    assert_eq!(handle.get(), Ok(None));
+    assert_eq!(handle.reject(), Ok(false));
+}
+
+#[test]
+fn fetch_block_with_utxo_and_kernel() {
+    let factories = CryptoFactories::default();
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+
+    // Block 1
+    let schema = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![6 * T, 3 * T])];
+    assert_eq!(
+        generate_new_block(
+            &mut db,
+            &mut blocks,
+            &mut outputs,
+            schema,
+            &consensus_manager.consensus_constants(),
+        ),
+        Ok(BlockAddResult::Ok)
+    );
+    // Block 2
+    let schema = vec![txn_schema!(from: vec![outputs[1][0].clone()], to: vec![3 * T, 1 * T])];
+    assert_eq!(
+        generate_new_block(
+            &mut db,
+            &mut blocks,
+            &mut outputs,
+            schema,
+            &consensus_manager.consensus_constants(),
+        ),
+        Ok(BlockAddResult::Ok)
+    );
+
+    let commitment = outputs[1][1]
+        .as_transaction_input(&factories.commitment, OutputFeatures::default())
+        .commitment;
+    let block = db.fetch_block_with_utxo(commitment).unwrap().unwrap();
+    assert_eq!(block.block().header.height, 1);
+    let commitment = outputs[2][0]
+        .as_transaction_input(&factories.commitment, OutputFeatures::default())
+        .commitment;
+    let block = db.fetch_block_with_utxo(commitment).unwrap().unwrap();
+    assert_eq!(block.block().header.height, 2);
+    // Spent outputs are no longer in the UTXO set
+    let commitment = outputs[1][0]
+        .as_transaction_input(&factories.commitment, OutputFeatures::default())
+        .commitment;
+    assert!(db.fetch_block_with_utxo(commitment).unwrap().is_none());
+
+    for height in 0..=2 {
+        for kernel in blocks[height].body.kernels() {
+            let block = db.fetch_block_with_kernel(kernel.excess.clone()).unwrap().unwrap();
+            assert_eq!(block.block().header.height, height as u64);
+        }
+    }
+    let unknown_excess = create_test_kernel(10.into(), 0).excess;
+    assert!(db.fetch_block_with_kernel(unknown_excess).unwrap().is_none());
+
+    // Rewinding removes the kernels of the rewound blocks from the excess index
+    let excess = blocks[2].body.kernels()[0].excess.clone();
+    assert!(db.fetch_kernel_with_excess(excess.clone()).unwrap().is_some());
+    assert!(db.rewind_to_height(1).is_ok());
+    assert!(db.fetch_kernel_with_excess(excess.clone()).unwrap().is_none());
+    assert!(db.fetch_block_with_kernel(excess).unwrap().is_none());
+}
@@ -532,7 +532,7 @@ fn setup_base_node_services(
             mempool,
             mempool_service_config,
         ))
-        .add_initializer(ChainMetadataServiceInitializer)
+        .add_initializer(ChainMetadataServiceInitializer::new(comms.node_identity()))
         .finish();
 
     let handles = runtime.block_on(fut).expect("Service initialization failed");
@@ -28,7 +28,7 @@ use helpers::{
     pow_blockchain::{append_to_pow_blockchain, create_test_pow_blockchain},
 };
 use tari_core::{
-    consensus::{ConsensusManagerBuilder, Network},
+    consensus::{ConsensusConstantsBuilder, ConsensusManagerBuilder, Network},
     helpers::create_mem_db,
     proof_of_work::PowAlgorithm,
 };
@@ -169,3 +169,52 @@ fn test_median_timestamp_odd_order() {
     // Median timestamp should be block 3 and not block 2
     assert_eq!(timestamp, prev_timestamp);
 }
+
+#[test]
+fn test_median_timestamp_count_at_fork_height() {
+    let network = Network::LocalNet;
+    let fork_constants = ConsensusConstantsBuilder::new(network)
+        .with_effective_from_height(5)
+        .with_median_timestamp_count(3)
+        .with_future_time_limit(60)
+        .build();
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(fork_constants)
+        .build();
+    assert_eq!(
+        consensus_manager
+            .consensus_constants_at_height(4)
+            .get_median_timestamp_count(),
+        11
+    );
+    assert_eq!(
+        consensus_manager
+            .consensus_constants_at_height(5)
+            .get_median_timestamp_count(),
+        3
+    );
+    assert_eq!(
+        consensus_manager
+            .consensus_constants_at_height(5)
+            .get_future_time_limit(),
+        60
+    );
+
+    let store = create_mem_db(&consensus_manager);
+    let pow_algos = vec![PowAlgorithm::Blake; 8];
+    create_test_pow_blockchain(&store, pow_algos, &consensus_manager.consensus_constants());
+    let header2_timestamp = store.fetch_header(2).unwrap().timestamp;
+    let header6_timestamp = store.fetch_header(6).unwrap().timestamp;
+
+    // Before the fork the median is taken over all of the available headers
+    let timestamp = consensus_manager
+        .get_median_timestamp_at_height(&*store.db_read_access().unwrap(), 4)
+        .expect("median returned an error");
+    assert_eq!(timestamp, header2_timestamp);
+
+    // After the fork only the last 3 headers are used
+    let timestamp = consensus_manager
+        .get_median_timestamp_at_height(&*store.db_read_access().unwrap(), 7)
+        .expect("median returned an error");
+    assert_eq!(timestamp, header6_timestamp);
+}
@@ -47,6 +47,7 @@ use tari_core::{
         MempoolServiceConfig,
         MempoolServiceError,
         MempoolValidators,
+        TxRejectionReason,
         TxStorageResponse,
     },
     proof_of_work::Difficulty,
@@ -59,7 +60,7 @@ use tari_core::{
     },
     tx,
     txn_schema,
-    validation::transaction_validators::TxInputAndMaturityValidator,
+    validation::transaction_validators::{FullTxValidator, TxInputAndMaturityValidator},
 };
 use tari_mmr::MmrCacheConfig;
 use tari_p2p::{services::liveness::LivenessConfig, tari_message::TariMessageType};
@@ -470,6 +471,94 @@ fn test_orphaned_mempool_transactions() {
     assert_eq!(stats.orphan_txs, 0);
 }
 
+#[test]
+fn test_min_fee_per_gram() {
+    let network = Network::LocalNet;
+    let factories = CryptoFactories::default();
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(
+            ConsensusConstantsBuilder::new(network)
+                .with_min_fee_per_gram(20 * uT)
+                .build(),
+        )
+        .build();
+    let mempool_validator =
+        MempoolValidators::new(FullTxValidator::new(rules, factories), TxInputAndMaturityValidator {});
+    let mempool = Mempool::new(store.clone(), MempoolConfig::default(), mempool_validator);
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(
+        &mut store,
+        &mut blocks,
+        &mut outputs,
+        txs,
+        &consensus_manager.consensus_constants(),
+    )
+    .unwrap();
+    mempool.process_published_block(blocks[1].clone()).unwrap();
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 10*uT),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    assert_eq!(
+        mempool.insert(tx[0].clone()).unwrap(),
+        TxStorageResponse::Rejected(TxRejectionReason::FeeTooLow)
+    );
+    assert_eq!(
+        mempool.insert(tx[1].clone()).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.total_txs, 1);
+    assert_eq!(stats.unconfirmed_txs, 1);
+}
+
+#[test]
+fn test_min_output_value() {
+    let network = Network::LocalNet;
+    let factories = CryptoFactories::default();
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(
+            ConsensusConstantsBuilder::new(network)
+                .with_min_output_value(100 * uT)
+                .build(),
+        )
+        .build();
+    let mempool_validator =
+        MempoolValidators::new(FullTxValidator::new(rules, factories), TxInputAndMaturityValidator {});
+    let mempool = Mempool::new(store.clone(), MempoolConfig::default(), mempool_validator);
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(
+        &mut store,
+        &mut blocks,
+        &mut outputs,
+        txs,
+        &consensus_manager.consensus_constants(),
+    )
+    .unwrap();
+    mempool.process_published_block(blocks[1].clone()).unwrap();
+
+    // The range proofs of the first transaction are not constructed for the consensus minimum output value
+    let below_min = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![500 * uT]);
+    let mut at_min = txn_schema!(from: vec![outputs[1][1].clone()], to: vec![500 * uT]);
+    at_min.min_output_value = 100 * uT;
+    let (tx, _) = schema_to_transaction(&[below_min, at_min]);
+    assert_eq!(
+        mempool.insert(tx[0].clone()).unwrap(),
+        TxStorageResponse::Rejected(TxRejectionReason::InvalidTransaction)
+    );
+    assert_eq!(
+        mempool.insert(tx[1].clone()).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.total_txs, 1);
+    assert_eq!(stats.unconfirmed_txs, 1);
+}
+
 #[test]
 fn request_response_get_stats() {
     let factories = CryptoFactories::default();
@@ -605,6 +694,68 @@ fn request_response_get_tx_state_with_excess_sig() {
     });
 }
 
+#[test]
+fn request_response_submit_transaction() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_coinbase_lockheight(100)
+        .with_emission_amounts(100_000_000.into(), 0.999, 100.into())
+        .build();
+    let (block0, utxo) = create_genesis_block(&factories, &consensus_constants);
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants)
+        .with_block(block0.clone())
+        .build();
+    let (mut alice, bob, _consensus_manager) = create_network_with_2_base_nodes_with_config(
+        &mut runtime,
+        BaseNodeServiceConfig::default(),
+        MmrCacheConfig { rewind_hist_len: 10 },
+        MempoolServiceConfig::default(),
+        LivenessConfig::default(),
+        consensus_manager,
+        temp_dir.path().to_str().unwrap(),
+    );
+
+    let (tx, _, _) = spend_utxos(txn_schema!(from: vec![utxo], to: vec![2 * T, 2 * T, 2 * T]));
+    let (orphan_tx, _, _) = tx!(1*T, fee: 100*uT);
+
+    // Bob validates each submitted transaction before responding with the pool it was stored in
+    runtime.block_on(async {
+        assert_eq!(
+            alice
+                .outbound_mp_interface
+                .submit_transaction(tx.clone())
+                .await
+                .unwrap(),
+            TxStorageResponse::PendingPool
+        );
+        assert_eq!(
+            alice
+                .outbound_mp_interface
+                .submit_transaction(orphan_tx.clone())
+                .await
+                .unwrap(),
+            TxStorageResponse::OrphanPool
+        );
+        // Submitting a transaction again reports where it is already stored
+        assert_eq!(
+            alice.outbound_mp_interface.submit_transaction(tx).await.unwrap(),
+            TxStorageResponse::PendingPool
+        );
+
+        alice.comms.shutdown().await;
+        bob.comms.shutdown().await;
+    });
+
+    let stats = bob.mempool.stats().unwrap();
+    assert_eq!(stats.total_txs, 2);
+    assert_eq!(stats.orphan_txs, 1);
+    assert_eq!(stats.timelocked_txs, 1);
+}
+
 #[test]
 fn receive_and_propagate_transaction() {
     let factories = CryptoFactories::default();
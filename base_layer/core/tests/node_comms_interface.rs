@@ -39,6 +39,7 @@ use tari_core::{
     transactions::{
         helpers::{create_test_kernel, create_utxo},
         tari_amount::MicroTari,
+        transaction::KernelFeatures,
         types::{CryptoFactories, HashDigest},
     },
     validation::transaction_validators::TxInputAndMaturityValidator,
@@ -75,7 +76,7 @@ fn outbound_get_metadata() {
     let mut outbound_nci = OutboundNodeCommsInterface::new(request_sender, block_sender);
 
     block_on(async {
-        let metadata = ChainMetadata::new(5, vec![0u8], 3, 5.into());
+        let metadata = ChainMetadata::new(5, vec![0u8], 3, 5.into(), 0);
         let metadata_response = NodeCommsResponse::ChainMetadata(metadata.clone());
         let (received_metadata, _) = futures::join!(
             outbound_nci.get_metadata(),
@@ -177,6 +178,175 @@ fn inbound_fetch_kernels() {
     });
 }
 
+#[test]
+fn inbound_fetch_kernel_by_excess() {
+    let (mempool, store) = new_mempool();
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let (block_event_publisher, _block_event_subscriber) = bounded(100);
+    let (request_sender, _) = reply_channel::unbounded();
+    let (block_sender, _) = futures_mpsc_channel_unbounded();
+    let outbound_nci = OutboundNodeCommsInterface::new(request_sender, block_sender);
+    let inbound_nch = InboundNodeCommsHandlers::new(
+        block_event_publisher,
+        store.clone(),
+        mempool,
+        consensus_manager,
+        outbound_nci,
+    );
+
+    let kernel = create_test_kernel(5.into(), 0);
+    let excess = kernel.excess.clone();
+    let mut txn = DbTransaction::new();
+    txn.insert_kernel(kernel.clone(), true);
+    assert!(store.commit(txn).is_ok());
+    let unknown_excess = create_test_kernel(10.into(), 0).excess;
+
+    test_async(move |rt| {
+        rt.spawn(async move {
+            if let Ok(NodeCommsResponse::TransactionKernels(received_kernels)) = inbound_nch
+                .handle_request(&NodeCommsRequest::FetchKernelByExcess(excess))
+                .await
+            {
+                assert_eq!(received_kernels.len(), 1);
+                assert_eq!(received_kernels[0], kernel);
+            } else {
+                assert!(false);
+            }
+
+            if let Ok(NodeCommsResponse::TransactionKernels(received_kernels)) = inbound_nch
+                .handle_request(&NodeCommsRequest::FetchKernelByExcess(unknown_excess))
+                .await
+            {
+                assert!(received_kernels.is_empty());
+            } else {
+                assert!(false);
+            }
+        });
+    });
+}
+
+#[test]
+fn inbound_get_total_burned() {
+    let (mempool, store) = new_mempool();
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let (block_event_publisher, _block_event_subscriber) = bounded(100);
+    let (request_sender, _) = reply_channel::unbounded();
+    let (block_sender, _) = futures_mpsc_channel_unbounded();
+    let outbound_nci = OutboundNodeCommsInterface::new(request_sender, block_sender);
+    let inbound_nch = InboundNodeCommsHandlers::new(
+        block_event_publisher,
+        store.clone(),
+        mempool,
+        consensus_manager,
+        outbound_nci,
+    );
+
+    let mut burn_kernel1 = create_test_kernel(5.into(), 0);
+    burn_kernel1.features = KernelFeatures::create_burn();
+    burn_kernel1.burned_value = MicroTari(1_000);
+    let block1 = BlockHeader::from_previous(&store.fetch_tip_header().unwrap())
+        .into_builder()
+        .add_kernels(vec![burn_kernel1, create_test_kernel(10.into(), 0)])
+        .build();
+    assert!(store.add_block(block1.clone()).is_ok());
+    assert_eq!(store.fetch_total_burned(), Ok(MicroTari(1_000)));
+
+    // The running total is reduced again when the block that burned the value is rewound
+    let mut burn_kernel2 = create_test_kernel(5.into(), 0);
+    burn_kernel2.features = KernelFeatures::create_burn();
+    burn_kernel2.burned_value = MicroTari(500);
+    let block2 = BlockHeader::from_previous(&block1.header)
+        .into_builder()
+        .add_kernels(vec![burn_kernel2])
+        .build();
+    assert!(store.add_block(block2).is_ok());
+    assert_eq!(store.fetch_total_burned(), Ok(MicroTari(1_500)));
+    assert!(store.rewind_to_height(1).is_ok());
+    assert_eq!(store.fetch_total_burned(), Ok(MicroTari(1_000)));
+
+    test_async(move |rt| {
+        rt.spawn(async move {
+            if let Ok(NodeCommsResponse::TotalBurned(total_burned)) =
+                inbound_nch.handle_request(&NodeCommsRequest::GetTotalBurned).await
+            {
+                assert_eq!(total_burned, MicroTari(1_000));
+            } else {
+                assert!(false);
+            }
+        });
+    });
+}
+
+#[test]
+fn inbound_get_tail_emission_height() {
+    let (mempool, store) = new_mempool();
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let tail_start_height = consensus_manager.emission_schedule().tail_start_height();
+    let (block_event_publisher, _block_event_subscriber) = bounded(100);
+    let (request_sender, _) = reply_channel::unbounded();
+    let (block_sender, _) = futures_mpsc_channel_unbounded();
+    let outbound_nci = OutboundNodeCommsInterface::new(request_sender, block_sender);
+    let inbound_nch =
+        InboundNodeCommsHandlers::new(block_event_publisher, store, mempool, consensus_manager, outbound_nci);
+
+    test_async(move |rt| {
+        rt.spawn(async move {
+            if let Ok(NodeCommsResponse::TailEmissionHeight(height)) = inbound_nch
+                .handle_request(&NodeCommsRequest::GetTailEmissionHeight)
+                .await
+            {
+                assert_eq!(height, tail_start_height);
+            } else {
+                assert!(false);
+            }
+        });
+    });
+}
+
+#[test]
+fn inbound_fetch_difficulty_stats() {
+    let (mempool, store) = new_mempool();
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let min_pow_difficulty = consensus_manager.consensus_constants().min_pow_difficulty();
+    let genesis_header = store.fetch_header(0).unwrap();
+    let (block_event_publisher, _block_event_subscriber) = bounded(100);
+    let (request_sender, _) = reply_channel::unbounded();
+    let (block_sender, _) = futures_mpsc_channel_unbounded();
+    let outbound_nci = OutboundNodeCommsInterface::new(request_sender, block_sender);
+    let inbound_nch =
+        InboundNodeCommsHandlers::new(block_event_publisher, store, mempool, consensus_manager, outbound_nci);
+
+    test_async(move |rt| {
+        rt.spawn(async move {
+            // The range is limited to the chain tip, which is the genesis block
+            if let Ok(NodeCommsResponse::DifficultyStats(stats)) = inbound_nch
+                .handle_request(&NodeCommsRequest::FetchDifficultyStats(0, 10))
+                .await
+            {
+                assert_eq!(stats.len(), 1);
+                assert_eq!(stats[0].height, 0);
+                assert_eq!(stats[0].timestamp, genesis_header.timestamp);
+                assert_eq!(stats[0].solve_time, 0);
+                assert_eq!(stats[0].target_difficulty, min_pow_difficulty);
+            } else {
+                assert!(false);
+            }
+            if let Ok(NodeCommsResponse::DifficultyStats(stats)) = inbound_nch
+                .handle_request(&NodeCommsRequest::FetchDifficultyStats(5, 10))
+                .await
+            {
+                assert!(stats.is_empty());
+            } else {
+                assert!(false);
+            }
+        });
+    });
+}
+
 #[test]
 fn outbound_fetch_headers() {
     let (request_sender, mut request_receiver) = reply_channel::unbounded();
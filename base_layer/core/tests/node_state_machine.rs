@@ -57,6 +57,7 @@ use tari_core::{
         },
         BaseNodeStateMachine,
         BaseNodeStateMachineConfig,
+        MaintenanceModeHandle,
     },
     consensus::{ConsensusConstantsBuilder, ConsensusManagerBuilder, Network},
     helpers::create_mem_db,
@@ -106,6 +107,7 @@ fn test_listening_lagging() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -167,6 +169,7 @@ fn test_event_channel() {
     let state_machine = BaseNodeStateMachine::new(
         &db,
         &node.outbound_nci,
+        consensus_manager.clone(),
         node.comms.peer_manager(),
         node.comms.connection_manager(),
         mock.subscriber(),
@@ -180,6 +183,7 @@ fn test_event_channel() {
     let PeerChainMetadata {
         node_id,
         chain_metadata,
+        ..
     } = random_peer_metadata(10, 5_000.into());
     runtime
         .block_on(mock.publish_chain_metadata(&node_id, &chain_metadata))
@@ -242,6 +246,7 @@ fn test_block_sync() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -281,6 +286,92 @@ fn test_block_sync() {
     });
 }
 
+#[test]
+fn test_block_sync_in_maintenance_mode() {
+    let mut runtime = Runtime::new().unwrap();
+    let factories = CryptoFactories::default();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_emission_amounts(100_000_000.into(), 0.999, 100.into())
+        .build();
+    let (mut prev_block, _) = create_genesis_block(&factories, &consensus_constants);
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants)
+        .with_block(prev_block.clone())
+        .build();
+    let (alice_node, bob_node, consensus_manager) = create_network_with_2_base_nodes_with_config(
+        &mut runtime,
+        BaseNodeServiceConfig::default(),
+        MmrCacheConfig::default(),
+        MempoolServiceConfig::default(),
+        LivenessConfig::default(),
+        consensus_manager,
+        temp_dir.path().to_str().unwrap(),
+    );
+    let state_machine_config = BaseNodeStateMachineConfig {
+        block_sync_config: BlockSyncConfig {
+            random_sync_peer_with_chain: true,
+            max_metadata_request_retry_attempts: 3,
+            max_header_request_retry_attempts: 20,
+            max_block_request_retry_attempts: 20,
+            max_add_block_retry_attempts: 3,
+            header_request_size: 5,
+            block_request_size: 1,
+            ..Default::default()
+        },
+    };
+    let maintenance_mode = MaintenanceModeHandle::default();
+    maintenance_mode.enter(Duration::from_secs(60)).unwrap();
+    let shutdown = Shutdown::new();
+    let mut alice_state_machine = BaseNodeStateMachine::new(
+        &alice_node.blockchain_db,
+        &alice_node.outbound_nci,
+        consensus_manager.clone(),
+        alice_node.comms.peer_manager(),
+        alice_node.comms.connection_manager(),
+        alice_node.chain_metadata_handle.get_event_stream(),
+        state_machine_config,
+        shutdown.to_signal(),
+    )
+    .with_maintenance_mode(maintenance_mode.clone());
+
+    runtime.block_on(async {
+        let alice_db = &alice_node.blockchain_db;
+        let bob_db = &bob_node.blockchain_db;
+        for _ in 1..6 {
+            prev_block = append_block(
+                bob_db,
+                &prev_block,
+                vec![],
+                &consensus_manager.consensus_constants(),
+                1.into(),
+            )
+            .unwrap();
+        }
+
+        // No blocks are added while the node is in maintenance
+        let network_tip = bob_db.get_metadata().unwrap();
+        let mut sync_peers = vec![bob_node.node_identity.node_id().clone()];
+        let state_event = BestChainMetadataBlockSyncInfo {}
+            .next_event(&mut alice_state_machine, &network_tip, &mut sync_peers)
+            .await;
+        assert_eq!(state_event, StateEvent::BlockSyncFailure);
+        assert_eq!(alice_db.get_height(), Ok(Some(0)));
+
+        // Blocks are synced again once the node resumes
+        assert!(maintenance_mode.exit().unwrap());
+        let state_event = BestChainMetadataBlockSyncInfo {}
+            .next_event(&mut alice_state_machine, &network_tip, &mut sync_peers)
+            .await;
+        assert_eq!(state_event, StateEvent::BlocksSynchronized);
+        assert_eq!(alice_db.get_height(), bob_db.get_height());
+
+        alice_node.comms.shutdown().await;
+        bob_node.comms.shutdown().await;
+    });
+}
+
 #[test]
 fn test_lagging_block_sync() {
     let mut runtime = Runtime::new().unwrap();
@@ -320,6 +411,7 @@ fn test_lagging_block_sync() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -415,6 +507,7 @@ fn test_block_sync_recovery() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -510,6 +603,7 @@ fn test_forked_block_sync() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -663,6 +757,7 @@ fn test_sync_peer_banning() {
     let mut alice_state_machine = BaseNodeStateMachine::new(
         &alice_node.blockchain_db,
         &alice_node.outbound_nci,
+        consensus_manager.clone(),
         alice_node.comms.peer_manager(),
         alice_node.comms.connection_manager(),
         alice_node.chain_metadata_handle.get_event_stream(),
@@ -0,0 +1,218 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::block_builders::chain_block;
+use tari_core::{
+    blocks::Block,
+    chain_storage::{BlockchainDatabase, MemoryDatabase},
+    consensus::{
+        version_bits::signals_bit,
+        ConsensusConstants,
+        ConsensusConstantsBuilder,
+        ConsensusManagerBuilder,
+        ConsensusManagerError,
+        Deployment,
+        DeploymentState,
+        Network,
+    },
+    helpers::create_mem_db,
+    transactions::types::HashDigest,
+};
+
+// Appends a block with each of the given header versions to the chain tip
+fn append_blocks_with_versions(
+    db: &BlockchainDatabase<MemoryDatabase<HashDigest>>,
+    chain_tip: Block,
+    versions: &[u16],
+    consensus: &ConsensusConstants,
+) -> Block
+{
+    let mut prev_block = chain_tip;
+    for version in versions {
+        let new_block = chain_block(&prev_block, Vec::new(), consensus);
+        let mut new_block = db.calculate_mmr_roots(new_block).unwrap();
+        new_block.header.version = *version;
+        new_block.header.timestamp = prev_block
+            .header
+            .timestamp
+            .increase(consensus.get_target_block_interval());
+        db.add_block(new_block.clone()).unwrap();
+        prev_block = new_block;
+    }
+    prev_block
+}
+
+#[test]
+fn deployment_activation() {
+    let network = Network::LocalNet;
+    let constants = ConsensusConstantsBuilder::new(network)
+        .with_version_bits_window(4, 3)
+        .with_deployment(Deployment {
+            name: "signalled",
+            bit: 1,
+            start_height: 4,
+            timeout_height: 20,
+        })
+        .with_deployment(Deployment {
+            name: "ignored",
+            bit: 2,
+            start_height: 4,
+            timeout_height: 8,
+        })
+        .build();
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(constants.clone())
+        .build();
+    let store = create_mem_db(&rules);
+    let signal = 0x0201;
+    let tip = store.fetch_block(0).unwrap().block().clone();
+    // Window 0 (heights 0 to 3) is before the start height
+    let tip = append_blocks_with_versions(&store, tip, &[1, 1, 1], &constants);
+    {
+        let db = store.db_read_access().unwrap();
+        assert_eq!(
+            rules.get_deployment_state(&*db, "signalled", 3).unwrap(),
+            DeploymentState::Defined
+        );
+        assert_eq!(
+            rules.get_deployment_state(&*db, "signalled", 4).unwrap(),
+            DeploymentState::Started
+        );
+        // Miners signal for both started deployments
+        let version = rules.get_block_version(&*db, 4).unwrap();
+        assert!(signals_bit(version, 1));
+        assert!(signals_bit(version, 2));
+        assert_eq!(
+            rules.get_deployment_state(&*db, "unknown", 4),
+            Err(ConsensusManagerError::UnknownDeployment("unknown".to_string()))
+        );
+    }
+
+    // Three of the four blocks in window 1 (heights 4 to 7) signal for the first deployment
+    let tip = append_blocks_with_versions(&store, tip, &[signal, 1, signal, signal], &constants);
+    {
+        let db = store.db_read_access().unwrap();
+        assert_eq!(rules.get_deployment_signal_count(&*db, "signalled", 5).unwrap(), 1);
+        assert_eq!(rules.get_deployment_signal_count(&*db, "signalled", 7).unwrap(), 3);
+        assert_eq!(rules.get_deployment_signal_count(&*db, "ignored", 7).unwrap(), 0);
+        assert_eq!(
+            rules.get_deployment_state(&*db, "signalled", 7).unwrap(),
+            DeploymentState::Started
+        );
+        assert_eq!(
+            rules.get_deployment_state(&*db, "signalled", 8).unwrap(),
+            DeploymentState::LockedIn
+        );
+        assert_eq!(
+            rules.get_deployment_state(&*db, "ignored", 8).unwrap(),
+            DeploymentState::Failed
+        );
+        let version = rules.get_block_version(&*db, 8).unwrap();
+        assert!(signals_bit(version, 1));
+        assert!(!signals_bit(version, 2));
+    }
+
+    append_blocks_with_versions(&store, tip, &[1, 1, 1, 1], &constants);
+    let db = store.db_read_access().unwrap();
+    assert_eq!(
+        rules.get_deployment_state(&*db, "signalled", 12).unwrap(),
+        DeploymentState::Active
+    );
+    assert_eq!(rules.get_block_version(&*db, 12).unwrap(), 1);
+}
+
+#[test]
+fn deployment_walk_starts_at_start_height() {
+    let network = Network::LocalNet;
+    let constants = ConsensusConstantsBuilder::new(network)
+        .with_version_bits_window(4, 3)
+        .with_deployment(Deployment {
+            name: "unaligned",
+            bit: 1,
+            start_height: 5,
+            timeout_height: 20,
+        })
+        .with_deployment(Deployment {
+            name: "never",
+            bit: 2,
+            start_height: std::u64::MAX,
+            timeout_height: std::u64::MAX,
+        })
+        .build();
+    let rules = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(constants.clone())
+        .build();
+    let store = create_mem_db(&rules);
+    let tip = store.fetch_block(0).unwrap().block().clone();
+    append_blocks_with_versions(&store, tip, &[1, 1, 1, 1, 1, 1, 1], &constants);
+    let db = store.db_read_access().unwrap();
+    // The deployment only starts at the first window boundary at or after its start height
+    assert_eq!(
+        rules.get_deployment_state(&*db, "unaligned", 7).unwrap(),
+        DeploymentState::Defined
+    );
+    assert_eq!(
+        rules.get_deployment_state(&*db, "unaligned", 8).unwrap(),
+        DeploymentState::Started
+    );
+    assert_eq!(
+        rules.get_deployment_state(&*db, "never", 1_000_000).unwrap(),
+        DeploymentState::Defined
+    );
+}
+
+#[test]
+fn invalid_version_bits_rejected() {
+    let network = Network::LocalNet;
+    let zero_window = ConsensusConstantsBuilder::new(network)
+        .with_version_bits_window(0, 0)
+        .build();
+    let result = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(zero_window)
+        .try_build();
+    match result {
+        Err(ConsensusManagerError::InvalidVersionBits(_)) => {},
+        _ => panic!("A zero version bits window should be rejected"),
+    }
+
+    let unreachable_threshold = ConsensusConstantsBuilder::new(network)
+        .with_version_bits_window(4, 5)
+        .build();
+    let result = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(unreachable_threshold)
+        .try_build();
+    match result {
+        Err(ConsensusManagerError::InvalidVersionBits(_)) => {},
+        _ => panic!("A threshold larger than the window should be rejected"),
+    }
+
+    let valid = ConsensusConstantsBuilder::new(network)
+        .with_version_bits_window(4, 4)
+        .build();
+    assert!(ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(valid)
+        .try_build()
+        .is_ok());
+}
@@ -129,6 +129,8 @@ fn wallet_base_node_integration_test() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses: Vec::new(),
+        additional_public_addresses: Vec::new(),
     };
     let alice_wallet_config = WalletConfig {
         comms_config: alice_comms_config,
@@ -180,6 +182,8 @@ fn wallet_base_node_integration_test() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses: Vec::new(),
+        additional_public_addresses: Vec::new(),
     };
     let bob_wallet_config = WalletConfig {
         comms_config: bob_comms_config,
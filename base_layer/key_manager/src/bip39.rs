@@ -0,0 +1,192 @@
+// Copyright 2019 The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! BIP-39 mnemonic sequences. Unlike the Tari mnemonic, which encodes a secret key directly, a BIP-39 sequence encodes
+//! between 128 and 256 bits of entropy followed by a checksum, and the seed is derived from the sequence and an
+//! optional passphrase with PBKDF2-HMAC-SHA512. Sequences in English are compatible with other BIP-39 wallets, the
+//! other word lists are stored without diacritics so their seeds are only compatible with Tari wallets.
+
+use crate::mnemonic::{find_mnemonic_index_from_word, find_mnemonic_word_from_index, MnemonicError, MnemonicLanguage};
+use digest::Digest;
+use hmac::Hmac;
+use sha2::{Sha256, Sha512};
+
+/// The number of PBKDF2 rounds used to derive the seed from a mnemonic sequence
+const BIP39_PBKDF2_ROUNDS: usize = 2048;
+/// The length of the seed derived from a mnemonic sequence, in bytes
+pub const BIP39_SEED_LENGTH: usize = 64;
+
+/// Encodes the entropy, which must be 16, 20, 24, 28 or 32 bytes long, as a BIP-39 mnemonic sequence in the given
+/// language
+pub fn from_entropy(entropy: &[u8], language: &MnemonicLanguage) -> Result<Vec<String>, MnemonicError> {
+    if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+        return Err(MnemonicError::ConversionProblem);
+    }
+    // One checksum bit for every 32 bits of entropy
+    let checksum_bits = entropy.len() / 4;
+    let mut data = entropy.to_vec();
+    data.push(Sha256::digest(entropy)[0]);
+    let word_count = (entropy.len() * 8 + checksum_bits) / 11;
+    (0..word_count)
+        .map(|i| find_mnemonic_word_from_index(read_bits(&data, i * 11, 11), language))
+        .collect()
+}
+
+/// Decodes a BIP-39 mnemonic sequence of 12, 15, 18, 21 or 24 words into the entropy it encodes, checking its
+/// checksum. The language of the mnemonic sequence is auto detected if no language is given.
+pub fn to_entropy(mnemonic_seq: &[String], language: Option<&MnemonicLanguage>) -> Result<Vec<u8>, MnemonicError> {
+    if mnemonic_seq.len() < 12 || mnemonic_seq.len() > 24 || mnemonic_seq.len() % 3 != 0 {
+        return Err(MnemonicError::ConversionProblem);
+    }
+    let language = match language {
+        Some(language) => language.clone(),
+        None => MnemonicLanguage::detect_language(mnemonic_seq)?,
+    };
+    let total_bits = mnemonic_seq.len() * 11;
+    let checksum_bits = total_bits / 33;
+    let entropy_bits = total_bits - checksum_bits;
+
+    let mut data = vec![0u8; (total_bits + 7) / 8];
+    for (i, word) in mnemonic_seq.iter().enumerate() {
+        let index = find_mnemonic_index_from_word(word, &language)?;
+        for bit in 0..11 {
+            if (index >> (10 - bit)) & 1 == 1 {
+                let pos = i * 11 + bit;
+                data[pos / 8] |= 1 << (7 - pos % 8);
+            }
+        }
+    }
+
+    let entropy = data[..entropy_bits / 8].to_vec();
+    let checksum = Sha256::digest(&entropy)[0] >> (8 - checksum_bits);
+    if read_bits(&data, entropy_bits, checksum_bits) != checksum as usize {
+        return Err(MnemonicError::InvalidChecksum);
+    }
+    Ok(entropy)
+}
+
+/// Derives the 64 byte BIP-39 seed from a mnemonic sequence and passphrase, which may be empty. The mnemonic sequence
+/// must have a valid checksum. The language of the mnemonic sequence is auto detected if no language is given.
+pub fn to_seed(
+    mnemonic_seq: &[String],
+    language: Option<&MnemonicLanguage>,
+    passphrase: &str,
+) -> Result<Vec<u8>, MnemonicError>
+{
+    let language = match language {
+        Some(language) => language.clone(),
+        None => MnemonicLanguage::detect_language(mnemonic_seq)?,
+    };
+    // The seed is derived from the words as they appear in the word list, so that the case of the given words does
+    // not change the seed
+    let entropy = to_entropy(mnemonic_seq, Some(&language))?;
+    let words = from_entropy(&entropy, &language)?;
+    let separator = match language {
+        MnemonicLanguage::Japanese => "\u{3000}",
+        _ => " ",
+    };
+    let salt = format!("mnemonic{}", passphrase);
+    let mut seed = vec![0u8; BIP39_SEED_LENGTH];
+    pbkdf2::pbkdf2::<Hmac<Sha512>>(
+        words.join(separator).as_bytes(),
+        salt.as_bytes(),
+        BIP39_PBKDF2_ROUNDS,
+        &mut seed,
+    );
+    Ok(seed)
+}
+
+// Reads `count` bits, most significant first, starting at bit `start` of the bytes
+fn read_bits(bytes: &[u8], start: usize, count: usize) -> usize {
+    (start..start + count).fold(0, |value, i| {
+        (value << 1) | ((bytes[i / 8] >> (7 - i % 8)) & 1) as usize
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_crypto::tari_utilities::hex::{from_hex, to_hex};
+
+    fn words(sequence: &str) -> Vec<String> {
+        sequence.split(' ').map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4a\
+                 b7c81b2f001698e7463b04",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd3\
+                 81ee6260e8d9739fce1f607",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+                "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a\
+                 10be8ed2a5e608d68f92fcc8",
+            ),
+        ];
+        for (entropy, sequence, seed) in vectors.iter() {
+            let entropy = from_hex(entropy).unwrap();
+            let sequence = words(sequence);
+            assert_eq!(from_entropy(&entropy, &MnemonicLanguage::English).unwrap(), sequence);
+            assert_eq!(to_entropy(&sequence, None).unwrap(), entropy);
+            assert_eq!(to_hex(&to_seed(&sequence, None, "TREZOR").unwrap()), *seed);
+        }
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        // The last word carries the checksum
+        let sequence =
+            words("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon");
+        assert_eq!(to_entropy(&sequence, None), Err(MnemonicError::InvalidChecksum));
+        assert_eq!(
+            to_seed(&sequence, Some(&MnemonicLanguage::English), ""),
+            Err(MnemonicError::InvalidChecksum)
+        );
+        assert_eq!(to_entropy(&sequence[..11], None), Err(MnemonicError::ConversionProblem));
+        assert_eq!(
+            from_entropy(&[0u8; 15], &MnemonicLanguage::English),
+            Err(MnemonicError::ConversionProblem)
+        );
+    }
+
+    #[test]
+    fn test_passphrase_changes_seed() {
+        let sequence = from_entropy(&[42u8; 32], &MnemonicLanguage::Spanish).unwrap();
+        let language = Some(&MnemonicLanguage::Spanish);
+        let seed = to_seed(&sequence, language, "").unwrap();
+        assert_eq!(seed.len(), BIP39_SEED_LENGTH);
+        assert_ne!(to_seed(&sequence, language, "25th word").unwrap(), seed);
+        assert_eq!(to_entropy(&sequence, language).unwrap(), vec![42u8; 32]);
+    }
+}
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mnemonic;
+use crate::{bip39, mnemonic};
 use derive_error::Error;
 use digest::Digest;
 use rand::{CryptoRng, Rng};
@@ -118,6 +118,48 @@ where
         }
     }
 
+    /// Creates a KeyManager from the provided sequence of mnemonic words using the specified language
+    pub fn from_mnemonic_with_language(
+        mnemonic_seq: &[String],
+        language: &mnemonic::MnemonicLanguage,
+        branch_seed: String,
+        primary_key_index: usize,
+    ) -> Result<KeyManager<K, D>, KeyManagerError>
+    {
+        match K::from_mnemonic_with_language(mnemonic_seq, language) {
+            Ok(master_key) => Ok(KeyManager {
+                master_key,
+                branch_seed,
+                primary_key_index,
+                digest_type: PhantomData,
+            }),
+            Err(e) => Err(KeyManagerError::from(e)),
+        }
+    }
+
+    /// Creates a KeyManager from the provided BIP-39 sequence of mnemonic words and passphrase, which may be empty. The
+    /// master key is the digest of the BIP-39 seed, so a different passphrase results in a different master key. The
+    /// language of the mnemonic sequence is auto detected if no language is given.
+    pub fn from_bip39_mnemonic(
+        mnemonic_seq: &[String],
+        language: Option<&mnemonic::MnemonicLanguage>,
+        passphrase: &str,
+        branch_seed: String,
+        primary_key_index: usize,
+    ) -> Result<KeyManager<K, D>, KeyManagerError>
+    {
+        let seed = bip39::to_seed(mnemonic_seq, language, passphrase)?;
+        match K::from_bytes(D::digest(&seed).as_slice()) {
+            Ok(master_key) => Ok(KeyManager {
+                master_key,
+                branch_seed,
+                primary_key_index,
+                digest_type: PhantomData,
+            }),
+            Err(e) => Err(KeyManagerError::from(e)),
+        }
+    }
+
     /// Derive a new private key from master key: derived_key=SHA256(master_key||branch_seed||index)
     pub fn derive_key(&self, key_index: usize) -> Result<DerivedKey<K>, ByteArrayError> {
         let concatenated = format!("{}{}", self.master_key.to_hex(), key_index.to_string());
@@ -136,7 +178,11 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{file_backup::*, key_manager::*};
+    use crate::{
+        file_backup::*,
+        key_manager::*,
+        mnemonic::{Mnemonic, MnemonicLanguage},
+    };
     use rand::rngs::OsRng;
     use sha2::Sha256;
     use std::fs::remove_file;
@@ -192,6 +238,59 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_from_bip39_mnemonic() {
+        let mnemonic_seq = crate::bip39::from_entropy(&[7u8; 32], &MnemonicLanguage::English).unwrap();
+        let branch_seed = "".to_string();
+        let km1 = KeyManager::<RistrettoSecretKey, Sha256>::from_bip39_mnemonic(
+            &mnemonic_seq,
+            None,
+            "",
+            branch_seed.clone(),
+            0,
+        )
+        .unwrap();
+        let km2 = KeyManager::<RistrettoSecretKey, Sha256>::from_bip39_mnemonic(
+            &mnemonic_seq,
+            Some(&MnemonicLanguage::English),
+            "",
+            branch_seed.clone(),
+            0,
+        )
+        .unwrap();
+        let km3 = KeyManager::<RistrettoSecretKey, Sha256>::from_bip39_mnemonic(
+            &mnemonic_seq,
+            None,
+            "passphrase",
+            branch_seed,
+            0,
+        )
+        .unwrap();
+        assert_eq!(km1.master_key, km2.master_key);
+        assert_ne!(km1.master_key, km3.master_key);
+    }
+
+    #[test]
+    fn test_from_mnemonic_with_language() {
+        let km = KeyManager::<RistrettoSecretKey, Sha256>::new(&mut OsRng);
+        let korean_seq = km.master_key.to_mnemonic(&MnemonicLanguage::Korean).unwrap();
+        let km_korean = KeyManager::<RistrettoSecretKey, Sha256>::from_mnemonic_with_language(
+            &korean_seq,
+            &MnemonicLanguage::Korean,
+            "".to_string(),
+            0,
+        )
+        .unwrap();
+        assert_eq!(km_korean.master_key, km.master_key);
+        assert!(KeyManager::<RistrettoSecretKey, Sha256>::from_mnemonic_with_language(
+            &korean_seq,
+            &MnemonicLanguage::English,
+            "".to_string(),
+            0
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_derive_and_next_key() {
         let mut km = KeyManager::<RistrettoSecretKey, Sha256>::new(&mut OsRng);
@@ -1,3 +1,4 @@
+pub mod bip39;
 pub mod diacritics;
 pub mod file_backup;
 pub mod key_manager;
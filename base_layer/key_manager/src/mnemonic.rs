@@ -45,6 +45,8 @@ pub enum MnemonicError {
     ByteArrayError(ByteArrayError),
     // Encoding and decoding a mnemonic sequence from bytes require exactly 32 bytes or 24 mnemonic words
     ConversionProblem,
+    // The checksum of a BIP-39 mnemonic sequence does not match its words
+    InvalidChecksum,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -69,6 +71,24 @@ impl MnemonicLanguage {
         Err(MnemonicError::UnknownLanguage)
     }
 
+    /// Detects the mnemonic language of a sequence of words, the language is the first defined language whose word
+    /// list contains every word of the sequence. Some words appear in more than one word list, so checking the whole
+    /// sequence avoids detecting the wrong language from a single shared word.
+    pub fn detect_language(mnemonic_seq: &[String]) -> Result<MnemonicLanguage, MnemonicError> {
+        if mnemonic_seq.is_empty() {
+            return Err(MnemonicError::UnknownLanguage);
+        }
+        for language in MnemonicLanguage::iterator() {
+            if mnemonic_seq
+                .iter()
+                .all(|word| find_mnemonic_index_from_word(word, language).is_ok())
+            {
+                return Ok((*language).clone());
+            }
+        }
+        Err(MnemonicError::UnknownLanguage)
+    }
+
     /// Returns an iterator for the MnemonicLanguage enum group to allow iteration over all defined languages
     pub fn iterator() -> Iter<'static, MnemonicLanguage> {
         static MNEMONIC_LANGUAGES: [MnemonicLanguage; 7] = [
@@ -85,7 +105,7 @@ impl MnemonicLanguage {
 }
 
 /// Finds and returns the index of a specific word in a mnemonic word list defined by the specified language
-fn find_mnemonic_index_from_word(word: &str, language: &MnemonicLanguage) -> Result<usize, MnemonicError> {
+pub(crate) fn find_mnemonic_index_from_word(word: &str, language: &MnemonicLanguage) -> Result<usize, MnemonicError> {
     let search_result: Result<usize, usize>;
     let lowercase_word = word.to_lowercase();
     match language {
@@ -116,7 +136,11 @@ fn find_mnemonic_index_from_word(word: &str, language: &MnemonicLanguage) -> Res
 }
 
 /// Finds and returns the word for a specific index in a mnemonic word list defined by the specified language
-fn find_mnemonic_word_from_index(index: usize, language: &MnemonicLanguage) -> Result<String, MnemonicError> {
+pub(crate) fn find_mnemonic_word_from_index(
+    index: usize,
+    language: &MnemonicLanguage,
+) -> Result<String, MnemonicError>
+{
     if index < MNEMONIC_ENGLISH_WORDS.len() {
         Ok(match language {
             // Select word according to specified language
@@ -167,7 +191,7 @@ pub fn from_secret_key<K: SecretKey>(k: &K, language: &MnemonicLanguage) -> Resu
 /// Generates a vector of bytes that represent the provided mnemonic sequence of words, the language of the mnemonic
 /// sequence is autodetected
 pub fn to_bytes(mnemonic_seq: &[String]) -> Result<Vec<u8>, MnemonicError> {
-    let language = MnemonicLanguage::from(&mnemonic_seq[0])?; // Autodetect language
+    let language = MnemonicLanguage::detect_language(mnemonic_seq)?; // Autodetect language
     to_bytes_with_language(mnemonic_seq, &language)
 }
 
@@ -309,6 +333,54 @@ mod test {
         assert!(MnemonicLanguage::from(&"desvelado".to_string()).is_err()); // Invalid Mnemonic Spanish word
     }
 
+    #[test]
+    fn test_sequence_language_detection() {
+        // Every language round trips through a secret key with autodetection
+        let desired_k = RistrettoSecretKey::random(&mut OsRng);
+        for language in MnemonicLanguage::iterator() {
+            let mnemonic_seq = desired_k.to_mnemonic(language).unwrap();
+            assert_eq!(MnemonicLanguage::detect_language(&mnemonic_seq), Ok(language.clone()));
+            assert_eq!(RistrettoSecretKey::from_mnemonic(&mnemonic_seq), Ok(desired_k.clone()));
+        }
+
+        // "fragile" is in both the English and the French word list, the rest of the sequence decides the language
+        let french_seq = vec!["fragile", "risque", "abaisser"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            MnemonicLanguage::detect_language(&french_seq),
+            Ok(MnemonicLanguage::French)
+        );
+
+        // A sequence that mixes languages, or has no words, has no language
+        let mixed_seq = vec!["trick", "risque"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            MnemonicLanguage::detect_language(&mixed_seq),
+            Err(MnemonicError::UnknownLanguage)
+        );
+        assert_eq!(
+            MnemonicLanguage::detect_language(&[]),
+            Err(MnemonicError::UnknownLanguage)
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_with_wrong_language() {
+        let desired_k = RistrettoSecretKey::random(&mut OsRng);
+        let english_seq = desired_k.to_mnemonic(&MnemonicLanguage::English).unwrap();
+        let spanish_seq = desired_k.to_mnemonic(&MnemonicLanguage::Spanish).unwrap();
+        assert_ne!(english_seq, spanish_seq);
+        assert_eq!(
+            RistrettoSecretKey::from_mnemonic_with_language(&spanish_seq, &MnemonicLanguage::Spanish),
+            Ok(desired_k)
+        );
+        assert!(RistrettoSecretKey::from_mnemonic_with_language(&english_seq, &MnemonicLanguage::Japanese).is_err());
+    }
+
     #[test]
     fn test_find_index_from_word_or_word_from_index() {
         // Encoding and Decoding using Chinese Simplified
@@ -185,6 +185,8 @@ mod pingpong {
             allow_test_addresses: true,
             listener_liveness_whitelist_cidrs: Vec::new(),
             listener_liveness_max_sessions: 0,
+            additional_listener_addresses: Vec::new(),
+            additional_public_addresses: Vec::new(),
         };
 
         let (comms, dht) = rt.block_on(initialize_comms(comms_config, publisher)).unwrap();
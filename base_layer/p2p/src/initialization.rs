@@ -31,7 +31,8 @@ use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{error::Error, iter, path::PathBuf, sync::Arc, time::Duration};
 use tari_comms::{
     backoff::ConstantBackoff,
-    peer_manager::NodeIdentity,
+    multiaddr::Multiaddr,
+    peer_manager::{NodeIdentity, NodeIdentityError},
     pipeline,
     pipeline::SinkService,
     tor,
@@ -52,6 +53,7 @@ pub enum CommsInitializationError {
     CommsBuilderError(CommsBuilderError),
     DhtInitializationError(DhtInitializationError),
     HiddenServiceBuilderError(tor::HiddenServiceBuilderError),
+    NodeIdentityError(NodeIdentityError),
     #[error(non_std, no_from, msg_embedded)]
     InvalidLivenessCidrs(String),
 }
@@ -83,6 +85,10 @@ pub struct CommsConfig {
     pub listener_liveness_max_sessions: usize,
     /// CIDR for addresses allowed to enter into liveness check mode on the listener.
     pub listener_liveness_whitelist_cidrs: Vec<String>,
+    /// Addresses the node listens on in addition to the listener address of the transport
+    pub additional_listener_addresses: Vec<Multiaddr>,
+    /// Publicly accessible addresses of the node, advertised to peers in addition to its public address
+    pub additional_public_addresses: Vec<Multiaddr>,
 }
 
 /// Initialize Tari Comms configured for tests
@@ -281,7 +287,12 @@ where
     let listener_liveness_whitelist_cidrs = parse_cidrs(&config.listener_liveness_whitelist_cidrs)
         .map_err(CommsInitializationError::InvalidLivenessCidrs)?;
 
+    for address in &config.additional_public_addresses {
+        config.node_identity.add_public_address(address.clone())?;
+    }
+
     let comms = builder
+        .with_additional_listener_addresses(config.additional_listener_addresses.clone())
         .with_listener_liveness_max_sessions(config.listener_liveness_max_sessions)
         .with_listener_liveness_whitelist_cidrs(listener_liveness_whitelist_cidrs)
         .with_dial_backoff(ConstantBackoff::new(Duration::from_millis(500)))
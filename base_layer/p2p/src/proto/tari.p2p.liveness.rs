@@ -10,6 +10,9 @@ pub struct PingPongMessage {
     /// Metadata attached to the message. The int32 key SHOULD always be one of the keys in `MetadataKey`.
     #[prost(map = "int32, bytes", tag = "3")]
     pub metadata: ::std::collections::HashMap<i32, std::vec::Vec<u8>>,
+    /// The sender's clock, as a unix timestamp in milliseconds, when the message was created. Zero if not provided.
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -26,6 +29,8 @@ pub enum PingPong {
 pub enum MetadataKey {
     /// The default key. This should never be used as it represents the absence of a key.
     None = 0,
-    /// The value for this key contains chain metadata
+    /// The value for this key contains unsigned chain metadata
     ChainMetadata = 1,
+    /// The value for this key contains chain metadata signed by the node that sent it
+    SignedChainMetadata = 2,
 }
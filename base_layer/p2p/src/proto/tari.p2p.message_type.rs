@@ -17,6 +17,8 @@ pub enum TariMessageType {
     MempoolResponse = 72,
     /// -- DAN Messages --
     TransactionFinalized = 73,
+    TransactionStatusQuery = 74,
+    TransactionStatusReply = 75,
     // -- Extended --
     Text = 225,
     TextAck = 226,
@@ -35,6 +35,8 @@ pub struct LivenessConfig {
     pub refresh_random_pool_interval: Duration,
     /// The ratio of random to neighbouring peers to include in ping rounds (Default: 0)
     pub random_peer_selection_ratio: f32,
+    /// The maximum offset of the local clock from network time before a warning is raised. (default: 60 seconds)
+    pub max_allowed_time_offset: Duration,
 }
 
 impl Default for LivenessConfig {
@@ -45,6 +47,7 @@ impl Default for LivenessConfig {
             refresh_neighbours_interval: Duration::from_secs(2 * 60),
             refresh_random_pool_interval: Duration::from_secs(2 * 60 * 60),
             random_peer_selection_ratio: 0.0,
+            max_allowed_time_offset: Duration::from_secs(60),
         }
     }
 }
@@ -45,6 +45,8 @@ pub enum LivenessRequest {
     AddNodeId(NodeId),
     /// Get stats for a monitored NodeId
     GetNodeIdStats(NodeId),
+    /// Get the estimated offset of the local clock from network time
+    GetNetworkTimeOffset,
 }
 
 /// Response type for `LivenessService`
@@ -60,6 +62,8 @@ pub enum LivenessResponse {
     NumActiveNeighbours(usize),
     NodeIdAdded,
     NodeIdStats(NodeStats),
+    /// The estimated offset in milliseconds of the local clock from network time, if enough peers have reported
+    NetworkTimeOffset(Option<i64>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -71,6 +75,9 @@ pub enum LivenessEvent {
     ReceivedPong(Box<PongEvent>),
     BroadcastedNeighbourPings(usize),
     BroadcastedMonitoredNodeIdPings(usize),
+    /// The estimated offset in milliseconds of the local clock from network time has exceeded the configured
+    /// `max_allowed_time_offset`. A positive offset means that the local clock is ahead of the network.
+    NetworkTimeOffsetExceeded(i64),
 }
 
 /// Repressents a pong event
@@ -167,6 +174,15 @@ impl LivenessHandle {
         }
     }
 
+    /// Get the estimated offset in milliseconds of the local clock from network time. A positive offset means that the
+    /// local clock is ahead of the network. Returns None if too few peers have reported their time.
+    pub async fn get_network_time_offset(&mut self) -> Result<Option<i64>, LivenessError> {
+        match self.handle.call(LivenessRequest::GetNetworkTimeOffset).await?? {
+            LivenessResponse::NetworkTimeOffset(offset) => Ok(offset),
+            _ => Err(LivenessError::UnexpectedApiResponse),
+        }
+    }
+
     /// Get stats for NodeId that is being monitored
     pub async fn get_node_id_stats(&mut self, node_id: NodeId) -> Result<NodeStats, LivenessError> {
         match self.handle.call(LivenessRequest::GetNodeIdStats(node_id)).await?? {
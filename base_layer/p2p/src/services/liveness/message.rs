@@ -23,6 +23,7 @@
 use crate::services::liveness::state::Metadata;
 
 pub use crate::proto::liveness::{PingPong, PingPongMessage};
+use chrono::Utc;
 use rand::{rngs::OsRng, RngCore};
 
 impl PingPongMessage {
@@ -31,6 +32,7 @@ impl PingPongMessage {
             ping_pong: ping_pong as i32,
             nonce,
             metadata: metadata.into(),
+            timestamp: Utc::now().timestamp_millis() as u64,
         }
     }
 
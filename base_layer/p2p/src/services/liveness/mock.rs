@@ -139,6 +139,7 @@ impl LivenessMock {
             GetNodeIdStats(_n) => reply_tx
                 .send(Ok(LivenessResponse::NodeIdStats(NodeStats::new())))
                 .unwrap(),
+            GetNetworkTimeOffset => reply_tx.send(Ok(LivenessResponse::NetworkTimeOffset(None))).unwrap(),
         }
     }
 }
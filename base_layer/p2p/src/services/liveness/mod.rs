@@ -43,7 +43,11 @@ mod peer_pool;
 mod service;
 mod state;
 
-use self::{message::PingPongMessage, service::LivenessService, state::LivenessState};
+use self::{
+    message::PingPongMessage,
+    service::{LivenessService, SERVICE_NAME},
+    state::LivenessState,
+};
 use crate::{
     comms_connector::PeerMessage,
     domain_message::DomainMessage,
@@ -59,6 +63,7 @@ use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -159,6 +164,10 @@ impl ServiceInitializer for LivenessInitializer {
             let outbound_handle = handles
                 .get_handle::<OutboundMessageRequester>()
                 .expect("Liveness service requires CommsOutbound service handle");
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
 
             if config.enable_auto_join {
                 match dht_requester.send_join().await {
@@ -186,7 +195,8 @@ impl ServiceInitializer for LivenessInitializer {
                 outbound_handle,
                 publisher,
                 shutdown,
-            );
+            )
+            .with_monitor(monitor);
             service.run().await;
             debug!(target: LOG_TARGET, "Liveness service has shut down");
         });
@@ -34,6 +34,7 @@ use crate::{
     services::liveness::{peer_pool::PeerPool, LivenessEvent, PongEvent},
     tari_message::TariMessageType,
 };
+use chrono::Utc;
 use futures::{future::Either, pin_mut, stream::StreamExt, SinkExt, Stream};
 use log::*;
 use std::{cmp, time::Instant};
@@ -50,10 +51,16 @@ use tari_comms_dht::{
     outbound::{DhtOutboundError, OutboundEncryption, OutboundMessageRequester},
     DhtRequester,
 };
-use tari_service_framework::RequestContext;
+use tari_service_framework::{
+    watchdog::{ServiceMonitor, Watchdog},
+    RequestContext,
+};
 use tari_shutdown::ShutdownSignal;
 use tokio::time;
 
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Liveness Service";
+
 /// Service responsible for testing Liveness of Peers.
 pub struct LivenessService<THandleStream, TPingStream> {
     config: LivenessConfig,
@@ -68,6 +75,7 @@ pub struct LivenessService<THandleStream, TPingStream> {
     neighbours: PeerPool,
     random_peers: PeerPool,
     active_pool: PeerPool,
+    monitor: ServiceMonitor,
 }
 
 impl<THandleStream, TPingStream> LivenessService<THandleStream, TPingStream>
@@ -101,9 +109,16 @@ where
             random_peers: PeerPool::new(config.refresh_random_pool_interval),
             active_pool: PeerPool::new(config.refresh_neighbours_interval),
             config,
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Report the activities of the service to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     pub async fn run(mut self) {
         info!(target: LOG_TARGET, "Liveness service started");
         debug!(target: LOG_TARGET, "Config = {:?}", self.config);
@@ -130,6 +145,7 @@ where
             futures::select! {
                 // Requests from the handle
                 request_context = request_stream.select_next_some() => {
+                    let _activity = self.monitor.enter("API request");
                     let (request, reply_tx) = request_context.split();
                     let _ = reply_tx.send(self.handle_request(request).await).or_else(|resp| {
                         error!(target: LOG_TARGET, "Failed to send reply");
@@ -138,6 +154,7 @@ where
                 },
 
                 event = connection_manager_events.select_next_some() => {
+                    let _activity = self.monitor.enter("connection manager event");
                     if let Ok(event) = event {
                         let _ = self.handle_connection_manager_event(&*event).await.or_else(|err| {
                             error!(target: LOG_TARGET, "Error when handling connection manager event: {:?}", err);
@@ -147,6 +164,7 @@ where
                 },
 
                 _ = ping_tick.select_next_some() => {
+                    let _activity = self.monitor.enter("ping round");
                     let _ = self.ping_active_pool().await.or_else(|err| {
                         error!(target: LOG_TARGET, "Error when pinging peers: {:?}", err);
                         Err(err)
@@ -158,6 +176,7 @@ where
                 },
                 // Incoming messages from the Comms layer
                 msg = ping_stream.select_next_some() => {
+                    let _activity = self.monitor.enter("ping pong message");
                     let _ = self.handle_incoming_message(msg).await.or_else(|err| {
                         error!(target: LOG_TARGET, "Failed to handle incoming PingPong message: {:?}", err);
                         Err(err)
@@ -203,6 +222,10 @@ where
                 self.refresh_peer_pools_if_stale().await?;
                 let maybe_latency = self.state.record_pong(ping_pong_msg.nonce);
                 let is_monitored = self.state.is_monitored_node_id(&node_id);
+                if let Some(latency) = maybe_latency {
+                    self.record_time_offset(&node_id, ping_pong_msg.timestamp, latency)
+                        .await?;
+                }
 
                 trace!(
                     target: LOG_TARGET,
@@ -227,6 +250,40 @@ where
         Ok(())
     }
 
+    // Records the offset of the local clock from the peer's clock and raises a warning if the estimated network time
+    // offset has moved beyond the configured threshold. The peer's timestamp is compared to the local time at the
+    // midpoint of the round trip.
+    async fn record_time_offset(
+        &mut self,
+        node_id: &NodeId,
+        timestamp: u64,
+        latency: u32,
+    ) -> Result<(), LivenessError>
+    {
+        if timestamp == 0 {
+            return Ok(());
+        }
+        let local_timestamp = Utc::now().timestamp_millis() - i64::from(latency / 2);
+        self.state
+            .add_time_offset_sample(node_id.clone(), local_timestamp - timestamp as i64);
+
+        let offset = match self.state.network_time_offset() {
+            Some(offset) => offset,
+            None => return Ok(()),
+        };
+        let is_exceeded = u128::from(offset.abs() as u64) > self.config.max_allowed_time_offset.as_millis();
+        if is_exceeded && !self.state.is_time_offset_exceeded() {
+            warn!(
+                target: LOG_TARGET,
+                "The local clock differs from network time by {}ms. Check that the system clock is correct.", offset
+            );
+            self.publish_event(LivenessEvent::NetworkTimeOffsetExceeded(offset))
+                .await?;
+        }
+        self.state.set_time_offset_exceeded(is_exceeded);
+        Ok(())
+    }
+
     async fn handle_connection_manager_event(&mut self, event: &ConnectionManagerEvent) -> Result<(), LivenessError> {
         use ConnectionManagerEvent::*;
         match event {
@@ -308,6 +365,7 @@ where
                 .state
                 .get_node_id_stats(&node_id)
                 .map(LivenessResponse::NodeIdStats),
+            GetNetworkTimeOffset => Ok(LivenessResponse::NetworkTimeOffset(self.state.network_time_offset())),
         }
     }
 
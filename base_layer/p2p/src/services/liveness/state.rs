@@ -31,6 +31,8 @@ use tari_comms::peer_manager::NodeId;
 
 const LATENCY_SAMPLE_WINDOW_SIZE: usize = 25;
 const MAX_INFLIGHT_TTL: Duration = Duration::from_secs(20);
+const TIME_OFFSET_SAMPLE_WINDOW_SIZE: usize = 25;
+const MIN_TIME_OFFSET_SAMPLES: usize = 3;
 
 /// Represents metadata in a ping/pong message.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -78,6 +80,9 @@ pub struct LivenessState {
 
     pong_metadata: Metadata,
     nodes_to_monitor: HashMap<NodeId, NodeStats>,
+
+    time_offsets: NetworkTimeOffset,
+    is_time_offset_exceeded: bool,
 }
 
 impl LivenessState {
@@ -221,6 +226,25 @@ impl LivenessState {
             Some(s) => Ok((*s).clone()),
         }
     }
+
+    /// Records the clock offset in milliseconds of the local clock from the clock of the given peer
+    pub fn add_time_offset_sample(&mut self, node_id: NodeId, offset_ms: i64) {
+        self.time_offsets.add_sample(node_id, offset_ms);
+    }
+
+    /// Returns the estimated offset in milliseconds of the local clock from network time, or None if too few peers
+    /// have reported their time. A positive offset means that the local clock is ahead of the network.
+    pub fn network_time_offset(&self) -> Option<i64> {
+        self.time_offsets.median()
+    }
+
+    pub fn is_time_offset_exceeded(&self) -> bool {
+        self.is_time_offset_exceeded
+    }
+
+    pub fn set_time_offset_exceeded(&mut self, exceeded: bool) {
+        self.is_time_offset_exceeded = exceeded;
+    }
 }
 
 /// Convert `chrono::Duration` to `std::time::Duration`
@@ -263,6 +287,42 @@ impl AverageLatency {
     }
 }
 
+/// Estimates the offset of the local clock from network time as the median of the clock offsets reported by peers.
+/// Only the latest sample of each peer is kept so that a single peer cannot skew the estimate. If samples from more
+/// than [TIME_OFFSET_SAMPLE_WINDOW_SIZE](self::TIME_OFFSET_SAMPLE_WINDOW_SIZE) peers are added the oldest sample is
+/// discarded.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkTimeOffset {
+    samples: Vec<(NodeId, i64)>,
+}
+
+impl NetworkTimeOffset {
+    /// Add the offset in milliseconds of the local clock from the clock of the given peer, replacing any previous
+    /// sample for that peer
+    pub fn add_sample(&mut self, node_id: NodeId, offset_ms: i64) {
+        self.samples.retain(|(n, _)| *n != node_id);
+        if self.samples.len() >= TIME_OFFSET_SAMPLE_WINDOW_SIZE {
+            self.samples.remove(0);
+        }
+        self.samples.push((node_id, offset_ms));
+    }
+
+    /// Calculate the median of the recorded samples, or None if there are not enough samples for a meaningful estimate
+    pub fn median(&self) -> Option<i64> {
+        if self.samples.len() < MIN_TIME_OFFSET_SAMPLES {
+            return None;
+        }
+        let mut offsets = self.samples.iter().map(|(_, offset)| *offset).collect::<Vec<_>>();
+        offsets.sort();
+        let mid = offsets.len() / 2;
+        if offsets.len() % 2 == 0 {
+            Some((offsets[mid - 1] + offsets[mid]) / 2)
+        } else {
+            Some(offsets[mid])
+        }
+    }
+}
+
 /// This struct contains the stats about a Node that is being monitored by the Liveness Service
 #[derive(Clone, Debug, Default)]
 pub struct NodeStats {
@@ -284,6 +344,7 @@ impl NodeStats {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::convert::TryFrom;
 
     #[test]
     fn new() {
@@ -375,4 +436,31 @@ mod test {
 
         assert_eq!(stats.average_latency.calc_average(), latency);
     }
+
+    #[test]
+    fn network_time_offset() {
+        let node_id = |n: u8| NodeId::try_from(&[n; 13][..]).unwrap();
+        let mut state = LivenessState::new();
+        state.add_time_offset_sample(node_id(1), 1_000);
+        state.add_time_offset_sample(node_id(2), -200);
+        // Too few peers have reported their time
+        assert_eq!(state.network_time_offset(), None);
+
+        state.add_time_offset_sample(node_id(3), 90_000);
+        assert_eq!(state.network_time_offset(), Some(1_000));
+        state.add_time_offset_sample(node_id(4), 2_000);
+        assert_eq!(state.network_time_offset(), Some(1_500));
+
+        // Only the latest sample for each peer is used
+        state.add_time_offset_sample(node_id(3), -1_000);
+        state.add_time_offset_sample(node_id(3), -2_000);
+        assert_eq!(state.network_time_offset(), Some(400));
+
+        let mut offsets = NetworkTimeOffset::default();
+        for n in 0..30 {
+            offsets.add_sample(node_id(n), i64::from(n));
+        }
+        assert_eq!(offsets.samples.len(), TIME_OFFSET_SAMPLE_WINDOW_SIZE);
+        assert_eq!(offsets.median(), Some(17));
+    }
 }
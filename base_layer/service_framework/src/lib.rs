@@ -30,6 +30,11 @@
 //! implements `futures::Stream` and will provide a `RequestContext` object that contains a `oneshot` reply channel
 //! that the service can use to reply back to the caller.
 //!
+//! ## `watchdog`
+//!
+//! Services report the activities of their select loops to a [ServiceMonitor]. The [Watchdog] logs and publishes an
+//! event when a service has been busy with an activity, and so has not polled its select loop, for too long.
+//!
 //! ## Examples
 //!
 //! ### `reply_channel`
@@ -62,6 +67,8 @@
 //! [StackBuilder]: ./stack/struct.StackBuilder.html
 //! [ServiceHandlesFuture]: ./handles/future/struct.ServiceHandlesFuture.html
 //! [SenderService]: ./reply_channel/struct.SenderService.html
+//! [ServiceMonitor]: ./watchdog/struct.ServiceMonitor.html
+//! [Watchdog]: ./watchdog/struct.Watchdog.html
 
 // Used to eliminate the need for boxing futures in many cases.
 // Tracking issue: https://github.com/rust-lang/rust/issues/63063
@@ -73,6 +80,7 @@ mod stack;
 pub mod handles;
 pub mod reply_channel;
 pub mod tower;
+pub mod watchdog;
 
 pub use self::{
     initializer::{ServiceInitializationError, ServiceInitializer},
@@ -0,0 +1,392 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Watchdog
+//!
+//! Service loops report what they are busy with to a [ServiceMonitor] by entering named activities. An activity lasts
+//! until the [ActivityGuard] returned by [ServiceMonitor::enter] is dropped, and activities entered while another is
+//! in progress are nested in it, much like tracing spans. The duration of every activity is logged at trace level.
+//! Services that hand their work off to spawned tasks can enter activities in those tasks with clones of the monitor,
+//! in which case the activities in progress are listed in the order they were entered.
+//!
+//! The [Watchdog] periodically checks the activities of all registered services. A service that has been busy with
+//! the same outermost activity for longer than the threshold has not returned to its select loop, or has a handler
+//! that has not finished, in that time. It is reported as stalled with a warning that names the activities it is
+//! stuck in, and a [WatchdogEvent::ServiceStalled] event is published to subscribers.
+//!
+//! [ServiceMonitor]: ./struct.ServiceMonitor.html
+//! [ServiceMonitor::enter]: ./struct.ServiceMonitor.html#method.enter
+//! [ActivityGuard]: ./struct.ActivityGuard.html
+//! [Watchdog]: ./struct.Watchdog.html
+//! [WatchdogEvent::ServiceStalled]: ./enum.WatchdogEvent.html#variant.ServiceStalled
+
+use crate::{handles::ServiceHandlesFuture, initializer::ServiceInitializationError, ServiceInitializer};
+use futures::{channel::mpsc, future, pin_mut};
+use log::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::{runtime, time};
+
+const LOG_TARGET: &str = "service_framework::watchdog";
+
+/// The default time a service may be busy with an activity before it is reported as stalled
+pub const DEFAULT_STALL_THRESHOLD: Duration = Duration::from_secs(60);
+/// The default interval between the checks of the registered services
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Events published by the watchdog
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// The service has been busy with the activity for longer than the stall threshold
+    ServiceStalled {
+        service: String,
+        activity: String,
+        elapsed: Duration,
+    },
+    /// A service that was reported as stalled finished the activity it was stalled in
+    ServiceRecovered {
+        service: String,
+        activity: String,
+        elapsed: Duration,
+    },
+}
+
+impl fmt::Display for WatchdogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchdogEvent::ServiceStalled {
+                service,
+                activity,
+                elapsed,
+            } => write!(f, "{} stalled in '{}' for {:.1?}", service, activity, elapsed),
+            WatchdogEvent::ServiceRecovered {
+                service,
+                activity,
+                elapsed,
+            } => write!(f, "{} recovered from '{}' after {:.1?}", service, activity, elapsed),
+        }
+    }
+}
+
+struct Activity {
+    id: u64,
+    name: &'static str,
+    started: Instant,
+}
+
+struct ServiceState {
+    name: String,
+    // The activities the service is busy with, in the order they were entered
+    activities: Vec<Activity>,
+    // The activity the service was last reported as stalled in, until it is finished
+    stalled_activity: Option<u64>,
+}
+
+impl ServiceState {
+    fn activity_path(&self) -> String {
+        self.activities.iter().map(|a| a.name).collect::<Vec<_>>().join(" > ")
+    }
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    services: HashMap<u64, ServiceState>,
+    next_id: u64,
+    next_activity_id: u64,
+    subscribers: Vec<mpsc::UnboundedSender<WatchdogEvent>>,
+}
+
+impl WatchdogState {
+    fn publish(&mut self, event: WatchdogEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+/// Keeps track of the activities of the registered services and reports the services that stall. The watchdog is
+/// cheap to clone and all clones share the same state.
+#[derive(Clone)]
+pub struct Watchdog {
+    threshold: Duration,
+    state: Arc<Mutex<WatchdogState>>,
+}
+
+impl Watchdog {
+    /// Create a watchdog that reports services that are busy with an activity for longer than `threshold`
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            state: Arc::new(Mutex::new(WatchdogState::default())),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WatchdogState> {
+        match self.state.lock() {
+            Ok(lock) => lock,
+            Err(poisoned) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Lock has been POISONED and will be silently recovered"
+                );
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    /// Register a service and return the monitor that the service reports its activities to
+    pub fn register<S: Into<String>>(&self, service: S) -> ServiceMonitor {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.services.insert(id, ServiceState {
+            name: service.into(),
+            activities: Vec::new(),
+            stalled_activity: None,
+        });
+        ServiceMonitor {
+            id,
+            watchdog: self.clone(),
+        }
+    }
+
+    /// Subscribe to the events published by the watchdog
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<WatchdogEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.lock().subscribers.push(sender);
+        receiver
+    }
+
+    /// Report the services that have been busy with an activity for longer than the threshold and have not been
+    /// reported yet. The stalled services are returned.
+    pub fn check(&self) -> Vec<WatchdogEvent> {
+        let now = Instant::now();
+        let mut state = self.lock();
+        let mut events = Vec::new();
+        for service in state.services.values_mut() {
+            if service.stalled_activity.is_some() {
+                continue;
+            }
+            let (id, elapsed) = match service.activities.first() {
+                Some(activity) => (activity.id, now.duration_since(activity.started)),
+                None => continue,
+            };
+            if elapsed < self.threshold {
+                continue;
+            }
+            service.stalled_activity = Some(id);
+            let activity = service.activity_path();
+            warn!(
+                target: LOG_TARGET,
+                "{} has been busy for {:.1?}, it is stuck in '{}'", service.name, elapsed, activity
+            );
+            events.push(WatchdogEvent::ServiceStalled {
+                service: service.name.clone(),
+                activity,
+                elapsed,
+            });
+        }
+        for event in events.iter() {
+            state.publish(event.clone());
+        }
+        events
+    }
+
+    /// Check the registered services every `check_interval` until shutdown
+    pub async fn run(self, check_interval: Duration, shutdown: ShutdownSignal) {
+        let checks = async move {
+            let mut ticker = time::interval(check_interval);
+            loop {
+                ticker.tick().await;
+                self.check();
+            }
+        };
+        pin_mut!(checks);
+        future::select(checks, shutdown).await;
+        debug!(target: LOG_TARGET, "Watchdog shutdown");
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new(DEFAULT_STALL_THRESHOLD)
+    }
+}
+
+/// The handle a service uses to report its activities to the watchdog
+#[derive(Clone)]
+pub struct ServiceMonitor {
+    id: u64,
+    watchdog: Watchdog,
+}
+
+impl ServiceMonitor {
+    /// Enter a named activity, typically the handling of one arm of the service's select loop or a long-running part
+    /// of a handler. The activity ends when the returned guard is dropped.
+    pub fn enter(&self, activity: &'static str) -> ActivityGuard {
+        let started = Instant::now();
+        let mut state = self.watchdog.lock();
+        let id = state.next_activity_id;
+        state.next_activity_id += 1;
+        if let Some(service) = state.services.get_mut(&self.id) {
+            service.activities.push(Activity {
+                id,
+                name: activity,
+                started,
+            });
+        }
+        ActivityGuard {
+            monitor: self.clone(),
+            id,
+            activity,
+            started,
+        }
+    }
+
+    fn exit(&self, id: u64, activity: &'static str, started: Instant) {
+        let elapsed = started.elapsed();
+        let mut state = self.watchdog.lock();
+        let recovered = match state.services.get_mut(&self.id) {
+            Some(service) => {
+                trace!(
+                    target: LOG_TARGET,
+                    "{} finished '{}' in {:.1?}",
+                    service.name,
+                    activity,
+                    elapsed
+                );
+                service.activities.retain(|a| a.id != id);
+                if service.stalled_activity == Some(id) {
+                    service.stalled_activity = None;
+                    Some(service.name.clone())
+                } else {
+                    None
+                }
+            },
+            None => None,
+        };
+        if let Some(service) = recovered {
+            info!(
+                target: LOG_TARGET,
+                "{} recovered from '{}' after {:.1?}", service, activity, elapsed
+            );
+            state.publish(WatchdogEvent::ServiceRecovered {
+                service,
+                activity: activity.to_string(),
+                elapsed,
+            });
+        }
+    }
+}
+
+/// Marks the activity entered with `ServiceMonitor::enter` as finished when dropped
+#[must_use = "the activity ends as soon as the guard is dropped"]
+pub struct ActivityGuard {
+    monitor: ServiceMonitor,
+    id: u64,
+    activity: &'static str,
+    started: Instant,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        self.monitor.exit(self.id, self.activity, self.started);
+    }
+}
+
+/// Registers a watchdog handle for the services of the stack to register with. The same watchdog can be shared by
+/// several stacks, so its checks are not started here and `Watchdog::run` should be spawned once by the owner.
+pub struct WatchdogInitializer {
+    watchdog: Watchdog,
+}
+
+impl WatchdogInitializer {
+    pub fn new(watchdog: Watchdog) -> Self {
+        Self { watchdog }
+    }
+}
+
+impl ServiceInitializer for WatchdogInitializer {
+    type Future = future::Ready<Result<(), ServiceInitializationError>>;
+
+    fn initialize(&mut self, _: runtime::Handle, handles_fut: ServiceHandlesFuture, _: ShutdownSignal) -> Self::Future {
+        handles_fut.register(self.watchdog.clone());
+        future::ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn reports_stalled_service_once() {
+        let watchdog = Watchdog::new(Duration::from_millis(50));
+        let mut events = watchdog.subscribe();
+        let monitor = watchdog.register("TestService");
+        let _idle_monitor = watchdog.register("IdleService");
+
+        // An idle service waiting in its select loop is never reported
+        assert!(watchdog.check().is_empty());
+
+        {
+            let _handler = monitor.enter("request");
+            let _query = monitor.enter("base node query");
+            assert!(watchdog.check().is_empty());
+            std::thread::sleep(Duration::from_millis(60));
+            let stalled = watchdog.check();
+            assert_eq!(stalled.len(), 1);
+            match &stalled[0] {
+                WatchdogEvent::ServiceStalled { service, activity, .. } => {
+                    assert_eq!(service, "TestService");
+                    assert_eq!(activity, "request > base node query");
+                },
+                e => panic!("Unexpected event {:?}", e),
+            }
+            // The stall is only reported once
+            assert!(watchdog.check().is_empty());
+        }
+
+        let received = futures::executor::block_on(events.by_ref().take(2).collect::<Vec<_>>());
+        match &received[0] {
+            WatchdogEvent::ServiceStalled { service, .. } => assert_eq!(service, "TestService"),
+            e => panic!("Unexpected event {:?}", e),
+        }
+        match &received[1] {
+            WatchdogEvent::ServiceRecovered { service, activity, .. } => {
+                assert_eq!(service, "TestService");
+                assert_eq!(activity, "request");
+            },
+            e => panic!("Unexpected event {:?}", e),
+        }
+
+        // A new stall is reported again after the service recovered
+        let _handler = monitor.enter("request");
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(watchdog.check().len(), 1);
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures::channel::mpsc;
+use rand::rngs::OsRng;
+use std::time::Duration;
+use tari_broadcast_channel::bounded;
+use tari_comms_dht::outbound::mock::create_outbound_service_mock;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::UnblindedOutput,
+    types::{CryptoFactories, PrivateKey},
+};
+use tari_crypto::{keys::SecretKey, tari_utilities::hash::Hashable};
+use tari_service_framework::reply_channel;
+use tari_wallet::{
+    output_manager_service::{
+        config::OutputManagerServiceConfig,
+        handle::OutputManagerHandle,
+        service::OutputManagerService,
+        storage::{
+            database::{OutputManagerBackend, OutputManagerDatabase},
+            memory_db::OutputManagerMemoryDatabase,
+            sqlite_db::OutputManagerSqliteDatabase,
+        },
+    },
+    storage::connection_manager::run_migration_and_create_sqlite_connection,
+};
+use tempdir::TempDir;
+use tokio::runtime::Runtime;
+
+const OUTPUT_COUNTS: [usize; 3] = [100, 10_000, 100_000];
+
+fn make_outputs(n: usize) -> Vec<UnblindedOutput> {
+    (0..n)
+        .map(|i| {
+            UnblindedOutput::new(
+                MicroTari::from(1_000 + (i as u64 % 1_000) * 100),
+                PrivateKey::random(&mut OsRng),
+                None,
+            )
+        })
+        .collect()
+}
+
+fn memory_backend() -> (OutputManagerMemoryDatabase, Option<TempDir>) {
+    (OutputManagerMemoryDatabase::new(), None)
+}
+
+fn sqlite_backend() -> (OutputManagerSqliteDatabase, Option<TempDir>) {
+    let db_folder = TempDir::new("oms_bench").unwrap();
+    let connection = run_migration_and_create_sqlite_connection(db_folder.path().join("oms_bench.sqlite3")).unwrap();
+    (OutputManagerSqliteDatabase::new(connection), Some(db_folder))
+}
+
+/// Create an Output Manager database populated with `n` unspent outputs
+fn populated_db<T: OutputManagerBackend + 'static>(
+    runtime: &mut Runtime,
+    backend: T,
+    n: usize,
+) -> OutputManagerDatabase<T>
+{
+    let db = OutputManagerDatabase::new(backend);
+    for o in make_outputs(n) {
+        runtime.block_on(db.add_unspent_output(o)).unwrap();
+    }
+    db
+}
+
+fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
+    runtime: &mut Runtime,
+    db: OutputManagerDatabase<T>,
+) -> OutputManagerHandle
+{
+    let (outbound_message_requester, mock_outbound_service) = create_outbound_service_mock(20);
+    let (oms_request_sender, oms_request_receiver) = reply_channel::unbounded();
+    let (_base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
+    let (oms_event_publisher, oms_event_subscriber) = bounded(100);
+
+    let output_manager_service = runtime
+        .block_on(OutputManagerService::new(
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(3),
+                ..Default::default()
+            },
+            outbound_message_requester,
+            oms_request_receiver,
+            base_node_response_receiver,
+            db,
+            oms_event_publisher,
+            CryptoFactories::default(),
+        ))
+        .unwrap();
+    runtime.spawn(async move { output_manager_service.start().await.unwrap() });
+    runtime.spawn(mock_outbound_service.run());
+
+    OutputManagerHandle::new(oms_request_sender, oms_event_subscriber)
+}
+
+fn bench_select_utxos<T, F>(c: &mut Criterion, backend_name: &str, create_backend: F)
+where
+    T: OutputManagerBackend + 'static,
+    F: Fn() -> (T, Option<TempDir>),
+{
+    for n in OUTPUT_COUNTS.iter() {
+        let mut runtime = Runtime::new().unwrap();
+        let (backend, temp_dir) = create_backend();
+        let db = populated_db(&mut runtime, backend, *n);
+        let mut oms = setup_output_manager_service(&mut runtime, db);
+
+        c.bench_function(&format!("select_utxos {} ({} outputs)", backend_name, n), move |b| {
+            let _temp_dir = &temp_dir;
+            b.iter(|| {
+                // Selection encumbers the chosen outputs so release them again to keep every iteration identical
+                let stp = runtime
+                    .block_on(oms.prepare_transaction_to_send(
+                        MicroTari::from(50_000),
+                        MicroTari::from(20),
+                        None,
+                        "".to_string(),
+                    ))
+                    .unwrap();
+                runtime
+                    .block_on(oms.cancel_transaction(stp.get_tx_id().unwrap()))
+                    .unwrap();
+            });
+        });
+    }
+}
+
+fn bench_get_balance<T, F>(c: &mut Criterion, backend_name: &str, create_backend: F)
+where
+    T: OutputManagerBackend + 'static,
+    F: Fn() -> (T, Option<TempDir>),
+{
+    for n in OUTPUT_COUNTS.iter() {
+        let mut runtime = Runtime::new().unwrap();
+        let (backend, temp_dir) = create_backend();
+        let db = populated_db(&mut runtime, backend, *n);
+
+        c.bench_function(&format!("get_balance {} ({} outputs)", backend_name, n), move |b| {
+            let _temp_dir = &temp_dir;
+            b.iter(|| runtime.block_on(db.get_balance(None)).unwrap());
+        });
+    }
+}
+
+fn bench_validation_hashes<T, F>(c: &mut Criterion, backend_name: &str, create_backend: F)
+where
+    T: OutputManagerBackend + 'static,
+    F: Fn() -> (T, Option<TempDir>),
+{
+    let factories = CryptoFactories::default();
+    for n in OUTPUT_COUNTS.iter() {
+        let mut runtime = Runtime::new().unwrap();
+        let (backend, temp_dir) = create_backend();
+        let db = populated_db(&mut runtime, backend, *n);
+        let factories = factories.clone();
+
+        c.bench_function(
+            &format!("validation_hashes {} ({} outputs)", backend_name, n),
+            move |b| {
+                let _temp_dir = &temp_dir;
+                // This mirrors the preparation of the UTXO query that is sent to the base node
+                b.iter(|| {
+                    runtime
+                        .block_on(db.get_unspent_outputs())
+                        .unwrap()
+                        .iter()
+                        .map(|uo| uo.as_transaction_output(&factories).unwrap().hash())
+                        .collect::<Vec<_>>()
+                });
+            },
+        );
+    }
+}
+
+fn bench_encumbrance<T, F>(c: &mut Criterion, backend_name: &str, create_backend: F)
+where
+    T: OutputManagerBackend + 'static,
+    F: Fn() -> (T, Option<TempDir>),
+{
+    for n in OUTPUT_COUNTS.iter() {
+        let mut runtime = Runtime::new().unwrap();
+        let (backend, temp_dir) = create_backend();
+        let db = populated_db(&mut runtime, backend, *n);
+        let to_send = runtime.block_on(db.fetch_sorted_unspent_outputs()).unwrap()[..2].to_vec();
+        let to_receive = make_outputs(1);
+
+        c.bench_function(&format!("encumbrance {} ({} outputs)", backend_name, n), move |b| {
+            let _temp_dir = &temp_dir;
+            b.iter(|| {
+                runtime
+                    .block_on(db.encumber_outputs(
+                        1,
+                        MicroTari::from(1000),
+                        MicroTari::from(100),
+                        to_send.clone(),
+                        to_receive.clone(),
+                    ))
+                    .unwrap();
+                runtime.block_on(db.cancel_pending_transaction_outputs(1)).unwrap();
+            });
+        });
+    }
+}
+
+fn output_manager_memory(c: &mut Criterion) {
+    bench_select_utxos(c, "memory", memory_backend);
+    bench_get_balance(c, "memory", memory_backend);
+    bench_validation_hashes(c, "memory", memory_backend);
+    bench_encumbrance(c, "memory", memory_backend);
+}
+
+fn output_manager_sqlite(c: &mut Criterion) {
+    bench_select_utxos(c, "sqlite", sqlite_backend);
+    bench_get_balance(c, "sqlite", sqlite_backend);
+    bench_validation_hashes(c, "sqlite", sqlite_backend);
+    bench_encumbrance(c, "sqlite", sqlite_backend);
+}
+
+criterion_group!(
+    name = output_manager;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500)).sample_size(10);
+    targets = output_manager_memory, output_manager_sqlite
+);
+
+criterion_main!(output_manager);
@@ -0,0 +1,5 @@
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY,
+    event TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE output_sources (
+    spending_key BLOB PRIMARY KEY NOT NULL,
+    tag TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,4 @@
+CREATE TABLE wallet_settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
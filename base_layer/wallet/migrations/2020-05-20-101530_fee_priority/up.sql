@@ -0,0 +1,2 @@
+ALTER TABLE outbound_transactions ADD COLUMN fee_priority INTEGER NULL;
+ALTER TABLE completed_transactions ADD COLUMN fee_priority INTEGER NULL;
@@ -0,0 +1,5 @@
+CREATE TABLE output_invalidations (
+    spending_key BLOB PRIMARY KEY NOT NULL,
+    reason TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
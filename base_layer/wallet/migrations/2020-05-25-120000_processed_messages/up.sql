@@ -0,0 +1,6 @@
+CREATE TABLE processed_messages (
+    sender BLOB NOT NULL,
+    message_hash BLOB NOT NULL,
+    timestamp DATETIME NOT NULL,
+    PRIMARY KEY (sender, message_hash)
+);
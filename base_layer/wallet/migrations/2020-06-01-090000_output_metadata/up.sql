@@ -0,0 +1,8 @@
+CREATE TABLE output_metadata (
+    spending_key BLOB PRIMARY KEY NOT NULL,
+    label TEXT NULL,
+    source_tx_id INTEGER NULL,
+    discovered_height INTEGER NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE output_manager_encryption (
+    id INTEGER PRIMARY KEY NOT NULL,
+    salt BLOB NOT NULL,
+    check_value BLOB NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE key_manager_states ADD COLUMN branch_indices TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,4 @@
+CREATE TABLE transaction_replacements (
+    tx_id BIGINT PRIMARY KEY NOT NULL,
+    replacement_tx_id BIGINT NOT NULL
+);
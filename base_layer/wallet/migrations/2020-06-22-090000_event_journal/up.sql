@@ -0,0 +1,5 @@
+CREATE TABLE event_journal (
+    sequence BIGINT PRIMARY KEY NOT NULL,
+    event TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
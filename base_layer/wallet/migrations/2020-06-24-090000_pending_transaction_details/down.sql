@@ -0,0 +1,13 @@
+PRAGMA foreign_keys=off;
+
+ALTER TABLE pending_transaction_outputs RENAME TO pending_transaction_outputs_old;
+CREATE TABLE pending_transaction_outputs (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    short_term INTEGER NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+INSERT INTO pending_transaction_outputs (tx_id, short_term, timestamp)
+SELECT tx_id, short_term, timestamp FROM pending_transaction_outputs_old;
+DROP TABLE pending_transaction_outputs_old;
+
+PRAGMA foreign_keys=on;
@@ -0,0 +1,3 @@
+ALTER TABLE pending_transaction_outputs ADD COLUMN amount INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_transaction_outputs ADD COLUMN fee INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_transaction_outputs ADD COLUMN destination_public_key BLOB NULL;
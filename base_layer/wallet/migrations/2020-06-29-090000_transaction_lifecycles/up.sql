@@ -0,0 +1,5 @@
+CREATE TABLE transaction_lifecycles (
+    tx_id BIGINT PRIMARY KEY NOT NULL,
+    state INTEGER NOT NULL,
+    timestamp DATETIME NOT NULL
+);
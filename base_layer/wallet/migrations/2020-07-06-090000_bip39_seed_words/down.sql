@@ -0,0 +1,16 @@
+PRAGMA foreign_keys=off;
+
+ALTER TABLE key_manager_states RENAME TO key_manager_states_old;
+CREATE TABLE key_manager_states (
+    id INTEGER PRIMARY KEY,
+    master_seed BLOB NOT NULL,
+    branch_seed TEXT NOT NULL,
+    primary_key_index INTEGER NOT NULL,
+    timestamp DATETIME NOT NULL,
+    branch_indices TEXT NOT NULL DEFAULT '{}'
+);
+INSERT INTO key_manager_states (id, master_seed, branch_seed, primary_key_index, timestamp, branch_indices)
+SELECT id, master_seed, branch_seed, primary_key_index, timestamp, branch_indices FROM key_manager_states_old;
+DROP TABLE key_manager_states_old;
+
+PRAGMA foreign_keys=on;
@@ -0,0 +1 @@
+ALTER TABLE key_manager_states ADD COLUMN bip39_entropy BLOB NULL;
@@ -0,0 +1,9 @@
+CREATE TABLE sync_history (
+    request_key INTEGER PRIMARY KEY NOT NULL,
+    base_node_public_key BLOB NOT NULL,
+    output_hashes TEXT NOT NULL,
+    sent_at DATETIME NOT NULL,
+    response_time_ms INTEGER NULL,
+    invalidated_hashes TEXT NOT NULL,
+    outcome TEXT NOT NULL
+);
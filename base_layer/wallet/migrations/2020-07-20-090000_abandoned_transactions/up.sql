@@ -0,0 +1,5 @@
+CREATE TABLE abandoned_transactions (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    reason TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
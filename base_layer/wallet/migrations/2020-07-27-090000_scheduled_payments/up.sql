@@ -0,0 +1,11 @@
+CREATE TABLE scheduled_payments (
+    id BIGINT PRIMARY KEY NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount BIGINT NOT NULL,
+    fee_priority INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    interval_secs BIGINT NOT NULL,
+    next_payment_at DATETIME NOT NULL,
+    payments_made BIGINT NOT NULL,
+    last_tx_id BIGINT NULL
+);
@@ -0,0 +1,185 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Keeps enough spendable outputs in the wallet for it to make several transactions at once. Every unconfirmed
+//! transaction encumbers the outputs it spends, so a wallet with only a few large outputs has to wait for each send to
+//! be mined before making the next one. When an automatic coin split policy is set and the number of spendable
+//! outputs drops below its minimum, a coin split is created by the Output Manager Service and broadcast through the
+//! Transaction Service.
+//!
+//! Outputs that pending transactions will return to the wallet are counted as spendable, so a split that has not been
+//! mined yet does not cause another one to be made.
+
+use crate::{
+    error::WalletError,
+    output_manager_service::{
+        handle::{OutputManagerEvent, OutputManagerHandle},
+        TxId,
+    },
+    storage::database::{WalletBackend, WalletDatabase},
+    transaction_service::handle::{TransactionEvent, TransactionEventReceiver, TransactionServiceHandle},
+};
+use futures::{stream::Fuse, FutureExt, StreamExt};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use tari_broadcast_channel::Subscriber;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "wallet::auto_coin_split";
+
+/// When and how the wallet splits its funds into more outputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoCoinSplitPolicy {
+    /// A coin split is made when the wallet has fewer spendable outputs than this
+    pub min_spendable_outputs: usize,
+    /// The value of each output created by the coin split
+    pub target_output_value: MicroTari,
+    /// The fee per gram paid for the coin split transaction
+    pub fee_per_gram: MicroTari,
+}
+
+/// Applies the automatic coin split policy stored in the wallet settings whenever the spendable outputs of the wallet
+/// may have changed
+pub struct AutoCoinSplitter<T>
+where T: WalletBackend + 'static
+{
+    db: WalletDatabase<T>,
+    output_manager_service: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+}
+
+impl<T> AutoCoinSplitter<T>
+where T: WalletBackend + 'static
+{
+    pub fn new(
+        db: WalletDatabase<T>,
+        output_manager_service: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+    ) -> Self
+    {
+        Self {
+            db,
+            output_manager_service,
+            transaction_service,
+        }
+    }
+
+    /// Check the policy after every base node sync, maturing of funds and completed or mined transaction until the
+    /// wallet shuts down
+    pub async fn run(
+        mut self,
+        mut output_manager_events: Fuse<Subscriber<OutputManagerEvent>>,
+        mut transaction_events: Fuse<TransactionEventReceiver>,
+        shutdown_signal: ShutdownSignal,
+    )
+    {
+        let mut shutdown_signal = shutdown_signal.fuse();
+        loop {
+            let triggered = futures::select! {
+                event = output_manager_events.select_next_some() => match &*event {
+                    OutputManagerEvent::SyncCompleted { .. } | OutputManagerEvent::FundsMatured(_) => true,
+                    _ => false,
+                },
+                event = transaction_events.select_next_some() => match event.as_ref().map(|e| &**e) {
+                    Ok(TransactionEvent::ReceivedTransactionReply(_)) |
+                    Ok(TransactionEvent::TransactionCancelled(_)) |
+                    Ok(TransactionEvent::TransactionMined(_)) => true,
+                    Ok(_) => false,
+                    // Missed events may have been any of the above
+                    Err(_) => true,
+                },
+                _ = shutdown_signal => break,
+                complete => break,
+            };
+            if triggered {
+                if let Err(e) = self.check().await {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Could not apply the automatic coin split policy: {:?}", e
+                    );
+                }
+            }
+        }
+        debug!(target: LOG_TARGET, "Automatic coin splitting stopped");
+    }
+
+    /// Make and broadcast a coin split if the policy calls for one. Returns the id of the coin split transaction.
+    pub async fn check(&mut self) -> Result<Option<TxId>, WalletError> {
+        let policy = match self.db.get_auto_coin_split_policy().await? {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+        // Sending is locked until a clone detection is acknowledged and a split would spend outputs all the same
+        if self.db.get_clone_detection().await?.is_some() {
+            return Ok(None);
+        }
+
+        let unspent = self.output_manager_service.get_unspent_outputs().await?.len();
+        let pending = self
+            .output_manager_service
+            .get_pending_transactions()
+            .await?
+            .values()
+            .map(|p| p.outputs_to_be_received.len())
+            .sum::<usize>();
+        let spendable = unspent + pending;
+        if spendable >= policy.min_spendable_outputs {
+            return Ok(None);
+        }
+
+        // One target value is held back to pay for the fee and leave some change
+        let available = self.output_manager_service.get_balance().await?.available_balance;
+        let affordable = (u64::from(available) / cmp::max(u64::from(policy.target_output_value), 1)) as usize;
+        let split_count = cmp::min(policy.min_spendable_outputs - spendable, affordable.saturating_sub(1));
+        if split_count == 0 {
+            debug!(
+                target: LOG_TARGET,
+                "{} spendable outputs is below the minimum of {} but the available balance of {} is too low to split",
+                spendable,
+                policy.min_spendable_outputs,
+                available
+            );
+            return Ok(None);
+        }
+
+        let (tx_id, tx, fee, amount) = self
+            .output_manager_service
+            .create_coin_split(policy.target_output_value, split_count, policy.fee_per_gram, None)
+            .await?;
+        self.transaction_service
+            .submit_transaction(tx_id, tx, fee, amount, "Automatic coin split".to_string())
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Split {} into {} outputs of {} (TxId: {}) to raise the {} spendable outputs to the minimum of {}",
+            amount,
+            split_count,
+            policy.target_output_value,
+            tx_id,
+            spendable,
+            policy.min_spendable_outputs
+        );
+        Ok(Some(tx_id))
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A backup bundle holds everything needed to move a wallet to another device in a single encrypted file: the comms
+//! node identity, the key manager state the wallet keys are derived from and the settings the user has chosen. The
+//! outputs and transaction history are not included, they are recovered from the blockchain with a seed scan after
+//! the bundle has been restored.
+
+use crate::{
+    error::{BackupError, WalletError},
+    output_manager_service::{
+        error::OutputManagerError,
+        storage::database::{KeyManagerBranch, KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+    },
+    storage::database::{ConfirmationPolicy, WalletBackend, WalletDatabase},
+    util::encryption::DatabaseCipher,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use tari_comms::{
+    multiaddr::Multiaddr,
+    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    types::CommsPublicKey,
+};
+use tari_core::transactions::{tari_amount::MicroTari, types::PrivateKey};
+use tari_crypto::tari_utilities::hex::Hex;
+
+/// The version of the backup bundle format written by this wallet
+pub const BACKUP_BUNDLE_VERSION: u32 = 1;
+
+// The length of the salt that the bundle encryption key is derived with, which precedes the encrypted contents
+const SALT_SIZE: usize = 16;
+
+/// The contents of a wallet backup bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupBundle {
+    pub version: u32,
+    pub created_at: NaiveDateTime,
+    pub node_identity: NodeIdentity,
+    pub seed: BackupSeed,
+    pub settings: BackupSettings,
+}
+
+/// The key manager state of the wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupSeed {
+    /// The master seed as hex
+    pub master_seed: String,
+    pub branch_seed: String,
+    pub primary_key_index: usize,
+    pub branch_indices: Vec<(KeyManagerBranch, usize)>,
+    pub bip39_entropy: Option<Vec<u8>>,
+}
+
+/// The wallet settings that are restored with the bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// The public key, as hex, and the address of the base node the wallet is using
+    pub base_node: Option<(String, String)>,
+    pub default_fee_per_gram: Option<MicroTari>,
+    pub confirmation_policy: Option<ConfirmationPolicy>,
+}
+
+impl BackupBundle {
+    pub fn new(node_identity: NodeIdentity, key_manager_state: &KeyManagerState, settings: BackupSettings) -> Self {
+        Self {
+            version: BACKUP_BUNDLE_VERSION,
+            created_at: Utc::now().naive_utc(),
+            node_identity,
+            seed: BackupSeed::from(key_manager_state),
+            settings,
+        }
+    }
+
+    /// Encrypt the bundle with a key derived from the passphrase. The random salt the key is derived with precedes the
+    /// encrypted contents.
+    pub fn encrypt(&self, passphrase: &str) -> Result<Vec<u8>, BackupError> {
+        let salt = DatabaseCipher::generate_salt();
+        let cipher = DatabaseCipher::from_passphrase(passphrase, &salt)?;
+        let mut bundle = salt;
+        bundle.extend(cipher.encrypt(serde_json::to_string(self)?.as_bytes())?);
+        Ok(bundle)
+    }
+
+    /// Decrypt a bundle produced by `encrypt`
+    pub fn decrypt(bundle: &[u8], passphrase: &str) -> Result<Self, BackupError> {
+        if bundle.len() <= SALT_SIZE {
+            return Err(BackupError::BundleTooShort);
+        }
+        let (salt, ciphertext) = bundle.split_at(SALT_SIZE);
+        let cipher = DatabaseCipher::from_passphrase(passphrase, salt)?;
+        let contents: Self = serde_json::from_slice(&cipher.decrypt(ciphertext)?)?;
+        if contents.version > BACKUP_BUNDLE_VERSION {
+            return Err(BackupError::UnsupportedVersion(contents.version));
+        }
+        Ok(contents)
+    }
+}
+
+impl From<&KeyManagerState> for BackupSeed {
+    fn from(state: &KeyManagerState) -> Self {
+        let mut branch_indices = state
+            .branch_indices
+            .iter()
+            .map(|(branch, index)| (*branch, *index))
+            .collect::<Vec<_>>();
+        branch_indices.sort_by_key(|(branch, _)| KeyManagerBranch::all().iter().position(|b| b == branch));
+        Self {
+            master_seed: state.master_seed.to_hex(),
+            branch_seed: state.branch_seed.clone(),
+            primary_key_index: state.primary_key_index,
+            branch_indices,
+            bip39_entropy: state.bip39_entropy.clone(),
+        }
+    }
+}
+
+impl BackupSeed {
+    pub fn to_key_manager_state(&self) -> Result<KeyManagerState, BackupError> {
+        Ok(KeyManagerState {
+            master_seed: PrivateKey::from_hex(&self.master_seed).map_err(|_| BackupError::InvalidMasterSeed)?,
+            branch_seed: self.branch_seed.clone(),
+            primary_key_index: self.primary_key_index,
+            branch_indices: self.branch_indices.iter().cloned().collect(),
+            bip39_entropy: self.bip39_entropy.clone(),
+        })
+    }
+}
+
+impl BackupSettings {
+    /// Read the settings to back up from the wallet database
+    pub async fn from_db<T: WalletBackend + 'static>(db: &WalletDatabase<T>) -> Result<Self, WalletError> {
+        let base_node = db.get_peers().await?.into_iter().next().and_then(|peer| {
+            let address = peer.addresses.address_iter().next()?.to_string();
+            Some((peer.public_key.to_hex(), address))
+        });
+        Ok(Self {
+            base_node,
+            default_fee_per_gram: db.get_default_fee_per_gram().await?,
+            confirmation_policy: db.get_confirmation_policy().await?,
+        })
+    }
+}
+
+/// Restore a backup bundle onto a device for a wallet that has not been started yet. The node identity is written to
+/// `node_identity_path` as JSON and the key manager state and settings are written to the wallet databases. Neither an
+/// existing node identity file nor the master seed of an existing wallet are replaced.
+pub async fn restore_backup_bundle<T, U>(
+    bundle: &BackupBundle,
+    node_identity_path: &Path,
+    wallet_db: &WalletDatabase<T>,
+    output_manager_db: &OutputManagerDatabase<U>,
+) -> Result<(), WalletError>
+where
+    T: WalletBackend + 'static,
+    U: OutputManagerBackend + 'static,
+{
+    if node_identity_path.exists() {
+        return Err(BackupError::IdentityFileExists.into());
+    }
+    if output_manager_db
+        .get_key_manager_state()
+        .await
+        .map_err(OutputManagerError::from)?
+        .is_some()
+    {
+        return Err(BackupError::WalletAlreadyInitialized.into());
+    }
+    let key_manager_state = bundle.seed.to_key_manager_state()?;
+    let base_node = bundle
+        .settings
+        .base_node
+        .as_ref()
+        .map(|(public_key, address)| base_node_peer(public_key, address))
+        .transpose()?;
+
+    if let Some(parent) = node_identity_path.parent() {
+        fs::create_dir_all(parent).map_err(BackupError::from)?;
+    }
+    let node_identity = serde_json::to_string(&bundle.node_identity).map_err(BackupError::from)?;
+    fs::write(node_identity_path, node_identity).map_err(BackupError::from)?;
+
+    output_manager_db
+        .set_key_manager_state(key_manager_state)
+        .await
+        .map_err(OutputManagerError::from)?;
+    if let Some(peer) = base_node {
+        wallet_db.set_selected_base_node(peer.public_key.clone()).await?;
+        wallet_db.save_peer(peer).await?;
+    }
+    if let Some(fee_per_gram) = bundle.settings.default_fee_per_gram {
+        wallet_db.set_default_fee_per_gram(fee_per_gram).await?;
+    }
+    if let Some(policy) = bundle.settings.confirmation_policy.clone() {
+        wallet_db.set_confirmation_policy(policy).await?;
+    }
+    Ok(())
+}
+
+fn base_node_peer(public_key: &str, address: &str) -> Result<Peer, BackupError> {
+    let public_key = CommsPublicKey::from_hex(public_key).map_err(|_| BackupError::InvalidBaseNode)?;
+    let address = address.parse::<Multiaddr>().map_err(|_| BackupError::InvalidBaseNode)?;
+    let node_id = NodeId::from_key(&public_key).map_err(|_| BackupError::InvalidBaseNode)?;
+    Ok(Peer::new(
+        public_key,
+        node_id,
+        vec![address].into(),
+        PeerFlags::empty(),
+        PeerFeatures::COMMUNICATION_NODE,
+        &[],
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backup::{restore_backup_bundle, BackupBundle, BackupSettings},
+        error::{BackupError, WalletError},
+        output_manager_service::storage::{
+            database::{KeyManagerBranch, KeyManagerState, OutputManagerDatabase},
+            memory_db::OutputManagerMemoryDatabase,
+        },
+        storage::{
+            database::{ConfirmationPolicy, WalletDatabase},
+            memory_db::WalletMemoryDatabase,
+        },
+        util::encryption::EncryptionError,
+    };
+    use rand::rngs::OsRng;
+    use std::{collections::HashMap, fs};
+    use tari_comms::{
+        peer_manager::{NodeIdentity, PeerFeatures},
+        types::CommsPublicKey,
+    };
+    use tari_core::transactions::{tari_amount::MicroTari, types::PrivateKey};
+    use tari_crypto::{
+        keys::{PublicKey, SecretKey},
+        tari_utilities::hex::Hex,
+    };
+    use tempdir::TempDir;
+    use tokio::runtime::Runtime;
+
+    fn key_manager_state() -> KeyManagerState {
+        let mut branch_indices = HashMap::new();
+        branch_indices.insert(KeyManagerBranch::Coinbase, 3);
+        KeyManagerState {
+            master_seed: PrivateKey::random(&mut OsRng),
+            branch_seed: "".to_string(),
+            primary_key_index: 7,
+            branch_indices,
+            bip39_entropy: None,
+        }
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_bundle() {
+        let node_identity = NodeIdentity::random(
+            &mut OsRng,
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_CLIENT,
+        )
+        .unwrap();
+        let state = key_manager_state();
+        let bundle = BackupBundle::new(node_identity.clone(), &state, BackupSettings::default());
+
+        let encrypted = bundle.encrypt("correct horse battery staple").unwrap();
+        let decrypted = BackupBundle::decrypt(&encrypted, "correct horse battery staple").unwrap();
+        assert_eq!(decrypted.node_identity.public_key(), node_identity.public_key());
+        assert_eq!(decrypted.node_identity.secret_key(), node_identity.secret_key());
+        assert_eq!(decrypted.seed.to_key_manager_state().unwrap(), state);
+
+        match BackupBundle::decrypt(&encrypted, "incorrect horse") {
+            Err(BackupError::EncryptionError(EncryptionError::DecryptionFailed)) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        match BackupBundle::decrypt(&encrypted[..8], "correct horse battery staple") {
+            Err(BackupError::BundleTooShort) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn restore_bundle() {
+        let mut runtime = Runtime::new().unwrap();
+        let temp_dir = TempDir::new("restore_bundle").unwrap();
+        let identity_path = temp_dir.path().join("config").join("wallet_identity.json");
+        let node_identity = NodeIdentity::random(
+            &mut OsRng,
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_CLIENT,
+        )
+        .unwrap();
+        let (_, base_node_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let settings = BackupSettings {
+            base_node: Some((base_node_public_key.to_hex(), "/ip4/127.0.0.1/tcp/18189".to_string())),
+            default_fee_per_gram: Some(MicroTari::from(25)),
+            confirmation_policy: Some(ConfirmationPolicy::Confirmations(3)),
+        };
+        let state = key_manager_state();
+        let bundle = BackupBundle::new(node_identity.clone(), &state, settings);
+
+        let wallet_db = WalletDatabase::new(WalletMemoryDatabase::new());
+        let output_manager_db = OutputManagerDatabase::new(OutputManagerMemoryDatabase::new());
+        runtime
+            .block_on(restore_backup_bundle(
+                &bundle,
+                &identity_path,
+                &wallet_db,
+                &output_manager_db,
+            ))
+            .unwrap();
+
+        let restored_identity: NodeIdentity =
+            serde_json::from_str(&fs::read_to_string(&identity_path).unwrap()).unwrap();
+        assert_eq!(restored_identity.public_key(), node_identity.public_key());
+        assert_eq!(
+            runtime.block_on(output_manager_db.get_key_manager_state()).unwrap(),
+            Some(state)
+        );
+        assert_eq!(
+            runtime.block_on(wallet_db.get_selected_base_node()).unwrap(),
+            Some(base_node_public_key)
+        );
+        assert_eq!(runtime.block_on(wallet_db.get_peers()).unwrap().len(), 1);
+        assert_eq!(
+            runtime.block_on(wallet_db.get_default_fee_per_gram()).unwrap(),
+            Some(MicroTari::from(25))
+        );
+        assert_eq!(
+            runtime.block_on(wallet_db.get_confirmation_policy()).unwrap(),
+            Some(ConfirmationPolicy::Confirmations(3))
+        );
+
+        // Restoring again must not replace the identity file or the master seed
+        match runtime.block_on(restore_backup_bundle(
+            &bundle,
+            &identity_path,
+            &wallet_db,
+            &output_manager_db,
+        )) {
+            Err(WalletError::BackupError(BackupError::IdentityFileExists)) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        fs::remove_file(&identity_path).unwrap();
+        match runtime.block_on(restore_backup_bundle(
+            &bundle,
+            &temp_dir.path().join("wallet_identity.json"),
+            &wallet_db,
+            &output_manager_db,
+        )) {
+            Err(WalletError::BackupError(BackupError::WalletAlreadyInitialized)) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
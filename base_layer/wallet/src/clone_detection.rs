@@ -0,0 +1,229 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Detects when the wallet database is used by more than one wallet instance, either because it was opened by two
+//! processes at once or because a backup of it was restored on a second device. Two instances spending from the same
+//! outputs will double-spend each other, so sending is locked as soon as a clone is detected and stays locked until
+//! the operator acknowledges the detection.
+//!
+//! Two signals are used:
+//! - Every running instance writes a heartbeat with its own random nonce to the wallet settings. Finding another nonce
+//!   in place of the one last written means a second process is writing to the same database.
+//! - An unspent output of a mined transaction that the base node no longer has in its UTXO set was spent by someone
+//!   holding the same keys, i.e. by a restored copy of the wallet. A reorg can cause the same observation, so the
+//!   operator is asked to confirm rather than the wallet acting on its own.
+
+use crate::{
+    error::WalletError,
+    output_manager_service::{handle::OutputManagerEvent, TxId},
+    storage::database::{EventJournalEntry, WalletBackend, WalletDatabase, WalletEvent},
+    transaction_service::{handle::TransactionServiceHandle, storage::database::TransactionStatus},
+};
+use chrono::{NaiveDateTime, Utc};
+use futures::{stream::Fuse, FutureExt, StreamExt};
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Error, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+use tari_broadcast_channel::Subscriber;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_shutdown::ShutdownSignal;
+use tokio::{sync::broadcast, time};
+
+const LOG_TARGET: &str = "wallet::clone_detection";
+
+/// How often a running wallet instance writes its heartbeat
+pub const INSTANCE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The heartbeat of the wallet instance that last wrote to the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceHeartbeat {
+    pub nonce: u64,
+    pub timestamp: NaiveDateTime,
+}
+
+/// Why the wallet was detected as cloned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CloneDetectionReason {
+    /// Another wallet instance, identified by its heartbeat nonce, is writing to the same database
+    ConcurrentInstance { nonce: u64, last_seen: NaiveDateTime },
+    /// An output of a mined transaction disappeared from the UTXO set without this wallet spending it
+    OwnOutputSpentElsewhere {
+        commitment: String,
+        value: MicroTari,
+        tx_id: TxId,
+    },
+}
+
+impl Display for CloneDetectionReason {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            CloneDetectionReason::ConcurrentInstance { nonce, last_seen } => write!(
+                f,
+                "Another wallet instance (nonce {}) wrote to this wallet database at {}",
+                nonce, last_seen
+            ),
+            CloneDetectionReason::OwnOutputSpentElsewhere {
+                commitment,
+                value,
+                tx_id,
+            } => write!(
+                f,
+                "Output {} ({}) of transaction {} was spent by another copy of this wallet",
+                commitment, value, tx_id
+            ),
+        }
+    }
+}
+
+/// Writes the heartbeat of this wallet instance and watches the Output Manager Service for outputs that were spent
+/// elsewhere. A detected clone is persisted, journaled as a `WalletCloneDetected` event and locks sending in the
+/// Transaction Service.
+pub struct CloneDetector<T>
+where T: WalletBackend + 'static
+{
+    db: WalletDatabase<T>,
+    transaction_service: TransactionServiceHandle,
+    event_journal_publisher: broadcast::Sender<Arc<EventJournalEntry>>,
+    nonce: u64,
+    heartbeat_written: bool,
+}
+
+impl<T> CloneDetector<T>
+where T: WalletBackend + 'static
+{
+    pub fn new(
+        db: WalletDatabase<T>,
+        transaction_service: TransactionServiceHandle,
+        event_journal_publisher: broadcast::Sender<Arc<EventJournalEntry>>,
+    ) -> Self
+    {
+        Self {
+            db,
+            transaction_service,
+            event_journal_publisher,
+            nonce: OsRng.next_u64(),
+            heartbeat_written: false,
+        }
+    }
+
+    /// Write a heartbeat every `INSTANCE_HEARTBEAT_INTERVAL` and follow the Output Manager Service events until the
+    /// wallet shuts down
+    pub async fn run(
+        mut self,
+        mut output_manager_events: Fuse<Subscriber<OutputManagerEvent>>,
+        shutdown_signal: ShutdownSignal,
+    )
+    {
+        let mut heartbeats = time::interval(INSTANCE_HEARTBEAT_INTERVAL).fuse();
+        let mut shutdown_signal = shutdown_signal.fuse();
+        loop {
+            futures::select! {
+                _ = heartbeats.select_next_some() => {
+                    if let Err(e) = self.heartbeat().await {
+                        warn!(target: LOG_TARGET, "Could not write the wallet instance heartbeat: {:?}", e);
+                    }
+                },
+                event = output_manager_events.select_next_some() => {
+                    if let Err(e) = self.handle_output_manager_event(&event).await {
+                        warn!(target: LOG_TARGET, "Could not check invalidated output: {:?}", e);
+                    }
+                },
+                _ = shutdown_signal => break,
+                complete => break,
+            }
+        }
+        debug!(target: LOG_TARGET, "Wallet clone detection stopped");
+    }
+
+    /// Check that the heartbeat in the database is still the one this instance last wrote and write a new one.
+    /// Returns whether another instance was detected.
+    pub async fn heartbeat(&mut self) -> Result<bool, WalletError> {
+        let mut detected = false;
+        if self.heartbeat_written {
+            if let Some(heartbeat) = self.db.get_instance_heartbeat().await? {
+                if heartbeat.nonce != self.nonce {
+                    self.detect(CloneDetectionReason::ConcurrentInstance {
+                        nonce: heartbeat.nonce,
+                        last_seen: heartbeat.timestamp,
+                    })
+                    .await?;
+                    detected = true;
+                }
+            }
+        }
+
+        self.db
+            .set_instance_heartbeat(InstanceHeartbeat {
+                nonce: self.nonce,
+                timestamp: Utc::now().naive_utc(),
+            })
+            .await?;
+        self.heartbeat_written = true;
+        Ok(detected)
+    }
+
+    // Outputs of transactions that were never mined are expected to be invalidated, only an output that was mined
+    // and then spent without this wallet's involvement points to a clone
+    async fn handle_output_manager_event(&mut self, event: &OutputManagerEvent) -> Result<(), WalletError> {
+        if let OutputManagerEvent::TxoInvalidated {
+            commitment,
+            value,
+            tx_id: Some(tx_id),
+        } = event
+        {
+            let completed_transactions = self.transaction_service.get_completed_transactions().await?;
+            if completed_transactions.get(tx_id).map(|tx| &tx.status) == Some(&TransactionStatus::Mined) {
+                self.detect(CloneDetectionReason::OwnOutputSpentElsewhere {
+                    commitment: commitment.to_hex(),
+                    value: *value,
+                    tx_id: *tx_id,
+                })
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the detection, lock sending and publish a `WalletCloneDetected` event. An earlier detection that has
+    /// not been acknowledged is kept as the recorded reason.
+    async fn detect(&mut self, reason: CloneDetectionReason) -> Result<(), WalletError> {
+        error!(target: LOG_TARGET, "Wallet clone detected, locking sending: {}", reason);
+        if self.db.get_clone_detection().await?.is_none() {
+            self.db.set_clone_detection(reason.clone()).await?;
+        }
+        self.transaction_service.set_sending_locked(true).await?;
+
+        let entry = self
+            .db
+            .append_journal_event(WalletEvent::WalletCloneDetected(reason))
+            .await?;
+        // There may be no subscribers, in which case the event is only journaled
+        let _ = self.event_journal_publisher.send(Arc::new(entry));
+        Ok(())
+    }
+}
@@ -22,7 +22,7 @@
 
 use crate::contacts_service::{
     handle::ContactsServiceHandle,
-    service::ContactsService,
+    service::{ContactsService, SERVICE_NAME},
     storage::database::{ContactsBackend, ContactsDatabase},
 };
 use futures::{future, Future};
@@ -30,6 +30,7 @@ use log::*;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -82,7 +83,15 @@ where T: ContactsBackend + 'static
             .expect("Cannot start Contacts Service without setting a storage backend");
 
         executor.spawn(async move {
-            let service = ContactsService::new(receiver, ContactsDatabase::new(backend)).start();
+            let handles = handles_fut.await;
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
+
+            let service = ContactsService::new(receiver, ContactsDatabase::new(backend))
+                .with_monitor(monitor)
+                .start();
 
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
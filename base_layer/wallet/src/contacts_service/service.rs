@@ -27,9 +27,14 @@ use crate::contacts_service::{
 };
 use futures::{pin_mut, StreamExt};
 use log::*;
-use tari_service_framework::reply_channel;
+use tari_service_framework::{
+    reply_channel,
+    watchdog::{ServiceMonitor, Watchdog},
+};
 
 const LOG_TARGET: &str = "wallet:contacts_service";
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Contacts Service";
 
 pub struct ContactsService<T>
 where T: ContactsBackend + 'static
@@ -37,6 +42,7 @@ where T: ContactsBackend + 'static
     db: ContactsDatabase<T>,
     request_stream:
         Option<reply_channel::Receiver<ContactsServiceRequest, Result<ContactsServiceResponse, ContactsServiceError>>>,
+    monitor: ServiceMonitor,
 }
 
 impl<T> ContactsService<T>
@@ -54,9 +60,16 @@ where T: ContactsBackend + 'static
         Self {
             db,
             request_stream: Some(request_stream),
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Report the activities of the service to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     pub async fn start(mut self) -> Result<(), ContactsServiceError> {
         let request_stream = self
             .request_stream
@@ -69,6 +82,7 @@ where T: ContactsBackend + 'static
         loop {
             futures::select! {
                 request_context = request_stream.select_next_some() => {
+                    let _activity = self.monitor.enter("API request");
                     let (request, reply_tx) = request_context.split();
                     let _ = reply_tx.send(self.handle_request(request).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
@@ -25,6 +25,7 @@ use crate::{
     output_manager_service::error::OutputManagerError,
     storage::database::DbKey,
     transaction_service::error::TransactionServiceError,
+    util::encryption::EncryptionError,
 };
 use derive_error::Error;
 use diesel::result::Error as DieselError;
@@ -32,6 +33,7 @@ use log::SetLoggerError;
 use serde_json::Error as SerdeJsonError;
 use tari_comms::{multiaddr, peer_manager::PeerManagerError};
 use tari_comms_dht::store_forward::StoreAndForwardError;
+use tari_core::transactions::transaction_protocol::TransactionProtocolError;
 use tari_p2p::{initialization::CommsInitializationError, services::liveness::error::LivenessError};
 
 #[derive(Debug, Error)]
@@ -46,6 +48,8 @@ pub enum WalletError {
     ContactsServiceError(ContactsServiceError),
     LivenessServiceError(LivenessError),
     StoreAndForwardError(StoreAndForwardError),
+    FaucetError(FaucetError),
+    BackupError(BackupError),
 }
 
 #[derive(Debug, Error)]
@@ -74,4 +78,52 @@ pub enum WalletStorageError {
     BlockingTaskSpawnError(String),
     /// The storage path was invalid unicode or not supported by the host OS
     InvalidUnicodePath,
+    /// The wallet database was created on a different network
+    #[error(msg_embedded, non_std, no_from)]
+    NetworkMismatch(String),
+}
+
+#[derive(Debug, Error)]
+pub enum FaucetError {
+    SerdeJsonError(SerdeJsonError),
+    /// The claims file contains a commitment that is not valid hex
+    #[error(msg_embedded, non_std, no_from)]
+    InvalidCommitment(String),
+    /// The faucet public key in the claims file is not valid hex
+    InvalidSourcePublicKey,
+}
+
+#[derive(Debug, Error)]
+pub enum TestVectorError {
+    SerdeJsonError(SerdeJsonError),
+    TransactionProtocolError(TransactionProtocolError),
+    /// The parameters of the test vector do not describe a valid transaction
+    #[error(msg_embedded, non_std, no_from)]
+    InvalidParameters(String),
+    /// A field of the test vector does not match the value produced by this implementation
+    #[error(msg_embedded, non_std, no_from)]
+    Mismatch(String),
+    /// The transaction in the test vector failed validation
+    #[error(msg_embedded, non_std, no_from)]
+    InvalidTransaction(String),
+}
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    SerdeJsonError(SerdeJsonError),
+    EncryptionError(EncryptionError),
+    IoError(std::io::Error),
+    /// The backup bundle was created by a newer version of the wallet
+    #[error(no_from, non_std)]
+    UnsupportedVersion(u32),
+    /// The backup bundle is too short to contain the salt and the encrypted contents
+    BundleTooShort,
+    /// The master seed in the backup bundle is not valid hex
+    InvalidMasterSeed,
+    /// The base node in the backup bundle does not have a valid public key or address
+    InvalidBaseNode,
+    /// The wallet already has a master seed. Replacing it would make the outputs of the wallet unspendable.
+    WalletAlreadyInitialized,
+    /// A node identity file already exists at the restore path
+    IdentityFileExists,
 }
@@ -4,10 +4,14 @@
 
 #[macro_use]
 mod macros;
+pub mod auto_coin_split;
+pub mod backup;
+pub mod clone_detection;
 pub mod contacts_service;
 pub mod error;
 pub mod output_manager_service;
 pub mod storage;
+pub mod transaction_lifecycle;
 pub mod transaction_service;
 pub mod types;
 pub mod util;
@@ -20,17 +20,142 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::output_manager_service::service::UTXOSelectionStrategy;
+use rand::{CryptoRng, RngCore};
 use std::time::Duration;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_key_manager::{
+    bip39,
+    mnemonic::{MnemonicError, MnemonicLanguage},
+};
 
 #[derive(Clone)]
 pub struct OutputManagerServiceConfig {
     pub base_node_query_timeout: Duration,
+    /// When not zero, the base node query timeout is tuned to this multiple of the average round trip latency observed
+    /// to the base node, within `min_base_node_query_timeout` and `max_base_node_query_timeout`. Zero always uses
+    /// `base_node_query_timeout`.
+    pub base_node_query_timeout_latency_factor: u32,
+    /// The shortest base node query timeout the adaptive tuning may choose
+    pub min_base_node_query_timeout: Duration,
+    /// The longest base node query timeout the adaptive tuning may choose
+    pub max_base_node_query_timeout: Duration,
+    /// How many times a timed out UTXO query is retried before the sync is abandoned and
+    /// `OutputManagerEvent::BaseNodeSyncFailed` is published
+    pub base_node_query_max_retries: usize,
+    /// The delay before the first retry of a timed out UTXO query. The delay doubles with every further retry, and a
+    /// zero delay retries immediately.
+    pub base_node_query_retry_backoff: Duration,
+    /// The most output hashes sent to the base node in one UTXO query request. Larger wallets split the query into
+    /// several requests.
+    pub base_node_query_chunk_size: usize,
+    /// The smallest output value the wallet will create. This must match the network's consensus `min_output_value`,
+    /// because the range proofs of the outputs the wallet creates are constructed for it. The default is that of the
+    /// Rincewind network.
+    pub min_output_value: MicroTari,
+    /// The smallest fee per gram the wallet will pay. This should match the network's consensus `min_fee_per_gram`;
+    /// zero allows zero-fee transactions, as on LocalNet.
+    pub min_fee_per_gram: MicroTari,
+    /// The strategy used to select which unspent outputs fund a transaction
+    pub utxo_selection_strategy: UTXOSelectionStrategy,
+    /// How often the base node is asked for its chain tip height, which determines which time-locked outputs are
+    /// spendable
+    pub chain_tip_poll_interval: Duration,
+    /// The unspent and invalid outputs are validated against the base node when it reports a new chain tip or a new
+    /// base node is set, but no more often than this. A new tip reported within this interval is validated after it.
+    pub validation_min_interval: Duration,
+    /// The output values a recovery scan looks for. A commitment hides its value, so the base node can only be asked
+    /// for the outputs of a regenerated key with a value from this list.
+    pub recovery_scan_values: Vec<MicroTari>,
+    /// Unspent outputs below this value are dust and are not selected to fund a transaction unless the transaction
+    /// asks for them, because spending them costs more in fees than they are worth. Zero treats no output as dust.
+    pub dust_threshold: MicroTari,
+    /// The language of the seed words returned when no language is requested
+    pub mnemonic_language: MnemonicLanguage,
+    /// The seed words the master seed of a new wallet is created from, e.g. to restore a wallet. A random master seed
+    /// is created if none are given. They are ignored once the wallet has a master seed.
+    pub seed_words: Option<WalletSeed>,
+    /// The number of equal outputs, each with a fresh key, that the change of a sent transaction is split into to make
+    /// amount analysis harder. Fewer are created when the change can't pay for them all.
+    pub num_change_outputs: usize,
+    /// The number of blocks a coinbase output is locked for after the block it is mined in. This should match the
+    /// network's consensus `coinbase_lock_height`, and is used to work out the height of the block a coinbase was
+    /// meant for from its maturity.
+    pub coinbase_lock_height: u64,
+    /// A coinbase that has not been mined by the time the chain tip is this many blocks past the block it was meant
+    /// for is invalidated, because that block was won by another miner. Zero never invalidates pending coinbases.
+    pub coinbase_expiry_blocks: u64,
+    /// The expected time between blocks, used to estimate when time-locked coinbases become spendable. This should
+    /// match the network's consensus target block interval.
+    pub block_interval: Duration,
+    /// Run as a view-only wallet without a master seed. No key manager state is read or created, and requests that
+    /// derive keys or spend outputs fail with `ReadOnlyWallet`, while balances, output lists and base node sync keep
+    /// working on the persisted outputs.
+    pub read_only: bool,
+    /// The number of most recent UTXO queries kept in the sync history
+    pub max_sync_history_entries: usize,
 }
 
 impl Default for OutputManagerServiceConfig {
     fn default() -> Self {
         Self {
             base_node_query_timeout: Duration::from_secs(30),
+            base_node_query_timeout_latency_factor: 0,
+            min_base_node_query_timeout: Duration::from_secs(5),
+            max_base_node_query_timeout: Duration::from_secs(120),
+            base_node_query_max_retries: 5,
+            base_node_query_retry_backoff: Duration::from_secs(10),
+            base_node_query_chunk_size: 500,
+            min_output_value: MicroTari(0),
+            min_fee_per_gram: MicroTari(5),
+            utxo_selection_strategy: UTXOSelectionStrategy::MaturityThenSmallest,
+            chain_tip_poll_interval: Duration::from_secs(60),
+            validation_min_interval: Duration::from_secs(30),
+            recovery_scan_values: Vec::new(),
+            dust_threshold: MicroTari(0),
+            mnemonic_language: MnemonicLanguage::English,
+            seed_words: None,
+            num_change_outputs: 1,
+            coinbase_lock_height: 60,
+            coinbase_expiry_blocks: 10,
+            block_interval: Duration::from_secs(120),
+            read_only: false,
+            max_sync_history_entries: 100,
         }
     }
 }
+
+/// Seed words that the master seed of a wallet is created from
+#[derive(Clone)]
+pub enum WalletSeed {
+    /// Tari seed words, which encode the master seed itself. The language is detected if none is given.
+    Tari {
+        words: Vec<String>,
+        language: Option<MnemonicLanguage>,
+    },
+    /// BIP-39 seed words. The master seed is derived from the words and the passphrase, which may be empty, so the
+    /// same words with a different passphrase create a different wallet. The language is detected if none is given.
+    Bip39 {
+        words: Vec<String>,
+        language: Option<MnemonicLanguage>,
+        passphrase: String,
+    },
+}
+
+impl WalletSeed {
+    /// Random 24 word BIP-39 seed words in the given language, protected by the given passphrase
+    pub fn random_bip39<R: CryptoRng + RngCore>(
+        rng: &mut R,
+        language: MnemonicLanguage,
+        passphrase: String,
+    ) -> Result<Self, MnemonicError>
+    {
+        let mut entropy = [0u8; 32];
+        rng.fill_bytes(&mut entropy);
+        Ok(WalletSeed::Bip39 {
+            words: bip39::from_entropy(&entropy, &language)?,
+            language: Some(language),
+            passphrase,
+        })
+    }
+}
@@ -20,9 +20,13 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::storage::database::DbKey;
+use crate::{
+    output_manager_service::{storage::database::DbKey, TxId},
+    util::encryption::EncryptionError,
+};
 use derive_error::Error;
 use diesel::result::Error as DieselError;
+use serde_json::Error as SerdeJsonError;
 use tari_comms_dht::outbound::DhtOutboundError;
 use tari_core::transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError};
 use tari_crypto::tari_utilities::ByteArrayError;
@@ -43,12 +47,34 @@ pub enum OutputManagerError {
     KeyManagerError(KeyManagerError),
     TransactionError(TransactionError),
     DhtOutboundError(DhtOutboundError),
+    SerdeJsonError(SerdeJsonError),
+    EncryptionError(EncryptionError),
     #[error(msg_embedded, no_from, non_std)]
     ConversionError(String),
     /// Not all the transaction inputs and outputs are present to be confirmed
     IncompleteTransaction,
     /// Not enough funds to fulfil transaction
     NotEnoughFunds,
+    /// Not enough spendable funds to fulfil transaction, but there will be once time-locked outputs mature
+    FundsPendingMaturity,
+    /// Not enough funds from any single output source to fulfil transaction without mixing sources
+    NotEnoughFundsFromSingleSource,
+    /// A pinned input is not one of the wallet's unspent outputs
+    #[error(msg_embedded, no_from, non_std)]
+    InputNotFound(String),
+    /// The output is not one of the wallet's unspent outputs
+    #[error(msg_embedded, no_from, non_std)]
+    OutputNotFound(String),
+    /// The same input was pinned more than once
+    DuplicateInput,
+    /// A coin join needs at least two spendable outputs to consolidate
+    NotEnoughOutputsToJoin,
+    /// The requested output value is below the minimum output value
+    BelowMinimumOutputValue,
+    /// A transaction to several recipients was requested without any recipients
+    NoRecipients,
+    /// The requested fee per gram is below the network's minimum fee per gram
+    FeePerGramBelowMinimum,
     /// Output already exists
     DuplicateOutput,
     /// Error sending a message to the public API
@@ -64,8 +90,30 @@ pub enum OutputManagerError {
     InvalidResponseError(String),
     /// No Base Node public key has been provided for this service to use for contacting a base node
     NoBaseNodeKeysProvided,
+    /// A recovery scan is already waiting for the base node
+    RecoveryScanInProgress,
+    /// A recovery scan needs a gap limit of at least one key
+    InvalidGapLimit,
+    /// A scan for outputs needs at least one value in the `recovery_scan_values` config to look for
+    NoRecoveryScanValues,
+    /// The start height of a height range scan is above its end height
+    InvalidHeightRange,
+    /// The key manager branch has not been initialised
+    KeyManagerBranchNotInitialized,
+    /// The secret key provider does not expose the master key of the wallet
+    MasterKeyNotAvailable,
+    /// The wallet is read-only and has no master seed to derive keys or spend outputs with
+    ReadOnlyWallet,
+    /// The output export file could not be read
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidOutputExport(String),
     /// An error occured sending an event out on the event stream
     EventStreamError,
+    #[error(msg_embedded, no_from, non_std)]
+    BlockingTaskSpawnError(String),
+    /// The transaction with this TxId was abandoned and cannot be accepted again
+    #[error(no_from, non_std)]
+    TransactionAbandoned(TxId),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -96,4 +144,14 @@ pub enum OutputManagerStorageError {
     DatabaseMigrationError(String),
     #[error(msg_embedded, non_std, no_from)]
     BlockingTaskSpawnError(String),
+    SerdeJsonError(SerdeJsonError),
+    EncryptionError(EncryptionError),
+    /// Encryption has already been applied to the database
+    AlreadyEncrypted,
+    /// The database is not encrypted
+    NotEncrypted,
+    /// The database is encrypted and has not been unlocked with the passphrase
+    DatabaseLocked,
+    /// The passphrase does not match the one the database was encrypted with
+    InvalidPassphrase,
 }
@@ -22,19 +22,32 @@
 
 use crate::output_manager_service::{
     error::OutputManagerError,
-    service::Balance,
-    storage::database::PendingTransactionOutputs,
+    service::{Balance, BaseNodeSyncStatus, OutputStatistics, PendingCoinbase, TransactionSendOptions},
+    storage::database::{
+        AbandonedTransaction,
+        AbandonmentReason,
+        InvalidOutput,
+        KeyManagerBranch,
+        KeyManagerState,
+        OutputMetadata,
+        PendingTransactionOutputs,
+        SyncHistoryEntry,
+    },
+    TxId,
 };
 use futures::{stream::Fuse, StreamExt};
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt, time::Duration};
 use tari_broadcast_channel::Subscriber;
-use tari_comms::types::CommsPublicKey;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{Transaction, TransactionInput, TransactionOutput, UnblindedOutput},
-    types::PrivateKey,
+    types::{Commitment, PrivateKey},
     SenderTransactionProtocol,
 };
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_key_manager::mnemonic::MnemonicLanguage;
 use tari_service_framework::reply_channel::SenderService;
 use tower::Service;
 
@@ -42,46 +55,142 @@ use tower::Service;
 #[derive(Debug)]
 pub enum OutputManagerRequest {
     GetBalance,
+    GetBalanceAtHeight(u64),
     AddOutput(UnblindedOutput),
+    AddOutputs(Vec<UnblindedOutput>),
     GetRecipientKey((u64, MicroTari)),
     GetCoinbaseKey((u64, MicroTari, u64)),
+    GetNextKey(KeyManagerBranch),
     ConfirmPendingTransaction(u64),
     ConfirmTransaction((u64, Vec<TransactionInput>, Vec<TransactionOutput>)),
-    PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String)),
+    PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String, TransactionSendOptions)),
+    PrepareToSendTransactionWithInputs((MicroTari, MicroTari, Option<u64>, String, Vec<Commitment>)),
+    PrepareToSendTransactionToMany((Vec<MicroTari>, MicroTari, Option<u64>, String, TransactionSendOptions)),
+    PrepareToSendAll((MicroTari, String)),
+    EstimateFee((MicroTari, MicroTari, usize)),
     CancelTransaction(u64),
+    ReinstateTransaction(PendingTransactionOutputs),
+    AbandonTransaction((TxId, AbandonmentReason)),
+    GetAbandonedTransactions,
+    ReserveOutputs { amount: MicroTari, fee_per_gram: MicroTari },
+    ReleaseReservation(u64),
     TimeoutTransactions(Duration),
     GetPendingTransactions,
+    GetPendingTransaction(TxId),
+    SetPendingTransactionDestination((TxId, CommsPublicKey)),
+    GetPendingCoinbases,
     GetSpentOutputs,
     GetUnspentOutputs,
+    GetDustOutputs,
     GetInvalidOutputs,
-    GetSeedWords,
+    GetOutputStatistics,
+    GetSeedWords(Option<MnemonicLanguage>),
+    GetKeyManagerState,
     SetBaseNodePublicKey(CommsPublicKey),
     SyncWithBaseNode,
+    CancelBaseNodeSync,
+    GetBaseNodeSyncStatus,
+    RevalidateOutputs,
     CreateCoinSplit((MicroTari, usize, MicroTari, Option<u64>)),
+    CreateCoinJoin((usize, MicroTari)),
+    SetOutputSource((u64, String)),
+    SetOutputLabel((Commitment, Option<String>)),
+    FetchOutputsByLabel(String),
+    GetOutputMetadata,
+    GetSyncHistory(usize),
+    CreateBurnTransaction((MicroTari, MicroTari, Option<u64>)),
+    ScanForOutputsFromSeed((Vec<String>, Option<MnemonicLanguage>, usize, bool)),
+    ScanOutputsForHeightRange((u64, u64)),
+    ApplyEncryption(String),
+    RemoveEncryption,
+    ExportOutputs(String),
+    ImportOutputs((String, String)),
+    SetBaseNodeQueryTimeout(Duration),
+    GetBaseNodeQueryTimeout,
+    ReportPeerLatency((NodeId, Duration)),
 }
 
 impl fmt::Display for OutputManagerRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::GetBalance => f.write_str("GetBalance"),
+            Self::GetBalanceAtHeight(height) => f.write_str(&format!("GetBalanceAtHeight ({})", height)),
             Self::AddOutput(v) => f.write_str(&format!("AddOutput ({})", v.value)),
+            Self::AddOutputs(v) => f.write_str(&format!("AddOutputs ({} outputs)", v.len())),
             Self::GetRecipientKey(v) => f.write_str(&format!("GetRecipientKey ({})", v.0)),
             Self::GetCoinbaseKey(v) => f.write_str(&format!("GetCoinbaseKey ({})", v.0)),
+            Self::GetNextKey(branch) => f.write_str(&format!("GetNextKey ({})", branch)),
             Self::ConfirmTransaction(v) => f.write_str(&format!("ConfirmTransaction ({})", v.0)),
             Self::ConfirmPendingTransaction(v) => f.write_str(&format!("ConfirmPendingTransaction ({})", v)),
-            Self::PrepareToSendTransaction((_, _, _, msg)) => {
+            Self::PrepareToSendTransaction((_, _, _, msg, _)) => {
                 f.write_str(&format!("PrepareToSendTransaction ({})", msg))
             },
+            Self::PrepareToSendTransactionWithInputs((_, _, _, msg, inputs)) => f.write_str(&format!(
+                "PrepareToSendTransactionWithInputs ({}, {} inputs)",
+                msg,
+                inputs.len()
+            )),
+            Self::PrepareToSendTransactionToMany((amounts, _, _, msg, _)) => f.write_str(&format!(
+                "PrepareToSendTransactionToMany ({}, {} recipients)",
+                msg,
+                amounts.len()
+            )),
+            Self::PrepareToSendAll((_, msg)) => f.write_str(&format!("PrepareToSendAll ({})", msg)),
+            Self::EstimateFee((amount, _, num_outputs)) => {
+                f.write_str(&format!("EstimateFee ({}, {} outputs)", amount, num_outputs))
+            },
             Self::CancelTransaction(v) => f.write_str(&format!("CancelTransaction ({})", v)),
+            Self::ReinstateTransaction(v) => f.write_str(&format!("ReinstateTransaction ({})", v.tx_id)),
+            Self::ReserveOutputs { amount, .. } => f.write_str(&format!("ReserveOutputs ({})", amount)),
+            Self::ReleaseReservation(v) => f.write_str(&format!("ReleaseReservation ({})", v)),
             Self::TimeoutTransactions(d) => f.write_str(&format!("TimeoutTransactions ({}s)", d.as_secs())),
             Self::GetPendingTransactions => f.write_str("GetPendingTransactions"),
+            Self::GetPendingTransaction(tx_id) => f.write_str(&format!("GetPendingTransaction ({})", tx_id)),
+            Self::SetPendingTransactionDestination((tx_id, destination)) => f.write_str(&format!(
+                "SetPendingTransactionDestination ({}, {})",
+                tx_id, destination
+            )),
+            Self::GetPendingCoinbases => f.write_str("GetPendingCoinbases"),
             Self::GetSpentOutputs => f.write_str("GetSpentOutputs"),
             Self::GetUnspentOutputs => f.write_str("GetUnspentOutputs"),
+            Self::GetDustOutputs => f.write_str("GetDustOutputs"),
             Self::GetInvalidOutputs => f.write_str("GetInvalidOutputs"),
-            Self::GetSeedWords => f.write_str("GetSeedWords"),
+            Self::GetOutputStatistics => f.write_str("GetOutputStatistics"),
+            Self::GetSeedWords(v) => f.write_str(&format!("GetSeedWords ({:?})", v)),
+            Self::GetKeyManagerState => f.write_str("GetKeyManagerState"),
             Self::SetBaseNodePublicKey(k) => f.write_str(&format!("SetBaseNodePublicKey ({})", k)),
             Self::SyncWithBaseNode => f.write_str("SyncWithBaseNode"),
+            Self::CancelBaseNodeSync => f.write_str("CancelBaseNodeSync"),
+            Self::GetBaseNodeSyncStatus => f.write_str("GetBaseNodeSyncStatus"),
+            Self::RevalidateOutputs => f.write_str("RevalidateOutputs"),
             Self::CreateCoinSplit(v) => f.write_str(&format!("CreateCoinSplit ({})", v.0)),
+            Self::CreateCoinJoin(v) => f.write_str(&format!("CreateCoinJoin ({})", v.0)),
+            Self::SetOutputSource(v) => f.write_str(&format!("SetOutputSource ({}, {})", v.0, v.1)),
+            Self::SetOutputLabel(v) => f.write_str(&format!("SetOutputLabel ({}, {:?})", v.0.to_hex(), v.1)),
+            Self::FetchOutputsByLabel(v) => f.write_str(&format!("FetchOutputsByLabel ({})", v)),
+            Self::GetOutputMetadata => f.write_str("GetOutputMetadata"),
+            Self::GetSyncHistory(limit) => f.write_str(&format!("GetSyncHistory ({})", limit)),
+            Self::AbandonTransaction((tx_id, reason)) => {
+                f.write_str(&format!("AbandonTransaction ({}, {})", tx_id, reason))
+            },
+            Self::GetAbandonedTransactions => f.write_str("GetAbandonedTransactions"),
+            Self::CreateBurnTransaction(v) => f.write_str(&format!("CreateBurnTransaction ({})", v.0)),
+            Self::ScanForOutputsFromSeed(v) => f.write_str(&format!(
+                "ScanForOutputsFromSeed ({:?}, gap limit {}, replace key manager {})",
+                v.1, v.2, v.3
+            )),
+            Self::ScanOutputsForHeightRange((start, end)) => {
+                f.write_str(&format!("ScanOutputsForHeightRange ({}..={})", start, end))
+            },
+            Self::ApplyEncryption(_) => f.write_str("ApplyEncryption"),
+            Self::RemoveEncryption => f.write_str("RemoveEncryption"),
+            Self::ExportOutputs(_) => f.write_str("ExportOutputs"),
+            Self::ImportOutputs(_) => f.write_str("ImportOutputs"),
+            Self::SetBaseNodeQueryTimeout(t) => f.write_str(&format!("SetBaseNodeQueryTimeout ({:?})", t)),
+            Self::GetBaseNodeQueryTimeout => f.write_str("GetBaseNodeQueryTimeout"),
+            Self::ReportPeerLatency((node_id, latency)) => {
+                f.write_str(&format!("ReportPeerLatency ({}, {:?})", node_id, latency))
+            },
         }
     }
 }
@@ -90,29 +199,100 @@ impl fmt::Display for OutputManagerRequest {
 pub enum OutputManagerResponse {
     Balance(Balance),
     OutputAdded,
+    OutputsAdded,
     RecipientKeyGenerated(PrivateKey),
+    NextKey(PrivateKey),
     OutputConfirmed,
     PendingTransactionConfirmed,
     TransactionConfirmed,
     TransactionToSend(SenderTransactionProtocol),
+    FeeEstimate((MicroTari, Vec<UnblindedOutput>)),
     TransactionCancelled,
+    TransactionReinstated,
+    TransactionAbandoned,
+    AbandonedTransactions(Vec<AbandonedTransaction>),
+    OutputsReserved((u64, Vec<UnblindedOutput>)),
+    ReservationReleased,
     TransactionsTimedOut,
     PendingTransactions(HashMap<u64, PendingTransactionOutputs>),
+    PendingTransaction(PendingTransactionOutputs),
+    PendingTransactionDestinationSet,
+    PendingCoinbases(Vec<PendingCoinbase>),
     SpentOutputs(Vec<UnblindedOutput>),
     UnspentOutputs(Vec<UnblindedOutput>),
-    InvalidOutputs(Vec<UnblindedOutput>),
+    DustOutputs(Vec<UnblindedOutput>),
+    InvalidOutputs(Vec<InvalidOutput>),
+    OutputStatistics(OutputStatistics),
     SeedWords(Vec<String>),
+    KeyManagerState(KeyManagerState),
     BaseNodePublicKeySet,
     StartedBaseNodeSync(u64),
+    BaseNodeSyncCancelled(Vec<u64>),
+    /// The coinbase with this TxId was invalidated because the block it was meant for was won by another miner
+    CoinbaseInvalidated(TxId),
+    BaseNodeSyncStatus(BaseNodeSyncStatus),
+    OutputRevalidationStarted(Option<u64>),
     Transaction((u64, Transaction, MicroTari, MicroTari)),
+    OutputSourceSet,
+    OutputLabelSet,
+    LabelledOutputs(Vec<UnblindedOutput>),
+    OutputMetadata(Vec<OutputMetadata>),
+    SyncHistory(Vec<SyncHistoryEntry>),
+    RecoveryScanStarted(u64),
+    HeightRangeScanStarted(u64),
+    EncryptionApplied,
+    EncryptionRemoved,
+    OutputsExported(String),
+    OutputsImported(usize),
+    BaseNodeQueryTimeoutSet,
+    BaseNodeQueryTimeout(Duration),
+    PeerLatencyReported,
 }
 
 /// Events that can be published on the Text Message Service Event Stream
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OutputManagerEvent {
     BaseNodeSyncRequestTimedOut(u64),
-    ReceiveBaseNodeResponse(u64),
-    Error(String),
+    /// The UTXO query with this key timed out after the configured number of retries and the sync was abandoned
+    BaseNodeSyncFailed(u64),
+    /// The in-flight UTXO queries with these request keys were cancelled, and a scheduled retry was dropped
+    BaseNodeSyncCancelled(Vec<u64>),
+    /// The base node has answered for this many of the total number of outputs in the current UTXO query. Published
+    /// as the response to each request of a query that is split into several requests arrives.
+    SyncProgress {
+        validated: usize,
+        total: usize,
+    },
+    /// Every response to the UTXO query with this request key was received and processed
+    SyncCompleted {
+        request_key: u64,
+        num_invalidated: usize,
+    },
+    /// An unspent output was not returned by the base node and was moved to the invalid outputs
+    TxoInvalidated {
+        commitment: Commitment,
+        value: MicroTari,
+        tx_id: Option<TxId>,
+    },
+    /// Time-locked outputs with this total value have become spendable because the chain tip reached their maturity
+    FundsMatured(MicroTari),
+    /// The base node answered the revalidation query with this request key and this many invalid outputs were found
+    /// on the blockchain again and restored
+    OutputsRevalidated((u64, usize)),
+    /// The range proofs of this many of the total number of outputs of a coin split have been generated
+    CoinSplitProgress(usize, usize),
+    /// A recovery scan finished after recovering this many outputs
+    RecoveryScanComplete(usize),
+    /// A height range scan finished after finding this many outputs
+    HeightRangeScanComplete(usize),
+    /// No one or two outputs could fund a transaction with the `PrivacyFirst` selection strategy, so it links this
+    /// many inputs
+    WouldLinkManyInputs(usize),
+    /// A response from this base node could not be processed
+    BaseNodeResponseFailed {
+        request_key: u64,
+        base_node_public_key: CommsPublicKey,
+    },
 }
 
 #[derive(Clone)]
@@ -141,6 +321,15 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Add a batch of unblinded outputs to the unspent outputs in a single database transaction. This should be used
+    /// instead of repeated calls to `add_output` when importing or recovering a large number of outputs.
+    pub async fn add_outputs(&mut self, outputs: Vec<UnblindedOutput>) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::AddOutputs(outputs)).await?? {
+            OutputManagerResponse::OutputsAdded => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_balance(&mut self) -> Result<Balance, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetBalance).await?? {
             OutputManagerResponse::Balance(b) => Ok(b),
@@ -148,6 +337,26 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Fetch the balance with the funds that are time-locked or immature at the provided chain tip height broken out
+    pub async fn get_balance_at_height(&mut self, height: u64) -> Result<Balance, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetBalanceAtHeight(height))
+            .await??
+        {
+            OutputManagerResponse::Balance(b) => Ok(b),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Derive the next key on the given key manager branch. The key is not associated with any output.
+    pub async fn get_next_key(&mut self, branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetNextKey(branch)).await?? {
+            OutputManagerResponse::NextKey(k) => Ok(k),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_recipient_spending_key(
         &mut self,
         tx_id: u64,
@@ -188,6 +397,25 @@ impl OutputManagerHandle {
         lock_height: Option<u64>,
         message: String,
     ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        self.prepare_transaction_to_send_with_options(
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+            TransactionSendOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn prepare_transaction_to_send_with_options(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        options: TransactionSendOptions,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
     {
         match self
             .handle
@@ -196,6 +424,7 @@ impl OutputManagerHandle {
                 fee_per_gram,
                 lock_height,
                 message,
+                options,
             )))
             .await??
         {
@@ -204,6 +433,98 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Prepare a single transaction that pays each of the amounts to a different recipient, sharing the fee and a
+    /// single change output
+    pub async fn prepare_transaction_to_send_to_many(
+        &mut self,
+        amounts: Vec<MicroTari>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        options: TransactionSendOptions,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareToSendTransactionToMany((
+                amounts,
+                fee_per_gram,
+                lock_height,
+                message,
+                options,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Prepare a transaction that spends exactly the unspent outputs with the given commitments instead of selecting
+    /// the inputs automatically
+    pub async fn prepare_transaction_to_send_with_inputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        inputs: Vec<Commitment>,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareToSendTransactionWithInputs((
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+                inputs,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Prepare a transaction that sends the entire spendable balance, less the fee, to a single recipient. Every
+    /// spendable output is spent and no change output is created.
+    pub async fn prepare_transaction_to_send_all(
+        &mut self,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareToSendAll((fee_per_gram, message)))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Estimate the fee of sending the amount to the given number of recipient outputs. The same UTXO selection as
+    /// `prepare_transaction_to_send` is performed, but nothing is encumbered. Returns the fee and the outputs that
+    /// would be spent.
+    pub async fn estimate_fee(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        num_outputs: usize,
+    ) -> Result<(MicroTari, Vec<UnblindedOutput>), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::EstimateFee((amount, fee_per_gram, num_outputs)))
+            .await??
+        {
+            OutputManagerResponse::FeeEstimate(estimate) => Ok(estimate),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn confirm_pending_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
         match self
             .handle
@@ -247,6 +568,96 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Encumber the outputs of a cancelled transaction against it again, as they were before it was cancelled
+    pub async fn reinstate_transaction(
+        &mut self,
+        pending: PendingTransactionOutputs,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReinstateTransaction(pending))
+            .await??
+        {
+            OutputManagerResponse::TransactionReinstated => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Abandon a pending transaction that the counterparty cancelled or that can never be mined. The outputs it
+    /// would have created are never returned to the unspent pool and the transaction cannot be accepted again.
+    pub async fn abandon_transaction(
+        &mut self,
+        tx_id: TxId,
+        reason: AbandonmentReason,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::AbandonTransaction((tx_id, reason)))
+            .await??
+        {
+            OutputManagerResponse::TransactionAbandoned => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_abandoned_transactions(&mut self) -> Result<Vec<AbandonedTransaction>, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAbandonedTransactions)
+            .await??
+        {
+            OutputManagerResponse::AbandonedTransactions(t) => Ok(t),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Select and short-term encumber unspent outputs that cover the amount and the fee of a transaction with a single
+    /// recipient output, without building a Sender Transaction Protocol. Returns the reservation id and the reserved
+    /// outputs so that a caller can construct its own transaction from them. The reservation is released on restart,
+    /// with `release_reservation` or when it is finalised with `confirm_pending_transaction`.
+    pub async fn reserve_outputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<(u64, Vec<UnblindedOutput>), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReserveOutputs { amount, fee_per_gram })
+            .await??
+        {
+            OutputManagerResponse::OutputsReserved(reservation) => Ok(reservation),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Release the outputs held by a reservation back into the unspent pool
+    pub async fn release_reservation(&mut self, reservation_id: u64) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReleaseReservation(reservation_id))
+            .await??
+        {
+            OutputManagerResponse::ReservationReleased => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Record the source of the outputs that will be received in the specified pending transaction. The tag is
+    /// typically the public key of the counterparty sending the funds.
+    pub async fn set_output_source(&mut self, tx_id: u64, tag: String) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::SetOutputSource((tx_id, tag)))
+            .await??
+        {
+            OutputManagerResponse::OutputSourceSet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn timeout_transactions(&mut self, period: Duration) -> Result<(), OutputManagerError> {
         match self
             .handle
@@ -267,6 +678,52 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Returns the outputs, amount and fee of a single pending transaction
+    pub async fn get_pending_transaction(
+        &mut self,
+        tx_id: TxId,
+    ) -> Result<PendingTransactionOutputs, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetPendingTransaction(tx_id))
+            .await??
+        {
+            OutputManagerResponse::PendingTransaction(p) => Ok(p),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Record the recipient of a pending outbound transaction, so that it can be reported with the amount and fee of
+    /// the pending spend
+    pub async fn set_pending_transaction_destination(
+        &mut self,
+        tx_id: TxId,
+        destination: CommsPublicKey,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::SetPendingTransactionDestination((
+                tx_id,
+                destination,
+            )))
+            .await??
+        {
+            OutputManagerResponse::PendingTransactionDestinationSet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the coinbases that were requested for blocks that have not been mined yet, with the estimated time at
+    /// which each becomes spendable if its block is won
+    pub async fn get_pending_coinbases(&mut self) -> Result<Vec<PendingCoinbase>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetPendingCoinbases).await?? {
+            OutputManagerResponse::PendingCoinbases(p) => Ok(p),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_spent_outputs(&mut self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetSpentOutputs).await?? {
             OutputManagerResponse::SpentOutputs(s) => Ok(s),
@@ -281,20 +738,53 @@ impl OutputManagerHandle {
         }
     }
 
-    pub async fn get_invalid_outputs(&mut self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+    /// Returns the unspent outputs below the configured dust threshold, which are not used to fund transactions unless
+    /// the transaction asks for them
+    pub async fn get_dust_outputs(&mut self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetDustOutputs).await?? {
+            OutputManagerResponse::DustOutputs(s) => Ok(s),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the outputs that were found to be invalid, along with why and when each output was invalidated
+    pub async fn get_invalid_outputs(&mut self) -> Result<Vec<InvalidOutput>, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetInvalidOutputs).await?? {
             OutputManagerResponse::InvalidOutputs(s) => Ok(s),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
 
-    pub async fn get_seed_words(&mut self) -> Result<Vec<String>, OutputManagerError> {
-        match self.handle.call(OutputManagerRequest::GetSeedWords).await?? {
+    /// Returns the counts, values and value distribution of the unspent, spent, pending and invalid outputs
+    pub async fn get_output_statistics(&mut self) -> Result<OutputStatistics, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetOutputStatistics).await?? {
+            OutputManagerResponse::OutputStatistics(s) => Ok(s),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the seed words of the wallet in the given language, or in the configured `mnemonic_language` if no
+    /// language is given
+    pub async fn get_seed_words(
+        &mut self,
+        language: Option<MnemonicLanguage>,
+    ) -> Result<Vec<String>, OutputManagerError>
+    {
+        match self.handle.call(OutputManagerRequest::GetSeedWords(language)).await?? {
             OutputManagerResponse::SeedWords(s) => Ok(s),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
 
+    /// Return the persisted state of the key manager, i.e. the master seed and the key indices of every branch, so that
+    /// it can be included in a wallet backup
+    pub async fn get_key_manager_state(&mut self) -> Result<KeyManagerState, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetKeyManagerState).await?? {
+            OutputManagerResponse::KeyManagerState(s) => Ok(s),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn set_base_node_public_key(&mut self, public_key: CommsPublicKey) -> Result<(), OutputManagerError> {
         match self
             .handle
@@ -313,6 +803,80 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Cancel the UTXO queries that are waiting for a response from the base node, along with their timeouts and any
+    /// scheduled retry. Responses that arrive for the cancelled queries are ignored. This should be used before
+    /// switching base nodes mid-sync. Returns the request keys of the cancelled queries.
+    pub async fn cancel_base_node_sync(&mut self) -> Result<Vec<u64>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::CancelBaseNodeSync).await?? {
+            OutputManagerResponse::BaseNodeSyncCancelled(request_keys) => Ok(request_keys),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the retry state of the UTXO query to the base node
+    pub async fn get_base_node_sync_status(&mut self) -> Result<BaseNodeSyncStatus, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetBaseNodeSyncStatus).await?? {
+            OutputManagerResponse::BaseNodeSyncStatus(s) => Ok(s),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Ask the base node whether any of the invalid outputs are on the blockchain again, which can happen after a
+    /// reorg. Outputs that are found are restored to the unspent outputs. Returns the request key of the query, or
+    /// `None` if there are no invalid outputs.
+    pub async fn revalidate_invalid_outputs(&mut self) -> Result<Option<u64>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::RevalidateOutputs).await?? {
+            OutputManagerResponse::OutputRevalidationStarted(request_key) => Ok(request_key),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the label of the unspent output with the given commitment. A label of `None` clears it.
+    pub async fn set_output_label(
+        &mut self,
+        commitment: Commitment,
+        label: Option<String>,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::SetOutputLabel((commitment, label)))
+            .await??
+        {
+            OutputManagerResponse::OutputLabelSet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the unspent outputs with the given label
+    pub async fn fetch_outputs_by_label(&mut self, label: String) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::FetchOutputsByLabel(label))
+            .await??
+        {
+            OutputManagerResponse::LabelledOutputs(o) => Ok(o),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the labels and provenance recorded for the wallet's outputs
+    pub async fn get_output_metadata(&mut self) -> Result<Vec<OutputMetadata>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetOutputMetadata).await?? {
+            OutputManagerResponse::OutputMetadata(m) => Ok(m),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns up to `limit` of the most recent UTXO queries sent to the base node, newest first, with the outputs
+    /// each of them invalidated
+    pub async fn get_sync_history(&mut self, limit: usize) -> Result<Vec<SyncHistoryEntry>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetSyncHistory(limit)).await?? {
+            OutputManagerResponse::SyncHistory(h) => Ok(h),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn create_coin_split(
         &mut self,
         amount_per_split: MicroTari,
@@ -335,4 +899,183 @@ impl OutputManagerHandle {
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    /// Consolidate up to `max_inputs` of the smallest spendable outputs into a single output
+    pub async fn create_coin_join(
+        &mut self,
+        max_inputs: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateCoinJoin((max_inputs, fee_per_gram)))
+            .await??
+        {
+            OutputManagerResponse::Transaction(ct) => Ok(ct),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn create_burn_transaction(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateBurnTransaction((
+                amount,
+                fee_per_gram,
+                lock_height,
+            )))
+            .await??
+        {
+            OutputManagerResponse::Transaction(ct) => Ok(ct),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Start a scan that regenerates the keys of the wallet with the given seed words and asks the base node for their
+    /// outputs, adding the outputs it finds to the database. The scan stops once `gap_limit` consecutive keys have no
+    /// outputs; `OutputManagerEvent::RecoveryScanComplete` is published when it finishes. Outputs are looked for with
+    /// the values in the `recovery_scan_values` config, and the scan fails with `NoRecoveryScanValues` if there are
+    /// none. When the seed is that of the wallet, the wallet continues deriving its keys after the recovered keys. The
+    /// wallet only continues from a different seed, replacing its key manager state, when it held no outputs and had
+    /// derived no keys when the scan started, or when `replace_key_manager` is set. The language of the seed words is
+    /// detected if no language is given. Returns the request key of the first base node query.
+    pub async fn scan_for_outputs_from_seed(
+        &mut self,
+        seed_words: Vec<String>,
+        language: Option<MnemonicLanguage>,
+        gap_limit: usize,
+        replace_key_manager: bool,
+    ) -> Result<u64, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ScanForOutputsFromSeed((
+                seed_words,
+                language,
+                gap_limit,
+                replace_key_manager,
+            )))
+            .await??
+        {
+            OutputManagerResponse::RecoveryScanStarted(request_key) => Ok(request_key),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Start a scan that asks the base node for the blocks from `start_height` to `end_height`, inclusive, and adds the
+    /// unspent outputs in them that belong to the wallet, including outputs paid to its one-sided keys. This recovers
+    /// outputs received while the wallet was offline without a full resync. Outputs are recognised by the values in
+    /// the `recovery_scan_values` config, and the scan fails with `NoRecoveryScanValues` if there are none;
+    /// `OutputManagerEvent::HeightRangeScanComplete` is published when the scan finishes. Returns the request key of
+    /// the first base node query.
+    pub async fn scan_outputs_for_height_range(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<u64, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ScanOutputsForHeightRange((
+                start_height,
+                end_height,
+            )))
+            .await??
+        {
+            OutputManagerResponse::HeightRangeScanStarted(request_key) => Ok(request_key),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Encrypt the spending keys and key manager state stored in the database with a key derived from the passphrase.
+    /// If the database is already encrypted this unlocks it instead, which must be done before any other request that
+    /// touches the database.
+    pub async fn apply_encryption(&mut self, passphrase: String) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ApplyEncryption(passphrase))
+            .await??
+        {
+            OutputManagerResponse::EncryptionApplied => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Export the unspent outputs to a portable JSON file, encrypted with a key derived from the passphrase. Returns
+    /// the contents of the file.
+    pub async fn export_outputs(&mut self, passphrase: String) -> Result<String, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ExportOutputs(passphrase))
+            .await??
+        {
+            OutputManagerResponse::OutputsExported(export) => Ok(export),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import the outputs in an export file produced by `export_outputs` as unspent outputs and validate them against
+    /// the base node. Outputs the wallet already holds are skipped. Returns the number of outputs imported.
+    pub async fn import_outputs(&mut self, export: String, passphrase: String) -> Result<usize, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ImportOutputs((export, passphrase)))
+            .await??
+        {
+            OutputManagerResponse::OutputsImported(n) => Ok(n),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the timeout of the queries sent to the base node. This stops the adaptive tuning of the timeout.
+    pub async fn set_base_node_query_timeout(&mut self, timeout: Duration) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::SetBaseNodeQueryTimeout(timeout))
+            .await??
+        {
+            OutputManagerResponse::BaseNodeQueryTimeoutSet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Fetch the timeout currently used for the queries sent to the base node
+    pub async fn get_base_node_query_timeout(&mut self) -> Result<Duration, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetBaseNodeQueryTimeout)
+            .await??
+        {
+            OutputManagerResponse::BaseNodeQueryTimeout(t) => Ok(t),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Report a round trip latency measured to a peer, e.g. by the liveness service. Latencies of peers other than the
+    /// base node are ignored.
+    pub async fn report_peer_latency(&mut self, node_id: NodeId, latency: Duration) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ReportPeerLatency((node_id, latency)))
+            .await??
+        {
+            OutputManagerResponse::PeerLatencyReported => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Decrypt the values stored in the database. The database must have been unlocked first.
+    pub async fn remove_encryption(&mut self) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::RemoveEncryption).await?? {
+            OutputManagerResponse::EncryptionRemoved => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{handle::OutputManagerHandle, service::OutputManagerService};
+use crate::output_manager_service::{
+    handle::OutputManagerHandle,
+    service::{OutputManagerService, SERVICE_NAME},
+};
 
 use crate::output_manager_service::{
     config::OutputManagerServiceConfig,
@@ -42,6 +45,7 @@ use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -51,6 +55,8 @@ use tokio::runtime;
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod output_export;
+pub mod secret_key_provider;
 #[allow(unused_assignments)]
 pub mod service;
 pub mod storage;
@@ -129,6 +135,10 @@ where T: OutputManagerBackend + 'static
             let outbound_message_service = handles
                 .get_handle::<OutboundMessageRequester>()
                 .expect("OMS handle required for Output Manager Service");
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
 
             let service = OutputManagerService::new(
                 config,
@@ -141,6 +151,7 @@ where T: OutputManagerBackend + 'static
             )
             .await
             .expect("Could not initialize Output Manager Service")
+            .with_monitor(monitor)
             .start();
 
             futures::pin_mut!(service);
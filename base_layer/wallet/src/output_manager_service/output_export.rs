@@ -0,0 +1,164 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A portable file format for the unspent outputs of a wallet, used to move outputs between wallet implementations.
+//! The outputs are serialized to JSON and encrypted with a key derived from a passphrase, so the file can be stored
+//! and transported like a backup.
+
+use crate::{output_manager_service::error::OutputManagerError, util::encryption::DatabaseCipher};
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, UnblindedOutput},
+    types::PrivateKey,
+};
+use tari_crypto::tari_utilities::hex::{from_hex, to_hex, Hex};
+
+/// The version of the export format written by this wallet
+pub const OUTPUT_EXPORT_VERSION: u32 = 1;
+
+/// A single output in an output export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedOutput {
+    pub value: MicroTari,
+    /// The hex encoded spending key of the output
+    pub spending_key: String,
+    #[serde(default)]
+    pub features: OutputFeatures,
+}
+
+/// An encrypted list of outputs, as written to an export file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputExport {
+    pub version: u32,
+    /// The hex encoded salt the encryption key was derived from the passphrase with
+    pub salt: String,
+    /// The hex encoded, encrypted JSON list of `ExportedOutput`s
+    pub outputs: String,
+}
+
+impl OutputExport {
+    /// Encrypt the outputs with a key derived from the passphrase
+    pub fn new(outputs: &[UnblindedOutput], passphrase: &str) -> Result<Self, OutputManagerError> {
+        let exported_outputs = outputs
+            .iter()
+            .map(|o| ExportedOutput {
+                value: o.value,
+                spending_key: o.spending_key.to_hex(),
+                features: o.features.clone(),
+            })
+            .collect::<Vec<_>>();
+        let salt = DatabaseCipher::generate_salt();
+        let cipher = DatabaseCipher::from_passphrase(passphrase, &salt)?;
+        let ciphertext = cipher.encrypt(serde_json::to_string(&exported_outputs)?.as_bytes())?;
+        Ok(Self {
+            version: OUTPUT_EXPORT_VERSION,
+            salt: to_hex(&salt),
+            outputs: to_hex(&ciphertext),
+        })
+    }
+
+    /// Parse an export file from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, OutputManagerError> {
+        let export: Self = serde_json::from_str(json)?;
+        if export.version > OUTPUT_EXPORT_VERSION {
+            return Err(OutputManagerError::InvalidOutputExport(format!(
+                "Unsupported export version {}",
+                export.version
+            )));
+        }
+        Ok(export)
+    }
+
+    pub fn to_json(&self) -> Result<String, OutputManagerError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Decrypt the outputs with a key derived from the passphrase
+    pub fn decrypt(&self, passphrase: &str) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        let salt = from_hex(&self.salt)
+            .map_err(|_| OutputManagerError::InvalidOutputExport("Salt is not valid hex".to_string()))?;
+        let ciphertext = from_hex(&self.outputs)
+            .map_err(|_| OutputManagerError::InvalidOutputExport("Outputs are not valid hex".to_string()))?;
+        let cipher = DatabaseCipher::from_passphrase(passphrase, &salt)?;
+        let plaintext = cipher.decrypt(&ciphertext)?;
+        let exported_outputs: Vec<ExportedOutput> = serde_json::from_slice(&plaintext)?;
+        exported_outputs
+            .into_iter()
+            .map(|o| {
+                let spending_key = PrivateKey::from_hex(&o.spending_key).map_err(|_| {
+                    OutputManagerError::InvalidOutputExport(format!("Invalid spending key for output of {}", o.value))
+                })?;
+                Ok(UnblindedOutput::new(o.value, spending_key, Some(o.features)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        output_manager_service::{error::OutputManagerError, output_export::OutputExport},
+        util::encryption::EncryptionError,
+    };
+    use rand::rngs::OsRng;
+    use tari_core::transactions::{
+        tari_amount::MicroTari,
+        transaction::{OutputFeatures, UnblindedOutput},
+        types::PrivateKey,
+    };
+    use tari_crypto::{keys::SecretKey, tari_utilities::hex::Hex};
+
+    #[test]
+    fn export_and_import_outputs() {
+        let outputs = vec![
+            UnblindedOutput::new(MicroTari::from(1000), PrivateKey::random(&mut OsRng), None),
+            UnblindedOutput::new(
+                MicroTari::from(2000),
+                PrivateKey::random(&mut OsRng),
+                Some(OutputFeatures::create_coinbase(10)),
+            ),
+        ];
+
+        let export = OutputExport::new(&outputs, "hunter2").unwrap();
+        let json = export.to_json().unwrap();
+        assert!(!json.contains(&outputs[0].spending_key.to_hex()));
+
+        let parsed = OutputExport::from_json(&json).unwrap();
+        assert_eq!(parsed, export);
+        let imported = parsed.decrypt("hunter2").unwrap();
+        assert_eq!(imported.len(), outputs.len());
+        for (imported, output) in imported.iter().zip(outputs.iter()) {
+            assert_eq!(imported.value, output.value);
+            assert_eq!(imported.spending_key, output.spending_key);
+            assert_eq!(imported.features, output.features);
+        }
+        match parsed.decrypt("hunter3") {
+            Err(OutputManagerError::EncryptionError(EncryptionError::DecryptionFailed)) => {},
+            r => panic!("Expected decryption to fail, got {:?}", r),
+        }
+
+        let mut future_export = export;
+        future_export.version += 1;
+        assert!(OutputExport::from_json(&future_export.to_json().unwrap()).is_err());
+    }
+}
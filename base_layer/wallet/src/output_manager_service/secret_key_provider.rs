@@ -0,0 +1,96 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::{
+        error::OutputManagerError,
+        storage::database::{KeyManagerBranch, KeyManagerState},
+    },
+    types::KeyDigest,
+};
+use std::collections::HashMap;
+use tari_core::transactions::types::PrivateKey;
+use tari_key_manager::key_manager::KeyManager;
+
+/// The source of the secret keys of the outputs the Output Manager Service creates: the spending keys of received
+/// payments and coinbases and of change outputs. The default provider derives the keys from a master seed held in
+/// memory; implementing this trait allows the keys to come from an external signer, such as a hardware wallet, that
+/// does not reveal its master seed.
+pub trait SecretKeyProvider: Send {
+    /// Returns the next unused secret key on the given key manager branch
+    fn next_key(&mut self, branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError>;
+
+    /// Returns the master key all the keys are derived from, which is used to produce the seed words of the wallet, or
+    /// `None` if the provider does not expose its master key
+    fn master_key(&self) -> Option<PrivateKey>;
+}
+
+/// A SecretKeyProvider that derives the keys of each key manager branch from a master seed held in memory
+pub struct SeedKeyProvider {
+    key_managers: HashMap<KeyManagerBranch, KeyManager<PrivateKey, KeyDigest>>,
+}
+
+impl SeedKeyProvider {
+    /// Create a key manager for every key manager branch, continuing from the key indices in the state
+    pub fn from_state(state: &KeyManagerState) -> Self {
+        let key_managers = KeyManagerBranch::all()
+            .into_iter()
+            .map(|branch| {
+                let km = KeyManager::from(
+                    state.master_seed.clone(),
+                    state.branch_seed(branch),
+                    state.key_index(branch),
+                );
+                (branch, km)
+            })
+            .collect();
+        Self { key_managers }
+    }
+}
+
+impl SecretKeyProvider for SeedKeyProvider {
+    fn next_key(&mut self, branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError> {
+        match self.key_managers.get_mut(&branch) {
+            Some(km) => Ok(km.next_key()?.k),
+            None => Err(OutputManagerError::KeyManagerBranchNotInitialized),
+        }
+    }
+
+    fn master_key(&self) -> Option<PrivateKey> {
+        self.key_managers
+            .get(&KeyManagerBranch::Spending)
+            .map(|km| km.master_key.clone())
+    }
+}
+
+/// The SecretKeyProvider of a read-only wallet, which has no master seed and so cannot provide any keys
+pub struct ReadOnlyKeyProvider;
+
+impl SecretKeyProvider for ReadOnlyKeyProvider {
+    fn next_key(&mut self, _branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError> {
+        Err(OutputManagerError::ReadOnlyWallet)
+    }
+
+    fn master_key(&self) -> Option<PrivateKey> {
+        None
+    }
+}
@@ -22,21 +22,52 @@
 
 use crate::{
     output_manager_service::{
-        config::OutputManagerServiceConfig,
-        error::OutputManagerError,
+        config::{OutputManagerServiceConfig, WalletSeed},
+        error::{OutputManagerError, OutputManagerStorageError},
         handle::{OutputManagerEvent, OutputManagerRequest, OutputManagerResponse},
-        storage::database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
+        output_export::OutputExport,
+        secret_key_provider::{ReadOnlyKeyProvider, SecretKeyProvider, SeedKeyProvider},
+        storage::database::{
+            AbandonmentReason,
+            InvalidOutput,
+            InvalidationReason,
+            KeyManagerBranch,
+            KeyManagerState,
+            OutputManagerBackend,
+            OutputManagerDatabase,
+            OutputSource,
+            PendingTransactionOutputs,
+            SyncHistoryEntry,
+            SyncOutcome,
+        },
         TxId,
     },
     types::{HashDigest, KeyDigest},
     util::futures::StateDelay,
 };
-use futures::{future::BoxFuture, pin_mut, stream::FuturesUnordered, FutureExt, SinkExt, Stream, StreamExt};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use futures::{
+    channel::mpsc,
+    future::BoxFuture,
+    pin_mut,
+    stream::FuturesUnordered,
+    FutureExt,
+    SinkExt,
+    Stream,
+    StreamExt,
+};
 use log::*;
-use rand::{rngs::OsRng, RngCore};
-use std::{cmp::Ordering, collections::HashMap, convert::TryFrom, fmt, sync::Mutex, time::Duration};
+use rand::{rngs::OsRng, seq::SliceRandom, RngCore};
+use std::{
+    cmp::{max, min, Ordering},
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tari_broadcast_channel::Publisher;
-use tari_comms::types::CommsPublicKey;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
     outbound::{OutboundEncryption, OutboundMessageRequester},
@@ -50,29 +81,55 @@ use tari_core::{
         },
     },
     transactions::{
-        fee::Fee,
+        fee::{FeeModel, WeightedFeeModel},
         tari_amount::MicroTari,
         transaction::{
             KernelFeatures,
             OutputFeatures,
+            OutputFlags,
             Transaction,
             TransactionInput,
             TransactionOutput,
             UnblindedOutput,
+            MINIMUM_TRANSACTION_FEE,
         },
-        types::{CryptoFactories, PrivateKey},
+        transaction_protocol::transaction_initializer::SenderTransactionInitializer,
+        types::{Commitment, CryptoFactories, PrivateKey},
         SenderTransactionProtocol,
     },
 };
-use tari_crypto::{keys::SecretKey as SecretKeyTrait, tari_utilities::hash::Hashable};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::SecretKey as SecretKeyTrait,
+    tari_utilities::{hash::Hashable, hex::Hex, ByteArray},
+};
 use tari_key_manager::{
+    bip39,
     key_manager::KeyManager,
     mnemonic::{from_secret_key, MnemonicLanguage},
 };
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
-use tari_service_framework::reply_channel;
+use tari_service_framework::{
+    reply_channel,
+    watchdog::{ServiceMonitor, Watchdog},
+};
+use tokio::time;
 
 const LOG_TARGET: &str = "wallet::output_manager_service";
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Output Manager Service";
+/// Outputs received within this period are only used by the `PrivacyRandom` selection strategy once older outputs have
+/// been exhausted
+const FRESH_OUTPUT_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+/// The maximum number of branches the branch and bound UTXO selection explores before falling back
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
+/// The number of most recent base node latencies averaged by the adaptive base node query timeout
+const BASE_NODE_LATENCY_SAMPLE_SIZE: usize = 10;
+/// The number of blocks a height range scan asks the base node for in one request
+const HEIGHT_RANGE_SCAN_BATCH_SIZE: u64 = 50;
+/// The number of keys past the last derived key of each key manager branch that a height range scan looks for, so that
+/// outputs paid to keys the wallet has not derived yet are found
+const HEIGHT_RANGE_SCAN_KEY_LOOKAHEAD: usize = 20;
 
 /// This service will manage a wallet's available outputs and the key manager that produces the keys for these outputs.
 /// The service will assemble transactions to be sent from the wallets available outputs and provide keys to receive
@@ -82,7 +139,11 @@ pub struct OutputManagerService<TBackend, BNResponseStream>
 where TBackend: OutputManagerBackend + 'static
 {
     config: OutputManagerServiceConfig,
-    key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
+    // The source of the secret keys of new outputs, by default a key manager for each key manager branch derived from
+    // the same master seed
+    key_provider: Mutex<Box<dyn SecretKeyProvider>>,
+    // Calculates the fees of the transactions built and allowed for in UTXO selection
+    fee_model: Arc<dyn FeeModel>,
     db: OutputManagerDatabase<TBackend>,
     outbound_message_service: OutboundMessageRequester,
     request_stream:
@@ -90,8 +151,46 @@ where TBackend: OutputManagerBackend + 'static
     base_node_response_stream: Option<BNResponseStream>,
     factories: CryptoFactories,
     base_node_public_key: Option<CommsPublicKey>,
-    pending_utxo_query_keys: HashMap<u64, Vec<Vec<u8>>>,
+    pending_utxo_query_keys: HashMap<u64, PendingUtxoQuery>,
+    // The responses received so far for each UTXO query that is split into several requests, keyed by generation
+    utxo_query_responses: HashMap<u64, UtxoQueryResponses>,
+    utxo_query_generation: u64,
+    // The generation of the newest UTXO query that included each output, keyed by output hash. Only the response to
+    // that query may change the state of the output, so that a stale response arriving late cannot invalidate it.
+    output_query_generations: HashMap<Vec<u8>, u64>,
+    // The chain tip height last reported by the base node, used to determine which time-locked outputs are spendable
+    chain_tip_height: Option<u64>,
+    // The hash of the best block last reported by the base node
+    chain_tip_block: Option<Vec<u8>>,
+    // The keys of the chain tip queries awaiting a response and when they were sent. Queries older than the base node
+    // query timeout are dropped when the next one is sent.
+    pending_chain_tip_query_keys: HashMap<u64, Instant>,
+    // Set when the base node reports a new chain tip or a new base node is set, and cleared when the outputs are
+    // validated
+    validation_due: bool,
+    last_validation: Option<Instant>,
+    pending_revalidation_query_keys: HashSet<u64>,
+    // The spending keys of the imported outputs that the base node has not returned yet. If the base node does not
+    // return one of them it is invalidated as a failed import.
+    unconfirmed_import_keys: HashSet<Vec<u8>>,
+    // The recovery scan that is waiting for the base node to return the outputs of a batch of regenerated keys
+    recovery_scan: Option<RecoveryScan>,
+    // The height range scan that is waiting for the base node to return a batch of blocks
+    height_range_scan: Option<HeightRangeScan>,
+    base_node_sync_status: BaseNodeSyncStatus,
+    // The key of the delay that fires when the scheduled retry of a timed out UTXO query is due
+    utxo_query_retry_key: Option<u64>,
+    // The balance at the current chain tip height, served to `GetBalance` requests until the state of an output or the
+    // chain tip height changes. It is shared with the tasks that answer read-only queries concurrently.
+    balance_cache: Arc<Mutex<BalanceCache>>,
+    // The timeout of the queries sent to the base node, which is tuned to the observed base node latency while
+    // `adaptive_query_timeout` is set
+    base_node_query_timeout: Duration,
+    adaptive_query_timeout: bool,
+    base_node_latencies: VecDeque<Duration>,
     event_publisher: Publisher<OutputManagerEvent>,
+    // Reports the activities of the select loop to the watchdog
+    monitor: ServiceMonitor,
 }
 
 impl<TBackend, BNResponseStream> OutputManagerService<TBackend, BNResponseStream>
@@ -112,42 +211,115 @@ where
         factories: CryptoFactories,
     ) -> Result<OutputManagerService<TBackend, BNResponseStream>, OutputManagerError>
     {
-        // Check to see if there is any persisted state, otherwise start fresh
-        let key_manager_state = match db.get_key_manager_state().await? {
-            None => {
-                let starting_state = KeyManagerState {
-                    master_seed: PrivateKey::random(&mut OsRng),
-                    branch_seed: "".to_string(),
-                    primary_key_index: 0,
-                };
-                db.set_key_manager_state(starting_state.clone()).await?;
-                starting_state
-            },
-            Some(km) => km,
+        let key_provider: Box<dyn SecretKeyProvider> = if config.read_only {
+            if config.seed_words.is_some() {
+                warn!(
+                    target: LOG_TARGET,
+                    "The configured seed words are ignored because the wallet is read-only"
+                );
+            }
+            Box::new(ReadOnlyKeyProvider)
+        } else {
+            Box::new(SeedKeyProvider::from_state(
+                &Self::load_key_manager_state(&config, &db).await?,
+            ))
         };
 
         // Clear any encumberances for transactions that were being negotiated but did not complete to become official
         // Pending Transactions.
         db.clear_short_term_encumberances().await?;
 
+        let base_node_query_timeout = config.base_node_query_timeout;
+        let adaptive_query_timeout = config.base_node_query_timeout_latency_factor > 0;
         Ok(OutputManagerService {
             config,
             outbound_message_service,
-            key_manager: Mutex::new(KeyManager::<PrivateKey, KeyDigest>::from(
-                key_manager_state.master_seed,
-                key_manager_state.branch_seed,
-                key_manager_state.primary_key_index,
-            )),
+            key_provider: Mutex::new(key_provider),
+            fee_model: Arc::new(WeightedFeeModel),
             db,
             request_stream: Some(request_stream),
             base_node_response_stream: Some(base_node_response_stream),
             factories,
             base_node_public_key: None,
             pending_utxo_query_keys: HashMap::new(),
+            utxo_query_responses: HashMap::new(),
+            utxo_query_generation: 0,
+            output_query_generations: HashMap::new(),
+            chain_tip_height: None,
+            chain_tip_block: None,
+            pending_chain_tip_query_keys: HashMap::new(),
+            validation_due: false,
+            last_validation: None,
+            pending_revalidation_query_keys: HashSet::new(),
+            unconfirmed_import_keys: HashSet::new(),
+            recovery_scan: None,
+            height_range_scan: None,
+            base_node_sync_status: BaseNodeSyncStatus::default(),
+            utxo_query_retry_key: None,
+            balance_cache: Arc::new(Mutex::new(BalanceCache::default())),
+            base_node_query_timeout,
+            adaptive_query_timeout,
+            base_node_latencies: VecDeque::with_capacity(BASE_NODE_LATENCY_SAMPLE_SIZE),
             event_publisher,
+            monitor: Watchdog::default().register(SERVICE_NAME),
         })
     }
 
+    /// Report the activities of the service to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    // Load the persisted key manager state, or create and persist a new one for a new wallet
+    async fn load_key_manager_state(
+        config: &OutputManagerServiceConfig,
+        db: &OutputManagerDatabase<TBackend>,
+    ) -> Result<KeyManagerState, OutputManagerError>
+    {
+        // Check to see if there is any persisted state, otherwise start fresh
+        let key_manager_state = match db.get_key_manager_state().await? {
+            None => {
+                let starting_state = match config.seed_words.as_ref() {
+                    Some(seed_words) => create_from_seed_words(seed_words)?,
+                    None => KeyManagerState {
+                        master_seed: PrivateKey::random(&mut OsRng),
+                        branch_seed: "".to_string(),
+                        primary_key_index: 0,
+                        branch_indices: HashMap::new(),
+                        bip39_entropy: None,
+                    },
+                };
+                db.set_key_manager_state(starting_state.clone()).await?;
+                starting_state
+            },
+            Some(km) => {
+                if config.seed_words.is_some() {
+                    warn!(
+                        target: LOG_TARGET,
+                        "The configured seed words are ignored because the wallet already has a master seed"
+                    );
+                }
+                km
+            },
+        };
+        Ok(key_manager_state)
+    }
+
+    /// Take the secret keys of new outputs from the given provider, e.g. a hardware signer, instead of deriving them
+    /// from the master seed stored in the database
+    pub fn with_secret_key_provider(mut self, key_provider: Box<dyn SecretKeyProvider>) -> Self {
+        self.key_provider = Mutex::new(key_provider);
+        self
+    }
+
+    /// Calculate the fees of the transactions this service builds, and the fees UTXO selection allows for, with the
+    /// given fee model instead of the `WeightedFeeModel` of the current consensus rules
+    pub fn with_fee_model(mut self, fee_model: Arc<dyn FeeModel>) -> Self {
+        self.fee_model = fee_model;
+        self
+    }
+
     pub async fn start(mut self) -> Result<(), OutputManagerError> {
         let request_stream = self
             .request_stream
@@ -165,39 +337,77 @@ where
 
         let mut utxo_query_timeout_futures: FuturesUnordered<BoxFuture<'static, u64>> = FuturesUnordered::new();
 
+        let chain_tip_poll_interval = self.config.chain_tip_poll_interval;
+        let mut chain_tip_poll_ticker = time::interval_at(
+            (Instant::now() + chain_tip_poll_interval).into(),
+            chain_tip_poll_interval,
+        )
+        .fuse();
+
         info!(target: LOG_TARGET, "Output Manager Service started");
         loop {
             futures::select! {
                 request_context = request_stream.select_next_some() => {
-                trace!(target: LOG_TARGET, "Handling Service API Request");
+                    trace!(target: LOG_TARGET, "Handling Service API Request");
+                    let _activity = self.monitor.enter("API request");
                     let (request, reply_tx) = request_context.split();
-                    let _ = reply_tx.send(self.handle_request(request, &mut utxo_query_timeout_futures).await.or_else(|resp| {
-                        error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
-                        Err(resp)
-                    })).or_else(|resp| {
-                        error!(target: LOG_TARGET, "Failed to send reply");
-                        Err(resp)
-                    });
+                    // Read-only queries are answered on their own task so that they are not held up behind the
+                    // requests that are handled here, which remain serialised
+                    if let Some(query) = self.concurrent_query(&request) {
+                        tokio::spawn(async move {
+                            let _ = reply_tx.send(query.await.or_else(|resp| {
+                                error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                                Err(resp)
+                            })).or_else(|resp| {
+                                error!(target: LOG_TARGET, "Failed to send reply");
+                                Err(resp)
+                            });
+                        });
+                    } else {
+                        let response = self.handle_request(request, &mut utxo_query_timeout_futures).await;
+                        let _ = reply_tx.send(response.or_else(|resp| {
+                            error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                            Err(resp)
+                        })).or_else(|resp| {
+                            error!(target: LOG_TARGET, "Failed to send reply");
+                            Err(resp)
+                        });
+                    }
                 },
                  // Incoming messages from the Comms layer
                 msg = base_node_response_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Base Node Response");
+                    let _activity = self.monitor.enter("base node response");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let result = self.handle_base_node_response(inner_msg).await.or_else(|resp| {
+                    let request_key = inner_msg.request_key;
+                    acquire_lock!(self.balance_cache).invalidate();
+                    let result = self.handle_base_node_response(inner_msg, &mut utxo_query_timeout_futures).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling base node service response from {}: {:?}", origin_public_key, resp);
                         Err(resp)
                     });
 
                     if result.is_err() {
                         let _ = self.event_publisher
-                                .send(OutputManagerEvent::Error(
-                                    "Error handling Base Node Response message".to_string(),
-                                ))
+                                .send(OutputManagerEvent::BaseNodeResponseFailed {
+                                    request_key,
+                                    base_node_public_key: origin_public_key,
+                                })
                                 .await;
                     }
                 }
+                _ = chain_tip_poll_ticker.select_next_some() => {
+                    let _activity = self.monitor.enter("chain tip poll");
+                    if self.base_node_public_key.is_some() {
+                        let _ = self.query_chain_tip().await.or_else(|resp| {
+                            error!(target: LOG_TARGET, "Error querying the chain tip height: {:?}", resp);
+                            Err(resp)
+                        });
+                    }
+                }
                 utxo_hash = utxo_query_timeout_futures.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Base Node Sync Timeout");
+                    let _activity = self.monitor.enter("UTXO query timeout");
+                    acquire_lock!(self.balance_cache).invalidate();
                     let _ = self.handle_utxo_query_timeout(utxo_hash, &mut  utxo_query_timeout_futures).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling UTXO query timeout : {:?}", resp);
                         Err(resp)
@@ -214,6 +424,84 @@ where
         Ok(())
     }
 
+    // Returns the query that answers a read-only request from the database alone, so that it can run concurrently
+    // with the requests handled by the service. Requests that change the state of outputs, or that depend on state
+    // held by the service other than the chain tip height, are not answered concurrently.
+    fn concurrent_query(
+        &self,
+        request: &OutputManagerRequest,
+    ) -> Option<BoxFuture<'static, Result<OutputManagerResponse, OutputManagerError>>>
+    {
+        let db = self.db.clone();
+        let query = match request {
+            OutputManagerRequest::GetBalance => {
+                let balance_cache = self.balance_cache.clone();
+                let chain_tip_height = self.chain_tip_height;
+                async move {
+                    cached_balance(&db, &balance_cache, chain_tip_height)
+                        .await
+                        .map(OutputManagerResponse::Balance)
+                }
+                .boxed()
+            },
+            OutputManagerRequest::GetBalanceAtHeight(height) => {
+                let height = *height;
+                async move { Ok(OutputManagerResponse::Balance(db.get_balance(Some(height)).await?)) }.boxed()
+            },
+            OutputManagerRequest::GetPendingTransactions => async move {
+                Ok(OutputManagerResponse::PendingTransactions(
+                    db.fetch_all_pending_transaction_outputs().await?,
+                ))
+            }
+            .boxed(),
+            OutputManagerRequest::GetPendingTransaction(tx_id) => {
+                let tx_id = *tx_id;
+                async move {
+                    Ok(OutputManagerResponse::PendingTransaction(
+                        db.fetch_pending_transaction_outputs(tx_id).await?,
+                    ))
+                }
+                .boxed()
+            },
+            OutputManagerRequest::GetSpentOutputs => {
+                async move { Ok(OutputManagerResponse::SpentOutputs(db.fetch_spent_outputs().await?)) }.boxed()
+            },
+            OutputManagerRequest::GetUnspentOutputs => async move {
+                Ok(OutputManagerResponse::UnspentOutputs(
+                    db.fetch_sorted_unspent_outputs().await?,
+                ))
+            }
+            .boxed(),
+            OutputManagerRequest::GetDustOutputs => {
+                let dust_threshold = self.config.dust_threshold;
+                async move {
+                    let mut uo = db.fetch_sorted_unspent_outputs().await?;
+                    uo.retain(|o| o.value < dust_threshold);
+                    Ok(OutputManagerResponse::DustOutputs(uo))
+                }
+                .boxed()
+            },
+            OutputManagerRequest::GetInvalidOutputs => {
+                async move { Ok(OutputManagerResponse::InvalidOutputs(db.get_invalid_outputs().await?)) }.boxed()
+            },
+            OutputManagerRequest::GetOutputMetadata => {
+                async move { Ok(OutputManagerResponse::OutputMetadata(db.fetch_output_metadata().await?)) }.boxed()
+            },
+            OutputManagerRequest::GetSyncHistory(limit) => {
+                let limit = *limit;
+                async move { Ok(OutputManagerResponse::SyncHistory(db.fetch_sync_history(limit).await?)) }.boxed()
+            },
+            OutputManagerRequest::GetAbandonedTransactions => async move {
+                Ok(OutputManagerResponse::AbandonedTransactions(
+                    db.fetch_abandoned_transactions().await?,
+                ))
+            }
+            .boxed(),
+            _ => return None,
+        };
+        Some(query)
+    }
+
     /// This handler is called when the Service executor loops receives an API request
     async fn handle_request(
         &mut self,
@@ -222,19 +510,61 @@ where
     ) -> Result<OutputManagerResponse, OutputManagerError>
     {
         trace!(target: LOG_TARGET, "Handling Service Request: {}", request);
+        if self.config.read_only && requires_master_seed(&request) {
+            return Err(OutputManagerError::ReadOnlyWallet);
+        }
+        if !is_read_only_request(&request) {
+            acquire_lock!(self.balance_cache).invalidate();
+        }
         match request {
             OutputManagerRequest::AddOutput(uo) => {
                 self.add_output(uo).await.map(|_| OutputManagerResponse::OutputAdded)
             },
+            OutputManagerRequest::AddOutputs(uos) => {
+                self.add_outputs(uos).await.map(|_| OutputManagerResponse::OutputsAdded)
+            },
             OutputManagerRequest::GetBalance => self.get_balance().await.map(OutputManagerResponse::Balance),
+            OutputManagerRequest::GetBalanceAtHeight(height) => self
+                .get_balance_at_height(height)
+                .await
+                .map(OutputManagerResponse::Balance),
             OutputManagerRequest::GetRecipientKey((tx_id, amount)) => self
                 .get_recipient_spending_key(tx_id, amount)
                 .await
                 .map(OutputManagerResponse::RecipientKeyGenerated),
-            OutputManagerRequest::PrepareToSendTransaction((amount, fee_per_gram, lock_height, message)) => self
-                .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message)
+            OutputManagerRequest::PrepareToSendTransaction((amount, fee_per_gram, lock_height, message, options)) => {
+                self.prepare_transaction_to_send_with_options(amount, fee_per_gram, lock_height, message, options)
+                    .await
+                    .map(OutputManagerResponse::TransactionToSend)
+            },
+            OutputManagerRequest::PrepareToSendTransactionWithInputs((
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+                inputs,
+            )) => self
+                .prepare_transaction_to_send_with_inputs(amount, fee_per_gram, lock_height, message, inputs)
+                .await
+                .map(OutputManagerResponse::TransactionToSend),
+            OutputManagerRequest::PrepareToSendTransactionToMany((
+                amounts,
+                fee_per_gram,
+                lock_height,
+                message,
+                options,
+            )) => self
+                .prepare_transaction_to_send_to_many(amounts, fee_per_gram, lock_height, message, options)
+                .await
+                .map(OutputManagerResponse::TransactionToSend),
+            OutputManagerRequest::PrepareToSendAll((fee_per_gram, message)) => self
+                .prepare_transaction_to_send_all(fee_per_gram, message)
                 .await
                 .map(OutputManagerResponse::TransactionToSend),
+            OutputManagerRequest::EstimateFee((amount, fee_per_gram, num_outputs)) => self
+                .estimate_fee(amount, fee_per_gram, num_outputs)
+                .await
+                .map(OutputManagerResponse::FeeEstimate),
             OutputManagerRequest::ConfirmPendingTransaction(tx_id) => self
                 .confirm_encumberance(tx_id)
                 .await
@@ -247,6 +577,25 @@ where
                 .cancel_transaction(tx_id)
                 .await
                 .map(|_| OutputManagerResponse::TransactionCancelled),
+            OutputManagerRequest::ReinstateTransaction(pending) => self
+                .reinstate_transaction(pending)
+                .await
+                .map(|_| OutputManagerResponse::TransactionReinstated),
+            OutputManagerRequest::AbandonTransaction((tx_id, reason)) => self
+                .abandon_transaction(tx_id, reason)
+                .await
+                .map(|_| OutputManagerResponse::TransactionAbandoned),
+            OutputManagerRequest::GetAbandonedTransactions => Ok(OutputManagerResponse::AbandonedTransactions(
+                self.db.fetch_abandoned_transactions().await?,
+            )),
+            OutputManagerRequest::ReserveOutputs { amount, fee_per_gram } => self
+                .reserve_outputs(amount, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::OutputsReserved),
+            OutputManagerRequest::ReleaseReservation(reservation_id) => self
+                .release_reservation(reservation_id)
+                .await
+                .map(|_| OutputManagerResponse::ReservationReleased),
             OutputManagerRequest::TimeoutTransactions(period) => self
                 .timeout_pending_transactions(period)
                 .await
@@ -255,6 +604,17 @@ where
                 .fetch_pending_transaction_outputs()
                 .await
                 .map(OutputManagerResponse::PendingTransactions),
+            OutputManagerRequest::GetPendingTransaction(tx_id) => Ok(OutputManagerResponse::PendingTransaction(
+                self.db.fetch_pending_transaction_outputs(tx_id).await?,
+            )),
+            OutputManagerRequest::SetPendingTransactionDestination((tx_id, destination)) => {
+                self.db.set_pending_transaction_destination(tx_id, destination).await?;
+                Ok(OutputManagerResponse::PendingTransactionDestinationSet)
+            },
+            OutputManagerRequest::GetPendingCoinbases => self
+                .get_pending_coinbases()
+                .await
+                .map(OutputManagerResponse::PendingCoinbases),
             OutputManagerRequest::GetSpentOutputs => self
                 .fetch_spent_outputs()
                 .await
@@ -263,27 +623,138 @@ where
                 .fetch_unspent_outputs()
                 .await
                 .map(OutputManagerResponse::UnspentOutputs),
-            OutputManagerRequest::GetSeedWords => self.get_seed_words().map(OutputManagerResponse::SeedWords),
+            OutputManagerRequest::GetDustOutputs => {
+                self.fetch_dust_outputs().await.map(OutputManagerResponse::DustOutputs)
+            },
+            OutputManagerRequest::GetSeedWords(language) => self
+                .get_seed_words(language)
+                .await
+                .map(OutputManagerResponse::SeedWords),
+            OutputManagerRequest::GetKeyManagerState => self
+                .get_key_manager_state()
+                .await
+                .map(OutputManagerResponse::KeyManagerState),
             OutputManagerRequest::GetCoinbaseKey((tx_id, amount, maturity_height)) => self
                 .get_coinbase_spending_key(tx_id, amount, maturity_height)
                 .await
                 .map(OutputManagerResponse::RecipientKeyGenerated),
+            OutputManagerRequest::GetNextKey(branch) => {
+                self.get_next_key(branch).await.map(OutputManagerResponse::NextKey)
+            },
             OutputManagerRequest::SetBaseNodePublicKey(pk) => self
                 .set_base_node_public_key(pk, utxo_query_timeout_futures)
                 .await
                 .map(|_| OutputManagerResponse::BaseNodePublicKeySet),
-            OutputManagerRequest::SyncWithBaseNode => self
-                .query_unspent_outputs_status(utxo_query_timeout_futures)
+            OutputManagerRequest::SyncWithBaseNode => {
+                self.reset_base_node_sync_status();
+                self.query_unspent_outputs_status(utxo_query_timeout_futures)
+                    .await
+                    .map(OutputManagerResponse::StartedBaseNodeSync)
+            },
+            OutputManagerRequest::CancelBaseNodeSync => self
+                .cancel_base_node_sync(utxo_query_timeout_futures)
+                .await
+                .map(OutputManagerResponse::BaseNodeSyncCancelled),
+            OutputManagerRequest::GetBaseNodeSyncStatus => Ok(OutputManagerResponse::BaseNodeSyncStatus(
+                self.base_node_sync_status.clone(),
+            )),
+            OutputManagerRequest::RevalidateOutputs => self
+                .revalidate_invalid_outputs()
                 .await
-                .map(OutputManagerResponse::StartedBaseNodeSync),
+                .map(OutputManagerResponse::OutputRevalidationStarted),
             OutputManagerRequest::GetInvalidOutputs => self
                 .fetch_invalid_outputs()
                 .await
                 .map(OutputManagerResponse::InvalidOutputs),
+            OutputManagerRequest::GetOutputStatistics => self
+                .get_output_statistics()
+                .await
+                .map(OutputManagerResponse::OutputStatistics),
             OutputManagerRequest::CreateCoinSplit((amount_per_split, split_count, fee_per_gram, lock_height)) => self
                 .create_coin_split(amount_per_split, split_count, fee_per_gram, lock_height)
                 .await
                 .map(OutputManagerResponse::Transaction),
+            OutputManagerRequest::CreateCoinJoin((max_inputs, fee_per_gram)) => self
+                .create_coin_join(max_inputs, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::Transaction),
+            OutputManagerRequest::CreateBurnTransaction((amount, fee_per_gram, lock_height)) => self
+                .create_burn_transaction(amount, fee_per_gram, lock_height)
+                .await
+                .map(OutputManagerResponse::Transaction),
+            OutputManagerRequest::SetOutputLabel((commitment, label)) => self
+                .set_output_label(commitment, label)
+                .await
+                .map(|_| OutputManagerResponse::OutputLabelSet),
+            OutputManagerRequest::FetchOutputsByLabel(label) => self
+                .fetch_outputs_by_label(label)
+                .await
+                .map(OutputManagerResponse::LabelledOutputs),
+            OutputManagerRequest::GetOutputMetadata => Ok(OutputManagerResponse::OutputMetadata(
+                self.db.fetch_output_metadata().await?,
+            )),
+            OutputManagerRequest::GetSyncHistory(limit) => Ok(OutputManagerResponse::SyncHistory(
+                self.db.fetch_sync_history(limit).await?,
+            )),
+            OutputManagerRequest::SetOutputSource((tx_id, tag)) => self
+                .set_output_source(tx_id, tag)
+                .await
+                .map(|_| OutputManagerResponse::OutputSourceSet),
+            OutputManagerRequest::ScanForOutputsFromSeed((seed_words, language, gap_limit, replace_key_manager)) => {
+                self.scan_for_outputs_from_seed(
+                    seed_words,
+                    language,
+                    gap_limit,
+                    replace_key_manager,
+                    utxo_query_timeout_futures,
+                )
+                .await
+                .map(OutputManagerResponse::RecoveryScanStarted)
+            },
+            OutputManagerRequest::ScanOutputsForHeightRange((start_height, end_height)) => self
+                .scan_outputs_for_height_range(start_height, end_height, utxo_query_timeout_futures)
+                .await
+                .map(OutputManagerResponse::HeightRangeScanStarted),
+            OutputManagerRequest::ApplyEncryption(passphrase) => self
+                .db
+                .apply_encryption(passphrase)
+                .await
+                .map(|_| OutputManagerResponse::EncryptionApplied)
+                .map_err(OutputManagerError::OutputManagerStorageError),
+            OutputManagerRequest::RemoveEncryption => self
+                .db
+                .remove_encryption()
+                .await
+                .map(|_| OutputManagerResponse::EncryptionRemoved)
+                .map_err(OutputManagerError::OutputManagerStorageError),
+            OutputManagerRequest::ExportOutputs(passphrase) => self
+                .export_outputs(passphrase)
+                .await
+                .map(OutputManagerResponse::OutputsExported),
+            OutputManagerRequest::ImportOutputs((export, passphrase)) => self
+                .import_outputs(export, passphrase, utxo_query_timeout_futures)
+                .await
+                .map(OutputManagerResponse::OutputsImported),
+            OutputManagerRequest::SetBaseNodeQueryTimeout(timeout) => {
+                self.base_node_query_timeout = timeout;
+                self.adaptive_query_timeout = false;
+                Ok(OutputManagerResponse::BaseNodeQueryTimeoutSet)
+            },
+            OutputManagerRequest::GetBaseNodeQueryTimeout => Ok(OutputManagerResponse::BaseNodeQueryTimeout(
+                self.base_node_query_timeout,
+            )),
+            OutputManagerRequest::ReportPeerLatency((node_id, latency)) => {
+                let is_base_node = self
+                    .base_node_public_key
+                    .as_ref()
+                    .and_then(|pk| NodeId::from_key(pk).ok())
+                    .map(|base_node_id| base_node_id == node_id)
+                    .unwrap_or(false);
+                if is_base_node {
+                    self.record_base_node_latency(latency);
+                }
+                Ok(OutputManagerResponse::PeerLatencyReported)
+            },
         }
     }
 
@@ -291,19 +762,68 @@ where
     pub async fn handle_base_node_response(
         &mut self,
         response: BaseNodeProto::BaseNodeServiceResponse,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
     ) -> Result<(), OutputManagerError>
     {
         let request_key = response.request_key;
 
         let response: Vec<tari_core::transactions::proto::types::TransactionOutput> = match response.response {
             Some(BaseNodeResponseProto::TransactionOutputs(outputs)) => outputs.outputs,
+            Some(BaseNodeResponseProto::HistoricalBlocks(blocks)) => {
+                if self.height_range_scan.as_ref().map(|scan| scan.request_key) != Some(request_key) {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Ignoring Historical Blocks Response with unexpected request key ({}), it was not meant for \
+                         this service.",
+                        request_key
+                    );
+                    return Ok(());
+                }
+                return self
+                    .handle_height_range_scan_response(blocks.blocks, utxo_query_timeout_futures)
+                    .await;
+            },
+            Some(BaseNodeResponseProto::ChainMetadata(metadata)) => {
+                if self.pending_chain_tip_query_keys.remove(&request_key).is_none() {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Ignoring Chain Metadata Response with unexpected request key ({}), it was not meant for this \
+                         service.",
+                        request_key
+                    );
+                    return Ok(());
+                }
+                let new_tip = (metadata.best_block.is_some() && metadata.best_block != self.chain_tip_block) ||
+                    (metadata.height_of_longest_chain.is_some() &&
+                        metadata.height_of_longest_chain != self.chain_tip_height);
+                if new_tip {
+                    self.validation_due = true;
+                }
+                if metadata.best_block.is_some() {
+                    self.chain_tip_block = metadata.best_block;
+                }
+                if let Some(height) = metadata.height_of_longest_chain {
+                    self.update_chain_tip_height(height).await?;
+                }
+                return self.validate_outputs_if_due(utxo_query_timeout_futures).await;
+            },
             _ => {
                 return Ok(());
             },
         };
 
+        if self.pending_revalidation_query_keys.remove(&request_key) {
+            return self.handle_revalidation_response(request_key, response).await;
+        }
+
+        if self.recovery_scan.as_ref().map(|scan| scan.request_key) == Some(request_key) {
+            return self
+                .handle_recovery_scan_response(response, utxo_query_timeout_futures)
+                .await;
+        }
+
         // Only process requests with a request_key that we are expecting.
-        let queried_hashes: Vec<Vec<u8>> = match self.pending_utxo_query_keys.remove(&request_key) {
+        let query = match self.pending_utxo_query_keys.remove(&request_key) {
             None => {
                 trace!(
                     target: LOG_TARGET,
@@ -312,43 +832,165 @@ where
                 );
                 return Ok(());
             },
-            Some(qh) => qh,
+            Some(q) => q,
         };
 
         trace!(
             target: LOG_TARGET,
             "Handling a Base Node Response meant for this service"
         );
+        self.record_base_node_latency(query.sent_at.elapsed());
+
+        let responses = match self.utxo_query_responses.get_mut(&query.generation) {
+            None => {
+                trace!(
+                    target: LOG_TARGET,
+                    "Ignoring Base Node Response ({}) for an abandoned query",
+                    request_key
+                );
+                return Ok(());
+            },
+            Some(r) => r,
+        };
+        responses.pending_request_keys.remove(&request_key);
+        for output in response.iter() {
+            let response_hash = TransactionOutput::try_from(output.clone())
+                .map_err(OutputManagerError::ConversionError)?
+                .hash();
+            if query.output_hashes.contains(&response_hash) {
+                responses.returned_hashes.insert(response_hash);
+            }
+        }
+        responses.num_validated += query.output_hashes.len();
+        let _ = self
+            .event_publisher
+            .send(OutputManagerEvent::SyncProgress {
+                validated: responses.num_validated,
+                total: responses.output_hashes.len(),
+            })
+            .await
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        // Outputs are only invalidated once every request of the query has been answered
+        if !responses.pending_request_keys.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Waiting for {} more response(s) to Query {}",
+                responses.pending_request_keys.len(),
+                responses.query_key
+            );
+            return Ok(());
+        }
+        let responses = match self.utxo_query_responses.remove(&query.generation) {
+            None => return Ok(()),
+            Some(r) => r,
+        };
+        let request_key = responses.query_key;
+        self.reset_base_node_sync_status();
 
-        // Construct a HashMap of all the unspent outputs
+        // Construct a HashMap of all the unspent outputs that were queried, excluding outputs that were included in a
+        // newer query. Their state can only be changed by the response to that query.
         let unspent_outputs: Vec<UnblindedOutput> = self.db.get_unspent_outputs().await?;
 
         let mut output_hashes = HashMap::new();
+        let mut num_stale = 0;
         for uo in unspent_outputs.iter() {
             let hash = uo.as_transaction_output(&self.factories)?.hash();
-            if queried_hashes.iter().any(|h| &hash == h) {
-                output_hashes.insert(hash.clone(), uo.clone());
+            if responses.output_hashes.contains(&hash) {
+                if self.output_query_generations.get(&hash) == Some(&query.generation) {
+                    output_hashes.insert(hash.clone(), uo.clone());
+                } else {
+                    num_stale += 1;
+                }
             }
         }
+        if num_stale > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Ignoring the result of Query {} for {} output(s) that were included in a newer query",
+                request_key,
+                num_stale
+            );
+        }
 
         // Go through all the returned UTXOs and if they are in the hashmap remove them
-        for output in response.iter() {
-            let response_hash = TransactionOutput::try_from(output.clone())
-                .map_err(OutputManagerError::ConversionError)?
-                .hash();
-
-            let _ = output_hashes.remove(&response_hash);
+        for response_hash in responses.returned_hashes.iter() {
+            if let Some(uo) = output_hashes.remove(response_hash) {
+                self.unconfirmed_import_keys.remove(&uo.spending_key.to_vec());
+            }
         }
 
         // If there are any remaining Unspent Outputs we will move them to the invalid collection
-        for (_k, v) in output_hashes {
+        let num_invalidated = output_hashes.len();
+        let mut history = responses.history;
+        let output_metadata = if num_invalidated > 0 {
+            self.db.fetch_output_metadata().await?
+        } else {
+            Vec::new()
+        };
+        for (hash, v) in output_hashes {
             warn!(
                 target: LOG_TARGET,
                 "Output with value {} not returned from Base Node query and is thus being invalidated", v.value
             );
-            self.db.invalidate_output(v).await?;
+            history.invalidated_hashes.push(hash);
+            let commitment = self.factories.commitment.commit_value(&v.spending_key, v.value.into());
+            let value = v.value;
+            let metadata = output_metadata.iter().find(|m| m.spending_key == v.spending_key);
+            let tx_id = metadata.and_then(|m| m.source_tx_id);
+            let base_node_public_key = responses.base_node_public_key.to_hex();
+            let chain_tip_height = self.chain_tip_height;
+            let reason = if self.unconfirmed_import_keys.remove(&v.spending_key.to_vec()) {
+                InvalidationReason::ImportFailed {
+                    request_key,
+                    base_node_public_key,
+                    chain_tip_height,
+                }
+            } else if let Some(mined_height) = metadata.and_then(|m| m.discovered_height) {
+                InvalidationReason::RemovedByReorg {
+                    request_key,
+                    base_node_public_key,
+                    mined_height,
+                    chain_tip_height,
+                }
+            } else {
+                InvalidationReason::NotReturnedByBaseNode {
+                    request_key,
+                    base_node_public_key,
+                    chain_tip_height,
+                }
+            };
+            self.db.invalidate_output(v, reason).await?;
+            let _ = self
+                .event_publisher
+                .send(OutputManagerEvent::TxoInvalidated {
+                    commitment,
+                    value,
+                    tx_id,
+                })
+                .await
+                .map_err(|e| {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Error sending event, usually because there are no subscribers: {:?}",
+                        e
+                    );
+                    e
+                });
         }
 
+        history.response_time = Some(responses.sent_at.elapsed());
+        history.outcome = SyncOutcome::Completed;
+        self.db
+            .record_sync_history_entry(history, self.config.max_sync_history_entries)
+            .await?;
+
         debug!(
             target: LOG_TARGET,
             "Handled Base Node response for Query {}", request_key
@@ -356,7 +998,10 @@ where
 
         let _ = self
             .event_publisher
-            .send(OutputManagerEvent::ReceiveBaseNodeResponse(request_key))
+            .send(OutputManagerEvent::SyncCompleted {
+                request_key,
+                num_invalidated,
+            })
             .await
             .map_err(|e| {
                 trace!(
@@ -377,87 +1022,781 @@ where
         utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
     ) -> Result<(), OutputManagerError>
     {
-        if self.pending_utxo_query_keys.remove(&query_key).is_some() {
-            error!(target: LOG_TARGET, "UTXO Query {} timed out", query_key);
+        if self.recovery_scan.as_ref().map(|scan| scan.request_key) == Some(query_key) {
+            if let Some(scan) = self.recovery_scan.take() {
+                error!(
+                    target: LOG_TARGET,
+                    "Recovery scan query {} timed out, resending", query_key
+                );
+                self.send_recovery_scan_query(scan, utxo_query_timeout_futures).await?;
+            }
+            return Ok(());
+        }
+        if self.height_range_scan.as_ref().map(|scan| scan.request_key) == Some(query_key) {
+            if let Some(scan) = self.height_range_scan.take() {
+                error!(
+                    target: LOG_TARGET,
+                    "Height range scan query {} timed out, resending", query_key
+                );
+                self.send_height_range_scan_query(scan, utxo_query_timeout_futures)
+                    .await?;
+            }
+            return Ok(());
+        }
+        if self.utxo_query_retry_key == Some(query_key) {
+            self.utxo_query_retry_key = None;
+            self.base_node_sync_status.next_retry_delay = None;
+            debug!(
+                target: LOG_TARGET,
+                "Retrying UTXO Query (retry {})", self.base_node_sync_status.retry_count
+            );
             self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
-            // TODO Remove this once this bug is fixed
-            trace!(target: LOG_TARGET, "Finished queueing new Base Node query timeout");
-            let _ = self
-                .event_publisher
-                .send(OutputManagerEvent::BaseNodeSyncRequestTimedOut(query_key))
-                .await
-                .map_err(|e| {
-                    trace!(
+            return Ok(());
+        }
+        if let Some(query) = self.pending_utxo_query_keys.remove(&query_key) {
+            error!(target: LOG_TARGET, "UTXO Query {} timed out", query_key);
+            // The other requests of the query are abandoned, the retry covers all of the outputs again
+            if let Some(responses) = self.utxo_query_responses.remove(&query.generation) {
+                for key in responses.pending_request_keys {
+                    self.pending_utxo_query_keys.remove(&key);
+                }
+                let mut history = responses.history;
+                history.outcome = SyncOutcome::TimedOut;
+                self.db
+                    .record_sync_history_entry(history, self.config.max_sync_history_entries)
+                    .await?;
+            }
+
+            let event = if self.base_node_sync_status.retry_count >= self.config.base_node_query_max_retries {
+                error!(
+                    target: LOG_TARGET,
+                    "UTXO Query {} failed after {} retries, abandoning the base node sync",
+                    query_key,
+                    self.base_node_sync_status.retry_count
+                );
+                self.base_node_sync_status.failed = true;
+                OutputManagerEvent::BaseNodeSyncFailed(query_key)
+            } else {
+                let delay = retry_backoff_delay(
+                    self.config.base_node_query_retry_backoff,
+                    self.base_node_sync_status.retry_count,
+                );
+                self.base_node_sync_status.retry_count += 1;
+                if delay == Duration::from_secs(0) {
+                    self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+                } else {
+                    debug!(
                         target: LOG_TARGET,
-                        "Error sending event, usually because there are no subscribers: {:?}",
-                        e
+                        "Retrying UTXO Query {} in {} seconds",
+                        query_key,
+                        delay.as_secs()
                     );
+                    let retry_key = OsRng.next_u64();
+                    self.utxo_query_retry_key = Some(retry_key);
+                    self.base_node_sync_status.next_retry_delay = Some(delay);
+                    let retry_delay = StateDelay::new(delay, retry_key);
+                    utxo_query_timeout_futures.push(retry_delay.delay().boxed());
+                }
+                OutputManagerEvent::BaseNodeSyncRequestTimedOut(query_key)
+            };
+
+            let _ = self.event_publisher.send(event).await.map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
                     e
-                });
+                );
+                e
+            });
         }
         Ok(())
     }
 
-    /// Send queries to the base node to check the status of all unspent outputs. If the outputs are no longer
-    /// available their status will be updated in the wallet.
-    pub async fn query_unspent_outputs_status(
+    /// Cancel the pending UTXO queries and a scheduled retry. Their timeouts are dropped by replacing the timeout
+    /// futures, and the timeout of a recovery scan that is in progress is started again.
+    async fn cancel_base_node_sync(
         &mut self,
         utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
-    ) -> Result<u64, OutputManagerError>
+    ) -> Result<Vec<u64>, OutputManagerError>
     {
-        match self.base_node_public_key.as_ref() {
-            None => Err(OutputManagerError::NoBaseNodeKeysProvided),
-            Some(pk) => {
-                let unspent_outputs: Vec<UnblindedOutput> = self.db.get_unspent_outputs().await?;
-                let mut output_hashes = Vec::new();
-                for uo in unspent_outputs.iter() {
-                    let hash = uo.as_transaction_output(&self.factories)?.hash();
-                    output_hashes.push(hash.clone());
-                }
-
-                let request_key = OsRng.next_u64();
+        let mut request_keys = self
+            .pending_utxo_query_keys
+            .drain()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        request_keys.sort();
+        let cancelled = self
+            .utxo_query_responses
+            .drain()
+            .map(|(_, responses)| responses.history)
+            .collect::<Vec<_>>();
+        for mut history in cancelled {
+            history.outcome = SyncOutcome::Cancelled;
+            self.db
+                .record_sync_history_entry(history, self.config.max_sync_history_entries)
+                .await?;
+        }
+        self.reset_base_node_sync_status();
 
-                let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs {
-                    outputs: output_hashes.clone(),
-                });
-                let service_request = BaseNodeProto::BaseNodeServiceRequest {
-                    request_key,
-                    request: Some(request),
-                };
-                // TODO Remove this once this bug is fixed
-                trace!(target: LOG_TARGET, "About to attempt to send query to base node");
-                self.outbound_message_service
-                    .send_direct(
-                        pk.clone(),
-                        OutboundEncryption::None,
-                        OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
-                    )
-                    .await?;
-                // TODO Remove this once this bug is fixed
-                trace!(target: LOG_TARGET, "Query sent to Base Node");
-                self.pending_utxo_query_keys.insert(request_key, output_hashes);
-                let state_timeout = StateDelay::new(self.config.base_node_query_timeout, request_key);
-                utxo_query_timeout_futures.push(state_timeout.delay().boxed());
-                debug!(
-                    target: LOG_TARGET,
-                    "Output Manager Sync query ({}) sent to Base Node", request_key
-                );
-                Ok(request_key)
-            },
+        *utxo_query_timeout_futures = FuturesUnordered::new();
+        if let Some(scan) = self.recovery_scan.as_ref() {
+            let state_timeout = StateDelay::new(self.base_node_query_timeout, scan.request_key);
+            utxo_query_timeout_futures.push(state_timeout.delay().boxed());
         }
+        debug!(
+            target: LOG_TARGET,
+            "Cancelled {} pending UTXO queries",
+            request_keys.len()
+        );
+
+        let _ = self
+            .event_publisher
+            .send(OutputManagerEvent::BaseNodeSyncCancelled(request_keys.clone()))
+            .await
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        Ok(request_keys)
+    }
+
+    // Start the retries of the UTXO query over. A scheduled retry is dropped, the caller is about to send a new query.
+    fn reset_base_node_sync_status(&mut self) {
+        self.base_node_sync_status = BaseNodeSyncStatus::default();
+        self.utxo_query_retry_key = None;
+    }
+
+    /// Send queries to the base node to check the status of all unspent outputs. If the outputs are no longer
+    /// available their status will be updated in the wallet.
+    pub async fn query_unspent_outputs_status(
+        &mut self,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
+    {
+        match self.base_node_public_key.as_ref() {
+            None => Err(OutputManagerError::NoBaseNodeKeysProvided),
+            Some(pk) => {
+                let pk = pk.clone();
+                let unspent_outputs: Vec<UnblindedOutput> = self.db.get_unspent_outputs().await?;
+                let mut output_hashes = Vec::new();
+                for uo in unspent_outputs.iter() {
+                    let hash = uo.as_transaction_output(&self.factories)?.hash();
+                    output_hashes.push(hash.clone());
+                }
+
+                // Large wallets split the query into several requests, an empty wallet still sends one request
+                let mut chunks = output_hashes
+                    .chunks(max(self.config.base_node_query_chunk_size, 1))
+                    .map(|c| c.to_vec())
+                    .collect::<Vec<_>>();
+                if chunks.is_empty() {
+                    chunks.push(Vec::new());
+                }
+
+                self.utxo_query_generation += 1;
+                let generation = self.utxo_query_generation;
+                let mut responses = UtxoQueryResponses {
+                    query_key: 0,
+                    base_node_public_key: pk.clone(),
+                    output_hashes: output_hashes.iter().cloned().collect(),
+                    pending_request_keys: HashSet::new(),
+                    returned_hashes: HashSet::new(),
+                    num_validated: 0,
+                    sent_at: Instant::now(),
+                    history: SyncHistoryEntry {
+                        request_key: 0,
+                        base_node_public_key: pk.clone(),
+                        output_hashes: output_hashes.clone(),
+                        sent_at: Utc::now().naive_utc(),
+                        response_time: None,
+                        invalidated_hashes: Vec::new(),
+                        outcome: SyncOutcome::Pending,
+                    },
+                };
+                for (i, chunk) in chunks.into_iter().enumerate() {
+                    let request_key = OsRng.next_u64();
+                    if i == 0 {
+                        responses.query_key = request_key;
+                        responses.history.request_key = request_key;
+                    }
+
+                    let request =
+                        BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: chunk.clone() });
+                    let service_request = BaseNodeProto::BaseNodeServiceRequest {
+                        request_key,
+                        request: Some(request),
+                    };
+                    {
+                        let _activity = self.monitor.enter("send UTXO query to base node");
+                        self.outbound_message_service
+                            .send_direct(
+                                pk.clone(),
+                                OutboundEncryption::None,
+                                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+                            )
+                            .await?;
+                    }
+                    self.pending_utxo_query_keys.insert(request_key, PendingUtxoQuery {
+                        generation,
+                        output_hashes: chunk.into_iter().collect(),
+                        sent_at: Instant::now(),
+                    });
+                    responses.pending_request_keys.insert(request_key);
+                    let state_timeout = StateDelay::new(self.base_node_query_timeout, request_key);
+                    utxo_query_timeout_futures.push(state_timeout.delay().boxed());
+                }
+                self.output_query_generations = output_hashes.iter().map(|h| (h.clone(), generation)).collect();
+                self.db
+                    .record_sync_history_entry(responses.history.clone(), self.config.max_sync_history_entries)
+                    .await?;
+                let query_key = responses.query_key;
+                debug!(
+                    target: LOG_TARGET,
+                    "Output Manager Sync query ({}) sent to Base Node in {} request(s)",
+                    query_key,
+                    responses.pending_request_keys.len()
+                );
+                self.utxo_query_responses.insert(generation, responses);
+                Ok(query_key)
+            },
+        }
+    }
+
+    /// Start a scan that regenerates the keys of the wallet with the given seed words, from the first key index, and
+    /// asks the base node for the outputs of each key with each of the configured `recovery_scan_values`. Keys are
+    /// queried in batches, and the scan of a key manager branch stops once `gap_limit` consecutive keys on it have no
+    /// outputs, after which the next branch is scanned. The language of the seed words is detected if no language is
+    /// given. The key manager state of the wallet is only replaced by that of a different seed if
+    /// `replace_key_manager` is set or the wallet is empty when the scan starts. Returns the request key of the first
+    /// query.
+    pub async fn scan_for_outputs_from_seed(
+        &mut self,
+        seed_words: Vec<String>,
+        language: Option<MnemonicLanguage>,
+        gap_limit: usize,
+        replace_key_manager: bool,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
+    {
+        if self.base_node_public_key.is_none() {
+            return Err(OutputManagerError::NoBaseNodeKeysProvided);
+        }
+        if self.recovery_scan.is_some() || self.height_range_scan.is_some() {
+            return Err(OutputManagerError::RecoveryScanInProgress);
+        }
+        if gap_limit == 0 {
+            return Err(OutputManagerError::InvalidGapLimit);
+        }
+        if self.config.recovery_scan_values.is_empty() {
+            return Err(OutputManagerError::NoRecoveryScanValues);
+        }
+        let key_manager = match language {
+            Some(language) => KeyManager::<PrivateKey, KeyDigest>::from_mnemonic_with_language(
+                &seed_words,
+                &language,
+                "".to_string(),
+                0,
+            )?,
+            None => KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "".to_string(), 0)?,
+        };
+        let mut remaining_branches = KeyManagerBranch::all();
+        remaining_branches.reverse();
+        let branch = remaining_branches.pop().unwrap_or(KeyManagerBranch::Spending);
+        let replace_key_manager = replace_key_manager || self.is_empty_wallet().await?;
+        let scan = RecoveryScan {
+            key_manager,
+            branch,
+            remaining_branches,
+            gap_limit,
+            batch_start: 1,
+            batch_end: 1 + gap_limit,
+            last_found_index: 0,
+            found_indices: HashMap::new(),
+            request_key: 0,
+            recovered: 0,
+            replace_key_manager,
+        };
+        self.send_recovery_scan_query(scan, utxo_query_timeout_futures).await
+    }
+
+    // Ask the base node for the outputs of the keys in the current batch of the recovery scan. The scan is only kept
+    // as the pending scan once its query has been sent, so a failed query does not block a new scan.
+    async fn send_recovery_scan_query(
+        &mut self,
+        mut scan: RecoveryScan,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
+    {
+        let pk = self
+            .base_node_public_key
+            .clone()
+            .ok_or_else(|| OutputManagerError::NoBaseNodeKeysProvided)?;
+        let request_key = OsRng.next_u64();
+        scan.request_key = request_key;
+        let output_hashes = scan
+            .candidates(&self.config.recovery_scan_values, &self.factories)?
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+
+        let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: output_hashes });
+        let service_request = BaseNodeProto::BaseNodeServiceRequest {
+            request_key,
+            request: Some(request),
+        };
+        self.outbound_message_service
+            .send_direct(
+                pk,
+                OutboundEncryption::None,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+            )
+            .await?;
+        let state_timeout = StateDelay::new(self.base_node_query_timeout, request_key);
+        utxo_query_timeout_futures.push(state_timeout.delay().boxed());
+        self.recovery_scan = Some(scan);
+        debug!(
+            target: LOG_TARGET,
+            "Recovery scan query ({}) sent to Base Node", request_key
+        );
+        Ok(request_key)
+    }
+
+    // Add the outputs the base node returned for the current batch of the recovery scan, then query the next batch or
+    // finish the scan once `gap_limit` consecutive keys had no outputs
+    async fn handle_recovery_scan_response(
+        &mut self,
+        response: Vec<tari_core::transactions::proto::types::TransactionOutput>,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<(), OutputManagerError>
+    {
+        let mut scan = match self.recovery_scan.take() {
+            Some(scan) => scan,
+            None => return Ok(()),
+        };
+        let candidates: HashMap<Vec<u8>, (usize, UnblindedOutput)> = scan
+            .candidates(&self.config.recovery_scan_values, &self.factories)?
+            .into_iter()
+            .collect();
+
+        for output in response.iter() {
+            let hash = TransactionOutput::try_from(output.clone())
+                .map_err(OutputManagerError::ConversionError)?
+                .hash();
+            if let Some((index, uo)) = candidates.get(&hash) {
+                match self.db.add_unspent_output(uo.clone()).await {
+                    Ok(_) => {
+                        scan.recovered += 1;
+                        info!(
+                            target: LOG_TARGET,
+                            "Recovered output with value {} at key index {} of the {} branch",
+                            uo.value,
+                            index,
+                            scan.branch
+                        );
+                    },
+                    Err(OutputManagerStorageError::DuplicateOutput) => {},
+                    Err(e) => return Err(e.into()),
+                }
+                scan.last_found_index = max(scan.last_found_index, *index);
+            }
+        }
+
+        // Keep scanning until `gap_limit` consecutive keys after the last recovered output have been queried
+        let scan_end = scan.last_found_index + 1 + scan.gap_limit;
+        if scan.batch_end < scan_end {
+            scan.batch_start = scan.batch_end;
+            scan.batch_end = scan_end;
+            self.send_recovery_scan_query(scan, utxo_query_timeout_futures).await?;
+            return Ok(());
+        }
+
+        // Then scan the next branch from its first key index
+        scan.found_indices.insert(scan.branch, scan.last_found_index);
+        if let Some(branch) = scan.remaining_branches.pop() {
+            let master_seed = scan.key_manager.master_key.clone();
+            let branch_seed = match branch {
+                KeyManagerBranch::Spending => scan.key_manager.branch_seed.clone(),
+                _ => branch.to_string(),
+            };
+            scan.key_manager = KeyManager::from(master_seed, branch_seed, 0);
+            scan.branch = branch;
+            scan.batch_start = 1;
+            scan.batch_end = 1 + scan.gap_limit;
+            scan.last_found_index = 0;
+            self.send_recovery_scan_query(scan, utxo_query_timeout_futures).await?;
+            return Ok(());
+        }
+
+        self.finish_recovery_scan(scan).await
+    }
+
+    /// Start a scan that asks the base node for the blocks from `start_height` to `end_height`, inclusive, and adds
+    /// the outputs in them that belong to the wallet. This recovers outputs that were received while the wallet was
+    /// offline without scanning the whole chain. The outputs of the wallet are recognised by their commitments, using
+    /// the keys derived so far on every key manager branch, and the next `HEIGHT_RANGE_SCAN_KEY_LOOKAHEAD` keys, with
+    /// each of the configured `recovery_scan_values`. Blocks are requested in batches, and
+    /// `OutputManagerEvent::HeightRangeScanComplete` is published once the last batch has been scanned. Returns the
+    /// request key of the first query.
+    pub async fn scan_outputs_for_height_range(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
+    {
+        if self.base_node_public_key.is_none() {
+            return Err(OutputManagerError::NoBaseNodeKeysProvided);
+        }
+        if self.recovery_scan.is_some() || self.height_range_scan.is_some() {
+            return Err(OutputManagerError::RecoveryScanInProgress);
+        }
+        if start_height > end_height {
+            return Err(OutputManagerError::InvalidHeightRange);
+        }
+        if self.config.recovery_scan_values.is_empty() {
+            return Err(OutputManagerError::NoRecoveryScanValues);
+        }
+        let state = self
+            .db
+            .get_key_manager_state()
+            .await?
+            .ok_or_else(|| OutputManagerStorageError::KeyManagerNotInitialized)?;
+
+        let mut candidates = HashMap::new();
+        for branch in KeyManagerBranch::all() {
+            let key_manager =
+                KeyManager::<PrivateKey, KeyDigest>::from(state.master_seed.clone(), state.branch_seed(branch), 0);
+            let indices = 1..=state.key_index(branch) + HEIGHT_RANGE_SCAN_KEY_LOOKAHEAD;
+            for (index, key, value) in candidate_keys(&key_manager, indices, &self.config.recovery_scan_values)? {
+                let commitment = self.factories.commitment.commit_value(&key, value.as_u64());
+                candidates.insert(commitment.as_bytes().to_vec(), (branch, index, key, value));
+            }
+        }
+
+        let scan = HeightRangeScan {
+            batch_start: start_height,
+            end_height,
+            candidates,
+            found_indices: HashMap::new(),
+            request_key: 0,
+            recovered: 0,
+        };
+        self.send_height_range_scan_query(scan, utxo_query_timeout_futures)
+            .await
+    }
+
+    // Ask the base node for the next batch of blocks of the height range scan. As with the recovery scan, the scan is
+    // only kept as the pending scan once its query has been sent.
+    async fn send_height_range_scan_query(
+        &mut self,
+        mut scan: HeightRangeScan,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
+    {
+        let pk = self
+            .base_node_public_key
+            .clone()
+            .ok_or_else(|| OutputManagerError::NoBaseNodeKeysProvided)?;
+        let request_key = OsRng.next_u64();
+        scan.request_key = request_key;
+
+        let request = BaseNodeRequestProto::FetchBlocks(BaseNodeProto::BlockHeights {
+            heights: (scan.batch_start..=scan.batch_end()).collect(),
+        });
+        let service_request = BaseNodeProto::BaseNodeServiceRequest {
+            request_key,
+            request: Some(request),
+        };
+        self.outbound_message_service
+            .send_direct(
+                pk,
+                OutboundEncryption::None,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+            )
+            .await?;
+        let state_timeout = StateDelay::new(self.base_node_query_timeout, request_key);
+        utxo_query_timeout_futures.push(state_timeout.delay().boxed());
+        debug!(
+            target: LOG_TARGET,
+            "Height range scan query ({}) for blocks {} to {} sent to Base Node",
+            request_key,
+            scan.batch_start,
+            scan.batch_end()
+        );
+        self.height_range_scan = Some(scan);
+        Ok(request_key)
+    }
+
+    // Add the outputs of the wallet found in the blocks the base node returned, then query the next batch of blocks or
+    // finish the scan
+    async fn handle_height_range_scan_response(
+        &mut self,
+        blocks: Vec<tari_core::proto::core::HistoricalBlock>,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<(), OutputManagerError>
+    {
+        let mut scan = match self.height_range_scan.take() {
+            Some(scan) => scan,
+            None => return Ok(()),
+        };
+
+        for historical_block in blocks {
+            // Outputs of the block that have been spent since are not recovered
+            let spent_commitments = historical_block
+                .spent_commitments
+                .into_iter()
+                .map(|c| c.data)
+                .collect::<HashSet<_>>();
+            let block = match historical_block.block {
+                Some(block) => block,
+                None => continue,
+            };
+            let height = block.header.map(|h| h.height).unwrap_or_default();
+            for output in block.body.map(|b| b.outputs).unwrap_or_default() {
+                let output = TransactionOutput::try_from(output).map_err(OutputManagerError::ConversionError)?;
+                let commitment = output.commitment.as_bytes().to_vec();
+                if spent_commitments.contains(&commitment) {
+                    continue;
+                }
+                let (branch, index, key, value) = match scan.candidates.get(&commitment) {
+                    Some(candidate) => candidate.clone(),
+                    None => continue,
+                };
+                let uo = UnblindedOutput::new(value, key, Some(output.features));
+                match self.db.add_unspent_output(uo).await {
+                    Ok(_) => {
+                        scan.recovered += 1;
+                        info!(
+                            target: LOG_TARGET,
+                            "Found output with value {} at key index {} of the {} branch in block {}",
+                            value,
+                            index,
+                            branch,
+                            height
+                        );
+                    },
+                    Err(OutputManagerStorageError::DuplicateOutput) => {},
+                    Err(e) => return Err(e.into()),
+                }
+                let found_index = scan.found_indices.entry(branch).or_insert(0);
+                *found_index = max(*found_index, index);
+            }
+        }
+
+        if scan.batch_end() < scan.end_height {
+            scan.batch_start = scan.batch_end() + 1;
+            self.send_height_range_scan_query(scan, utxo_query_timeout_futures)
+                .await?;
+            return Ok(());
+        }
+
+        self.finish_height_range_scan(scan).await
+    }
+
+    // Move the key index of each branch past the keys the scan found outputs for, so that those keys are not reused
+    async fn finish_height_range_scan(&mut self, scan: HeightRangeScan) -> Result<(), OutputManagerError> {
+        if let Some(mut state) = self.db.get_key_manager_state().await? {
+            let mut advanced = false;
+            for (branch, found_index) in scan.found_indices.iter() {
+                if *found_index > state.key_index(*branch) {
+                    state.set_key_index(*branch, *found_index);
+                    advanced = true;
+                }
+            }
+            if advanced {
+                self.db.set_key_manager_state(state.clone()).await?;
+                *acquire_lock!(self.key_provider) = Box::new(SeedKeyProvider::from_state(&state));
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Height range scan complete, {} output(s) found", scan.recovered
+        );
+
+        let _ = self
+            .event_publisher
+            .send(OutputManagerEvent::HeightRangeScanComplete(scan.recovered))
+            .await
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        Ok(())
+    }
+
+    // Whether the wallet holds no outputs and has derived no keys, so that restoring a seed into it loses nothing
+    async fn is_empty_wallet(&self) -> Result<bool, OutputManagerError> {
+        if let Some(state) = self.db.get_key_manager_state().await? {
+            if KeyManagerBranch::all().into_iter().any(|b| state.key_index(b) > 0) {
+                return Ok(false);
+            }
+        }
+        Ok(self.db.get_unspent_outputs().await?.is_empty() &&
+            self.db.fetch_spent_outputs().await?.is_empty() &&
+            self.db.fetch_all_pending_transaction_outputs().await?.is_empty())
+    }
+
+    // Continue deriving keys on each branch from the recovered seed after the last recovered key, so that keys are not
+    // reused. The key manager state of a wallet in use is kept if the seed is a different one, unless the scan was
+    // asked to replace it; the recovered outputs carry their own spending keys either way.
+    async fn finish_recovery_scan(&mut self, scan: RecoveryScan) -> Result<(), OutputManagerError> {
+        let master_seed = scan.key_manager.master_key.clone();
+        let current_state = self.db.get_key_manager_state().await?;
+        let same_seed = current_state
+            .as_ref()
+            .map(|s| s.master_seed == master_seed)
+            .unwrap_or(false);
+        if !same_seed && !scan.replace_key_manager {
+            info!(
+                target: LOG_TARGET,
+                "Recovery scan complete, {} output(s) recovered. The scanned seed is not that of the wallet, so the \
+                 key manager state of the wallet is kept",
+                scan.recovered
+            );
+            self.publish_recovery_scan_complete(scan.recovered).await;
+            return Ok(());
+        }
+        let mut state = KeyManagerState {
+            master_seed: master_seed.clone(),
+            branch_seed: "".to_string(),
+            primary_key_index: 0,
+            branch_indices: HashMap::new(),
+            bip39_entropy: current_state
+                .as_ref()
+                .filter(|s| s.master_seed == master_seed)
+                .and_then(|s| s.bip39_entropy.clone()),
+        };
+        for (branch, found_index) in scan.found_indices.iter() {
+            let mut index = *found_index;
+            if let Some(current_state) = current_state.as_ref() {
+                if current_state.master_seed == master_seed {
+                    index = max(index, current_state.key_index(*branch));
+                }
+            }
+            state.set_key_index(*branch, index);
+        }
+        self.db.set_key_manager_state(state.clone()).await?;
+        // The recovered seed replaces the key provider of the wallet, including an external signer
+        *acquire_lock!(self.key_provider) = Box::new(SeedKeyProvider::from_state(&state));
+        info!(
+            target: LOG_TARGET,
+            "Recovery scan complete, {} output(s) recovered", scan.recovered
+        );
+        self.publish_recovery_scan_complete(scan.recovered).await;
+        Ok(())
+    }
+
+    async fn publish_recovery_scan_complete(&mut self, recovered: usize) {
+        let _ = self
+            .event_publisher
+            .send(OutputManagerEvent::RecoveryScanComplete(recovered))
+            .await
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
     }
 
     /// Add an unblinded output to the unspent outputs list
     pub async fn add_output(&mut self, output: UnblindedOutput) -> Result<(), OutputManagerError> {
-        Ok(self.db.add_unspent_output(output).await?)
+        let spending_key = output.spending_key.clone();
+        self.db.add_unspent_output(output).await?;
+        Ok(self.db.update_output_metadata(vec![spending_key], None, None).await?)
+    }
+
+    /// Add a batch of unblinded outputs to the unspent outputs list atomically
+    pub async fn add_outputs(&mut self, outputs: Vec<UnblindedOutput>) -> Result<(), OutputManagerError> {
+        let spending_keys = outputs.iter().map(|o| o.spending_key.clone()).collect();
+        self.db.add_unspent_outputs(outputs).await?;
+        Ok(self.db.update_output_metadata(spending_keys, None, None).await?)
+    }
+
+    /// Export the unspent outputs into a portable JSON document, encrypted with the given passphrase
+    pub async fn export_outputs(&self, passphrase: String) -> Result<String, OutputManagerError> {
+        let unspent_outputs = self.db.get_unspent_outputs().await?;
+        // Deriving the encryption key from the passphrase is deliberately expensive, so it is done on the blocking
+        // thread pool
+        tokio::task::spawn_blocking(move || OutputExport::new(&unspent_outputs, &passphrase)?.to_json())
+            .await
+            .map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))?
+    }
+
+    /// Import the outputs of an export produced by `export_outputs` as unspent outputs. Outputs the wallet already
+    /// knows are skipped. The imported outputs are validated against the base node straight away, or as soon as a base
+    /// node is set. Returns the number of outputs that were imported.
+    pub async fn import_outputs(
+        &mut self,
+        export: String,
+        passphrase: String,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<usize, OutputManagerError>
+    {
+        let outputs = tokio::task::spawn_blocking(move || OutputExport::from_json(&export)?.decrypt(&passphrase))
+            .await
+            .map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))??;
+
+        let mut imported = 0;
+        for output in outputs {
+            let spending_key = output.spending_key.to_vec();
+            match self.add_output(output).await {
+                Ok(()) => {
+                    self.unconfirmed_import_keys.insert(spending_key);
+                    imported += 1;
+                },
+                Err(OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::DuplicateOutput)) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Skipping imported output that is already in the wallet"
+                    );
+                },
+                Err(e) => return Err(e),
+            }
+        }
+        info!(target: LOG_TARGET, "Imported {} outputs", imported);
+
+        if imported > 0 {
+            if self.base_node_public_key.is_some() {
+                self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+            } else {
+                self.validation_due = true;
+            }
+        }
+        Ok(imported)
+    }
+
+    /// Calculate the balance at the chain tip height last reported by the base node
+    /// The cached balance is returned if no output has changed state since it was calculated.
+    pub async fn get_balance(&mut self) -> Result<Balance, OutputManagerError> {
+        cached_balance(&self.db, &self.balance_cache, self.chain_tip_height).await
     }
 
-    pub async fn get_balance(&self) -> Result<Balance, OutputManagerError> {
-        let balance = self.db.get_balance().await?;
-        trace!(target: LOG_TARGET, "Balance: {:?}", balance);
+    /// Calculate the balance with the funds that are not yet spendable at the provided chain tip height broken out
+    pub async fn get_balance_at_height(&self, height: u64) -> Result<Balance, OutputManagerError> {
+        let balance = self.db.get_balance(Some(height)).await?;
+        trace!(target: LOG_TARGET, "Balance at height {}: {:?}", height, balance);
         Ok(balance)
     }
 
+    /// Get the next key on the given key manager branch from the secret key provider and persist the branch's new key
+    /// index
+    pub async fn get_next_key(&mut self, branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError> {
+        let key = acquire_lock!(self.key_provider).next_key(branch)?;
+        self.db.increment_key_index(branch).await?;
+        Ok(key)
+    }
+
     /// Request a spending key to be used to accept a transaction from a sender.
     pub async fn get_recipient_spending_key(
         &mut self,
@@ -465,13 +1804,11 @@ where
         amount: MicroTari,
     ) -> Result<PrivateKey, OutputManagerError>
     {
-        let mut key = PrivateKey::default();
-        {
-            let mut km = acquire_lock!(self.key_manager);
-            key = km.next_key()?.k;
+        // A retried transaction that was abandoned would otherwise be accepted again with a fresh key
+        if self.is_abandoned(tx_id).await? {
+            return Err(OutputManagerError::TransactionAbandoned(tx_id));
         }
-
-        self.db.increment_key_index().await?;
+        let key = self.get_next_key(KeyManagerBranch::Spending).await?;
         self.db
             .accept_incoming_pending_transaction(tx_id, amount, key.clone(), OutputFeatures::default())
             .await?;
@@ -492,14 +1829,7 @@ where
         maturity_height: u64,
     ) -> Result<PrivateKey, OutputManagerError>
     {
-        let mut key = PrivateKey::default();
-
-        {
-            let mut km = acquire_lock!(self.key_manager);
-            key = km.next_key()?.k;
-        }
-
-        self.db.increment_key_index().await?;
+        let key = self.get_next_key(KeyManagerBranch::Coinbase).await?;
         self.db
             .accept_incoming_pending_transaction(
                 tx_id,
@@ -523,11 +1853,14 @@ where
         let pending_transaction = self.db.fetch_pending_transaction_outputs(tx_id.clone()).await?;
 
         // Assumption: We are only allowing a single output per receiver in the current transaction protocols.
-        if pending_transaction.outputs_to_be_received.len() != 1 ||
-            pending_transaction.outputs_to_be_received[0]
-                .as_transaction_input(&self.factories.commitment, OutputFeatures::default())
-                .commitment !=
-                received_output.commitment
+        if pending_transaction.outputs_to_be_received.len() != 1 {
+            return Err(OutputManagerError::IncompleteTransaction);
+        }
+        let expected_output = &pending_transaction.outputs_to_be_received[0];
+        let output_to_check =
+            expected_output.as_transaction_input(&self.factories.commitment, expected_output.features.clone());
+        if output_to_check.commitment != received_output.commitment ||
+            output_to_check.features != received_output.features
         {
             return Err(OutputManagerError::IncompleteTransaction);
         }
@@ -549,64 +1882,355 @@ where
         message: String,
     ) -> Result<SenderTransactionProtocol, OutputManagerError>
     {
+        self.prepare_transaction_to_send_with_options(
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+            TransactionSendOptions::default(),
+        )
+        .await
+    }
+
+    /// Prepare a Sender Transaction Protocol as with `prepare_transaction_to_send`, applying the provided send options
+    /// to the selection of the inputs
+    pub async fn prepare_transaction_to_send_with_options(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        options: TransactionSendOptions,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        if amount < self.config.min_output_value {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
         let (outputs, _) = self
-            .select_utxos(amount, fee_per_gram, 1, UTXOSelectionStrategy::MaturityThenSmallest)
+            .select_utxos(
+                amount,
+                fee_per_gram,
+                1,
+                options
+                    .utxo_selection_strategy
+                    .clone()
+                    .unwrap_or_else(|| self.config.utxo_selection_strategy.clone()),
+                options.source_tag.clone(),
+                options.include_dust,
+            )
             .await?;
-        let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
-
-        let offset = PrivateKey::random(&mut OsRng);
-        let nonce = PrivateKey::random(&mut OsRng);
-
-        let mut builder = SenderTransactionProtocol::builder(1);
-        builder
-            .with_lock_height(lock_height.unwrap_or(0))
-            .with_fee_per_gram(fee_per_gram)
-            .with_offset(offset.clone())
-            .with_private_nonce(nonce.clone())
-            .with_amount(0, amount)
-            .with_message(message);
 
-        for uo in outputs.iter() {
-            builder.with_input(
-                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
-                uo.clone(),
-            );
-        }
+        self.build_transaction_to_send(
+            vec![amount],
+            fee_per_gram,
+            lock_height,
+            message,
+            outputs,
+            options.source_tag,
+        )
+        .await
+    }
 
-        let fee_without_change = Fee::calculate(fee_per_gram, 1, outputs.len(), 1);
-        let mut change_key: Option<PrivateKey> = None;
-        // If the input values > the amount to be sent + fees_without_change then we will need to include a change
-        // output
-        if total > amount + fee_without_change {
-            let mut key = PrivateKey::default();
-            {
-                let mut km = acquire_lock!(self.key_manager);
-                key = km.next_key()?.k;
+    /// Prepare a Sender Transaction Protocol that pays each of the amounts to a different recipient in a single
+    /// transaction. The recipients share the fee and a single change output is produced if required. The amounts are
+    /// paid in the order of the messages returned by `get_multi_recipient_messages` of the protocol.
+    pub async fn prepare_transaction_to_send_to_many(
+        &mut self,
+        amounts: Vec<MicroTari>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        options: TransactionSendOptions,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        if amounts.is_empty() {
+            return Err(OutputManagerError::NoRecipients);
+        }
+        if amounts.iter().any(|a| *a < self.config.min_output_value) {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
+        let total_amount = amounts.iter().sum::<MicroTari>();
+        // Every recipient gets its own kernel, which the selection, costing a single kernel, does not account for
+        let extra_kernels_fee = self
+            .fee_model
+            .calculate(fee_per_gram, amounts.len(), 0, 0)
+            .checked_sub(self.fee_model.calculate(fee_per_gram, 1, 0, 0))
+            .unwrap_or_else(|| MicroTari::from(0));
+        let (outputs, _) = self
+            .select_utxos(
+                total_amount + extra_kernels_fee,
+                fee_per_gram,
+                amounts.len(),
+                options
+                    .utxo_selection_strategy
+                    .clone()
+                    .unwrap_or_else(|| self.config.utxo_selection_strategy.clone()),
+                options.source_tag.clone(),
+                options.include_dust,
+            )
+            .await?;
+
+        self.build_transaction_to_send(amounts, fee_per_gram, lock_height, message, outputs, options.source_tag)
+            .await
+    }
+
+    /// Prepare a Sender Transaction Protocol that spends exactly the unspent outputs with the given commitments. No
+    /// automatic selection takes place, so the pinned outputs must cover the amount and the fee.
+    pub async fn prepare_transaction_to_send_with_inputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        inputs: Vec<Commitment>,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        if amount < self.config.min_output_value {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
+        if inputs.is_empty() {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+
+        let mut unspent_outputs = HashMap::new();
+        for uo in self.db.get_unspent_outputs().await? {
+            let commitment = self
+                .factories
+                .commitment
+                .commit_value(&uo.spending_key, uo.value.into());
+            unspent_outputs.insert(commitment.to_vec(), uo);
+        }
+
+        let mut outputs = Vec::with_capacity(inputs.len());
+        let mut pinned = HashSet::new();
+        for commitment in inputs.iter() {
+            if !pinned.insert(commitment.to_vec()) {
+                return Err(OutputManagerError::DuplicateInput);
             }
-            self.db.increment_key_index().await?;
-            change_key = Some(key.clone());
-            builder.with_change_secret(key);
+            let uo = unspent_outputs
+                .remove(&commitment.to_vec())
+                .ok_or_else(|| OutputManagerError::InputNotFound(commitment.to_hex()))?;
+            outputs.push(uo);
         }
 
-        let stp = builder
-            .build::<HashDigest>(&self.factories)
-            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+        let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+        if total < amount + self.fee_model.calculate(fee_per_gram, 1, outputs.len(), 1) {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
 
-        // If a change output was created add it to the pending_outputs list.
-        let mut change_output = Vec::<UnblindedOutput>::new();
-        if let Some(key) = change_key {
-            change_output.push(UnblindedOutput {
-                value: stp.get_amount_to_self()?,
-                spending_key: key,
-                features: OutputFeatures::default(),
-            });
+        self.build_transaction_to_send(vec![amount], fee_per_gram, lock_height, message, outputs, None)
+            .await
+    }
+
+    /// Prepare a Sender Transaction Protocol that spends every spendable output and sends the total, less the fee, to a
+    /// single recipient. No change output is created. Outputs that are still time-locked at the last known chain tip
+    /// are left out.
+    pub async fn prepare_transaction_to_send_all(
+        &mut self,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        self.check_fee_per_gram(fee_per_gram)?;
+        let mut outputs = self.db.fetch_sorted_unspent_outputs().await?;
+        if let Some(height) = self.chain_tip_height {
+            outputs.retain(|o| o.features.maturity <= height);
+        }
+        if outputs.is_empty() {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+
+        let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+        let fee = max(
+            self.fee_model.calculate(fee_per_gram, 1, outputs.len(), 1),
+            self.min_transaction_fee(),
+        );
+        let amount = total.checked_sub(fee).ok_or(OutputManagerError::NotEnoughFunds)?;
+        if amount < self.config.min_output_value {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+
+        self.build_transaction_to_send(vec![amount], fee_per_gram, None, message, outputs, None)
+            .await
+    }
+
+    /// Estimate the fee of sending the amount to `num_outputs` recipient outputs by running the configured UTXO
+    /// selection without encumbering the selected outputs. The randomised selection strategies may select different
+    /// outputs, and so arrive at a different fee, when the transaction is actually prepared.
+    pub async fn estimate_fee(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        num_outputs: usize,
+    ) -> Result<(MicroTari, Vec<UnblindedOutput>), OutputManagerError>
+    {
+        if amount < self.config.min_output_value {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
+        let (outputs, _) = self
+            .select_utxos(
+                amount,
+                fee_per_gram,
+                num_outputs,
+                self.config.utxo_selection_strategy.clone(),
+                None,
+                false,
+            )
+            .await?;
+
+        // Mirror the transaction builder, which splits the change over as many change outputs as it can pay for and
+        // adds change that would be dust or not cover its own fee to the fee
+        let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+        let mut fee = total - amount;
+        for num_change in (1..=max(1, self.config.num_change_outputs)).rev() {
+            let fee_with_change = self
+                .fee_model
+                .calculate(fee_per_gram, 1, outputs.len(), num_outputs + num_change);
+            if let Some(change) = total.checked_sub(amount + fee_with_change) {
+                let part = MicroTari(change.0 / num_change as u64);
+                if part > MicroTari(0) && part >= self.config.min_output_value {
+                    fee = fee_with_change;
+                    break;
+                }
+            }
+        }
+
+        Ok((fee, outputs))
+    }
+
+    /// Select unspent outputs that cover the amount and fee of a single recipient transaction and short-term encumber
+    /// them under a new reservation id. No Sender Transaction Protocol is built, the caller constructs the transaction
+    /// from the returned outputs itself.
+    pub async fn reserve_outputs(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<(u64, Vec<UnblindedOutput>), OutputManagerError>
+    {
+        if amount < self.config.min_output_value {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
+        let (outputs, _) = self
+            .select_utxos(
+                amount,
+                fee_per_gram,
+                1,
+                self.config.utxo_selection_strategy.clone(),
+                None,
+                false,
+            )
+            .await?;
+
+        let reservation_id = OsRng.next_u64();
+        self.db
+            .encumber_outputs(reservation_id, amount, MicroTari(0), outputs.clone(), Vec::new())
+            .await?;
+        debug!(
+            target: LOG_TARGET,
+            "Reserved {} outputs under reservation {}",
+            outputs.len(),
+            reservation_id
+        );
+
+        Ok((reservation_id, outputs))
+    }
+
+    /// Release a reservation made with `reserve_outputs`, placing the reserved outputs back into the unspent pool
+    pub async fn release_reservation(&mut self, reservation_id: u64) -> Result<(), OutputManagerError> {
+        debug!(target: LOG_TARGET, "Releasing output reservation {}", reservation_id);
+        Ok(self.db.cancel_pending_transaction_outputs(reservation_id).await?)
+    }
+
+    /// Build a Sender Transaction Protocol that pays the amounts to one recipient each from the given outputs and
+    /// encumber them, along with the change output if one is required. A transaction to several recipients has a
+    /// single change output.
+    async fn build_transaction_to_send(
+        &mut self,
+        amounts: Vec<MicroTari>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        outputs: Vec<UnblindedOutput>,
+        source_tag: Option<String>,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+        let amount = amounts.iter().sum::<MicroTari>();
+        let num_recipients = amounts.len();
+
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+
+        let mut builder = SenderTransactionProtocol::builder(num_recipients);
+        builder
+            .with_lock_height(lock_height.unwrap_or(0))
+            .with_fee_per_gram(fee_per_gram)
+            .with_fee_model(self.fee_model.clone())
+            .with_offset(offset.clone())
+            .with_private_nonce(nonce.clone())
+            .with_min_output_value(self.config.min_output_value)
+            .with_min_fee(self.min_transaction_fee())
+            .with_message(message);
+        for (i, recipient_amount) in amounts.into_iter().enumerate() {
+            builder.with_amount(i, recipient_amount);
+        }
+
+        for uo in outputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
+                uo.clone(),
+            );
+        }
+
+        let fee_without_change = self
+            .fee_model
+            .calculate(fee_per_gram, num_recipients, outputs.len(), num_recipients);
+        let num_change_outputs = if num_recipients > 1 {
+            1
+        } else {
+            max(1, self.config.num_change_outputs)
+        };
+        let mut change_keys = Vec::new();
+        // If the input values > the amount to be sent + fees_without_change then we will need to include change
+        // outputs
+        if total > amount + fee_without_change {
+            for _ in 0..num_change_outputs {
+                change_keys.push(self.get_next_key(KeyManagerBranch::Spending).await?);
+            }
+            builder.with_change_secrets(change_keys.clone());
         }
 
+        let stp = self.build_sender_protocol(builder).await?;
+
+        // Add the change outputs that were created to the pending_outputs list. Dust change is added to the fee by the
+        // builder, in which case there are no change outputs, and change that can't pay for every change output is
+        // split over fewer of them.
+        let change_output = stp
+            .get_change_amounts()?
+            .into_iter()
+            .zip(change_keys)
+            .map(|(value, spending_key)| UnblindedOutput {
+                value,
+                spending_key,
+                features: OutputFeatures::default(),
+            })
+            .collect::<Vec<_>>();
+
         // The Transaction Protocol built successfully so we will pull the unspent outputs out of the unspent list and
         // store them until the transaction times out OR is confirmed
+        let tx_id = stp.get_tx_id()?;
         self.db
-            .encumber_outputs(stp.get_tx_id()?, outputs, change_output)
+            .encumber_outputs(tx_id, amount, stp.get_fee_amount()?, outputs, change_output)
             .await?;
+        // Change from a restricted send keeps the source tag so that it stays isolated from other funds
+        if let Some(tag) = source_tag {
+            self.db.set_pending_transaction_output_source(tx_id, tag).await?;
+        }
 
         Ok(stp)
     }
@@ -631,26 +2255,24 @@ where
     {
         let pending_transaction = self.db.fetch_pending_transaction_outputs(tx_id.clone()).await?;
 
-        // Check that outputs to be spent can all be found in the provided transaction inputs
+        // Check that outputs to be spent can all be found in the provided transaction inputs. The inputs are
+        // reconstructed with the stored features of each output so that coinbase and time locked outputs match.
         let mut inputs_confirmed = true;
         for output_to_spend in pending_transaction.outputs_to_be_spent.iter() {
-            let input_to_check = output_to_spend
-                .clone()
-                .as_transaction_input(&self.factories.commitment, OutputFeatures::default());
-            inputs_confirmed =
-                inputs_confirmed && inputs.iter().any(|input| input.commitment == input_to_check.commitment);
+            let input_to_check =
+                output_to_spend.as_transaction_input(&self.factories.commitment, output_to_spend.features.clone());
+            inputs_confirmed = inputs_confirmed && inputs.iter().any(|input| input == &input_to_check);
         }
 
         // Check that outputs to be received can all be found in the provided transaction outputs
         let mut outputs_confirmed = true;
         for output_to_receive in pending_transaction.outputs_to_be_received.iter() {
-            let output_to_check = output_to_receive
-                .clone()
-                .as_transaction_input(&self.factories.commitment, OutputFeatures::default());
+            let output_to_check =
+                output_to_receive.as_transaction_input(&self.factories.commitment, output_to_receive.features.clone());
             outputs_confirmed = outputs_confirmed &&
-                outputs
-                    .iter()
-                    .any(|output| output.commitment == output_to_check.commitment);
+                outputs.iter().any(|output| {
+                    output.commitment == output_to_check.commitment && output.features == output_to_check.features
+                });
         }
 
         if !inputs_confirmed || !outputs_confirmed {
@@ -661,102 +2283,668 @@ where
             .confirm_pending_transaction_outputs(pending_transaction.tx_id)
             .await?;
 
+        // Record where the received outputs came from and the chain height at which they were found
+        let spending_keys = pending_transaction
+            .outputs_to_be_received
+            .iter()
+            .map(|o| o.spending_key.clone())
+            .collect();
+        self.db
+            .update_output_metadata(spending_keys, Some(pending_transaction.tx_id), self.chain_tip_height)
+            .await?;
+
         Ok(())
     }
 
-    /// Cancel a pending transaction and place the encumbered outputs back into the unspent pool
+    /// Cancel a pending transaction and place the encumbered outputs back into the unspent pool. Cancelling a
+    /// transaction that was already abandoned has no effect.
     pub async fn cancel_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
         trace!(
             target: LOG_TARGET,
             "Cancelling pending transaction outputs for TxId: tx_id"
         );
+        if self.is_abandoned(tx_id).await? {
+            return Ok(());
+        }
         Ok(self.db.cancel_pending_transaction_outputs(tx_id).await?)
     }
 
+    /// Encumber the outputs of a cancelled transaction against it again, e.g. when the transaction that was to replace
+    /// it could not be built
+    pub async fn reinstate_transaction(
+        &mut self,
+        pending: PendingTransactionOutputs,
+    ) -> Result<(), OutputManagerError>
+    {
+        let tx_id = pending.tx_id;
+        self.db
+            .encumber_outputs(
+                tx_id,
+                pending.amount,
+                pending.fee,
+                pending.outputs_to_be_spent,
+                pending.outputs_to_be_received,
+            )
+            .await?;
+        self.db.confirm_encumbered_outputs(tx_id).await?;
+        if let Some(destination) = pending.destination {
+            self.db.set_pending_transaction_destination(tx_id, destination).await?;
+        }
+        Ok(())
+    }
+
+    /// Abandon a pending transaction. The encumbered outputs are placed back into the unspent pool, but unlike a
+    /// cancellation the outputs it would have created are marked as abandoned for good, along with the reason.
+    pub async fn abandon_transaction(
+        &mut self,
+        tx_id: TxId,
+        reason: AbandonmentReason,
+    ) -> Result<(), OutputManagerError>
+    {
+        info!(
+            target: LOG_TARGET,
+            "Abandoning pending transaction outputs for TxId: {} ({})", tx_id, reason
+        );
+        Ok(self.db.abandon_pending_transaction_outputs(tx_id, reason).await?)
+    }
+
+    async fn is_abandoned(&self, tx_id: TxId) -> Result<bool, OutputManagerError> {
+        Ok(self
+            .db
+            .fetch_abandoned_transactions()
+            .await?
+            .iter()
+            .any(|t| t.tx_id == tx_id))
+    }
+
+    /// Set or clear the label of the unspent output with the given commitment
+    pub async fn set_output_label(
+        &mut self,
+        commitment: Commitment,
+        label: Option<String>,
+    ) -> Result<(), OutputManagerError>
+    {
+        let output = self
+            .db
+            .get_unspent_outputs()
+            .await?
+            .into_iter()
+            .find(|uo| {
+                uo.as_transaction_input(&self.factories.commitment, uo.features.clone())
+                    .commitment ==
+                    commitment
+            })
+            .ok_or_else(|| OutputManagerError::OutputNotFound(commitment.to_hex()))?;
+        Ok(self.db.set_output_label(output.spending_key, label).await?)
+    }
+
+    /// Fetch the unspent outputs with the given label
+    pub async fn fetch_outputs_by_label(&self, label: String) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        let labelled = self
+            .db
+            .fetch_output_metadata()
+            .await?
+            .into_iter()
+            .filter(|m| m.label.as_ref() == Some(&label))
+            .map(|m| m.spending_key)
+            .collect::<Vec<_>>();
+        Ok(self
+            .db
+            .get_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|uo| labelled.contains(&uo.spending_key))
+            .collect())
+    }
+
+    /// Record the source of the outputs that will be received in the specified pending transaction so that the
+    /// `PrivacyRandom` selection strategy can avoid combining them with outputs from other sources
+    pub async fn set_output_source(&mut self, tx_id: TxId, tag: String) -> Result<(), OutputManagerError> {
+        Ok(self.db.set_pending_transaction_output_source(tx_id, tag).await?)
+    }
+
     /// Go through the pending transaction and if any have existed longer than the specified duration, cancel them
     pub async fn timeout_pending_transactions(&mut self, period: Duration) -> Result<(), OutputManagerError> {
         Ok(self.db.timeout_pending_transaction_outputs(period).await?)
     }
 
     /// Select which unspent transaction outputs to use to send a transaction of the specified amount. Use the specified
-    /// selection strategy to choose the outputs. It also determines if a change output is required.
+    /// selection strategy to choose the outputs. It also determines if a change output is required. If a source tag is
+    /// provided only outputs carrying that tag will be considered.
+    /// Reject a fee per gram below the network minimum, which the mempool would refuse to accept or relay
+    fn check_fee_per_gram(&self, fee_per_gram: MicroTari) -> Result<(), OutputManagerError> {
+        if fee_per_gram < self.config.min_fee_per_gram {
+            return Err(OutputManagerError::FeePerGramBelowMinimum);
+        }
+        Ok(())
+    }
+
+    /// The smallest absolute fee a transaction may pay. Networks without a minimum fee per gram also waive the
+    /// absolute minimum, so that zero-fee transactions can be built.
+    fn min_transaction_fee(&self) -> MicroTari {
+        if self.config.min_fee_per_gram == MicroTari(0) {
+            MicroTari(0)
+        } else {
+            MINIMUM_TRANSACTION_FEE
+        }
+    }
+
     async fn select_utxos(
         &mut self,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         output_count: usize,
         strategy: UTXOSelectionStrategy,
+        source_tag: Option<String>,
+        include_dust: bool,
     ) -> Result<(Vec<UnblindedOutput>, bool), OutputManagerError>
     {
-        let mut utxos = Vec::new();
-        let mut total = MicroTari::from(0);
-        let mut fee_without_change = MicroTari::from(0);
-        let mut fee_with_change = MicroTari::from(0);
+        let mut uo = self.db.fetch_sorted_unspent_outputs().await?;
+
+        if let Some(tag) = source_tag {
+            let tagged_keys = self
+                .db
+                .fetch_output_sources()
+                .await?
+                .into_iter()
+                .filter(|s| s.tag == tag)
+                .map(|s| s.spending_key.to_vec())
+                .collect::<HashSet<_>>();
+            uo.retain(|o| tagged_keys.contains(&o.spending_key.to_vec()));
+        }
+
+        if !include_dust {
+            uo.retain(|o| o.value >= self.config.dust_threshold);
+        }
+        // Once the chain tip is known, outputs that are still time-locked cannot be spent and are not considered
+        let mut immature = Vec::new();
+        if let Some(height) = self.chain_tip_height {
+            let (mature, time_locked): (Vec<_>, Vec<_>) = uo.into_iter().partition(|o| o.features.maturity <= height);
+            uo = mature;
+            immature = time_locked;
+        }
+
+        match self
+            .select_utxos_with_strategy(uo.clone(), amount, fee_per_gram, output_count, strategy)
+            .await
+        {
+            // Distinguish funds that will become spendable once their outputs mature from a shortfall
+            Err(OutputManagerError::NotEnoughFunds) if !immature.is_empty() => {
+                uo.extend(immature);
+                if accumulate_utxos(self.fee_model.as_ref(), &uo, amount, fee_per_gram, output_count).is_some() {
+                    Err(OutputManagerError::FundsPendingMaturity)
+                } else {
+                    Err(OutputManagerError::NotEnoughFunds)
+                }
+            },
+            result => result,
+        }
+    }
 
-        let uo = self.db.fetch_sorted_unspent_outputs().await?;
+    // Select from the spendable outputs using the provided strategy
+    async fn select_utxos_with_strategy(
+        &mut self,
+        uo: Vec<UnblindedOutput>,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        output_count: usize,
+        strategy: UTXOSelectionStrategy,
+    ) -> Result<(Vec<UnblindedOutput>, bool), OutputManagerError>
+    {
+        // All spendable outputs are treated as having the same maturity
+        let tip_height = self.chain_tip_height.unwrap_or(0);
 
-        let uo = match strategy {
-            UTXOSelectionStrategy::Smallest => uo,
-            // TODO: We should pass in the current height and group
-            // all funds less than the current height as maturity 0
+        match strategy {
+            UTXOSelectionStrategy::Smallest => {
+                accumulate_utxos(self.fee_model.as_ref(), &uo, amount, fee_per_gram, output_count)
+                    .ok_or(OutputManagerError::NotEnoughFunds)
+            },
             UTXOSelectionStrategy::MaturityThenSmallest => {
                 let mut new_uo = uo;
-                new_uo.sort_by(|a, b| match a.features.maturity.cmp(&b.features.maturity) {
+                let maturity = |o: &UnblindedOutput| {
+                    if o.features.maturity <= tip_height {
+                        0
+                    } else {
+                        o.features.maturity
+                    }
+                };
+                new_uo.sort_by(|a, b| match maturity(a).cmp(&maturity(b)) {
                     Ordering::Equal => a.value.cmp(&b.value),
                     Ordering::Less => Ordering::Less,
                     Ordering::Greater => Ordering::Greater,
                 });
-                new_uo
+                accumulate_utxos(self.fee_model.as_ref(), &new_uo, amount, fee_per_gram, output_count)
+                    .ok_or(OutputManagerError::NotEnoughFunds)
             },
-        };
+            UTXOSelectionStrategy::Largest => {
+                let mut new_uo = uo;
+                new_uo.sort_by(|a, b| b.value.cmp(&a.value));
+                accumulate_utxos(self.fee_model.as_ref(), &new_uo, amount, fee_per_gram, output_count)
+                    .ok_or(OutputManagerError::NotEnoughFunds)
+            },
+            UTXOSelectionStrategy::BranchAndBound => {
+                if let Some(selection) =
+                    branch_and_bound_utxos(self.fee_model.as_ref(), &uo, amount, fee_per_gram, output_count)
+                {
+                    return Ok((selection, false));
+                }
+                // No changeless selection exists, so fall back to a random order which does not reveal a preference
+                // for particular outputs
+                let mut new_uo = uo;
+                new_uo.shuffle(&mut OsRng);
+                accumulate_utxos(self.fee_model.as_ref(), &new_uo, amount, fee_per_gram, output_count)
+                    .ok_or(OutputManagerError::NotEnoughFunds)
+            },
+            UTXOSelectionStrategy::PrivacyRandom { avoid_mixing_sources } => {
+                let sources = self.db.fetch_output_sources().await?;
+                let sources: HashMap<Vec<u8>, OutputSource> =
+                    sources.into_iter().map(|s| (s.spending_key.to_vec(), s)).collect();
+                let fresh_cutoff = Utc::now().naive_utc() -
+                    ChronoDuration::from_std(FRESH_OUTPUT_PERIOD).expect("Fresh output period is in range");
+                let is_fresh = |o: &UnblindedOutput| {
+                    sources
+                        .get(&o.spending_key.to_vec())
+                        .map(|s| s.timestamp > fresh_cutoff)
+                        .unwrap_or(false)
+                };
+                // Shuffle the outputs and then move freshly received outputs to the back so that they are only
+                // combined with other outputs when there is no alternative. The sort is stable so the random order
+                // is preserved within each partition.
+                let shuffle = |mut outputs: Vec<UnblindedOutput>| {
+                    outputs.shuffle(&mut OsRng);
+                    outputs.sort_by_key(&is_fresh);
+                    outputs
+                };
 
-        let mut require_change_output = false;
-        for o in uo.iter() {
-            utxos.push(o.clone());
-            total += o.value;
-            // I am assuming that the only output will be the payment output and change if required
-            fee_without_change = Fee::calculate(fee_per_gram, 1, utxos.len(), output_count);
-            if total == amount + fee_without_change {
-                break;
-            }
-            fee_with_change = Fee::calculate(fee_per_gram, 1, utxos.len(), output_count + 1);
-            if total >= amount + fee_with_change {
-                require_change_output = true;
-                break;
-            }
+                if !avoid_mixing_sources {
+                    return accumulate_utxos(
+                        self.fee_model.as_ref(),
+                        &shuffle(uo),
+                        amount,
+                        fee_per_gram,
+                        output_count,
+                    )
+                    .ok_or(OutputManagerError::NotEnoughFunds);
+                }
+
+                // Outputs without a recorded source (e.g. coinbases and change) are treated as a single source
+                let mut groups: HashMap<Option<String>, Vec<UnblindedOutput>> = HashMap::new();
+                for o in uo {
+                    let tag = sources.get(&o.spending_key.to_vec()).map(|s| s.tag.clone());
+                    groups.entry(tag).or_insert_with(Vec::new).push(o);
+                }
+                let mut groups = groups.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+                groups.shuffle(&mut OsRng);
+                for group in groups {
+                    if let Some(selection) = accumulate_utxos(
+                        self.fee_model.as_ref(),
+                        &shuffle(group),
+                        amount,
+                        fee_per_gram,
+                        output_count,
+                    ) {
+                        return Ok(selection);
+                    }
+                }
+                Err(OutputManagerError::NotEnoughFundsFromSingleSource)
+            },
+            UTXOSelectionStrategy::PrivacyFirst => {
+                if let Some(selection) =
+                    privacy_first_utxos(self.fee_model.as_ref(), &uo, amount, fee_per_gram, output_count)
+                {
+                    return Ok(selection);
+                }
+                // Fall back to the fewest inputs that cover the amount, and warn that they will be linked
+                let mut new_uo = uo;
+                new_uo.sort_by(|a, b| b.value.cmp(&a.value));
+                let selection = accumulate_utxos(self.fee_model.as_ref(), &new_uo, amount, fee_per_gram, output_count)
+                    .ok_or(OutputManagerError::NotEnoughFunds)?;
+                warn!(
+                    target: LOG_TARGET,
+                    "No one or two outputs cover {} with fees, {} inputs will be linked by the transaction",
+                    amount,
+                    selection.0.len()
+                );
+                let _ = self
+                    .event_publisher
+                    .send(OutputManagerEvent::WouldLinkManyInputs(selection.0.len()))
+                    .await
+                    .map_err(|e| {
+                        trace!(
+                            target: LOG_TARGET,
+                            "Error sending event, usually because there are no subscribers: {:?}",
+                            e
+                        );
+                        e
+                    });
+                Ok(selection)
+            },
         }
+    }
 
-        if (total != amount + fee_without_change) && (total < amount + fee_with_change) {
-            return Err(OutputManagerError::NotEnoughFunds);
+    /// Ask the base node for its chain tip height. The response is handled in `handle_base_node_response`.
+    async fn query_chain_tip(&mut self) -> Result<(), OutputManagerError> {
+        let pk = self
+            .base_node_public_key
+            .clone()
+            .ok_or_else(|| OutputManagerError::NoBaseNodeKeysProvided)?;
+        let request_key = OsRng.next_u64();
+        let service_request = BaseNodeProto::BaseNodeServiceRequest {
+            request_key,
+            request: Some(BaseNodeRequestProto::GetChainMetadata(true)),
+        };
+        self.outbound_message_service
+            .send_direct(
+                pk,
+                OutboundEncryption::None,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+            )
+            .await?;
+        let timeout = self.base_node_query_timeout;
+        self.pending_chain_tip_query_keys
+            .retain(|_, sent_at| sent_at.elapsed() < timeout);
+        self.pending_chain_tip_query_keys.insert(request_key, Instant::now());
+        Ok(())
+    }
+
+    /// Ask the base node for the invalid outputs, which may have reappeared on the blockchain after a reorg. The
+    /// response is handled in `handle_revalidation_response`. Returns `None` if there are no invalid outputs to query.
+    pub async fn revalidate_invalid_outputs(&mut self) -> Result<Option<u64>, OutputManagerError> {
+        let pk = self
+            .base_node_public_key
+            .clone()
+            .ok_or_else(|| OutputManagerError::NoBaseNodeKeysProvided)?;
+        let invalid_outputs = self.db.get_invalid_outputs().await?;
+        if invalid_outputs.is_empty() {
+            return Ok(None);
+        }
+        let mut output_hashes = Vec::with_capacity(invalid_outputs.len());
+        for io in invalid_outputs.iter() {
+            output_hashes.push(io.output.as_transaction_output(&self.factories)?.hash());
         }
 
-        Ok((utxos, require_change_output))
+        let request_key = OsRng.next_u64();
+        let service_request = BaseNodeProto::BaseNodeServiceRequest {
+            request_key,
+            request: Some(BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs {
+                outputs: output_hashes,
+            })),
+        };
+        self.outbound_message_service
+            .send_direct(
+                pk,
+                OutboundEncryption::None,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+            )
+            .await?;
+        self.pending_revalidation_query_keys.insert(request_key);
+        debug!(
+            target: LOG_TARGET,
+            "Revalidation query ({}) for {} invalid output(s) sent to Base Node",
+            request_key,
+            invalid_outputs.len()
+        );
+        Ok(Some(request_key))
     }
 
-    /// Set the base node public key to the list that will be used to check the status of UTXO's on the base chain. If
-    /// this is the first time the base node public key is set do the UTXO queries.
-    async fn set_base_node_public_key(
+    /// Restore the invalid outputs that the base node returned to the unspent outputs
+    async fn handle_revalidation_response(
         &mut self,
-        base_node_public_key: CommsPublicKey,
-        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+        request_key: u64,
+        response: Vec<tari_core::transactions::proto::types::TransactionOutput>,
     ) -> Result<(), OutputManagerError>
     {
-        let startup_query = self.base_node_public_key.is_none();
-
-        self.base_node_public_key = Some(base_node_public_key);
+        let mut returned_hashes = HashSet::new();
+        for output in response.iter() {
+            let hash = TransactionOutput::try_from(output.clone())
+                .map_err(OutputManagerError::ConversionError)?
+                .hash();
+            returned_hashes.insert(hash);
+        }
 
-        if startup_query {
-            self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+        let mut num_restored = 0;
+        for io in self.db.get_invalid_outputs().await? {
+            if returned_hashes.contains(&io.output.as_transaction_output(&self.factories)?.hash()) {
+                info!(
+                    target: LOG_TARGET,
+                    "Output with value {} was returned by the Base Node again and is being restored", io.output.value
+                );
+                self.db.revalidate_output(io.output).await?;
+                num_restored += 1;
+            }
         }
-        Ok(())
-    }
 
-    pub async fn fetch_pending_transaction_outputs(
+        let _ = self
+            .event_publisher
+            .send(OutputManagerEvent::OutputsRevalidated((request_key, num_restored)))
+            .await
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        Ok(())
+    }
+
+    /// The coinbases that were requested for blocks that have not been mined yet. The height of the block a coinbase
+    /// was meant for is worked out from its maturity and the configured coinbase lock height.
+    pub async fn get_pending_coinbases(&mut self) -> Result<Vec<PendingCoinbase>, OutputManagerError> {
+        let now = Utc::now().naive_utc();
+        let mut coinbases = Vec::new();
+        for (tx_id, pending) in self.db.fetch_all_pending_transaction_outputs().await? {
+            if !pending.outputs_to_be_spent.is_empty() || pending.outputs_to_be_received.len() != 1 {
+                continue;
+            }
+            let output = &pending.outputs_to_be_received[0];
+            if !output.features.flags.contains(OutputFlags::COINBASE_OUTPUT) {
+                continue;
+            }
+            let maturity_height = output.features.maturity;
+            let estimated_spendable_time = self.chain_tip_height.map(|height| {
+                let blocks = maturity_height.saturating_sub(height);
+                now + ChronoDuration::seconds((self.config.block_interval.as_secs() * blocks) as i64)
+            });
+            coinbases.push(PendingCoinbase {
+                tx_id,
+                value: output.value,
+                block_height: maturity_height.saturating_sub(self.config.coinbase_lock_height),
+                maturity_height,
+                estimated_spendable_time,
+            });
+        }
+        coinbases.sort_by_key(|c| (c.block_height, c.tx_id));
+        Ok(coinbases)
+    }
+
+    /// Cancel the pending coinbases whose block was not won by the time the chain tip is the configured number of
+    /// blocks past it, and publish a `CoinbaseInvalidated` event for each
+    async fn invalidate_expired_coinbases(&mut self, height: u64) -> Result<(), OutputManagerError> {
+        if self.config.coinbase_expiry_blocks == 0 {
+            return Ok(());
+        }
+        for coinbase in self.get_pending_coinbases().await? {
+            if height < coinbase.block_height + self.config.coinbase_expiry_blocks {
+                continue;
+            }
+            info!(
+                target: LOG_TARGET,
+                "Coinbase (TxId: {}) for block {} was not mined by height {}, invalidating it",
+                coinbase.tx_id,
+                coinbase.block_height,
+                height
+            );
+            self.db.cancel_pending_transaction_outputs(coinbase.tx_id).await?;
+            let _ = self
+                .event_publisher
+                .send(OutputManagerEvent::CoinbaseInvalidated(coinbase.tx_id))
+                .await
+                .map_err(|e| {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Error sending event, usually because there are no subscribers: {:?}",
+                        e
+                    );
+                    e
+                });
+        }
+        Ok(())
+    }
+
+    /// Record a new chain tip height and publish a `FundsMatured` event for the unspent outputs whose maturity has been
+    /// reached since the previous height. The first height received only establishes a baseline. Pending coinbases
+    /// whose block was won by another miner are invalidated.
+    async fn update_chain_tip_height(&mut self, height: u64) -> Result<(), OutputManagerError> {
+        self.invalidate_expired_coinbases(height).await?;
+        let previous_height = self.chain_tip_height.replace(height);
+        let previous_height = match previous_height {
+            Some(h) if h < height => h,
+            _ => return Ok(()),
+        };
+
+        let matured = self
+            .db
+            .get_unspent_outputs()
+            .await?
+            .iter()
+            .filter(|o| o.features.maturity > previous_height && o.features.maturity <= height)
+            .fold(MicroTari::from(0), |acc, o| acc + o.value);
+        if matured > MicroTari::from(0) {
+            debug!(
+                target: LOG_TARGET,
+                "{} of outputs matured between heights {} and {}", matured, previous_height, height
+            );
+            let _ = self
+                .event_publisher
+                .send(OutputManagerEvent::FundsMatured(matured))
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Set the base node public key to the list that will be used to check the status of UTXO's on the base chain. If
+    /// this is the first time the base node public key is set do the UTXO queries, otherwise switching to a different
+    /// base node makes a validation of the outputs due.
+    async fn set_base_node_public_key(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<(), OutputManagerError>
+    {
+        let startup_query = self.base_node_public_key.is_none();
+
+        let previous_public_key = self.base_node_public_key.replace(base_node_public_key);
+        if previous_public_key != self.base_node_public_key {
+            // Latencies observed to the previous base node say nothing about the new one
+            self.base_node_latencies.clear();
+        }
+
+        if startup_query {
+            self.reset_base_node_sync_status();
+            self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+            self.last_validation = Some(Instant::now());
+        } else if previous_public_key != self.base_node_public_key {
+            self.validation_due = true;
+            self.validate_outputs_if_due(utxo_query_timeout_futures).await?;
+        }
+        Ok(())
+    }
+
+    // Add a round trip latency observed to the base node to the latency window and, while the timeout is adaptive, tune
+    // the base node query timeout to the configured multiple of the average latency
+    fn record_base_node_latency(&mut self, latency: Duration) {
+        if self.base_node_latencies.len() >= BASE_NODE_LATENCY_SAMPLE_SIZE {
+            self.base_node_latencies.pop_front();
+        }
+        self.base_node_latencies.push_back(latency);
+        if !self.adaptive_query_timeout {
+            return;
+        }
+
+        let average = self.base_node_latencies.iter().sum::<Duration>() / self.base_node_latencies.len() as u32;
+        let timeout = average
+            .checked_mul(self.config.base_node_query_timeout_latency_factor)
+            .unwrap_or(self.config.max_base_node_query_timeout);
+        self.base_node_query_timeout = max(
+            self.config.min_base_node_query_timeout,
+            min(timeout, self.config.max_base_node_query_timeout),
+        );
+        trace!(
+            target: LOG_TARGET,
+            "Base node query timeout tuned to {:?} for an average base node latency of {:?}",
+            self.base_node_query_timeout,
+            average
+        );
+    }
+
+    /// Validate the unspent and invalid outputs against the base node if a validation is due and the minimum interval
+    /// since the last validation has passed. A validation that is not yet allowed stays due and is run when the next
+    /// chain tip is received.
+    async fn validate_outputs_if_due(
+        &mut self,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<(), OutputManagerError>
+    {
+        if !self.validation_due || self.base_node_public_key.is_none() {
+            return Ok(());
+        }
+        if let Some(last_validation) = self.last_validation {
+            if last_validation.elapsed() < self.config.validation_min_interval {
+                trace!(
+                    target: LOG_TARGET,
+                    "Deferring output validation, the last validation was {:?} ago",
+                    last_validation.elapsed()
+                );
+                return Ok(());
+            }
+        }
+        self.validation_due = false;
+        self.last_validation = Some(Instant::now());
+
+        self.reset_base_node_sync_status();
+        self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+        self.revalidate_invalid_outputs().await?;
+        Ok(())
+    }
+
+    pub async fn fetch_pending_transaction_outputs(
         &self,
     ) -> Result<HashMap<u64, PendingTransactionOutputs>, OutputManagerError> {
         Ok(self.db.fetch_all_pending_transaction_outputs().await?)
     }
 
+    /// Summarise the outputs in each collection. The pending collection holds both the outputs encumbered by and the
+    /// outputs expected from pending transactions.
+    pub async fn get_output_statistics(&self) -> Result<OutputStatistics, OutputManagerError> {
+        let values = |outputs: &[UnblindedOutput]| outputs.iter().map(|o| o.value).collect::<Vec<_>>();
+        let unspent = self.db.fetch_sorted_unspent_outputs().await?;
+        let spent = self.db.fetch_spent_outputs().await?;
+        let pending = self
+            .db
+            .fetch_all_pending_transaction_outputs()
+            .await?
+            .values()
+            .flat_map(|p| p.outputs_to_be_spent.iter().chain(p.outputs_to_be_received.iter()))
+            .map(|o| o.value)
+            .collect::<Vec<_>>();
+        let invalid = self
+            .db
+            .get_invalid_outputs()
+            .await?
+            .iter()
+            .map(|o| o.output.value)
+            .collect::<Vec<_>>();
+
+        Ok(OutputStatistics {
+            unspent: OutputCollectionStatistics::from_values(&values(&unspent)),
+            spent: OutputCollectionStatistics::from_values(&values(&spent)),
+            pending: OutputCollectionStatistics::from_values(&pending),
+            invalid: OutputCollectionStatistics::from_values(&invalid),
+        })
+    }
+
     pub async fn fetch_spent_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
         Ok(self.db.fetch_spent_outputs().await?)
     }
@@ -765,7 +2953,14 @@ where
         Ok(self.db.fetch_sorted_unspent_outputs().await?)
     }
 
-    pub async fn fetch_invalid_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+    /// The unspent outputs below the configured dust threshold
+    pub async fn fetch_dust_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        let mut uo = self.db.fetch_sorted_unspent_outputs().await?;
+        uo.retain(|o| o.value < self.config.dust_threshold);
+        Ok(uo)
+    }
+
+    pub async fn fetch_invalid_outputs(&self) -> Result<Vec<InvalidOutput>, OutputManagerError> {
         Ok(self.db.get_invalid_outputs().await?)
     }
 
@@ -781,6 +2976,10 @@ where
             target: LOG_TARGET,
             "Select UTXOs and estimate coin split transaction fee."
         );
+        if amount_per_split < self.config.min_output_value {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
         let mut output_count = split_count;
         let total_split_amount = amount_per_split * split_count as u64;
         let (inputs, require_change_output) = self
@@ -789,6 +2988,8 @@ where
                 fee_per_gram,
                 output_count,
                 UTXOSelectionStrategy::MaturityThenSmallest,
+                None,
+                false,
             )
             .await?;
         let utxo_total = inputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
@@ -796,7 +2997,17 @@ where
         if require_change_output {
             output_count = split_count + 1
         };
-        let fee = Fee::calculate(fee_per_gram, 1, input_count, output_count);
+        let mut fee = self.fee_model.calculate(fee_per_gram, 1, input_count, output_count);
+        let change_output = utxo_total
+            .checked_sub(fee)
+            .ok_or(OutputManagerError::NotEnoughFunds)?
+            .checked_sub(total_split_amount)
+            .ok_or(OutputManagerError::NotEnoughFunds)?;
+        // Change below the minimum output value is added to the fee rather than creating a dust output
+        if require_change_output && change_output < self.config.min_output_value {
+            output_count = split_count;
+            fee = utxo_total - total_split_amount;
+        }
 
         trace!(target: LOG_TARGET, "Construct coin split transaction.");
         let offset = PrivateKey::random(&mut OsRng);
@@ -805,8 +3016,11 @@ where
         builder
             .with_lock_height(lock_height.unwrap_or(0))
             .with_fee_per_gram(fee_per_gram)
+            .with_fee_model(self.fee_model.clone())
             .with_offset(offset.clone())
-            .with_private_nonce(nonce.clone());
+            .with_private_nonce(nonce.clone())
+            .with_min_output_value(self.config.min_output_value)
+            .with_min_fee(self.min_transaction_fee());
         trace!(target: LOG_TARGET, "Add inputs to coin split transaction.");
         for uo in inputs.iter() {
             builder.with_input(
@@ -816,11 +3030,6 @@ where
         }
         trace!(target: LOG_TARGET, "Add outputs to coin split transaction.");
         let mut outputs = Vec::with_capacity(output_count);
-        let change_output = utxo_total
-            .checked_sub(fee)
-            .ok_or(OutputManagerError::NotEnoughFunds)?
-            .checked_sub(total_split_amount)
-            .ok_or(OutputManagerError::NotEnoughFunds)?;
         for i in 0..output_count {
             let output_amount = if i < split_count {
                 amount_per_split
@@ -828,21 +3037,13 @@ where
                 change_output
             };
 
-            let mut spend_key = PrivateKey::default();
-            {
-                let mut km = acquire_lock!(self.key_manager);
-                spend_key = km.next_key()?.k;
-            }
-            self.db.increment_key_index().await?;
+            let spend_key = self.get_next_key(KeyManagerBranch::Spending).await?;
             let utxo = UnblindedOutput::new(output_amount, spend_key, None);
             outputs.push(utxo.clone());
             builder.with_output(utxo);
         }
         trace!(target: LOG_TARGET, "Build coin split transaction.");
-        let factories = CryptoFactories::default();
-        let mut stp = builder
-            .build::<HashDigest>(&self.factories)
-            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+        let stp = self.build_sender_protocol_with_progress(builder).await?;
         // The Transaction Protocol built successfully so we will pull the unspent outputs out of the unspent list and
         // store them until the transaction times out OR is confirmed
         let tx_id = stp.get_tx_id()?;
@@ -851,31 +3052,868 @@ where
             "Encumber coin split transaction ({}) outputs.",
             tx_id
         );
-        self.db.encumber_outputs(tx_id, inputs, outputs).await?;
+        self.db
+            .encumber_outputs(tx_id, MicroTari(0), fee, inputs, outputs)
+            .await?;
         self.confirm_encumberance(tx_id).await?;
         trace!(target: LOG_TARGET, "Finalize coin split transaction ({}).", tx_id);
-        stp.finalize(KernelFeatures::empty(), &factories)?;
+        let stp = self.finalize_sender_protocol(stp).await?;
         let tx = stp.get_transaction().map(Clone::clone)?;
         Ok((tx_id, tx, fee, utxo_total))
     }
 
-    /// Return the Seed words for the current Master Key set in the Key Manager
-    pub fn get_seed_words(&self) -> Result<Vec<String>, OutputManagerError> {
-        Ok(from_secret_key(
-            &acquire_lock!(self.key_manager).master_key,
-            &MnemonicLanguage::English,
-        )?)
+    /// Create a transaction that sweeps up to `max_inputs` of the smallest spendable outputs into a single new output,
+    /// consolidating dust into an output that is worth spending. Returns the transaction id, the transaction, the fee
+    /// and the total value of the joined outputs, as with a coin split.
+    pub async fn create_coin_join(
+        &mut self,
+        max_inputs: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        trace!(
+            target: LOG_TARGET,
+            "Select UTXOs and estimate coin join transaction fee."
+        );
+        self.check_fee_per_gram(fee_per_gram)?;
+        let mut inputs = self.db.fetch_sorted_unspent_outputs().await?;
+        // Outputs that are still time-locked can't be spent yet
+        if let Some(height) = self.chain_tip_height {
+            inputs.retain(|o| o.features.maturity <= height);
+        }
+        inputs.sort_by(|a, b| a.value.cmp(&b.value));
+        inputs.truncate(max_inputs);
+        if inputs.len() < 2 {
+            return Err(OutputManagerError::NotEnoughOutputsToJoin);
+        }
+
+        let utxo_total = inputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+        let fee = self.fee_model.calculate(fee_per_gram, 1, inputs.len(), 1);
+        let output_amount = utxo_total.checked_sub(fee).ok_or(OutputManagerError::NotEnoughFunds)?;
+        if output_amount < self.config.min_output_value {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+
+        trace!(target: LOG_TARGET, "Construct coin join transaction.");
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_fee_per_gram(fee_per_gram)
+            .with_fee_model(self.fee_model.clone())
+            .with_offset(offset.clone())
+            .with_private_nonce(nonce.clone())
+            .with_min_output_value(self.config.min_output_value)
+            .with_min_fee(self.min_transaction_fee());
+        for uo in inputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
+                uo.clone(),
+            );
+        }
+
+        let spend_key = self.get_next_key(KeyManagerBranch::Spending).await?;
+        let output = UnblindedOutput::new(output_amount, spend_key, None);
+        builder.with_output(output.clone());
+
+        trace!(target: LOG_TARGET, "Build coin join transaction.");
+        let stp = self.build_sender_protocol(builder).await?;
+        let tx_id = stp.get_tx_id()?;
+        trace!(
+            target: LOG_TARGET,
+            "Encumber coin join transaction ({}) outputs.",
+            tx_id
+        );
+        self.db
+            .encumber_outputs(tx_id, MicroTari(0), fee, inputs, vec![output])
+            .await?;
+        self.confirm_encumberance(tx_id).await?;
+        trace!(target: LOG_TARGET, "Finalize coin join transaction ({}).", tx_id);
+        let stp = self.finalize_sender_protocol(stp).await?;
+        let tx = stp.get_transaction().map(Clone::clone)?;
+        Ok((tx_id, tx, fee, utxo_total))
+    }
+
+    /// Create a transaction that provably destroys `amount` by recording it as the burned value of a burn kernel.
+    /// Only a change output is created, so the burned funds can never be spent again.
+    pub async fn create_burn_transaction(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        trace!(target: LOG_TARGET, "Select UTXOs and estimate burn transaction fee.");
+        if amount == MicroTari(0) {
+            return Err(OutputManagerError::BelowMinimumOutputValue);
+        }
+        self.check_fee_per_gram(fee_per_gram)?;
+        let (inputs, require_change_output) = self
+            .select_utxos(
+                amount,
+                fee_per_gram,
+                0,
+                UTXOSelectionStrategy::MaturityThenSmallest,
+                None,
+                false,
+            )
+            .await?;
+
+        trace!(target: LOG_TARGET, "Construct burn transaction.");
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(lock_height.unwrap_or(0))
+            .with_fee_per_gram(fee_per_gram)
+            .with_fee_model(self.fee_model.clone())
+            .with_offset(offset.clone())
+            .with_private_nonce(nonce.clone())
+            .with_burned_value(amount)
+            .with_min_output_value(self.config.min_output_value)
+            .with_min_fee(self.min_transaction_fee());
+        for uo in inputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
+                uo.clone(),
+            );
+        }
+
+        let mut change_key: Option<PrivateKey> = None;
+        if require_change_output {
+            let key = self.get_next_key(KeyManagerBranch::Spending).await?;
+            change_key = Some(key.clone());
+            builder.with_change_secret(key);
+        }
+
+        trace!(target: LOG_TARGET, "Build burn transaction.");
+        let stp = self.build_sender_protocol(builder).await?;
+
+        // Dust change is added to the fee by the builder, in which case there is no change output
+        let mut change_output = Vec::<UnblindedOutput>::new();
+        if let Some(key) = change_key {
+            let change = stp.get_amount_to_self()?;
+            if change > MicroTari(0) {
+                change_output.push(UnblindedOutput {
+                    value: change,
+                    spending_key: key,
+                    features: OutputFeatures::default(),
+                });
+            }
+        }
+
+        let tx_id = stp.get_tx_id()?;
+        trace!(target: LOG_TARGET, "Encumber burn transaction ({}) outputs.", tx_id);
+        self.db
+            .encumber_outputs(tx_id, amount, stp.get_fee_amount()?, inputs, change_output)
+            .await?;
+        self.confirm_encumberance(tx_id).await?;
+        trace!(target: LOG_TARGET, "Finalize burn transaction ({}).", tx_id);
+        let stp = self.finalize_sender_protocol(stp).await?;
+        let tx = stp.get_transaction().map(Clone::clone)?;
+        let fee = tx.body.get_total_fee();
+        Ok((tx_id, tx, fee, amount))
+    }
+
+    // Build a sender transaction protocol on the blocking thread pool. Building generates the range proofs of the
+    // outputs, which would otherwise stall the other tasks on the runtime.
+    async fn build_sender_protocol(
+        &self,
+        builder: SenderTransactionInitializer,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        let factories = self.factories.clone();
+        tokio::task::spawn_blocking(move || builder.build::<HashDigest>(&factories))
+            .await
+            .map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))?
+            .map_err(|e| OutputManagerError::BuildError(e.message))
+    }
+
+    // Build a sender transaction protocol with many outputs on the blocking thread pool. The range proofs are
+    // generated in parallel and a `CoinSplitProgress` event is published each time the proof of an output is done.
+    async fn build_sender_protocol_with_progress(
+        &mut self,
+        builder: SenderTransactionInitializer,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        let factories = self.factories.clone();
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let build_task = tokio::task::spawn_blocking(move || {
+            builder.build_with_progress::<HashDigest>(&factories, &|completed, total| {
+                let _ = progress_tx.unbounded_send((completed, total));
+            })
+        });
+
+        // The progress channel closes once the build is done and the sender has been dropped
+        while let Some((completed, total)) = progress_rx.next().await {
+            trace!(
+                target: LOG_TARGET,
+                "Range proofs of {} of {} outputs done",
+                completed,
+                total
+            );
+            let _ = self
+                .event_publisher
+                .send(OutputManagerEvent::CoinSplitProgress(completed, total))
+                .await
+                .map_err(|e| {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Error sending event, usually because there are no subscribers: {:?}",
+                        e
+                    );
+                    e
+                });
+        }
+
+        build_task
+            .await
+            .map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))?
+            .map_err(|e| OutputManagerError::BuildError(e.message))
+    }
+
+    // Finalize a sender transaction protocol on the blocking thread pool, because finalizing verifies the range proofs
+    // of the transaction
+    async fn finalize_sender_protocol(
+        &self,
+        mut stp: SenderTransactionProtocol,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        let factories = self.factories.clone();
+        tokio::task::spawn_blocking(move || {
+            stp.finalize(KernelFeatures::empty(), &factories)?;
+            Ok(stp)
+        })
+        .await
+        .map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))?
+    }
+
+    /// Return the persisted key manager state. It is only returned if it matches the master key in use, which is not
+    /// available to the service when the keys are provided by an external key provider.
+    pub async fn get_key_manager_state(&self) -> Result<KeyManagerState, OutputManagerError> {
+        let master_key = match acquire_lock!(self.key_provider).master_key() {
+            Some(master_key) => master_key,
+            None => return Err(OutputManagerError::MasterKeyNotAvailable),
+        };
+        self.db
+            .get_key_manager_state()
+            .await?
+            .filter(|state| state.master_seed == master_key)
+            .ok_or_else(|| OutputManagerError::MasterKeyNotAvailable)
+    }
+
+    /// Return the Seed words for the current Master Key set in the Key Manager, in the given language or in the
+    /// configured `mnemonic_language`. A wallet created from BIP-39 seed words returns its BIP-39 seed words, which
+    /// together with the passphrase they were created with restore the wallet.
+    pub async fn get_seed_words(&self, language: Option<MnemonicLanguage>) -> Result<Vec<String>, OutputManagerError> {
+        let language = language.unwrap_or_else(|| self.config.mnemonic_language.clone());
+        let master_key = match acquire_lock!(self.key_provider).master_key() {
+            Some(master_key) => master_key,
+            None => return Err(OutputManagerError::MasterKeyNotAvailable),
+        };
+        let bip39_entropy = self
+            .db
+            .get_key_manager_state()
+            .await?
+            .filter(|state| state.master_seed == master_key)
+            .and_then(|state| state.bip39_entropy);
+        if let Some(entropy) = bip39_entropy {
+            return Ok(bip39::from_entropy(&entropy, &language)?);
+        }
+        Ok(from_secret_key(&master_key, &language)?)
     }
 }
 
 /// Different UTXO selection strategies for choosing which UTXO's are used to fulfill a transaction
 /// TODO Investigate and implement more optimal strategies
+#[derive(Clone, Debug, PartialEq)]
 pub enum UTXOSelectionStrategy {
     // Start from the smallest UTXOs and work your way up until the amount is covered. Main benefit
     // is removing small UTXOs from the blockchain, con is that it costs more in fees
     Smallest,
     // Start from oldest maturity to reduce the likelihood of grabbing locked up UTXOs
     MaturityThenSmallest,
+    // Select UTXOs in a random order, preferring outputs that were not received recently, to make it harder to link
+    // the inputs of a transaction to their origin. If `avoid_mixing_sources` is set then all the inputs must come
+    // from the same counterparty, and the selection fails rather than combining outputs from different sources.
+    PrivacyRandom { avoid_mixing_sources: bool },
+    // Start from the largest UTXOs and work your way down until the amount is covered. This uses the fewest inputs
+    // and so the lowest fee, at the cost of leaving small UTXOs unspent
+    Largest,
+    // Search for a combination of UTXOs that covers the amount so closely that no change output is needed, which
+    // hides which output of the transaction belongs to the sender. If no such combination exists the UTXOs are
+    // selected in a random order.
+    BranchAndBound,
+    // Use the smallest single UTXO that covers the amount, or else the smallest pair, so that a transaction does not
+    // link many of the wallet's outputs together. If more inputs are needed the fewest possible are used and a
+    // `WouldLinkManyInputs` event is published.
+    PrivacyFirst,
+}
+
+/// A UTXO query request that has been sent to the base node and is waiting for a response
+struct PendingUtxoQuery {
+    generation: u64,
+    output_hashes: HashSet<Vec<u8>>,
+    sent_at: Instant,
+}
+
+/// The progress of a UTXO query whose output hashes are split over several requests
+struct UtxoQueryResponses {
+    // The request key of the first request, which identifies the query
+    query_key: u64,
+    base_node_public_key: CommsPublicKey,
+    output_hashes: HashSet<Vec<u8>>,
+    pending_request_keys: HashSet<u64>,
+    returned_hashes: HashSet<Vec<u8>>,
+    // The number of queried outputs whose request has been answered
+    num_validated: usize,
+    sent_at: Instant,
+    // The entry recorded for the query in the sync history
+    history: SyncHistoryEntry,
+}
+
+/// A recovery scan of the keys regenerated from a seed
+struct RecoveryScan {
+    // The key manager of the branch that is being scanned
+    key_manager: KeyManager<PrivateKey, KeyDigest>,
+    branch: KeyManagerBranch,
+    // The branches that still have to be scanned, the next one last
+    remaining_branches: Vec<KeyManagerBranch>,
+    gap_limit: usize,
+    // The key indices [batch_start, batch_end) that are being queried
+    batch_start: usize,
+    batch_end: usize,
+    // The highest key index an output was found for on the current branch, zero if none has been found
+    last_found_index: usize,
+    // The highest key index an output was found for on each branch that has been scanned
+    found_indices: HashMap<KeyManagerBranch, usize>,
+    request_key: u64,
+    recovered: usize,
+    // Whether the key manager state of the wallet is replaced by that of the scanned seed if the seeds differ
+    replace_key_manager: bool,
+}
+
+/// A scan of the blocks in a height range for outputs that belong to the wallet
+struct HeightRangeScan {
+    // The first height of the batch of blocks that is being queried
+    batch_start: u64,
+    end_height: u64,
+    // The key manager branch, key index, spending key and value of every commitment the scan looks for
+    candidates: HashMap<Vec<u8>, (KeyManagerBranch, usize, PrivateKey, MicroTari)>,
+    // The highest key index an output was found for on each branch
+    found_indices: HashMap<KeyManagerBranch, usize>,
+    request_key: u64,
+    recovered: usize,
+}
+
+impl HeightRangeScan {
+    // The last height of the batch of blocks that is being queried
+    fn batch_end(&self) -> u64 {
+        min(
+            self.end_height,
+            self.batch_start.saturating_add(HEIGHT_RANGE_SCAN_BATCH_SIZE - 1),
+        )
+    }
+}
+
+impl RecoveryScan {
+    // The output hash of every key in the current batch with every candidate value, with the key index and output
+    fn candidates(
+        &self,
+        values: &[MicroTari],
+        factories: &CryptoFactories,
+    ) -> Result<Vec<(Vec<u8>, (usize, UnblindedOutput))>, OutputManagerError>
+    {
+        Ok(
+            candidate_keys(&self.key_manager, self.batch_start..self.batch_end, values)?
+                .into_iter()
+                .map(|(index, key, value)| {
+                    let uo = UnblindedOutput::new(value, key, None);
+                    let hash = uo
+                        .as_transaction_input(&factories.commitment, OutputFeatures::default())
+                        .hash();
+                    (hash, (index, uo))
+                })
+                .collect(),
+        )
+    }
+}
+
+// The key index and spending key of every key in `indices` with every value an output of a scan may have. Both the
+// recovery scan and the height range scan recognise the outputs of the wallet by these.
+fn candidate_keys<I>(
+    key_manager: &KeyManager<PrivateKey, KeyDigest>,
+    indices: I,
+    values: &[MicroTari],
+) -> Result<Vec<(usize, PrivateKey, MicroTari)>, OutputManagerError>
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut candidates = Vec::new();
+    for index in indices {
+        let key = key_manager.derive_key(index)?.k;
+        for value in values {
+            candidates.push((index, key.clone(), *value));
+        }
+    }
+    Ok(candidates)
+}
+
+/// Options that control how the inputs of a transaction being sent are selected
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransactionSendOptions {
+    pub source_tag: Option<String>,
+    pub utxo_selection_strategy: Option<UTXOSelectionStrategy>,
+    pub include_dust: bool,
+}
+
+impl TransactionSendOptions {
+    /// Only fund the transaction from outputs carrying the given source tag. This guarantees that funds from different
+    /// sources, e.g. customer and operational funds, are never combined in one transaction.
+    pub fn restrict_to_source<T: Into<String>>(mut self, tag: T) -> Self {
+        self.source_tag = Some(tag.into());
+        self
+    }
+
+    /// Select the inputs of the transaction with the given strategy instead of the one configured for the service
+    pub fn with_utxo_selection_strategy(mut self, strategy: UTXOSelectionStrategy) -> Self {
+        self.utxo_selection_strategy = Some(strategy);
+        self
+    }
+
+    /// Allow outputs below the configured dust threshold to fund the transaction
+    pub fn include_dust_outputs(mut self) -> Self {
+        self.include_dust = true;
+        self
+    }
+}
+
+/// Accumulate outputs in the given order until they cover the amount and the fee. Returns the selected outputs and
+/// whether a change output is required, or None if the outputs are insufficient.
+fn accumulate_utxos(
+    fee_model: &dyn FeeModel,
+    uo: &[UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    output_count: usize,
+) -> Option<(Vec<UnblindedOutput>, bool)>
+{
+    let mut utxos = Vec::new();
+    let mut total = MicroTari::from(0);
+    let mut fee_without_change = MicroTari::from(0);
+    let mut fee_with_change = MicroTari::from(0);
+
+    let mut require_change_output = false;
+    for o in uo.iter() {
+        utxos.push(o.clone());
+        total += o.value;
+        // I am assuming that the only output will be the payment output and change if required
+        fee_without_change = fee_model.calculate(fee_per_gram, 1, utxos.len(), output_count);
+        if total == amount + fee_without_change {
+            break;
+        }
+        fee_with_change = fee_model.calculate(fee_per_gram, 1, utxos.len(), output_count + 1);
+        if total >= amount + fee_with_change {
+            require_change_output = true;
+            break;
+        }
+    }
+
+    if (total != amount + fee_without_change) && (total < amount + fee_with_change) {
+        return None;
+    }
+
+    Some((utxos, require_change_output))
+}
+
+/// Select the smallest single output that covers the amount and fee or, failing that, the pair of outputs with the
+/// smallest total that does. A pair that needs no change output is preferred over one that does. Returns None if more
+/// than two inputs are needed.
+fn privacy_first_utxos(
+    fee_model: &dyn FeeModel,
+    uo: &[UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    output_count: usize,
+) -> Option<(Vec<UnblindedOutput>, bool)>
+{
+    let mut outputs = uo.to_vec();
+    outputs.sort_by(|a, b| a.value.cmp(&b.value));
+    if let Some(single) = outputs
+        .iter()
+        .find_map(|o| accumulate_utxos(fee_model, std::slice::from_ref(o), amount, fee_per_gram, output_count))
+    {
+        return Some(single);
+    }
+    if outputs.len() < 2 {
+        return None;
+    }
+
+    let without_change = amount + fee_model.calculate(fee_per_gram, 1, 2, output_count);
+    let with_change = amount + fee_model.calculate(fee_per_gram, 1, 2, output_count + 1);
+    // Both searches move two indices towards each other over the outputs sorted by value
+    let mut exact = None;
+    let (mut i, mut j) = (0, outputs.len() - 1);
+    while i < j && exact.is_none() {
+        let total = outputs[i].value + outputs[j].value;
+        if total == without_change {
+            exact = Some((i, j));
+        } else if total > without_change {
+            j -= 1;
+        } else {
+            i += 1;
+        }
+    }
+    let mut smallest: Option<(MicroTari, usize, usize)> = None;
+    let (mut i, mut j) = (0, outputs.len() - 1);
+    while i < j {
+        let total = outputs[i].value + outputs[j].value;
+        if total >= with_change {
+            if smallest.map_or(true, |(t, _, _)| total < t) {
+                smallest = Some((total, i, j));
+            }
+            j -= 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    let (i, j) = exact.or_else(|| smallest.map(|(_, i, j)| (i, j)))?;
+    accumulate_utxos(
+        fee_model,
+        &[outputs[i].clone(), outputs[j].clone()],
+        amount,
+        fee_per_gram,
+        output_count,
+    )
+}
+
+/// Search for a set of outputs whose total covers the amount and fee without needing a change output, i.e. the excess
+/// is no more than the fee that a change output would cost, which the builder then adds to the fee. Returns None if no
+/// such set is found within `BRANCH_AND_BOUND_MAX_TRIES` branches.
+fn branch_and_bound_utxos(
+    fee_model: &dyn FeeModel,
+    uo: &[UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    output_count: usize,
+) -> Option<Vec<UnblindedOutput>>
+{
+    let mut outputs = uo.to_vec();
+    outputs.sort_by(|a, b| b.value.cmp(&a.value));
+    // The total value of the outputs from each index onwards, used to prune branches that can never cover the amount
+    let mut remaining = vec![MicroTari::from(0); outputs.len() + 1];
+    for i in (0..outputs.len()).rev() {
+        remaining[i] = remaining[i + 1] + outputs[i].value;
+    }
+
+    let mut search = BranchAndBoundSearch {
+        fee_model,
+        outputs: &outputs,
+        remaining,
+        amount,
+        fee_per_gram,
+        output_count,
+        selected: Vec::new(),
+        tries: 0,
+    };
+    if search.search(0, MicroTari::from(0)) {
+        Some(search.selected.iter().map(|i| outputs[*i].clone()).collect())
+    } else {
+        None
+    }
+}
+
+struct BranchAndBoundSearch<'a> {
+    fee_model: &'a dyn FeeModel,
+    outputs: &'a [UnblindedOutput],
+    remaining: Vec<MicroTari>,
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    output_count: usize,
+    selected: Vec<usize>,
+    tries: usize,
+}
+
+impl BranchAndBoundSearch<'_> {
+    /// Depth first search that either includes or excludes the output at `index`, largest outputs first
+    fn search(&mut self, index: usize, total: MicroTari) -> bool {
+        self.tries += 1;
+        if self.tries > BRANCH_AND_BOUND_MAX_TRIES {
+            return false;
+        }
+
+        if !self.selected.is_empty() {
+            let fee_without_change =
+                self.fee_model
+                    .calculate(self.fee_per_gram, 1, self.selected.len(), self.output_count);
+            let fee_with_change =
+                self.fee_model
+                    .calculate(self.fee_per_gram, 1, self.selected.len(), self.output_count + 1);
+            let target = self.amount + fee_without_change;
+            if total >= target {
+                // Adding more outputs only increases the excess, so this branch ends here either way
+                return total - target <= fee_with_change - fee_without_change;
+            }
+        }
+
+        // The fee for one more input is a lower bound on the fee of any selection extending this one
+        let min_fee = self
+            .fee_model
+            .calculate(self.fee_per_gram, 1, self.selected.len() + 1, self.output_count);
+        if index >= self.outputs.len() || total + self.remaining[index] < self.amount + min_fee {
+            return false;
+        }
+
+        self.selected.push(index);
+        if self.search(index + 1, total + self.outputs[index].value) {
+            return true;
+        }
+        self.selected.pop();
+        self.search(index + 1, total)
+    }
+}
+
+/// The retry state of the UTXO query the Output Manager Service sends to the base node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BaseNodeSyncStatus {
+    /// The number of times the current query has timed out and been retried
+    pub retry_count: usize,
+    /// The delay before the scheduled retry is sent, if a retry is waiting
+    pub next_retry_delay: Option<Duration>,
+    /// True once the query timed out after the configured number of retries. A new sync starts the retries over.
+    pub failed: bool,
+}
+
+// The key manager state of a new wallet created from the given seed words
+fn create_from_seed_words(seed_words: &WalletSeed) -> Result<KeyManagerState, OutputManagerError> {
+    let (master_seed, bip39_entropy) = match seed_words {
+        WalletSeed::Tari { words, language } => {
+            let key_manager = match language {
+                Some(language) => KeyManager::<PrivateKey, KeyDigest>::from_mnemonic_with_language(
+                    words,
+                    language,
+                    "".to_string(),
+                    0,
+                )?,
+                None => KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(words, "".to_string(), 0)?,
+            };
+            (key_manager.master_key, None)
+        },
+        WalletSeed::Bip39 {
+            words,
+            language,
+            passphrase,
+        } => {
+            let key_manager = KeyManager::<PrivateKey, KeyDigest>::from_bip39_mnemonic(
+                words,
+                language.as_ref(),
+                passphrase,
+                "".to_string(),
+                0,
+            )?;
+            (
+                key_manager.master_key,
+                Some(bip39::to_entropy(words, language.as_ref())?),
+            )
+        },
+    };
+    Ok(KeyManagerState {
+        master_seed,
+        branch_seed: "".to_string(),
+        primary_key_index: 0,
+        branch_indices: HashMap::new(),
+        bip39_entropy,
+    })
+}
+
+// Whether a request leaves the outputs and the chain tip height unchanged, so that a cached balance remains valid. New
+// requests invalidate the cache unless they are listed here.
+fn is_read_only_request(request: &OutputManagerRequest) -> bool {
+    match request {
+        OutputManagerRequest::GetBalance |
+        OutputManagerRequest::GetBalanceAtHeight(_) |
+        OutputManagerRequest::EstimateFee(_) |
+        OutputManagerRequest::GetPendingTransactions |
+        OutputManagerRequest::GetPendingTransaction(_) |
+        OutputManagerRequest::SetPendingTransactionDestination(_) |
+        OutputManagerRequest::GetPendingCoinbases |
+        OutputManagerRequest::GetSpentOutputs |
+        OutputManagerRequest::GetUnspentOutputs |
+        OutputManagerRequest::GetDustOutputs |
+        OutputManagerRequest::GetInvalidOutputs |
+        OutputManagerRequest::GetOutputStatistics |
+        OutputManagerRequest::GetSeedWords(_) |
+        OutputManagerRequest::GetKeyManagerState |
+        OutputManagerRequest::GetBaseNodeSyncStatus |
+        OutputManagerRequest::FetchOutputsByLabel(_) |
+        OutputManagerRequest::GetOutputMetadata |
+        OutputManagerRequest::GetSyncHistory(_) |
+        OutputManagerRequest::GetAbandonedTransactions |
+        OutputManagerRequest::ExportOutputs(_) |
+        OutputManagerRequest::SetBaseNodeQueryTimeout(_) |
+        OutputManagerRequest::GetBaseNodeQueryTimeout |
+        OutputManagerRequest::ReportPeerLatency(_) => true,
+        _ => false,
+    }
+}
+
+/// Requests that derive keys from the master seed or spend outputs, which a read-only wallet cannot service
+fn requires_master_seed(request: &OutputManagerRequest) -> bool {
+    match request {
+        OutputManagerRequest::GetRecipientKey(_) |
+        OutputManagerRequest::GetCoinbaseKey(_) |
+        OutputManagerRequest::GetNextKey(_) |
+        OutputManagerRequest::PrepareToSendTransaction(_) |
+        OutputManagerRequest::PrepareToSendTransactionWithInputs(_) |
+        OutputManagerRequest::PrepareToSendTransactionToMany(_) |
+        OutputManagerRequest::PrepareToSendAll(_) |
+        OutputManagerRequest::ReserveOutputs { .. } |
+        OutputManagerRequest::CreateCoinSplit(_) |
+        OutputManagerRequest::CreateCoinJoin(_) |
+        OutputManagerRequest::CreateBurnTransaction(_) |
+        OutputManagerRequest::GetSeedWords(_) |
+        OutputManagerRequest::GetKeyManagerState |
+        OutputManagerRequest::ScanForOutputsFromSeed(_) => true,
+        _ => false,
+    }
+}
+
+/// The cached balance of the service. The generation is advanced whenever the cache is invalidated, so that a balance
+/// calculated concurrently with a change to the outputs is not cached after the change.
+#[derive(Default)]
+struct BalanceCache {
+    generation: u64,
+    balance: Option<Balance>,
+}
+
+impl BalanceCache {
+    fn invalidate(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.balance = None;
+    }
+}
+
+// The balance at the chain tip height, from the cache if it is valid
+async fn cached_balance<T>(
+    db: &OutputManagerDatabase<T>,
+    balance_cache: &Mutex<BalanceCache>,
+    chain_tip_height: Option<u64>,
+) -> Result<Balance, OutputManagerError>
+where
+    T: OutputManagerBackend + 'static,
+{
+    let generation = {
+        let cache = acquire_lock!(balance_cache);
+        if let Some(balance) = cache.balance.as_ref() {
+            trace!(target: LOG_TARGET, "Balance (cached): {:?}", balance);
+            return Ok(balance.clone());
+        }
+        cache.generation
+    };
+    let balance = db.get_balance(chain_tip_height).await?;
+    trace!(target: LOG_TARGET, "Balance: {:?}", balance);
+    let mut cache = acquire_lock!(balance_cache);
+    if cache.generation == generation {
+        cache.balance = Some(balance.clone());
+    }
+    Ok(balance)
+}
+
+// The delay before a retry, which doubles the backoff for every retry already made
+fn retry_backoff_delay(backoff: Duration, retry_count: usize) -> Duration {
+    let factor = 1u32.checked_shl(retry_count as u32).unwrap_or(u32::MAX);
+    backoff
+        .checked_mul(factor)
+        .unwrap_or_else(|| Duration::from_secs(u64::MAX))
+}
+
+/// A coinbase that was requested for a block that has not been mined yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingCoinbase {
+    pub tx_id: TxId,
+    pub value: MicroTari,
+    /// The height of the block the coinbase was meant for
+    pub block_height: u64,
+    /// The height from which the coinbase can be spent, if its block is won
+    pub maturity_height: u64,
+    /// The estimated time at which the coinbase becomes spendable if its block is won, or `None` if the chain tip
+    /// height has not been received from the base node yet
+    pub estimated_spendable_time: Option<NaiveDateTime>,
+}
+
+/// Statistics of the outputs in each of the collections held by the Output Manager Service
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputStatistics {
+    pub unspent: OutputCollectionStatistics,
+    pub spent: OutputCollectionStatistics,
+    pub pending: OutputCollectionStatistics,
+    pub invalid: OutputCollectionStatistics,
+}
+
+/// The number, total value and value distribution of a collection of outputs. The minimum, maximum and mean are
+/// `None` for an empty collection.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OutputCollectionStatistics {
+    pub count: usize,
+    pub total_value: MicroTari,
+    pub min_value: Option<MicroTari>,
+    pub max_value: Option<MicroTari>,
+    pub mean_value: Option<MicroTari>,
+    /// The number of outputs per order of magnitude of their value, from the smallest up to the largest output
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// The number of outputs with a value of at least `lower_bound` and less than `upper_bound`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBucket {
+    pub lower_bound: MicroTari,
+    pub upper_bound: MicroTari,
+    pub count: usize,
+}
+
+impl OutputCollectionStatistics {
+    pub fn from_values(values: &[MicroTari]) -> Self {
+        let min = match values.iter().min() {
+            Some(min) => *min,
+            None => return Self::default(),
+        };
+        let max = *values.iter().max().expect("values is not empty");
+        let total = values.iter().map(|v| u64::from(*v) as u128).sum::<u128>();
+        let mean = (total / values.len() as u128) as u64;
+
+        // Buckets span a power of ten each, with the first one also holding zero valued outputs
+        let bucket_index = |v: MicroTari| {
+            let mut value = u64::from(v) / 10;
+            let mut index = 0;
+            while value > 0 {
+                value /= 10;
+                index += 1;
+            }
+            index
+        };
+        let bucket_bound = |i: u32| 10u64.checked_pow(i).unwrap_or(u64::MAX);
+        let (first, last) = (bucket_index(min), bucket_index(max));
+        let mut histogram = (first..=last)
+            .map(|i| HistogramBucket {
+                lower_bound: MicroTari::from(if i == 0 { 0 } else { bucket_bound(i) }),
+                upper_bound: MicroTari::from(bucket_bound(i + 1)),
+                count: 0,
+            })
+            .collect::<Vec<_>>();
+        for value in values {
+            histogram[(bucket_index(*value) - first) as usize].count += 1;
+        }
+
+        Self {
+            count: values.len(),
+            total_value: MicroTari::from(total.min(u64::MAX as u128) as u64),
+            min_value: Some(min),
+            max_value: Some(max),
+            mean_value: Some(MicroTari::from(mean)),
+            histogram,
+        }
+    }
 }
 
 /// This struct holds the detailed balance of the Output Manager Service.
@@ -887,13 +3925,19 @@ pub struct Balance {
     pub pending_incoming_balance: MicroTari,
     /// The current balance of funds encumbered in pending outbound transactions that have not been confirmed
     pub pending_outgoing_balance: MicroTari,
+    /// The current balance of unspent funds that cannot be spent until their lock height is reached
+    pub time_locked_balance: MicroTari,
+    /// The current balance of coinbase funds that have not yet reached their maturity height
+    pub unconfirmed_coinbase_balance: MicroTari,
 }
 
 impl fmt::Display for Balance {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Available balance: {}", self.available_balance)?;
         writeln!(f, "Pending incoming balance: {}", self.pending_incoming_balance)?;
-        write!(f, "Pending outgoing balance: {}", self.pending_outgoing_balance)?;
+        writeln!(f, "Pending outgoing balance: {}", self.pending_outgoing_balance)?;
+        writeln!(f, "Time-locked balance: {}", self.time_locked_balance)?;
+        write!(f, "Unconfirmed coinbase balance: {}", self.unconfirmed_coinbase_balance)?;
         Ok(())
     }
 }
@@ -23,15 +23,17 @@
 use crate::output_manager_service::{error::OutputManagerStorageError, service::Balance, TxId};
 use chrono::{NaiveDateTime, Utc};
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fmt::{Display, Error, Formatter},
     sync::Arc,
     time::Duration,
 };
+use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::{OutputFeatures, UnblindedOutput},
+    transaction::{OutputFeatures, OutputFlags, UnblindedOutput},
     types::{BlindingFactor, PrivateKey},
 };
 
@@ -56,9 +58,17 @@ pub trait OutputManagerBackend: Send + Sync {
     fn short_term_encumber_outputs(
         &self,
         tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
         outputs_to_send: &[UnblindedOutput],
         outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>;
+    /// Record the public key of the recipient of a pending outbound transaction against its `PendingTransactionOutputs`
+    fn set_pending_transaction_destination(
+        &self,
+        tx_id: TxId,
+        destination: CommsPublicKey,
+    ) -> Result<(), OutputManagerStorageError>;
     /// This method confirms that a transaction negotiation is complete and outputs can be fully encumbered. This
     /// reserves these outputs until the transaction is confirmed or cancelled
     fn confirm_encumbered_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
@@ -69,15 +79,38 @@ pub trait OutputManagerBackend: Send + Sync {
     /// `UnspentOutputs` pool. The `outputs_to_be_received`'` will be marked as cancelled inbound outputs in case they
     /// need to be recovered.
     fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
+    /// This method must take all the `outputs_to_be_spent` from the specified transaction and move them back into the
+    /// `UnspentOutputs` pool. Unlike a cancellation, the `outputs_to_be_received` are marked as abandoned, which is
+    /// terminal, and the transaction is recorded as abandoned along with the reason.
+    fn abandon_pending_transaction(
+        &self,
+        tx_id: TxId,
+        reason: &AbandonmentReason,
+    ) -> Result<(), OutputManagerStorageError>;
     /// This method must run through all the `PendingTransactionOutputs` and test if any have existed for longer that
     /// the specified duration. If they have they should be cancelled.
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError>;
-    /// This method will increment the currently stored key index for the key manager config. Increment this after each
-    /// key is generated
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError>;
+    /// This method will increment the currently stored key index of the given key manager branch. Increment this after
+    /// each key is generated on that branch
+    fn increment_key_index(&self, branch: KeyManagerBranch) -> Result<(), OutputManagerStorageError>;
+    /// Add a batch of outputs to the `unspent_outputs` collection in a single atomic operation. If any of the outputs
+    /// already exist in the database then none of them are added.
+    fn add_unspent_outputs(&self, outputs: &[UnblindedOutput]) -> Result<(), OutputManagerStorageError>;
     /// If an unspent output is detected as invalid (i.e. not available on the blockchain) then it should be moved to
-    /// the invalid outputs collection
-    fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError>;
+    /// the invalid outputs collection, and the reason it was invalidated recorded with it
+    fn invalidate_unspent_output(
+        &self,
+        output: &UnblindedOutput,
+        reason: &InvalidationReason,
+    ) -> Result<(), OutputManagerStorageError>;
+    /// If an invalid output is found on the blockchain again, e.g. after a reorg, it should be moved back to the
+    /// `unspent_outputs` collection and the reason it was invalidated discarded
+    fn revalidate_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError>;
+    /// Encrypt the spending keys and the key manager state with a key derived from the passphrase. If the stored data
+    /// is already encrypted this unlocks it instead, provided the passphrase matches the one it was encrypted with.
+    fn apply_encryption(&self, passphrase: &str) -> Result<(), OutputManagerStorageError>;
+    /// Decrypt the spending keys and the key manager state. The database must have been unlocked.
+    fn remove_encryption(&self) -> Result<(), OutputManagerStorageError>;
 }
 
 /// Holds the outputs that have been selected for a given pending transaction waiting for confirmation
@@ -87,14 +120,267 @@ pub struct PendingTransactionOutputs {
     pub outputs_to_be_spent: Vec<UnblindedOutput>,
     pub outputs_to_be_received: Vec<UnblindedOutput>,
     pub timestamp: NaiveDateTime,
+    /// The amount paid to the counterparty, or received from it for an inbound transaction
+    pub amount: MicroTari,
+    /// The fee paid by this wallet, which is zero for inbound transactions
+    pub fee: MicroTari,
+    /// The recipient of an outbound transaction, once it is known
+    pub destination: Option<CommsPublicKey>,
+}
+
+/// Records where an output was received from. This is used by the privacy focussed UTXO selection strategy to avoid
+/// linking outputs from different sources, or outputs that have only just been received, in a single transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputSource {
+    pub spending_key: BlindingFactor,
+    /// A label for the source of the output, typically the public key of the counterparty that sent it
+    pub tag: String,
+    pub timestamp: NaiveDateTime,
+}
+
+/// The provenance of an output and the label the user gave it, which wallet frontends display for coin control
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputMetadata {
+    pub spending_key: BlindingFactor,
+    pub label: Option<String>,
+    /// The transaction that created the output
+    pub source_tx_id: Option<TxId>,
+    /// The chain tip height when the transaction that created the output was mined
+    pub discovered_height: Option<u64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl OutputMetadata {
+    pub fn new(spending_key: BlindingFactor) -> Self {
+        let now = Utc::now().naive_utc();
+        Self {
+            spending_key,
+            label: None,
+            source_tx_id: None,
+            discovered_height: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// How a UTXO query sent to the base node ended
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SyncOutcome {
+    /// The base node has not answered every request of the query yet
+    Pending,
+    /// Every request of the query was answered and the outputs that were not returned were invalidated
+    Completed,
+    /// A request of the query timed out and the query was abandoned
+    TimedOut,
+    /// The query was cancelled before the base node answered it
+    Cancelled,
+}
+
+/// A UTXO query sent to the base node to validate the unspent outputs, kept so that users can see when and why
+/// outputs were invalidated
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncHistoryEntry {
+    /// The request key of the query, as published in the sync events and recorded in the invalidation reasons
+    pub request_key: u64,
+    pub base_node_public_key: CommsPublicKey,
+    /// The hashes of the unspent outputs that were queried
+    pub output_hashes: Vec<Vec<u8>>,
+    pub sent_at: NaiveDateTime,
+    /// How long the base node took to answer every request of the query
+    pub response_time: Option<Duration>,
+    /// The hashes of the queried outputs that the base node did not return and that were invalidated
+    pub invalidated_hashes: Vec<Vec<u8>>,
+    pub outcome: SyncOutcome,
+}
+
+/// Why an output was moved to the invalid outputs collection
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidationReason {
+    /// The output was not returned by the base node in response to a UTXO query. This happens if the output was
+    /// never mined.
+    NotReturnedByBaseNode {
+        request_key: u64,
+        base_node_public_key: String,
+        /// The chain tip height last reported by the base node when the output was invalidated
+        chain_tip_height: Option<u64>,
+    },
+    /// The output was mined, but the base node no longer returned it in response to a UTXO query because the block it
+    /// was mined in was removed from the main chain by a reorg
+    RemovedByReorg {
+        request_key: u64,
+        base_node_public_key: String,
+        /// The chain tip height when the transaction that created the output was mined
+        mined_height: u64,
+        chain_tip_height: Option<u64>,
+    },
+    /// The output was imported from an output export, but the base node did not return it in response to the UTXO
+    /// query that validated the import. This happens if the output was spent or never mined on this network.
+    ImportFailed {
+        request_key: u64,
+        base_node_public_key: String,
+        chain_tip_height: Option<u64>,
+    },
+}
+
+impl Display for InvalidationReason {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            InvalidationReason::NotReturnedByBaseNode {
+                request_key,
+                base_node_public_key,
+                chain_tip_height,
+            } => write!(
+                f,
+                "Not returned in UTXO query {} by base node {} at height {}",
+                request_key,
+                base_node_public_key,
+                display_height(chain_tip_height)
+            ),
+            InvalidationReason::RemovedByReorg {
+                request_key,
+                base_node_public_key,
+                mined_height,
+                chain_tip_height,
+            } => write!(
+                f,
+                "Mined at height {} but removed by a reorg, not returned in UTXO query {} by base node {} at height {}",
+                mined_height,
+                request_key,
+                base_node_public_key,
+                display_height(chain_tip_height)
+            ),
+            InvalidationReason::ImportFailed {
+                request_key,
+                base_node_public_key,
+                chain_tip_height,
+            } => write!(
+                f,
+                "Imported but not returned in UTXO query {} by base node {} at height {}",
+                request_key,
+                base_node_public_key,
+                display_height(chain_tip_height)
+            ),
+        }
+    }
+}
+
+fn display_height(height: &Option<u64>) -> String {
+    height.map(|h| h.to_string()).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Why the outputs of a pending transaction were abandoned
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AbandonmentReason {
+    /// The counterparty cancelled the transaction
+    CancelledByCounterparty,
+    /// The base node rejected the transaction, so it can never be mined
+    RejectedByBaseNode { reason: String },
+}
+
+impl Display for AbandonmentReason {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            AbandonmentReason::CancelledByCounterparty => f.write_str("Cancelled by the counterparty"),
+            AbandonmentReason::RejectedByBaseNode { reason } => write!(f, "Rejected by the base node ({})", reason),
+        }
+    }
+}
+
+/// A pending transaction that was abandoned. The outputs it would have created are never returned to the unspent pool
+/// and their spending keys are never used again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbandonedTransaction {
+    pub tx_id: TxId,
+    pub outputs: Vec<UnblindedOutput>,
+    pub reason: AbandonmentReason,
+    pub timestamp: NaiveDateTime,
+}
+
+/// An output in the invalid outputs collection along with why and when it was invalidated
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidOutput {
+    pub output: UnblindedOutput,
+    /// The reason is `None` for outputs that were invalidated before reasons were recorded
+    pub reason: Option<InvalidationReason>,
+    pub timestamp: Option<NaiveDateTime>,
 }
 
 /// Holds the state of the KeyManager being used by the Output Manager Service
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyManagerState {
     pub master_seed: PrivateKey,
+    /// The branch seed and key index of the spending branch
     pub branch_seed: String,
     pub primary_key_index: usize,
+    /// The key indices of the other branches. A branch that is not present has not had any keys derived on it yet.
+    pub branch_indices: HashMap<KeyManagerBranch, usize>,
+    /// The entropy of the BIP-39 seed words the master seed was derived from. It is `None` when the master seed is
+    /// encoded by Tari seed words.
+    pub bip39_entropy: Option<Vec<u8>>,
+}
+
+impl KeyManagerState {
+    /// The seed the keys of the given branch are derived with
+    pub fn branch_seed(&self, branch: KeyManagerBranch) -> String {
+        match branch {
+            KeyManagerBranch::Spending => self.branch_seed.clone(),
+            _ => branch.to_string(),
+        }
+    }
+
+    /// The index of the last key derived on the given branch
+    pub fn key_index(&self, branch: KeyManagerBranch) -> usize {
+        match branch {
+            KeyManagerBranch::Spending => self.primary_key_index,
+            _ => self.branch_indices.get(&branch).cloned().unwrap_or(0),
+        }
+    }
+
+    /// Set the index of the last key derived on the given branch
+    pub fn set_key_index(&mut self, branch: KeyManagerBranch, index: usize) {
+        match branch {
+            KeyManagerBranch::Spending => self.primary_key_index = index,
+            _ => {
+                self.branch_indices.insert(branch, index);
+            },
+        }
+    }
+}
+
+/// The branches of the key manager that keys are derived on. Each branch has its own key index so that keys derived for
+/// different purposes do not share an index space, and each branch can be scanned independently during recovery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyManagerBranch {
+    /// Keys for payments received and change outputs. This is the branch all keys were derived on before branches were
+    /// introduced, so it keeps the branch seed stored in the `KeyManagerState`.
+    Spending,
+    /// Keys for coinbase outputs
+    Coinbase,
+    /// Keys for one-sided payments
+    OneSided,
+}
+
+impl KeyManagerBranch {
+    /// All the branches, in the order they are scanned during recovery
+    pub fn all() -> Vec<KeyManagerBranch> {
+        vec![
+            KeyManagerBranch::Spending,
+            KeyManagerBranch::Coinbase,
+            KeyManagerBranch::OneSided,
+        ]
+    }
+}
+
+impl Display for KeyManagerBranch {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            KeyManagerBranch::Spending => f.write_str("spending"),
+            KeyManagerBranch::Coinbase => f.write_str("coinbase"),
+            KeyManagerBranch::OneSided => f.write_str("one-sided"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -107,6 +393,11 @@ pub enum DbKey {
     AllPendingTransactionOutputs,
     KeyManagerState,
     InvalidOutputs,
+    OutputSources,
+    OutputMetadata,
+    SyncHistoryEntry(u64),
+    SyncHistory,
+    AbandonedTransactions,
 }
 
 #[derive(Debug)]
@@ -116,9 +407,14 @@ pub enum DbValue {
     PendingTransactionOutputs(Box<PendingTransactionOutputs>),
     UnspentOutputs(Vec<UnblindedOutput>),
     SpentOutputs(Vec<UnblindedOutput>),
-    InvalidOutputs(Vec<UnblindedOutput>),
+    InvalidOutputs(Vec<InvalidOutput>),
     AllPendingTransactionOutputs(HashMap<TxId, PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    OutputSources(Vec<OutputSource>),
+    OutputMetadata(Vec<OutputMetadata>),
+    SyncHistoryEntry(Box<SyncHistoryEntry>),
+    SyncHistory(Vec<SyncHistoryEntry>),
+    AbandonedTransactions(Vec<AbandonedTransaction>),
 }
 
 pub enum DbKeyValuePair {
@@ -126,6 +422,12 @@ pub enum DbKeyValuePair {
     UnspentOutput(BlindingFactor, Box<UnblindedOutput>),
     PendingTransactionOutputs(TxId, Box<PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    /// Inserting an output source replaces any existing source recorded for the same output
+    OutputSource(BlindingFactor, Box<OutputSource>),
+    /// Inserting output metadata replaces any existing metadata recorded for the same output
+    OutputMetadata(BlindingFactor, Box<OutputMetadata>),
+    /// Inserting a sync history entry replaces any existing entry recorded for the same request key
+    SyncHistoryEntry(u64, Box<SyncHistoryEntry>),
 }
 
 pub enum WriteOperation {
@@ -154,6 +456,14 @@ where T: OutputManagerBackend + 'static
     db: Arc<T>,
 }
 
+impl<T> Clone for OutputManagerDatabase<T>
+where T: OutputManagerBackend + 'static
+{
+    fn clone(&self) -> Self {
+        Self { db: self.db.clone() }
+    }
+}
+
 impl<T> OutputManagerDatabase<T>
 where T: OutputManagerBackend + 'static
 {
@@ -185,9 +495,9 @@ where T: OutputManagerBackend + 'static
         Ok(())
     }
 
-    pub async fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
+    pub async fn increment_key_index(&self, branch: KeyManagerBranch) -> Result<(), OutputManagerStorageError> {
         let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.increment_key_index())
+        tokio::task::spawn_blocking(move || db_clone.increment_key_index(branch))
             .await
             .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
         Ok(())
@@ -207,7 +517,18 @@ where T: OutputManagerBackend + 'static
         Ok(())
     }
 
-    pub async fn get_balance(&self) -> Result<Balance, OutputManagerStorageError> {
+    pub async fn add_unspent_outputs(&self, outputs: Vec<UnblindedOutput>) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.add_unspent_outputs(&outputs))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+
+        Ok(())
+    }
+
+    /// Calculate the balance of the wallet. If the current chain tip height is provided, unspent outputs that cannot
+    /// be spent at that height are reported as time-locked or immature coinbase funds instead of as available funds.
+    pub async fn get_balance(&self, current_chain_tip: Option<u64>) -> Result<Balance, OutputManagerStorageError> {
         let db_clone = self.db.clone();
         let db_clone2 = self.db.clone();
 
@@ -230,7 +551,21 @@ where T: OutputManagerBackend + 'static
         .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
         if let DbValue::UnspentOutputs(uo) = unspent_outputs {
             if let DbValue::AllPendingTransactionOutputs(pto) = pending_txs {
-                let available_balance = uo.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+                let mut available_balance = MicroTari::from(0);
+                let mut time_locked_balance = MicroTari::from(0);
+                let mut unconfirmed_coinbase_balance = MicroTari::from(0);
+                for o in uo.iter() {
+                    match current_chain_tip {
+                        Some(height) if o.features.maturity > height => {
+                            if o.features.flags.contains(OutputFlags::COINBASE_OUTPUT) {
+                                unconfirmed_coinbase_balance += o.value;
+                            } else {
+                                time_locked_balance += o.value;
+                            }
+                        },
+                        _ => available_balance += o.value,
+                    }
+                }
                 let mut pending_incoming = MicroTari::from(0);
                 let mut pending_outgoing = MicroTari::from(0);
 
@@ -249,6 +584,8 @@ where T: OutputManagerBackend + 'static
                     available_balance,
                     pending_incoming_balance: pending_incoming,
                     pending_outgoing_balance: pending_outgoing,
+                    time_locked_balance,
+                    unconfirmed_coinbase_balance,
                 });
             }
         }
@@ -322,6 +659,9 @@ where T: OutputManagerBackend + 'static
                         features: output_features,
                     }],
                     timestamp: Utc::now().naive_utc(),
+                    amount,
+                    fee: MicroTari::from(0),
+                    destination: None,
                 }),
             )))
         })
@@ -331,17 +671,19 @@ where T: OutputManagerBackend + 'static
     }
 
     /// This method is called when a transaction is built to be sent. It will encumber unspent outputs against a pending
-    /// transaction in the short term.
+    /// transaction in the short term, recording the amount being sent and the fee it pays.
     pub async fn encumber_outputs(
         &self,
         tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
         outputs_to_send: Vec<UnblindedOutput>,
         outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>
     {
         let db_clone = self.db.clone();
         tokio::task::spawn_blocking(move || {
-            db_clone.short_term_encumber_outputs(tx_id, &outputs_to_send, &outputs_to_receive)
+            db_clone.short_term_encumber_outputs(tx_id, amount, fee, &outputs_to_send, &outputs_to_receive)
         })
         .await
         .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
@@ -378,6 +720,37 @@ where T: OutputManagerBackend + 'static
             .and_then(|inner_result| inner_result)
     }
 
+    /// When a pending transaction is abandoned the encumbered outputs are moved back to the `unspent_outputs`
+    /// collection and the outputs it would have created are marked as abandoned.
+    pub async fn abandon_pending_transaction_outputs(
+        &self,
+        tx_id: TxId,
+        reason: AbandonmentReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.abandon_pending_transaction(tx_id, &reason))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn fetch_abandoned_transactions(&self) -> Result<Vec<AbandonedTransaction>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::AbandonedTransactions) {
+            Ok(None) => log_error(
+                DbKey::AbandonedTransactions,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve abandoned transactions".to_string()),
+            ),
+            Ok(Some(DbValue::AbandonedTransactions(t))) => Ok(t),
+            Ok(Some(other)) => unexpected_result(DbKey::AbandonedTransactions, other),
+            Err(e) => log_error(DbKey::AbandonedTransactions, e),
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
     /// This method is check all pending transactions to see if any are older that the provided duration. If they are
     /// they will be cancelled.
     pub async fn timeout_pending_transaction_outputs(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
@@ -462,7 +835,7 @@ where T: OutputManagerBackend + 'static
         Ok(uo)
     }
 
-    pub async fn get_invalid_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
+    pub async fn get_invalid_outputs(&self) -> Result<Vec<InvalidOutput>, OutputManagerStorageError> {
         let db_clone = self.db.clone();
 
         let uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::InvalidOutputs) {
@@ -479,13 +852,237 @@ where T: OutputManagerBackend + 'static
         Ok(uo)
     }
 
-    pub async fn invalidate_output(&self, output: UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+    /// Record the source of the outputs that will be received in the specified pending transaction
+    pub async fn set_pending_transaction_output_source(
+        &self,
+        tx_id: TxId,
+        tag: String,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let pending_tx = self.fetch_pending_transaction_outputs(tx_id).await?;
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let timestamp = Utc::now().naive_utc();
+            for o in pending_tx.outputs_to_be_received {
+                db_clone.write(WriteOperation::Insert(DbKeyValuePair::OutputSource(
+                    o.spending_key.clone(),
+                    Box::new(OutputSource {
+                        spending_key: o.spending_key,
+                        tag: tag.clone(),
+                        timestamp,
+                    }),
+                )))?;
+            }
+            Ok(())
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn set_pending_transaction_destination(
+        &self,
+        tx_id: TxId,
+        destination: CommsPublicKey,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.set_pending_transaction_destination(tx_id, destination))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn fetch_output_sources(&self) -> Result<Vec<OutputSource>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+
+        let sources = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::OutputSources) {
+            Ok(None) => log_error(
+                DbKey::OutputSources,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve output sources".to_string()),
+            ),
+            Ok(Some(DbValue::OutputSources(s))) => Ok(s),
+            Ok(Some(other)) => unexpected_result(DbKey::OutputSources, other),
+            Err(e) => log_error(DbKey::OutputSources, e),
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(sources)
+    }
+
+    /// Record a UTXO query in the sync history, replacing the entry recorded for it before. Only the newest
+    /// `max_entries` queries are kept.
+    pub async fn record_sync_history_entry(
+        &self,
+        entry: SyncHistoryEntry,
+        max_entries: usize,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::SyncHistoryEntry(
+                entry.request_key,
+                Box::new(entry),
+            )))?;
+            for old_entry in fetch_sorted_sync_history(&*db_clone)?.into_iter().skip(max_entries) {
+                db_clone.write(WriteOperation::Remove(DbKey::SyncHistoryEntry(old_entry.request_key)))?;
+            }
+            Ok(())
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    /// The newest `limit` UTXO queries in the sync history, newest first
+    pub async fn fetch_sync_history(&self, limit: usize) -> Result<Vec<SyncHistoryEntry>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+
+        let mut history = tokio::task::spawn_blocking(move || fetch_sorted_sync_history(&*db_clone))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        history.truncate(limit);
+        Ok(history)
+    }
+
+    pub async fn fetch_output_metadata(&self) -> Result<Vec<OutputMetadata>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+
+        let metadata = tokio::task::spawn_blocking(move || fetch_all_output_metadata(&*db_clone))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(metadata)
+    }
+
+    /// Update the metadata of the outputs with the given spending keys, creating it for outputs that have none yet.
+    /// The label and creation time of existing metadata are kept.
+    pub async fn update_output_metadata(
+        &self,
+        spending_keys: Vec<BlindingFactor>,
+        source_tx_id: Option<TxId>,
+        discovered_height: Option<u64>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut existing = fetch_all_output_metadata(&*db_clone)?;
+            let now = Utc::now().naive_utc();
+            for k in spending_keys {
+                let mut metadata = match existing.iter().position(|m| m.spending_key == k) {
+                    Some(pos) => existing.remove(pos),
+                    None => OutputMetadata::new(k.clone()),
+                };
+                if source_tx_id.is_some() {
+                    metadata.source_tx_id = source_tx_id;
+                }
+                if discovered_height.is_some() {
+                    metadata.discovered_height = discovered_height;
+                }
+                metadata.updated_at = now;
+                db_clone.write(WriteOperation::Insert(DbKeyValuePair::OutputMetadata(
+                    k,
+                    Box::new(metadata),
+                )))?;
+            }
+            Ok(())
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    /// Set or clear the label of an output, creating its metadata if it has none yet
+    pub async fn set_output_label(
+        &self,
+        spending_key: BlindingFactor,
+        label: Option<String>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut metadata = fetch_all_output_metadata(&*db_clone)?
+                .into_iter()
+                .find(|m| m.spending_key == spending_key)
+                .unwrap_or_else(|| OutputMetadata::new(spending_key.clone()));
+            metadata.label = label;
+            metadata.updated_at = Utc::now().naive_utc();
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::OutputMetadata(
+                spending_key,
+                Box::new(metadata),
+            )))?;
+            Ok(())
+        })
+        .await
+        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn invalidate_output(
+        &self,
+        output: UnblindedOutput,
+        reason: InvalidationReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.invalidate_unspent_output(&output, &reason))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn revalidate_output(&self, output: UnblindedOutput) -> Result<(), OutputManagerStorageError> {
         let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.invalidate_unspent_output(&output))
+        tokio::task::spawn_blocking(move || db_clone.revalidate_output(&output))
             .await
             .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
             .and_then(|inner_result| inner_result)
     }
+
+    pub async fn apply_encryption(&self, passphrase: String) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.apply_encryption(&passphrase))
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+            .and_then(|inner_result| inner_result)
+    }
+
+    pub async fn remove_encryption(&self) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.remove_encryption())
+            .await
+            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
+            .and_then(|inner_result| inner_result)
+    }
+}
+
+fn fetch_all_output_metadata<T: OutputManagerBackend>(
+    db: &T,
+) -> Result<Vec<OutputMetadata>, OutputManagerStorageError> {
+    match db.fetch(&DbKey::OutputMetadata) {
+        Ok(None) => log_error(
+            DbKey::OutputMetadata,
+            OutputManagerStorageError::UnexpectedResult("Could not retrieve output metadata".to_string()),
+        ),
+        Ok(Some(DbValue::OutputMetadata(m))) => Ok(m),
+        Ok(Some(other)) => unexpected_result(DbKey::OutputMetadata, other),
+        Err(e) => log_error(DbKey::OutputMetadata, e),
+    }
+}
+
+fn fetch_sorted_sync_history<T: OutputManagerBackend>(
+    db: &T,
+) -> Result<Vec<SyncHistoryEntry>, OutputManagerStorageError> {
+    let mut history = match db.fetch(&DbKey::SyncHistory) {
+        Ok(None) => log_error(
+            DbKey::SyncHistory,
+            OutputManagerStorageError::UnexpectedResult("Could not retrieve sync history".to_string()),
+        ),
+        Ok(Some(DbValue::SyncHistory(h))) => Ok(h),
+        Ok(Some(other)) => unexpected_result(DbKey::SyncHistory, other),
+        Err(e) => log_error(DbKey::SyncHistory, e),
+    }?;
+    history.sort_by(|a, b| b.sent_at.cmp(&a.sent_at));
+    Ok(history)
 }
 
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, OutputManagerStorageError> {
@@ -507,6 +1104,11 @@ impl Display for DbKey {
             DbKey::AllPendingTransactionOutputs => f.write_str(&"All Pending Transaction Outputs".to_string()),
             DbKey::KeyManagerState => f.write_str(&"Key Manager State".to_string()),
             DbKey::InvalidOutputs => f.write_str(&"Invalid Outputs Key"),
+            DbKey::OutputSources => f.write_str(&"Output Sources Key"),
+            DbKey::OutputMetadata => f.write_str(&"Output Metadata Key"),
+            DbKey::SyncHistoryEntry(request_key) => f.write_str(&format!("Sync History Entry: {}", request_key)),
+            DbKey::SyncHistory => f.write_str(&"Sync History Key"),
+            DbKey::AbandonedTransactions => f.write_str(&"Abandoned Transactions Key"),
         }
     }
 }
@@ -522,6 +1124,11 @@ impl Display for DbValue {
             DbValue::AllPendingTransactionOutputs(_) => f.write_str("All Pending Transaction Outputs"),
             DbValue::KeyManagerState(_) => f.write_str("Key Manager State"),
             DbValue::InvalidOutputs(_) => f.write_str("Invalid Outputs"),
+            DbValue::OutputSources(_) => f.write_str("Output Sources"),
+            DbValue::OutputMetadata(_) => f.write_str("Output Metadata"),
+            DbValue::SyncHistoryEntry(_) => f.write_str("Sync History Entry"),
+            DbValue::SyncHistory(_) => f.write_str("Sync History"),
+            DbValue::AbandonedTransactions(_) => f.write_str("Abandoned Transactions"),
         }
     }
 }
@@ -23,23 +23,33 @@
 use crate::output_manager_service::{
     error::OutputManagerStorageError,
     storage::database::{
+        AbandonedTransaction,
+        AbandonmentReason,
         DbKey,
         DbKeyValuePair,
         DbValue,
+        InvalidOutput,
+        InvalidationReason,
+        KeyManagerBranch,
         KeyManagerState,
         OutputManagerBackend,
+        OutputMetadata,
+        OutputSource,
         PendingTransactionOutputs,
+        SyncHistoryEntry,
         WriteOperation,
     },
     TxId,
 };
 use chrono::{Duration as ChronoDuration, Utc};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
     time::Duration,
 };
-use tari_core::transactions::transaction::UnblindedOutput;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{tari_amount::MicroTari, transaction::UnblindedOutput};
+use tari_crypto::tari_utilities::ByteArray;
 
 /// This structure is an In-Memory database backend that implements the `OutputManagerBackend` trait and provides all
 /// the functionality required by the trait.
@@ -47,10 +57,16 @@ use tari_core::transactions::transaction::UnblindedOutput;
 pub struct InnerDatabase {
     unspent_outputs: Vec<UnblindedOutput>,
     spent_outputs: Vec<UnblindedOutput>,
-    invalid_outputs: Vec<UnblindedOutput>,
+    invalid_outputs: Vec<InvalidOutput>,
     pending_transactions: HashMap<TxId, PendingTransactionOutputs>,
     short_term_pending_transactions: HashMap<TxId, PendingTransactionOutputs>,
     key_manager_state: Option<KeyManagerState>,
+    output_sources: Vec<OutputSource>,
+    output_metadata: Vec<OutputMetadata>,
+    sync_history: Vec<SyncHistoryEntry>,
+    abandoned_transactions: Vec<AbandonedTransaction>,
+    // Nothing is stored at rest so only whether encryption was applied is tracked
+    encrypted: bool,
 }
 
 impl InnerDatabase {
@@ -62,6 +78,11 @@ impl InnerDatabase {
             pending_transactions: HashMap::new(),
             short_term_pending_transactions: Default::default(),
             key_manager_state: None,
+            output_sources: Vec::new(),
+            output_metadata: Vec::new(),
+            sync_history: Vec::new(),
+            abandoned_transactions: Vec::new(),
+            encrypted: false,
         }
     }
 }
@@ -114,6 +135,15 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 .as_ref()
                 .map(|km| DbValue::KeyManagerState(km.clone())),
             DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(db.invalid_outputs.clone())),
+            DbKey::OutputSources => Some(DbValue::OutputSources(db.output_sources.clone())),
+            DbKey::OutputMetadata => Some(DbValue::OutputMetadata(db.output_metadata.clone())),
+            DbKey::SyncHistoryEntry(request_key) => db
+                .sync_history
+                .iter()
+                .find(|e| e.request_key == *request_key)
+                .map(|e| DbValue::SyncHistoryEntry(Box::new(e.clone()))),
+            DbKey::SyncHistory => Some(DbValue::SyncHistory(db.sync_history.clone())),
+            DbKey::AbandonedTransactions => Some(DbValue::AbandonedTransactions(db.abandoned_transactions.clone())),
         };
 
         Ok(result)
@@ -133,7 +163,8 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 },
                 DbKeyValuePair::UnspentOutput(k, o) => {
                     if db.unspent_outputs.iter().any(|v| v.spending_key == k) ||
-                        db.spent_outputs.iter().any(|v| v.spending_key == k)
+                        db.spent_outputs.iter().any(|v| v.spending_key == k) ||
+                        db.invalid_outputs.iter().any(|v| v.output.spending_key == k)
                     {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
@@ -143,6 +174,18 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                     db.short_term_pending_transactions.insert(t, *p);
                 },
                 DbKeyValuePair::KeyManagerState(km) => db.key_manager_state = Some(km),
+                DbKeyValuePair::OutputSource(k, s) => {
+                    db.output_sources.retain(|v| v.spending_key != k);
+                    db.output_sources.push(*s);
+                },
+                DbKeyValuePair::OutputMetadata(k, m) => {
+                    db.output_metadata.retain(|v| v.spending_key != k);
+                    db.output_metadata.push(*m);
+                },
+                DbKeyValuePair::SyncHistoryEntry(k, e) => {
+                    db.sync_history.retain(|v| v.request_key != k);
+                    db.sync_history.push(*e);
+                },
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::SpentOutput(k) => match db.spent_outputs.iter().position(|v| v.spending_key == k) {
@@ -171,6 +214,16 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::OutputSources => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::OutputMetadata => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SyncHistoryEntry(k) => match db.sync_history.iter().position(|v| v.request_key == k) {
+                    None => return Err(OutputManagerStorageError::ValueNotFound(DbKey::SyncHistoryEntry(k))),
+                    Some(pos) => {
+                        return Ok(Some(DbValue::SyncHistoryEntry(Box::new(db.sync_history.remove(pos)))));
+                    },
+                },
+                DbKey::SyncHistory => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::AbandonedTransactions => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
         Ok(None)
@@ -203,6 +256,8 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
     fn short_term_encumber_outputs(
         &self,
         tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
         outputs_to_send: &[UnblindedOutput],
         outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>
@@ -222,6 +277,9 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
             outputs_to_be_spent,
             outputs_to_be_received: Vec::new(),
             timestamp: Utc::now().naive_utc(),
+            amount,
+            fee,
+            destination: None,
         };
 
         for co in outputs_to_receive {
@@ -246,6 +304,26 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
         Ok(())
     }
 
+    fn set_pending_transaction_destination(
+        &self,
+        tx_id: TxId,
+        destination: CommsPublicKey,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let mut db = acquire_write_lock!(self.db);
+
+        let pending_tx = if db.short_term_pending_transactions.contains_key(&tx_id) {
+            db.short_term_pending_transactions.get_mut(&tx_id)
+        } else {
+            db.pending_transactions.get_mut(&tx_id)
+        };
+        pending_tx
+            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?
+            .destination = Some(destination);
+
+        Ok(())
+    }
+
     fn clear_short_term_encumberances(&self) -> Result<(), OutputManagerStorageError> {
         let db = acquire_write_lock!(self.db);
 
@@ -277,6 +355,35 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
         Ok(())
     }
 
+    fn abandon_pending_transaction(
+        &self,
+        tx_id: TxId,
+        reason: &AbandonmentReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let mut db = acquire_write_lock!(self.db);
+        let mut pending_tx = db.pending_transactions.remove(&tx_id);
+
+        if pending_tx.is_none() {
+            pending_tx = db.short_term_pending_transactions.remove(&tx_id);
+        }
+
+        let mut pending_tx = pending_tx
+            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
+
+        for o in pending_tx.outputs_to_be_spent.drain(..) {
+            db.unspent_outputs.push(o);
+        }
+        db.abandoned_transactions.push(AbandonedTransaction {
+            tx_id,
+            outputs: pending_tx.outputs_to_be_received,
+            reason: reason.clone(),
+            timestamp: Utc::now().naive_utc(),
+        });
+
+        Ok(())
+    }
+
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
         let db = acquire_write_lock!(self.db);
         let mut transactions_to_be_cancelled = Vec::new();
@@ -300,7 +407,30 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
         Ok(())
     }
 
-    fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+    fn add_unspent_outputs(&self, outputs: &[UnblindedOutput]) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        let mut keys = HashSet::new();
+        for o in outputs.iter() {
+            if !keys.insert(o.spending_key.to_vec()) ||
+                db.unspent_outputs.iter().any(|v| v.spending_key == o.spending_key) ||
+                db.spent_outputs.iter().any(|v| v.spending_key == o.spending_key) ||
+                db.invalid_outputs
+                    .iter()
+                    .any(|v| v.output.spending_key == o.spending_key)
+            {
+                return Err(OutputManagerStorageError::DuplicateOutput);
+            }
+        }
+        db.unspent_outputs.extend_from_slice(outputs);
+        Ok(())
+    }
+
+    fn invalidate_unspent_output(
+        &self,
+        output: &UnblindedOutput,
+        reason: &InvalidationReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
         let mut db = acquire_write_lock!(self.db);
         match db
             .unspent_outputs
@@ -309,24 +439,62 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
         {
             Some(pos) => {
                 let output = db.unspent_outputs.remove(pos);
-                db.invalid_outputs.push(output);
+                db.invalid_outputs.push(InvalidOutput {
+                    output,
+                    reason: Some(reason.clone()),
+                    timestamp: Some(Utc::now().naive_utc()),
+                });
             },
             None => return Err(OutputManagerStorageError::ValuesNotFound),
         }
         Ok(())
     }
 
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
+    fn revalidate_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
         let mut db = acquire_write_lock!(self.db);
+        match db
+            .invalid_outputs
+            .iter()
+            .position(|v| v.output.spending_key == output.spending_key)
+        {
+            Some(pos) => {
+                let invalid_output = db.invalid_outputs.remove(pos);
+                db.unspent_outputs.push(invalid_output.output);
+            },
+            None => return Err(OutputManagerStorageError::ValuesNotFound),
+        }
+        Ok(())
+    }
 
-        if db.key_manager_state.is_none() {
-            return Err(OutputManagerStorageError::KeyManagerNotInitialized);
+    fn increment_key_index(&self, branch: KeyManagerBranch) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+
+        match db.key_manager_state.as_mut() {
+            Some(state) => {
+                let index = state.key_index(branch) + 1;
+                state.set_key_index(branch, index);
+            },
+            None => return Err(OutputManagerStorageError::KeyManagerNotInitialized),
         }
-        db.key_manager_state = db.key_manager_state.clone().map(|mut state| {
-            state.primary_key_index += 1;
-            state
-        });
 
         Ok(())
     }
+
+    fn apply_encryption(&self, _passphrase: &str) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        if db.encrypted {
+            return Err(OutputManagerStorageError::AlreadyEncrypted);
+        }
+        db.encrypted = true;
+        Ok(())
+    }
+
+    fn remove_encryption(&self) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        if !db.encrypted {
+            return Err(OutputManagerStorageError::NotEncrypted);
+        }
+        db.encrypted = false;
+        Ok(())
+    }
 }
@@ -24,75 +24,170 @@ use crate::{
     output_manager_service::{
         error::OutputManagerStorageError,
         storage::database::{
+            AbandonedTransaction,
+            AbandonmentReason,
             DbKey,
             DbKeyValuePair,
             DbValue,
+            InvalidOutput,
+            InvalidationReason,
+            KeyManagerBranch,
             KeyManagerState,
             OutputManagerBackend,
+            OutputMetadata,
+            OutputSource,
             PendingTransactionOutputs,
+            SyncHistoryEntry,
             WriteOperation,
         },
         TxId,
     },
-    schema::{key_manager_states, outputs, pending_transaction_outputs},
+    schema::{
+        abandoned_transactions,
+        key_manager_states,
+        output_invalidations,
+        output_manager_encryption,
+        output_metadata,
+        output_sources,
+        outputs,
+        pending_transaction_outputs,
+        sync_history,
+    },
+    storage::connection_manager::WalletDbConnection,
+    util::encryption::DatabaseCipher,
 };
 use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 #[cfg(test)]
 use diesel::expression::dsl::not;
 use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
-    sync::{Arc, Mutex},
+    sync::{Arc, RwLock},
     time::Duration,
 };
+use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, OutputFlags, UnblindedOutput},
     types::PrivateKey,
 };
-use tari_crypto::tari_utilities::ByteArray;
+use tari_crypto::tari_utilities::{
+    hex::{from_hex, to_hex},
+    ByteArray,
+};
+
+/// The maximum number of bound parameters Sqlite accepts in a single statement by default
+const MAX_SQL_PARAMETERS: usize = 999;
+
+/// A known value stored encrypted alongside the encrypted data, which is used to check a passphrase
+const ENCRYPTION_CHECK_VALUE: &[u8] = b"tari output manager";
 
 /// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
 #[derive(Clone)]
 pub struct OutputManagerSqliteDatabase {
-    database_connection: Arc<Mutex<SqliteConnection>>,
+    database_connection: WalletDbConnection,
+    // The cipher the spending keys and key manager state are encrypted with, once the database has been unlocked
+    cipher: Arc<RwLock<Option<DatabaseCipher>>>,
 }
 impl OutputManagerSqliteDatabase {
-    pub fn new(database_connection: Arc<Mutex<SqliteConnection>>) -> Self {
-        Self { database_connection }
+    pub fn new(database_connection: WalletDbConnection) -> Self {
+        Self {
+            database_connection,
+            cipher: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// The cipher to encrypt and decrypt values with, or `None` if the database is not encrypted. Fails if the database
+    /// is encrypted but has not been unlocked.
+    fn cipher(&self, conn: &SqliteConnection) -> Result<Option<DatabaseCipher>, OutputManagerStorageError> {
+        let cipher = acquire_read_lock!(self.cipher).clone();
+        if cipher.is_none() && OutputManagerEncryptionSql::get(conn)?.is_some() {
+            return Err(OutputManagerStorageError::DatabaseLocked);
+        }
+        Ok(cipher)
+    }
+}
+
+/// The bytes a spending key is stored as, which are encrypted once encryption has been applied. Encryption is
+/// deterministic so these can be used to look up the output.
+fn spending_key_bytes(key: &PrivateKey, cipher: &Option<DatabaseCipher>) -> Result<Vec<u8>, OutputManagerStorageError> {
+    match cipher {
+        Some(c) => Ok(c.encrypt(key.as_bytes())?),
+        None => Ok(key.to_vec()),
+    }
+}
+
+/// A row with values that are stored encrypted once encryption has been applied to the database
+trait Encryptable: Sized {
+    fn encrypt(self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError>;
+    fn decrypt(self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError>;
+}
+
+fn encrypt_if_necessary<T: Encryptable>(
+    row: T,
+    cipher: &Option<DatabaseCipher>,
+) -> Result<T, OutputManagerStorageError>
+{
+    match cipher {
+        Some(c) => row.encrypt(c),
+        None => Ok(row),
     }
 }
+
+fn decrypt_if_necessary<T: Encryptable>(
+    row: T,
+    cipher: &Option<DatabaseCipher>,
+) -> Result<T, OutputManagerStorageError>
+{
+    match cipher {
+        Some(c) => row.decrypt(c),
+        None => Ok(row),
+    }
+}
+
 impl OutputManagerBackend for OutputManagerSqliteDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let cipher = self.cipher(&conn)?;
 
         let result = match key {
-            DbKey::SpentOutput(k) => match OutputSql::find_status(&k.to_vec(), OutputStatus::Spent, &(*conn)) {
-                Ok(o) => Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))),
-                Err(e) => {
-                    match e {
-                        OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                        e => return Err(e),
-                    };
-                    None
-                },
+            DbKey::SpentOutput(k) => {
+                match OutputSql::find_status(&spending_key_bytes(k, &cipher)?, OutputStatus::Spent, &(*conn)) {
+                    Ok(o) => Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(
+                        decrypt_if_necessary(o, &cipher)?,
+                    )?))),
+                    Err(e) => {
+                        match e {
+                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                            e => return Err(e),
+                        };
+                        None
+                    },
+                }
             },
-            DbKey::UnspentOutput(k) => match OutputSql::find_status(&k.to_vec(), OutputStatus::Unspent, &(*conn)) {
-                Ok(o) => Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(o)?))),
-                Err(e) => {
-                    match e {
-                        OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                        e => return Err(e),
-                    };
-                    None
-                },
+            DbKey::UnspentOutput(k) => {
+                match OutputSql::find_status(&spending_key_bytes(k, &cipher)?, OutputStatus::Unspent, &(*conn)) {
+                    Ok(o) => Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(
+                        decrypt_if_necessary(o, &cipher)?,
+                    )?))),
+                    Err(e) => {
+                        match e {
+                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                            e => return Err(e),
+                        };
+                        None
+                    },
+                }
             },
             DbKey::PendingTransactionOutputs(tx_id) => match PendingTransactionOutputSql::find(*tx_id, &(*conn)) {
                 Ok(p) => {
                     let outputs = OutputSql::find_by_tx_id_and_encumbered(*tx_id, &(*conn))?;
                     Some(DbValue::PendingTransactionOutputs(Box::new(
-                        pending_transaction_outputs_from_sql_outputs(p.tx_id as u64, &p.timestamp, outputs)?,
+                        pending_transaction_outputs_from_sql_outputs(&p, outputs, &cipher)?,
                     )))
                 },
                 Err(e) => {
@@ -106,13 +201,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             DbKey::UnspentOutputs => Some(DbValue::UnspentOutputs(
                 OutputSql::index_status(OutputStatus::Unspent, &(*conn))?
                     .iter()
-                    .map(|o| UnblindedOutput::try_from(o.clone()))
+                    .map(|o| UnblindedOutput::try_from(decrypt_if_necessary(o.clone(), &cipher)?))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
             DbKey::SpentOutputs => Some(DbValue::SpentOutputs(
                 OutputSql::index_status(OutputStatus::Spent, &(*conn))?
                     .iter()
-                    .map(|o| UnblindedOutput::try_from(o.clone()))
+                    .map(|o| UnblindedOutput::try_from(decrypt_if_necessary(o.clone(), &cipher)?))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
             DbKey::AllPendingTransactionOutputs => {
@@ -122,89 +217,183 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     let outputs = OutputSql::find_by_tx_id_and_encumbered(p_tx.tx_id as u64, &(*conn))?;
                     pending_txs.insert(
                         p_tx.tx_id as u64,
-                        pending_transaction_outputs_from_sql_outputs(p_tx.tx_id as u64, &p_tx.timestamp, outputs)?,
+                        pending_transaction_outputs_from_sql_outputs(&p_tx, outputs, &cipher)?,
                     );
                 }
                 Some(DbValue::AllPendingTransactionOutputs(pending_txs))
             },
             DbKey::KeyManagerState => match KeyManagerStateSql::get_state(&(*conn)).ok() {
                 None => None,
-                Some(km) => Some(DbValue::KeyManagerState(KeyManagerState::try_from(km)?)),
+                Some(km) => Some(DbValue::KeyManagerState(KeyManagerState::try_from(
+                    decrypt_if_necessary(km, &cipher)?,
+                )?)),
             },
-            DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(
-                OutputSql::index_status(OutputStatus::Invalid, &(*conn))?
-                    .iter()
-                    .map(|o| UnblindedOutput::try_from(o.clone()))
+            DbKey::InvalidOutputs => {
+                let mut invalidations = OutputInvalidationSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|i| (i.spending_key.clone(), i))
+                    .collect::<HashMap<_, _>>();
+                let mut invalid_outputs = Vec::new();
+                for o in OutputSql::index_status(OutputStatus::Invalid, &(*conn))? {
+                    let invalidation = invalidations.remove(&o.spending_key);
+                    invalid_outputs.push(InvalidOutput {
+                        output: UnblindedOutput::try_from(decrypt_if_necessary(o, &cipher)?)?,
+                        reason: match invalidation.as_ref() {
+                            Some(i) => Some(serde_json::from_str(&i.reason)?),
+                            None => None,
+                        },
+                        timestamp: invalidation.map(|i| i.timestamp),
+                    });
+                }
+                Some(DbValue::InvalidOutputs(invalid_outputs))
+            },
+            DbKey::OutputSources => Some(DbValue::OutputSources(
+                OutputSourceSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|s| OutputSource::try_from(decrypt_if_necessary(s, &cipher)?))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::OutputMetadata => Some(DbValue::OutputMetadata(
+                OutputMetadataSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|m| OutputMetadata::try_from(decrypt_if_necessary(m, &cipher)?))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::SyncHistoryEntry(request_key) => match SyncHistorySql::find(*request_key, &(*conn)) {
+                Ok(e) => Some(DbValue::SyncHistoryEntry(Box::new(SyncHistoryEntry::try_from(e)?))),
+                Err(e) => {
+                    match e {
+                        OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                        e => return Err(e),
+                    };
+                    None
+                },
+            },
+            DbKey::SyncHistory => Some(DbValue::SyncHistory(
+                SyncHistorySql::index(&(*conn))?
+                    .into_iter()
+                    .map(SyncHistoryEntry::try_from)
                     .collect::<Result<Vec<_>, _>>()?,
             )),
+            DbKey::AbandonedTransactions => {
+                let mut outputs = HashMap::new();
+                for o in OutputSql::index_status(OutputStatus::Abandoned, &(*conn))? {
+                    outputs
+                        .entry(o.tx_id)
+                        .or_insert_with(Vec::new)
+                        .push(UnblindedOutput::try_from(decrypt_if_necessary(o, &cipher)?)?);
+                }
+                let mut abandoned_transactions = Vec::new();
+                for t in AbandonedTransactionSql::index(&(*conn))? {
+                    abandoned_transactions.push(AbandonedTransaction {
+                        tx_id: t.tx_id as u64,
+                        outputs: outputs.remove(&Some(t.tx_id)).unwrap_or_default(),
+                        reason: serde_json::from_str(&t.reason)?,
+                        timestamp: t.timestamp,
+                    });
+                }
+                Some(DbValue::AbandonedTransactions(abandoned_transactions))
+            },
         };
 
         Ok(result)
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let cipher = self.cipher(&conn)?;
 
         match op {
             WriteOperation::Insert(kvp) => match kvp {
                 DbKeyValuePair::SpentOutput(k, o) => {
-                    if OutputSql::find(&k.to_vec(), &(*conn)).is_ok() {
+                    if OutputSql::find(&spending_key_bytes(&k, &cipher)?, &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
-                    OutputSql::new(*o, OutputStatus::Spent, None).commit(&(*conn))?
+                    encrypt_if_necessary(OutputSql::new(*o, OutputStatus::Spent, None), &cipher)?.commit(&(*conn))?
                 },
                 DbKeyValuePair::UnspentOutput(k, o) => {
-                    if OutputSql::find(&k.to_vec(), &(*conn)).is_ok() {
+                    if OutputSql::find(&spending_key_bytes(&k, &cipher)?, &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
-                    OutputSql::new(*o, OutputStatus::Unspent, None).commit(&(*conn))?
+                    encrypt_if_necessary(OutputSql::new(*o, OutputStatus::Unspent, None), &cipher)?.commit(&(*conn))?
                 },
                 DbKeyValuePair::PendingTransactionOutputs(tx_id, p) => {
                     if PendingTransactionOutputSql::find(tx_id, &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
-                    PendingTransactionOutputSql::new(p.tx_id, true, p.timestamp).commit(&(*conn))?;
+                    let mut pending_tx_sql =
+                        PendingTransactionOutputSql::new(p.tx_id, true, p.timestamp, p.amount, p.fee);
+                    pending_tx_sql.destination_public_key = p.destination.as_ref().map(|d| d.to_vec());
+                    pending_tx_sql.commit(&(*conn))?;
                     for o in p.outputs_to_be_spent {
-                        OutputSql::new(o.clone(), OutputStatus::EncumberedToBeSpent, Some(p.tx_id)).commit(&(*conn))?;
+                        encrypt_if_necessary(
+                            OutputSql::new(o.clone(), OutputStatus::EncumberedToBeSpent, Some(p.tx_id)),
+                            &cipher,
+                        )?
+                        .commit(&(*conn))?;
                     }
                     for o in p.outputs_to_be_received {
-                        OutputSql::new(o.clone(), OutputStatus::EncumberedToBeReceived, Some(p.tx_id))
-                            .commit(&(*conn))?;
+                        encrypt_if_necessary(
+                            OutputSql::new(o.clone(), OutputStatus::EncumberedToBeReceived, Some(p.tx_id)),
+                            &cipher,
+                        )?
+                        .commit(&(*conn))?;
                     }
                 },
-                DbKeyValuePair::KeyManagerState(km) => KeyManagerStateSql::set_state(km, &(*conn))?,
+                DbKeyValuePair::KeyManagerState(km) => KeyManagerStateSql::set_state(
+                    encrypt_if_necessary(KeyManagerStateSql::try_from(km)?, &cipher)?,
+                    &(*conn),
+                )?,
+                DbKeyValuePair::OutputSource(_, s) => {
+                    encrypt_if_necessary(OutputSourceSql::from(*s), &cipher)?.commit(&(*conn))?
+                },
+                DbKeyValuePair::OutputMetadata(_, m) => {
+                    encrypt_if_necessary(OutputMetadataSql::from(*m), &cipher)?.commit(&(*conn))?
+                },
+                DbKeyValuePair::SyncHistoryEntry(_, e) => SyncHistorySql::try_from(*e)?.commit(&(*conn))?,
             },
             WriteOperation::Remove(k) => match k {
-                DbKey::SpentOutput(s) => match OutputSql::find_status(&s.to_vec(), OutputStatus::Spent, &(*conn)) {
-                    Ok(o) => {
-                        o.delete(&(*conn))?;
-                        return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))));
-                    },
-                    Err(e) => {
-                        match e {
-                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                            e => return Err(e),
-                        };
-                    },
+                DbKey::SpentOutput(s) => {
+                    match OutputSql::find_status(&spending_key_bytes(&s, &cipher)?, OutputStatus::Spent, &(*conn)) {
+                        Ok(o) => {
+                            o.delete(&(*conn))?;
+                            return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(
+                                decrypt_if_necessary(o, &cipher)?,
+                            )?))));
+                        },
+                        Err(e) => {
+                            match e {
+                                OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                                e => return Err(e),
+                            };
+                        },
+                    }
                 },
-                DbKey::UnspentOutput(k) => match OutputSql::find_status(&k.to_vec(), OutputStatus::Unspent, &(*conn)) {
-                    Ok(o) => {
-                        o.delete(&(*conn))?;
-                        return Ok(Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(o)?))));
-                    },
-                    Err(e) => {
-                        match e {
-                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                            e => return Err(e),
-                        };
-                    },
+                DbKey::UnspentOutput(k) => {
+                    match OutputSql::find_status(&spending_key_bytes(&k, &cipher)?, OutputStatus::Unspent, &(*conn)) {
+                        Ok(o) => {
+                            o.delete(&(*conn))?;
+                            return Ok(Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(
+                                decrypt_if_necessary(o, &cipher)?,
+                            )?))));
+                        },
+                        Err(e) => {
+                            match e {
+                                OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                                e => return Err(e),
+                            };
+                        },
+                    }
                 },
                 DbKey::PendingTransactionOutputs(tx_id) => match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
                     Ok(p) => {
                         let outputs = OutputSql::find_by_tx_id_and_encumbered(p.tx_id as u64, &(*conn))?;
                         p.delete(&(*conn))?;
                         return Ok(Some(DbValue::PendingTransactionOutputs(Box::new(
-                            pending_transaction_outputs_from_sql_outputs(p.tx_id as u64, &p.timestamp, outputs)?,
+                            pending_transaction_outputs_from_sql_outputs(&p, outputs, &cipher)?,
                         ))));
                     },
                     Err(e) => {
@@ -219,6 +408,24 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::InvalidOutputs => {},
+                DbKey::OutputSources => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::OutputMetadata => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SyncHistoryEntry(request_key) => match SyncHistorySql::find(request_key, &(*conn)) {
+                    Ok(e) => {
+                        e.delete(&(*conn))?;
+                        return Ok(Some(DbValue::SyncHistoryEntry(Box::new(SyncHistoryEntry::try_from(
+                            e,
+                        )?))));
+                    },
+                    Err(e) => {
+                        match e {
+                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                            e => return Err(e),
+                        };
+                    },
+                },
+                DbKey::SyncHistory => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::AbandonedTransactions => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
 
@@ -226,7 +433,10 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
     }
 
     fn confirm_transaction(&self, tx_id: u64) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
         match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
             Ok(p) => {
@@ -268,22 +478,29 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
     fn short_term_encumber_outputs(
         &self,
         tx_id: u64,
+        amount: MicroTari,
+        fee: MicroTari,
         outputs_to_send: &[UnblindedOutput],
         outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>
     {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        let cipher = self.cipher(&conn)?;
 
         let mut outputs_to_be_spent = Vec::new();
         for i in outputs_to_send {
-            let output = OutputSql::find(&i.spending_key.to_vec(), &(*conn))?;
+            let output = OutputSql::find(&spending_key_bytes(&i.spending_key, &cipher)?, &(*conn))?;
             if output.status == (OutputStatus::Spent as i32) {
                 return Err(OutputManagerStorageError::OutputAlreadySpent);
             }
             outputs_to_be_spent.push(output);
         }
 
-        PendingTransactionOutputSql::new(tx_id, true, Utc::now().naive_utc()).commit(&(*conn))?;
+        PendingTransactionOutputSql::new(tx_id, true, Utc::now().naive_utc(), amount, fee).commit(&(*conn))?;
 
         for o in outputs_to_be_spent {
             o.update(
@@ -296,14 +513,21 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         }
 
         for co in outputs_to_receive {
-            OutputSql::new(co.clone(), OutputStatus::EncumberedToBeReceived, Some(tx_id)).commit(&(*conn))?;
+            encrypt_if_necessary(
+                OutputSql::new(co.clone(), OutputStatus::EncumberedToBeReceived, Some(tx_id)),
+                &cipher,
+            )?
+            .commit(&(*conn))?;
         }
 
         Ok(())
     }
 
     fn confirm_encumbered_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
         match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
             Ok(p) => {
@@ -324,8 +548,47 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         Ok(())
     }
 
+    fn set_pending_transaction_destination(
+        &self,
+        tx_id: TxId,
+        destination: CommsPublicKey,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
+            Ok(p) => {
+                p.update(
+                    UpdatePendingTransactionOutputSql {
+                        short_term: None,
+                        destination_public_key: Some(destination.to_vec()),
+                    },
+                    &(*conn),
+                )?;
+            },
+            Err(e) => {
+                match e {
+                    OutputManagerStorageError::DieselError(DieselError::NotFound) => {
+                        return Err(OutputManagerStorageError::ValueNotFound(
+                            DbKey::PendingTransactionOutputs(tx_id),
+                        ))
+                    },
+                    e => return Err(e),
+                };
+            },
+        }
+
+        Ok(())
+    }
+
     fn clear_short_term_encumberances(&self) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
         let pending_transaction_outputs = PendingTransactionOutputSql::index_short_term(&(*conn))?;
         drop(conn);
@@ -338,7 +601,10 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
     }
 
     fn cancel_pending_transaction(&self, tx_id: u64) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
         match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
             Ok(p) => {
@@ -382,8 +648,64 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         Ok(())
     }
 
+    fn abandon_pending_transaction(
+        &self,
+        tx_id: TxId,
+        reason: &AbandonmentReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        let p = match PendingTransactionOutputSql::find(tx_id, &(*conn)) {
+            Ok(p) => p,
+            Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(OutputManagerStorageError::ValueNotFound(
+                    DbKey::PendingTransactionOutputs(tx_id),
+                ))
+            },
+            Err(e) => return Err(e),
+        };
+
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            for o in OutputSql::find_by_tx_id_and_encumbered(tx_id, &(*conn))? {
+                if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
+                    // The transaction id is kept so the output can be listed with the abandoned transaction
+                    o.update(
+                        UpdateOutput {
+                            status: Some(OutputStatus::Abandoned),
+                            tx_id: None,
+                        },
+                        &(*conn),
+                    )?;
+                } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
+                    o.update(
+                        UpdateOutput {
+                            status: Some(OutputStatus::Unspent),
+                            tx_id: None,
+                        },
+                        &(*conn),
+                    )?;
+                    o.update_null(NullOutputSql { tx_id: None }, &(*conn))?;
+                }
+            }
+            p.delete(&(*conn))?;
+            AbandonedTransactionSql {
+                tx_id: tx_id as i64,
+                reason: serde_json::to_string(reason)?,
+                timestamp: Utc::now().naive_utc(),
+            }
+            .commit(&(*conn))
+        })
+    }
+
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
         let older_pending_txs = PendingTransactionOutputSql::index_older(
             Utc::now().naive_utc() - ChronoDuration::from_std(period)?,
@@ -396,51 +718,244 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         Ok(())
     }
 
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+    fn increment_key_index(&self, branch: KeyManagerBranch) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        KeyManagerStateSql::increment_index(branch, &(*conn))?;
+
+        Ok(())
+    }
+
+    fn add_unspent_outputs(&self, outputs: &[UnblindedOutput]) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        let cipher = self.cipher(&conn)?;
+
+        let outputs_sql = outputs
+            .iter()
+            .map(|o| encrypt_if_necessary(OutputSql::new(o.clone(), OutputStatus::Unspent, None), &cipher))
+            .collect::<Result<Vec<_>, _>>()?;
+        let keys = outputs_sql
+            .iter()
+            .map(|o| o.spending_key.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if keys.len() != outputs_sql.len() {
+            return Err(OutputManagerStorageError::DuplicateOutput);
+        }
+
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            // Sqlite limits the number of bound parameters in a single statement so the keys are checked in chunks
+            for chunk in keys.chunks(MAX_SQL_PARAMETERS) {
+                if OutputSql::count_existing(chunk, &(*conn))? > 0 {
+                    return Err(OutputManagerStorageError::DuplicateOutput);
+                }
+            }
+            OutputSql::commit_batch(&outputs_sql, &(*conn))
+        })
+    }
 
-        KeyManagerStateSql::increment_index(&(*conn))?;
+    fn invalidate_unspent_output(
+        &self,
+        output: &UnblindedOutput,
+        reason: &InvalidationReason,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let cipher = self.cipher(&conn)?;
+        let output = OutputSql::find(&spending_key_bytes(&output.spending_key, &cipher)?, &conn)?;
+        let reason = serde_json::to_string(reason)?;
+        // The status update and the invalidation record are written together, so that an output is never left
+        // invalid without the reason it was invalidated
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            let output = output.update(
+                UpdateOutput {
+                    status: Some(OutputStatus::Invalid),
+                    tx_id: None,
+                },
+                &(*conn),
+            )?;
+            OutputInvalidationSql {
+                spending_key: output.spending_key,
+                reason,
+                timestamp: Utc::now().naive_utc(),
+            }
+            .commit(&(*conn))
+        })?;
 
         Ok(())
     }
 
-    fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
-        let conn = acquire_lock!(self.database_connection);
-        let output = OutputSql::find(&output.spending_key.to_vec(), &conn)?;
-        let _ = output.update(
+    fn revalidate_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let cipher = self.cipher(&conn)?;
+        let output = OutputSql::find(&spending_key_bytes(&output.spending_key, &cipher)?, &conn)?;
+        if output.status != (OutputStatus::Invalid as i32) {
+            return Err(OutputManagerStorageError::ValuesNotFound);
+        }
+        let output = output.update(
             UpdateOutput {
-                status: Some(OutputStatus::Invalid),
+                status: Some(OutputStatus::Unspent),
                 tx_id: None,
             },
             &(*conn),
         )?;
+        OutputInvalidationSql::delete(&output.spending_key, &(*conn))?;
+
+        Ok(())
+    }
+
+    fn apply_encryption(&self, passphrase: &str) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let mut current_cipher = acquire_write_lock!(self.cipher);
+        if current_cipher.is_some() {
+            return Err(OutputManagerStorageError::AlreadyEncrypted);
+        }
+
+        // If the database is already encrypted the passphrase is checked and used to unlock it
+        if let Some(settings) = OutputManagerEncryptionSql::get(&conn)? {
+            let cipher = DatabaseCipher::from_passphrase(passphrase, &settings.salt)?;
+            match cipher.decrypt(&settings.check_value) {
+                Ok(v) if v.as_slice() == ENCRYPTION_CHECK_VALUE => {
+                    *current_cipher = Some(cipher);
+                    return Ok(());
+                },
+                _ => return Err(OutputManagerStorageError::InvalidPassphrase),
+            }
+        }
+
+        let salt = DatabaseCipher::generate_salt();
+        let cipher = DatabaseCipher::from_passphrase(passphrase, &salt)?;
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            update_encrypted_values(&conn, |v| Ok(cipher.encrypt(v)?))?;
+            OutputManagerEncryptionSql {
+                id: 0,
+                salt,
+                check_value: cipher.encrypt(ENCRYPTION_CHECK_VALUE)?,
+            }
+            .commit(&conn)
+        })?;
+        *current_cipher = Some(cipher);
+
+        Ok(())
+    }
+
+    fn remove_encryption(&self) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let mut current_cipher = acquire_write_lock!(self.cipher);
+        let cipher = match current_cipher.as_ref() {
+            Some(c) => c,
+            None => {
+                return match OutputManagerEncryptionSql::get(&conn)? {
+                    Some(_) => Err(OutputManagerStorageError::DatabaseLocked),
+                    None => Err(OutputManagerStorageError::NotEncrypted),
+                };
+            },
+        };
+
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            update_encrypted_values(&conn, |v| Ok(cipher.decrypt(v)?))?;
+            OutputManagerEncryptionSql::delete(&conn)
+        })?;
+        *current_cipher = None;
 
         Ok(())
     }
 }
 
-/// A utility function to construct a PendingTransactionOutputs structure for a TxId, set of Outputs and a Timestamp
+/// Replace every value that is stored encrypted (the spending keys and the key manager's master seed) with the result
+/// of `f`. This is used to encrypt or decrypt the values in place when encryption is applied or removed.
+fn update_encrypted_values<F>(conn: &SqliteConnection, f: F) -> Result<(), OutputManagerStorageError>
+where F: Fn(&[u8]) -> Result<Vec<u8>, OutputManagerStorageError> {
+    for k in outputs::table.select(outputs::spending_key).load::<Vec<u8>>(conn)? {
+        diesel::update(outputs::table.filter(outputs::spending_key.eq(&k)))
+            .set(outputs::spending_key.eq(f(&k)?))
+            .execute(conn)?;
+    }
+    for k in output_sources::table
+        .select(output_sources::spending_key)
+        .load::<Vec<u8>>(conn)?
+    {
+        diesel::update(output_sources::table.filter(output_sources::spending_key.eq(&k)))
+            .set(output_sources::spending_key.eq(f(&k)?))
+            .execute(conn)?;
+    }
+    for k in output_metadata::table
+        .select(output_metadata::spending_key)
+        .load::<Vec<u8>>(conn)?
+    {
+        diesel::update(output_metadata::table.filter(output_metadata::spending_key.eq(&k)))
+            .set(output_metadata::spending_key.eq(f(&k)?))
+            .execute(conn)?;
+    }
+    for k in output_invalidations::table
+        .select(output_invalidations::spending_key)
+        .load::<Vec<u8>>(conn)?
+    {
+        diesel::update(output_invalidations::table.filter(output_invalidations::spending_key.eq(&k)))
+            .set(output_invalidations::spending_key.eq(f(&k)?))
+            .execute(conn)?;
+    }
+    if let Ok(km) = KeyManagerStateSql::get_state(conn) {
+        diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
+            .set(key_manager_states::master_seed.eq(f(&km.master_seed)?))
+            .execute(conn)?;
+        if let Some(entropy) = km.bip39_entropy.as_ref() {
+            diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
+                .set(key_manager_states::bip39_entropy.eq(Some(f(entropy)?)))
+                .execute(conn)?;
+        }
+    }
+    Ok(())
+}
+
+/// A utility function to construct a PendingTransactionOutputs structure from its Sql record and set of Outputs
 fn pending_transaction_outputs_from_sql_outputs(
-    tx_id: TxId,
-    timestamp: &NaiveDateTime,
+    pending_tx: &PendingTransactionOutputSql,
     outputs: Vec<OutputSql>,
+    cipher: &Option<DatabaseCipher>,
 ) -> Result<PendingTransactionOutputs, OutputManagerStorageError>
 {
     let mut outputs_to_be_spent = Vec::new();
     let mut outputs_to_be_received = Vec::new();
     for o in outputs {
         if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
-            outputs_to_be_received.push(UnblindedOutput::try_from(o.clone())?);
+            outputs_to_be_received.push(UnblindedOutput::try_from(decrypt_if_necessary(o.clone(), cipher)?)?);
         } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
-            outputs_to_be_spent.push(UnblindedOutput::try_from(o.clone())?);
+            outputs_to_be_spent.push(UnblindedOutput::try_from(decrypt_if_necessary(o.clone(), cipher)?)?);
         }
     }
 
     Ok(PendingTransactionOutputs {
-        tx_id,
+        tx_id: pending_tx.tx_id as u64,
         outputs_to_be_spent,
         outputs_to_be_received,
-        timestamp: *timestamp,
+        timestamp: pending_tx.timestamp,
+        amount: MicroTari::from(pending_tx.amount as u64),
+        fee: MicroTari::from(pending_tx.fee as u64),
+        destination: match &pending_tx.destination_public_key {
+            Some(d) => Some(CommsPublicKey::from_bytes(d).map_err(|_| OutputManagerStorageError::ConversionError)?),
+            None => None,
+        },
     })
 }
 
@@ -453,6 +968,7 @@ enum OutputStatus {
     EncumberedToBeSpent,
     Invalid,
     CancelledInbound,
+    Abandoned,
 }
 
 impl TryFrom<i32> for OutputStatus {
@@ -466,6 +982,7 @@ impl TryFrom<i32> for OutputStatus {
             3 => Ok(OutputStatus::EncumberedToBeSpent),
             4 => Ok(OutputStatus::Invalid),
             5 => Ok(OutputStatus::CancelledInbound),
+            6 => Ok(OutputStatus::Abandoned),
             _ => Err(OutputManagerStorageError::ConversionError),
         }
     }
@@ -502,6 +1019,25 @@ impl OutputSql {
         Ok(())
     }
 
+    /// Write a batch of outputs to the database. Diesel reuses a single cached prepared statement for every row of a
+    /// batch insert on Sqlite so this should be called within a transaction to avoid a disk sync per row.
+    pub fn commit_batch(outputs: &[OutputSql], conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(outputs::table).values(outputs).execute(conn)?;
+        Ok(())
+    }
+
+    /// Count how many of the provided spending keys already exist in the database
+    pub fn count_existing(
+        spending_keys: &[Vec<u8>],
+        conn: &SqliteConnection,
+    ) -> Result<i64, OutputManagerStorageError>
+    {
+        Ok(outputs::table
+            .filter(outputs::spending_key.eq_any(spending_keys))
+            .count()
+            .get_result(conn)?)
+    }
+
     /// Return all unencumbered outputs
     #[cfg(test)]
     pub fn index(conn: &SqliteConnection) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
@@ -664,13 +1200,19 @@ struct PendingTransactionOutputSql {
     tx_id: i64,
     short_term: i32,
     timestamp: NaiveDateTime,
+    amount: i64,
+    fee: i64,
+    destination_public_key: Option<Vec<u8>>,
 }
 impl PendingTransactionOutputSql {
-    pub fn new(tx_id: TxId, short_term: bool, timestamp: NaiveDateTime) -> Self {
+    pub fn new(tx_id: TxId, short_term: bool, timestamp: NaiveDateTime, amount: MicroTari, fee: MicroTari) -> Self {
         Self {
             tx_id: tx_id as i64,
             short_term: short_term as i32,
             timestamp,
+            amount: u64::from(amount) as i64,
+            fee: u64::from(fee) as i64,
+            destination_public_key: None,
         }
     }
 
@@ -740,7 +1282,31 @@ impl PendingTransactionOutputSql {
         let num_updated = diesel::update(
             pending_transaction_outputs::table.filter(pending_transaction_outputs::tx_id.eq(&self.tx_id)),
         )
-        .set(UpdatePendingTransactionOutputSql { short_term: Some(0i32) })
+        .set(UpdatePendingTransactionOutputSql {
+            short_term: Some(0i32),
+            destination_public_key: None,
+        })
+        .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(OutputManagerStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(PendingTransactionOutputSql::find(self.tx_id as u64, conn)?)
+    }
+
+    pub fn update(
+        &self,
+        updated_pending_tx: UpdatePendingTransactionOutputSql,
+        conn: &SqliteConnection,
+    ) -> Result<PendingTransactionOutputSql, OutputManagerStorageError>
+    {
+        let num_updated = diesel::update(
+            pending_transaction_outputs::table.filter(pending_transaction_outputs::tx_id.eq(&self.tx_id)),
+        )
+        .set(updated_pending_tx)
         .execute(conn)?;
 
         if num_updated == 0 {
@@ -757,6 +1323,245 @@ impl PendingTransactionOutputSql {
 #[table_name = "pending_transaction_outputs"]
 pub struct UpdatePendingTransactionOutputSql {
     short_term: Option<i32>,
+    destination_public_key: Option<Vec<u8>>,
+}
+
+/// This struct represents an OutputSource in the Sql database.
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "output_sources"]
+struct OutputSourceSql {
+    spending_key: Vec<u8>,
+    tag: String,
+    timestamp: NaiveDateTime,
+}
+
+impl OutputSourceSql {
+    /// Write this struct to the database, replacing any existing source for the same output
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(output_sources::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<OutputSourceSql>, OutputManagerStorageError> {
+        Ok(output_sources::table.load::<OutputSourceSql>(conn)?)
+    }
+}
+
+impl From<OutputSource> for OutputSourceSql {
+    fn from(s: OutputSource) -> Self {
+        Self {
+            spending_key: s.spending_key.to_vec(),
+            tag: s.tag,
+            timestamp: s.timestamp,
+        }
+    }
+}
+
+impl TryFrom<OutputSourceSql> for OutputSource {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(s: OutputSourceSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            spending_key: PrivateKey::from_vec(&s.spending_key)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            tag: s.tag,
+            timestamp: s.timestamp,
+        })
+    }
+}
+
+/// This struct represents the OutputMetadata of an output in the Sql database.
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "output_metadata"]
+struct OutputMetadataSql {
+    spending_key: Vec<u8>,
+    label: Option<String>,
+    source_tx_id: Option<i64>,
+    discovered_height: Option<i64>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl OutputMetadataSql {
+    /// Write this struct to the database, replacing any existing metadata for the same output
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(output_metadata::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<OutputMetadataSql>, OutputManagerStorageError> {
+        Ok(output_metadata::table.load::<OutputMetadataSql>(conn)?)
+    }
+}
+
+impl From<OutputMetadata> for OutputMetadataSql {
+    fn from(m: OutputMetadata) -> Self {
+        Self {
+            spending_key: m.spending_key.to_vec(),
+            label: m.label,
+            source_tx_id: m.source_tx_id.map(|id| id as i64),
+            discovered_height: m.discovered_height.map(|h| h as i64),
+            created_at: m.created_at,
+            updated_at: m.updated_at,
+        }
+    }
+}
+
+impl TryFrom<OutputMetadataSql> for OutputMetadata {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(m: OutputMetadataSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            spending_key: PrivateKey::from_vec(&m.spending_key)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            label: m.label,
+            source_tx_id: m.source_tx_id.map(|id| id as u64),
+            discovered_height: m.discovered_height.map(|h| h as u64),
+            created_at: m.created_at,
+            updated_at: m.updated_at,
+        })
+    }
+}
+
+/// This struct represents a UTXO query in the sync history in the Sql database. The output hashes and the outcome are
+/// stored as JSON.
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "sync_history"]
+struct SyncHistorySql {
+    request_key: i64,
+    base_node_public_key: Vec<u8>,
+    output_hashes: String,
+    sent_at: NaiveDateTime,
+    response_time_ms: Option<i64>,
+    invalidated_hashes: String,
+    outcome: String,
+}
+
+impl SyncHistorySql {
+    /// Write this struct to the database, replacing any existing entry for the same request key
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(sync_history::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<SyncHistorySql>, OutputManagerStorageError> {
+        Ok(sync_history::table.load::<SyncHistorySql>(conn)?)
+    }
+
+    pub fn find(request_key: u64, conn: &SqliteConnection) -> Result<SyncHistorySql, OutputManagerStorageError> {
+        Ok(sync_history::table
+            .filter(sync_history::request_key.eq(request_key as i64))
+            .first::<SyncHistorySql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::delete(sync_history::table.filter(sync_history::request_key.eq(self.request_key))).execute(conn)?;
+        Ok(())
+    }
+}
+
+fn hashes_to_json(hashes: &[Vec<u8>]) -> Result<String, OutputManagerStorageError> {
+    Ok(serde_json::to_string(
+        &hashes.iter().map(|h| to_hex(h)).collect::<Vec<_>>(),
+    )?)
+}
+
+fn hashes_from_json(json: &str) -> Result<Vec<Vec<u8>>, OutputManagerStorageError> {
+    serde_json::from_str::<Vec<String>>(json)?
+        .iter()
+        .map(|h| from_hex(h).map_err(|_| OutputManagerStorageError::ConversionError))
+        .collect()
+}
+
+impl TryFrom<SyncHistoryEntry> for SyncHistorySql {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(e: SyncHistoryEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            request_key: e.request_key as i64,
+            base_node_public_key: e.base_node_public_key.to_vec(),
+            output_hashes: hashes_to_json(&e.output_hashes)?,
+            sent_at: e.sent_at,
+            response_time_ms: e.response_time.map(|t| t.as_millis() as i64),
+            invalidated_hashes: hashes_to_json(&e.invalidated_hashes)?,
+            outcome: serde_json::to_string(&e.outcome)?,
+        })
+    }
+}
+
+impl TryFrom<SyncHistorySql> for SyncHistoryEntry {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(e: SyncHistorySql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            request_key: e.request_key as u64,
+            base_node_public_key: CommsPublicKey::from_bytes(&e.base_node_public_key)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            output_hashes: hashes_from_json(&e.output_hashes)?,
+            sent_at: e.sent_at,
+            response_time: e.response_time_ms.map(|t| Duration::from_millis(t as u64)),
+            invalidated_hashes: hashes_from_json(&e.invalidated_hashes)?,
+            outcome: serde_json::from_str(&e.outcome)?,
+        })
+    }
+}
+
+/// This struct records why an output was invalidated in the Sql database. The reason is stored as JSON.
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "output_invalidations"]
+struct OutputInvalidationSql {
+    spending_key: Vec<u8>,
+    reason: String,
+    timestamp: NaiveDateTime,
+}
+
+impl OutputInvalidationSql {
+    /// Write this struct to the database, replacing any existing reason recorded for the same output
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(output_invalidations::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<OutputInvalidationSql>, OutputManagerStorageError> {
+        Ok(output_invalidations::table.load::<OutputInvalidationSql>(conn)?)
+    }
+
+    /// Remove the reason recorded for an output, if there is one
+    pub fn delete(spending_key: &[u8], conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::delete(output_invalidations::table.filter(output_invalidations::spending_key.eq(spending_key)))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// This struct records why a pending transaction was abandoned in the Sql database. The reason is stored as JSON.
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "abandoned_transactions"]
+struct AbandonedTransactionSql {
+    tx_id: i64,
+    reason: String,
+    timestamp: NaiveDateTime,
+}
+
+impl AbandonedTransactionSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(abandoned_transactions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<AbandonedTransactionSql>, OutputManagerStorageError> {
+        Ok(abandoned_transactions::table.load::<AbandonedTransactionSql>(conn)?)
+    }
 }
 
 #[derive(Clone, Debug, Queryable, Insertable)]
@@ -767,17 +1572,24 @@ struct KeyManagerStateSql {
     branch_seed: String,
     primary_key_index: i64,
     timestamp: NaiveDateTime,
+    // The key indices of the branches other than the spending branch, stored as JSON
+    branch_indices: String,
+    bip39_entropy: Option<Vec<u8>>,
 }
 
-impl From<KeyManagerState> for KeyManagerStateSql {
-    fn from(km: KeyManagerState) -> Self {
-        Self {
+impl TryFrom<KeyManagerState> for KeyManagerStateSql {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(km: KeyManagerState) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: None,
             master_seed: km.master_seed.to_vec(),
             branch_seed: km.branch_seed,
             primary_key_index: km.primary_key_index as i64,
             timestamp: Utc::now().naive_utc(),
-        }
+            branch_indices: serde_json::to_string(&km.branch_indices)?,
+            bip39_entropy: km.bip39_entropy,
+        })
     }
 }
 
@@ -790,6 +1602,8 @@ impl TryFrom<KeyManagerStateSql> for KeyManagerState {
                 .map_err(|_| OutputManagerStorageError::ConversionError)?,
             branch_seed: km.branch_seed,
             primary_key_index: km.primary_key_index as usize,
+            branch_indices: serde_json::from_str(&km.branch_indices)?,
+            bip39_entropy: km.bip39_entropy,
         })
     }
 }
@@ -808,21 +1622,24 @@ impl KeyManagerStateSql {
             .map_err(|_| OutputManagerStorageError::KeyManagerNotInitialized)?)
     }
 
+    /// Store the provided state, which may already have had its master seed encrypted, replacing the existing state
     pub fn set_state(
-        key_manager_state: KeyManagerState,
+        key_manager_state: KeyManagerStateSql,
         conn: &SqliteConnection,
     ) -> Result<(), OutputManagerStorageError>
     {
         match KeyManagerStateSql::get_state(conn) {
             Ok(km) => {
-                let update = KeyManagerStateUpdate {
+                let update = KeyManagerStateUpdateSql {
                     master_seed: Some(key_manager_state.master_seed),
                     branch_seed: Some(key_manager_state.branch_seed),
                     primary_key_index: Some(key_manager_state.primary_key_index),
+                    branch_indices: Some(key_manager_state.branch_indices),
+                    bip39_entropy: Some(key_manager_state.bip39_entropy),
                 };
 
                 let num_updated = diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
-                    .set(KeyManagerStateUpdateSql::from(update))
+                    .set(update)
                     .execute(conn)?;
                 if num_updated == 0 {
                     return Err(OutputManagerStorageError::UnexpectedResult(
@@ -830,22 +1647,42 @@ impl KeyManagerStateSql {
                     ));
                 }
             },
-            Err(_) => KeyManagerStateSql::from(key_manager_state).commit(conn)?,
+            Err(_) => key_manager_state.commit(conn)?,
         }
         Ok(())
     }
 
-    pub fn increment_index(conn: &SqliteConnection) -> Result<usize, OutputManagerStorageError> {
+    pub fn increment_index(
+        branch: KeyManagerBranch,
+        conn: &SqliteConnection,
+    ) -> Result<usize, OutputManagerStorageError>
+    {
         Ok(match KeyManagerStateSql::get_state(conn) {
             Ok(km) => {
-                let current_index = (km.primary_key_index + 1) as usize;
-                let update = KeyManagerStateUpdate {
+                let mut update = KeyManagerStateUpdateSql {
                     master_seed: None,
                     branch_seed: None,
-                    primary_key_index: Some(current_index),
+                    primary_key_index: None,
+                    branch_indices: None,
+                    bip39_entropy: None,
+                };
+                let current_index = match branch {
+                    KeyManagerBranch::Spending => {
+                        let current_index = (km.primary_key_index + 1) as usize;
+                        update.primary_key_index = Some(current_index as i64);
+                        current_index
+                    },
+                    _ => {
+                        let mut branch_indices: HashMap<KeyManagerBranch, usize> =
+                            serde_json::from_str(&km.branch_indices)?;
+                        let current_index = branch_indices.get(&branch).cloned().unwrap_or(0) + 1;
+                        branch_indices.insert(branch, current_index);
+                        update.branch_indices = Some(serde_json::to_string(&branch_indices)?);
+                        current_index
+                    },
                 };
                 let num_updated = diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
-                    .set(KeyManagerStateUpdateSql::from(update))
+                    .set(update)
                     .execute(conn)?;
                 if num_updated == 0 {
                     return Err(OutputManagerStorageError::UnexpectedResult(
@@ -859,40 +1696,112 @@ impl KeyManagerStateSql {
     }
 }
 
-struct KeyManagerStateUpdate {
-    master_seed: Option<PrivateKey>,
-    branch_seed: Option<String>,
-    primary_key_index: Option<usize>,
-}
-
 #[derive(AsChangeset)]
 #[table_name = "key_manager_states"]
 struct KeyManagerStateUpdateSql {
     master_seed: Option<Vec<u8>>,
     branch_seed: Option<String>,
     primary_key_index: Option<i64>,
+    branch_indices: Option<String>,
+    bip39_entropy: Option<Option<Vec<u8>>>,
 }
 
-impl From<KeyManagerStateUpdate> for KeyManagerStateUpdateSql {
-    fn from(km: KeyManagerStateUpdate) -> Self {
-        Self {
-            master_seed: km.master_seed.map(|ms| ms.to_vec()),
-            branch_seed: km.branch_seed,
-            primary_key_index: km.primary_key_index.map(|i| i as i64),
+impl Encryptable for OutputSql {
+    fn encrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.encrypt(&self.spending_key)?;
+        Ok(self)
+    }
+
+    fn decrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.decrypt(&self.spending_key)?;
+        Ok(self)
+    }
+}
+
+impl Encryptable for OutputSourceSql {
+    fn encrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.encrypt(&self.spending_key)?;
+        Ok(self)
+    }
+
+    fn decrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.decrypt(&self.spending_key)?;
+        Ok(self)
+    }
+}
+
+impl Encryptable for OutputMetadataSql {
+    fn encrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.encrypt(&self.spending_key)?;
+        Ok(self)
+    }
+
+    fn decrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = cipher.decrypt(&self.spending_key)?;
+        Ok(self)
+    }
+}
+
+impl Encryptable for KeyManagerStateSql {
+    fn encrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.master_seed = cipher.encrypt(&self.master_seed)?;
+        if let Some(entropy) = self.bip39_entropy.as_ref() {
+            self.bip39_entropy = Some(cipher.encrypt(entropy)?);
+        }
+        Ok(self)
+    }
+
+    fn decrypt(mut self, cipher: &DatabaseCipher) -> Result<Self, OutputManagerStorageError> {
+        self.master_seed = cipher.decrypt(&self.master_seed)?;
+        if let Some(entropy) = self.bip39_entropy.as_ref() {
+            self.bip39_entropy = Some(cipher.decrypt(entropy)?);
         }
+        Ok(self)
+    }
+}
+
+/// This struct records the salt the encryption key was derived with and an encrypted check value used to verify a
+/// passphrase. The row is only present while the database is encrypted.
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "output_manager_encryption"]
+struct OutputManagerEncryptionSql {
+    id: i64,
+    salt: Vec<u8>,
+    check_value: Vec<u8>,
+}
+
+impl OutputManagerEncryptionSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(output_manager_encryption::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn get(conn: &SqliteConnection) -> Result<Option<OutputManagerEncryptionSql>, OutputManagerStorageError> {
+        match output_manager_encryption::table.first::<OutputManagerEncryptionSql>(conn) {
+            Ok(e) => Ok(Some(e)),
+            Err(DieselError::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn delete(conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::delete(output_manager_encryption::table).execute(conn)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::output_manager_service::storage::{
-        database::KeyManagerState,
+        database::{KeyManagerBranch, KeyManagerState},
         sqlite_db::{KeyManagerStateSql, OutputSql, OutputStatus, PendingTransactionOutputSql, UpdateOutput},
     };
     use chrono::{Duration as ChronoDuration, Utc};
     use diesel::{Connection, SqliteConnection};
     use rand::{distributions::Alphanumeric, rngs::OsRng, CryptoRng, Rng, RngCore};
-    use std::{convert::TryFrom, iter, time::Duration};
+    use std::{collections::HashMap, convert::TryFrom, iter, time::Duration};
     use tari_core::transactions::{
         tari_amount::MicroTari,
         transaction::{OutputFeatures, TransactionInput, UnblindedOutput},
@@ -977,17 +1886,45 @@ mod test {
 
         let tx_id = 44u64;
 
-        PendingTransactionOutputSql::new(tx_id, true, Utc::now().naive_utc())
-            .commit(&conn)
-            .unwrap();
+        PendingTransactionOutputSql::new(
+            tx_id,
+            true,
+            Utc::now().naive_utc(),
+            MicroTari::from(1000),
+            MicroTari::from(100),
+        )
+        .commit(&conn)
+        .unwrap();
 
-        PendingTransactionOutputSql::new(11u64, true, Utc::now().naive_utc())
-            .commit(&conn)
-            .unwrap();
+        PendingTransactionOutputSql::new(
+            11u64,
+            true,
+            Utc::now().naive_utc(),
+            MicroTari::from(0),
+            MicroTari::from(0),
+        )
+        .commit(&conn)
+        .unwrap();
 
         let pt = PendingTransactionOutputSql::find(tx_id, &conn).unwrap();
 
         assert_eq!(pt.tx_id as u64, tx_id);
+        assert_eq!(pt.amount, 1000);
+        assert_eq!(pt.fee, 100);
+        assert_eq!(pt.destination_public_key, None);
+
+        let destination = vec![1u8; 32];
+        let pt = pt
+            .update(
+                UpdatePendingTransactionOutputSql {
+                    short_term: None,
+                    destination_public_key: Some(destination.clone()),
+                },
+                &conn,
+            )
+            .unwrap();
+        assert_eq!(pt.destination_public_key, Some(destination));
+        assert_eq!(pt.short_term, 1);
 
         let pts = PendingTransactionOutputSql::index(&conn).unwrap();
 
@@ -1023,6 +1960,8 @@ mod test {
             12u64,
             true,
             Utc::now().naive_utc() - ChronoDuration::from_std(Duration::from_millis(600_000)).unwrap(),
+            MicroTari::from(0),
+            MicroTari::from(0),
         )
         .commit(&conn)
         .unwrap();
@@ -1058,9 +1997,11 @@ mod test {
             master_seed: PrivateKey::random(&mut OsRng),
             branch_seed: random_string(8),
             primary_key_index: 0,
+            branch_indices: HashMap::new(),
+            bip39_entropy: None,
         };
 
-        KeyManagerStateSql::set_state(state1.clone(), &conn).unwrap();
+        KeyManagerStateSql::set_state(KeyManagerStateSql::try_from(state1.clone()).unwrap(), &conn).unwrap();
 
         let state1_read = KeyManagerStateSql::get_state(&conn).unwrap();
 
@@ -1070,19 +2011,24 @@ mod test {
             master_seed: PrivateKey::random(&mut OsRng),
             branch_seed: random_string(8),
             primary_key_index: 0,
+            branch_indices: HashMap::new(),
+            bip39_entropy: None,
         };
 
-        KeyManagerStateSql::set_state(state2.clone(), &conn).unwrap();
+        KeyManagerStateSql::set_state(KeyManagerStateSql::try_from(state2.clone()).unwrap(), &conn).unwrap();
 
         let state2_read = KeyManagerStateSql::get_state(&conn).unwrap();
 
         assert_eq!(state2, KeyManagerState::try_from(state2_read).unwrap());
 
-        KeyManagerStateSql::increment_index(&conn).unwrap();
-        KeyManagerStateSql::increment_index(&conn).unwrap();
+        KeyManagerStateSql::increment_index(KeyManagerBranch::Spending, &conn).unwrap();
+        KeyManagerStateSql::increment_index(KeyManagerBranch::Spending, &conn).unwrap();
+        KeyManagerStateSql::increment_index(KeyManagerBranch::Coinbase, &conn).unwrap();
 
-        let state3_read = KeyManagerStateSql::get_state(&conn).unwrap();
+        let state3_read = KeyManagerState::try_from(KeyManagerStateSql::get_state(&conn).unwrap()).unwrap();
 
         assert_eq!(state3_read.primary_key_index, 2);
+        assert_eq!(state3_read.key_index(KeyManagerBranch::Coinbase), 1);
+        assert_eq!(state3_read.key_index(KeyManagerBranch::OneSided), 0);
     }
 }
@@ -1,3 +1,19 @@
+table! {
+    abandoned_transactions (tx_id) {
+        tx_id -> BigInt,
+        reason -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Nullable<BigInt>,
+        event -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
 table! {
     coinbase_transactions (tx_id) {
         tx_id -> BigInt,
@@ -18,6 +34,7 @@ table! {
         status -> Integer,
         message -> Text,
         timestamp -> Timestamp,
+        fee_priority -> Nullable<Integer>,
     }
 }
 
@@ -28,6 +45,14 @@ table! {
     }
 }
 
+table! {
+    event_journal (sequence) {
+        sequence -> BigInt,
+        event -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
 table! {
     inbound_transactions (tx_id) {
         tx_id -> BigInt,
@@ -46,6 +71,8 @@ table! {
         branch_seed -> Text,
         primary_key_index -> BigInt,
         timestamp -> Timestamp,
+        branch_indices -> Text,
+        bip39_entropy -> Nullable<Binary>,
     }
 }
 
@@ -58,6 +85,7 @@ table! {
         sender_protocol -> Text,
         message -> Text,
         timestamp -> Timestamp,
+        fee_priority -> Nullable<Integer>,
     }
 }
 
@@ -72,6 +100,41 @@ table! {
     }
 }
 
+table! {
+    output_invalidations (spending_key) {
+        spending_key -> Binary,
+        reason -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    output_manager_encryption (id) {
+        id -> BigInt,
+        salt -> Binary,
+        check_value -> Binary,
+    }
+}
+
+table! {
+    output_metadata (spending_key) {
+        spending_key -> Binary,
+        label -> Nullable<Text>,
+        source_tx_id -> Nullable<BigInt>,
+        discovered_height -> Nullable<BigInt>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    output_sources (spending_key) {
+        spending_key -> Binary,
+        tag -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
 table! {
     peers (public_key) {
         public_key -> Binary,
@@ -84,17 +147,89 @@ table! {
         tx_id -> BigInt,
         short_term -> Integer,
         timestamp -> Timestamp,
+        amount -> BigInt,
+        fee -> BigInt,
+        destination_public_key -> Nullable<Binary>,
+    }
+}
+
+table! {
+    processed_messages (sender, message_hash) {
+        sender -> Binary,
+        message_hash -> Binary,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    scheduled_payments (id) {
+        id -> BigInt,
+        destination_public_key -> Binary,
+        amount -> BigInt,
+        fee_priority -> Integer,
+        message -> Text,
+        interval_secs -> BigInt,
+        next_payment_at -> Timestamp,
+        payments_made -> BigInt,
+        last_tx_id -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    sync_history (request_key) {
+        request_key -> BigInt,
+        base_node_public_key -> Binary,
+        output_hashes -> Text,
+        sent_at -> Timestamp,
+        response_time_ms -> Nullable<BigInt>,
+        invalidated_hashes -> Text,
+        outcome -> Text,
+    }
+}
+
+table! {
+    transaction_lifecycles (tx_id) {
+        tx_id -> BigInt,
+        state -> Integer,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    transaction_replacements (tx_id) {
+        tx_id -> BigInt,
+        replacement_tx_id -> BigInt,
+    }
+}
+
+table! {
+    wallet_settings (key) {
+        key -> Text,
+        value -> Text,
     }
 }
 
 allow_tables_to_appear_in_same_query!(
+    abandoned_transactions,
+    audit_log,
     coinbase_transactions,
     completed_transactions,
     contacts,
+    event_journal,
     inbound_transactions,
     key_manager_states,
     outbound_transactions,
+    output_invalidations,
+    output_manager_encryption,
+    output_metadata,
+    output_sources,
     outputs,
     peers,
     pending_transaction_outputs,
+    processed_messages,
+    scheduled_payments,
+    sync_history,
+    transaction_lifecycles,
+    transaction_replacements,
+    wallet_settings,
 );
@@ -21,14 +21,81 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::error::WalletStorageError;
-use diesel::{Connection, SqliteConnection};
+use diesel::{
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PoolError, PooledConnection},
+    Connection,
+    SqliteConnection,
+};
 use std::{
     io,
+    ops::Deref,
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
-pub type WalletDbConnection = Arc<Mutex<SqliteConnection>>;
+/// The number of connections kept in the wallet database connection pool
+const CONNECTION_POOL_SIZE: u32 = 8;
+/// How long a connection waits for another connection's lock on the database before failing with `SQLITE_BUSY`
+const BUSY_TIMEOUT_MS: u32 = 60_000;
+
+pub type WalletConnectionPool = Pool<ConnectionManager<SqliteConnection>>;
+pub type PooledWalletConnection = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// A pool of connections to the wallet Sqlite database, shared by the wallet services. The database runs in WAL mode
+/// so read-only queries on their own pooled connections are not blocked by a write. Writes are serialized through a
+/// single write lock so that a transaction that reads before it writes cannot fail to upgrade its lock.
+#[derive(Clone)]
+pub struct WalletDbConnection {
+    pool: WalletConnectionPool,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl WalletDbConnection {
+    /// Get a pooled connection for read-only queries
+    pub fn get_read_connection(&self) -> Result<PooledWalletConnection, PoolError> {
+        self.pool.get()
+    }
+
+    /// Get a pooled connection for queries that write to the database. The write lock is held until the connection
+    /// is dropped.
+    pub fn get_write_connection(&self) -> Result<WalletWriteConnection<'_>, PoolError> {
+        let write_guard = acquire_lock!(self.write_lock);
+        let connection = self.pool.get()?;
+        Ok(WalletWriteConnection {
+            connection,
+            _write_guard: write_guard,
+        })
+    }
+}
+
+/// A pooled connection that holds the wallet database write lock
+pub struct WalletWriteConnection<'a> {
+    connection: PooledWalletConnection,
+    _write_guard: MutexGuard<'a, ()>,
+}
+
+impl Deref for WalletWriteConnection<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.connection
+    }
+}
+
+/// Sets the per-connection pragmas on every connection the pool opens
+#[derive(Debug)]
+struct ConnectionPragmas;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionPragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.execute(&format!(
+            "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+            BUSY_TIMEOUT_MS
+        ))
+        .map(|_| ())
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
 
 pub fn run_migration_and_create_sqlite_connection<P: AsRef<Path>>(
     db_path: P,
@@ -39,13 +106,26 @@ pub fn run_migration_and_create_sqlite_connection<P: AsRef<Path>>(
         .to_str()
         .ok_or_else(|| WalletStorageError::InvalidUnicodePath)?;
     let connection = SqliteConnection::establish(path_str)?;
-    connection.execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 60000;")?;
+    // The journal mode is stored in the database file, so it only has to be set once
+    connection.execute("PRAGMA journal_mode = WAL;")?;
+    connection.execute("PRAGMA foreign_keys = ON;")?;
 
     if !db_exists {
         embed_migrations!("./migrations");
         embedded_migrations::run_with_output(&connection, &mut io::stdout())
             .map_err(|err| WalletStorageError::DatabaseMigrationError(format!("Database migration failed {}", err)))?;
     }
+    drop(connection);
+
+    let manager = ConnectionManager::<SqliteConnection>::new(path_str);
+    let pool = Pool::builder()
+        .max_size(CONNECTION_POOL_SIZE)
+        .connection_customizer(Box::new(ConnectionPragmas))
+        .build(manager)
+        .map_err(|_| WalletStorageError::R2d2Error)?;
 
-    Ok(Arc::new(Mutex::new(connection)))
+    Ok(WalletDbConnection {
+        pool,
+        write_lock: Arc::new(Mutex::new(())),
+    })
 }
@@ -20,13 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::error::WalletStorageError;
+use crate::{
+    auto_coin_split::AutoCoinSplitPolicy,
+    clone_detection::{CloneDetectionReason, InstanceHeartbeat},
+    error::WalletStorageError,
+    output_manager_service::{handle::OutputManagerEvent, TxId},
+    transaction_lifecycle::lifecycle::TransactionLifecycle,
+    transaction_service::handle::TransactionEvent,
+};
+use chrono::{NaiveDateTime, Utc};
 use log::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt::{Display, Error, Formatter},
     sync::Arc,
 };
 use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_crypto::tari_utilities::hex::Hex;
 
 const LOG_TARGET: &str = "wallet::database";
 
@@ -42,15 +53,85 @@ pub trait WalletBackend: Send + Sync {
 pub enum DbKey {
     Peer(CommsPublicKey),
     Peers,
+    AuditLog,
+    /// The event journal entries from this sequence number on
+    EventJournal(u64),
+    Setting(WalletSetting),
+    TransactionLifecycle(TxId),
+    TransactionLifecycles,
 }
 
 pub enum DbValue {
     Peer(Box<Peer>),
     Peers(Vec<Peer>),
+    AuditLog(Vec<AuditLogEntry>),
+    EventJournal(Vec<EventJournalEntry>),
+    Setting(String),
+    TransactionLifecycle(Box<TransactionLifecycle>),
+    TransactionLifecycles(Vec<TransactionLifecycle>),
 }
 
 pub enum DbKeyValuePair {
     Peer(CommsPublicKey, Peer),
+    AuditLogEntry(AuditLogEntry),
+    /// Inserting an event into the journal assigns it the next sequence number and returns the journaled entry
+    EventJournalEntry(WalletEvent, NaiveDateTime),
+    /// Inserting a setting replaces any existing value for that setting
+    Setting(WalletSetting, String),
+    /// Inserting a lifecycle replaces the existing lifecycle of that transaction
+    TransactionLifecycle(TransactionLifecycle),
+}
+
+/// The keys of the persistent wallet settings. Values are stored as JSON strings and accessed through the typed
+/// getters and setters on `WalletDatabase`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletSetting {
+    /// The public key of the base node the wallet is currently using
+    SelectedBaseNode,
+    /// The fee per gram used when the user does not specify one
+    DefaultFeePerGram,
+    /// When a sent or received transaction is considered complete
+    ConfirmationPolicy,
+    /// The time of the last successful wallet backup
+    LastBackupTime,
+    /// The hash of the last faucet claims file that was imported
+    FaucetClaimsImported,
+    /// The nonce of the wallet instance that last wrote its heartbeat to the database
+    InstanceHeartbeat,
+    /// Why the wallet was detected as cloned. Sending stays locked while this is set.
+    CloneDetected,
+    /// When the wallet automatically splits its funds into more outputs
+    AutoCoinSplitPolicy,
+    /// The hex encoded genesis block hash of the network the wallet was created on
+    NetworkGenesisHash,
+}
+
+impl WalletSetting {
+    /// The key under which the setting is persisted. These values must never change once released.
+    pub fn as_key(self) -> &'static str {
+        match self {
+            WalletSetting::SelectedBaseNode => "selected_base_node",
+            WalletSetting::DefaultFeePerGram => "default_fee_per_gram",
+            WalletSetting::ConfirmationPolicy => "confirmation_policy",
+            WalletSetting::LastBackupTime => "last_backup_time",
+            WalletSetting::FaucetClaimsImported => "faucet_claims_imported",
+            WalletSetting::InstanceHeartbeat => "instance_heartbeat",
+            WalletSetting::CloneDetected => "clone_detected",
+            WalletSetting::AutoCoinSplitPolicy => "auto_coin_split_policy",
+            WalletSetting::NetworkGenesisHash => "network_genesis_hash",
+        }
+    }
+}
+
+/// Describes when a transaction is treated as complete by the wallet front-end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConfirmationPolicy {
+    /// Complete as soon as the transaction has been accepted into a base node mempool
+    Broadcast,
+    /// Complete once the transaction has been mined
+    Mined,
+    /// Complete once the transaction has been mined with the given number of blocks on top of it
+    Confirmations(u64),
 }
 
 pub enum WriteOperation {
@@ -58,6 +139,67 @@ pub enum WriteOperation {
     Remove(DbKey),
 }
 
+/// The security-relevant wallet actions that are recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WalletAuditEvent {
+    /// The wallet master seed was exported
+    SeedExported,
+    /// An outbound transaction was initiated
+    SendInitiated {
+        tx_id: TxId,
+        destination_public_key: String,
+        amount: MicroTari,
+    },
+    /// An outbound transaction was approved by the operator. This is recorded before the transaction is created, so
+    /// every transaction that leaves the wallet has an audit record.
+    SendApproved {
+        destination_public_key: String,
+        amount: MicroTari,
+    },
+    /// The base node peer used by the wallet was changed
+    BaseNodeChanged { public_key: String, net_address: String },
+    /// Encryption of the wallet database was switched on or off
+    EncryptionToggled { enabled: bool },
+    /// The operator acknowledged that the wallet was detected as cloned and sending was unlocked
+    CloneDetectionAcknowledged,
+    /// A backup bundle containing the node identity and the wallet seed was created
+    BackupBundleCreated,
+}
+
+/// A single entry in the append-only wallet audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub timestamp: NaiveDateTime,
+    pub event: WalletAuditEvent,
+}
+
+impl AuditLogEntry {
+    pub fn new(event: WalletAuditEvent) -> Self {
+        Self {
+            timestamp: Utc::now().naive_utc(),
+            event,
+        }
+    }
+}
+
+/// An event published by one of the wallet services, as recorded in the event journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WalletEvent {
+    TransactionService(TransactionEvent),
+    OutputManager(OutputManagerEvent),
+    /// The wallet database is in use by another wallet instance. Sending is locked until this is acknowledged.
+    WalletCloneDetected(CloneDetectionReason),
+}
+
+/// A single entry in the append-only wallet event journal. Sequence numbers start at 1 and increase by one for every
+/// journaled event, so that a subscriber can resume from the last sequence number it saw.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventJournalEntry {
+    pub sequence: u64,
+    pub timestamp: NaiveDateTime,
+    pub event: WalletEvent,
+}
+
 // Private macro that pulls out all the boiler plate of extracting a DB query result from its variants
 macro_rules! fetch {
     ($db:ident, $key_val:expr, $key_var:ident) => {{
@@ -77,6 +219,14 @@ where T: WalletBackend + 'static
     db: Arc<T>,
 }
 
+impl<T> Clone for WalletDatabase<T>
+where T: WalletBackend + 'static
+{
+    fn clone(&self) -> Self {
+        Self { db: self.db.clone() }
+    }
+}
+
 impl<T> WalletDatabase<T>
 where T: WalletBackend + 'static
 {
@@ -133,7 +283,12 @@ where T: WalletBackend + 'static
                 .ok_or_else(|| WalletStorageError::ValueNotFound(DbKey::Peer(pub_key.clone())))?
             {
                 DbValue::Peer(c) => Ok(*c),
-                DbValue::Peers(_) => Err(WalletStorageError::UnexpectedResult(
+                DbValue::Peers(_) |
+                DbValue::AuditLog(_) |
+                DbValue::EventJournal(_) |
+                DbValue::Setting(_) |
+                DbValue::TransactionLifecycle(_) |
+                DbValue::TransactionLifecycles(_) => Err(WalletStorageError::UnexpectedResult(
                     "Incorrect response from backend.".to_string(),
                 )),
             }
@@ -142,6 +297,260 @@ where T: WalletBackend + 'static
         .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))
         .and_then(|inner_result| inner_result)
     }
+
+    /// Append an event to the wallet audit log. Entries can never be modified or removed once written.
+    pub async fn append_audit_event(&self, event: WalletAuditEvent) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::AuditLogEntry(
+                AuditLogEntry::new(event),
+            )))
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(())
+    }
+
+    /// Retrieve all the audit log entries in the order in which they were recorded
+    pub async fn get_audit_log(&self) -> Result<Vec<AuditLogEntry>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let log = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::AuditLog) {
+            Ok(None) => log_error(
+                DbKey::AuditLog,
+                WalletStorageError::UnexpectedResult("Could not retrieve audit log".to_string()),
+            ),
+            Ok(Some(DbValue::AuditLog(l))) => Ok(l),
+            Ok(Some(other)) => unexpected_result(DbKey::AuditLog, other),
+            Err(e) => log_error(DbKey::AuditLog, e),
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(log)
+    }
+
+    /// Export the full audit log as a JSON array so that it can be archived by the wallet operator
+    pub async fn export_audit_log(&self) -> Result<String, WalletStorageError> {
+        let log = self.get_audit_log().await?;
+        Ok(serde_json::to_string(&log)?)
+    }
+
+    /// Append an event to the wallet event journal and return the journaled entry with its sequence number
+    pub async fn append_journal_event(&self, event: WalletEvent) -> Result<EventJournalEntry, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            match db_clone.write(WriteOperation::Insert(DbKeyValuePair::EventJournalEntry(
+                event,
+                Utc::now().naive_utc(),
+            ))) {
+                Ok(Some(DbValue::EventJournal(mut entries))) if entries.len() == 1 => Ok(entries.remove(0)),
+                Ok(_) => Err(WalletStorageError::UnexpectedResult(
+                    "Journaled event was not returned".to_string(),
+                )),
+                Err(e) => Err(e),
+            }
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    /// Retrieve the event journal entries with a sequence number of at least `from_sequence`, in sequence order
+    pub async fn get_event_journal(&self, from_sequence: u64) -> Result<Vec<EventJournalEntry>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let key = DbKey::EventJournal(from_sequence);
+        let journal = tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => log_error(
+                key,
+                WalletStorageError::UnexpectedResult("Could not retrieve event journal".to_string()),
+            ),
+            Ok(Some(DbValue::EventJournal(j))) => Ok(j),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(journal)
+    }
+
+    /// Export the event journal entries from `from_sequence` on as a JSON array
+    pub async fn export_event_journal(&self, from_sequence: u64) -> Result<String, WalletStorageError> {
+        let journal = self.get_event_journal(from_sequence).await?;
+        Ok(serde_json::to_string(&journal)?)
+    }
+
+    /// Retrieve the canonical lifecycle of a transaction, if the transaction lifecycle coordinator has seen it
+    pub async fn get_transaction_lifecycle(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<TransactionLifecycle>, WalletStorageError>
+    {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::TransactionLifecycle(tx_id)) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::TransactionLifecycle(l))) => Ok(Some(*l)),
+            Ok(Some(other)) => unexpected_result(DbKey::TransactionLifecycle(tx_id), other),
+            Err(e) => log_error(DbKey::TransactionLifecycle(tx_id), e),
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))
+        .and_then(|inner_result| inner_result)
+    }
+
+    /// Retrieve the lifecycles of all transactions, in the order of their transaction ids
+    pub async fn get_transaction_lifecycles(&self) -> Result<Vec<TransactionLifecycle>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let lifecycles = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::TransactionLifecycles) {
+            Ok(None) => log_error(
+                DbKey::TransactionLifecycles,
+                WalletStorageError::UnexpectedResult("Could not retrieve transaction lifecycles".to_string()),
+            ),
+            Ok(Some(DbValue::TransactionLifecycles(l))) => Ok(l),
+            Ok(Some(other)) => unexpected_result(DbKey::TransactionLifecycles, other),
+            Err(e) => log_error(DbKey::TransactionLifecycles, e),
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(lifecycles)
+    }
+
+    /// Store the lifecycle of a transaction, replacing its previous state
+    pub async fn save_transaction_lifecycle(&self, lifecycle: TransactionLifecycle) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::TransactionLifecycle(lifecycle)))
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(())
+    }
+
+    pub async fn get_selected_base_node(&self) -> Result<Option<CommsPublicKey>, WalletStorageError> {
+        self.get_setting::<String>(WalletSetting::SelectedBaseNode)
+            .await?
+            .map(|hex| CommsPublicKey::from_hex(&hex).map_err(|_| WalletStorageError::ConversionError))
+            .transpose()
+    }
+
+    pub async fn set_selected_base_node(&self, public_key: CommsPublicKey) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::SelectedBaseNode, public_key.to_hex())
+            .await
+    }
+
+    pub async fn get_default_fee_per_gram(&self) -> Result<Option<MicroTari>, WalletStorageError> {
+        self.get_setting(WalletSetting::DefaultFeePerGram).await
+    }
+
+    pub async fn set_default_fee_per_gram(&self, fee_per_gram: MicroTari) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::DefaultFeePerGram, fee_per_gram).await
+    }
+
+    pub async fn get_confirmation_policy(&self) -> Result<Option<ConfirmationPolicy>, WalletStorageError> {
+        self.get_setting(WalletSetting::ConfirmationPolicy).await
+    }
+
+    pub async fn set_confirmation_policy(&self, policy: ConfirmationPolicy) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::ConfirmationPolicy, policy).await
+    }
+
+    pub async fn get_last_backup_time(&self) -> Result<Option<NaiveDateTime>, WalletStorageError> {
+        self.get_setting(WalletSetting::LastBackupTime).await
+    }
+
+    pub async fn set_last_backup_time(&self, time: NaiveDateTime) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::LastBackupTime, time).await
+    }
+
+    pub async fn get_faucet_claims_imported(&self) -> Result<Option<String>, WalletStorageError> {
+        self.get_setting(WalletSetting::FaucetClaimsImported).await
+    }
+
+    pub async fn set_faucet_claims_imported(&self, claims_hash: String) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::FaucetClaimsImported, claims_hash).await
+    }
+
+    pub async fn get_instance_heartbeat(&self) -> Result<Option<InstanceHeartbeat>, WalletStorageError> {
+        self.get_setting(WalletSetting::InstanceHeartbeat).await
+    }
+
+    pub async fn set_instance_heartbeat(&self, heartbeat: InstanceHeartbeat) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::InstanceHeartbeat, heartbeat).await
+    }
+
+    pub async fn get_clone_detection(&self) -> Result<Option<CloneDetectionReason>, WalletStorageError> {
+        self.get_setting(WalletSetting::CloneDetected).await
+    }
+
+    pub async fn set_clone_detection(&self, reason: CloneDetectionReason) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::CloneDetected, reason).await
+    }
+
+    pub async fn get_auto_coin_split_policy(&self) -> Result<Option<AutoCoinSplitPolicy>, WalletStorageError> {
+        self.get_setting(WalletSetting::AutoCoinSplitPolicy).await
+    }
+
+    pub async fn set_auto_coin_split_policy(&self, policy: AutoCoinSplitPolicy) -> Result<(), WalletStorageError> {
+        self.set_setting(WalletSetting::AutoCoinSplitPolicy, policy).await
+    }
+
+    /// Check that the wallet was created on the network with the given genesis block hash. A wallet that has not been
+    /// used on any network yet is marked as belonging to this one.
+    pub async fn check_network_genesis_hash(&self, genesis_hash: String) -> Result<(), WalletStorageError> {
+        match self.get_setting::<String>(WalletSetting::NetworkGenesisHash).await? {
+            None => self.set_setting(WalletSetting::NetworkGenesisHash, genesis_hash).await,
+            Some(stored) if stored == genesis_hash => Ok(()),
+            Some(stored) => Err(WalletStorageError::NetworkMismatch(format!(
+                "The wallet belongs to the network with genesis block {} instead of {}",
+                stored, genesis_hash
+            ))),
+        }
+    }
+
+    /// Remove a setting so that the front-end falls back to its default value
+    pub async fn clear_setting(&self, setting: WalletSetting) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::Setting(setting))))
+            .await
+            .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(())
+    }
+
+    async fn get_setting<V: DeserializeOwned>(&self, setting: WalletSetting) -> Result<Option<V>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let value = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Setting(setting)) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::Setting(v))) => Ok(Some(v)),
+            Ok(Some(other)) => unexpected_result(DbKey::Setting(setting), other),
+            Err(e) => log_error(DbKey::Setting(setting), e),
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+
+        match value {
+            None => Ok(None),
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+        }
+    }
+
+    async fn set_setting<V: Serialize>(&self, setting: WalletSetting, value: V) -> Result<(), WalletStorageError> {
+        let value = serde_json::to_string(&value)?;
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::Setting(setting, value)))
+        })
+        .await
+        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        Ok(())
+    }
 }
 
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, WalletStorageError> {
@@ -155,6 +564,11 @@ impl Display for DbKey {
         match self {
             DbKey::Peer(c) => f.write_str(&format!("Peer: {:?}", c)),
             DbKey::Peers => f.write_str(&"Peers".to_string()),
+            DbKey::AuditLog => f.write_str(&"Audit Log".to_string()),
+            DbKey::EventJournal(s) => f.write_str(&format!("Event Journal from: {}", s)),
+            DbKey::Setting(s) => f.write_str(&format!("Setting: {}", s.as_key())),
+            DbKey::TransactionLifecycle(tx_id) => f.write_str(&format!("Transaction Lifecycle: {}", tx_id)),
+            DbKey::TransactionLifecycles => f.write_str(&"Transaction Lifecycles".to_string()),
         }
     }
 }
@@ -164,6 +578,11 @@ impl Display for DbValue {
         match self {
             DbValue::Peer(_) => f.write_str(&"Peer".to_string()),
             DbValue::Peers(_) => f.write_str(&"Peers".to_string()),
+            DbValue::AuditLog(_) => f.write_str(&"Audit Log".to_string()),
+            DbValue::EventJournal(_) => f.write_str(&"Event Journal".to_string()),
+            DbValue::Setting(_) => f.write_str(&"Setting".to_string()),
+            DbValue::TransactionLifecycle(_) => f.write_str(&"Transaction Lifecycle".to_string()),
+            DbValue::TransactionLifecycles(_) => f.write_str(&"Transaction Lifecycles".to_string()),
         }
     }
 }
@@ -182,21 +601,35 @@ fn log_error<T>(req: DbKey, err: WalletStorageError) -> Result<T, WalletStorageE
 mod test {
     use crate::{
         error::WalletStorageError,
+        output_manager_service::handle::OutputManagerEvent,
         storage::{
             connection_manager::run_migration_and_create_sqlite_connection,
-            database::{DbKey, WalletBackend, WalletDatabase},
+            database::{
+                AuditLogEntry,
+                ConfirmationPolicy,
+                DbKey,
+                WalletAuditEvent,
+                WalletBackend,
+                WalletDatabase,
+                WalletEvent,
+                WalletSetting,
+                WriteOperation,
+            },
             memory_db::WalletMemoryDatabase,
             sqlite_db::WalletSqliteDatabase,
         },
+        transaction_lifecycle::lifecycle::{TransactionLifecycle, TransactionLifecycleState},
+        transaction_service::handle::TransactionEvent,
     };
+    use chrono::Utc;
     use rand::rngs::OsRng;
     use tari_comms::{
         multiaddr::Multiaddr,
         peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
         types::{CommsPublicKey, CommsSecretKey},
     };
-    use tari_core::transactions::types::PublicKey;
-    use tari_crypto::keys::PublicKey as PublicKeyTrait;
+    use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+    use tari_crypto::{keys::PublicKey as PublicKeyTrait, tari_utilities::hex::Hex};
     use tari_test_utils::random::string;
     use tempdir::TempDir;
     use tokio::runtime::Runtime;
@@ -251,6 +684,145 @@ mod test {
         let got_peers = runtime.block_on(db.get_peers()).unwrap();
 
         assert_eq!(peers, got_peers);
+
+        assert!(runtime.block_on(db.get_audit_log()).unwrap().is_empty());
+        let events = vec![
+            WalletAuditEvent::BaseNodeChanged {
+                public_key: peers[0].public_key.to_hex(),
+                net_address: "/ip4/1.2.3.4/tcp/9000".to_string(),
+            },
+            WalletAuditEvent::SendInitiated {
+                tx_id: 1,
+                destination_public_key: peers[1].public_key.to_hex(),
+                amount: MicroTari::from(5000),
+            },
+            WalletAuditEvent::SeedExported,
+        ];
+        for e in events.iter() {
+            runtime.block_on(db.append_audit_event(e.clone())).unwrap();
+        }
+        let log = runtime.block_on(db.get_audit_log()).unwrap();
+        assert_eq!(log.iter().map(|e| e.event.clone()).collect::<Vec<_>>(), events);
+
+        let exported = runtime.block_on(db.export_audit_log()).unwrap();
+        let imported: Vec<AuditLogEntry> = serde_json::from_str(&exported).unwrap();
+        assert_eq!(imported, log);
+
+        match db.db.write(WriteOperation::Remove(DbKey::AuditLog)) {
+            Err(WalletStorageError::OperationNotSupported) => (),
+            _ => assert!(false),
+        }
+
+        assert!(runtime.block_on(db.get_event_journal(0)).unwrap().is_empty());
+        let events = vec![
+            WalletEvent::TransactionService(TransactionEvent::ReceivedTransaction(1)),
+            WalletEvent::OutputManager(OutputManagerEvent::FundsMatured(MicroTari::from(100))),
+            WalletEvent::TransactionService(TransactionEvent::TransactionDirectSendResult(2, true)),
+        ];
+        for (i, e) in events.iter().enumerate() {
+            let entry = runtime.block_on(db.append_journal_event(e.clone())).unwrap();
+            assert_eq!(entry.sequence, i as u64 + 1);
+            assert_eq!(&entry.event, e);
+        }
+        let journal = runtime.block_on(db.get_event_journal(0)).unwrap();
+        assert_eq!(journal.iter().map(|e| e.event.clone()).collect::<Vec<_>>(), events);
+        let replayed = runtime.block_on(db.get_event_journal(2)).unwrap();
+        assert_eq!(replayed.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(replayed, journal[1..].to_vec());
+        assert!(runtime.block_on(db.get_event_journal(4)).unwrap().is_empty());
+        match db.db.write(WriteOperation::Remove(DbKey::EventJournal(0))) {
+            Err(WalletStorageError::OperationNotSupported) => (),
+            _ => assert!(false),
+        }
+
+        assert!(runtime.block_on(db.get_transaction_lifecycles()).unwrap().is_empty());
+        assert_eq!(runtime.block_on(db.get_transaction_lifecycle(2)).unwrap(), None);
+        let lifecycles = vec![
+            TransactionLifecycle::new(2, TransactionLifecycleState::Negotiating),
+            TransactionLifecycle::new(1, TransactionLifecycleState::Broadcast),
+        ];
+        for l in lifecycles.iter() {
+            runtime.block_on(db.save_transaction_lifecycle(l.clone())).unwrap();
+        }
+        let updated = TransactionLifecycle::new(2, TransactionLifecycleState::Encumbered);
+        runtime
+            .block_on(db.save_transaction_lifecycle(updated.clone()))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(db.get_transaction_lifecycle(2)).unwrap(),
+            Some(updated.clone())
+        );
+        assert_eq!(runtime.block_on(db.get_transaction_lifecycles()).unwrap(), vec![
+            lifecycles[1].clone(),
+            updated
+        ]);
+        match db.db.write(WriteOperation::Remove(DbKey::TransactionLifecycle(1))) {
+            Err(WalletStorageError::OperationNotSupported) => (),
+            _ => assert!(false),
+        }
+
+        assert_eq!(runtime.block_on(db.get_selected_base_node()).unwrap(), None);
+        assert_eq!(runtime.block_on(db.get_default_fee_per_gram()).unwrap(), None);
+        assert_eq!(runtime.block_on(db.get_confirmation_policy()).unwrap(), None);
+        assert_eq!(runtime.block_on(db.get_last_backup_time()).unwrap(), None);
+
+        runtime
+            .block_on(db.set_selected_base_node(peers[0].public_key.clone()))
+            .unwrap();
+        runtime
+            .block_on(db.set_selected_base_node(peers[1].public_key.clone()))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(db.get_selected_base_node()).unwrap(),
+            Some(peers[1].public_key.clone())
+        );
+
+        runtime
+            .block_on(db.set_default_fee_per_gram(MicroTari::from(25)))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(db.get_default_fee_per_gram()).unwrap(),
+            Some(MicroTari::from(25))
+        );
+
+        runtime
+            .block_on(db.set_confirmation_policy(ConfirmationPolicy::Confirmations(3)))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(db.get_confirmation_policy()).unwrap(),
+            Some(ConfirmationPolicy::Confirmations(3))
+        );
+
+        let backup_time = Utc::now().naive_utc();
+        runtime.block_on(db.set_last_backup_time(backup_time)).unwrap();
+        assert_eq!(runtime.block_on(db.get_last_backup_time()).unwrap(), Some(backup_time));
+
+        assert_eq!(runtime.block_on(db.get_faucet_claims_imported()).unwrap(), None);
+        runtime
+            .block_on(db.set_faucet_claims_imported("abcdef".to_string()))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(db.get_faucet_claims_imported()).unwrap(),
+            Some("abcdef".to_string())
+        );
+
+        runtime
+            .block_on(db.clear_setting(WalletSetting::DefaultFeePerGram))
+            .unwrap();
+        assert_eq!(runtime.block_on(db.get_default_fee_per_gram()).unwrap(), None);
+        assert!(runtime.block_on(db.get_confirmation_policy()).unwrap().is_some());
+
+        // The first network the wallet is checked against is the one it belongs to
+        runtime
+            .block_on(db.check_network_genesis_hash("aabb".to_string()))
+            .unwrap();
+        runtime
+            .block_on(db.check_network_genesis_hash("aabb".to_string()))
+            .unwrap();
+        match runtime.block_on(db.check_network_genesis_hash("ccdd".to_string())) {
+            Err(WalletStorageError::NetworkMismatch(_)) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
     }
 
     #[test]
@@ -22,19 +22,42 @@
 
 use crate::{
     error::WalletStorageError,
-    storage::database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+    output_manager_service::TxId,
+    storage::database::{
+        AuditLogEntry,
+        DbKey,
+        DbKeyValuePair,
+        DbValue,
+        EventJournalEntry,
+        WalletBackend,
+        WriteOperation,
+    },
+    transaction_lifecycle::lifecycle::TransactionLifecycle,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
 };
-use std::sync::{Arc, RwLock};
 use tari_comms::peer_manager::Peer;
 
 #[derive(Default)]
 pub struct InnerDatabase {
     peers: Vec<Peer>,
+    audit_log: Vec<AuditLogEntry>,
+    event_journal: Vec<EventJournalEntry>,
+    settings: HashMap<&'static str, String>,
+    transaction_lifecycles: BTreeMap<TxId, TransactionLifecycle>,
 }
 
 impl InnerDatabase {
     pub fn new() -> Self {
-        Self { peers: Vec::new() }
+        Self {
+            peers: Vec::new(),
+            audit_log: Vec::new(),
+            event_journal: Vec::new(),
+            settings: HashMap::new(),
+            transaction_lifecycles: BTreeMap::new(),
+        }
     }
 }
 
@@ -66,6 +89,22 @@ impl WalletBackend for WalletMemoryDatabase {
                 .find(|v| &v.public_key == pk)
                 .map(|p| DbValue::Peer(Box::new(p.clone()))),
             DbKey::Peers => Some(DbValue::Peers(db.peers.clone())),
+            DbKey::AuditLog => Some(DbValue::AuditLog(db.audit_log.clone())),
+            DbKey::EventJournal(from) => Some(DbValue::EventJournal(
+                db.event_journal
+                    .iter()
+                    .filter(|e| e.sequence >= *from)
+                    .cloned()
+                    .collect(),
+            )),
+            DbKey::Setting(s) => db.settings.get(s.as_key()).map(|v| DbValue::Setting(v.clone())),
+            DbKey::TransactionLifecycle(tx_id) => db
+                .transaction_lifecycles
+                .get(tx_id)
+                .map(|l| DbValue::TransactionLifecycle(Box::new(l.clone()))),
+            DbKey::TransactionLifecycles => Some(DbValue::TransactionLifecycles(
+                db.transaction_lifecycles.values().cloned().collect(),
+            )),
         };
 
         Ok(result)
@@ -81,13 +120,36 @@ impl WalletBackend for WalletMemoryDatabase {
                     }
                     db.peers.push(p)
                 },
+                DbKeyValuePair::AuditLogEntry(e) => db.audit_log.push(e),
+                DbKeyValuePair::EventJournalEntry(event, timestamp) => {
+                    let entry = EventJournalEntry {
+                        sequence: db.event_journal.len() as u64 + 1,
+                        timestamp,
+                        event,
+                    };
+                    db.event_journal.push(entry.clone());
+                    return Ok(Some(DbValue::EventJournal(vec![entry])));
+                },
+                DbKeyValuePair::Setting(s, v) => {
+                    db.settings.insert(s.as_key(), v);
+                },
+                DbKeyValuePair::TransactionLifecycle(l) => {
+                    db.transaction_lifecycles.insert(l.tx_id, l);
+                },
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::Peer(pk) => match db.peers.iter().position(|p| p.public_key == pk) {
                     None => return Err(WalletStorageError::ValueNotFound(DbKey::Peer(pk))),
                     Some(pos) => return Ok(Some(DbValue::Peer(Box::new(db.peers.remove(pos))))),
                 },
-                DbKey::Peers => {
+                DbKey::Setting(s) => {
+                    return Ok(db.settings.remove(s.as_key()).map(DbValue::Setting));
+                },
+                DbKey::Peers |
+                DbKey::AuditLog |
+                DbKey::EventJournal(_) |
+                DbKey::TransactionLifecycle(_) |
+                DbKey::TransactionLifecycles => {
                     return Err(WalletStorageError::OperationNotSupported);
                 },
             },
@@ -22,30 +22,36 @@
 
 use crate::{
     error::WalletStorageError,
-    schema::peers,
-    storage::database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+    output_manager_service::TxId,
+    schema::{audit_log, event_journal, peers, transaction_lifecycles, wallet_settings},
+    storage::{
+        connection_manager::WalletDbConnection,
+        database::{AuditLogEntry, DbKey, DbKeyValuePair, DbValue, EventJournalEntry, WalletBackend, WriteOperation},
+    },
+    transaction_lifecycle::lifecycle::{TransactionLifecycle, TransactionLifecycleState},
 };
+use chrono::NaiveDateTime;
 use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
-use std::{
-    convert::TryFrom,
-    sync::{Arc, Mutex},
-};
+use std::convert::TryFrom;
 use tari_comms::peer_manager::Peer;
 use tari_crypto::tari_utilities::ByteArray;
 
 /// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
 pub struct WalletSqliteDatabase {
-    database_connection: Arc<Mutex<SqliteConnection>>,
+    database_connection: WalletDbConnection,
 }
 impl WalletSqliteDatabase {
-    pub fn new(database_connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(database_connection: WalletDbConnection) -> Self {
         Self { database_connection }
     }
 }
 
 impl WalletBackend for WalletSqliteDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, WalletStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| WalletStorageError::R2d2Error)?;
 
         let result = match key {
             DbKey::Peer(pk) => match PeerSql::find(&pk.to_vec(), &(*conn)) {
@@ -59,13 +65,46 @@ impl WalletBackend for WalletSqliteDatabase {
                     .map(|c| Peer::try_from(c.clone()))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
+            DbKey::AuditLog => Some(DbValue::AuditLog(
+                AuditLogEntrySql::index(&conn)?
+                    .into_iter()
+                    .map(AuditLogEntry::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::EventJournal(from) => Some(DbValue::EventJournal(
+                EventJournalEntrySql::index_from(*from, &conn)?
+                    .into_iter()
+                    .map(EventJournalEntry::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::Setting(s) => match WalletSettingSql::find(s.as_key(), &(*conn)) {
+                Ok(s) => Some(DbValue::Setting(s.value)),
+                Err(WalletStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::TransactionLifecycle(tx_id) => match TransactionLifecycleSql::find(*tx_id, &(*conn)) {
+                Ok(l) => Some(DbValue::TransactionLifecycle(Box::new(TransactionLifecycle::try_from(
+                    l,
+                )?))),
+                Err(WalletStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::TransactionLifecycles => Some(DbValue::TransactionLifecycles(
+                TransactionLifecycleSql::index(&conn)?
+                    .into_iter()
+                    .map(TransactionLifecycle::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
         };
 
         Ok(result)
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, WalletStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| WalletStorageError::R2d2Error)?;
 
         match op {
             WriteOperation::Insert(kvp) => match kvp {
@@ -75,6 +114,24 @@ impl WalletBackend for WalletSqliteDatabase {
                     }
                     PeerSql::try_from(p)?.commit(&conn)?;
                 },
+                DbKeyValuePair::AuditLogEntry(e) => AuditLogEntrySql::try_from(e)?.commit(&conn)?,
+                DbKeyValuePair::EventJournalEntry(event, timestamp) => {
+                    // Sequence numbers are assigned here rather than by Sqlite so that they can be returned, which is
+                    // safe because all writes go through the single write connection
+                    let entry = EventJournalEntry {
+                        sequence: EventJournalEntrySql::last_sequence(&conn)?.unwrap_or(0) as u64 + 1,
+                        timestamp,
+                        event,
+                    };
+                    EventJournalEntrySql::try_from(entry.clone())?.commit(&conn)?;
+                    return Ok(Some(DbValue::EventJournal(vec![entry])));
+                },
+                DbKeyValuePair::Setting(s, v) => WalletSettingSql {
+                    key: s.as_key().to_string(),
+                    value: v,
+                }
+                .commit(&conn)?,
+                DbKeyValuePair::TransactionLifecycle(l) => TransactionLifecycleSql::from(l).commit(&conn)?,
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::Peer(k) => match PeerSql::find(&k.to_vec(), &(*conn)) {
@@ -85,7 +142,19 @@ impl WalletBackend for WalletSqliteDatabase {
                     Err(WalletStorageError::DieselError(DieselError::NotFound)) => (),
                     Err(e) => return Err(e),
                 },
-                DbKey::Peers => return Err(WalletStorageError::OperationNotSupported),
+                DbKey::Setting(s) => match WalletSettingSql::find(s.as_key(), &(*conn)) {
+                    Ok(s) => {
+                        s.delete(&conn)?;
+                        return Ok(Some(DbValue::Setting(s.value)));
+                    },
+                    Err(WalletStorageError::DieselError(DieselError::NotFound)) => (),
+                    Err(e) => return Err(e),
+                },
+                DbKey::Peers |
+                DbKey::AuditLog |
+                DbKey::EventJournal(_) |
+                DbKey::TransactionLifecycle(_) |
+                DbKey::TransactionLifecycles => return Err(WalletStorageError::OperationNotSupported),
             },
         }
 
@@ -151,3 +220,197 @@ impl TryFrom<Peer> for PeerSql {
         })
     }
 }
+
+/// A Sql version of the AuditLogEntry struct
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "audit_log"]
+struct AuditLogEntrySql {
+    id: Option<i64>,
+    event: String,
+    timestamp: NaiveDateTime,
+}
+
+impl AuditLogEntrySql {
+    /// Append this entry to the audit log
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::insert_into(audit_log::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all audit log entries in the order they were recorded
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<AuditLogEntrySql>, WalletStorageError> {
+        Ok(audit_log::table
+            .order(audit_log::id.asc())
+            .load::<AuditLogEntrySql>(conn)?)
+    }
+}
+
+impl TryFrom<AuditLogEntrySql> for AuditLogEntry {
+    type Error = WalletStorageError;
+
+    fn try_from(e: AuditLogEntrySql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            timestamp: e.timestamp,
+            event: serde_json::from_str(&e.event)?,
+        })
+    }
+}
+
+impl TryFrom<AuditLogEntry> for AuditLogEntrySql {
+    type Error = WalletStorageError;
+
+    fn try_from(e: AuditLogEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: None,
+            event: serde_json::to_string(&e.event)?,
+            timestamp: e.timestamp,
+        })
+    }
+}
+
+/// A Sql version of the EventJournalEntry struct
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "event_journal"]
+struct EventJournalEntrySql {
+    sequence: i64,
+    event: String,
+    timestamp: NaiveDateTime,
+}
+
+impl EventJournalEntrySql {
+    /// Append this entry to the event journal
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::insert_into(event_journal::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return the journal entries with a sequence number of at least `from_sequence`, in sequence order
+    pub fn index_from(
+        from_sequence: u64,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<EventJournalEntrySql>, WalletStorageError>
+    {
+        Ok(event_journal::table
+            .filter(event_journal::sequence.ge(from_sequence as i64))
+            .order(event_journal::sequence.asc())
+            .load::<EventJournalEntrySql>(conn)?)
+    }
+
+    /// The sequence number of the last journaled entry, if any
+    pub fn last_sequence(conn: &SqliteConnection) -> Result<Option<i64>, WalletStorageError> {
+        Ok(event_journal::table
+            .select(diesel::dsl::max(event_journal::sequence))
+            .first::<Option<i64>>(conn)?)
+    }
+}
+
+impl TryFrom<EventJournalEntrySql> for EventJournalEntry {
+    type Error = WalletStorageError;
+
+    fn try_from(e: EventJournalEntrySql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sequence: e.sequence as u64,
+            timestamp: e.timestamp,
+            event: serde_json::from_str(&e.event)?,
+        })
+    }
+}
+
+impl TryFrom<EventJournalEntry> for EventJournalEntrySql {
+    type Error = WalletStorageError;
+
+    fn try_from(e: EventJournalEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sequence: e.sequence as i64,
+            event: serde_json::to_string(&e.event)?,
+            timestamp: e.timestamp,
+        })
+    }
+}
+
+/// A single persisted wallet setting
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "wallet_settings"]
+struct WalletSettingSql {
+    key: String,
+    value: String,
+}
+
+impl WalletSettingSql {
+    /// Write this setting to the database, replacing any existing value
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::replace_into(wallet_settings::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(key: &str, conn: &SqliteConnection) -> Result<WalletSettingSql, WalletStorageError> {
+        Ok(wallet_settings::table
+            .filter(wallet_settings::key.eq(key))
+            .first::<WalletSettingSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::delete(wallet_settings::table.filter(wallet_settings::key.eq(&self.key))).execute(conn)?;
+        Ok(())
+    }
+}
+
+/// A Sql version of the TransactionLifecycle struct
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "transaction_lifecycles"]
+struct TransactionLifecycleSql {
+    tx_id: i64,
+    state: i32,
+    timestamp: NaiveDateTime,
+}
+
+impl TransactionLifecycleSql {
+    /// Write this lifecycle to the database, replacing the existing lifecycle of the transaction
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
+        diesel::replace_into(transaction_lifecycles::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return all lifecycles in the order of their transaction ids
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<TransactionLifecycleSql>, WalletStorageError> {
+        Ok(transaction_lifecycles::table
+            .order(transaction_lifecycles::tx_id.asc())
+            .load::<TransactionLifecycleSql>(conn)?)
+    }
+
+    pub fn find(tx_id: TxId, conn: &SqliteConnection) -> Result<TransactionLifecycleSql, WalletStorageError> {
+        Ok(transaction_lifecycles::table
+            .filter(transaction_lifecycles::tx_id.eq(tx_id as i64))
+            .first::<TransactionLifecycleSql>(conn)?)
+    }
+}
+
+impl TryFrom<TransactionLifecycleSql> for TransactionLifecycle {
+    type Error = WalletStorageError;
+
+    fn try_from(l: TransactionLifecycleSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: l.tx_id as u64,
+            state: TransactionLifecycleState::try_from(l.state)?,
+            timestamp: l.timestamp,
+        })
+    }
+}
+
+impl From<TransactionLifecycle> for TransactionLifecycleSql {
+    fn from(l: TransactionLifecycle) -> Self {
+        Self {
+            tx_id: l.tx_id as i64,
+            state: l.state as i32,
+            timestamp: l.timestamp,
+        }
+    }
+}
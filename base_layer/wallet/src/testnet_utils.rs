@@ -140,6 +140,8 @@ pub fn create_wallet(
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses: Vec::new(),
+        additional_public_addresses: Vec::new(),
     };
 
     let config = WalletConfig {
@@ -713,6 +715,7 @@ pub fn complete_sent_transaction<
                 message: p.message.clone(),
                 status: TransactionStatus::Completed,
                 timestamp: Utc::now().naive_utc(),
+                fee_priority: None,
             };
             wallet.runtime.block_on(
                 wallet
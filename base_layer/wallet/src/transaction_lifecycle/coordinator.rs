@@ -0,0 +1,187 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    error::WalletError,
+    output_manager_service::{
+        error::{OutputManagerError, OutputManagerStorageError},
+        handle::OutputManagerHandle,
+        TxId,
+    },
+    storage::database::{WalletBackend, WalletDatabase},
+    transaction_lifecycle::lifecycle::{lifecycle_transitions, TransactionLifecycle, TransactionLifecycleState},
+    transaction_service::handle::{TransactionEventReceiver, TransactionServiceHandle},
+};
+use futures::{stream::Fuse, StreamExt};
+use log::*;
+
+const LOG_TARGET: &str = "wallet::transaction_lifecycle::coordinator";
+
+/// The coordinator owns the lifecycle of every wallet transaction. It advances the persisted lifecycle as the
+/// Transaction Service reports progress and drives the Output Manager Service whenever its outputs disagree with the
+/// lifecycle, e.g. when the outputs of a cancelled transaction are still encumbered.
+/// Follows the Transaction Service events to keep the canonical lifecycle of every transaction, and reconciles the
+/// Output Manager Service with it: the outputs of cancelled transactions are released and mined transactions are
+/// confirmed. The services still negotiate, encumber, broadcast and cancel transactions themselves; the coordinator
+/// does not drive them.
+pub struct TransactionLifecycleCoordinator<T>
+where T: WalletBackend + 'static
+{
+    db: WalletDatabase<T>,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+}
+
+impl<T> TransactionLifecycleCoordinator<T>
+where T: WalletBackend + 'static
+{
+    pub fn new(
+        db: WalletDatabase<T>,
+        transaction_service: TransactionServiceHandle,
+        output_manager_service: OutputManagerHandle,
+    ) -> Self
+    {
+        Self {
+            db,
+            transaction_service,
+            output_manager_service,
+        }
+    }
+
+    /// Resume the lifecycles that were left unfinished when the wallet was last shut down and then follow the
+    /// Transaction Service events until the service stops
+    pub async fn run(mut self, mut transaction_events: Fuse<TransactionEventReceiver>) {
+        if let Err(e) = self.resume().await {
+            warn!(target: LOG_TARGET, "Could not resume transaction lifecycles: {:?}", e);
+        }
+
+        while let Some(event) = transaction_events.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Transaction service events were missed by the lifecycle coordinator: {:?}", e
+                    );
+                    continue;
+                },
+            };
+            for (tx_id, state) in lifecycle_transitions(&event) {
+                if let Err(e) = self.advance(tx_id, state).await {
+                    error!(
+                        target: LOG_TARGET,
+                        "Could not move transaction {} to {}: {:?}", tx_id, state, e
+                    );
+                }
+            }
+        }
+        debug!(target: LOG_TARGET, "Transaction lifecycle coordinator stopped");
+    }
+
+    /// Catch up with the transitions that were missed while the wallet was not running, using the transaction status
+    /// recorded by the Transaction Service
+    pub async fn resume(&mut self) -> Result<(), WalletError> {
+        let unfinished = self
+            .db
+            .get_transaction_lifecycles()
+            .await?
+            .into_iter()
+            .filter(|l| !l.state.is_final())
+            .collect::<Vec<_>>();
+        if unfinished.is_empty() {
+            return Ok(());
+        }
+
+        let completed_transactions = self.transaction_service.get_completed_transactions().await?;
+        for lifecycle in unfinished {
+            let state = completed_transactions
+                .get(&lifecycle.tx_id)
+                .and_then(|tx| TransactionLifecycleState::from_transaction_status(&tx.status))
+                .unwrap_or(lifecycle.state);
+            self.advance(lifecycle.tx_id, state).await?;
+        }
+        Ok(())
+    }
+
+    /// Move the lifecycle of a transaction to `state` and bring the Output Manager Service in line with it. A
+    /// transition that is not valid from the current state, e.g. from an event that arrived late, is ignored and leaves
+    /// the Output Manager Service untouched. Returns the lifecycle as it stands afterwards.
+    pub async fn advance(
+        &mut self,
+        tx_id: TxId,
+        state: TransactionLifecycleState,
+    ) -> Result<TransactionLifecycle, WalletError>
+    {
+        let mut lifecycle = match self.db.get_transaction_lifecycle(tx_id).await? {
+            Some(current) if current.state == state => current,
+            Some(current) if !current.state.can_transition_to(state) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ignoring transition of transaction {} from {} to {}", tx_id, current.state, state
+                );
+                return Ok(current);
+            },
+            _ => {
+                let lifecycle = TransactionLifecycle::new(tx_id, state);
+                self.db.save_transaction_lifecycle(lifecycle.clone()).await?;
+                debug!(target: LOG_TARGET, "Transaction {} moved to {}", tx_id, state);
+                lifecycle
+            },
+        };
+
+        match lifecycle.state {
+            // The Transaction Service releases the outputs of the transactions it cancels, unless the Output Manager
+            // could not be reached at the time
+            TransactionLifecycleState::Cancelled => {
+                if self.has_pending_outputs(tx_id).await? {
+                    info!(
+                        target: LOG_TARGET,
+                        "Releasing the outputs of cancelled transaction {}", tx_id
+                    );
+                    self.output_manager_service.cancel_transaction(tx_id).await?;
+                }
+            },
+            // A mined transaction is confirmed once the Output Manager has no outputs pending on it
+            TransactionLifecycleState::Mined => {
+                if !self.has_pending_outputs(tx_id).await? {
+                    lifecycle = TransactionLifecycle::new(tx_id, TransactionLifecycleState::Confirmed);
+                    self.db.save_transaction_lifecycle(lifecycle.clone()).await?;
+                    debug!(target: LOG_TARGET, "Transaction {} confirmed", tx_id);
+                }
+            },
+            _ => (),
+        }
+
+        Ok(lifecycle)
+    }
+
+    // Whether the Output Manager Service still holds encumbered outputs for the transaction
+    async fn has_pending_outputs(&mut self, tx_id: TxId) -> Result<bool, WalletError> {
+        match self.output_manager_service.get_pending_transaction(tx_id).await {
+            Ok(_) => Ok(true),
+            Err(OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound(_))) => {
+                Ok(false)
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+}
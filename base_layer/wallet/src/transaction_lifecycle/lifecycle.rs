@@ -0,0 +1,200 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    error::WalletStorageError,
+    output_manager_service::TxId,
+    transaction_service::{handle::TransactionEvent, storage::database::TransactionStatus},
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Error, Formatter},
+};
+
+/// The stages a wallet transaction moves through, from its negotiation with the counterparty until its outputs are
+/// confirmed on the blockchain or it is cancelled. Transitions only move forward and `Confirmed` and `Cancelled` are
+/// final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TransactionLifecycleState {
+    /// The transaction is being negotiated with the counterparty
+    Negotiating,
+    /// The negotiation is complete and the outputs of the transaction are encumbered by the output manager
+    Encumbered,
+    /// The transaction has been submitted to a base node mempool
+    Broadcast,
+    /// The transaction has been mined but the output manager has not confirmed its outputs yet
+    Mined,
+    /// The transaction has been mined and its outputs are spent or spendable
+    Confirmed,
+    /// The transaction was cancelled or replaced and its outputs released
+    Cancelled,
+}
+
+impl TransactionLifecycleState {
+    /// Whether the transaction has left the lifecycle, after which its state never changes again
+    pub fn is_final(self) -> bool {
+        self == TransactionLifecycleState::Confirmed || self == TransactionLifecycleState::Cancelled
+    }
+
+    /// Whether a transaction in this state may move to `next`. A transaction can be cancelled up to the point it is
+    /// mined and can otherwise only move forward, possibly skipping states whose events were missed.
+    pub fn can_transition_to(self, next: TransactionLifecycleState) -> bool {
+        if self.is_final() {
+            return false;
+        }
+        match next {
+            TransactionLifecycleState::Cancelled => self != TransactionLifecycleState::Mined,
+            next => next > self,
+        }
+    }
+
+    /// The lifecycle state matching the status of a transaction in the Transaction Service database, if the status
+    /// pins it down
+    pub fn from_transaction_status(status: &TransactionStatus) -> Option<TransactionLifecycleState> {
+        match status {
+            TransactionStatus::Pending => Some(TransactionLifecycleState::Negotiating),
+            TransactionStatus::Completed => Some(TransactionLifecycleState::Encumbered),
+            TransactionStatus::Broadcast => Some(TransactionLifecycleState::Broadcast),
+            TransactionStatus::Mined => Some(TransactionLifecycleState::Mined),
+            TransactionStatus::Cancelled => Some(TransactionLifecycleState::Cancelled),
+            TransactionStatus::Imported | TransactionStatus::Reconstructed => None,
+        }
+    }
+}
+
+impl TryFrom<i32> for TransactionLifecycleState {
+    type Error = WalletStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TransactionLifecycleState::Negotiating),
+            1 => Ok(TransactionLifecycleState::Encumbered),
+            2 => Ok(TransactionLifecycleState::Broadcast),
+            3 => Ok(TransactionLifecycleState::Mined),
+            4 => Ok(TransactionLifecycleState::Confirmed),
+            5 => Ok(TransactionLifecycleState::Cancelled),
+            _ => Err(WalletStorageError::ConversionError),
+        }
+    }
+}
+
+impl Display for TransactionLifecycleState {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            TransactionLifecycleState::Negotiating => f.write_str("Negotiating"),
+            TransactionLifecycleState::Encumbered => f.write_str("Encumbered"),
+            TransactionLifecycleState::Broadcast => f.write_str("Broadcast"),
+            TransactionLifecycleState::Mined => f.write_str("Mined"),
+            TransactionLifecycleState::Confirmed => f.write_str("Confirmed"),
+            TransactionLifecycleState::Cancelled => f.write_str("Cancelled"),
+        }
+    }
+}
+
+/// The canonical state of a single wallet transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionLifecycle {
+    pub tx_id: TxId,
+    pub state: TransactionLifecycleState,
+    /// The time of the last transition
+    pub timestamp: NaiveDateTime,
+}
+
+impl TransactionLifecycle {
+    pub fn new(tx_id: TxId, state: TransactionLifecycleState) -> Self {
+        Self {
+            tx_id,
+            state,
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// The lifecycle transitions signalled by a Transaction Service event. A replacement cancels the original transaction
+/// and starts the negotiation of its replacement.
+pub fn lifecycle_transitions(event: &TransactionEvent) -> Vec<(TxId, TransactionLifecycleState)> {
+    match event {
+        TransactionEvent::ReceivedTransaction(tx_id) | TransactionEvent::TransactionNegotiationProgress(tx_id, _) => {
+            vec![(*tx_id, TransactionLifecycleState::Negotiating)]
+        },
+        // The outbound outputs are fully encumbered once the reply is received and the inbound outputs once the
+        // finalized transaction is received
+        TransactionEvent::ReceivedTransactionReply(tx_id) | TransactionEvent::ReceivedFinalizedTransaction(tx_id) => {
+            vec![(*tx_id, TransactionLifecycleState::Encumbered)]
+        },
+        TransactionEvent::TransactionBroadcast(tx_id) => vec![(*tx_id, TransactionLifecycleState::Broadcast)],
+        TransactionEvent::TransactionMined(tx_id) => vec![(*tx_id, TransactionLifecycleState::Mined)],
+        TransactionEvent::TransactionCancelled(tx_id) => vec![(*tx_id, TransactionLifecycleState::Cancelled)],
+        TransactionEvent::TransactionReplaced(tx_id, replacement_tx_id) => vec![
+            (*tx_id, TransactionLifecycleState::Cancelled),
+            (*replacement_tx_id, TransactionLifecycleState::Negotiating),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        transaction_lifecycle::lifecycle::{lifecycle_transitions, TransactionLifecycleState},
+        transaction_service::handle::TransactionEvent,
+    };
+
+    #[test]
+    fn lifecycle_transitions_only_move_forward() {
+        use TransactionLifecycleState::*;
+
+        assert!(Negotiating.can_transition_to(Encumbered));
+        assert!(Negotiating.can_transition_to(Mined));
+        assert!(Broadcast.can_transition_to(Mined));
+        assert!(Mined.can_transition_to(Confirmed));
+        assert!(!Broadcast.can_transition_to(Encumbered));
+        assert!(!Mined.can_transition_to(Mined));
+
+        assert!(Negotiating.can_transition_to(Cancelled));
+        assert!(Broadcast.can_transition_to(Cancelled));
+        assert!(!Mined.can_transition_to(Cancelled));
+
+        for state in &[Negotiating, Encumbered, Broadcast, Mined, Confirmed, Cancelled] {
+            assert!(!Confirmed.can_transition_to(*state));
+            assert!(!Cancelled.can_transition_to(*state));
+        }
+    }
+
+    #[test]
+    fn replacement_cancels_the_original_transaction() {
+        assert_eq!(
+            lifecycle_transitions(&TransactionEvent::TransactionReplaced(1, 2)),
+            vec![
+                (1, TransactionLifecycleState::Cancelled),
+                (2, TransactionLifecycleState::Negotiating)
+            ]
+        );
+        assert_eq!(lifecycle_transitions(&TransactionEvent::TransactionMined(3)), vec![(
+            3,
+            TransactionLifecycleState::Mined
+        )]);
+        assert!(lifecycle_transitions(&TransactionEvent::TransactionMinedRequestTimedOut(3)).is_empty());
+    }
+}
@@ -20,7 +20,9 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::transaction_service::storage::database::FeePriority;
 use std::time::Duration;
+use tari_core::transactions::tari_amount::{uT, MicroTari};
 
 #[derive(Clone)]
 pub struct TransactionServiceConfig {
@@ -30,6 +32,38 @@ pub struct TransactionServiceConfig {
     pub mempool_broadcast_timeout: Duration,
     pub initial_base_node_mined_timeout: Duration,
     pub base_node_mined_timeout: Duration,
+    // The number of additional random base nodes whose mempools are queried once our base node has accepted a
+    // transaction, to confirm that it has propagated through the network. Zero disables propagation sampling.
+    pub propagation_sample_size: usize,
+    pub propagation_sample_timeout: Duration,
+    // The fee per gram used for each fee preset when no fee estimator has been set, or the estimator has no estimate
+    pub slow_fee_per_gram: MicroTari,
+    pub normal_fee_per_gram: MicroTari,
+    pub fast_fee_per_gram: MicroTari,
+    // How long the record of a processed peer message is kept to reject replays of it, and how often old records are
+    // pruned
+    pub processed_message_retention: Duration,
+    pub processed_message_prune_interval: Duration,
+    // Whether this wallet answers the transaction status queries of its counterparties. Disabling this stops peers
+    // from learning whether this wallet is online, at the cost of not automatically resolving stuck pending
+    // transactions.
+    pub respond_to_status_queries: bool,
+    // How often pending outbound transactions are checked, and how old they must be, before the recipient is asked
+    // whether it has seen them
+    pub pending_transaction_status_query_interval: Duration,
+    // How often the scheduled payments are checked for payments that have fallen due
+    pub scheduled_payment_check_interval: Duration,
+}
+
+impl TransactionServiceConfig {
+    /// The configured fee per gram for the given fee preset
+    pub fn fee_per_gram(&self, priority: FeePriority) -> MicroTari {
+        match priority {
+            FeePriority::Slow => self.slow_fee_per_gram,
+            FeePriority::Normal => self.normal_fee_per_gram,
+            FeePriority::Fast => self.fast_fee_per_gram,
+        }
+    }
 }
 
 impl Default for TransactionServiceConfig {
@@ -39,6 +73,16 @@ impl Default for TransactionServiceConfig {
             mempool_broadcast_timeout: Duration::from_secs(30),
             initial_base_node_mined_timeout: Duration::from_secs(5),
             base_node_mined_timeout: Duration::from_secs(30),
+            propagation_sample_size: 0,
+            propagation_sample_timeout: Duration::from_secs(30),
+            slow_fee_per_gram: 10 * uT,
+            normal_fee_per_gram: 25 * uT,
+            fast_fee_per_gram: 50 * uT,
+            processed_message_retention: Duration::from_secs(3 * 24 * 60 * 60),
+            processed_message_prune_interval: Duration::from_secs(60 * 60),
+            respond_to_status_queries: true,
+            pending_transaction_status_query_interval: Duration::from_secs(30 * 60),
+            scheduled_payment_check_interval: Duration::from_secs(60),
         }
     }
 }
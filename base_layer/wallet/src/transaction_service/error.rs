@@ -85,6 +85,17 @@ pub enum TransactionServiceError {
     UnexpectedBaseNodeResponse,
     /// The current transaction has been cancelled
     TransactionCancelled,
+    /// Only outbound transactions that have not been broadcast can be replaced
+    TransactionNotReplaceable,
+    /// The fee of a replacement transaction must be higher than the fee of the transaction it replaces
+    FeeNotIncreased,
+    /// Sending is locked until a detected wallet clone has been acknowledged
+    SendingLocked,
+    /// The interval of a scheduled payment must be at least a second
+    InvalidPaymentInterval,
+    /// No scheduled payment exists with the given id
+    #[error(no_from, non_std)]
+    ScheduledPaymentNotFound(u64),
     DhtOutboundError(DhtOutboundError),
     OutputManagerError(OutputManagerError),
     TransportChannelError(TransportChannelError),
@@ -100,6 +111,8 @@ pub enum TransactionServiceError {
     NodeIdError(NodeIdError),
     BroadcastRecvError(RecvError),
     OneshotCancelled(Canceled),
+    #[error(msg_embedded, no_from, non_std)]
+    BlockingTaskSpawnError(String),
 }
 
 #[derive(Debug, Error)]
@@ -21,20 +21,54 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    output_manager_service::TxId,
+    output_manager_service::{service::TransactionSendOptions, TxId},
     transaction_service::{
         error::TransactionServiceError,
         service::PendingCoinbaseSpendingKey,
-        storage::database::{CompletedTransaction, InboundTransaction, OutboundTransaction},
+        storage::database::{
+            CompletedTransaction,
+            FeePriority,
+            InboundTransaction,
+            OutboundTransaction,
+            ScheduledPayment,
+        },
     },
 };
+use chrono::NaiveDateTime;
 use futures::{stream::Fuse, StreamExt};
-use std::{collections::HashMap, fmt, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 use tari_comms::types::CommsPublicKey;
-use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction};
+use tari_core::{
+    mempool::TxRejectionReason,
+    transactions::{tari_amount::MicroTari, transaction::Transaction, transaction_protocol::proto},
+};
 use tari_service_framework::reply_channel::SenderService;
 use tokio::sync::broadcast;
 use tower::Service;
+
+/// Resolves a fee preset to a fee per gram at the time a transaction is sent, e.g. from a fee estimate provided by
+/// the client. If it returns `None` the fee per gram configured for the preset is used.
+#[derive(Clone)]
+pub struct FeeEstimator(Arc<dyn Fn(FeePriority) -> Option<MicroTari> + Send + Sync>);
+
+impl FeeEstimator {
+    pub fn new<F>(estimate: F) -> Self
+    where F: Fn(FeePriority) -> Option<MicroTari> + Send + Sync + 'static {
+        Self(Arc::new(estimate))
+    }
+
+    pub fn estimate(&self, priority: FeePriority) -> Option<MicroTari> {
+        (self.0)(priority)
+    }
+}
+
+impl fmt::Debug for FeeEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FeeEstimator")
+    }
+}
+
 /// API Request enum
 #[derive(Debug)]
 pub enum TransactionServiceRequest {
@@ -42,13 +76,24 @@ pub enum TransactionServiceRequest {
     GetPendingOutboundTransactions,
     GetCompletedTransactions,
     SetBaseNodePublicKey(CommsPublicKey),
-    SendTransaction((CommsPublicKey, MicroTari, MicroTari, String)),
+    SendTransaction((CommsPublicKey, MicroTari, MicroTari, String, TransactionSendOptions)),
+    SendTransactionWithPriority((CommsPublicKey, MicroTari, FeePriority, String)),
+    SetFeeEstimator(Option<FeeEstimator>),
+    SetSendingLocked(bool),
+    GetFeePerGram(FeePriority),
     CancelTransaction(TxId),
+    RebroadcastWithHigherFee((TxId, MicroTari)),
+    QueryTransactionStatus(TxId),
+    GetRawTransaction(TxId),
     RequestCoinbaseSpendingKey((MicroTari, u64)),
     CompleteCoinbaseTransaction((TxId, Transaction)),
     CancelPendingCoinbaseTransaction(TxId),
     ImportUtxo(MicroTari, CommsPublicKey, String),
     SubmitTransaction((TxId, Transaction, MicroTari, MicroTari, String)),
+    ReconstructTransactionHistory,
+    SchedulePayment((CommsPublicKey, MicroTari, Duration, NaiveDateTime, FeePriority, String)),
+    CancelScheduledPayment(u64),
+    GetScheduledPayments,
     #[cfg(feature = "test_harness")]
     CompletePendingOutboundTransaction(CompletedTransaction),
     #[cfg(feature = "test_harness")]
@@ -68,10 +113,23 @@ impl fmt::Display for TransactionServiceRequest {
             Self::GetPendingOutboundTransactions => f.write_str("GetPendingOutboundTransactions"),
             Self::GetCompletedTransactions => f.write_str("GetCompletedTransactions"),
             Self::SetBaseNodePublicKey(k) => f.write_str(&format!("SetBaseNodePublicKey ({})", k)),
-            Self::SendTransaction((k, v, _, msg)) => {
+            Self::SendTransaction((k, v, _, msg, _)) => {
                 f.write_str(&format!("SendTransaction (to {}, {}, {})", k, v, msg))
             },
+            Self::SendTransactionWithPriority((k, v, p, msg)) => f.write_str(&format!(
+                "SendTransactionWithPriority (to {}, {}, {}, {})",
+                k, v, p, msg
+            )),
+            Self::SetFeeEstimator(e) => f.write_str(&format!("SetFeeEstimator (set={})", e.is_some())),
+            Self::SetSendingLocked(l) => f.write_str(&format!("SetSendingLocked ({})", l)),
+            Self::GetFeePerGram(p) => f.write_str(&format!("GetFeePerGram ({})", p)),
             Self::CancelTransaction(t) => f.write_str(&format!("CancelTransaction ({})", t)),
+            Self::RebroadcastWithHigherFee((t, fee_per_gram)) => f.write_str(&format!(
+                "RebroadcastWithHigherFee ({}, fee_per_gram={})",
+                t, fee_per_gram
+            )),
+            Self::QueryTransactionStatus(t) => f.write_str(&format!("QueryTransactionStatus ({})", t)),
+            Self::GetRawTransaction(t) => f.write_str(&format!("GetRawTransaction ({})", t)),
             Self::RequestCoinbaseSpendingKey((v, h)) => {
                 f.write_str(&format!("RequestCoinbaseSpendingKey ({}, maturity={})", v, h))
             },
@@ -81,6 +139,17 @@ impl fmt::Display for TransactionServiceRequest {
             },
             Self::ImportUtxo(v, k, msg) => f.write_str(&format!("ImportUtxo (from {}, {}, {})", k, v, msg)),
             Self::SubmitTransaction((id, _, _, _, _)) => f.write_str(&format!("SubmitTransaction ({})", id)),
+            Self::ReconstructTransactionHistory => f.write_str("ReconstructTransactionHistory"),
+            Self::SchedulePayment((k, v, interval, start, _, msg)) => f.write_str(&format!(
+                "SchedulePayment (to {}, {}, every {}s from {}, {})",
+                k,
+                v,
+                interval.as_secs(),
+                start,
+                msg
+            )),
+            Self::CancelScheduledPayment(id) => f.write_str(&format!("CancelScheduledPayment ({})", id)),
+            Self::GetScheduledPayments => f.write_str("GetScheduledPayments"),
             #[cfg(feature = "test_harness")]
             Self::CompletePendingOutboundTransaction(tx) => {
                 f.write_str(&format!("CompletePendingOutboundTransaction ({})", tx.tx_id))
@@ -103,7 +172,13 @@ impl fmt::Display for TransactionServiceRequest {
 #[derive(Debug)]
 pub enum TransactionServiceResponse {
     TransactionSent(TxId),
+    FeeEstimatorSet,
+    SendingLockSet,
+    FeePerGram(MicroTari),
     TransactionCancelled,
+    TransactionReplaced(TxId),
+    TransactionStatusQuerySent,
+    RawTransaction(Vec<u8>),
     PendingInboundTransactions(HashMap<u64, InboundTransaction>),
     PendingOutboundTransactions(HashMap<u64, OutboundTransaction>),
     CompletedTransactions(HashMap<u64, CompletedTransaction>),
@@ -113,6 +188,10 @@ pub enum TransactionServiceResponse {
     BaseNodePublicKeySet,
     UtxoImported(TxId),
     TransactionSubmitted,
+    TransactionHistoryReconstructionStarted(u64),
+    PaymentScheduled(u64),
+    ScheduledPaymentCancelled,
+    ScheduledPayments(Vec<ScheduledPayment>),
     #[cfg(feature = "test_harness")]
     CompletedPendingTransaction,
     #[cfg(feature = "test_harness")]
@@ -125,8 +204,76 @@ pub enum TransactionServiceResponse {
     TransactionBroadcast,
 }
 
+/// The stages an interactive send passes through while it is negotiated with the recipient and handed to the base node.
+/// The last stage reported for a transaction indicates where it stalled if it is later cancelled or times out.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NegotiationStage {
+    /// The sender's partial transaction was sent to the recipient, directly or via store and forward
+    SenderMessageSent,
+    /// A valid reply was received from the recipient
+    RecipientReplyReceived,
+    /// The transaction was finalized and the finalized transaction sent to the recipient
+    Finalized,
+    /// The finalized transaction has been handed to the base node for inclusion in its mempool and is waiting for
+    /// the broadcast to be confirmed
+    BroadcastPending,
+}
+
+impl fmt::Display for NegotiationStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NegotiationStage::SenderMessageSent => f.write_str("Sender Message Sent"),
+            NegotiationStage::RecipientReplyReceived => f.write_str("Recipient Reply Received"),
+            NegotiationStage::Finalized => f.write_str("Finalized"),
+            NegotiationStage::BroadcastPending => f.write_str("Broadcast Pending"),
+        }
+    }
+}
+
+/// The status of a transaction as reported by the counterparty in reply to a transaction status query
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterpartyTransactionStatus {
+    /// The counterparty has no record of the transaction, e.g. because the sender's message never arrived
+    Unknown,
+    /// The counterparty replied to the transaction but has not received the finalized transaction
+    Pending,
+    /// The counterparty received the finalized transaction
+    Completed,
+}
+
+impl fmt::Display for CounterpartyTransactionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CounterpartyTransactionStatus::Unknown => f.write_str("Unknown"),
+            CounterpartyTransactionStatus::Pending => f.write_str("Pending"),
+            CounterpartyTransactionStatus::Completed => f.write_str("Completed"),
+        }
+    }
+}
+
+impl From<proto::CounterpartyTransactionStatus> for CounterpartyTransactionStatus {
+    fn from(status: proto::CounterpartyTransactionStatus) -> Self {
+        use proto::CounterpartyTransactionStatus::*;
+        match status {
+            Unknown => CounterpartyTransactionStatus::Unknown,
+            Pending => CounterpartyTransactionStatus::Pending,
+            Completed => CounterpartyTransactionStatus::Completed,
+        }
+    }
+}
+
+impl From<CounterpartyTransactionStatus> for proto::CounterpartyTransactionStatus {
+    fn from(status: CounterpartyTransactionStatus) -> Self {
+        match status {
+            CounterpartyTransactionStatus::Unknown => proto::CounterpartyTransactionStatus::Unknown,
+            CounterpartyTransactionStatus::Pending => proto::CounterpartyTransactionStatus::Pending,
+            CounterpartyTransactionStatus::Completed => proto::CounterpartyTransactionStatus::Completed,
+        }
+    }
+}
+
 /// Events that can be published on the Text Message Service Event Stream
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransactionEvent {
     MempoolBroadcastTimedOut(TxId),
     ReceivedTransaction(TxId),
@@ -135,9 +282,25 @@ pub enum TransactionEvent {
     TransactionDirectSendResult(TxId, bool),
     TransactionStoreForwardSendResult(TxId, bool),
     TransactionCancelled(TxId),
+    /// The first transaction was replaced by the second, which spends the same inputs at a higher fee
+    TransactionReplaced(TxId, TxId),
+    /// A base node rejected the transaction when it was submitted to its mempool
+    TransactionRejected(TxId, TxRejectionReason),
+    /// An outbound transaction reached a new stage of its negotiation with the recipient
+    TransactionNegotiationProgress(TxId, NegotiationStage),
     TransactionBroadcast(TxId),
+    /// The transaction was found in the mempools of `n` of the `m` additional base nodes that were sampled
+    TransactionPropagated(TxId, usize, usize),
     TransactionMined(TxId),
     TransactionMinedRequestTimedOut(TxId),
+    /// The counterparty of a pending transaction replied to a status query
+    TransactionStatusReplyReceived(TxId, CounterpartyTransactionStatus),
+    /// A transaction history reconstruction finished after adding this many reconstructed transactions
+    TransactionHistoryReconstructed(usize),
+    /// A scheduled payment fell due and the transaction for it was sent
+    ScheduledPaymentSent(u64, TxId),
+    /// A scheduled payment fell due but could not be sent; it is attempted again in its next period
+    ScheduledPaymentFailed(u64, String),
     Error(String),
 }
 
@@ -174,6 +337,26 @@ impl TransactionServiceHandle {
         fee_per_gram: MicroTari,
         message: String,
     ) -> Result<TxId, TransactionServiceError>
+    {
+        self.send_transaction_with_options(
+            dest_pubkey,
+            amount,
+            fee_per_gram,
+            message,
+            TransactionSendOptions::default(),
+        )
+        .await
+    }
+
+    /// Send a transaction as with `send_transaction`, using the provided options to control which outputs fund it
+    pub async fn send_transaction_with_options(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        options: TransactionSendOptions,
+    ) -> Result<TxId, TransactionServiceError>
     {
         match self
             .handle
@@ -182,6 +365,32 @@ impl TransactionServiceHandle {
                 amount,
                 fee_per_gram,
                 message,
+                options,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a transaction with the fee per gram resolved from the given fee preset. The preset is recorded on the
+    /// transaction.
+    pub async fn send_transaction_with_priority(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_priority: FeePriority,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendTransactionWithPriority((
+                dest_pubkey,
+                amount,
+                fee_priority,
+                message,
             )))
             .await??
         {
@@ -190,6 +399,69 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Set the fee estimator used to resolve fee presets, or clear it with `None` to use the configured presets
+    pub async fn set_fee_estimator(&mut self, estimator: Option<FeeEstimator>) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SetFeeEstimator(estimator))
+            .await??
+        {
+            TransactionServiceResponse::FeeEstimatorSet => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Lock or unlock sending. While sending is locked, new transactions, submitted transactions such as coin splits
+    /// and fee bumps are refused with `SendingLocked`; transactions that are already in progress are unaffected.
+    pub async fn set_sending_locked(&mut self, locked: bool) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SetSendingLocked(locked))
+            .await??
+        {
+            TransactionServiceResponse::SendingLockSet => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// The fee per gram that a transaction sent now with the given fee preset would use
+    pub async fn get_fee_per_gram(&mut self, fee_priority: FeePriority) -> Result<MicroTari, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetFeePerGram(fee_priority))
+            .await??
+        {
+            TransactionServiceResponse::FeePerGram(fee_per_gram) => Ok(fee_per_gram),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Ask the recipient of a pending outbound transaction whether it has seen the transaction. The reply is published
+    /// as a `TransactionStatusReplyReceived` event.
+    pub async fn query_transaction_status(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::QueryTransactionStatus(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionStatusQuerySent => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the finalized transaction of a completed transaction in its canonical binary encoding, so that it can be
+    /// submitted to a base node manually if it could not be broadcast over the network
+    pub async fn get_raw_transaction(&mut self, tx_id: TxId) -> Result<Vec<u8>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetRawTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::RawTransaction(bytes) => Ok(bytes),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
         match self
             .handle
@@ -201,6 +473,94 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Replace an outbound transaction that has not been broadcast, e.g. because its fee is too low for it to be
+    /// mined in reasonable time, with a transaction that spends the same inputs at the given higher fee per gram. The
+    /// replaced transaction is cancelled and the TxId of the replacement is returned. A broadcast transaction can't be
+    /// replaced, and the replaced transaction is left as it was if the replacement can't be built.
+    pub async fn rebroadcast_with_higher_fee(
+        &mut self,
+        tx_id: TxId,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RebroadcastWithHigherFee((
+                tx_id,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionReplaced(id) => Ok(id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Rebuild a best-effort transaction history for unspent outputs that no transaction accounts for, e.g. outputs
+    /// recovered from a seed. The block each output was mined in is fetched from the base node and an inbound
+    /// transaction with the `Reconstructed` status is added for it. Returns the id of the reconstruction protocol;
+    /// `TransactionEvent::TransactionHistoryReconstructed` is published when it finishes.
+    pub async fn reconstruct_transaction_history(&mut self) -> Result<u64, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ReconstructTransactionHistory)
+            .await??
+        {
+            TransactionServiceResponse::TransactionHistoryReconstructionStarted(id) => Ok(id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send `amount` to `dest_pubkey` every `interval`, starting at `start_time`. Returns the id of the schedule.
+    pub async fn schedule_payment(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        interval: Duration,
+        start_time: NaiveDateTime,
+        fee_priority: FeePriority,
+        message: String,
+    ) -> Result<u64, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SchedulePayment((
+                dest_pubkey,
+                amount,
+                interval,
+                start_time,
+                fee_priority,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::PaymentScheduled(id) => Ok(id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn cancel_scheduled_payment(&mut self, id: u64) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelScheduledPayment(id))
+            .await??
+        {
+            TransactionServiceResponse::ScheduledPaymentCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_scheduled_payments(&mut self) -> Result<Vec<ScheduledPayment>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetScheduledPayments)
+            .await??
+        {
+            TransactionServiceResponse::ScheduledPayments(p) => Ok(p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_pending_inbound_transactions(
         &mut self,
     ) -> Result<HashMap<u64, InboundTransaction>, TransactionServiceError> {
@@ -325,6 +685,9 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Submit a transaction that was completed by the output manager, such as a coin split, for broadcast. This fails
+    /// with `SendingLocked` while sending is locked, in which case the outputs encumbered for the transaction are
+    /// released.
     pub async fn submit_transaction(
         &mut self,
         tx_id: u64,
@@ -32,7 +32,7 @@ use crate::{
     transaction_service::{
         config::TransactionServiceConfig,
         handle::TransactionServiceHandle,
-        service::TransactionService,
+        service::{TransactionService, SERVICE_NAME},
         storage::database::{TransactionBackend, TransactionDatabase},
     },
 };
@@ -56,6 +56,7 @@ use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
+    watchdog::Watchdog,
     ServiceInitializationError,
     ServiceInitializer,
 };
@@ -116,6 +117,20 @@ where T: TransactionBackend
             .filter_map(ok_or_skip_result)
     }
 
+    fn transaction_status_query_stream(&self) -> impl Stream<Item = DomainMessage<proto::TransactionStatusQuery>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::TransactionStatusQuery)
+            .map(map_decode::<proto::TransactionStatusQuery>)
+            .filter_map(ok_or_skip_result)
+    }
+
+    fn transaction_status_reply_stream(&self) -> impl Stream<Item = DomainMessage<proto::TransactionStatusReply>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::TransactionStatusReply)
+            .map(map_decode::<proto::TransactionStatusReply>)
+            .filter_map(ok_or_skip_result)
+    }
+
     fn mempool_response_stream(&self) -> impl Stream<Item = DomainMessage<MempoolProto::MempoolServiceResponse>> {
         self.subscription_factory
             .get_subscription(TariMessageType::MempoolResponse)
@@ -147,6 +162,8 @@ where T: TransactionBackend + Clone + 'static
         let transaction_stream = self.transaction_stream();
         let transaction_reply_stream = self.transaction_reply_stream();
         let transaction_finalized_stream = self.transaction_finalized_stream();
+        let transaction_status_query_stream = self.transaction_status_query_stream();
+        let transaction_status_reply_stream = self.transaction_status_reply_stream();
         let mempool_response_stream = self.mempool_response_stream();
         let base_node_response_stream = self.base_node_response_stream();
 
@@ -175,6 +192,10 @@ where T: TransactionBackend + Clone + 'static
             let output_manager_service = handles
                 .get_handle::<OutputManagerHandle>()
                 .expect("Output Manager Service handle required for TransactionService");
+            let monitor = handles
+                .get_handle::<Watchdog>()
+                .unwrap_or_default()
+                .register(SERVICE_NAME);
 
             let service = TransactionService::new(
                 config,
@@ -183,6 +204,8 @@ where T: TransactionBackend + Clone + 'static
                 transaction_stream,
                 transaction_reply_stream,
                 transaction_finalized_stream,
+                transaction_status_query_stream,
+                transaction_status_reply_stream,
                 mempool_response_stream,
                 base_node_response_stream,
                 output_manager_service,
@@ -191,6 +214,7 @@ where T: TransactionBackend + Clone + 'static
                 node_identity,
                 factories,
             )
+            .with_monitor(monitor)
             .start();
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
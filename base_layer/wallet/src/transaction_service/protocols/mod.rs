@@ -22,5 +22,6 @@
 
 pub mod transaction_broadcast_protocol;
 pub mod transaction_chain_monitoring_protocol;
+pub mod transaction_history_reconstruction_protocol;
 pub mod transaction_receive_protocol;
 pub mod transaction_send_protocol;
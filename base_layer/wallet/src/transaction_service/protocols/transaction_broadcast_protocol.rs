@@ -20,17 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::transaction_service::{
-    error::{TransactionServiceError, TransactionServiceProtocolError},
-    handle::TransactionEvent,
-    service::TransactionServiceResources,
-    storage::database::{TransactionBackend, TransactionStatus},
+use crate::{
+    output_manager_service::storage::database::AbandonmentReason,
+    transaction_service::{
+        error::{TransactionServiceError, TransactionServiceProtocolError},
+        handle::TransactionEvent,
+        service::TransactionServiceResources,
+        storage::database::{TransactionBackend, TransactionStatus},
+    },
+    util::futures::Deadline,
 };
 use futures::{channel::mpsc::Receiver, FutureExt, StreamExt};
 use log::*;
 use std::{convert::TryFrom, sync::Arc, time::Duration};
-use tari_comms::types::CommsPublicKey;
-use tari_comms_dht::{domain_message::OutboundDomainMessage, outbound::OutboundEncryption};
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, SendMessageParams},
+};
 use tari_core::{
     base_node::proto::{
         base_node as BaseNodeProto,
@@ -44,11 +51,10 @@ use tari_core::{
         service::{MempoolResponse, MempoolServiceResponse},
         TxStorageResponse,
     },
-    transactions::transaction::TransactionOutput,
+    transactions::{transaction::TransactionKernel, types::Signature},
 };
-use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_crypto::tari_utilities::hex::Hex;
 use tari_p2p::tari_message::TariMessageType;
-use tokio::time::delay_for;
 
 const LOG_TARGET: &str = "wallet::transaction_service::protocols::broadcast_protocol";
 
@@ -60,6 +66,8 @@ where TBackend: TransactionBackend + Clone + 'static
     id: u64,
     resources: TransactionServiceResources<TBackend>,
     timeout: Duration,
+    propagation_sample_size: usize,
+    propagation_sample_timeout: Duration,
     base_node_public_key: CommsPublicKey,
     mempool_response_receiver: Option<Receiver<MempoolServiceResponse>>,
     base_node_response_receiver: Option<Receiver<BaseNodeProto::BaseNodeServiceResponse>>,
@@ -72,6 +80,8 @@ where TBackend: TransactionBackend + Clone + 'static
         id: u64,
         resources: TransactionServiceResources<TBackend>,
         timeout: Duration,
+        propagation_sample_size: usize,
+        propagation_sample_timeout: Duration,
         base_node_public_key: CommsPublicKey,
         mempool_response_receiver: Receiver<MempoolServiceResponse>,
         base_node_response_receiver: Receiver<BaseNodeProto::BaseNodeServiceResponse>,
@@ -81,6 +91,8 @@ where TBackend: TransactionBackend + Clone + 'static
             id,
             resources,
             timeout,
+            propagation_sample_size,
+            propagation_sample_timeout,
             base_node_public_key,
             mempool_response_receiver: Some(mempool_response_receiver),
             base_node_response_receiver: Some(base_node_response_receiver),
@@ -101,9 +113,10 @@ where TBackend: TransactionBackend + Clone + 'static
 
         // This is the main loop of the protocol and following the following steps
         // 1) Check transaction being monitored is still in the Completed state and needs to be monitored
-        // 2) Send a MempoolRequest::SubmitTransaction to Mempool and a Mined? Request to base node
+        // 2) Send a MempoolRequest::SubmitTransaction to Mempool and a FetchKernelByExcess request to base node
         // 3) Wait for a either a Mempool response, Base Node response for the correct Id OR a Timeout
-        //      a) A Mempool response for this Id is received >  update the Tx status and end the protocol
+        //      a) A Mempool response for this Id is received >  update the Tx status, sample the mempools of other
+        //         base nodes to confirm propagation if configured and end the protocol
         //      b) A Basenode response for this Id is received showing it is mined > Update Tx status and end protocol
         //      c) Timeout is reached > Start again
         loop {
@@ -161,12 +174,9 @@ where TBackend: TransactionBackend + Clone + 'static
                 .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
             // Send Base Node query
-            let mut hashes = Vec::new();
-            for o in completed_tx.transaction.body.outputs() {
-                hashes.push(o.hash());
-            }
-
-            let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: hashes });
+            let request = BaseNodeRequestProto::FetchKernelByExcess(
+                completed_tx.transaction.body.kernels()[0].excess.clone().into(),
+            );
             let service_request = BaseNodeProto::BaseNodeServiceRequest {
                 request_key: self.id,
                 request: Some(request),
@@ -181,10 +191,12 @@ where TBackend: TransactionBackend + Clone + 'static
                 .await
                 .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
-            let mut delay = delay_for(self.timeout).fuse();
+            let mut delay = Deadline::new(self.timeout).wait().boxed().fuse();
             futures::select! {
                 mempool_response = mempool_response_receiver.select_next_some() => {
                     if self.handle_mempool_response(mempool_response).await? {
+                        let excess_sig = completed_tx.transaction.body.kernels()[0].excess_sig.clone();
+                        self.sample_propagation(excess_sig, &mut mempool_response_receiver).await?;
                         break;
                     }
                 },
@@ -265,19 +277,40 @@ where TBackend: TransactionBackend + Clone + 'static
                     TransactionStatus::Completed => match ts {
                         // Getting this response means the Mempool Rejected this transaction so it will be
                         // cancelled.
-                        TxStorageResponse::NotStored => {
+                        TxStorageResponse::NotStored | TxStorageResponse::Rejected(_) => {
+                            let rejection_reason = match ts {
+                                TxStorageResponse::Rejected(reason) => Some(reason),
+                                _ => None,
+                            };
                             error!(
                                 target: LOG_TARGET,
-                                "Mempool response received for TxId: {:?}. Transaction was REJECTED. Cancelling \
-                                 transaction.",
-                                self.id
+                                "Mempool response received for TxId: {:?}. Transaction was REJECTED (reason: {:?}). \
+                                 Cancelling transaction.",
+                                self.id,
+                                rejection_reason
                             );
-                            if let Err(e) = self
-                                .resources
-                                .output_manager_service
-                                .cancel_transaction(completed_tx.tx_id)
-                                .await
-                            {
+                            // A rejected transaction can never be mined, so its outputs are abandoned rather than
+                            // cancelled
+                            let result = match rejection_reason.as_ref() {
+                                Some(reason) => {
+                                    self.resources
+                                        .output_manager_service
+                                        .abandon_transaction(
+                                            completed_tx.tx_id,
+                                            AbandonmentReason::RejectedByBaseNode {
+                                                reason: reason.to_string(),
+                                            },
+                                        )
+                                        .await
+                                },
+                                None => {
+                                    self.resources
+                                        .output_manager_service
+                                        .cancel_transaction(completed_tx.tx_id)
+                                        .await
+                                },
+                            };
+                            if let Err(e) = result {
                                 error!(
                                     target: LOG_TARGET,
                                     "Failed to Cancel outputs for TX_ID: {} after failed sending attempt with error \
@@ -306,6 +339,20 @@ where TBackend: TransactionBackend + Clone + 'static
                                     );
                                     e
                                 });
+                            if let Some(reason) = rejection_reason {
+                                let _ = self
+                                    .resources
+                                    .event_publisher
+                                    .send(Arc::new(TransactionEvent::TransactionRejected(self.id, reason)))
+                                    .map_err(|e| {
+                                        trace!(
+                                            target: LOG_TARGET,
+                                            "Error sending event, usually because there are no subscribers: {:?}",
+                                            e
+                                        );
+                                        e
+                                    });
+                            }
 
                             return Err(TransactionServiceProtocolError::new(
                                 self.id,
@@ -359,6 +406,106 @@ where TBackend: TransactionBackend + Clone + 'static
         Ok(false)
     }
 
+    /// Query the mempools of a random sample of base nodes, other than our own base node, for the state of this
+    /// transaction. Publishes a `TransactionPropagated` event with the number of sampled nodes that reported the
+    /// transaction out of the number of nodes that the query was sent to.
+    async fn sample_propagation(
+        &mut self,
+        excess_sig: Signature,
+        mempool_response_receiver: &mut Receiver<MempoolServiceResponse>,
+    ) -> Result<(), TransactionServiceProtocolError>
+    {
+        if self.propagation_sample_size == 0 {
+            return Ok(());
+        }
+
+        let base_node_id = NodeId::from_key(&self.base_node_public_key)
+            .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+        let request = MempoolProto::MempoolServiceRequest {
+            request_key: self.id,
+            request: Some(MempoolProto::mempool_service_request::Request::GetTxStateWithExcessSig(
+                excess_sig.into(),
+            )),
+        };
+        let send_states = self
+            .resources
+            .outbound_message_service
+            .send_message(
+                SendMessageParams::new()
+                    .random_excluding(self.propagation_sample_size, vec![base_node_id])
+                    .with_encryption(OutboundEncryption::None)
+                    .finish(),
+                OutboundDomainMessage::new(TariMessageType::MempoolRequest, request),
+            )
+            .await
+            .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?
+            .resolve_ok()
+            .await;
+
+        let num_sampled = match send_states {
+            Some(send_states) if !send_states.is_empty() => send_states.len(),
+            _ => {
+                warn!(
+                    target: LOG_TARGET,
+                    "No base nodes available to sample for propagation of Transaction (TxId: {})", self.id
+                );
+                return Ok(());
+            },
+        };
+
+        let mut num_responses = 0;
+        let mut num_confirmed = 0;
+        let mut delay = Deadline::new(self.propagation_sample_timeout).wait().boxed().fuse();
+        loop {
+            futures::select! {
+                response = mempool_response_receiver.select_next_some() => {
+                    if response.request_key != self.id {
+                        continue;
+                    }
+                    if let MempoolResponse::TxStorage(ts) = response.response {
+                        num_responses += 1;
+                        match ts {
+                            TxStorageResponse::NotStored | TxStorageResponse::Rejected(_) => (),
+                            _ => num_confirmed += 1,
+                        }
+                    }
+                    if num_responses >= num_sampled {
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Transaction (TxId: {}) found in the mempools of {} of {} sampled base nodes",
+            self.id,
+            num_confirmed,
+            num_sampled
+        );
+        let _ = self
+            .resources
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionPropagated(
+                self.id,
+                num_confirmed,
+                num_sampled,
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+
+        Ok(())
+    }
+
     async fn handle_base_node_response(
         &mut self,
         response: BaseNodeProto::BaseNodeServiceResponse,
@@ -372,8 +519,8 @@ where TBackend: TransactionBackend + Clone + 'static
             return Ok(false);
         }
 
-        let response: Vec<tari_core::transactions::proto::types::TransactionOutput> = match response.response {
-            Some(BaseNodeResponseProto::TransactionOutputs(outputs)) => outputs.outputs,
+        let response: Vec<tari_core::transactions::proto::types::TransactionKernel> = match response.response {
+            Some(BaseNodeResponseProto::TransactionKernels(kernels)) => kernels.kernels,
             _ => {
                 return Ok(false);
             },
@@ -397,26 +544,26 @@ where TBackend: TransactionBackend + Clone + 'static
             (completed_tx.status == TransactionStatus::Broadcast ||
                 completed_tx.status == TransactionStatus::Completed)
         {
-            let mut check = true;
+            let mut mined = false;
 
-            for output in response.iter() {
-                let transaction_output = TransactionOutput::try_from(output.clone()).map_err(|_| {
+            for kernel in response.iter() {
+                let transaction_kernel = TransactionKernel::try_from(kernel.clone()).map_err(|_| {
                     TransactionServiceProtocolError::new(
                         self.id,
-                        TransactionServiceError::ConversionError("Could not convert Transaction Output".to_string()),
+                        TransactionServiceError::ConversionError("Could not convert Transaction Kernel".to_string()),
                     )
                 })?;
 
-                check = check &&
+                mined = mined ||
                     completed_tx
                         .transaction
                         .body
-                        .outputs()
+                        .kernels()
                         .iter()
-                        .any(|item| item == &transaction_output);
+                        .any(|item| item == &transaction_kernel);
             }
-            // If all outputs are present then mark this transaction as mined.
-            if check && !response.is_empty() {
+            // If the transaction kernel is present on the base node then mark this transaction as mined.
+            if mined {
                 self.resources
                     .output_manager_service
                     .confirm_transaction(
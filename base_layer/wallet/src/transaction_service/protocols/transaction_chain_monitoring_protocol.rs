@@ -21,13 +21,14 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    output_manager_service::TxId,
+    output_manager_service::{storage::database::AbandonmentReason, TxId},
     transaction_service::{
         error::{TransactionServiceError, TransactionServiceProtocolError},
         handle::TransactionEvent,
         service::TransactionServiceResources,
         storage::database::{TransactionBackend, TransactionStatus},
     },
+    util::futures::Deadline,
 };
 use futures::{channel::mpsc::Receiver, FutureExt, StreamExt};
 use log::*;
@@ -47,11 +48,10 @@ use tari_core::{
         service::{MempoolResponse, MempoolServiceResponse},
         TxStorageResponse,
     },
-    transactions::transaction::TransactionOutput,
+    transactions::transaction::TransactionKernel,
 };
-use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_crypto::tari_utilities::hex::Hex;
 use tari_p2p::tari_message::TariMessageType;
-use tokio::time::delay_for;
 
 const LOG_TARGET: &str = "wallet::transaction_service::protocols::chain_monitoring_protocol";
 
@@ -114,7 +114,7 @@ where TBackend: TransactionBackend + Clone + 'static
 
         // This is the main loop of the protocol and following the following steps
         // 1) Check transaction being monitored is still in the Broadcast state and needs to be monitored
-        // 2) Send a MempoolRequest::GetTxStateWithExcessSig to Mempool and a Mined? Request to base node
+        // 2) Send a MempoolRequest::GetTxStateWithExcessSig to Mempool and a FetchKernelByExcess request to base node
         // 3) Wait for both a Mempool response and Base Node response for the correct Id OR a Timeout
         //      a) If the Tx is not in the mempool AND is not mined the protocol ends and Tx should be cancelled
         //      b) If the Tx is in the mempool AND not mined > perform another iteration
@@ -147,21 +147,14 @@ where TBackend: TransactionBackend + Clone + 'static
                 return Ok(self.id);
             }
 
-            let mut hashes = Vec::new();
-            for o in completed_tx.transaction.body.outputs() {
-                hashes.push(o.hash());
-            }
-
             info!(
                 target: LOG_TARGET,
-                "Sending Transaction Mined? request for TxId: {} and Kernel Signature {} to Base Node (Contains {} \
-                 outputs)",
+                "Sending Transaction Mined? request for TxId: {} and Kernel Signature {} to Base Node",
                 completed_tx.tx_id,
                 completed_tx.transaction.body.kernels()[0]
                     .excess_sig
                     .get_signature()
                     .to_hex(),
-                hashes.len(),
             );
 
             // Send Mempool query
@@ -184,7 +177,9 @@ where TBackend: TransactionBackend + Clone + 'static
                 .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
             // Send Base Node query
-            let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: hashes });
+            let request = BaseNodeRequestProto::FetchKernelByExcess(
+                completed_tx.transaction.body.kernels()[0].excess.clone().into(),
+            );
             let service_request = BaseNodeProto::BaseNodeServiceRequest {
                 request_key: self.id,
                 request: Some(request),
@@ -199,7 +194,7 @@ where TBackend: TransactionBackend + Clone + 'static
                 .await
                 .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
 
-            let mut delay = delay_for(self.timeout).fuse();
+            let mut delay = Deadline::new(self.timeout).wait().boxed().fuse();
             let mut received_mempool_response = None;
             let mut mempool_response_received = false;
             let mut base_node_response_received = false;
@@ -312,19 +307,35 @@ where TBackend: TransactionBackend + Clone + 'static
                     TransactionStatus::Broadcast => match ts {
                         // Getting this response means the Mempool Rejected this transaction so it will be
                         // cancelled.
-                        TxStorageResponse::NotStored => {
+                        TxStorageResponse::NotStored | TxStorageResponse::Rejected(_) => {
                             error!(
                                 target: LOG_TARGET,
                                 "Mempool response received for TxId: {:?}. Transaction was REJECTED. Cancelling \
                                  transaction.",
                                 tx_id
                             );
-                            if let Err(e) = self
-                                .resources
-                                .output_manager_service
-                                .cancel_transaction(completed_tx.tx_id)
-                                .await
-                            {
+                            // A rejected transaction can never be mined, so its outputs are abandoned rather than
+                            // cancelled
+                            let result = match &ts {
+                                TxStorageResponse::Rejected(reason) => {
+                                    self.resources
+                                        .output_manager_service
+                                        .abandon_transaction(
+                                            completed_tx.tx_id,
+                                            AbandonmentReason::RejectedByBaseNode {
+                                                reason: reason.to_string(),
+                                            },
+                                        )
+                                        .await
+                                },
+                                _ => {
+                                    self.resources
+                                        .output_manager_service
+                                        .cancel_transaction(completed_tx.tx_id)
+                                        .await
+                                },
+                            };
+                            if let Err(e) = result {
                                 error!(
                                     target: LOG_TARGET,
                                     "Failed to Cancel outputs for TX_ID: {} after failed sending attempt with error \
@@ -392,8 +403,8 @@ where TBackend: TransactionBackend + Clone + 'static
         response: BaseNodeProto::BaseNodeServiceResponse,
     ) -> Result<bool, TransactionServiceProtocolError>
     {
-        let response: Vec<tari_core::transactions::proto::types::TransactionOutput> = match response.response {
-            Some(BaseNodeResponseProto::TransactionOutputs(outputs)) => outputs.outputs,
+        let response: Vec<tari_core::transactions::proto::types::TransactionKernel> = match response.response {
+            Some(BaseNodeResponseProto::TransactionKernels(kernels)) => kernels.kernels,
             _ => {
                 return Ok(false);
             },
@@ -416,26 +427,26 @@ where TBackend: TransactionBackend + Clone + 'static
         };
 
         if completed_tx.status == TransactionStatus::Broadcast {
-            let mut check = true;
+            let mut mined = false;
 
-            for output in response.iter() {
-                let transaction_output = TransactionOutput::try_from(output.clone()).map_err(|_| {
+            for kernel in response.iter() {
+                let transaction_kernel = TransactionKernel::try_from(kernel.clone()).map_err(|_| {
                     TransactionServiceProtocolError::new(
                         self.id,
-                        TransactionServiceError::ConversionError("Could not convert Transaction Output".to_string()),
+                        TransactionServiceError::ConversionError("Could not convert Transaction Kernel".to_string()),
                     )
                 })?;
 
-                check = check &&
+                mined = mined ||
                     completed_tx
                         .transaction
                         .body
-                        .outputs()
+                        .kernels()
                         .iter()
-                        .any(|item| item == &transaction_output);
+                        .any(|item| item == &transaction_kernel);
             }
-            // If all outputs are present then mark this transaction as mined.
-            if check && !response.is_empty() {
+            // If the transaction kernel is present on the base node then mark this transaction as mined.
+            if mined {
                 self.resources
                     .output_manager_service
                     .confirm_transaction(
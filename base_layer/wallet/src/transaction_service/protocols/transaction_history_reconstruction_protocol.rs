@@ -0,0 +1,340 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{
+        error::{TransactionServiceError, TransactionServiceProtocolError},
+        handle::TransactionEvent,
+        service::TransactionServiceResources,
+        storage::database::{CompletedTransaction, TransactionBackend, TransactionStatus},
+    },
+    util::futures::Deadline,
+};
+use chrono::{NaiveDateTime, Utc};
+use futures::{channel::mpsc::Receiver, FutureExt, StreamExt};
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{domain_message::OutboundDomainMessage, outbound::OutboundEncryption};
+use tari_core::{
+    base_node::proto::{
+        base_node as BaseNodeProto,
+        base_node::{
+            base_node_service_request::Request as BaseNodeRequestProto,
+            base_node_service_response::Response as BaseNodeResponseProto,
+        },
+    },
+    proto::core as CoreProto,
+    transactions::{
+        tari_amount::{uT, MicroTari},
+        transaction::{KernelFeatures, OutputFlags, Transaction, TransactionKernel, TransactionOutput},
+        types::{BlindingFactor, Commitment},
+    },
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, tari_utilities::hex::Hex};
+use tari_p2p::tari_message::TariMessageType;
+
+const LOG_TARGET: &str = "wallet::transaction_service::protocols::history_reconstruction_protocol";
+
+/// This protocol rebuilds a best-effort transaction history for unspent outputs that no stored transaction accounts
+/// for, as is the case for every output after a wallet is restored from its seed. The block each output was mined in
+/// is fetched from the base node and an inbound transaction with the `Reconstructed` status is added for the output.
+pub struct TransactionHistoryReconstructionProtocol<TBackend>
+where TBackend: TransactionBackend + Clone + 'static
+{
+    id: u64,
+    resources: TransactionServiceResources<TBackend>,
+    timeout: Duration,
+    base_node_public_key: CommsPublicKey,
+    base_node_response_receiver: Option<Receiver<BaseNodeProto::BaseNodeServiceResponse>>,
+}
+
+impl<TBackend> TransactionHistoryReconstructionProtocol<TBackend>
+where TBackend: TransactionBackend + Clone + 'static
+{
+    pub fn new(
+        id: u64,
+        resources: TransactionServiceResources<TBackend>,
+        timeout: Duration,
+        base_node_public_key: CommsPublicKey,
+        base_node_response_receiver: Receiver<BaseNodeProto::BaseNodeServiceResponse>,
+    ) -> Self
+    {
+        Self {
+            id,
+            resources,
+            timeout,
+            base_node_public_key,
+            base_node_response_receiver: Some(base_node_response_receiver),
+        }
+    }
+
+    /// The task that defines the execution of the protocol.
+    pub async fn execute(mut self) -> Result<u64, TransactionServiceProtocolError> {
+        let mut base_node_response_receiver = self
+            .base_node_response_receiver
+            .take()
+            .ok_or_else(|| TransactionServiceProtocolError::new(self.id, TransactionServiceError::InvalidStateError))?;
+
+        trace!(
+            target: LOG_TARGET,
+            "Starting transaction history reconstruction protocol with Protocol ID: {}",
+            self.id
+        );
+
+        // This protocol follows the following steps
+        // 1) Find the unspent outputs that are not accounted for by any stored transaction
+        // 2) Send a FetchBlockWithUtxo request to the base node for each of these outputs
+        // 3) Add a reconstructed transaction for every output found in a returned block until all outputs are found
+        //    or the timeout expires
+        let mut unaccounted_outputs = self.find_unaccounted_outputs().await?;
+        let mut reconstructed_count = 0;
+
+        if !unaccounted_outputs.is_empty() {
+            info!(
+                target: LOG_TARGET,
+                "Reconstructing transaction history for {} outputs",
+                unaccounted_outputs.len()
+            );
+
+            for (commitment, _) in unaccounted_outputs.iter() {
+                let service_request = BaseNodeProto::BaseNodeServiceRequest {
+                    request_key: self.id,
+                    request: Some(BaseNodeRequestProto::FetchBlockWithUtxo(commitment.clone().into())),
+                };
+                self.resources
+                    .outbound_message_service
+                    .send_direct(
+                        self.base_node_public_key.clone(),
+                        OutboundEncryption::None,
+                        OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+                    )
+                    .await
+                    .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+            }
+
+            let mut delay = Deadline::new(self.timeout).wait().boxed().fuse();
+            loop {
+                futures::select! {
+                    base_node_response = base_node_response_receiver.select_next_some() => {
+                        reconstructed_count += self
+                            .handle_base_node_response(base_node_response, &mut unaccounted_outputs)
+                            .await?;
+                        if unaccounted_outputs.is_empty() {
+                            break;
+                        }
+                    },
+                    () = delay => {
+                        info!(
+                            target: LOG_TARGET,
+                            "Transaction history reconstruction timed out with {} outputs not found on the base node",
+                            unaccounted_outputs.len()
+                        );
+                        break;
+                    },
+                }
+            }
+        }
+
+        let _ = self
+            .resources
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionHistoryReconstructed(
+                reconstructed_count,
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+
+        Ok(self.id)
+    }
+
+    /// Returns the commitments and values of the unspent outputs that do not appear in any completed transaction.
+    /// Imported transactions carry no outputs so each of them accounts for one output of the same value.
+    async fn find_unaccounted_outputs(
+        &mut self,
+    ) -> Result<Vec<(Commitment, MicroTari)>, TransactionServiceProtocolError> {
+        let unspent_outputs = self
+            .resources
+            .output_manager_service
+            .get_unspent_outputs()
+            .await
+            .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+        let completed_txs = self
+            .resources
+            .db
+            .get_completed_transactions()
+            .await
+            .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+
+        let mut unaccounted_outputs = unspent_outputs
+            .iter()
+            .map(|o| {
+                let commitment = self
+                    .resources
+                    .factories
+                    .commitment
+                    .commit_value(&o.spending_key, o.value.into());
+                (commitment, o.value)
+            })
+            .collect::<Vec<_>>();
+        for tx in completed_txs.values() {
+            unaccounted_outputs.retain(|(c, _)| tx.transaction.body.outputs().iter().all(|o| &o.commitment != c));
+            if tx.status == TransactionStatus::Imported {
+                if let Some(pos) = unaccounted_outputs.iter().position(|(_, v)| *v == tx.amount) {
+                    unaccounted_outputs.remove(pos);
+                }
+            }
+        }
+
+        Ok(unaccounted_outputs)
+    }
+
+    /// Adds a reconstructed transaction for every unaccounted output in the blocks of the response and returns how
+    /// many were added
+    async fn handle_base_node_response(
+        &mut self,
+        response: BaseNodeProto::BaseNodeServiceResponse,
+        unaccounted_outputs: &mut Vec<(Commitment, MicroTari)>,
+    ) -> Result<usize, TransactionServiceProtocolError>
+    {
+        let blocks: Vec<CoreProto::HistoricalBlock> = match response.response {
+            Some(BaseNodeResponseProto::HistoricalBlocks(blocks)) => blocks.blocks,
+            _ => {
+                return Ok(0);
+            },
+        };
+
+        let mut reconstructed_count = 0;
+        for block in blocks {
+            let (header, body) = match block.block {
+                Some(CoreProto::Block {
+                    header: Some(header),
+                    body: Some(body),
+                }) => (header, body),
+                _ => continue,
+            };
+            let outputs = body
+                .outputs
+                .into_iter()
+                .map(TransactionOutput::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| {
+                    TransactionServiceProtocolError::new(
+                        self.id,
+                        TransactionServiceError::ConversionError("Could not convert Transaction Output".to_string()),
+                    )
+                })?;
+            let kernels = body
+                .kernels
+                .into_iter()
+                .map(TransactionKernel::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| {
+                    TransactionServiceProtocolError::new(
+                        self.id,
+                        TransactionServiceError::ConversionError("Could not convert Transaction Kernel".to_string()),
+                    )
+                })?;
+            let timestamp = header
+                .timestamp
+                .map(|t| NaiveDateTime::from_timestamp(t.seconds, 0))
+                .unwrap_or_else(|| Utc::now().naive_utc());
+
+            for output in outputs {
+                if let Some(pos) = unaccounted_outputs.iter().position(|(c, _)| c == &output.commitment) {
+                    let (_, value) = unaccounted_outputs.remove(pos);
+                    self.add_reconstructed_transaction(output, value, header.height, timestamp, &kernels)
+                        .await?;
+                    reconstructed_count += 1;
+                }
+            }
+        }
+
+        Ok(reconstructed_count)
+    }
+
+    async fn add_reconstructed_transaction(
+        &mut self,
+        output: TransactionOutput,
+        value: MicroTari,
+        height: u64,
+        timestamp: NaiveDateTime,
+        block_kernels: &[TransactionKernel],
+    ) -> Result<(), TransactionServiceProtocolError>
+    {
+        // Only the kernel of a coinbase can be attributed to an output; any other kernel in the block could belong to
+        // an unrelated transaction
+        let is_coinbase = output.features.flags.contains(OutputFlags::COINBASE_OUTPUT);
+        let kernels = if is_coinbase {
+            block_kernels
+                .iter()
+                .filter(|k| k.features.contains(KernelFeatures::COINBASE_KERNEL))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let message = format!(
+            "Reconstructed {} output mined at height {}",
+            if is_coinbase { "coinbase" } else { "received" },
+            height
+        );
+        let tx_id = OsRng.next_u64();
+
+        info!(
+            target: LOG_TARGET,
+            "Adding reconstructed transaction (TxId: {}) for output {} of {} mined at height {}",
+            tx_id,
+            output.commitment.to_hex(),
+            value,
+            height
+        );
+
+        let completed_tx = CompletedTransaction {
+            tx_id,
+            source_public_key: CommsPublicKey::default(),
+            destination_public_key: self.resources.node_identity.public_key().clone(),
+            amount: value,
+            fee: 0 * uT,
+            transaction: Transaction::new(Vec::new(), vec![output], kernels, BlindingFactor::default()),
+            status: TransactionStatus::Reconstructed,
+            message,
+            timestamp,
+            fee_priority: None,
+        };
+        self.resources
+            .db
+            .insert_completed_transaction(tx_id, completed_tx)
+            .await
+            .map_err(|e| TransactionServiceProtocolError::new(self.id, TransactionServiceError::from(e)))?;
+
+        Ok(())
+    }
+}
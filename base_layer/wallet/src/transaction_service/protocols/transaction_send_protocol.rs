@@ -28,9 +28,15 @@ use log::*;
 
 use crate::transaction_service::{
     error::{TransactionServiceError, TransactionServiceProtocolError},
-    handle::TransactionEvent,
+    handle::{NegotiationStage, TransactionEvent},
     service::TransactionServiceResources,
-    storage::database::{CompletedTransaction, OutboundTransaction, TransactionBackend, TransactionStatus},
+    storage::database::{
+        CompletedTransaction,
+        FeePriority,
+        OutboundTransaction,
+        TransactionBackend,
+        TransactionStatus,
+    },
 };
 use futures::channel::oneshot;
 use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
@@ -63,6 +69,7 @@ where TBackend: TransactionBackend + Clone + 'static
     message: String,
     sender_protocol: SenderTransactionProtocol,
     stage: TransactionProtocolStage,
+    fee_priority: Option<FeePriority>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -79,6 +86,7 @@ where TBackend: TransactionBackend + Clone + 'static
         message: String,
         sender_protocol: SenderTransactionProtocol,
         stage: TransactionProtocolStage,
+        fee_priority: Option<FeePriority>,
     ) -> Self
     {
         Self {
@@ -91,6 +99,7 @@ where TBackend: TransactionBackend + Clone + 'static
             message,
             sender_protocol,
             stage,
+            fee_priority,
         }
     }
 
@@ -207,6 +216,7 @@ where TBackend: TransactionBackend + Clone + 'static
             status: TransactionStatus::Completed,
             message: outbound_tx.message.clone(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: outbound_tx.fee_priority,
         };
 
         self.resources
@@ -236,6 +246,7 @@ where TBackend: TransactionBackend + Clone + 'static
                 );
                 e
             });
+        self.publish_progress(NegotiationStage::RecipientReplyReceived);
 
         // TODO Actually monitor the send status of this message
         self.resources
@@ -302,10 +313,33 @@ where TBackend: TransactionBackend + Clone + 'static
                 );
             },
         };
+        self.publish_progress(NegotiationStage::Finalized);
 
         Ok(self.id)
     }
 
+    /// Publish the stage this transaction's negotiation has reached
+    fn publish_progress(&self, stage: NegotiationStage) {
+        debug!(
+            target: LOG_TARGET,
+            "Transaction (TxId: {}) negotiation reached stage: {}", self.id, stage
+        );
+        let _ = self
+            .resources
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionNegotiationProgress(
+                self.id, stage,
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+    }
+
     /// Contains all the logic to initially send the transaction. This will only be done on the first time this Protocol
     /// is executed.
     async fn send_transaction(&mut self) -> Result<(), TransactionServiceProtocolError> {
@@ -486,6 +520,7 @@ where TBackend: TransactionBackend + Clone + 'static
             status: TransactionStatus::Pending,
             message: self.message.clone(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: self.fee_priority,
         };
 
         self.resources
@@ -505,6 +540,7 @@ where TBackend: TransactionBackend + Clone + 'static
             .send(Arc::new(TransactionEvent::TransactionStoreForwardSendResult(
                 tx_id, true,
             )));
+        self.publish_progress(NegotiationStage::SenderMessageSent);
 
         Ok(())
     }
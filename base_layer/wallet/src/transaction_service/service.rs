@@ -21,28 +21,40 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    output_manager_service::{handle::OutputManagerHandle, TxId},
+    output_manager_service::{handle::OutputManagerHandle, service::TransactionSendOptions, TxId},
     transaction_service::{
         config::TransactionServiceConfig,
         error::{TransactionServiceError, TransactionServiceProtocolError},
-        handle::{TransactionEvent, TransactionEventSender, TransactionServiceRequest, TransactionServiceResponse},
+        handle::{
+            CounterpartyTransactionStatus,
+            FeeEstimator,
+            NegotiationStage,
+            TransactionEvent,
+            TransactionEventSender,
+            TransactionServiceRequest,
+            TransactionServiceResponse,
+        },
         protocols::{
             transaction_broadcast_protocol::TransactionBroadcastProtocol,
             transaction_chain_monitoring_protocol::TransactionChainMonitoringProtocol,
+            transaction_history_reconstruction_protocol::TransactionHistoryReconstructionProtocol,
             transaction_send_protocol::{TransactionProtocolStage, TransactionSendProtocol},
         },
         storage::database::{
             CompletedTransaction,
+            FeePriority,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ScheduledPayment,
             TransactionBackend,
             TransactionDatabase,
             TransactionStatus,
         },
     },
 };
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
+use digest::Digest;
 use futures::{
     channel::{mpsc, mpsc::Sender, oneshot},
     pin_mut,
@@ -52,11 +64,13 @@ use futures::{
     StreamExt,
 };
 use log::*;
+use prost::Message;
 use rand::{rngs::OsRng, RngCore};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
     sync::Arc,
+    time::{Duration, Instant},
 };
 use tari_comms::{
     peer_manager::{NodeId, NodeIdentity},
@@ -73,6 +87,7 @@ use tari_core::{
     base_node::proto::base_node as BaseNodeProto,
     mempool::{proto::mempool as MempoolProto, service::MempoolServiceResponse},
     transactions::{
+        fee::Fee,
         tari_amount::MicroTari,
         transaction::{KernelFeatures, OutputFeatures, OutputFlags, Transaction},
         transaction_protocol::{
@@ -80,16 +95,23 @@ use tari_core::{
             recipient::{RecipientSignedMessage, RecipientState},
             sender::TransactionSenderMessage,
         },
-        types::{CryptoFactories, PrivateKey},
+        types::{CryptoFactories, HashDigest, PrivateKey},
         ReceiverTransactionProtocol,
+        SenderTransactionProtocol,
     },
 };
-use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey, tari_utilities::hex::Hex};
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
-use tari_service_framework::{reply_channel, reply_channel::Receiver};
-use tokio::task::JoinHandle;
+use tari_service_framework::{
+    reply_channel,
+    reply_channel::Receiver,
+    watchdog::{ServiceMonitor, Watchdog},
+};
+use tokio::{task::JoinHandle, time};
 
 const LOG_TARGET: &str = "wallet::transaction_service::service";
+/// The name the service is registered with at the watchdog
+pub const SERVICE_NAME: &str = "Transaction Service";
 
 /// Contains the generated TxId and SpendingKey for a Pending Coinbase transaction
 #[derive(Debug)]
@@ -114,8 +136,16 @@ pub struct PendingCoinbaseSpendingKey {
 /// `pending_inbound_transactions` - List of transaction protocols that have been received and responded to.
 /// `completed_transaction` - List of sent transactions that have been responded to and are completed.
 
-pub struct TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
-where TBackend: TransactionBackend + Clone + 'static
+pub struct TransactionService<
+    TTxStream,
+    TTxReplyStream,
+    TTxFinalizedStream,
+    TTxStatusQueryStream,
+    TTxStatusReplyStream,
+    MReplyStream,
+    BNResponseStream,
+    TBackend,
+> where TBackend: TransactionBackend + Clone + 'static
 {
     config: TransactionServiceConfig,
     db: TransactionDatabase<TBackend>,
@@ -124,6 +154,8 @@ where TBackend: TransactionBackend + Clone + 'static
     transaction_stream: Option<TTxStream>,
     transaction_reply_stream: Option<TTxReplyStream>,
     transaction_finalized_stream: Option<TTxFinalizedStream>,
+    transaction_status_query_stream: Option<TTxStatusQueryStream>,
+    transaction_status_reply_stream: Option<TTxStatusReplyStream>,
     mempool_response_stream: Option<MReplyStream>,
     base_node_response_stream: Option<BNResponseStream>,
     request_stream: Option<
@@ -138,15 +170,39 @@ where TBackend: TransactionBackend + Clone + 'static
     mempool_response_senders: HashMap<u64, Sender<MempoolServiceResponse>>,
     base_node_response_senders: HashMap<u64, Sender<BaseNodeProto::BaseNodeServiceResponse>>,
     send_transaction_cancellation_senders: HashMap<u64, oneshot::Sender<()>>,
+    fee_estimator: Option<FeeEstimator>,
+    sending_locked: bool,
+    // Reports the activities of the select loop to the watchdog
+    monitor: ServiceMonitor,
 }
 
 #[allow(clippy::too_many_arguments)]
-impl<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
-    TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
+impl<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        TTxStatusQueryStream,
+        TTxStatusReplyStream,
+        MReplyStream,
+        BNResponseStream,
+        TBackend,
+    >
+    TransactionService<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        TTxStatusQueryStream,
+        TTxStatusReplyStream,
+        MReplyStream,
+        BNResponseStream,
+        TBackend,
+    >
 where
     TTxStream: Stream<Item = DomainMessage<proto::TransactionSenderMessage>>,
     TTxReplyStream: Stream<Item = DomainMessage<proto::RecipientSignedMessage>>,
     TTxFinalizedStream: Stream<Item = DomainMessage<proto::TransactionFinalizedMessage>>,
+    TTxStatusQueryStream: Stream<Item = DomainMessage<proto::TransactionStatusQuery>>,
+    TTxStatusReplyStream: Stream<Item = DomainMessage<proto::TransactionStatusReply>>,
     MReplyStream: Stream<Item = DomainMessage<MempoolProto::MempoolServiceResponse>>,
     BNResponseStream: Stream<Item = DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
     TBackend: TransactionBackend + Clone + 'static,
@@ -161,6 +217,8 @@ where
         transaction_stream: TTxStream,
         transaction_reply_stream: TTxReplyStream,
         transaction_finalized_stream: TTxFinalizedStream,
+        transaction_status_query_stream: TTxStatusQueryStream,
+        transaction_status_reply_stream: TTxStatusReplyStream,
         mempool_response_stream: MReplyStream,
         base_node_response_stream: BNResponseStream,
         output_manager_service: OutputManagerHandle,
@@ -188,6 +246,8 @@ where
             transaction_stream: Some(transaction_stream),
             transaction_reply_stream: Some(transaction_reply_stream),
             transaction_finalized_stream: Some(transaction_finalized_stream),
+            transaction_status_query_stream: Some(transaction_status_query_stream),
+            transaction_status_reply_stream: Some(transaction_status_reply_stream),
             mempool_response_stream: Some(mempool_response_stream),
             base_node_response_stream: Some(base_node_response_stream),
             request_stream: Some(request_stream),
@@ -200,9 +260,18 @@ where
             mempool_response_senders: HashMap::new(),
             base_node_response_senders: HashMap::new(),
             send_transaction_cancellation_senders: HashMap::new(),
+            fee_estimator: None,
+            sending_locked: false,
+            monitor: Watchdog::default().register(SERVICE_NAME),
         }
     }
 
+    /// Report the activities of the service to the watchdog the monitor was registered with
+    pub fn with_monitor(mut self, monitor: ServiceMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
     #[warn(unreachable_code)]
     pub async fn start(mut self) -> Result<(), TransactionServiceError> {
         let request_stream = self
@@ -229,6 +298,18 @@ where
             .expect("Transaction Service initialized without transaction_finalized_stream")
             .fuse();
         pin_mut!(transaction_finalized_stream);
+        let transaction_status_query_stream = self
+            .transaction_status_query_stream
+            .take()
+            .expect("Transaction Service initialized without transaction_status_query_stream")
+            .fuse();
+        pin_mut!(transaction_status_query_stream);
+        let transaction_status_reply_stream = self
+            .transaction_status_reply_stream
+            .take()
+            .expect("Transaction Service initialized without transaction_status_reply_stream")
+            .fuse();
+        pin_mut!(transaction_status_reply_stream);
         let mempool_response_stream = self
             .mempool_response_stream
             .take()
@@ -254,14 +335,26 @@ where
             JoinHandle<Result<u64, TransactionServiceProtocolError>>,
         > = FuturesUnordered::new();
 
+        let mut history_reconstruction_protocol_handles: FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        > = FuturesUnordered::new();
+
+        self.prune_processed_messages().await;
+        let mut processed_message_prune_ticker = time::interval(self.config.processed_message_prune_interval).fuse();
+        let status_query_interval = self.config.pending_transaction_status_query_interval;
+        let mut pending_transaction_status_query_ticker =
+            time::interval_at((Instant::now() + status_query_interval).into(), status_query_interval).fuse();
+        let mut scheduled_payment_ticker = time::interval(self.config.scheduled_payment_check_interval).fuse();
+
         info!(target: LOG_TARGET, "Transaction Service started");
         loop {
             futures::select! {
                 //Incoming request
                 request_context = request_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Service API Request");
+                    let _activity = self.monitor.enter("API request");
                     let (request, reply_tx) = request_context.split();
-                    let _ = reply_tx.send(self.handle_request(request, &mut send_transaction_protocol_handles,  &mut transaction_broadcast_protocol_handles, &mut transaction_chain_monitoring_protocol_handles).await.or_else(|resp| {
+                    let _ = reply_tx.send(self.handle_request(request, &mut send_transaction_protocol_handles,  &mut transaction_broadcast_protocol_handles, &mut transaction_chain_monitoring_protocol_handles, &mut history_reconstruction_protocol_handles).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
                         Err(resp)
                     })).or_else(|resp| {
@@ -272,8 +365,16 @@ where
                 // Incoming messages from the Comms layer
                 msg = transaction_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Transaction Message");
+                    let _activity = self.monitor.enter("transaction message");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let result  = self.accept_transaction(origin_public_key, inner_msg).await;
+                    let message_hash = hash_message(TariMessageType::SenderPartialTransaction, &inner_msg);
+                    if self.is_replayed_message(&origin_public_key, &message_hash).await {
+                        continue;
+                    }
+                    let result  = self.accept_transaction(origin_public_key.clone(), inner_msg).await;
+                    if result.is_ok() {
+                        self.mark_message_processed(origin_public_key, message_hash).await;
+                    }
 
                     match result {
                         Err(TransactionServiceError::RepeatedMessageError) => {
@@ -289,8 +390,16 @@ where
                  // Incoming messages from the Comms layer
                 msg = transaction_reply_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Transaction Reply Message");
+                    let _activity = self.monitor.enter("transaction reply message");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let result = self.accept_recipient_reply(origin_public_key, inner_msg).await;
+                    let message_hash = hash_message(TariMessageType::ReceiverPartialTransactionReply, &inner_msg);
+                    if self.is_replayed_message(&origin_public_key, &message_hash).await {
+                        continue;
+                    }
+                    let result = self.accept_recipient_reply(origin_public_key.clone(), inner_msg).await;
+                    if result.is_ok() {
+                        self.mark_message_processed(origin_public_key, message_hash).await;
+                    }
 
                     match result {
                         Err(TransactionServiceError::TransactionDoesNotExistError) => {
@@ -306,36 +415,98 @@ where
                // Incoming messages from the Comms layer
                 msg = transaction_finalized_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Transaction Finalized Message");
+                    let _activity = self.monitor.enter("transaction finalized message");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let result = self.accept_finalized_transaction(origin_public_key, inner_msg, &mut transaction_broadcast_protocol_handles).await.or_else(|err| {
+                    let message_hash = hash_message(TariMessageType::TransactionFinalized, &inner_msg);
+                    if self.is_replayed_message(&origin_public_key, &message_hash).await {
+                        continue;
+                    }
+                    let result = self.accept_finalized_transaction(origin_public_key.clone(), inner_msg, &mut transaction_broadcast_protocol_handles).await.or_else(|err| {
                         error!(target: LOG_TARGET, "Failed to handle incoming Transaction Finalized message: {:?} for NodeID: {}", err , self.node_identity.node_id().short_str());
                         Err(err)
                     });
 
                     if result.is_err() {
                         let _ = self.event_publisher.send(Arc::new(TransactionEvent::Error("Error handling Transaction Finalized message".to_string(),)));
+                    } else {
+                        self.mark_message_processed(origin_public_key, message_hash).await;
                     }
                 },
                 // Incoming messages from the Comms layer
+                msg = transaction_status_query_stream.select_next_some() => {
+                    trace!(target: LOG_TARGET, "Handling Transaction Status Query");
+                    let _activity = self.monitor.enter("transaction status query");
+                    let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
+                    let _ = self.handle_transaction_status_query(origin_public_key, inner_msg).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling transaction status query: {:?}", resp);
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = transaction_status_reply_stream.select_next_some() => {
+                    trace!(target: LOG_TARGET, "Handling Transaction Status Reply");
+                    let _activity = self.monitor.enter("transaction status reply");
+                    let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
+                    let _ = self.handle_transaction_status_reply(origin_public_key, inner_msg).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling transaction status reply: {:?}", resp);
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
                 msg = mempool_response_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Mempool Response");
+                    let _activity = self.monitor.enter("mempool response");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let _ = self.handle_mempool_response(inner_msg).await.or_else(|resp| {
+                    let message_hash = hash_message(TariMessageType::MempoolResponse, &inner_msg);
+                    if self.is_replayed_message(&origin_public_key, &message_hash).await {
+                        continue;
+                    }
+                    let result = self.handle_mempool_response(inner_msg).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling mempool service response: {:?}", resp);
                         Err(resp)
                     });
+                    if result.is_ok() {
+                        self.mark_message_processed(origin_public_key, message_hash).await;
+                    }
                 }
                 // Incoming messages from the Comms layer
                 msg = base_node_response_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Base Node Response");
+                    let _activity = self.monitor.enter("base node response");
                     let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
-                    let _ = self.handle_base_node_response(inner_msg).await.or_else(|resp| {
+                    let message_hash = hash_message(TariMessageType::BaseNodeResponse, &inner_msg);
+                    if self.is_replayed_message(&origin_public_key, &message_hash).await {
+                        continue;
+                    }
+                    let result = self.handle_base_node_response(inner_msg).await.or_else(|resp| {
                         error!(target: LOG_TARGET, "Error handling base node service response from {}: {:?} for NodeID: {}", origin_public_key, resp, self.node_identity.node_id().short_str());
                         Err(resp)
                     });
+                    if result.is_ok() {
+                        self.mark_message_processed(origin_public_key, message_hash).await;
+                    }
+                }
+                _ = processed_message_prune_ticker.select_next_some() => {
+                    let _activity = self.monitor.enter("processed message pruning");
+                    self.prune_processed_messages().await;
+                }
+                _ = pending_transaction_status_query_ticker.select_next_some() => {
+                    let _activity = self.monitor.enter("pending transaction status query");
+                    let _ = self.query_stale_pending_transactions().await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error querying the status of pending transactions: {:?}", resp);
+                        Err(resp)
+                    });
+                }
+                _ = scheduled_payment_ticker.select_next_some() => {
+                    let _activity = self.monitor.enter("scheduled payments");
+                    let _ = self.send_due_scheduled_payments(&mut send_transaction_protocol_handles).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error sending scheduled payments: {:?}", resp);
+                        Err(resp)
+                    });
                 }
                 join_result = send_transaction_protocol_handles.select_next_some() => {
                     trace!(target: LOG_TARGET, "Send Protocol for Transaction has ended with result {:?}", join_result);
+                    let _activity = self.monitor.enter("send protocol completion");
                     match join_result {
                         Ok(join_result_inner) => self.complete_send_transaction_protocol(join_result_inner, &mut transaction_broadcast_protocol_handles).await,
                         Err(e) => error!(target: LOG_TARGET, "Error resolving Join Handle: {:?}", e),
@@ -343,6 +514,7 @@ where
                 }
                 join_result = transaction_broadcast_protocol_handles.select_next_some() => {
                     trace!(target: LOG_TARGET, "Transaction Broadcast protocol has ended with result {:?}", join_result);
+                    let _activity = self.monitor.enter("broadcast protocol completion");
                     match join_result {
                         Ok(join_result_inner) => self.complete_transaction_broadcast_protocol(join_result_inner, &mut transaction_chain_monitoring_protocol_handles).await,
                         Err(e) => error!(target: LOG_TARGET, "Error resolving Join Handle: {:?}", e),
@@ -350,11 +522,20 @@ where
                 }
                 join_result = transaction_chain_monitoring_protocol_handles.select_next_some() => {
                     trace!(target: LOG_TARGET, "Transaction chain monitoring protocol has ended with result {:?}", join_result);
+                    let _activity = self.monitor.enter("chain monitoring protocol completion");
                     match join_result {
                         Ok(join_result_inner) => self.complete_transaction_chain_monitoring_protocol(join_result_inner),
                         Err(e) => error!(target: LOG_TARGET, "Error resolving Join Handle: {:?}", e),
                     };
                 }
+                join_result = history_reconstruction_protocol_handles.select_next_some() => {
+                    trace!(target: LOG_TARGET, "Transaction history reconstruction protocol has ended with result {:?}", join_result);
+                    let _activity = self.monitor.enter("history reconstruction protocol completion");
+                    match join_result {
+                        Ok(join_result_inner) => self.complete_history_reconstruction_protocol(join_result_inner),
+                        Err(e) => error!(target: LOG_TARGET, "Error resolving Join Handle: {:?}", e),
+                    };
+                }
                 complete => {
                     info!(target: LOG_TARGET, "Transaction service shutting down");
                     break;
@@ -373,24 +554,66 @@ where
             JoinHandle<Result<u64, TransactionServiceProtocolError>>,
         >,
         chain_monitoring_join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+        history_reconstruction_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
     ) -> Result<TransactionServiceResponse, TransactionServiceError>
     {
         trace!(target: LOG_TARGET, "Handling Service Request: {}", request);
         match request {
-            TransactionServiceRequest::SendTransaction((dest_pubkey, amount, fee_per_gram, message)) => self
+            TransactionServiceRequest::SendTransaction((dest_pubkey, amount, fee_per_gram, message, options)) => self
                 .send_transaction(
                     dest_pubkey,
                     amount,
                     fee_per_gram,
                     message,
+                    options,
+                    None,
                     send_transaction_join_handles,
                 )
                 .await
                 .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::SendTransactionWithPriority((dest_pubkey, amount, fee_priority, message)) => {
+                let fee_per_gram = self.resolve_fee_per_gram(fee_priority);
+                self.send_transaction(
+                    dest_pubkey,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    TransactionSendOptions::default(),
+                    Some(fee_priority),
+                    send_transaction_join_handles,
+                )
+                .await
+                .map(TransactionServiceResponse::TransactionSent)
+            },
+            TransactionServiceRequest::SetFeeEstimator(estimator) => {
+                self.fee_estimator = estimator;
+                Ok(TransactionServiceResponse::FeeEstimatorSet)
+            },
+            TransactionServiceRequest::SetSendingLocked(locked) => {
+                self.sending_locked = locked;
+                Ok(TransactionServiceResponse::SendingLockSet)
+            },
+            TransactionServiceRequest::GetFeePerGram(fee_priority) => Ok(TransactionServiceResponse::FeePerGram(
+                self.resolve_fee_per_gram(fee_priority),
+            )),
             TransactionServiceRequest::CancelTransaction(tx_id) => self
                 .cancel_transaction(tx_id)
                 .await
                 .map(|_| TransactionServiceResponse::TransactionCancelled),
+            TransactionServiceRequest::RebroadcastWithHigherFee((tx_id, fee_per_gram)) => self
+                .rebroadcast_with_higher_fee(tx_id, fee_per_gram, send_transaction_join_handles)
+                .await
+                .map(TransactionServiceResponse::TransactionReplaced),
+            TransactionServiceRequest::QueryTransactionStatus(tx_id) => self
+                .query_transaction_status(tx_id)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionStatusQuerySent),
+            TransactionServiceRequest::GetRawTransaction(tx_id) => self
+                .get_raw_transaction(tx_id)
+                .await
+                .map(TransactionServiceResponse::RawTransaction),
             TransactionServiceRequest::GetPendingInboundTransactions => Ok(
                 TransactionServiceResponse::PendingInboundTransactions(self.get_pending_inbound_transactions().await?),
             ),
@@ -431,6 +654,30 @@ where
                 .submit_transaction(transaction_broadcast_join_handles, tx_id, tx, fee, amount, message)
                 .await
                 .map(|_| TransactionServiceResponse::TransactionSubmitted),
+            TransactionServiceRequest::ReconstructTransactionHistory => self
+                .start_history_reconstruction_protocol(history_reconstruction_join_handles)
+                .map(TransactionServiceResponse::TransactionHistoryReconstructionStarted),
+            TransactionServiceRequest::SchedulePayment((
+                dest_pubkey,
+                amount,
+                interval,
+                start_time,
+                fee_priority,
+                message,
+            )) => self
+                .schedule_payment(dest_pubkey, amount, interval, start_time, fee_priority, message)
+                .await
+                .map(TransactionServiceResponse::PaymentScheduled),
+            TransactionServiceRequest::CancelScheduledPayment(id) => {
+                self.db
+                    .remove_scheduled_payment(id)
+                    .await?
+                    .ok_or_else(|| TransactionServiceError::ScheduledPaymentNotFound(id))?;
+                Ok(TransactionServiceResponse::ScheduledPaymentCancelled)
+            },
+            TransactionServiceRequest::GetScheduledPayments => Ok(TransactionServiceResponse::ScheduledPayments(
+                self.db.get_scheduled_payments().await?,
+            )),
             #[cfg(feature = "test_harness")]
             TransactionServiceRequest::CompletePendingOutboundTransaction(completed_transaction) => {
                 self.complete_pending_outbound_transaction(completed_transaction)
@@ -465,21 +712,52 @@ where
     /// 'dest_pubkey': The Comms pubkey of the recipient node
     /// 'amount': The amount of Tari to send to the recipient
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    /// 'options': Options controlling which outputs may be used to fund the transaction
     pub async fn send_transaction(
         &mut self,
         dest_pubkey: CommsPublicKey,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
+        options: TransactionSendOptions,
+        fee_priority: Option<FeePriority>,
         join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
     ) -> Result<TxId, TransactionServiceError>
     {
+        if self.sending_locked {
+            return Err(TransactionServiceError::SendingLocked);
+        }
         let sender_protocol = self
             .output_manager_service
-            .prepare_transaction_to_send(amount, fee_per_gram, None, message.clone())
+            .prepare_transaction_to_send_with_options(amount, fee_per_gram, None, message.clone(), options)
             .await?;
 
+        self.start_send_protocol(
+            sender_protocol,
+            dest_pubkey,
+            amount,
+            message,
+            fee_priority,
+            join_handles,
+        )
+        .await
+    }
+
+    /// Spawn the protocol that negotiates a prepared outbound transaction with its recipient
+    async fn start_send_protocol(
+        &mut self,
+        sender_protocol: SenderTransactionProtocol,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        message: String,
+        fee_priority: Option<FeePriority>,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<TxId, TransactionServiceError>
+    {
         let tx_id = sender_protocol.get_tx_id()?;
+        self.output_manager_service
+            .set_pending_transaction_destination(tx_id, dest_pubkey.clone())
+            .await?;
 
         let (tx_reply_sender, tx_reply_receiver) = mpsc::channel(100);
         let (cancellation_sender, cancellation_receiver) = oneshot::channel();
@@ -496,6 +774,7 @@ where
             message,
             sender_protocol,
             TransactionProtocolStage::Initial,
+            fee_priority,
         );
 
         let join_handle = tokio::spawn(protocol.execute());
@@ -504,6 +783,59 @@ where
         Ok(tx_id)
     }
 
+    /// Resolve a fee preset to a fee per gram, using the fee estimator if one has been set and has an estimate and
+    /// the configured preset otherwise
+    fn resolve_fee_per_gram(&self, fee_priority: FeePriority) -> MicroTari {
+        self.fee_estimator
+            .as_ref()
+            .and_then(|estimator| estimator.estimate(fee_priority))
+            .unwrap_or_else(|| self.config.fee_per_gram(fee_priority))
+    }
+
+    /// Check whether this exact message has already been processed from this sender. Messages are only recorded once
+    /// they have been handled successfully, so a failure to read the record is treated as an unseen message.
+    async fn is_replayed_message(&self, sender: &CommsPublicKey, message_hash: &[u8]) -> bool {
+        match self
+            .db
+            .is_message_processed(sender.clone(), message_hash.to_vec())
+            .await
+        {
+            Ok(true) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ignoring replayed message from {} that has already been processed", sender
+                );
+                true
+            },
+            Ok(false) => false,
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not check whether message was processed: {:?}", e
+                );
+                false
+            },
+        }
+    }
+
+    async fn mark_message_processed(&self, sender: CommsPublicKey, message_hash: Vec<u8>) {
+        if let Err(e) = self.db.mark_message_processed(sender, message_hash).await {
+            warn!(target: LOG_TARGET, "Could not record processed message: {:?}", e);
+        }
+    }
+
+    /// Remove the replay protection records that are older than the configured retention period
+    async fn prune_processed_messages(&self) {
+        match self
+            .db
+            .prune_processed_messages(self.config.processed_message_retention)
+            .await
+        {
+            Ok(n) => trace!(target: LOG_TARGET, "Pruned {} processed message records", n),
+            Err(e) => warn!(target: LOG_TARGET, "Could not prune processed message records: {:?}", e),
+        }
+    }
+
     /// Accept the public reply from a recipient and apply the reply to the relevant transaction protocol
     /// # Arguments
     /// 'recipient_reply' - The public response from a recipient with data required to complete the transaction
@@ -609,6 +941,426 @@ where
         Ok(())
     }
 
+    /// Replace an outbound transaction that has not been broadcast with a transaction to the same recipient that spends
+    /// the same inputs at a higher fee per gram. Spending the same inputs ensures that at most one of the two can be
+    /// mined. The replaced transaction is cancelled and linked to its replacement in the database. If the replacement
+    /// can't be built the replaced transaction is left as it was.
+    pub async fn rebroadcast_with_higher_fee(
+        &mut self,
+        tx_id: TxId,
+        fee_per_gram: MicroTari,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        if self.sending_locked {
+            return Err(TransactionServiceError::SendingLocked);
+        }
+        let (dest_pubkey, amount, message, fee, fee_priority, completed) =
+            match self.db.get_pending_outbound_transaction(tx_id).await {
+                Ok(tx) => (
+                    tx.destination_public_key,
+                    tx.amount,
+                    tx.message,
+                    tx.fee,
+                    tx.fee_priority,
+                    false,
+                ),
+                Err(_) => {
+                    let tx = self.db.get_completed_transaction(tx_id).await?;
+                    // A broadcast transaction may already be in the mempools of other nodes, where it can't be
+                    // replaced
+                    if tx.source_public_key != *self.node_identity.public_key() ||
+                        tx.status != TransactionStatus::Completed
+                    {
+                        return Err(TransactionServiceError::TransactionNotReplaceable);
+                    }
+                    (
+                        tx.destination_public_key,
+                        tx.amount,
+                        tx.message,
+                        tx.fee,
+                        tx.fee_priority,
+                        true,
+                    )
+                },
+            };
+
+        let pending_outputs = self
+            .output_manager_service
+            .get_pending_transactions()
+            .await?
+            .remove(&tx_id)
+            .ok_or_else(|| TransactionServiceError::TransactionNotReplaceable)?;
+        // The replacement has at least the recipient output and the change outputs of the replaced transaction, so its
+        // fee can be no lower than this
+        let new_fee = Fee::calculate(
+            fee_per_gram,
+            1,
+            pending_outputs.outputs_to_be_spent.len(),
+            pending_outputs.outputs_to_be_received.len() + 1,
+        );
+        if new_fee <= fee {
+            return Err(TransactionServiceError::FeeNotIncreased);
+        }
+        let inputs = pending_outputs
+            .outputs_to_be_spent
+            .iter()
+            .map(|o| self.factories.commitment.commit(&o.spending_key, &o.value.into()))
+            .collect();
+
+        // Release the inputs of the replaced transaction so that they can be spent by the replacement, and encumber
+        // them against it again if the replacement can't be built
+        self.output_manager_service.cancel_transaction(tx_id).await?;
+        let sender_protocol = match self
+            .output_manager_service
+            .prepare_transaction_to_send_with_inputs(amount, fee_per_gram, None, message.clone(), inputs)
+            .await
+        {
+            Ok(sender_protocol) => sender_protocol,
+            Err(e) => {
+                self.output_manager_service
+                    .reinstate_transaction(pending_outputs)
+                    .await?;
+                return Err(e.into());
+            },
+        };
+        let replacement_tx_id = sender_protocol.get_tx_id()?;
+        if let Err(e) = self
+            .start_send_protocol(
+                sender_protocol,
+                dest_pubkey,
+                amount,
+                message,
+                fee_priority,
+                join_handles,
+            )
+            .await
+        {
+            self.output_manager_service
+                .cancel_transaction(replacement_tx_id)
+                .await?;
+            self.output_manager_service
+                .reinstate_transaction(pending_outputs)
+                .await?;
+            return Err(e);
+        }
+
+        if completed {
+            self.db.cancel_completed_transaction(tx_id).await?;
+        } else {
+            self.db.cancel_pending_transaction(tx_id).await?;
+            if let Some(cancellation_sender) = self.send_transaction_cancellation_senders.remove(&tx_id) {
+                let _ = cancellation_sender.send(());
+            }
+            let _ = self.pending_transaction_reply_senders.remove(&tx_id);
+        }
+        self.db.link_replacement_transaction(tx_id, replacement_tx_id).await?;
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionReplaced(
+                tx_id,
+                replacement_tx_id,
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+
+        info!(
+            target: LOG_TARGET,
+            "Transaction (TxId: {}) replaced by transaction (TxId: {}) at {} per gram",
+            tx_id,
+            replacement_tx_id,
+            fee_per_gram
+        );
+
+        Ok(replacement_tx_id)
+    }
+
+    /// Ask the recipient of a pending outbound transaction whether it has seen the transaction
+    pub async fn query_transaction_status(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let outbound_tx = self.db.get_pending_outbound_transaction(tx_id).await?;
+        self.send_transaction_status_query(tx_id, outbound_tx.destination_public_key)
+            .await
+    }
+
+    /// Returns the finalized transaction of a completed transaction in its canonical binary encoding. Imported,
+    /// cancelled and reconstructed transactions have no transaction that could be submitted.
+    pub async fn get_raw_transaction(&mut self, tx_id: TxId) -> Result<Vec<u8>, TransactionServiceError> {
+        let completed_tx = self.db.get_completed_transaction(tx_id).await?;
+        match completed_tx.status {
+            TransactionStatus::Imported | TransactionStatus::Cancelled | TransactionStatus::Reconstructed => {
+                Err(TransactionServiceError::InvalidCompletedTransaction)
+            },
+            _ => Ok(completed_tx.transaction.to_raw_bytes()),
+        }
+    }
+
+    async fn send_transaction_status_query(
+        &mut self,
+        tx_id: TxId,
+        dest_pubkey: CommsPublicKey,
+    ) -> Result<(), TransactionServiceError>
+    {
+        self.outbound_message_service
+            .send_direct(
+                dest_pubkey.clone(),
+                OutboundEncryption::EncryptFor(Box::new(dest_pubkey.clone())),
+                OutboundDomainMessage::new(TariMessageType::TransactionStatusQuery, proto::TransactionStatusQuery {
+                    tx_id,
+                }),
+            )
+            .await?;
+        debug!(
+            target: LOG_TARGET,
+            "Transaction status query for TxId: {} sent to {}", tx_id, dest_pubkey
+        );
+        Ok(())
+    }
+
+    /// Query the recipients of the pending outbound transactions that have been waiting for a reply for longer than
+    /// the status query interval, so that transactions stuck because a message was lost can be resolved
+    async fn query_stale_pending_transactions(&mut self) -> Result<(), TransactionServiceError> {
+        let now = Utc::now().naive_utc();
+        let interval = self.config.pending_transaction_status_query_interval;
+        let outbound_txs = self.db.get_pending_outbound_transactions().await?;
+        for (tx_id, tx) in outbound_txs {
+            let is_stale = now
+                .signed_duration_since(tx.timestamp)
+                .to_std()
+                .map(|age| age >= interval)
+                .unwrap_or(false);
+            if !is_stale {
+                continue;
+            }
+            if let Err(e) = self
+                .send_transaction_status_query(tx_id, tx.destination_public_key)
+                .await
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not send transaction status query for TxId: {}: {:?}", tx_id, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Store a new payment schedule and return its id. The first payment is sent on the first check at or after
+    /// `start_time`.
+    async fn schedule_payment(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        interval: Duration,
+        start_time: NaiveDateTime,
+        fee_priority: FeePriority,
+        message: String,
+    ) -> Result<u64, TransactionServiceError>
+    {
+        if interval.as_secs() == 0 {
+            return Err(TransactionServiceError::InvalidPaymentInterval);
+        }
+        let id = OsRng.next_u64();
+        self.db
+            .save_scheduled_payment(ScheduledPayment {
+                id,
+                destination_public_key: dest_pubkey,
+                amount,
+                fee_priority,
+                message,
+                interval,
+                next_payment_at: start_time,
+                payments_made: 0,
+                last_tx_id: None,
+            })
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Scheduled payment {} of {} every {}s starting at {}",
+            id,
+            amount,
+            interval.as_secs(),
+            start_time
+        );
+        Ok(id)
+    }
+
+    /// Send every scheduled payment that has fallen due and move its schedule on to the next payment. A payment that
+    /// cannot be sent, e.g. because the wallet has insufficient funds, is reported and not retried until the next
+    /// period.
+    async fn send_due_scheduled_payments(
+        &mut self,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let now = Utc::now().naive_utc();
+        let due_payments = self
+            .db
+            .get_scheduled_payments()
+            .await?
+            .into_iter()
+            .filter(|p| p.is_due(now));
+        for mut payment in due_payments {
+            let fee_per_gram = self.resolve_fee_per_gram(payment.fee_priority);
+            let result = self
+                .send_transaction(
+                    payment.destination_public_key.clone(),
+                    payment.amount,
+                    fee_per_gram,
+                    payment.message.clone(),
+                    TransactionSendOptions::default(),
+                    Some(payment.fee_priority),
+                    join_handles,
+                )
+                .await;
+            let event = match result {
+                Ok(tx_id) => {
+                    payment.payments_made += 1;
+                    payment.last_tx_id = Some(tx_id);
+                    TransactionEvent::ScheduledPaymentSent(payment.id, tx_id)
+                },
+                Err(e) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Could not send scheduled payment {}: {:?}", payment.id, e
+                    );
+                    TransactionEvent::ScheduledPaymentFailed(payment.id, format!("{:?}", e))
+                },
+            };
+            payment.advance(now);
+            self.db.save_scheduled_payment(payment).await?;
+            let _ = self.event_publisher.send(Arc::new(event));
+        }
+        Ok(())
+    }
+
+    /// Answer a transaction status query from a counterparty. Only the status of transactions received from the
+    /// querying party is disclosed, all other transactions are reported as unknown. If the transaction is still
+    /// pending our reply is sent again in case the sender never received it.
+    async fn handle_transaction_status_query(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        query: proto::TransactionStatusQuery,
+    ) -> Result<(), TransactionServiceError>
+    {
+        if !self.config.respond_to_status_queries {
+            trace!(
+                target: LOG_TARGET,
+                "Ignoring transaction status query from {} because status queries are disabled",
+                source_pubkey
+            );
+            return Ok(());
+        }
+
+        let tx_id = query.tx_id;
+        let status = match self.db.get_pending_inbound_transaction(tx_id).await {
+            Ok(tx) if tx.source_public_key == source_pubkey => {
+                let recipient_reply: proto::RecipientSignedMessage =
+                    tx.receiver_protocol.get_signed_data()?.clone().into();
+                self.outbound_message_service
+                    .send_direct(
+                        source_pubkey.clone(),
+                        OutboundEncryption::None,
+                        OutboundDomainMessage::new(TariMessageType::ReceiverPartialTransactionReply, recipient_reply),
+                    )
+                    .await?;
+                CounterpartyTransactionStatus::Pending
+            },
+            _ => match self.db.get_completed_transaction(tx_id).await {
+                Ok(tx) if tx.source_public_key == source_pubkey => CounterpartyTransactionStatus::Completed,
+                _ => CounterpartyTransactionStatus::Unknown,
+            },
+        };
+
+        let reply = proto::TransactionStatusReply {
+            tx_id,
+            status: proto::CounterpartyTransactionStatus::from(status) as i32,
+        };
+        self.outbound_message_service
+            .send_direct(
+                source_pubkey.clone(),
+                OutboundEncryption::EncryptFor(Box::new(source_pubkey.clone())),
+                OutboundDomainMessage::new(TariMessageType::TransactionStatusReply, reply),
+            )
+            .await?;
+        debug!(
+            target: LOG_TARGET,
+            "Replied to transaction status query for TxId: {} from {} with status {}", tx_id, source_pubkey, status
+        );
+        Ok(())
+    }
+
+    /// Handle the reply to a transaction status query. If the recipient has no record of the transaction the sender
+    /// message is sent again.
+    async fn handle_transaction_status_reply(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        reply: proto::TransactionStatusReply,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = reply.tx_id;
+        let status: CounterpartyTransactionStatus = proto::CounterpartyTransactionStatus::from_i32(reply.status)
+            .ok_or_else(|| {
+                TransactionServiceError::InvalidMessageError("Invalid counterparty transaction status".to_string())
+            })?
+            .into();
+
+        let outbound_tx = match self.db.get_pending_outbound_transaction(tx_id).await {
+            Ok(tx) => tx,
+            Err(_) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Transaction status reply for TxId: {} does not match a pending outbound transaction", tx_id
+                );
+                return Ok(());
+            },
+        };
+        if outbound_tx.destination_public_key != source_pubkey {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+        info!(
+            target: LOG_TARGET,
+            "Recipient reported status {} for pending transaction TxId: {}", status, tx_id
+        );
+
+        if status == CounterpartyTransactionStatus::Unknown {
+            let msg = outbound_tx.sender_protocol.get_single_round_message()?;
+            let proto_message = proto::TransactionSenderMessage::single(msg.into());
+            self.outbound_message_service
+                .send_direct(
+                    source_pubkey,
+                    OutboundEncryption::None,
+                    OutboundDomainMessage::new(TariMessageType::SenderPartialTransaction, proto_message),
+                )
+                .await?;
+            debug!(
+                target: LOG_TARGET,
+                "Resent the sender message for TxId: {} to the recipient", tx_id
+            );
+        }
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionStatusReplyReceived(
+                tx_id, status,
+            )))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        Ok(())
+    }
+
     async fn restart_all_send_transaction_protocols(
         &mut self,
         join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
@@ -636,6 +1388,7 @@ where
                     tx.message,
                     tx.sender_protocol,
                     TransactionProtocolStage::WaitForReply,
+                    tx.fee_priority,
                 );
 
                 let join_handle = tokio::spawn(protocol.execute());
@@ -685,15 +1438,25 @@ where
                 .output_manager_service
                 .get_recipient_spending_key(data.tx_id, data.amount)
                 .await?;
+            self.output_manager_service
+                .set_output_source(data.tx_id, source_pubkey.to_hex())
+                .await?;
             let nonce = PrivateKey::random(&mut OsRng);
 
-            let rtp = ReceiverTransactionProtocol::new(
-                sender_message,
-                nonce,
-                spending_key,
-                OutputFeatures::default(),
-                &self.factories,
-            );
+            // Constructing the receiver protocol generates the range proof of our output, so keep it off the core
+            // threads that drive the comms event loops
+            let factories = self.factories.clone();
+            let rtp = tokio::task::spawn_blocking(move || {
+                ReceiverTransactionProtocol::new(
+                    sender_message,
+                    nonce,
+                    spending_key,
+                    OutputFeatures::default(),
+                    &factories,
+                )
+            })
+            .await
+            .map_err(|e| TransactionServiceError::BlockingTaskSpawnError(e.to_string()))?;
             let recipient_reply = rtp.get_signed_data()?.clone();
 
             let tx_id = recipient_reply.tx_id;
@@ -833,6 +1596,7 @@ where
             status: TransactionStatus::Completed,
             message: inbound_tx.message.clone(),
             timestamp: inbound_tx.timestamp,
+            fee_priority: None,
         };
 
         self.db
@@ -949,6 +1713,7 @@ where
                 status: TransactionStatus::Completed,
                 message: "Coinbase Transaction".to_string(),
                 timestamp: Utc::now().naive_utc(),
+                fee_priority: None,
             })
             .await?;
 
@@ -1067,12 +1832,20 @@ where
                     tx_id,
                     self.service_resources.clone(),
                     self.config.mempool_broadcast_timeout,
+                    self.config.propagation_sample_size,
+                    self.config.propagation_sample_timeout,
                     pk,
                     mempool_response_receiver,
                     base_node_response_receiver,
                 );
                 let join_handle = tokio::spawn(protocol.execute());
                 join_handles.push(join_handle);
+                let _ = self
+                    .event_publisher
+                    .send(Arc::new(TransactionEvent::TransactionNegotiationProgress(
+                        tx_id,
+                        NegotiationStage::BroadcastPending,
+                    )));
             },
         }
 
@@ -1254,6 +2027,59 @@ where
         }
     }
 
+    /// Start a protocol that adds a reconstructed transaction for every unspent output that no transaction accounts
+    /// for, using the blocks the outputs were mined in as reported by the base node. Returns the protocol id.
+    fn start_history_reconstruction_protocol(
+        &mut self,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<u64, TransactionServiceError>
+    {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or_else(|| TransactionServiceError::NoBaseNodeKeysProvided)?;
+
+        let protocol_id = OsRng.next_u64();
+        let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(100);
+        self.base_node_response_senders
+            .insert(protocol_id, base_node_response_sender);
+        let protocol = TransactionHistoryReconstructionProtocol::new(
+            protocol_id,
+            self.service_resources.clone(),
+            self.config.base_node_mined_timeout,
+            base_node_public_key,
+            base_node_response_receiver,
+        );
+        let join_handle = tokio::spawn(protocol.execute());
+        join_handles.push(join_handle);
+
+        Ok(protocol_id)
+    }
+
+    /// Handle the final clean up after a Transaction History Reconstruction protocol completes
+    fn complete_history_reconstruction_protocol(&mut self, join_result: Result<u64, TransactionServiceProtocolError>) {
+        match join_result {
+            Ok(id) => {
+                let _ = self.base_node_response_senders.remove(&id);
+                trace!(
+                    target: LOG_TARGET,
+                    "Transaction history reconstruction Protocol (Id: {}) completed successfully",
+                    id
+                );
+            },
+            Err(TransactionServiceProtocolError { id, error }) => {
+                let _ = self.base_node_response_senders.remove(&id);
+                error!(
+                    target: LOG_TARGET,
+                    "Error completing Transaction history reconstruction Protocol (Id: {}): {:?}", id, error
+                );
+                let _ = self
+                    .event_publisher
+                    .send(Arc::new(TransactionEvent::Error(format!("{:?}", error))));
+            },
+        }
+    }
+
     /// Handle an incoming basenode response message
     pub async fn handle_base_node_response(
         &mut self,
@@ -1335,6 +2161,11 @@ where
         message: String,
     ) -> Result<(), TransactionServiceError>
     {
+        if self.sending_locked {
+            // The outputs spent by the transaction were encumbered when it was created, so they are released again
+            self.output_manager_service.cancel_transaction(tx_id).await?;
+            return Err(TransactionServiceError::SendingLocked);
+        }
         trace!(target: LOG_TARGET, "Submit transaction ({}) to db.", tx_id);
         self.db
             .insert_completed_transaction(tx_id, CompletedTransaction {
@@ -1347,6 +2178,7 @@ where
                 status: TransactionStatus::Completed,
                 message,
                 timestamp: Utc::now().naive_utc(),
+                fee_priority: None,
             })
             .await?;
         trace!(
@@ -1423,7 +2255,7 @@ where
                 pending_tx
                     .outputs_to_be_spent
                     .iter()
-                    .map(|o| o.as_transaction_input(&self.factories.commitment, OutputFeatures::default()))
+                    .map(|o| o.as_transaction_input(&self.factories.commitment, o.features.clone()))
                     .collect(),
                 pending_tx
                     .outputs_to_be_received
@@ -1563,6 +2395,7 @@ where
             status: TransactionStatus::Completed,
             message: found_tx.message.clone(),
             timestamp: found_tx.timestamp,
+            fee_priority: None,
         };
 
         self.db
@@ -1583,6 +2416,19 @@ where
     }
 }
 
+/// Hash a peer message so that a replay of it can be recognised. The message type is included so that identical
+/// payloads received on different message types are not confused with one another.
+fn hash_message<M: Message>(message_type: TariMessageType, message: &M) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(message.encoded_len());
+    // Encoding into a Vec can only fail if there is insufficient capacity, which a Vec will grow to provide
+    let _ = message.encode(&mut buf);
+    HashDigest::new()
+        .chain((message_type as i32).to_le_bytes())
+        .chain(buf)
+        .result()
+        .to_vec()
+}
+
 /// This struct is a collection of the common resources that a protocol in the service requires.
 #[derive(Clone)]
 pub struct TransactionServiceResources<TBackend>
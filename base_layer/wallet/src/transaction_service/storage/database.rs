@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{output_manager_service::TxId, transaction_service::error::TransactionStorageError};
-use chrono::{NaiveDateTime, Utc};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -29,6 +29,7 @@ use std::{
     convert::TryFrom,
     fmt::{Display, Error, Formatter},
     sync::Arc,
+    time::Duration,
 };
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
@@ -83,6 +84,34 @@ pub trait TransactionBackend: Send + Sync {
     fn cancel_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
     /// Cancel Completed transaction, this will update the transaction status
     fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Check whether a message with the given hash from the given sender has already been processed
+    fn is_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+    ) -> Result<bool, TransactionStorageError>;
+    /// Record that a message with the given hash from the given sender has been processed so that replays of it can
+    /// be ignored
+    fn mark_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), TransactionStorageError>;
+    /// Remove the records of processed messages that were processed before the given time, returning how many were
+    /// removed
+    fn prune_processed_messages(&self, older_than: NaiveDateTime) -> Result<usize, TransactionStorageError>;
+    /// Record that a transaction was replaced by a transaction that spends the same inputs at a higher fee
+    fn link_replacement_transaction(&self, tx_id: TxId, replacement_tx_id: TxId)
+        -> Result<(), TransactionStorageError>;
+    /// The transaction that replaced the given transaction, if it was replaced
+    fn get_replacement_transaction(&self, tx_id: TxId) -> Result<Option<TxId>, TransactionStorageError>;
+    /// Insert a scheduled payment, or replace the stored schedule with the same id
+    fn save_scheduled_payment(&self, payment: ScheduledPayment) -> Result<(), TransactionStorageError>;
+    /// All the scheduled payments that have not been cancelled
+    fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError>;
+    /// Remove a scheduled payment, returning it if it existed
+    fn remove_scheduled_payment(&self, id: u64) -> Result<Option<ScheduledPayment>, TransactionStorageError>;
     /// Update a completed transactions timestamp for use in test data generation
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
@@ -107,6 +136,9 @@ pub enum TransactionStatus {
     Pending,
     /// This transaction has been cancelled
     Cancelled,
+    /// This transaction was reconstructed from an output found on the blockchain, e.g. after restoring a wallet from
+    /// its seed. Only its direction, amount and the height of the block it was mined in are known.
+    Reconstructed,
 }
 
 impl TryFrom<i32> for TransactionStatus {
@@ -120,6 +152,7 @@ impl TryFrom<i32> for TransactionStatus {
             3 => Ok(TransactionStatus::Imported),
             4 => Ok(TransactionStatus::Pending),
             5 => Ok(TransactionStatus::Cancelled),
+            6 => Ok(TransactionStatus::Reconstructed),
             _ => Err(TransactionStorageError::ConversionError),
         }
     }
@@ -138,6 +171,80 @@ impl Display for TransactionStatus {
     }
 }
 
+/// A named fee level that is resolved to a fee per gram when a transaction is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FeePriority {
+    /// A low fee for transactions that can wait to be mined
+    Slow = 0,
+    /// The default fee for transactions that should be mined within a few blocks
+    Normal = 1,
+    /// A high fee for transactions that should be mined as soon as possible
+    Fast = 2,
+}
+
+impl TryFrom<i32> for FeePriority {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FeePriority::Slow),
+            1 => Ok(FeePriority::Normal),
+            2 => Ok(FeePriority::Fast),
+            _ => Err(TransactionStorageError::ConversionError),
+        }
+    }
+}
+
+impl Default for FeePriority {
+    fn default() -> Self {
+        FeePriority::Normal
+    }
+}
+
+impl Display for FeePriority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        // No struct or tuple variants
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A payment that the transaction service sends to the same destination every `interval`, starting at
+/// `next_payment_at`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledPayment {
+    pub id: u64,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee_priority: FeePriority,
+    pub message: String,
+    pub interval: Duration,
+    pub next_payment_at: NaiveDateTime,
+    pub payments_made: u64,
+    /// The transaction sent by the most recent payment
+    pub last_tx_id: Option<TxId>,
+}
+
+impl ScheduledPayment {
+    pub fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.next_payment_at <= now
+    }
+
+    /// Move `next_payment_at` forward by whole intervals until it lies after `now`. Periods that were missed while the
+    /// wallet was offline are skipped rather than paid in a burst.
+    pub fn advance(&mut self, now: NaiveDateTime) {
+        let interval = match ChronoDuration::from_std(self.interval) {
+            Ok(i) if i > ChronoDuration::zero() => i,
+            _ => return,
+        };
+        if self.next_payment_at > now {
+            return;
+        }
+        let elapsed = now.signed_duration_since(self.next_payment_at);
+        let missed = elapsed.num_milliseconds() / interval.num_milliseconds().max(1);
+        self.next_payment_at = self.next_payment_at + interval * (missed as i32 + 1);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InboundTransaction {
     pub tx_id: TxId,
@@ -159,6 +266,8 @@ pub struct OutboundTransaction {
     pub status: TransactionStatus,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    /// The fee preset that was used to determine the fee of this transaction, if it was sent with one
+    pub fee_priority: Option<FeePriority>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -180,6 +289,8 @@ pub struct CompletedTransaction {
     pub status: TransactionStatus,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    /// The fee preset that was used to determine the fee of this transaction, if it was sent with one
+    pub fee_priority: Option<FeePriority>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -243,6 +354,7 @@ impl From<CompletedTransaction> for OutboundTransaction {
             status: ct.status,
             message: ct.message,
             timestamp: ct.timestamp,
+            fee_priority: ct.fee_priority,
         }
     }
 }
@@ -577,6 +689,85 @@ where T: TransactionBackend + 'static
         Ok(())
     }
 
+    /// Check whether a message with the given hash from the given sender has already been processed
+    pub async fn is_message_processed(
+        &self,
+        sender: CommsPublicKey,
+        message_hash: Vec<u8>,
+    ) -> Result<bool, TransactionStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.is_message_processed(&sender, &message_hash))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    /// Record that a message with the given hash from the given sender has been processed
+    pub async fn mark_message_processed(
+        &self,
+        sender: CommsPublicKey,
+        message_hash: Vec<u8>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.mark_message_processed(&sender, &message_hash, Utc::now().naive_utc())
+        })
+        .await
+        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    /// Remove the records of processed messages that are older than the given retention period
+    pub async fn prune_processed_messages(&self, retention: Duration) -> Result<usize, TransactionStorageError> {
+        let older_than = Utc::now().naive_utc() - ChronoDuration::from_std(retention)?;
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.prune_processed_messages(older_than))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    /// Record that a transaction was replaced by a transaction that spends the same inputs at a higher fee
+    pub async fn link_replacement_transaction(
+        &self,
+        tx_id: TxId,
+        replacement_tx_id: TxId,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.link_replacement_transaction(tx_id, replacement_tx_id))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    /// The transaction that replaced the given transaction, if it was replaced
+    pub async fn get_replacement_transaction(&self, tx_id: TxId) -> Result<Option<TxId>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.get_replacement_transaction(tx_id))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    pub async fn save_scheduled_payment(&self, payment: ScheduledPayment) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.save_scheduled_payment(payment))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    pub async fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.get_scheduled_payments())
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
+    pub async fn remove_scheduled_payment(&self, id: u64) -> Result<Option<ScheduledPayment>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.remove_scheduled_payment(id))
+            .await
+            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))?
+    }
+
     /// Indicated that the specified completed transaction has been broadcast into the mempool
     pub async fn broadcast_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
         let db_clone = self.db.clone();
@@ -617,6 +808,7 @@ where T: TransactionBackend + 'static
             status: TransactionStatus::Imported,
             message,
             timestamp: Utc::now().naive_utc(),
+            fee_priority: None,
         };
 
         let db_clone = self.db.clone();
@@ -32,18 +32,19 @@ use crate::{
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ScheduledPayment,
             TransactionBackend,
             TransactionStatus,
             WriteOperation,
         },
     },
 };
-#[cfg(feature = "test_harness")]
 use chrono::NaiveDateTime;
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
 };
+use tari_comms::types::CommsPublicKey;
 
 #[derive(Default)]
 struct InnerDatabase {
@@ -51,6 +52,9 @@ struct InnerDatabase {
     pending_inbound_transactions: HashMap<TxId, InboundTransaction>,
     pending_coinbase_transactions: HashMap<TxId, PendingCoinbaseTransaction>,
     completed_transactions: HashMap<TxId, CompletedTransaction>,
+    processed_messages: HashMap<(CommsPublicKey, Vec<u8>), NaiveDateTime>,
+    replacement_transactions: HashMap<TxId, TxId>,
+    scheduled_payments: HashMap<u64, ScheduledPayment>,
 }
 
 impl InnerDatabase {
@@ -60,6 +64,9 @@ impl InnerDatabase {
             pending_inbound_transactions: HashMap::new(),
             pending_coinbase_transactions: HashMap::new(),
             completed_transactions: HashMap::new(),
+            processed_messages: HashMap::new(),
+            replacement_transactions: HashMap::new(),
+            scheduled_payments: HashMap::new(),
         }
     }
 }
@@ -376,6 +383,70 @@ impl TransactionBackend for TransactionMemoryDatabase {
         Ok(())
     }
 
+    fn is_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+    ) -> Result<bool, TransactionStorageError>
+    {
+        let db = acquire_read_lock!(self.db);
+        Ok(db
+            .processed_messages
+            .contains_key(&(sender.clone(), message_hash.to_vec())))
+    }
+
+    fn mark_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let mut db = acquire_write_lock!(self.db);
+        db.processed_messages
+            .insert((sender.clone(), message_hash.to_vec()), timestamp);
+        Ok(())
+    }
+
+    fn prune_processed_messages(&self, older_than: NaiveDateTime) -> Result<usize, TransactionStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        let count = db.processed_messages.len();
+        db.processed_messages.retain(|_, timestamp| *timestamp >= older_than);
+        Ok(count - db.processed_messages.len())
+    }
+
+    fn link_replacement_transaction(
+        &self,
+        tx_id: TxId,
+        replacement_tx_id: TxId,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let mut db = acquire_write_lock!(self.db);
+        db.replacement_transactions.insert(tx_id, replacement_tx_id);
+        Ok(())
+    }
+
+    fn get_replacement_transaction(&self, tx_id: TxId) -> Result<Option<TxId>, TransactionStorageError> {
+        let db = acquire_read_lock!(self.db);
+        Ok(db.replacement_transactions.get(&tx_id).cloned())
+    }
+
+    fn save_scheduled_payment(&self, payment: ScheduledPayment) -> Result<(), TransactionStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        db.scheduled_payments.insert(payment.id, payment);
+        Ok(())
+    }
+
+    fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError> {
+        let db = acquire_read_lock!(self.db);
+        Ok(db.scheduled_payments.values().cloned().collect())
+    }
+
+    fn remove_scheduled_payment(&self, id: u64) -> Result<Option<ScheduledPayment>, TransactionStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        Ok(db.scheduled_payments.remove(&id))
+    }
+
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
         &self,
@@ -22,7 +22,16 @@
 
 use crate::{
     output_manager_service::TxId,
-    schema::{coinbase_transactions, completed_transactions, inbound_transactions, outbound_transactions},
+    schema::{
+        coinbase_transactions,
+        completed_transactions,
+        inbound_transactions,
+        outbound_transactions,
+        processed_messages,
+        scheduled_payments,
+        transaction_replacements,
+    },
+    storage::connection_manager::WalletDbConnection,
     transaction_service::{
         error::TransactionStorageError,
         storage::database::{
@@ -30,9 +39,11 @@ use crate::{
             DbKey,
             DbKeyValuePair,
             DbValue,
+            FeePriority,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ScheduledPayment,
             TransactionBackend,
             TransactionStatus,
             WriteOperation,
@@ -41,11 +52,8 @@ use crate::{
 };
 use chrono::NaiveDateTime;
 use diesel::{prelude::*, result::Error as DieselError, SqliteConnection};
-use std::{
-    collections::HashMap,
-    convert::TryFrom,
-    sync::{Arc, Mutex, MutexGuard},
-};
+use std::{collections::HashMap, convert::TryFrom, time::Duration};
+use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     types::{Commitment, PublicKey},
@@ -55,14 +63,14 @@ use tari_crypto::tari_utilities::ByteArray;
 /// A Sqlite backend for the Transaction Service. The Backend is accessed via a connection pool to the Sqlite file.
 #[derive(Clone)]
 pub struct TransactionServiceSqliteDatabase {
-    database_connection: Arc<Mutex<SqliteConnection>>,
+    database_connection: WalletDbConnection,
 }
 impl TransactionServiceSqliteDatabase {
-    pub fn new(database_connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(database_connection: WalletDbConnection) -> Self {
         Self { database_connection }
     }
 
-    fn insert(kvp: DbKeyValuePair, conn: MutexGuard<SqliteConnection>) -> Result<(), TransactionStorageError> {
+    fn insert(kvp: DbKeyValuePair, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
         match kvp {
             DbKeyValuePair::PendingOutboundTransaction(k, v) => {
                 if OutboundTransactionSql::find(k, &(*conn)).is_ok() {
@@ -92,7 +100,7 @@ impl TransactionServiceSqliteDatabase {
         Ok(())
     }
 
-    fn remove(key: DbKey, conn: MutexGuard<SqliteConnection>) -> Result<Option<DbValue>, TransactionStorageError> {
+    fn remove(key: DbKey, conn: &SqliteConnection) -> Result<Option<DbValue>, TransactionStorageError> {
         match key {
             DbKey::PendingOutboundTransaction(k) => match OutboundTransactionSql::find(k, &(*conn)) {
                 Ok(v) => {
@@ -152,7 +160,10 @@ impl TransactionServiceSqliteDatabase {
 
 impl TransactionBackend for TransactionServiceSqliteDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         let result = match key {
             DbKey::PendingOutboundTransaction(t) => match OutboundTransactionSql::find(*t, &(*conn)) {
@@ -230,7 +241,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn contains(&self, key: &DbKey) -> Result<bool, TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         let result = match key {
             DbKey::PendingOutboundTransaction(k) => OutboundTransactionSql::find(*k, &(*conn)).is_ok(),
@@ -247,17 +261,23 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         match op {
-            WriteOperation::Insert(kvp) => TransactionServiceSqliteDatabase::insert(kvp, conn).map(|_| None),
+            WriteOperation::Insert(kvp) => TransactionServiceSqliteDatabase::insert(kvp, &conn).map(|_| None),
 
-            WriteOperation::Remove(key) => TransactionServiceSqliteDatabase::remove(key, conn),
+            WriteOperation::Remove(key) => TransactionServiceSqliteDatabase::remove(key, &conn),
         }
     }
 
     fn transaction_exists(&self, tx_id: u64) -> Result<bool, TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         Ok(OutboundTransactionSql::find(tx_id, &(*conn)).is_ok() ||
             InboundTransactionSql::find(tx_id, &(*conn)).is_ok() ||
@@ -271,7 +291,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         if CompletedTransactionSql::find(tx_id, &(*conn)).is_ok() {
             return Err(TransactionStorageError::TransactionAlreadyExists);
@@ -299,7 +322,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         if CompletedTransactionSql::find(tx_id, &(*conn)).is_ok() {
             return Err(TransactionStorageError::TransactionAlreadyExists);
@@ -327,7 +353,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         if CompletedTransactionSql::find(tx_id, &(*conn)).is_ok() {
             return Err(TransactionStorageError::TransactionAlreadyExists);
@@ -350,7 +379,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn broadcast_completed_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         match CompletedTransactionSql::find(tx_id, &(*conn)) {
             Ok(v) => {
@@ -375,7 +407,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn mine_completed_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         match CompletedTransactionSql::find(tx_id, &(*conn)) {
             Ok(v) => {
@@ -398,7 +433,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn cancel_completed_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
         match CompletedTransactionSql::find(tx_id, &(*conn)) {
             Ok(v) => {
                 v.cancel(&(*conn))?;
@@ -414,7 +452,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
     }
 
     fn cancel_pending_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
         match InboundTransactionSql::find(tx_id, &(*conn)) {
             Ok(v) => {
                 let _ = v.cancel(&(*conn))?;
@@ -434,6 +475,104 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
+    fn is_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+    ) -> Result<bool, TransactionStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        Ok(ProcessedMessageSql::find(sender.as_bytes(), message_hash, &(*conn))?.is_some())
+    }
+
+    fn mark_message_processed(
+        &self,
+        sender: &CommsPublicKey,
+        message_hash: &[u8],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        ProcessedMessageSql {
+            sender: sender.to_vec(),
+            message_hash: message_hash.to_vec(),
+            timestamp,
+        }
+        .commit(&(*conn))
+    }
+
+    fn prune_processed_messages(&self, older_than: NaiveDateTime) -> Result<usize, TransactionStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        ProcessedMessageSql::delete_older_than(older_than, &(*conn))
+    }
+
+    fn link_replacement_transaction(
+        &self,
+        tx_id: TxId,
+        replacement_tx_id: TxId,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        TransactionReplacementSql {
+            tx_id: tx_id as i64,
+            replacement_tx_id: replacement_tx_id as i64,
+        }
+        .commit(&(*conn))
+    }
+
+    fn get_replacement_transaction(&self, tx_id: TxId) -> Result<Option<TxId>, TransactionStorageError> {
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        Ok(TransactionReplacementSql::find(tx_id, &(*conn))?.map(|r| r.replacement_tx_id as TxId))
+    }
+
+    fn save_scheduled_payment(&self, payment: ScheduledPayment) -> Result<(), TransactionStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        ScheduledPaymentSql::from(payment).commit(&(*conn))
+    }
+
+    fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>, TransactionStorageError> {
+        let conn = self
+            .database_connection
+            .get_read_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        ScheduledPaymentSql::index(&(*conn))?
+            .into_iter()
+            .map(ScheduledPayment::try_from)
+            .collect()
+    }
+
+    fn remove_scheduled_payment(&self, id: u64) -> Result<Option<ScheduledPayment>, TransactionStorageError> {
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        match ScheduledPaymentSql::find(id, &(*conn))? {
+            Some(p) => {
+                p.delete(&(*conn))?;
+                Ok(Some(ScheduledPayment::try_from(p)?))
+            },
+            None => Ok(None),
+        }
+    }
+
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
         &self,
@@ -441,7 +580,10 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         timestamp: NaiveDateTime,
     ) -> Result<(), TransactionStorageError>
     {
-        let conn = acquire_lock!(self.database_connection);
+        let conn = self
+            .database_connection
+            .get_write_connection()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
 
         if let Ok(tx) = CompletedTransactionSql::find(tx_id, &(*conn)) {
             let _ = tx.update(
@@ -547,6 +689,7 @@ struct OutboundTransactionSql {
     sender_protocol: String,
     message: String,
     timestamp: NaiveDateTime,
+    fee_priority: Option<i32>,
 }
 
 impl OutboundTransactionSql {
@@ -597,6 +740,7 @@ impl TryFrom<OutboundTransaction> for OutboundTransactionSql {
             sender_protocol: serde_json::to_string(&i.sender_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            fee_priority: i.fee_priority.map(|p| p as i32),
         })
     }
 }
@@ -615,6 +759,7 @@ impl TryFrom<OutboundTransactionSql> for OutboundTransaction {
             status: TransactionStatus::Pending,
             message: i.message,
             timestamp: i.timestamp,
+            fee_priority: i.fee_priority.map(FeePriority::try_from).transpose()?,
         })
     }
 }
@@ -700,6 +845,7 @@ struct CompletedTransactionSql {
     status: i32,
     message: String,
     timestamp: NaiveDateTime,
+    fee_priority: Option<i32>,
 }
 
 impl CompletedTransactionSql {
@@ -788,6 +934,7 @@ impl TryFrom<CompletedTransaction> for CompletedTransactionSql {
             status: c.status as i32,
             message: c.message,
             timestamp: c.timestamp,
+            fee_priority: c.fee_priority.map(|p| p as i32),
         })
     }
 }
@@ -808,6 +955,7 @@ impl TryFrom<CompletedTransactionSql> for CompletedTransaction {
             status: TransactionStatus::try_from(c.status)?,
             message: c.message,
             timestamp: c.timestamp,
+            fee_priority: c.fee_priority.map(FeePriority::try_from).transpose()?,
         })
     }
 }
@@ -818,6 +966,148 @@ pub struct UpdateCompletedTransaction {
     timestamp: Option<NaiveDateTime>,
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "processed_messages"]
+struct ProcessedMessageSql {
+    sender: Vec<u8>,
+    message_hash: Vec<u8>,
+    timestamp: NaiveDateTime,
+}
+
+impl ProcessedMessageSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::replace_into(processed_messages::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(
+        sender: &[u8],
+        message_hash: &[u8],
+        conn: &SqliteConnection,
+    ) -> Result<Option<ProcessedMessageSql>, TransactionStorageError>
+    {
+        Ok(processed_messages::table
+            .filter(processed_messages::sender.eq(sender))
+            .filter(processed_messages::message_hash.eq(message_hash))
+            .first::<ProcessedMessageSql>(conn)
+            .optional()?)
+    }
+
+    pub fn delete_older_than(
+        older_than: NaiveDateTime,
+        conn: &SqliteConnection,
+    ) -> Result<usize, TransactionStorageError>
+    {
+        Ok(
+            diesel::delete(processed_messages::table.filter(processed_messages::timestamp.lt(older_than)))
+                .execute(conn)?,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "transaction_replacements"]
+struct TransactionReplacementSql {
+    tx_id: i64,
+    replacement_tx_id: i64,
+}
+
+impl TransactionReplacementSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::replace_into(transaction_replacements::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(
+        tx_id: TxId,
+        conn: &SqliteConnection,
+    ) -> Result<Option<TransactionReplacementSql>, TransactionStorageError>
+    {
+        Ok(transaction_replacements::table
+            .filter(transaction_replacements::tx_id.eq(tx_id as i64))
+            .first::<TransactionReplacementSql>(conn)
+            .optional()?)
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "scheduled_payments"]
+struct ScheduledPaymentSql {
+    id: i64,
+    destination_public_key: Vec<u8>,
+    amount: i64,
+    fee_priority: i32,
+    message: String,
+    interval_secs: i64,
+    next_payment_at: NaiveDateTime,
+    payments_made: i64,
+    last_tx_id: Option<i64>,
+}
+
+impl ScheduledPaymentSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::replace_into(scheduled_payments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<ScheduledPaymentSql>, TransactionStorageError> {
+        Ok(scheduled_payments::table.load::<ScheduledPaymentSql>(conn)?)
+    }
+
+    pub fn find(id: u64, conn: &SqliteConnection) -> Result<Option<ScheduledPaymentSql>, TransactionStorageError> {
+        Ok(scheduled_payments::table
+            .filter(scheduled_payments::id.eq(id as i64))
+            .first::<ScheduledPaymentSql>(conn)
+            .optional()?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::delete(scheduled_payments::table.filter(scheduled_payments::id.eq(self.id))).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl From<ScheduledPayment> for ScheduledPaymentSql {
+    fn from(p: ScheduledPayment) -> Self {
+        Self {
+            id: p.id as i64,
+            destination_public_key: p.destination_public_key.to_vec(),
+            amount: u64::from(p.amount) as i64,
+            fee_priority: p.fee_priority as i32,
+            message: p.message,
+            interval_secs: p.interval.as_secs() as i64,
+            next_payment_at: p.next_payment_at,
+            payments_made: p.payments_made as i64,
+            last_tx_id: p.last_tx_id.map(|id| id as i64),
+        }
+    }
+}
+
+impl TryFrom<ScheduledPaymentSql> for ScheduledPayment {
+    type Error = TransactionStorageError;
+
+    fn try_from(p: ScheduledPaymentSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: p.id as u64,
+            destination_public_key: PublicKey::from_vec(&p.destination_public_key)
+                .map_err(|_| TransactionStorageError::ConversionError)?,
+            amount: MicroTari::from(p.amount as u64),
+            fee_priority: FeePriority::try_from(p.fee_priority)?,
+            message: p.message,
+            interval: Duration::from_secs(p.interval_secs as u64),
+            next_payment_at: p.next_payment_at,
+            payments_made: p.payments_made as u64,
+            last_tx_id: p.last_tx_id.map(|id| id as TxId),
+        })
+    }
+}
+
 #[derive(AsChangeset)]
 #[table_name = "completed_transactions"]
 pub struct UpdateCompletedTransactionSql {
@@ -842,6 +1132,7 @@ mod test {
     use crate::transaction_service::storage::{
         database::{
             CompletedTransaction,
+            FeePriority,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
@@ -915,6 +1206,7 @@ mod test {
             status: TransactionStatus::Pending,
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: Some(FeePriority::Fast),
         };
 
         let outbound_tx2 = OutboundTransactionSql::try_from(OutboundTransaction {
@@ -927,6 +1219,7 @@ mod test {
 
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: None,
         })
         .unwrap();
 
@@ -1005,6 +1298,7 @@ mod test {
             status: TransactionStatus::Mined,
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: Some(FeePriority::Slow),
         };
         let completed_tx2 = CompletedTransaction {
             tx_id: 3,
@@ -1016,6 +1310,7 @@ mod test {
             status: TransactionStatus::Broadcast,
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: None,
         };
 
         CompletedTransactionSql::try_from(completed_tx1.clone())
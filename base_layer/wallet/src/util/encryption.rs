@@ -0,0 +1,131 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use blake2::{Blake2b, Digest};
+use chacha20poly1305::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    XChaCha20Poly1305,
+};
+use derive_error::Error;
+use rand::{rngs::OsRng, RngCore};
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 24;
+const SALT_SIZE: usize = 16;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EncryptionError {
+    /// A key could not be derived from the passphrase
+    #[error(msg_embedded, non_std, no_from)]
+    KeyDerivationError(String),
+    /// The value could not be encrypted
+    EncryptionFailed,
+    /// The value was not encrypted with this key or has been modified
+    DecryptionFailed,
+}
+
+/// A cipher for values stored in the wallet database, with a key derived from the user's passphrase. Values are
+/// encrypted with XChaCha20-Poly1305 under a nonce derived from the value itself, so the same value always encrypts to
+/// the same ciphertext and encrypted values can still be used to look up database rows.
+#[derive(Clone)]
+pub struct DatabaseCipher {
+    cipher: XChaCha20Poly1305,
+    nonce_key: Vec<u8>,
+}
+
+impl DatabaseCipher {
+    /// Derive the cipher from the passphrase with Argon2id. The salt must be stored with the encrypted data so that
+    /// the same cipher can be derived again.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, EncryptionError> {
+        let config = argon2::Config {
+            variant: argon2::Variant::Argon2id,
+            hash_length: (2 * KEY_SIZE) as u32,
+            ..Default::default()
+        };
+        let key = argon2::hash_raw(passphrase.as_bytes(), salt, &config)
+            .map_err(|e| EncryptionError::KeyDerivationError(e.to_string()))?;
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(GenericArray::clone_from_slice(&key[..KEY_SIZE])),
+            nonce_key: key[KEY_SIZE..].to_vec(),
+        })
+    }
+
+    /// Generate a random salt for `from_passphrase`
+    pub fn generate_salt() -> Vec<u8> {
+        let mut salt = vec![0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    /// Encrypt the value. The nonce is prepended to the ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let hash = Blake2b::new_keyed(&self.nonce_key, 64).chain(plaintext).result();
+        let nonce = GenericArray::from_slice(&hash[..NONCE_SIZE]);
+        let ciphertext = self
+            .cipher
+            .encrypt(nonce, plaintext)
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+        let mut result = nonce.to_vec();
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    /// Decrypt a value produced by `encrypt`
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if ciphertext.len() < NONCE_SIZE {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_SIZE);
+        self.cipher
+            .decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|_| EncryptionError::DecryptionFailed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::encryption::{DatabaseCipher, EncryptionError};
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let salt = DatabaseCipher::generate_salt();
+        let cipher = DatabaseCipher::from_passphrase("correct horse battery staple", &salt).unwrap();
+        let plaintext = b"spending key".to_vec();
+
+        let ciphertext = cipher.encrypt(&plaintext).unwrap();
+        assert_ne!(ciphertext, plaintext);
+        assert_eq!(cipher.encrypt(&plaintext).unwrap(), ciphertext);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), plaintext);
+
+        let cipher2 = DatabaseCipher::from_passphrase("correct horse battery staple", &salt).unwrap();
+        assert_eq!(cipher2.decrypt(&ciphertext).unwrap(), plaintext);
+
+        let wrong_passphrase = DatabaseCipher::from_passphrase("incorrect horse", &salt).unwrap();
+        assert_eq!(
+            wrong_passphrase.decrypt(&ciphertext),
+            Err(EncryptionError::DecryptionFailed)
+        );
+        let wrong_salt =
+            DatabaseCipher::from_passphrase("correct horse battery staple", &DatabaseCipher::generate_salt()).unwrap();
+        assert_eq!(wrong_salt.decrypt(&ciphertext), Err(EncryptionError::DecryptionFailed));
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Parsing of the faucet UTXO claims files that are published when a testnet is reset. A claims file lists the
+//! allocations made at genesis by commitment, so a wallet can only claim the entries it holds the claim key for.
+
+use crate::error::FaucetError;
+use serde::{Deserialize, Serialize};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, UnblindedOutput},
+    types::{Commitment, CryptoFactories, PrivateKey},
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, tari_utilities::hex::Hex};
+
+/// A single allocation in a faucet claims file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaucetUtxo {
+    pub value: MicroTari,
+    /// The hex encoded commitment of the output
+    pub commitment: String,
+    #[serde(default)]
+    pub features: OutputFeatures,
+}
+
+/// A published list of the faucet allocations made at the genesis of a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaucetClaims {
+    /// The name of the network the allocations were made on
+    pub network: String,
+    /// The hex encoded public key of the faucet, recorded as the source of the imported outputs
+    pub source_public_key: String,
+    pub utxos: Vec<FaucetUtxo>,
+}
+
+impl FaucetClaims {
+    /// Parse a claims file from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, FaucetError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, FaucetError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn source_public_key(&self) -> Result<CommsPublicKey, FaucetError> {
+        CommsPublicKey::from_hex(&self.source_public_key).map_err(|_| FaucetError::InvalidSourcePublicKey)
+    }
+
+    /// Returns the allocations that commit to one of the provided claim keys, as outputs carrying the features they
+    /// were allocated with. The claim key is the spending key of the output.
+    pub fn claimable_outputs(
+        &self,
+        claim_keys: &[PrivateKey],
+        factories: &CryptoFactories,
+    ) -> Result<Vec<UnblindedOutput>, FaucetError>
+    {
+        let mut outputs = Vec::new();
+        for utxo in self.utxos.iter() {
+            let commitment = Commitment::from_hex(&utxo.commitment)
+                .map_err(|_| FaucetError::InvalidCommitment(utxo.commitment.clone()))?;
+            let claim_key = claim_keys
+                .iter()
+                .find(|k| factories.commitment.commit_value(k, utxo.value.into()) == commitment);
+            if let Some(key) = claim_key {
+                outputs.push(UnblindedOutput::new(
+                    utxo.value,
+                    key.clone(),
+                    Some(utxo.features.clone()),
+                ));
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+impl FaucetUtxo {
+    /// Create the claims file entry for an allocation to the given claim key
+    pub fn new(
+        value: MicroTari,
+        claim_key: &PrivateKey,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+    ) -> Self
+    {
+        Self {
+            value,
+            commitment: factories.commitment.commit_value(claim_key, value.into()).to_hex(),
+            features,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::FaucetError,
+        util::faucet::{FaucetClaims, FaucetUtxo},
+    };
+    use rand::rngs::OsRng;
+    use tari_core::transactions::{
+        tari_amount::MicroTari,
+        transaction::OutputFeatures,
+        types::{CryptoFactories, PrivateKey, PublicKey},
+    };
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
+        tari_utilities::hex::Hex,
+    };
+
+    #[test]
+    fn claimable_outputs() {
+        let factories = CryptoFactories::default();
+        let ours = PrivateKey::random(&mut OsRng);
+        let theirs = PrivateKey::random(&mut OsRng);
+        let faucet_key = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        let claims = FaucetClaims {
+            network: "rincewind".to_string(),
+            source_public_key: faucet_key.to_hex(),
+            utxos: vec![
+                FaucetUtxo::new(MicroTari::from(1000), &theirs, OutputFeatures::default(), &factories),
+                FaucetUtxo::new(
+                    MicroTari::from(2000),
+                    &ours,
+                    OutputFeatures::with_maturity(5),
+                    &factories,
+                ),
+            ],
+        };
+
+        let parsed = FaucetClaims::from_json(&claims.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, claims);
+        assert_eq!(parsed.source_public_key().unwrap(), faucet_key);
+
+        let outputs = parsed.claimable_outputs(&[ours.clone()], &factories).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].value, MicroTari::from(2000));
+        assert_eq!(outputs[0].spending_key, ours);
+        assert_eq!(outputs[0].features, OutputFeatures::with_maturity(5));
+        assert!(parsed
+            .claimable_outputs(&[PrivateKey::random(&mut OsRng)], &factories)
+            .unwrap()
+            .is_empty());
+
+        let mut invalid = claims;
+        invalid.utxos[0].commitment = "not hex".to_string();
+        match invalid.claimable_outputs(&[ours], &factories) {
+            Err(FaucetError::InvalidCommitment(_)) => {},
+            _ => panic!("Expected an invalid commitment error"),
+        }
+        assert!(FaucetClaims::from_json("{}").is_err());
+    }
+}
@@ -20,9 +20,68 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::time::Duration;
+use std::{
+    cmp::{max, min},
+    time::{Duration, Instant, SystemTime},
+};
 use tokio::time::delay_for;
 
+// How often a waiting deadline measures the elapsed time, and so how late it can notice time that passed while the
+// device was suspended
+const DEADLINE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A deadline a period after it was created. The elapsed time is measured with the monotonic clock, so that changing
+/// the wall clock doesn't move the deadline. On mobile platforms the monotonic clock stops while the device is
+/// suspended, so the deadline also catches up with the time that passed on the wall clock while the app was in the
+/// background. A deadline that passed in the background is reached as soon as the app resumes.
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    period: Duration,
+    elapsed: Duration,
+    last_instant: Instant,
+    last_wall_clock: SystemTime,
+}
+
+impl Deadline {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            elapsed: Duration::from_secs(0),
+            last_instant: Instant::now(),
+            last_wall_clock: SystemTime::now(),
+        }
+    }
+
+    /// Measure the time elapsed since the last measurement and return the time left until the deadline
+    pub fn remaining(&mut self) -> Duration {
+        let now = Instant::now();
+        let wall_clock = SystemTime::now();
+        let monotonic_elapsed = now.duration_since(self.last_instant);
+        // The wall clock moving further than the monotonic clock means the device was suspended. A wall clock that
+        // moved backwards is ignored.
+        let wall_clock_elapsed = wall_clock
+            .duration_since(self.last_wall_clock)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        self.elapsed += max(monotonic_elapsed, wall_clock_elapsed);
+        self.last_instant = now;
+        self.last_wall_clock = wall_clock;
+        self.period
+            .checked_sub(self.elapsed)
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// The future that resolves when the deadline is reached
+    pub async fn wait(mut self) {
+        loop {
+            let remaining = self.remaining();
+            if remaining == Duration::from_secs(0) {
+                return;
+            }
+            delay_for(min(remaining, DEADLINE_CHECK_INTERVAL)).await;
+        }
+    }
+}
+
 /// The structure can be initialized with some state and an internal Delay future. The struct will resolve to the
 /// internal state when the delay elapses.
 /// This allows for one to create unique delays that can be await'ed upon in a collection like FuturesUnordered
@@ -36,16 +95,17 @@ impl<T> StateDelay<T> {
         Self { state, period }
     }
 
-    /// The future that will delay for the specified time and then return the internal state
+    /// The future that will delay for the specified time and then return the internal state. The delay is a
+    /// `Deadline`, so it also counts the time the device was suspended.
     pub async fn delay(self) -> T {
-        delay_for(self.period).await;
+        Deadline::new(self.period).wait().await;
         self.state
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::futures::StateDelay;
+    use crate::util::futures::{Deadline, StateDelay};
     use chrono::{Duration as ChronoDuration, Utc};
     use std::time::Duration;
     use tokio::runtime::Runtime;
@@ -71,4 +131,23 @@ mod test {
         assert!(tock.signed_duration_since(tick) > ChronoDuration::seconds(0i64));
         assert_eq!(result, state);
     }
+
+    #[test]
+    fn test_deadline_catches_up_with_suspended_time() {
+        let mut deadline = Deadline::new(Duration::from_secs(60));
+        assert!(deadline.remaining() <= Duration::from_secs(60));
+        // Simulate the wall clock moving on while the monotonic clock was stopped
+        deadline.last_wall_clock -= Duration::from_secs(45);
+        let remaining = deadline.remaining();
+        assert!(remaining <= Duration::from_secs(15));
+        assert!(remaining > Duration::from_secs(10));
+        // A wall clock that moves backwards doesn't move the deadline
+        deadline.last_wall_clock += Duration::from_secs(3600);
+        assert!(deadline.remaining() > Duration::from_secs(10));
+
+        deadline.last_wall_clock -= Duration::from_secs(30);
+        assert_eq!(deadline.remaining(), Duration::from_secs(0));
+        let mut runtime = Runtime::new().unwrap();
+        runtime.block_on(deadline.wait());
+    }
 }
@@ -21,5 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 pub mod emoji;
+pub mod encryption;
+pub mod faucet;
 pub mod futures;
 pub mod luhn;
+pub mod runtime;
+pub mod test_vectors;
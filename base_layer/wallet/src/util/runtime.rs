@@ -0,0 +1,85 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use std::{cmp::max, io};
+use tokio::runtime::{Builder, Runtime};
+
+const LOG_TARGET: &str = "wallet::util::runtime";
+
+/// The thread configuration of the runtime the wallet and its comms stack are run on. The core threads drive the
+/// service and comms event loops, while the blocking threads are used for the database calls and the heavy
+/// cryptography (range proof generation and verification) that the wallet services move onto `spawn_blocking`.
+#[derive(Clone, Debug)]
+pub struct RuntimeConfig {
+    pub core_threads: usize,
+    pub blocking_threads: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            core_threads: 4,
+            blocking_threads: 4,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    pub fn new(core_threads: usize, blocking_threads: usize) -> Self {
+        Self {
+            core_threads,
+            blocking_threads,
+        }
+    }
+
+    /// Build a threaded runtime using this configuration. At least one core and one blocking thread are always
+    /// configured.
+    pub fn build_runtime(&self) -> Result<Runtime, io::Error> {
+        let core_threads = max(self.core_threads, 1);
+        let blocking_threads = max(self.blocking_threads, 1);
+        debug!(
+            target: LOG_TARGET,
+            "Configuring the wallet to run on {} core threads and {} blocking worker threads.",
+            core_threads,
+            blocking_threads
+        );
+        Builder::new()
+            .threaded_scheduler()
+            .enable_all()
+            .core_threads(core_threads)
+            .max_threads(core_threads + blocking_threads)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::runtime::RuntimeConfig;
+
+    #[test]
+    fn test_build_runtime() {
+        let mut runtime = RuntimeConfig::new(0, 0).build_runtime().unwrap();
+        let result = runtime.block_on(async { tokio::task::spawn_blocking(|| 1 + 1).await.unwrap() });
+        assert_eq!(result, 2);
+    }
+}
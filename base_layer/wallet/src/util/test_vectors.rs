@@ -0,0 +1,349 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Deterministic test vectors for the single round transaction protocol. Every key and nonce used by the sender and
+//! the receiver is derived from a fixed seed, so the protocol messages, the kernel and the output commitments of a
+//! vector can be reproduced exactly by any implementation. Range proofs are constructed with fresh randomness, so they
+//! are recorded in the vectors but are only checked for validity, never compared byte for byte.
+//!
+//! A key is derived as `Blake256("<seed>:<label>:<index>")`, reduced modulo the group order, where the labels are
+//! `sender_input`, `sender_change`, `sender_offset`, `sender_nonce`, `receiver_spend` and `receiver_nonce`.
+
+use crate::{error::TestVectorError, types::HashDigest};
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{KernelFeatures, OutputFeatures, Transaction, TransactionInput, UnblindedOutput},
+    transaction_protocol::{
+        recipient::RecipientSignedMessage,
+        sender::{SingleRoundSenderData, TransactionSenderMessage},
+    },
+    types::{CryptoFactories, PrivateKey},
+    ReceiverTransactionProtocol,
+    SenderTransactionProtocol,
+};
+use tari_crypto::{common::Blake256, tari_utilities::ByteArray};
+
+/// The fixed parameters a test vector is generated from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVectorParams {
+    pub seed: String,
+    /// The values of the outputs the sender spends, one input is created for each
+    pub input_values: Vec<MicroTari>,
+    /// The amount sent to the receiver
+    pub amount: MicroTari,
+    pub fee_per_gram: MicroTari,
+    pub lock_height: u64,
+    pub message: String,
+}
+
+/// The secrets used by the sender, derived from the seed of the test vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SenderTestKeys {
+    pub input_keys: Vec<PrivateKey>,
+    pub change_key: PrivateKey,
+    pub offset: PrivateKey,
+    pub nonce: PrivateKey,
+}
+
+/// The secrets used by the receiver, derived from the seed of the test vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceiverTestKeys {
+    pub spend_key: PrivateKey,
+    pub nonce: PrivateKey,
+}
+
+/// A transcript of a single round transaction between a sender and a receiver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionTestVector {
+    pub params: TestVectorParams,
+    pub sender_keys: SenderTestKeys,
+    pub receiver_keys: ReceiverTestKeys,
+    pub inputs: Vec<TransactionInput>,
+    /// The message the sender sends to the receiver
+    pub sender_message: SingleRoundSenderData,
+    /// The reply of the receiver, including its output and partial signature
+    pub receiver_reply: RecipientSignedMessage,
+    /// The transaction finalized by the sender
+    pub transaction: Transaction,
+}
+
+/// Derive the secret key with the given label and index from the seed of a test vector
+pub fn derive_test_key(seed: &str, label: &str, index: usize) -> PrivateKey {
+    let digest = Blake256::digest(format!("{}:{}:{}", seed, label, index).as_bytes());
+    // A 32 byte digest is always reduced to a valid scalar
+    PrivateKey::from_bytes(digest.as_slice()).expect("A Blake256 digest is a valid secret key")
+}
+
+impl TestVectorParams {
+    pub fn sender_keys(&self) -> SenderTestKeys {
+        SenderTestKeys {
+            input_keys: (0..self.input_values.len())
+                .map(|i| derive_test_key(&self.seed, "sender_input", i))
+                .collect(),
+            change_key: derive_test_key(&self.seed, "sender_change", 0),
+            offset: derive_test_key(&self.seed, "sender_offset", 0),
+            nonce: derive_test_key(&self.seed, "sender_nonce", 0),
+        }
+    }
+
+    pub fn receiver_keys(&self) -> ReceiverTestKeys {
+        ReceiverTestKeys {
+            spend_key: derive_test_key(&self.seed, "receiver_spend", 0),
+            nonce: derive_test_key(&self.seed, "receiver_nonce", 0),
+        }
+    }
+}
+
+/// The parameters of the published set of test vectors
+pub fn default_test_vector_params() -> Vec<TestVectorParams> {
+    vec![
+        TestVectorParams {
+            seed: "tari test vector 0".to_string(),
+            input_values: vec![MicroTari::from(100_000)],
+            amount: MicroTari::from(25_000),
+            fee_per_gram: MicroTari::from(25),
+            lock_height: 0,
+            message: "".to_string(),
+        },
+        TestVectorParams {
+            seed: "tari test vector 1".to_string(),
+            input_values: vec![MicroTari::from(40_000), MicroTari::from(60_000), MicroTari::from(5_000)],
+            amount: MicroTari::from(90_000),
+            fee_per_gram: MicroTari::from(20),
+            lock_height: 1500,
+            message: "Payment for invoice 42".to_string(),
+        },
+    ]
+}
+
+/// Run the single round transaction protocol between a sender and a receiver using the keys derived from the
+/// parameters
+pub fn generate_test_vector(
+    params: &TestVectorParams,
+    factories: &CryptoFactories,
+) -> Result<TransactionTestVector, TestVectorError>
+{
+    let sender_keys = params.sender_keys();
+    let receiver_keys = params.receiver_keys();
+
+    let mut builder = SenderTransactionProtocol::builder(1);
+    builder
+        .with_lock_height(params.lock_height)
+        .with_fee_per_gram(params.fee_per_gram)
+        .with_offset(sender_keys.offset.clone())
+        .with_private_nonce(sender_keys.nonce.clone())
+        .with_change_secret(sender_keys.change_key.clone())
+        .with_amount(0, params.amount)
+        .with_message(params.message.clone());
+    let mut inputs = Vec::with_capacity(params.input_values.len());
+    for (value, key) in params.input_values.iter().zip(sender_keys.input_keys.iter()) {
+        let unblinded = UnblindedOutput::new(*value, key.clone(), None);
+        let input = unblinded.as_transaction_input(&factories.commitment, OutputFeatures::default());
+        builder.with_input(input.clone(), unblinded);
+        inputs.push(input);
+    }
+    let mut sender = builder
+        .build::<HashDigest>(factories)
+        .map_err(|e| TestVectorError::InvalidParameters(e.message))?;
+    let sender_message = sender.build_single_round_message()?;
+
+    let receiver = ReceiverTransactionProtocol::new(
+        TransactionSenderMessage::Single(Box::new(sender_message.clone())),
+        receiver_keys.nonce.clone(),
+        receiver_keys.spend_key.clone(),
+        OutputFeatures::default(),
+        factories,
+    );
+    let receiver_reply = receiver.get_signed_data()?.clone();
+
+    sender.add_single_recipient_info(receiver_reply.clone(), &factories.range_proof)?;
+    if !sender.finalize(KernelFeatures::empty(), factories)? {
+        return Err(TestVectorError::InvalidParameters(format!(
+            "The transaction could not be finalized: {:?}",
+            sender.failure_reason()
+        )));
+    }
+    let transaction = sender.take_transaction()?;
+
+    Ok(TransactionTestVector {
+        params: params.clone(),
+        sender_keys,
+        receiver_keys,
+        inputs,
+        sender_message,
+        receiver_reply,
+        transaction,
+    })
+}
+
+impl TransactionTestVector {
+    pub fn from_json(json: &str) -> Result<Self, TestVectorError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, TestVectorError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Check the test vector against this implementation. The keys, protocol messages, signatures, kernel and
+    /// commitments must match the values reproduced from the parameters exactly, and the range proofs and the
+    /// transaction must be valid.
+    pub fn verify(&self, factories: &CryptoFactories) -> Result<(), TestVectorError> {
+        let expected = generate_test_vector(&self.params, factories)?;
+        check_field("sender keys", &self.sender_keys, &expected.sender_keys)?;
+        check_field("receiver keys", &self.receiver_keys, &expected.receiver_keys)?;
+        check_field("inputs", &self.inputs, &expected.inputs)?;
+        check_field("sender message", &self.sender_message, &expected.sender_message)?;
+
+        let reply = &self.receiver_reply;
+        let expected_reply = &expected.receiver_reply;
+        check_field("receiver reply tx id", &reply.tx_id, &expected_reply.tx_id)?;
+        check_field(
+            "receiver public spend key",
+            &reply.public_spend_key,
+            &expected_reply.public_spend_key,
+        )?;
+        check_field(
+            "receiver partial signature",
+            &reply.partial_signature,
+            &expected_reply.partial_signature,
+        )?;
+        check_field(
+            "receiver output commitment",
+            &reply.output.commitment,
+            &expected_reply.output.commitment,
+        )?;
+        check_field(
+            "receiver output features",
+            &reply.output.features,
+            &expected_reply.output.features,
+        )?;
+
+        let tx = &self.transaction;
+        check_field("transaction offset", &tx.offset, &expected.transaction.offset)?;
+        check_field(
+            "transaction inputs",
+            tx.body.inputs(),
+            expected.transaction.body.inputs(),
+        )?;
+        check_field(
+            "transaction kernels",
+            tx.body.kernels(),
+            expected.transaction.body.kernels(),
+        )?;
+        let commitments = |tx: &Transaction| {
+            let mut commitments = tx
+                .body
+                .outputs()
+                .iter()
+                .map(|o| o.commitment.clone())
+                .collect::<Vec<_>>();
+            commitments.sort();
+            commitments
+        };
+        check_field(
+            "transaction output commitments",
+            &commitments(tx),
+            &commitments(&expected.transaction),
+        )?;
+        if !tx.body.outputs().contains(&reply.output) {
+            return Err(TestVectorError::Mismatch(
+                "The transaction does not contain the output of the receiver".to_string(),
+            ));
+        }
+        tx.validate_internal_consistency(factories, None)
+            .map_err(|e| TestVectorError::InvalidTransaction(format!("{:?}", e)))
+    }
+}
+
+fn check_field<T: PartialEq + std::fmt::Debug + ?Sized>(
+    name: &str,
+    found: &T,
+    expected: &T,
+) -> Result<(), TestVectorError>
+{
+    if found != expected {
+        return Err(TestVectorError::Mismatch(format!(
+            "{}: expected {:?}, found {:?}",
+            name, expected, found
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::TestVectorError,
+        util::test_vectors::{default_test_vector_params, generate_test_vector, TransactionTestVector},
+    };
+    use tari_core::transactions::{tari_amount::MicroTari, types::CryptoFactories};
+
+    #[test]
+    fn test_vectors_are_reproducible() {
+        let factories = CryptoFactories::default();
+        for params in default_test_vector_params() {
+            let vector = generate_test_vector(&params, &factories).unwrap();
+            let json = vector.to_json().unwrap();
+            let parsed = TransactionTestVector::from_json(&json).unwrap();
+            assert_eq!(parsed, vector);
+            parsed.verify(&factories).unwrap();
+
+            let again = generate_test_vector(&params, &factories).unwrap();
+            assert_eq!(again.sender_keys, vector.sender_keys);
+            assert_eq!(again.sender_message, vector.sender_message);
+            assert_eq!(
+                again.receiver_reply.partial_signature,
+                vector.receiver_reply.partial_signature
+            );
+            assert_eq!(again.transaction.body.kernels(), vector.transaction.body.kernels());
+            assert_eq!(again.transaction.offset, vector.transaction.offset);
+        }
+    }
+
+    #[test]
+    fn tampered_test_vectors_fail_verification() {
+        let factories = CryptoFactories::default();
+        let params = default_test_vector_params().remove(0);
+        let vector = generate_test_vector(&params, &factories).unwrap();
+
+        let mut tampered = vector.clone();
+        tampered.sender_message.amount = MicroTari::from(25_001);
+        match tampered.verify(&factories) {
+            Err(TestVectorError::Mismatch(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let mut tampered = vector.clone();
+        tampered.receiver_reply.partial_signature = vector.transaction.body.kernels()[0].excess_sig.clone();
+        match tampered.verify(&factories) {
+            Err(TestVectorError::Mismatch(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let mut tampered = vector;
+        tampered.params.amount = MicroTari::from(1_000_000);
+        assert!(tampered.verify(&factories).is_err());
+    }
+}
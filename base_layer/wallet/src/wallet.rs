@@ -21,26 +21,41 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    auto_coin_split::{AutoCoinSplitPolicy, AutoCoinSplitter},
+    backup::{BackupBundle, BackupSettings},
+    clone_detection::{CloneDetectionReason, CloneDetector},
     contacts_service::{handle::ContactsServiceHandle, storage::database::ContactsBackend, ContactsServiceInitializer},
     error::WalletError,
     output_manager_service::{
         config::OutputManagerServiceConfig,
-        handle::OutputManagerHandle,
+        handle::{OutputManagerEvent, OutputManagerHandle},
         storage::database::OutputManagerBackend,
         OutputManagerServiceInitializer,
         TxId,
     },
-    storage::database::{WalletBackend, WalletDatabase},
+    storage::database::{
+        EventJournalEntry,
+        WalletAuditEvent,
+        WalletBackend,
+        WalletDatabase,
+        WalletEvent,
+        WalletSetting,
+    },
+    transaction_lifecycle::{coordinator::TransactionLifecycleCoordinator, lifecycle::TransactionLifecycle},
     transaction_service::{
         config::TransactionServiceConfig,
-        handle::TransactionServiceHandle,
-        storage::database::TransactionBackend,
+        error::TransactionServiceError,
+        handle::{FeeEstimator, TransactionEventReceiver, TransactionServiceHandle},
+        storage::database::{FeePriority, TransactionBackend},
         TransactionServiceInitializer,
     },
+    util::faucet::FaucetClaims,
 };
 use blake2::Digest;
+use futures::{stream::Fuse, StreamExt};
 use log::*;
 use std::{marker::PhantomData, sync::Arc, time::Duration};
+use tari_broadcast_channel::Subscriber;
 use tari_comms::{
     multiaddr::Multiaddr,
     peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
@@ -57,21 +72,30 @@ use tari_crypto::{
     common::Blake256,
     ristretto::{RistrettoPublicKey, RistrettoSchnorr, RistrettoSecretKey},
     signatures::{SchnorrSignature, SchnorrSignatureError},
-    tari_utilities::hex::Hex,
+    tari_utilities::hex::{to_hex, Hex},
 };
+use tari_key_manager::mnemonic::MnemonicLanguage;
 use tari_p2p::{
     comms_connector::pubsub_connector,
     initialization::{initialize_comms, CommsConfig},
     services::{
         comms_outbound::CommsOutboundServiceInitializer,
-        liveness::{LivenessConfig, LivenessHandle, LivenessInitializer},
+        liveness::{LivenessConfig, LivenessEvent, LivenessHandle, LivenessInitializer},
     },
 };
-use tari_service_framework::StackBuilder;
-use tokio::runtime::Runtime;
+use tari_service_framework::{
+    watchdog::{Watchdog, WatchdogInitializer, DEFAULT_CHECK_INTERVAL},
+    StackBuilder,
+};
+use tokio::{runtime::Runtime, sync::broadcast};
 
 const LOG_TARGET: &str = "wallet";
 
+/// The number of journaled events that are buffered for each event journal subscriber
+const EVENT_JOURNAL_CHANNEL_SIZE: usize = 100;
+
+pub type EventJournalReceiver = broadcast::Receiver<Arc<EventJournalEntry>>;
+
 #[derive(Clone)]
 pub struct WalletConfig {
     pub comms_config: CommsConfig,
@@ -95,7 +119,9 @@ where
     pub output_manager_service: OutputManagerHandle,
     pub transaction_service: TransactionServiceHandle,
     pub contacts_service: ContactsServiceHandle,
+    pub watchdog: Watchdog,
     pub db: WalletDatabase<T>,
+    event_journal_publisher: broadcast::Sender<Arc<EventJournalEntry>>,
     pub runtime: Runtime,
     pub factories: CryptoFactories,
     #[cfg(feature = "test_harness")]
@@ -136,7 +162,11 @@ where
 
         let (comms, dht) = runtime.block_on(initialize_comms(config.comms_config.clone(), publisher))?;
 
+        let watchdog = Watchdog::default();
+        runtime.spawn(watchdog.clone().run(DEFAULT_CHECK_INTERVAL, comms.shutdown_signal()));
+
         let fut = StackBuilder::new(runtime.handle().clone(), comms.shutdown_signal())
+            .add_initializer(WatchdogInitializer::new(watchdog.clone()))
             .add_initializer(CommsOutboundServiceInitializer::new(dht.outbound_requester()))
             .add_initializer(LivenessInitializer::new(
                 LivenessConfig {
@@ -172,16 +202,63 @@ where
         let mut transaction_service_handle = handles
             .get_handle::<TransactionServiceHandle>()
             .expect("Could not get Transaction Service Handle");
-        let liveness_handle = handles
+        let mut liveness_handle = handles
             .get_handle::<LivenessHandle>()
             .expect("Could not get Liveness Service Handle");
         let contacts_handle = handles
             .get_handle::<ContactsServiceHandle>()
             .expect("Could not get Contacts Service Handle");
 
+        let (event_journal_publisher, _) = broadcast::channel(EVENT_JOURNAL_CHANNEL_SIZE);
+        runtime.spawn(journal_wallet_events(
+            db.clone(),
+            transaction_service_handle.get_event_stream_fused(),
+            output_manager_handle.get_event_stream_fused(),
+            event_journal_publisher.clone(),
+        ));
+        let lifecycle_coordinator = TransactionLifecycleCoordinator::new(
+            db.clone(),
+            transaction_service_handle.clone(),
+            output_manager_handle.clone(),
+        );
+        runtime.spawn(lifecycle_coordinator.run(transaction_service_handle.get_event_stream_fused()));
+        runtime.spawn(
+            CloneDetector::new(
+                db.clone(),
+                transaction_service_handle.clone(),
+                event_journal_publisher.clone(),
+            )
+            .run(output_manager_handle.get_event_stream_fused(), comms.shutdown_signal()),
+        );
+        // A detected clone that was not acknowledged before the wallet was last shut down still locks sending
+        if let Some(reason) = runtime.block_on(db.get_clone_detection())? {
+            warn!(
+                target: LOG_TARGET,
+                "Sending is locked until the wallet clone detection is acknowledged: {}", reason
+            );
+            runtime.block_on(transaction_service_handle.set_sending_locked(true))?;
+        }
+        runtime.spawn(
+            AutoCoinSplitter::new(
+                db.clone(),
+                output_manager_handle.clone(),
+                transaction_service_handle.clone(),
+            )
+            .run(
+                output_manager_handle.get_event_stream_fused(),
+                transaction_service_handle.get_event_stream_fused(),
+                comms.shutdown_signal(),
+            ),
+        );
+        runtime.spawn(report_peer_latencies(
+            liveness_handle.get_event_stream_fused(),
+            output_manager_handle.clone(),
+        ));
+
         for p in base_node_peers {
             runtime.block_on(transaction_service_handle.set_base_node_public_key(p.public_key.clone()))?;
             runtime.block_on(output_manager_handle.set_base_node_public_key(p.public_key.clone()))?;
+            runtime.block_on(liveness_handle.add_node_id(p.node_id.clone()))?;
         }
 
         let store_and_forward_requester = dht.store_and_forward_requester();
@@ -194,7 +271,9 @@ where
             output_manager_service: output_manager_handle,
             transaction_service: transaction_service_handle,
             contacts_service: contacts_handle,
+            watchdog,
             db,
+            event_journal_publisher,
             runtime,
             factories,
             #[cfg(feature = "test_harness")]
@@ -215,6 +294,10 @@ where
     /// state
     pub fn set_base_node_peer(&mut self, public_key: CommsPublicKey, net_address: String) -> Result<(), WalletError> {
         let address = net_address.parse::<Multiaddr>()?;
+        let audit_event = WalletAuditEvent::BaseNodeChanged {
+            public_key: public_key.to_hex(),
+            net_address,
+        };
         let peer = Peer::new(
             public_key.clone(),
             NodeId::from_key(&public_key).unwrap(),
@@ -237,12 +320,227 @@ where
             self.transaction_service
                 .set_base_node_public_key(peer.public_key.clone()),
         )?;
+        self.runtime.block_on(
+            self.output_manager_service
+                .set_base_node_public_key(peer.public_key.clone()),
+        )?;
+        // Ping the base node so that its latency is measured for the adaptive base node query timeout
+        self.runtime
+            .block_on(self.liveness_service.add_node_id(peer.node_id.clone()))?;
+        self.runtime.block_on(self.db.set_selected_base_node(peer.public_key))?;
+
+        self.runtime.block_on(self.db.append_audit_event(audit_event))?;
+
+        Ok(())
+    }
+
+    /// Send a transaction to the provided destination. The send is recorded as approved in the wallet audit log
+    /// before the transaction is created and as initiated once it has been sent.
+    pub fn send_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, WalletError>
+    {
+        self.record_send_approved(&dest_pubkey, amount)?;
+        let tx_id = self.runtime.block_on(self.transaction_service.send_transaction(
+            dest_pubkey.clone(),
+            amount,
+            fee_per_gram,
+            message,
+        ))?;
+        self.record_send_initiated(tx_id, &dest_pubkey, amount);
+
+        Ok(tx_id)
+    }
+
+    /// Send a transaction with the fee per gram resolved from a fee preset. The send is recorded in the wallet audit
+    /// log in the same way as by `send_transaction`.
+    pub fn send_transaction_with_priority(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_priority: FeePriority,
+        message: String,
+    ) -> Result<TxId, WalletError>
+    {
+        self.record_send_approved(&dest_pubkey, amount)?;
+        let tx_id = self
+            .runtime
+            .block_on(self.transaction_service.send_transaction_with_priority(
+                dest_pubkey.clone(),
+                amount,
+                fee_priority,
+                message,
+            ))?;
+        self.record_send_initiated(tx_id, &dest_pubkey, amount);
+
+        Ok(tx_id)
+    }
+
+    fn record_send_approved(&mut self, dest_pubkey: &CommsPublicKey, amount: MicroTari) -> Result<(), WalletError> {
+        self.runtime
+            .block_on(self.db.append_audit_event(WalletAuditEvent::SendApproved {
+                destination_public_key: dest_pubkey.to_hex(),
+                amount,
+            }))?;
+        Ok(())
+    }
+
+    fn record_send_initiated(&mut self, tx_id: TxId, dest_pubkey: &CommsPublicKey, amount: MicroTari) {
+        // The transaction has already been sent at this point, so failing to record it must not be reported to the
+        // caller as a failed send
+        if let Err(e) = self
+            .runtime
+            .block_on(self.db.append_audit_event(WalletAuditEvent::SendInitiated {
+                tx_id,
+                destination_public_key: dest_pubkey.to_hex(),
+                amount,
+            }))
+        {
+            error!(
+                target: LOG_TARGET,
+                "Could not record sent transaction {} in the audit log: {:?}", tx_id, e
+            );
+        }
+    }
+
+    /// Return the seed words of the wallet in the given language, or in the configured mnemonic language if no
+    /// language is given. The export is recorded in the wallet audit log before the words are returned.
+    pub fn get_seed_words(&mut self, language: Option<MnemonicLanguage>) -> Result<Vec<String>, WalletError> {
+        let seed_words = self
+            .runtime
+            .block_on(self.output_manager_service.get_seed_words(language))?;
+        self.runtime
+            .block_on(self.db.append_audit_event(WalletAuditEvent::SeedExported))?;
+        Ok(seed_words)
+    }
+
+    /// Encrypt the spending keys and key manager state of the wallet with a key derived from the passphrase, or unlock
+    /// them if they are already encrypted, and record it in the wallet audit log
+    pub fn apply_encryption(&mut self, passphrase: String) -> Result<(), WalletError> {
+        self.runtime
+            .block_on(self.output_manager_service.apply_encryption(passphrase))?;
+        self.runtime.block_on(
+            self.db
+                .append_audit_event(WalletAuditEvent::EncryptionToggled { enabled: true }),
+        )?;
+        Ok(())
+    }
+
+    /// Decrypt the spending keys and key manager state of the wallet and record it in the wallet audit log. The wallet
+    /// must have been unlocked with `apply_encryption` first.
+    pub fn remove_encryption(&mut self) -> Result<(), WalletError> {
+        self.runtime.block_on(self.output_manager_service.remove_encryption())?;
+        self.runtime.block_on(
+            self.db
+                .append_audit_event(WalletAuditEvent::EncryptionToggled { enabled: false }),
+        )?;
+        Ok(())
+    }
+
+    /// Set the fee estimator used to resolve fee presets when sending, or clear it to use the configured presets
+    pub fn set_fee_estimator(&mut self, estimator: Option<FeeEstimator>) -> Result<(), WalletError> {
         self.runtime
-            .block_on(self.output_manager_service.set_base_node_public_key(peer.public_key))?;
+            .block_on(self.transaction_service.set_fee_estimator(estimator))?;
+        Ok(())
+    }
 
+    /// Record a security-relevant action in the append-only wallet audit log
+    pub fn record_audit_event(&mut self, event: WalletAuditEvent) -> Result<(), WalletError> {
+        self.runtime.block_on(self.db.append_audit_event(event))?;
         Ok(())
     }
 
+    /// Export the wallet audit log as a JSON array of timestamped events
+    pub fn export_audit_log(&mut self) -> Result<String, WalletError> {
+        Ok(self.runtime.block_on(self.db.export_audit_log())?)
+    }
+
+    /// Replay the journaled wallet events from `from_sequence` on and subscribe to the events journaled after this
+    /// call. The subscription is opened before the journal is read, so events journaled in between are received
+    /// both ways; subscribers should skip entries with a sequence number at or below the last replayed one.
+    pub fn subscribe_to_event_journal(
+        &mut self,
+        from_sequence: u64,
+    ) -> Result<(Vec<EventJournalEntry>, EventJournalReceiver), WalletError>
+    {
+        let receiver = self.event_journal_publisher.subscribe();
+        let replayed = self.runtime.block_on(self.db.get_event_journal(from_sequence))?;
+        Ok((replayed, receiver))
+    }
+
+    /// Export the journaled wallet events from `from_sequence` on as a JSON array
+    pub fn export_event_journal(&mut self, from_sequence: u64) -> Result<String, WalletError> {
+        Ok(self.runtime.block_on(self.db.export_event_journal(from_sequence))?)
+    }
+
+    /// Create a backup bundle with the node identity, the wallet seed and the wallet settings, encrypted with a key
+    /// derived from the passphrase. The bundle can be restored on another device with `restore_backup_bundle`.
+    pub fn create_backup_bundle(&mut self, passphrase: &str) -> Result<Vec<u8>, WalletError> {
+        let key_manager_state = self
+            .runtime
+            .block_on(self.output_manager_service.get_key_manager_state())?;
+        let settings = self.runtime.block_on(BackupSettings::from_db(&self.db))?;
+        let bundle = BackupBundle::new((*self.comms.node_identity()).clone(), &key_manager_state, settings);
+        let encrypted = bundle.encrypt(passphrase)?;
+
+        self.runtime
+            .block_on(self.db.append_audit_event(WalletAuditEvent::BackupBundleCreated))?;
+        self.runtime.block_on(self.db.set_last_backup_time(bundle.created_at))?;
+        Ok(encrypted)
+    }
+
+    /// Get the reason the wallet was detected as cloned, if the detection has not been acknowledged yet. Sending is
+    /// locked while there is an unacknowledged detection.
+    pub fn get_clone_detection(&mut self) -> Result<Option<CloneDetectionReason>, WalletError> {
+        Ok(self.runtime.block_on(self.db.get_clone_detection())?)
+    }
+
+    /// Acknowledge a wallet clone detection and unlock sending. The operator should make sure that only one copy of
+    /// the wallet remains in use before doing so.
+    pub fn acknowledge_clone_detection(&mut self) -> Result<(), WalletError> {
+        self.runtime
+            .block_on(self.db.clear_setting(WalletSetting::CloneDetected))?;
+        self.runtime
+            .block_on(self.transaction_service.set_sending_locked(false))?;
+        self.runtime
+            .block_on(self.db.append_audit_event(WalletAuditEvent::CloneDetectionAcknowledged))?;
+        Ok(())
+    }
+
+    /// Get the automatic coin split policy, if one is set
+    pub fn get_auto_coin_split_policy(&mut self) -> Result<Option<AutoCoinSplitPolicy>, WalletError> {
+        Ok(self.runtime.block_on(self.db.get_auto_coin_split_policy())?)
+    }
+
+    /// Set the policy used to split the wallet funds into more outputs when it runs low on spendable outputs, or clear
+    /// it to stop splitting automatically. The policy is applied after the next base node sync or completed
+    /// transaction.
+    pub fn set_auto_coin_split_policy(&mut self, policy: Option<AutoCoinSplitPolicy>) -> Result<(), WalletError> {
+        match policy {
+            Some(policy) => self.runtime.block_on(self.db.set_auto_coin_split_policy(policy))?,
+            None => self
+                .runtime
+                .block_on(self.db.clear_setting(WalletSetting::AutoCoinSplitPolicy))?,
+        }
+        Ok(())
+    }
+
+    /// Get the canonical lifecycle state of a transaction, as tracked by the transaction lifecycle coordinator
+    pub fn get_transaction_lifecycle(&mut self, tx_id: TxId) -> Result<TransactionLifecycle, WalletError> {
+        self.runtime
+            .block_on(self.db.get_transaction_lifecycle(tx_id))?
+            .ok_or_else(|| WalletError::TransactionServiceError(TransactionServiceError::TransactionDoesNotExistError))
+    }
+
+    /// Get the canonical lifecycle states of all the transactions the transaction lifecycle coordinator has seen
+    pub fn get_transaction_lifecycles(&mut self) -> Result<Vec<TransactionLifecycle>, WalletError> {
+        Ok(self.runtime.block_on(self.db.get_transaction_lifecycles())?)
+    }
+
     /// Import an external spendable UTXO into the wallet. The output will be added to the Output Manager and made
     /// spendable. A faux incoming transaction will be created to provide a record of the event. The TxId of the
     /// generated transaction is returned.
@@ -277,6 +575,81 @@ where
         Ok(tx_id)
     }
 
+    /// Import a batch of external spendable UTXOs into the wallet. All of the outputs are added to the Output Manager
+    /// in a single database transaction, so either all of them are imported or none are. A faux incoming transaction
+    /// is created for each output and the generated TxIds are returned in the same order as the provided UTXOs.
+    pub fn import_utxos(
+        &mut self,
+        utxos: Vec<(MicroTari, PrivateKey)>,
+        source_public_key: &CommsPublicKey,
+        message: String,
+    ) -> Result<Vec<TxId>, WalletError>
+    {
+        let unblinded_outputs = utxos
+            .iter()
+            .map(|(amount, spending_key)| UnblindedOutput::new(*amount, spending_key.clone(), None))
+            .collect::<Vec<_>>();
+
+        self.runtime
+            .block_on(self.output_manager_service.add_outputs(unblinded_outputs))?;
+
+        let mut tx_ids = Vec::with_capacity(utxos.len());
+        for (amount, _) in utxos {
+            tx_ids.push(self.runtime.block_on(self.transaction_service.import_utxo(
+                amount,
+                source_public_key.clone(),
+                message.clone(),
+            ))?);
+        }
+
+        info!(target: LOG_TARGET, "{} UTXOs imported into wallet", tx_ids.len());
+
+        Ok(tx_ids)
+    }
+
+    /// Import the allocations in a published faucet claims file that belong to this wallet, i.e. those committing to
+    /// one of the provided claim keys. The outputs are imported with the features they were allocated with and a faux
+    /// incoming transaction from the faucet is created for each. A claims file is only imported once, so this can be
+    /// called on every start up; an empty list is returned if the file was already imported.
+    pub fn import_faucet_utxos(
+        &mut self,
+        claims_json: &str,
+        claim_keys: &[PrivateKey],
+    ) -> Result<Vec<TxId>, WalletError>
+    {
+        let claims_hash = to_hex(&Blake256::digest(claims_json.as_bytes()));
+        if self.runtime.block_on(self.db.get_faucet_claims_imported())? == Some(claims_hash.clone()) {
+            debug!(target: LOG_TARGET, "Faucet claims file already imported");
+            return Ok(Vec::new());
+        }
+
+        let claims = FaucetClaims::from_json(claims_json)?;
+        let source_public_key = claims.source_public_key()?;
+        let outputs = claims.claimable_outputs(claim_keys, &self.factories)?;
+
+        self.runtime
+            .block_on(self.output_manager_service.add_outputs(outputs.clone()))?;
+
+        let mut tx_ids = Vec::with_capacity(outputs.len());
+        for output in outputs {
+            tx_ids.push(self.runtime.block_on(self.transaction_service.import_utxo(
+                output.value,
+                source_public_key.clone(),
+                format!("Faucet allocation on {}", claims.network),
+            ))?);
+        }
+        self.runtime.block_on(self.db.set_faucet_claims_imported(claims_hash))?;
+
+        info!(
+            target: LOG_TARGET,
+            "{} faucet UTXOs for network {} imported into wallet",
+            tx_ids.len(),
+            claims.network
+        );
+
+        Ok(tx_ids)
+    }
+
     pub fn sign_message(
         &mut self,
         secret: RistrettoSecretKey,
@@ -313,3 +686,54 @@ where
         Ok(request_key)
     }
 }
+
+/// Record every event published by the transaction and output manager services in the wallet event journal, and
+/// publish each journaled entry to the event journal subscribers
+async fn journal_wallet_events<T: WalletBackend + 'static>(
+    db: WalletDatabase<T>,
+    mut transaction_events: Fuse<TransactionEventReceiver>,
+    mut output_manager_events: Fuse<Subscriber<OutputManagerEvent>>,
+    publisher: broadcast::Sender<Arc<EventJournalEntry>>,
+)
+{
+    loop {
+        let event = futures::select! {
+            event = transaction_events.select_next_some() => match event {
+                Ok(event) => WalletEvent::TransactionService((*event).clone()),
+                Err(e) => {
+                    warn!(target: LOG_TARGET, "Transaction service events were not journaled: {:?}", e);
+                    continue;
+                },
+            },
+            event = output_manager_events.select_next_some() => WalletEvent::OutputManager((*event).clone()),
+            complete => break,
+        };
+        match db.append_journal_event(event).await {
+            // There may be no subscribers, in which case the entry is only journaled
+            Ok(entry) => {
+                let _ = publisher.send(Arc::new(entry));
+            },
+            Err(e) => error!(target: LOG_TARGET, "Could not journal wallet event: {:?}", e),
+        }
+    }
+    debug!(target: LOG_TARGET, "Wallet event journaling stopped");
+}
+
+/// Report the latencies measured by the liveness service to the output manager, which uses the latency of the base node
+/// to tune its query timeout
+async fn report_peer_latencies(
+    mut liveness_events: Fuse<Subscriber<LivenessEvent>>,
+    mut output_manager: OutputManagerHandle,
+)
+{
+    while let Some(event) = liveness_events.next().await {
+        if let LivenessEvent::ReceivedPong(pong) = &*event {
+            if let Some(latency) = pong.latency {
+                let latency = Duration::from_millis(u64::from(latency));
+                if let Err(e) = output_manager.report_peer_latency(pong.node_id.clone(), latency).await {
+                    warn!(target: LOG_TARGET, "Could not report peer latency: {:?}", e);
+                }
+            }
+        }
+    }
+}
@@ -23,6 +23,7 @@
 #![feature(type_alias_impl_trait)]
 
 pub mod output_manager_service;
+pub mod reorg;
 pub mod support;
 // pub mod text_message_service;
 pub mod contacts_service;
@@ -24,6 +24,7 @@ use crate::support::{
     comms_and_services::create_dummy_message,
     utils::{make_input, random_string, TestParams},
 };
+use chrono::Utc;
 use futures::{
     channel::{mpsc, mpsc::Sender},
     FutureExt,
@@ -32,20 +33,29 @@ use futures::{
 };
 use prost::Message;
 use rand::{rngs::OsRng, RngCore};
-use std::{thread, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
 use tari_broadcast_channel::bounded;
 use tari_comms::{
     message::EnvelopeBody,
     peer_manager::{NodeIdentity, PeerFeatures},
+    types::CommsPublicKey,
 };
 use tari_comms_dht::outbound::mock::{create_outbound_service_mock, OutboundServiceMockState};
 use tari_core::{
     base_node::proto::{
         base_node as BaseNodeProto,
-        base_node::base_node_service_response::Response as BaseNodeResponseProto,
+        base_node::{
+            base_node_service_request::Request as BaseNodeRequestProto,
+            base_node_service_response::Response as BaseNodeResponseProto,
+        },
     },
     transactions::{
-        fee::Fee,
+        fee::{Fee, FeeModel},
         tari_amount::{uT, MicroTari},
         transaction::{KernelFeatures, OutputFeatures, Transaction, TransactionOutput, UnblindedOutput},
         transaction_protocol::single_receiver::SingleReceiverTransactionProtocol,
@@ -55,9 +65,14 @@ use tari_core::{
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
-    keys::SecretKey,
+    keys::{PublicKey, SecretKey},
     range_proof::RangeProofService,
-    tari_utilities::ByteArray,
+    tari_utilities::{hash::Hashable, ByteArray},
+};
+use tari_key_manager::{
+    bip39,
+    key_manager::KeyManager,
+    mnemonic::{Mnemonic, MnemonicLanguage},
 };
 use tari_p2p::domain_message::DomainMessage;
 use tari_service_framework::reply_channel;
@@ -65,17 +80,36 @@ use tari_shutdown::Shutdown;
 use tari_test_utils::collect_stream;
 use tari_wallet::{
     output_manager_service::{
-        config::OutputManagerServiceConfig,
+        config::{OutputManagerServiceConfig, WalletSeed},
         error::{OutputManagerError, OutputManagerStorageError},
         handle::{OutputManagerEvent, OutputManagerHandle},
-        service::OutputManagerService,
+        secret_key_provider::SecretKeyProvider,
+        service::{
+            BaseNodeSyncStatus,
+            OutputCollectionStatistics,
+            OutputManagerService,
+            TransactionSendOptions,
+            UTXOSelectionStrategy,
+        },
         storage::{
-            database::{DbKey, DbValue, OutputManagerBackend, OutputManagerDatabase},
+            database::{
+                DbKey,
+                DbKeyValuePair,
+                DbValue,
+                InvalidationReason,
+                KeyManagerBranch,
+                OutputManagerBackend,
+                OutputManagerDatabase,
+                OutputSource,
+                SyncOutcome,
+                WriteOperation,
+            },
             memory_db::OutputManagerMemoryDatabase,
             sqlite_db::OutputManagerSqliteDatabase,
         },
     },
     storage::connection_manager::run_migration_and_create_sqlite_connection,
+    types::KeyDigest,
 };
 use tempdir::TempDir;
 use tokio::{runtime::Runtime, time::delay_for};
@@ -89,6 +123,28 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
     Shutdown,
     Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
 )
+{
+    setup_output_manager_service_with_config(
+        runtime,
+        OutputManagerServiceConfig {
+            base_node_query_timeout: Duration::from_secs(3),
+            base_node_query_retry_backoff: Duration::from_secs(0),
+            ..Default::default()
+        },
+        backend,
+    )
+}
+
+pub fn setup_output_manager_service_with_config<T: OutputManagerBackend + 'static>(
+    runtime: &mut Runtime,
+    config: OutputManagerServiceConfig,
+    backend: T,
+) -> (
+    OutputManagerHandle,
+    OutboundServiceMockState,
+    Shutdown,
+    Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
+)
 {
     let shutdown = Shutdown::new();
     let factories = CryptoFactories::default();
@@ -100,9 +156,7 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
 
     let output_manager_service = runtime
         .block_on(OutputManagerService::new(
-            OutputManagerServiceConfig {
-                base_node_query_timeout: Duration::from_secs(3),
-            },
+            config,
             outbound_message_requester.clone(),
             oms_request_receiver,
             base_node_response_receiver,
@@ -183,6 +237,17 @@ fn sending_transaction_and_confirmation<T: Clone + OutputManagerBackend + 'stati
 
     let sender_tx_id = stp.get_tx_id().unwrap();
 
+    let pending_tx = runtime.block_on(oms.get_pending_transaction(sender_tx_id)).unwrap();
+    assert_eq!(pending_tx.amount, MicroTari::from(1000));
+    assert_eq!(pending_tx.fee, stp.get_fee_amount().unwrap());
+    assert_eq!(pending_tx.destination, None);
+    let (_, destination) = CommsPublicKey::random_keypair(&mut OsRng);
+    runtime
+        .block_on(oms.set_pending_transaction_destination(sender_tx_id, destination.clone()))
+        .unwrap();
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    assert_eq!(pending_txs.get(&sender_tx_id).unwrap().destination, Some(destination));
+
     let tx = runtime.block_on(complete_transaction(stp, oms.clone()));
 
     runtime
@@ -265,6 +330,38 @@ fn send_not_enough_funds_sqlite_db() {
     send_not_enough_funds(OutputManagerSqliteDatabase::new(connection));
 }
 
+#[test]
+fn send_below_min_output_value() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            min_output_value: MicroTari::from(100),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(10_000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    match runtime.block_on(oms.prepare_transaction_to_send(
+        MicroTari::from(99),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+    )) {
+        Err(OutputManagerError::BelowMinimumOutputValue) => assert!(true),
+        _ => assert!(false),
+    }
+
+    match runtime.block_on(oms.create_coin_split(MicroTari::from(99), 5, MicroTari::from(20), None)) {
+        Err(OutputManagerError::BelowMinimumOutputValue) => assert!(true),
+        _ => assert!(false),
+    }
+}
+
 fn send_no_change<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
@@ -341,6 +438,98 @@ fn send_no_change_sqlite_db() {
     send_no_change(OutputManagerSqliteDatabase::new(connection));
 }
 
+fn send_to_many<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    for _ in 0..3 {
+        runtime
+            .block_on(oms.add_output(UnblindedOutput::new(
+                MicroTari::from(2000),
+                PrivateKey::random(&mut OsRng),
+                None,
+            )))
+            .unwrap();
+    }
+
+    let amounts = vec![MicroTari::from(1000), MicroTari::from(1500), MicroTari::from(2000)];
+    match runtime.block_on(oms.prepare_transaction_to_send_to_many(
+        Vec::new(),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        TransactionSendOptions::default(),
+    )) {
+        Err(OutputManagerError::NoRecipients) => (),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    let mut stp = runtime
+        .block_on(oms.prepare_transaction_to_send_to_many(
+            amounts.clone(),
+            MicroTari::from(20),
+            None,
+            "Payout".to_string(),
+            TransactionSendOptions::default(),
+        ))
+        .unwrap();
+    assert!(stp.is_collecting_multiple_signatures());
+    let sender_tx_id = stp.get_tx_id().unwrap();
+    assert_eq!(stp.get_total_amount().unwrap(), MicroTari::from(4500));
+    // The recipients share the fee of one transaction with a kernel each and a single change output
+    let fee = Fee::calculate(MicroTari::from(20), 3, 3, 4);
+    assert_eq!(stp.get_fee_amount().unwrap(), fee);
+    assert_eq!(stp.get_change_amounts().unwrap(), vec![
+        MicroTari::from(6000) - MicroTari::from(4500) - fee
+    ]);
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 1);
+
+    let msgs = stp.get_multi_recipient_messages().unwrap();
+    assert_eq!(msgs.iter().map(|m| m.amount).collect::<Vec<_>>(), amounts);
+    for msg in msgs {
+        let b = TestParams::new(&mut OsRng);
+        let recv_info = SingleReceiverTransactionProtocol::create(
+            &msg,
+            b.nonce,
+            b.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .unwrap();
+        stp.add_multi_recipient_info(recv_info, &factories.range_proof).unwrap();
+    }
+    assert!(stp.finalize(KernelFeatures::empty(), &factories).unwrap());
+    let tx = stp.get_transaction().unwrap();
+    assert_eq!(tx.body.kernels().len(), 3);
+    assert_eq!(tx.body.outputs().len(), 4);
+
+    runtime
+        .block_on(oms.confirm_transaction(sender_tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(runtime.block_on(oms.get_spent_outputs()).unwrap().len(), 3);
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 1);
+}
+
+#[test]
+fn send_to_many_memory_db() {
+    send_to_many(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn send_to_many_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    send_to_many(OutputManagerSqliteDatabase::new(connection));
+}
+
 fn send_not_enough_for_change<T: OutputManagerBackend + 'static>(backend: T) {
     let mut runtime = Runtime::new().unwrap();
 
@@ -401,7 +590,7 @@ fn receiving_and_confirmation<T: OutputManagerBackend + 'static>(backend: T) {
     let commitment = factories.commitment.commit(&recv_key, &value.into());
     let rr = factories.range_proof.construct_proof(&recv_key, value.into()).unwrap();
     let output = TransactionOutput::new(
-        OutputFeatures::create_coinbase(1),
+        OutputFeatures::default(),
         commitment,
         RangeProof::from_bytes(&rr).unwrap(),
     );
@@ -588,6 +777,191 @@ fn test_get_balance_sqlite_db() {
     test_get_balance(OutputManagerSqliteDatabase::new(connection));
 }
 
+#[test]
+fn cached_balance_follows_output_changes() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari(0)
+    );
+    runtime
+        .block_on(oms.add_output(UnblindedOutput::new(
+            MicroTari::from(1000),
+            PrivateKey::random(&mut OsRng),
+            None,
+        )))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(1000)
+    );
+    // Served from the cache
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(1000)
+    );
+
+    runtime
+        .block_on(oms.get_recipient_spending_key(1, MicroTari::from(500)))
+        .unwrap();
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(500));
+
+    runtime.block_on(oms.cancel_transaction(1)).unwrap();
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_incoming_balance, MicroTari(0));
+    assert_eq!(balance.available_balance, MicroTari::from(1000));
+}
+
+#[test]
+fn concurrent_queries_follow_serialised_changes() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    for _ in 0..5 {
+        runtime
+            .block_on(oms.add_output(UnblindedOutput::new(
+                MicroTari::from(1000),
+                PrivateKey::random(&mut OsRng),
+                None,
+            )))
+            .unwrap();
+    }
+
+    // Queries answered while outputs are added must each see a consistent state, and a query made after a change
+    // must see the change
+    let mut oms_query = oms.clone();
+    let (added, balances) = runtime.block_on(futures::future::join(
+        async move {
+            for _ in 0..5 {
+                oms.add_output(UnblindedOutput::new(
+                    MicroTari::from(1000),
+                    PrivateKey::random(&mut OsRng),
+                    None,
+                ))
+                .await
+                .unwrap();
+            }
+            oms
+        },
+        async move {
+            let mut balances = Vec::new();
+            for _ in 0..20 {
+                balances.push(oms_query.get_balance().await.unwrap().available_balance);
+                let unspent = oms_query.get_unspent_outputs().await.unwrap();
+                assert!(unspent.len() >= 5);
+            }
+            balances
+        },
+    ));
+    oms = added;
+    for balance in balances {
+        assert!(balance >= MicroTari::from(5000) && balance <= MicroTari::from(10000));
+    }
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(10000)
+    );
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 10);
+}
+
+#[test]
+fn test_output_statistics() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    for value in &[7u64, 30, 45, 2000] {
+        let output = UnblindedOutput::new(MicroTari::from(*value), PrivateKey::random(&mut OsRng), None);
+        runtime.block_on(oms.add_output(output)).unwrap();
+    }
+    runtime
+        .block_on(oms.get_recipient_spending_key(1, MicroTari::from(500)))
+        .unwrap();
+
+    let stats = runtime.block_on(oms.get_output_statistics()).unwrap();
+    assert_eq!(stats.unspent.count, 4);
+    assert_eq!(stats.unspent.total_value, MicroTari::from(2082));
+    assert_eq!(stats.unspent.min_value, Some(MicroTari::from(7)));
+    assert_eq!(stats.unspent.max_value, Some(MicroTari::from(2000)));
+    assert_eq!(stats.unspent.mean_value, Some(MicroTari::from(520)));
+    let histogram = stats
+        .unspent
+        .histogram
+        .iter()
+        .map(|b| (u64::from(b.lower_bound), u64::from(b.upper_bound), b.count))
+        .collect::<Vec<_>>();
+    assert_eq!(histogram, vec![
+        (0, 10, 1),
+        (10, 100, 2),
+        (100, 1000, 0),
+        (1000, 10000, 1)
+    ]);
+
+    assert_eq!(stats.pending.count, 1);
+    assert_eq!(stats.pending.histogram.len(), 1);
+    assert_eq!(stats.pending.histogram[0].lower_bound, MicroTari::from(100));
+    assert_eq!(stats.spent, OutputCollectionStatistics::default());
+    assert_eq!(stats.invalid.count, 0);
+    assert_eq!(stats.invalid.mean_value, None);
+}
+
+fn test_get_balance_at_height<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let spendable = UnblindedOutput::new(MicroTari::from(1000), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(spendable)).unwrap();
+    let time_locked = UnblindedOutput::new(
+        MicroTari::from(2000),
+        PrivateKey::random(&mut OsRng),
+        Some(OutputFeatures::with_maturity(5)),
+    );
+    runtime.block_on(oms.add_output(time_locked)).unwrap();
+    let coinbase = UnblindedOutput::new(
+        MicroTari::from(4000),
+        PrivateKey::random(&mut OsRng),
+        Some(OutputFeatures::create_coinbase(10)),
+    );
+    runtime.block_on(oms.add_output(coinbase)).unwrap();
+
+    let balance = runtime.block_on(oms.get_balance_at_height(4)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(1000));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(2000));
+    assert_eq!(balance.unconfirmed_coinbase_balance, MicroTari::from(4000));
+
+    let balance = runtime.block_on(oms.get_balance_at_height(5)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(3000));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(0));
+    assert_eq!(balance.unconfirmed_coinbase_balance, MicroTari::from(4000));
+
+    let balance = runtime.block_on(oms.get_balance_at_height(10)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(7000));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(0));
+    assert_eq!(balance.unconfirmed_coinbase_balance, MicroTari::from(0));
+}
+
+#[test]
+fn test_get_balance_at_height_memory_db() {
+    test_get_balance_at_height(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn test_get_balance_at_height_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    test_get_balance_at_height(OutputManagerSqliteDatabase::new(connection));
+}
+
 fn test_confirming_received_output<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
@@ -601,7 +975,7 @@ fn test_confirming_received_output<T: OutputManagerBackend + 'static>(backend: T
 
     let rr = factories.range_proof.construct_proof(&recv_key, value.into()).unwrap();
     let output = TransactionOutput::new(
-        OutputFeatures::create_coinbase(1),
+        OutputFeatures::default(),
         commitment,
         RangeProof::from_bytes(&rr).unwrap(),
     );
@@ -609,6 +983,13 @@ fn test_confirming_received_output<T: OutputManagerBackend + 'static>(backend: T
         .block_on(oms.confirm_transaction(1, vec![], vec![output]))
         .unwrap();
     assert_eq!(runtime.block_on(oms.get_balance()).unwrap().available_balance, value);
+
+    // The received output records the transaction it came from
+    let metadata = runtime.block_on(oms.get_output_metadata()).unwrap();
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata[0].spending_key, recv_key);
+    assert_eq!(metadata[0].source_tx_id, Some(1));
+    assert_eq!(metadata[0].label, None);
 }
 
 #[test]
@@ -627,6 +1008,136 @@ fn test_confirming_received_output_sqlite_db() {
     test_confirming_received_output(OutputManagerSqliteDatabase::new(connection));
 }
 
+fn confirming_outputs_with_non_default_features<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    // A coinbase is only confirmed by an output carrying the coinbase features it was requested with
+    let value = MicroTari::from(5000);
+    let coinbase_key = runtime.block_on(oms.get_coinbase_spending_key(1, value, 10)).unwrap();
+    let commitment = factories.commitment.commit(&coinbase_key, &value.into());
+    let rr = factories
+        .range_proof
+        .construct_proof(&coinbase_key, value.into())
+        .unwrap();
+    let wrong_features = TransactionOutput::new(
+        OutputFeatures::default(),
+        commitment.clone(),
+        RangeProof::from_bytes(&rr).unwrap(),
+    );
+    assert!(runtime
+        .block_on(oms.confirm_transaction(1, vec![], vec![wrong_features]))
+        .is_err());
+    let output = TransactionOutput::new(
+        OutputFeatures::create_coinbase(10),
+        commitment,
+        RangeProof::from_bytes(&rr).unwrap(),
+    );
+    runtime
+        .block_on(oms.confirm_transaction(1, vec![], vec![output]))
+        .unwrap();
+    let unspent = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent.len(), 1);
+    assert_eq!(unspent[0].features, OutputFeatures::create_coinbase(10));
+
+    // Spending the coinbase is confirmed by an input carrying the same features
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    let tx_id = stp.get_tx_id().unwrap();
+    let tx = runtime.block_on(complete_transaction(stp, oms.clone()));
+    assert_eq!(tx.body.inputs()[0].features, OutputFeatures::create_coinbase(10));
+
+    let mut wrong_inputs = tx.body.inputs().clone();
+    wrong_inputs[0].features = OutputFeatures::default();
+    assert!(runtime
+        .block_on(oms.confirm_transaction(tx_id, wrong_inputs, tx.body.outputs().clone()))
+        .is_err());
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(runtime.block_on(oms.get_spent_outputs()).unwrap().len(), 1);
+}
+
+#[test]
+fn confirming_outputs_with_non_default_features_memory_db() {
+    confirming_outputs_with_non_default_features(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn confirming_outputs_with_non_default_features_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    confirming_outputs_with_non_default_features(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn output_labels<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let (_ti, uo1) = make_input(&mut OsRng, MicroTari::from(1000), &factories.commitment);
+    let (ti2, uo2) = make_input(&mut OsRng, MicroTari::from(2000), &factories.commitment);
+    let (ti3, uo3) = make_input(&mut OsRng, MicroTari::from(3000), &factories.commitment);
+    runtime
+        .block_on(oms.add_outputs(vec![uo1.clone(), uo2.clone(), uo3.clone()]))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_output_metadata()).unwrap().len(), 3);
+
+    runtime
+        .block_on(oms.set_output_label(ti2.commitment.clone(), Some("savings".to_string())))
+        .unwrap();
+    runtime
+        .block_on(oms.set_output_label(ti3.commitment.clone(), Some("savings".to_string())))
+        .unwrap();
+    let mut labelled = runtime
+        .block_on(oms.fetch_outputs_by_label("savings".to_string()))
+        .unwrap();
+    labelled.sort_by(|a, b| a.value.cmp(&b.value));
+    assert_eq!(labelled, vec![uo2, uo3.clone()]);
+
+    // Clearing a label removes the output from the label
+    runtime.block_on(oms.set_output_label(ti2.commitment, None)).unwrap();
+    let labelled = runtime
+        .block_on(oms.fetch_outputs_by_label("savings".to_string()))
+        .unwrap();
+    assert_eq!(labelled, vec![uo3]);
+    assert!(runtime
+        .block_on(oms.fetch_outputs_by_label("spending".to_string()))
+        .unwrap()
+        .is_empty());
+
+    let (ti4, _uo4) = make_input(&mut OsRng, MicroTari::from(4000), &factories.commitment);
+    match runtime.block_on(oms.set_output_label(ti4.commitment, Some("savings".to_string()))) {
+        Err(OutputManagerError::OutputNotFound(_)) => assert!(true),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn output_labels_memory_db() {
+    output_labels(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn output_labels_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    output_labels(OutputManagerSqliteDatabase::new(connection));
+}
+
 #[test]
 fn test_startup_utxo_scan() {
     let factories = CryptoFactories::default();
@@ -718,6 +1229,7 @@ fn test_startup_utxo_scan() {
         )),
     };
 
+    let mut event_stream = oms.get_event_stream_fused();
     runtime
         .block_on(base_node_response_sender.send(create_dummy_message(
             base_node_response,
@@ -725,28 +1237,40 @@ fn test_startup_utxo_scan() {
         )))
         .unwrap();
 
-    let result_stream = runtime.block_on(async {
-        collect_stream!(
-            oms.get_event_stream_fused().map(|i| (*i).clone()),
-            take = 2,
-            timeout = Duration::from_secs(60)
-        )
-    });
-
-    assert_eq!(
-        1,
-        result_stream.iter().fold(0, |acc, item| {
-            if let OutputManagerEvent::ReceiveBaseNodeResponse(_) = item {
-                acc + 1
-            } else {
-                acc
+    let (num_invalidated, invalidated) = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(60)).fuse();
+        let mut invalidated = Vec::new();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    match (*event).clone() {
+                        OutputManagerEvent::TxoInvalidated { commitment, value, tx_id } => {
+                            invalidated.push((commitment, value, tx_id))
+                        },
+                        OutputManagerEvent::SyncCompleted { num_invalidated, .. } => {
+                            break (Some(num_invalidated), invalidated)
+                        },
+                        _ => (),
+                    }
+                },
+                () = delay => break (None, invalidated),
             }
-        })
-    );
+        }
+    });
+    assert_eq!(num_invalidated, Some(1));
+    assert_eq!(invalidated, vec![(
+        output2.as_transaction_output(&factories).unwrap().commitment,
+        output2.value,
+        None
+    )]);
 
     let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
     assert_eq!(invalid_outputs.len(), 1);
-    assert_eq!(invalid_outputs[0], output2);
+    assert_eq!(invalid_outputs[0].output, output2);
+    match invalid_outputs[0].reason.as_ref() {
+        Some(InvalidationReason::NotReturnedByBaseNode { .. }) => (),
+        r => panic!("Unexpected invalidation reason: {:?}", r),
+    }
 
     let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
     assert_eq!(unspent_outputs.len(), 2);
@@ -786,7 +1310,7 @@ fn test_startup_utxo_scan() {
         loop {
             futures::select! {
                 event = event_stream.select_next_some() => {
-                    if let OutputManagerEvent::ReceiveBaseNodeResponse(_) = (*event).clone() {
+                    if let OutputManagerEvent::SyncCompleted { .. } = (*event).clone() {
                         acc += 1;
                         if acc >= 2 {
                             break;
@@ -805,164 +1329,2624 @@ fn test_startup_utxo_scan() {
     assert_eq!(invalid_txs.len(), 3);
 }
 
-fn sending_transaction_with_short_term_clear<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+#[test]
+fn test_stale_utxo_query_response_ignored() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let output1 = UnblindedOutput::new(MicroTari::from(500), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(output1.clone())).unwrap();
+    let output2 = UnblindedOutput::new(MicroTari::from(800), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(output2.clone())).unwrap();
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    // Setting the base node sends the first query, the sync sends a second one
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    runtime.block_on(oms.sync_with_base_node()).unwrap();
+    outbound_service.wait_call_count(2, Duration::from_secs(60)).unwrap();
+    let request_keys = outbound_service
+        .take_calls()
+        .into_iter()
+        .map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap()
+                .request_key
+        })
+        .collect::<Vec<_>>();
+
+    let mut event_stream = oms.get_event_stream_fused();
+
+    // The response to the newer query confirms both outputs, the late response to the older query returns none
+    let outputs = vec![
+        output1.clone().as_transaction_output(&factories).unwrap().into(),
+        output2.clone().as_transaction_output(&factories).unwrap().into(),
+    ];
+    for (request_key, outputs) in vec![(request_keys[1], outputs), (request_keys[0], vec![])] {
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key,
+            response: Some(BaseNodeResponseProto::TransactionOutputs(
+                BaseNodeProto::TransactionOutputs { outputs },
+            )),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut acc = 0;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::SyncCompleted { .. } = (*event).clone() {
+                        acc += 1;
+                        if acc >= 2 {
+                            break;
+                        }
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert!(acc >= 2, "Did not receive enough responses");
+    });
+
+    let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    assert_eq!(invalid_outputs.len(), 0);
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), 2);
+}
+
+#[test]
+fn cancel_base_node_sync() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            base_node_query_timeout: Duration::from_secs(1),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let output = UnblindedOutput::new(MicroTari::from(500), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(output)).unwrap();
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    let mut event_stream = oms.get_event_stream_fused();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+    let (_, body) = outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+    let request_key = envelope_body
+        .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+        .unwrap()
+        .unwrap()
+        .request_key;
+
+    assert_eq!(runtime.block_on(oms.cancel_base_node_sync()).unwrap(), vec![
+        request_key
+    ]);
+    assert_eq!(
+        runtime.block_on(oms.cancel_base_node_sync()).unwrap(),
+        Vec::<u64>::new()
+    );
+
+    // The cancelled query does not time out
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(3)).fuse();
+        let mut cancelled = false;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    match (*event).clone() {
+                        OutputManagerEvent::BaseNodeSyncCancelled(keys) => cancelled = keys == vec![request_key],
+                        OutputManagerEvent::BaseNodeSyncRequestTimedOut(_) => panic!("A cancelled query timed out"),
+                        _ => (),
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert!(cancelled, "Did not receive the cancellation event");
+    });
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_sync_status()).unwrap().retry_count,
+        0
+    );
+}
+
+#[test]
+fn recovery_scan_from_seed() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let value = MicroTari::from(5000);
+
+    // The original wallet receives an output on the first key derived from its seed
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let seed_words = runtime.block_on(oms.get_seed_words(None)).unwrap();
+    let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
+    let output = UnblindedOutput::new(value, recv_key, None);
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                recovery_scan_values: vec![MicroTari::from(1000), value],
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    match runtime.block_on(oms.scan_for_outputs_from_seed(seed_words.clone(), None, 3, false)) {
+        Err(OutputManagerError::NoBaseNodeKeysProvided) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    match runtime.block_on(oms.scan_for_outputs_from_seed(seed_words.clone(), None, 0, false)) {
+        Err(OutputManagerError::InvalidGapLimit) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let mut event_stream = oms.get_event_stream_fused();
+    let request_key = runtime
+        .block_on(oms.scan_for_outputs_from_seed(seed_words.clone(), None, 3, false))
+        .unwrap();
+    match runtime.block_on(oms.scan_for_outputs_from_seed(seed_words, None, 3, false)) {
+        Err(OutputManagerError::RecoveryScanInProgress) => assert!(true),
+        _ => assert!(false),
+    }
+    let _ = outbound_service.take_calls();
+
+    // Finding an output on the first key extends the scan by another batch, which comes back empty
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(
+            BaseNodeProto::TransactionOutputs {
+                outputs: vec![output.clone().as_transaction_output(&factories).unwrap().into()],
+            },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    // The next batch of the spending branch and the first batch of each of the other branches come back empty
+    for _ in 0..3 {
+        outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+        let (_, body) = outbound_service.pop_call().unwrap();
+        let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+        let request_key = envelope_body
+            .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+            .unwrap()
+            .unwrap()
+            .request_key;
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key,
+            response: Some(BaseNodeResponseProto::TransactionOutputs(
+                BaseNodeProto::TransactionOutputs { outputs: vec![] },
+            )),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut recovered = None;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::RecoveryScanComplete(n) = (*event).clone() {
+                        recovered = Some(n);
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(recovered, Some(1));
+    });
+
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs, vec![output]);
+
+    // The recovered wallet continues after the recovered key instead of reusing it
+    let next_key = runtime.block_on(oms.get_recipient_spending_key(2, value)).unwrap();
+    assert_ne!(next_key, unspent_outputs[0].spending_key);
+}
+
+// Answer every query of a recovery scan that finds no outputs, one per key manager branch, and wait for the scan to
+// complete
+fn complete_recovery_scan_without_outputs(
+    runtime: &mut Runtime,
+    oms: &mut OutputManagerHandle,
+    outbound_service: &OutboundServiceMockState,
+    base_node_response_sender: &mut Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
+    base_node_public_key: &CommsPublicKey,
+    request_key: u64,
+)
+{
+    let mut event_stream = oms.get_event_stream_fused();
+    let mut request_key = request_key;
+    for i in 0..KeyManagerBranch::all().len() {
+        if i > 0 {
+            outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+            let (_, body) = outbound_service.pop_call().unwrap();
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            request_key = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap()
+                .request_key;
+        }
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key,
+            response: Some(BaseNodeResponseProto::TransactionOutputs(
+                BaseNodeProto::TransactionOutputs { outputs: vec![] },
+            )),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(base_node_response, base_node_public_key)))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut recovered = None;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::RecoveryScanComplete(n) = (*event).clone() {
+                        recovered = Some(n);
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(recovered, Some(0));
+    });
+}
+
+#[test]
+fn recovery_scan_keeps_key_manager_of_wallet_in_use() {
+    let mut runtime = Runtime::new().unwrap();
+    let value = MicroTari::from(5000);
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    // The seed words of another wallet
+    let (mut other_oms, _, _other_shutdown, _) =
+        setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let other_seed_words = runtime.block_on(other_oms.get_seed_words(None)).unwrap();
+
+    // A scan has no values to look for unless they are configured
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    match runtime.block_on(oms.scan_for_outputs_from_seed(other_seed_words.clone(), None, 3, false)) {
+        Err(OutputManagerError::NoRecoveryScanValues) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                recovery_scan_values: vec![value],
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let seed_words = runtime.block_on(oms.get_seed_words(None)).unwrap();
+    // The wallet is in use once it has derived a key
+    let _ = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    // Scanning the seed of another wallet keeps the key manager state of the wallet
+    let request_key = runtime
+        .block_on(oms.scan_for_outputs_from_seed(other_seed_words.clone(), None, 3, false))
+        .unwrap();
+    let _ = outbound_service.take_calls();
+    complete_recovery_scan_without_outputs(
+        &mut runtime,
+        &mut oms,
+        &outbound_service,
+        &mut base_node_response_sender,
+        base_node_identity.public_key(),
+        request_key,
+    );
+    assert_eq!(runtime.block_on(oms.get_seed_words(None)).unwrap(), seed_words);
+
+    // Unless the scan is asked to replace it
+    let request_key = runtime
+        .block_on(oms.scan_for_outputs_from_seed(other_seed_words.clone(), None, 3, true))
+        .unwrap();
+    let _ = outbound_service.take_calls();
+    complete_recovery_scan_without_outputs(
+        &mut runtime,
+        &mut oms,
+        &outbound_service,
+        &mut base_node_response_sender,
+        base_node_identity.public_key(),
+        request_key,
+    );
+    assert_eq!(runtime.block_on(oms.get_seed_words(None)).unwrap(), other_seed_words);
+}
+
+#[test]
+fn scan_outputs_for_height_range() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let value = MicroTari::from(5000);
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                recovery_scan_values: vec![value],
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    match runtime.block_on(oms.scan_outputs_for_height_range(0, 60)) {
+        Err(OutputManagerError::NoBaseNodeKeysProvided) => assert!(true),
+        _ => assert!(false),
+    }
+
+    // Outputs were paid to one-sided keys the wallet has not derived yet, and one of them was spent since
+    let seed_words = runtime.block_on(oms.get_seed_words(None)).unwrap();
+    let master_key = KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "".to_string(), 0)
+        .unwrap()
+        .master_key;
+    let one_sided_key_manager =
+        KeyManager::<PrivateKey, KeyDigest>::from(master_key, KeyManagerBranch::OneSided.to_string(), 0);
+    let output = UnblindedOutput::new(value, one_sided_key_manager.derive_key(1).unwrap().k, None);
+    let spent_output = UnblindedOutput::new(value, one_sided_key_manager.derive_key(2).unwrap().k, None);
+    let spent_commitment = spent_output.as_transaction_output(&factories).unwrap().commitment;
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    match runtime.block_on(oms.scan_outputs_for_height_range(5, 1)) {
+        Err(OutputManagerError::InvalidHeightRange) => assert!(true),
+        _ => assert!(false),
+    }
+
+    // A wallet without configured values has nothing to look for
+    let (mut oms_without_values, _, _shutdown_without_values, _) =
+        setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    runtime
+        .block_on(oms_without_values.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    match runtime.block_on(oms_without_values.scan_outputs_for_height_range(0, 60)) {
+        Err(OutputManagerError::NoRecoveryScanValues) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let mut event_stream = oms.get_event_stream_fused();
+    runtime.block_on(oms.scan_outputs_for_height_range(0, 60)).unwrap();
+    match runtime.block_on(oms.scan_outputs_for_height_range(0, 60)) {
+        Err(OutputManagerError::RecoveryScanInProgress) => assert!(true),
+        _ => assert!(false),
+    }
+
+    // Wait for the next block query of the scan, skipping the queries sent when the base node was set
+    let next_block_query = || loop {
+        outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+        let query = outbound_service.take_calls().into_iter().find_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            let request = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap();
+            match request.request {
+                Some(BaseNodeRequestProto::FetchBlocks(heights)) => Some((request.request_key, heights.heights)),
+                _ => None,
+            }
+        });
+        if let Some(query) = query {
+            return query;
+        }
+    };
+
+    // The blocks are requested in two batches
+    let (request_key, heights) = next_block_query();
+    assert_eq!(heights, (0..50).collect::<Vec<u64>>());
+    let block = tari_core::proto::core::Block {
+        header: Some(tari_core::proto::core::BlockHeader {
+            height: 10,
+            ..Default::default()
+        }),
+        body: Some(tari_core::transactions::proto::types::AggregateBody {
+            outputs: vec![
+                output.clone().as_transaction_output(&factories).unwrap().into(),
+                spent_output.as_transaction_output(&factories).unwrap().into(),
+            ],
+            ..Default::default()
+        }),
+    };
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::HistoricalBlocks(
+            BaseNodeProto::HistoricalBlocks {
+                blocks: vec![tari_core::proto::core::HistoricalBlock {
+                    confirmations: 1,
+                    spent_commitments: vec![tari_core::transactions::proto::types::Commitment {
+                        data: spent_commitment.as_bytes().to_vec(),
+                    }],
+                    block: Some(block),
+                }],
+            },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    let (request_key, heights) = next_block_query();
+    assert_eq!(heights, (50..=60).collect::<Vec<u64>>());
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::HistoricalBlocks(
+            BaseNodeProto::HistoricalBlocks { blocks: vec![] },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut found = None;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::HeightRangeScanComplete(n) = (*event).clone() {
+                        found = Some(n);
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(found, Some(1));
+    });
+
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs, vec![output]);
+}
+
+#[test]
+fn key_manager_branches() {
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let seed_words = runtime.block_on(oms.get_seed_words(None)).unwrap();
+
+    let spending_key = runtime.block_on(oms.get_next_key(KeyManagerBranch::Spending)).unwrap();
+    let coinbase_key = runtime
+        .block_on(oms.get_coinbase_spending_key(1, MicroTari::from(5000), 10))
+        .unwrap();
+    let coinbase_key2 = runtime.block_on(oms.get_next_key(KeyManagerBranch::Coinbase)).unwrap();
+    let one_sided_key = runtime.block_on(oms.get_next_key(KeyManagerBranch::OneSided)).unwrap();
+
+    // The branches do not share an index space, so the first key of each branch is derived at the same index with a
+    // different branch seed
+    let mut spending_km = KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "".to_string(), 0).unwrap();
+    let mut coinbase_km =
+        KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "coinbase".to_string(), 0).unwrap();
+    let mut one_sided_km =
+        KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "one-sided".to_string(), 0).unwrap();
+    assert_eq!(spending_key, spending_km.next_key().unwrap().k);
+    assert_eq!(coinbase_key, coinbase_km.next_key().unwrap().k);
+    assert_eq!(coinbase_key2, coinbase_km.next_key().unwrap().k);
+    assert_eq!(one_sided_key, one_sided_km.next_key().unwrap().k);
+
+    let recv_key = runtime
+        .block_on(oms.get_recipient_spending_key(2, MicroTari::from(5000)))
+        .unwrap();
+    assert_eq!(recv_key, spending_km.next_key().unwrap().k);
+}
+
+#[test]
+fn seed_words_in_each_language() {
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            mnemonic_language: MnemonicLanguage::Italian,
+            recovery_scan_values: vec![MicroTari::from(5000)],
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+
+    // The configured language is used when no language is requested
+    let seed_words = runtime.block_on(oms.get_seed_words(None)).unwrap();
+    assert_eq!(
+        MnemonicLanguage::detect_language(&seed_words),
+        Ok(MnemonicLanguage::Italian)
+    );
+    let master_key = PrivateKey::from_mnemonic(&seed_words).unwrap();
+
+    // The seed words of every language are detected as that language and decode to the same master key
+    for language in MnemonicLanguage::iterator() {
+        let words = runtime.block_on(oms.get_seed_words(Some(language.clone()))).unwrap();
+        assert_eq!(words.len(), seed_words.len());
+        assert_eq!(MnemonicLanguage::detect_language(&words), Ok(language.clone()));
+        assert_eq!(PrivateKey::from_mnemonic(&words), Ok(master_key.clone()));
+        assert_eq!(
+            PrivateKey::from_mnemonic_with_language(&words, language),
+            Ok(master_key.clone())
+        );
+    }
+
+    // Restoring from seed words with the wrong language fails
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    match runtime.block_on(oms.scan_for_outputs_from_seed(seed_words, Some(MnemonicLanguage::English), 3, false)) {
+        Err(OutputManagerError::KeyManagerError(_)) => assert!(true),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn wallet_created_from_bip39_seed_words() {
+    let mut runtime = Runtime::new().unwrap();
+    let seed = WalletSeed::random_bip39(&mut OsRng, MnemonicLanguage::English, "25th word".to_string()).unwrap();
+    let words = match &seed {
+        WalletSeed::Bip39 { words, .. } => words.clone(),
+        _ => panic!("Expected BIP-39 seed words"),
+    };
+    let with_passphrase = |passphrase: &str| OutputManagerServiceConfig {
+        seed_words: Some(WalletSeed::Bip39 {
+            words: words.clone(),
+            language: None,
+            passphrase: passphrase.to_string(),
+        }),
+        ..Default::default()
+    };
+
+    let (mut oms1, _, _shutdown1, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        with_passphrase("25th word"),
+        OutputManagerMemoryDatabase::new(),
+    );
+    let (mut oms2, _, _shutdown2, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        with_passphrase("25th word"),
+        OutputManagerMemoryDatabase::new(),
+    );
+    let (mut oms3, _, _shutdown3, _) =
+        setup_output_manager_service_with_config(&mut runtime, with_passphrase(""), OutputManagerMemoryDatabase::new());
+
+    // The wallet returns the BIP-39 seed words it was created from, in any language
+    assert_eq!(runtime.block_on(oms1.get_seed_words(None)).unwrap(), words);
+    let spanish_words = runtime
+        .block_on(oms1.get_seed_words(Some(MnemonicLanguage::Spanish)))
+        .unwrap();
+    assert_eq!(
+        bip39::to_entropy(&spanish_words, Some(&MnemonicLanguage::Spanish)),
+        bip39::to_entropy(&words, None)
+    );
+    assert_eq!(runtime.block_on(oms3.get_seed_words(None)).unwrap(), words);
+
+    // The same words and passphrase restore the same keys, another passphrase creates a different wallet
+    let key1 = runtime
+        .block_on(oms1.get_recipient_spending_key(1, MicroTari::from(500)))
+        .unwrap();
+    let key2 = runtime
+        .block_on(oms2.get_recipient_spending_key(1, MicroTari::from(500)))
+        .unwrap();
+    let key3 = runtime
+        .block_on(oms3.get_recipient_spending_key(1, MicroTari::from(500)))
+        .unwrap();
+    assert_eq!(key1, key2);
+    assert_ne!(key1, key3);
+}
+
+#[test]
+fn utxo_query_split_into_chunks() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                base_node_query_chunk_size: 2,
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let mut outputs = HashMap::new();
+    for i in 0..5 {
+        let uo = UnblindedOutput::new(MicroTari::from(100 + i), PrivateKey::random(&mut OsRng), None);
+        runtime.block_on(oms.add_output(uo.clone())).unwrap();
+        let output = uo.as_transaction_output(&factories).unwrap();
+        outputs.insert(output.hash(), output);
+    }
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    outbound_service.wait_call_count(3, Duration::from_secs(60)).unwrap();
+    let requests = outbound_service
+        .take_calls()
+        .into_iter()
+        .filter_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            let request = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap();
+            match request.request {
+                Some(BaseNodeRequestProto::FetchUtxos(hashes)) => Some((request.request_key, hashes.outputs)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests.iter().map(|(_, h)| h.len()).collect::<Vec<_>>(), vec![2, 2, 1]);
+
+    let mut event_stream = oms.get_event_stream_fused();
+
+    // The base node doesn't return the output in the last request, which is answered first. Nothing is invalidated
+    // until the other requests have been answered as well.
+    for (i, (request_key, hashes)) in requests.iter().enumerate().rev() {
+        let returned = if i == 2 {
+            vec![]
+        } else {
+            hashes.iter().map(|h| outputs[h].clone().into()).collect()
+        };
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key: *request_key,
+            response: Some(BaseNodeResponseProto::TransactionOutputs(
+                BaseNodeProto::TransactionOutputs { outputs: returned },
+            )),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut response_keys = Vec::new();
+        let mut progress = Vec::new();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    match (*event).clone() {
+                        OutputManagerEvent::SyncProgress { validated, total } => progress.push((validated, total)),
+                        OutputManagerEvent::SyncCompleted { request_key: key, .. } => {
+                            response_keys.push(key);
+                            break;
+                        },
+                        _ => (),
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        // The query is identified by the key of its first request
+        assert_eq!(response_keys, vec![requests[0].0]);
+        // Progress is reported as the response to each request arrives
+        assert_eq!(progress, vec![(1, 5), (3, 5), (5, 5)]);
+    });
+
+    let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    assert_eq!(invalid_outputs.len(), 1);
+    let invalid_output = invalid_outputs[0].output.as_transaction_output(&factories).unwrap();
+    assert_eq!(invalid_output.hash(), requests[2].1[0]);
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), 4);
+
+    // The query and the output it invalidated are recorded in the sync history
+    let history = runtime.block_on(oms.get_sync_history(10)).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].request_key, requests[0].0);
+    assert_eq!(&history[0].base_node_public_key, base_node_identity.public_key());
+    assert_eq!(history[0].output_hashes.len(), 5);
+    assert_eq!(history[0].invalidated_hashes, vec![requests[2].1[0].clone()]);
+    assert_eq!(history[0].outcome, SyncOutcome::Completed);
+    assert!(history[0].response_time.is_some());
+    assert!(runtime.block_on(oms.get_sync_history(0)).unwrap().is_empty());
+}
+
+#[test]
+fn revalidate_invalid_outputs() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let output1 = UnblindedOutput::new(MicroTari::from(1000), PrivateKey::random(&mut OsRng), None);
+    let output2 = UnblindedOutput::new(MicroTari::from(2000), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(output1.clone())).unwrap();
+    runtime.block_on(oms.add_output(output2.clone())).unwrap();
+
+    // Nothing is queried while there are no invalid outputs
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58220".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.revalidate_invalid_outputs()).unwrap(), None);
+
+    // The base node returns neither output, so both are invalidated
+    let (_, body) = outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+    let bn_request: BaseNodeProto::BaseNodeServiceRequest = envelope_body
+        .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+        .unwrap()
+        .unwrap();
+    let event_stream = oms.get_event_stream_fused();
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key: bn_request.request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(
+            BaseNodeProto::TransactionOutputs { outputs: vec![] },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            event_stream.map(|i| (*i).clone()),
+            take = 4,
+            timeout = Duration::from_secs(30)
+        )
+    });
+    assert_eq!(result_stream[0], OutputManagerEvent::SyncProgress {
+        validated: 2,
+        total: 2
+    });
+    assert_eq!(result_stream[3], OutputManagerEvent::SyncCompleted {
+        request_key: bn_request.request_key,
+        num_invalidated: 2
+    });
+    assert_eq!(runtime.block_on(oms.get_invalid_outputs()).unwrap().len(), 2);
+
+    // After a reorg the base node returns the first output again, which restores it
+    let request_key = runtime.block_on(oms.revalidate_invalid_outputs()).unwrap().unwrap();
+    let (_, body) = outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+    let bn_request: BaseNodeProto::BaseNodeServiceRequest = envelope_body
+        .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(bn_request.request_key, request_key);
+    match bn_request.request {
+        Some(BaseNodeRequestProto::FetchUtxos(hashes)) => assert_eq!(hashes.outputs.len(), 2),
+        _ => assert!(false),
+    }
+
+    let event_stream = oms.get_event_stream_fused();
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(
+            BaseNodeProto::TransactionOutputs {
+                outputs: vec![output1.as_transaction_output(&factories).unwrap().into()],
+            },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            event_stream.map(|i| (*i).clone()),
+            take = 1,
+            timeout = Duration::from_secs(30)
+        )
+    });
+    assert_eq!(result_stream, vec![OutputManagerEvent::OutputsRevalidated((
+        request_key,
+        1
+    ))]);
+
+    let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    assert_eq!(invalid_outputs.len(), 1);
+    assert_eq!(invalid_outputs[0].output, output2);
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs, vec![output1]);
+}
+
+#[test]
+fn utxo_query_retries_with_backoff() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, _base_node_response_sender) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            base_node_query_timeout: Duration::from_secs(1),
+            base_node_query_max_retries: 1,
+            base_node_query_retry_backoff: Duration::from_secs(2),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let uo = UnblindedOutput::new(MicroTari::from(1000), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58219".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    // The first timeout schedules a retry after the backoff instead of querying again straight away
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            oms.get_event_stream_fused().map(|i| (*i).clone()),
+            take = 1,
+            timeout = Duration::from_secs(30)
+        )
+    });
+    match result_stream[0] {
+        OutputManagerEvent::BaseNodeSyncRequestTimedOut(_) => assert!(true),
+        _ => assert!(false),
+    }
+    assert_eq!(outbound_service.call_count(), 1);
+    let status = runtime.block_on(oms.get_base_node_sync_status()).unwrap();
+    assert_eq!(status.retry_count, 1);
+    assert_eq!(status.next_retry_delay, Some(Duration::from_secs(2)));
+    assert!(!status.failed);
+
+    // The retry times out as well, which uses up the retries
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            oms.get_event_stream_fused().map(|i| (*i).clone()),
+            take = 1,
+            timeout = Duration::from_secs(30)
+        )
+    });
+    match result_stream[0] {
+        OutputManagerEvent::BaseNodeSyncFailed(_) => assert!(true),
+        _ => assert!(false),
+    }
+    assert_eq!(outbound_service.call_count(), 2);
+    let status = runtime.block_on(oms.get_base_node_sync_status()).unwrap();
+    assert_eq!(status.retry_count, 1);
+    assert_eq!(status.next_retry_delay, None);
+    assert!(status.failed);
+
+    // Syncing again starts the retries over
+    runtime.block_on(oms.sync_with_base_node()).unwrap();
+    let status = runtime.block_on(oms.get_base_node_sync_status()).unwrap();
+    assert_eq!(status, BaseNodeSyncStatus::default());
+    assert_eq!(outbound_service.call_count(), 3);
+}
+
+fn sending_transaction_with_short_term_clear<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let available_balance = 10_000 * uT;
+    let (_ti, uo) = make_input(&mut OsRng.clone(), available_balance, &factories.commitment);
+    runtime.block_on(oms.add_output(uo.clone())).unwrap();
+
+    // Check that funds are encumbered and then unencumbered if the pending tx is not confirmed before restart
+    let _stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    let expected_change = balance.pending_incoming_balance;
+    assert_eq!(balance.pending_outgoing_balance, available_balance);
+
+    drop(oms);
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, available_balance);
+
+    // Check that a unconfirm Pending Transaction can be cancelled
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    let sender_tx_id = stp.get_tx_id().unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_outgoing_balance, available_balance);
+    runtime.block_on(oms.cancel_transaction(sender_tx_id)).unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, available_balance);
+
+    // Check that is the pending tx is confirmed that the encumberance persists after restart
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    let sender_tx_id = stp.get_tx_id().unwrap();
+    runtime.block_on(oms.confirm_pending_transaction(sender_tx_id)).unwrap();
+
+    drop(oms);
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_outgoing_balance, available_balance);
+
+    let tx = runtime.block_on(complete_transaction(stp, oms.clone()));
+
+    runtime
+        .block_on(oms.confirm_transaction(sender_tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, expected_change);
+}
+
+#[test]
+fn sending_transaction_with_short_term_clear_memory_db() {
+    sending_transaction_with_short_term_clear(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn sending_transaction_with_short_term_clear_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    sending_transaction_with_short_term_clear(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn coin_split_with_change<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let val1 = 6_000 * uT;
+    let val2 = 7_000 * uT;
+    let val3 = 8_000 * uT;
+    let (_ti, uo1) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
+    let (_ti, uo2) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
+    let (_ti, uo3) = make_input(&mut OsRng.clone(), val3, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo3)).is_ok());
+
+    let fee_per_gram = MicroTari::from(25);
+    let split_count = 8;
+    let (_tx_id, coin_split_tx, fee, amount) = runtime
+        .block_on(oms.create_coin_split(1000.into(), split_count, fee_per_gram, None))
+        .unwrap();
+    assert_eq!(coin_split_tx.body.inputs().len(), 2);
+    assert_eq!(coin_split_tx.body.outputs().len(), split_count + 1);
+    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 2, split_count + 1));
+    assert_eq!(amount, val1 + val2);
+}
+
+#[test]
+fn coin_split_with_change_memory_db() {
+    coin_split_with_change(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn coin_split_with_change_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    coin_split_with_change(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn coin_split_no_change<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let fee_per_gram = MicroTari::from(25);
+    let split_count = 15;
+    let fee = Fee::calculate(fee_per_gram, 1, 3, 15);
+    let val1 = 4_000 * uT;
+    let val2 = 5_000 * uT;
+    let val3 = 6_000 * uT + fee;
+    let (_ti, uo1) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
+    let (_ti, uo2) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
+    let (_ti, uo3) = make_input(&mut OsRng.clone(), val3, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo3)).is_ok());
+
+    let event_stream = oms.get_event_stream_fused();
+    let (_tx_id, coin_split_tx, fee, amount) = runtime
+        .block_on(oms.create_coin_split(1000.into(), split_count, fee_per_gram, None))
+        .unwrap();
+    assert_eq!(coin_split_tx.body.inputs().len(), 3);
+    assert_eq!(coin_split_tx.body.outputs().len(), split_count);
+    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 3, split_count));
+    assert_eq!(amount, val1 + val2 + val3);
+
+    // A progress event is published for the range proof of each output
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            event_stream.map(|i| (*i).clone()),
+            take = split_count,
+            timeout = Duration::from_secs(30)
+        )
+    });
+    let mut progress = result_stream
+        .iter()
+        .filter_map(|e| match e {
+            OutputManagerEvent::CoinSplitProgress(completed, total) => Some((*completed, *total)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    progress.sort();
+    assert_eq!(
+        progress,
+        (1..=split_count).map(|i| (i, split_count)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn coin_split_no_change_memory_db() {
+    coin_split_no_change(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn coin_split_no_change_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    coin_split_no_change(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn coin_join<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let fee_per_gram = MicroTari::from(25);
+    let (_ti, uo1) = make_input(&mut OsRng.clone(), 3_000 * uT, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
+    match runtime.block_on(oms.create_coin_join(3, fee_per_gram)) {
+        Err(OutputManagerError::NotEnoughOutputsToJoin) => assert!(true),
+        _ => assert!(false, "A single output can't be joined"),
+    }
+
+    let val1 = 2_000 * uT;
+    let val2 = 2_500 * uT;
+    let val3 = 8_000 * uT;
+    let (_ti, uo2) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
+    let (_ti, uo3) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
+    let (_ti, uo4) = make_input(&mut OsRng.clone(), val3, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo3)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo4)).is_ok());
+
+    let (_tx_id, coin_join_tx, fee, amount) = runtime.block_on(oms.create_coin_join(3, fee_per_gram)).unwrap();
+    assert_eq!(coin_join_tx.body.inputs().len(), 3);
+    assert_eq!(coin_join_tx.body.outputs().len(), 1);
+    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 3, 1));
+    assert_eq!(amount, val1 + val2 + 3_000 * uT);
+    let unspent = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent.len(), 1);
+    assert_eq!(unspent[0].value, val3);
+}
+
+#[test]
+fn coin_join_memory_db() {
+    coin_join(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn coin_join_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    coin_join(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn send_all<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let fee_per_gram = MicroTari::from(25);
+    match runtime.block_on(oms.prepare_transaction_to_send_all(fee_per_gram, "".to_string())) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "An empty wallet can't send all"),
+    }
+
+    let mut total = MicroTari::from(0);
+    for value in &[2_000 * uT, 3_000 * uT, 5_000 * uT] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), *value, &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+        total = total + *value;
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_all(fee_per_gram, "Send all".to_string()))
+        .unwrap();
+    let fee = Fee::calculate(fee_per_gram, 1, 3, 1);
+    assert_eq!(stp.get_fee_amount().unwrap(), fee);
+    assert_eq!(stp.get_total_amount().unwrap(), total - fee);
+    assert_eq!(stp.get_change_amount().unwrap(), MicroTari::from(0));
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_outgoing_balance, total);
+}
+
+#[test]
+fn send_all_memory_db() {
+    send_all(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn send_all_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    send_all(OutputManagerSqliteDatabase::new(connection));
+}
+
+#[test]
+fn dust_outputs_excluded_from_selection() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            dust_threshold: MicroTari::from(500),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+
+    let mut dust = Vec::new();
+    for value in &[300 * uT, 400 * uT, 5_000 * uT] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), *value, &factories.commitment);
+        runtime.block_on(oms.add_output(uo.clone())).unwrap();
+        if *value < MicroTari::from(500) {
+            dust.push(uo);
+        }
+    }
+    let mut dust_outputs = runtime.block_on(oms.get_dust_outputs()).unwrap();
+    dust_outputs.sort_by(|a, b| a.value.cmp(&b.value));
+    assert_eq!(dust_outputs, dust);
+
+    // The largest output alone can't cover the amount and the fee, and the dust is not used
+    let amount = MicroTari::from(4_950);
+    let fee_per_gram = MicroTari::from(5);
+    match runtime.block_on(oms.prepare_transaction_to_send(amount, fee_per_gram, None, "".to_string())) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "Dust outputs should not be selected"),
+    }
+
+    // Unless the transaction asks for it
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_with_options(
+            amount,
+            fee_per_gram,
+            None,
+            "".to_string(),
+            TransactionSendOptions::default().include_dust_outputs(),
+        ))
+        .unwrap();
+    assert_eq!(stp.get_total_amount().unwrap(), amount);
+    assert!(runtime.block_on(oms.get_dust_outputs()).unwrap().is_empty());
+}
+
+fn burn_transaction<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let val1 = 6_000 * uT;
+    let val2 = 7_000 * uT;
+    let (_ti, uo1) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
+    let (_ti, uo2) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
+    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
+    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
+
+    let fee_per_gram = MicroTari::from(25);
+    let (_tx_id, burn_tx, fee, amount) = runtime
+        .block_on(oms.create_burn_transaction(5_000 * uT, fee_per_gram, None))
+        .unwrap();
+    assert_eq!(amount, 5_000 * uT);
+    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 1, 1));
+    assert_eq!(burn_tx.body.inputs().len(), 1);
+    assert_eq!(burn_tx.body.outputs().len(), 1);
+    assert_eq!(burn_tx.body.kernels().len(), 1);
+    assert!(burn_tx.body.kernels()[0].is_burn());
+    assert_eq!(burn_tx.body.get_total_burned(), 5_000 * uT);
+    assert!(burn_tx.validate_internal_consistency(&factories, None).is_ok());
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, val2);
+    assert_eq!(balance.pending_incoming_balance, val1 - 5_000 * uT - fee);
+
+    assert!(runtime
+        .block_on(oms.create_burn_transaction(10_000 * uT, fee_per_gram, None))
+        .is_err());
+}
+
+#[test]
+fn burn_transaction_memory_db() {
+    burn_transaction(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn burn_transaction_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    burn_transaction(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn privacy_random_selection_does_not_mix_sources<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            base_node_query_timeout: Duration::from_secs(3),
+            utxo_selection_strategy: UTXOSelectionStrategy::PrivacyRandom {
+                avoid_mixing_sources: true,
+            },
+            ..Default::default()
+        },
+        backend.clone(),
+    );
+
+    let mut tags = HashMap::new();
+    for tag in &["alice", "bob"] {
+        for _ in 0..3 {
+            let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(1000), &factories.commitment);
+            backend
+                .write(WriteOperation::Insert(DbKeyValuePair::OutputSource(
+                    uo.spending_key.clone(),
+                    Box::new(OutputSource {
+                        spending_key: uo.spending_key.clone(),
+                        tag: tag.to_string(),
+                        timestamp: Utc::now().naive_utc(),
+                    }),
+                )))
+                .unwrap();
+            tags.insert(uo.spending_key.to_vec(), tag.to_string());
+            runtime.block_on(oms.add_output(uo)).unwrap();
+        }
+    }
+
+    // Each source holds 3000 uT so this can only be funded by mixing sources
+    match runtime.block_on(oms.prepare_transaction_to_send(
+        MicroTari::from(4000),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+    )) {
+        Err(OutputManagerError::NotEnoughFundsFromSingleSource) => assert!(true),
+        _ => assert!(false, "Selection should not mix output sources"),
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(2500), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    let sender_tx_id = stp.get_tx_id().unwrap();
+
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let spent = &pending_txs.get(&sender_tx_id).unwrap().outputs_to_be_spent;
+    assert_eq!(spent.len(), 3);
+    let spent_tags = spent
+        .iter()
+        .map(|o| tags.get(&o.spending_key.to_vec()).unwrap().clone())
+        .collect::<HashSet<_>>();
+    assert_eq!(spent_tags.len(), 1);
+}
+
+#[test]
+fn privacy_random_selection_does_not_mix_sources_memory_db() {
+    privacy_random_selection_does_not_mix_sources(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn privacy_random_selection_does_not_mix_sources_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    privacy_random_selection_does_not_mix_sources(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn send_restricted_to_source<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend.clone());
+
+    let mut customer_keys = HashSet::new();
+    for _ in 0..3 {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(1000), &factories.commitment);
+        backend
+            .write(WriteOperation::Insert(DbKeyValuePair::OutputSource(
+                uo.spending_key.clone(),
+                Box::new(OutputSource {
+                    spending_key: uo.spending_key.clone(),
+                    tag: "customer".to_string(),
+                    timestamp: Utc::now().naive_utc(),
+                }),
+            )))
+            .unwrap();
+        customer_keys.insert(uo.spending_key.to_vec());
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+    for _ in 0..5 {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(1000), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    match runtime.block_on(oms.prepare_transaction_to_send_with_options(
+        MicroTari::from(4000),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        TransactionSendOptions::default().restrict_to_source("customer"),
+    )) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "Selection should be restricted to the customer outputs"),
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_with_options(
+            MicroTari::from(2500),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            TransactionSendOptions::default().restrict_to_source("customer"),
+        ))
+        .unwrap();
+    let sender_tx_id = stp.get_tx_id().unwrap();
+
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let pending_tx = pending_txs.get(&sender_tx_id).unwrap();
+    assert_eq!(pending_tx.outputs_to_be_spent.len(), 3);
+    assert!(pending_tx
+        .outputs_to_be_spent
+        .iter()
+        .all(|o| customer_keys.contains(&o.spending_key.to_vec())));
+
+    // The change output inherits the source tag
+    assert_eq!(pending_tx.outputs_to_be_received.len(), 1);
+    let change_key = pending_tx.outputs_to_be_received[0].spending_key.clone();
+    if let DbValue::OutputSources(sources) = backend.fetch(&DbKey::OutputSources).unwrap().unwrap() {
+        let change_source = sources.iter().find(|s| s.spending_key == change_key).unwrap();
+        assert_eq!(change_source.tag, "customer".to_string());
+    } else {
+        assert!(false, "Incorrect DbValue");
+    }
+}
+
+#[test]
+fn send_restricted_to_source_memory_db() {
+    send_restricted_to_source(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn send_restricted_to_source_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    send_restricted_to_source(OutputManagerSqliteDatabase::new(connection));
+}
+
+#[test]
+fn send_with_per_transaction_utxo_selection_strategy() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    let fee_per_gram = MicroTari::from(20);
+    for value in &[1000, 2000, 5000, 10000] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(*value), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_with_options(
+            MicroTari::from(3000),
+            fee_per_gram,
+            None,
+            "".to_string(),
+            TransactionSendOptions::default().with_utxo_selection_strategy(UTXOSelectionStrategy::Largest),
+        ))
+        .unwrap();
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let pending_tx = pending_txs.get(&stp.get_tx_id().unwrap()).unwrap();
+    assert_eq!(pending_tx.outputs_to_be_spent.len(), 1);
+    assert_eq!(pending_tx.outputs_to_be_spent[0].value, MicroTari::from(10000));
+
+    // The 1000 and 2000 outputs cover this amount and the fee exactly, so no change output is required
+    let amount = MicroTari::from(3000) - Fee::calculate(fee_per_gram, 1, 2, 1);
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_with_options(
+            amount,
+            fee_per_gram,
+            None,
+            "".to_string(),
+            TransactionSendOptions::default().with_utxo_selection_strategy(UTXOSelectionStrategy::BranchAndBound),
+        ))
+        .unwrap();
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let pending_tx = pending_txs.get(&stp.get_tx_id().unwrap()).unwrap();
+    let mut spent = pending_tx
+        .outputs_to_be_spent
+        .iter()
+        .map(|o| o.value)
+        .collect::<Vec<_>>();
+    spent.sort();
+    assert_eq!(spent, vec![MicroTari::from(1000), MicroTari::from(2000)]);
+    assert!(pending_tx.outputs_to_be_received.is_empty());
+}
+
+#[test]
+fn send_with_privacy_first_utxo_selection() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let mut event_stream = oms.get_event_stream_fused();
+
+    let fee_per_gram = MicroTari::from(20);
+    for value in &[1000, 1000, 1000, 1000, 1000, 2000, 5000, 10000] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(*value), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+    let mut send = |amount: u64| {
+        let stp = runtime
+            .block_on(oms.prepare_transaction_to_send_with_options(
+                MicroTari::from(amount),
+                fee_per_gram,
+                None,
+                "".to_string(),
+                TransactionSendOptions::default().with_utxo_selection_strategy(UTXOSelectionStrategy::PrivacyFirst),
+            ))
+            .unwrap();
+        let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+        let mut spent = pending_txs
+            .get(&stp.get_tx_id().unwrap())
+            .unwrap()
+            .outputs_to_be_spent
+            .iter()
+            .map(|o| o.value)
+            .collect::<Vec<_>>();
+        spent.sort();
+        spent
+    };
+
+    // The smallest single output that covers the amount is preferred over combining smaller outputs
+    assert_eq!(send(3000), vec![MicroTari::from(5000)]);
+    // No single output covers this amount, so the pair with the smallest total is used
+    assert_eq!(send(10000), vec![MicroTari::from(1000), MicroTari::from(10000)]);
+
+    // No pair of the remaining outputs covers this amount, so more inputs are linked and a warning is published
+    assert_eq!(send(3000), vec![
+        MicroTari::from(1000),
+        MicroTari::from(1000),
+        MicroTari::from(2000)
+    ]);
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::WouldLinkManyInputs(inputs) = (*event).clone() {
+                        assert_eq!(inputs, 3);
+                        break;
+                    }
+                },
+                () = delay => panic!("No WouldLinkManyInputs event was published"),
+            }
+        }
+    });
+}
+
+#[test]
+fn test_funds_matured_on_chain_tip_update() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                chain_tip_poll_interval: Duration::from_secs(1),
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let coinbase = UnblindedOutput::new(
+        MicroTari::from(5000),
+        PrivateKey::random(&mut OsRng),
+        Some(OutputFeatures::create_coinbase(10)),
+    );
+    runtime.block_on(oms.add_output(coinbase)).unwrap();
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    let mut event_stream = oms.get_event_stream_fused();
+
+    // Wait for the next chain tip query, skipping the UTXO query sent when the base node was set
+    let next_chain_tip_query_key = || loop {
+        outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+        let request_key = outbound_service.take_calls().into_iter().find_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            let request = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap();
+            match request.request {
+                Some(BaseNodeRequestProto::GetChainMetadata(_)) => Some(request.request_key),
+                _ => None,
+            }
+        });
+        if let Some(key) = request_key {
+            return key;
+        }
+    };
+
+    // The first height only establishes a baseline, the second one passes the maturity of the coinbase
+    for height in &[5u64, 12] {
+        if *height == 12 {
+            // Before the coinbase matures the funds are reported as pending maturity rather than missing
+            let mut balance = runtime.block_on(oms.get_balance()).unwrap();
+            for _ in 0..30 {
+                if balance.available_balance == MicroTari::from(0) {
+                    break;
+                }
+                runtime.block_on(delay_for(Duration::from_secs(1)));
+                balance = runtime.block_on(oms.get_balance()).unwrap();
+            }
+            assert_eq!(balance.available_balance, MicroTari::from(0));
+            match runtime.block_on(oms.prepare_transaction_to_send(
+                MicroTari::from(1000),
+                MicroTari::from(10),
+                None,
+                "".to_string(),
+            )) {
+                Err(OutputManagerError::FundsPendingMaturity) => {},
+                r => panic!("Expected the funds to be pending maturity, got {:?}", r.map(|_| ())),
+            }
+            match runtime.block_on(oms.prepare_transaction_to_send(
+                MicroTari::from(10_000),
+                MicroTari::from(10),
+                None,
+                "".to_string(),
+            )) {
+                Err(OutputManagerError::NotEnoughFunds) => {},
+                r => panic!("Expected not enough funds, got {:?}", r.map(|_| ())),
+            }
+        }
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key: next_chain_tip_query_key(),
+            response: Some(BaseNodeResponseProto::ChainMetadata(BaseNodeProto::ChainMetadata {
+                height_of_longest_chain: Some(*height),
+                ..Default::default()
+            })),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut matured = None;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::FundsMatured(value) = (*event).clone() {
+                        matured = Some(value);
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(matured, Some(MicroTari::from(5000)));
+    });
+}
+
+#[test]
+fn pending_coinbases_invalidated_when_block_not_won() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                chain_tip_poll_interval: Duration::from_secs(1),
+                coinbase_lock_height: 60,
+                coinbase_expiry_blocks: 10,
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    runtime
+        .block_on(oms.get_coinbase_spending_key(1, MicroTari::from(5000), 80))
+        .unwrap();
+    runtime
+        .block_on(oms.get_coinbase_spending_key(2, MicroTari::from(6000), 90))
+        .unwrap();
+
+    let pending = runtime.block_on(oms.get_pending_coinbases()).unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(
+        (pending[0].tx_id, pending[0].block_height, pending[0].maturity_height),
+        (1, 20, 80)
+    );
+    assert_eq!(
+        (pending[1].tx_id, pending[1].block_height, pending[1].maturity_height),
+        (2, 30, 90)
+    );
+    assert!(pending.iter().all(|c| c.estimated_spendable_time.is_none()));
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58227".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    let mut event_stream = oms.get_event_stream_fused();
+
+    let next_chain_tip_query_key = || loop {
+        outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+        let request_key = outbound_service.take_calls().into_iter().find_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            let request = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap();
+            match request.request {
+                Some(BaseNodeRequestProto::GetChainMetadata(_)) => Some(request.request_key),
+                _ => None,
+            }
+        });
+        if let Some(key) = request_key {
+            return key;
+        }
+    };
+
+    // Only the coinbase for block 20 has expired by height 32
+    for height in &[25u64, 32] {
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key: next_chain_tip_query_key(),
+            response: Some(BaseNodeResponseProto::ChainMetadata(BaseNodeProto::ChainMetadata {
+                height_of_longest_chain: Some(*height),
+                ..Default::default()
+            })),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut invalidated = Vec::new();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::CoinbaseInvalidated(tx_id) = (*event).clone() {
+                        invalidated.push(tx_id);
+                        break;
+                    }
+                },
+                () = delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(invalidated, vec![1]);
+    });
+
+    let pending = runtime.block_on(oms.get_pending_coinbases()).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].tx_id, 2);
+    assert!(pending[0].estimated_spendable_time.is_some());
+}
+
+#[test]
+fn test_validation_on_new_chain_tip() {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service_with_config(
+            &mut runtime,
+            OutputManagerServiceConfig {
+                base_node_query_timeout: Duration::from_secs(60),
+                chain_tip_poll_interval: Duration::from_secs(1),
+                validation_min_interval: Duration::from_secs(0),
+                ..Default::default()
+            },
+            OutputManagerMemoryDatabase::new(),
+        );
+    let uo = UnblindedOutput::new(MicroTari::from(5000), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58221".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    // Wait for the next chain tip query and report whether the outputs were validated before it
+    let next_chain_tip_query = || {
+        let mut validated = false;
+        loop {
+            outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+            let mut chain_tip_query_key = None;
+            for (_, body) in outbound_service.take_calls() {
+                let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+                let request = envelope_body
+                    .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                    .unwrap()
+                    .unwrap();
+                match request.request {
+                    Some(BaseNodeRequestProto::GetChainMetadata(_)) => chain_tip_query_key = Some(request.request_key),
+                    Some(BaseNodeRequestProto::FetchUtxos(_)) => validated = true,
+                    _ => (),
+                }
+            }
+            if let Some(key) = chain_tip_query_key {
+                return (key, validated);
+            }
+        }
+    };
+
+    // The outputs are validated when the base node is set
+    let (mut request_key, validated) = next_chain_tip_query();
+    assert!(validated);
+
+    // Only a new tip triggers another validation
+    for (height, expect_validation) in &[(5u64, true), (5, false), (6, true)] {
+        let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+            request_key,
+            response: Some(BaseNodeResponseProto::ChainMetadata(BaseNodeProto::ChainMetadata {
+                height_of_longest_chain: Some(*height),
+                ..Default::default()
+            })),
+        };
+        runtime
+            .block_on(base_node_response_sender.send(create_dummy_message(
+                base_node_response,
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+        let (key, validated) = next_chain_tip_query();
+        assert_eq!(
+            validated, *expect_validation,
+            "Unexpected validation at height {}",
+            height
+        );
+        request_key = key;
+    }
+}
+
+#[test]
+fn send_with_pinned_inputs() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    let mut outputs = Vec::new();
+    for value in &[1000, 2000, 5000] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(*value), &factories.commitment);
+        runtime.block_on(oms.add_output(uo.clone())).unwrap();
+        outputs.push(uo);
+    }
+    let commitments = outputs
+        .iter()
+        .map(|o| factories.commitment.commit_value(&o.spending_key, o.value.into()))
+        .collect::<Vec<_>>();
+
+    let unknown = factories.commitment.commit_value(&PrivateKey::random(&mut OsRng), 3000);
+    match runtime.block_on(oms.prepare_transaction_to_send_with_inputs(
+        MicroTari::from(500),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        vec![commitments[0].clone(), unknown],
+    )) {
+        Err(OutputManagerError::InputNotFound(_)) => assert!(true),
+        _ => assert!(false, "An input that is not an unspent output should be rejected"),
+    }
+
+    match runtime.block_on(oms.prepare_transaction_to_send_with_inputs(
+        MicroTari::from(500),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        vec![commitments[0].clone(), commitments[0].clone()],
+    )) {
+        Err(OutputManagerError::DuplicateInput) => assert!(true),
+        _ => assert!(false, "A duplicated input should be rejected"),
+    }
+
+    match runtime.block_on(oms.prepare_transaction_to_send_with_inputs(
+        MicroTari::from(2500),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        vec![commitments[0].clone()],
+    )) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "Pinned inputs that do not cover the amount should be rejected"),
+    }
+
+    // Automatic selection would pick the 5000 output, the pinned inputs must be used instead
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send_with_inputs(
+            MicroTari::from(2500),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            vec![commitments[0].clone(), commitments[1].clone()],
+        ))
+        .unwrap();
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let pending_tx = pending_txs.get(&stp.get_tx_id().unwrap()).unwrap();
+    let mut spent = pending_tx
+        .outputs_to_be_spent
+        .iter()
+        .map(|o| o.spending_key.to_vec())
+        .collect::<Vec<_>>();
+    spent.sort();
+    let mut expected = vec![outputs[0].spending_key.to_vec(), outputs[1].spending_key.to_vec()];
+    expected.sort();
+    assert_eq!(spent, expected);
+
+    // The pinned outputs are now encumbered and can't be pinned again
+    match runtime.block_on(oms.prepare_transaction_to_send_with_inputs(
+        MicroTari::from(500),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        vec![commitments[1].clone()],
+    )) {
+        Err(OutputManagerError::InputNotFound(_)) => assert!(true),
+        _ => assert!(false, "An encumbered input should be rejected"),
+    }
+}
+
+#[test]
+fn estimate_fee_without_encumbering() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    let mut outputs = Vec::new();
+    for value in &[1000, 2000, 5000] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(*value), &factories.commitment);
+        runtime.block_on(oms.add_output(uo.clone())).unwrap();
+        outputs.push(uo);
+    }
+
+    match runtime.block_on(oms.estimate_fee(MicroTari::from(10_000), MicroTari::from(20), 1)) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "An amount larger than the balance can't be estimated"),
+    }
+
+    let (fee, inputs) = runtime
+        .block_on(oms.estimate_fee(MicroTari::from(1500), MicroTari::from(20), 1))
+        .unwrap();
+    assert_eq!(fee, Fee::calculate(MicroTari::from(20), 1, 2, 2));
+    let mut selected = inputs.iter().map(|o| o.spending_key.to_vec()).collect::<Vec<_>>();
+    selected.sort();
+    let mut expected = vec![outputs[0].spending_key.to_vec(), outputs[1].spending_key.to_vec()];
+    expected.sort();
+    assert_eq!(selected, expected);
+
+    // Nothing is encumbered by an estimate
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 3);
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1500), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    assert_eq!(stp.get_fee_amount().unwrap(), fee);
+}
+
+#[test]
+fn change_split_into_several_outputs() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            num_change_outputs: 3,
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(100_000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    let (estimated_fee, _) = runtime
+        .block_on(oms.estimate_fee(MicroTari::from(10_000), MicroTari::from(20), 1))
+        .unwrap();
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(10_000), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+    let fee = stp.get_fee_amount().unwrap();
+    assert_eq!(fee, Fee::calculate(MicroTari::from(20), 1, 1, 4));
+    assert_eq!(fee, estimated_fee);
+
+    let pending = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let change_outputs = &pending.get(&stp.get_tx_id().unwrap()).unwrap().outputs_to_be_received;
+    assert_eq!(change_outputs.len(), 3);
+    assert_eq!(
+        change_outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value),
+        MicroTari::from(100_000) - MicroTari::from(10_000) - fee
+    );
+    let mut keys = change_outputs
+        .iter()
+        .map(|o| o.spending_key.to_vec())
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), 3);
+}
+
+#[test]
+fn reserve_and_release_outputs() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+
+    for value in &[1000, 2000, 5000] {
+        let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(*value), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    match runtime.block_on(oms.reserve_outputs(MicroTari::from(10_000), MicroTari::from(20))) {
+        Err(OutputManagerError::NotEnoughFunds) => assert!(true),
+        _ => assert!(false, "An amount larger than the balance can't be reserved"),
+    }
+
+    let (reservation_id, reserved) = runtime
+        .block_on(oms.reserve_outputs(MicroTari::from(1500), MicroTari::from(20)))
+        .unwrap();
+    let total = reserved.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+    assert!(total >= MicroTari::from(1500) + Fee::calculate(MicroTari::from(20), 1, reserved.len(), 1));
+
+    // The reserved outputs can't be selected again until they are released
+    let pending = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    assert_eq!(
+        pending.get(&reservation_id).unwrap().outputs_to_be_spent.len(),
+        reserved.len()
+    );
+    assert_eq!(
+        runtime.block_on(oms.get_unspent_outputs()).unwrap().len(),
+        3 - reserved.len()
+    );
+
+    runtime.block_on(oms.release_reservation(reservation_id)).unwrap();
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 3);
+
+    match runtime.block_on(oms.release_reservation(reservation_id)) {
+        Err(OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound(_))) => {
+            assert!(true)
+        },
+        _ => assert!(false, "A released reservation can't be released again"),
+    }
+}
+
+#[test]
+fn fee_per_gram_below_minimum() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            min_fee_per_gram: MicroTari::from(20),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(5000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    match runtime.block_on(oms.prepare_transaction_to_send(
+        MicroTari::from(1000),
+        MicroTari::from(19),
+        None,
+        "".to_string(),
+    )) {
+        Err(OutputManagerError::FeePerGramBelowMinimum) => assert!(true),
+        _ => assert!(false, "A fee per gram below the minimum must be rejected"),
+    }
+    match runtime.block_on(oms.estimate_fee(MicroTari::from(1000), MicroTari::from(19), 1)) {
+        Err(OutputManagerError::FeePerGramBelowMinimum) => assert!(true),
+        _ => assert!(false, "A fee per gram below the minimum must be rejected"),
+    }
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert!(runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
+        .is_ok());
+}
+
+#[test]
+fn zero_fee_per_gram_allowed_without_minimum() {
     let factories = CryptoFactories::default();
-    let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+    let mut runtime = Runtime::new().unwrap();
 
-    let available_balance = 10_000 * uT;
-    let (_ti, uo) = make_input(&mut OsRng.clone(), available_balance, &factories.commitment);
-    runtime.block_on(oms.add_output(uo.clone())).unwrap();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            min_fee_per_gram: MicroTari::from(0),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let (_ti, uo1) = make_input(&mut OsRng.clone(), MicroTari::from(2000), &factories.commitment);
+    let (_ti, uo2) = make_input(&mut OsRng.clone(), MicroTari::from(3000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo1)).unwrap();
+    runtime.block_on(oms.add_output(uo2)).unwrap();
+
+    let (_tx_id, tx, fee, amount) = runtime.block_on(oms.create_coin_join(2, MicroTari::from(0))).unwrap();
+    assert_eq!(fee, MicroTari::from(0));
+    assert_eq!(tx.body.get_total_fee(), MicroTari::from(0));
+    assert_eq!(amount, MicroTari::from(5000));
+    assert!(tx.validate_internal_consistency(&factories, None).is_ok());
+}
 
-    // Check that funds are encumbered and then unencumbered if the pending tx is not confirmed before restart
-    let _stp = runtime
-        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
-        .unwrap();
+/// A signer that hands out a fixed list of keys and keeps its master key to itself
+struct FixedKeyProvider {
+    keys: Vec<PrivateKey>,
+    requested_branches: Arc<Mutex<Vec<KeyManagerBranch>>>,
+}
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    let expected_change = balance.pending_incoming_balance;
-    assert_eq!(balance.pending_outgoing_balance, available_balance);
+impl SecretKeyProvider for FixedKeyProvider {
+    fn next_key(&mut self, branch: KeyManagerBranch) -> Result<PrivateKey, OutputManagerError> {
+        self.requested_branches.lock().unwrap().push(branch);
+        self.keys
+            .pop()
+            .ok_or(OutputManagerError::KeyManagerBranchNotInitialized)
+    }
 
-    drop(oms);
-    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+    fn master_key(&self) -> Option<PrivateKey> {
+        None
+    }
+}
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    assert_eq!(balance.available_balance, available_balance);
+#[test]
+fn keys_are_taken_from_secret_key_provider() {
+    let mut runtime = Runtime::new().unwrap();
+    let factories = CryptoFactories::default();
 
-    // Check that a unconfirm Pending Transaction can be cancelled
-    let stp = runtime
-        .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
-        .unwrap();
-    let sender_tx_id = stp.get_tx_id().unwrap();
+    let keys = (0..3).map(|_| PrivateKey::random(&mut OsRng)).collect::<Vec<_>>();
+    let requested_branches = Arc::new(Mutex::new(Vec::new()));
+    let key_provider = FixedKeyProvider {
+        keys: keys.clone(),
+        requested_branches: requested_branches.clone(),
+    };
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    assert_eq!(balance.pending_outgoing_balance, available_balance);
-    runtime.block_on(oms.cancel_transaction(sender_tx_id)).unwrap();
+    let (outbound_message_requester, _) = create_outbound_service_mock(20);
+    let (oms_request_sender, oms_request_receiver) = reply_channel::unbounded();
+    let (_base_node_response_sender, base_node_response_receiver) =
+        mpsc::channel::<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>(20);
+    let (oms_event_publisher, oms_event_subscriber) = bounded(100);
+    let output_manager_service = runtime
+        .block_on(OutputManagerService::new(
+            OutputManagerServiceConfig::default(),
+            outbound_message_requester,
+            oms_request_receiver,
+            base_node_response_receiver,
+            OutputManagerDatabase::new(OutputManagerMemoryDatabase::new()),
+            oms_event_publisher,
+            factories.clone(),
+        ))
+        .unwrap()
+        .with_secret_key_provider(Box::new(key_provider));
+    let mut oms = OutputManagerHandle::new(oms_request_sender, oms_event_subscriber);
+    runtime.spawn(async move { output_manager_service.start().await.unwrap() });
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    assert_eq!(balance.available_balance, available_balance);
+    let recipient_key = runtime
+        .block_on(oms.get_recipient_spending_key(1, MicroTari::from(5000)))
+        .unwrap();
+    assert_eq!(recipient_key, keys[2]);
+    let coinbase_key = runtime
+        .block_on(oms.get_coinbase_spending_key(2, MicroTari::from(5000), 10))
+        .unwrap();
+    assert_eq!(coinbase_key, keys[1]);
 
-    // Check that is the pending tx is confirmed that the encumberance persists after restart
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(10_000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
     let stp = runtime
         .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
         .unwrap();
-    let sender_tx_id = stp.get_tx_id().unwrap();
-    runtime.block_on(oms.confirm_pending_transaction(sender_tx_id)).unwrap();
+    let pending_txs = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let change_output = &pending_txs
+        .get(&stp.get_tx_id().unwrap())
+        .unwrap()
+        .outputs_to_be_received[0];
+    assert_eq!(change_output.spending_key, keys[0]);
+
+    assert_eq!(*requested_branches.lock().unwrap(), vec![
+        KeyManagerBranch::Spending,
+        KeyManagerBranch::Coinbase,
+        KeyManagerBranch::Spending,
+    ]);
+
+    // The seed words cannot be shown without the master key
+    match runtime.block_on(oms.get_seed_words(None)) {
+        Err(OutputManagerError::MasterKeyNotAvailable) => {},
+        r => panic!("Expected the master key to be unavailable, got {:?}", r),
+    }
+}
 
-    drop(oms);
-    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
+#[test]
+fn export_and_import_outputs() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    assert_eq!(balance.pending_outgoing_balance, available_balance);
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let mut hashes = HashSet::new();
+    for value in &[1000, 2000] {
+        let features = OutputFeatures::with_maturity(*value);
+        let uo = UnblindedOutput::new(MicroTari::from(*value), PrivateKey::random(&mut OsRng), Some(features));
+        hashes.insert(uo.as_transaction_output(&factories).unwrap().hash());
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+    let export = runtime.block_on(oms.export_outputs("passphrase".to_string())).unwrap();
 
-    let tx = runtime.block_on(complete_transaction(stp, oms.clone()));
+    let (mut oms2, outbound_service, _shutdown2, mut base_node_response_sender) =
+        setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    match runtime.block_on(oms2.import_outputs(export.clone(), "wrong passphrase".to_string())) {
+        Err(OutputManagerError::EncryptionError(_)) => {},
+        r => panic!("Expected the import to fail with the wrong passphrase, got {:?}", r),
+    }
 
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
     runtime
-        .block_on(oms.confirm_transaction(sender_tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .block_on(oms2.set_base_node_public_key(base_node_identity.public_key().clone()))
         .unwrap();
+    let _ = outbound_service.take_calls();
 
-    let balance = runtime.block_on(oms.get_balance()).unwrap();
-    assert_eq!(balance.available_balance, expected_change);
-}
-
-#[test]
-fn sending_transaction_with_short_term_clear_memory_db() {
-    sending_transaction_with_short_term_clear(OutputManagerMemoryDatabase::new());
-}
+    assert_eq!(
+        runtime
+            .block_on(oms2.import_outputs(export.clone(), "passphrase".to_string()))
+            .unwrap(),
+        2
+    );
+    let balance = runtime.block_on(oms2.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(3000));
+    let imported = runtime.block_on(oms2.get_unspent_outputs()).unwrap();
+    assert!(imported.iter().all(|o| o.features.maturity == u64::from(o.value)));
+
+    // The imported outputs are queried from the base node straight away
+    outbound_service.wait_call_count(1, Duration::from_secs(60)).unwrap();
+    let (request_keys, queried): (Vec<_>, Vec<_>) = outbound_service
+        .take_calls()
+        .into_iter()
+        .filter_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.to_vec().as_slice()).unwrap();
+            let request = envelope_body
+                .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+                .unwrap()
+                .unwrap();
+            match request.request {
+                Some(BaseNodeRequestProto::FetchUtxos(hashes)) => Some((request.request_key, hashes.outputs)),
+                _ => None,
+            }
+        })
+        .unzip();
+    assert_eq!(request_keys.len(), 1);
+    assert_eq!(queried.into_iter().flatten().collect::<HashSet<_>>(), hashes);
 
-#[test]
-fn sending_transaction_with_short_term_clear_sqlite_db() {
-    let db_name = format!("{}.sqlite3", random_string(8).as_str());
-    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
-    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
-    let db_path = format!("{}/{}", db_folder, db_name);
-    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+    // An imported output that the base node does not return is invalidated as a failed import
+    let returned = imported.iter().find(|o| o.value == MicroTari::from(1000)).unwrap();
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key: request_keys[0],
+        response: Some(BaseNodeResponseProto::TransactionOutputs(
+            BaseNodeProto::TransactionOutputs {
+                outputs: vec![returned.as_transaction_output(&factories).unwrap().into()].into(),
+            },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    let mut invalid_outputs = runtime.block_on(oms2.get_invalid_outputs()).unwrap();
+    for _ in 0..30 {
+        if !invalid_outputs.is_empty() {
+            break;
+        }
+        runtime.block_on(delay_for(Duration::from_secs(1)));
+        invalid_outputs = runtime.block_on(oms2.get_invalid_outputs()).unwrap();
+    }
+    assert_eq!(invalid_outputs.len(), 1);
+    assert_eq!(invalid_outputs[0].output.value, MicroTari::from(2000));
+    match invalid_outputs[0].reason.as_ref() {
+        Some(InvalidationReason::ImportFailed { request_key, .. }) => assert_eq!(*request_key, request_keys[0]),
+        r => panic!("Unexpected invalidation reason: {:?}", r),
+    }
 
-    sending_transaction_with_short_term_clear(OutputManagerSqliteDatabase::new(connection));
+    // Importing the same outputs again adds nothing
+    assert_eq!(
+        runtime
+            .block_on(oms2.import_outputs(export, "passphrase".to_string()))
+            .unwrap(),
+        0
+    );
 }
 
-fn coin_split_with_change<T: Clone + OutputManagerBackend + 'static>(backend: T) {
-    let factories = CryptoFactories::default();
+#[test]
+fn base_node_query_timeout_tuned_to_latency() {
     let mut runtime = Runtime::new().unwrap();
-    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
 
-    let val1 = 6_000 * uT;
-    let val2 = 7_000 * uT;
-    let val3 = 8_000 * uT;
-    let (_ti, uo1) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
-    let (_ti, uo2) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
-    let (_ti, uo3) = make_input(&mut OsRng.clone(), val3, &factories.commitment);
-    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
-    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
-    assert!(runtime.block_on(oms.add_output(uo3)).is_ok());
+    let (mut oms, _outbound_service, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            base_node_query_timeout: Duration::from_secs(10),
+            base_node_query_timeout_latency_factor: 4,
+            min_base_node_query_timeout: Duration::from_secs(2),
+            max_base_node_query_timeout: Duration::from_secs(20),
+            ..Default::default()
+        },
+        OutputManagerMemoryDatabase::new(),
+    );
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58218".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    let other_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58219".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_query_timeout()).unwrap(),
+        Duration::from_secs(10)
+    );
 
-    let fee_per_gram = MicroTari::from(25);
-    let split_count = 8;
-    let (_tx_id, coin_split_tx, fee, amount) = runtime
-        .block_on(oms.create_coin_split(1000.into(), split_count, fee_per_gram, None))
+    // The timeout follows the average base node latency
+    runtime
+        .block_on(oms.report_peer_latency(base_node_identity.node_id().clone(), Duration::from_secs(1)))
         .unwrap();
-    assert_eq!(coin_split_tx.body.inputs().len(), 2);
-    assert_eq!(coin_split_tx.body.outputs().len(), split_count + 1);
-    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 2, split_count + 1));
-    assert_eq!(amount, val1 + val2);
-}
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_query_timeout()).unwrap(),
+        Duration::from_secs(4)
+    );
+    runtime
+        .block_on(oms.report_peer_latency(other_identity.node_id().clone(), Duration::from_secs(30)))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_query_timeout()).unwrap(),
+        Duration::from_secs(4)
+    );
+    runtime
+        .block_on(oms.report_peer_latency(base_node_identity.node_id().clone(), Duration::from_secs(14)))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_query_timeout()).unwrap(),
+        Duration::from_secs(20)
+    );
 
-#[test]
-fn coin_split_with_change_memory_db() {
-    coin_split_with_change(OutputManagerMemoryDatabase::new());
+    // A timeout set at runtime is kept
+    runtime
+        .block_on(oms.set_base_node_query_timeout(Duration::from_secs(7)))
+        .unwrap();
+    runtime
+        .block_on(oms.report_peer_latency(base_node_identity.node_id().clone(), Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_base_node_query_timeout()).unwrap(),
+        Duration::from_secs(7)
+    );
 }
 
 #[test]
-fn coin_split_with_change_sqlite_db() {
-    let db_name = format!("{}.sqlite3", random_string(8).as_str());
-    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
-    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
-    let db_path = format!("{}/{}", db_folder, db_name);
-    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
-
-    coin_split_with_change(OutputManagerSqliteDatabase::new(connection));
-}
-
-fn coin_split_no_change<T: Clone + OutputManagerBackend + 'static>(backend: T) {
+fn read_only_output_manager_service() {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
-    let (mut oms, _, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
 
-    let fee_per_gram = MicroTari::from(25);
-    let split_count = 15;
-    let fee = Fee::calculate(fee_per_gram, 1, 3, 15);
-    let val1 = 4_000 * uT;
-    let val2 = 5_000 * uT;
-    let val3 = 6_000 * uT + fee;
-    let (_ti, uo1) = make_input(&mut OsRng.clone(), val1, &factories.commitment);
-    let (_ti, uo2) = make_input(&mut OsRng.clone(), val2, &factories.commitment);
-    let (_ti, uo3) = make_input(&mut OsRng.clone(), val3, &factories.commitment);
-    assert!(runtime.block_on(oms.add_output(uo1)).is_ok());
-    assert!(runtime.block_on(oms.add_output(uo2)).is_ok());
-    assert!(runtime.block_on(oms.add_output(uo3)).is_ok());
+    let backend = OutputManagerMemoryDatabase::new();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service_with_config(
+        &mut runtime,
+        OutputManagerServiceConfig {
+            read_only: true,
+            ..Default::default()
+        },
+        backend.clone(),
+    );
 
-    let (_tx_id, coin_split_tx, fee, amount) = runtime
-        .block_on(oms.create_coin_split(1000.into(), split_count, fee_per_gram, None))
-        .unwrap();
-    assert_eq!(coin_split_tx.body.inputs().len(), 3);
-    assert_eq!(coin_split_tx.body.outputs().len(), split_count);
-    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 3, split_count));
-    assert_eq!(amount, val1 + val2 + val3);
+    // No master seed is created for a read-only wallet
+    let db = OutputManagerDatabase::new(backend);
+    assert!(runtime.block_on(db.get_key_manager_state()).unwrap().is_none());
+
+    // Outputs can still be tracked and the balance reported
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(10000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(10000)
+    );
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 1);
+
+    // Nothing that needs the master seed can be done
+    match runtime.block_on(oms.get_recipient_spending_key(1, MicroTari::from(5000))) {
+        Err(OutputManagerError::ReadOnlyWallet) => (),
+        r => panic!("Expected a ReadOnlyWallet error, got {:?}", r),
+    }
+    match runtime.block_on(oms.prepare_transaction_to_send(
+        MicroTari::from(5000),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+    )) {
+        Err(OutputManagerError::ReadOnlyWallet) => (),
+        _ => panic!("Expected a ReadOnlyWallet error"),
+    }
+    match runtime.block_on(oms.get_seed_words(None)) {
+        Err(OutputManagerError::ReadOnlyWallet) => (),
+        r => panic!("Expected a ReadOnlyWallet error, got {:?}", r),
+    }
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().pending_outgoing_balance,
+        MicroTari::from(0)
+    );
 }
 
-#[test]
-fn coin_split_no_change_memory_db() {
-    coin_split_no_change(OutputManagerMemoryDatabase::new());
+/// Charges the same fee for every transaction regardless of its size
+#[derive(Debug)]
+struct FlatFeeModel(MicroTari);
+
+impl FeeModel for FlatFeeModel {
+    fn calculate(&self, _: MicroTari, _: usize, _: usize, _: usize) -> MicroTari {
+        self.0
+    }
 }
 
 #[test]
-fn coin_split_no_change_sqlite_db() {
-    let db_name = format!("{}.sqlite3", random_string(8).as_str());
-    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
-    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
-    let db_path = format!("{}/{}", db_folder, db_name);
-    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+fn fees_are_calculated_with_fee_model() {
+    let mut runtime = Runtime::new().unwrap();
+    let factories = CryptoFactories::default();
 
-    coin_split_no_change(OutputManagerSqliteDatabase::new(connection));
+    let (outbound_message_requester, _) = create_outbound_service_mock(20);
+    let (oms_request_sender, oms_request_receiver) = reply_channel::unbounded();
+    let (_base_node_response_sender, base_node_response_receiver) =
+        mpsc::channel::<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>(20);
+    let (oms_event_publisher, oms_event_subscriber) = bounded(100);
+    let output_manager_service = runtime
+        .block_on(OutputManagerService::new(
+            OutputManagerServiceConfig::default(),
+            outbound_message_requester,
+            oms_request_receiver,
+            base_node_response_receiver,
+            OutputManagerDatabase::new(OutputManagerMemoryDatabase::new()),
+            oms_event_publisher,
+            factories.clone(),
+        ))
+        .unwrap()
+        .with_fee_model(Arc::new(FlatFeeModel(MicroTari::from(1000))));
+    let mut oms = OutputManagerHandle::new(oms_request_sender, oms_event_subscriber);
+    runtime.spawn(async move { output_manager_service.start().await.unwrap() });
+
+    let (_ti, uo) = make_input(&mut OsRng.clone(), MicroTari::from(5000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    // The weight based fee of this transaction is well below the flat fee, so only the fee model rejects it
+    let fee_per_gram = MicroTari::from(20);
+    assert!(Fee::calculate(fee_per_gram, 1, 1, 2) < MicroTari::from(500));
+    match runtime.block_on(oms.prepare_transaction_to_send(MicroTari::from(4500), fee_per_gram, None, "".to_string())) {
+        Err(OutputManagerError::NotEnoughFunds) => (),
+        _ => panic!("Expected a NotEnoughFunds error"),
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(3000), fee_per_gram, None, "".to_string()))
+        .unwrap();
+    assert_eq!(stp.get_fee_amount().unwrap(), MicroTari::from(1000));
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().pending_incoming_balance,
+        MicroTari::from(1000)
+    );
 }
@@ -22,23 +22,40 @@
 
 use crate::support::utils::{make_input, random_string};
 use chrono::{Duration as ChronoDuration, Utc};
+use diesel::{Connection, QueryDsl, RunQueryDsl};
 use rand::{rngs::OsRng, RngCore};
-use std::time::Duration;
+use std::{collections::HashMap, path::Path, time::Duration};
+use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::OutputFeatures,
     types::{CryptoFactories, PrivateKey},
 };
-use tari_crypto::keys::SecretKey;
+use tari_crypto::{
+    keys::{PublicKey, SecretKey},
+    tari_utilities::ByteArray,
+};
 use tari_wallet::{
     output_manager_service::{
+        error::OutputManagerStorageError,
         service::Balance,
         storage::{
-            database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
+            database::{
+                AbandonmentReason,
+                InvalidationReason,
+                KeyManagerBranch,
+                KeyManagerState,
+                OutputManagerBackend,
+                OutputManagerDatabase,
+                PendingTransactionOutputs,
+                SyncHistoryEntry,
+                SyncOutcome,
+            },
             memory_db::OutputManagerMemoryDatabase,
             sqlite_db::OutputManagerSqliteDatabase,
         },
     },
+    schema::{key_manager_states, outputs},
     storage::connection_manager::run_migration_and_create_sqlite_connection,
 };
 use tempdir::TempDir;
@@ -70,6 +87,13 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
             outputs_to_be_received: vec![],
             timestamp: Utc::now().naive_utc() -
                 ChronoDuration::from_std(Duration::from_millis(120_000_000 * i)).unwrap(),
+            amount: MicroTari::from(1000 * (i + 1)),
+            fee: MicroTari::from(20 * i),
+            destination: if i == 0 {
+                Some(CommsPublicKey::random_keypair(&mut OsRng).1)
+            } else {
+                None
+            },
         };
         for _ in 0..(OsRng.next_u64() % 5 + 1) {
             let (_ti, uo) = make_input(
@@ -124,11 +148,13 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
             .fold(MicroTari::from(0), |acc, x| acc + x.value);
     }
 
-    let balance = runtime.block_on(db.get_balance()).unwrap();
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari(0),
+        unconfirmed_coinbase_balance: MicroTari(0),
     });
 
     runtime
@@ -150,11 +176,13 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         .iter()
         .fold(MicroTari::from(0), |acc, x| acc + x.value);
 
-    let balance = runtime.block_on(db.get_balance()).unwrap();
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari(0),
+        unconfirmed_coinbase_balance: MicroTari(0),
     });
 
     let spent_outputs = runtime.block_on(db.fetch_spent_outputs()).unwrap();
@@ -176,19 +204,42 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
     let outputs_to_encumber = vec![outputs[0].clone(), outputs[1].clone()];
     let total_encumbered = outputs[0].clone().value + outputs[1].clone().value;
     runtime
-        .block_on(db.encumber_outputs(2, outputs_to_encumber, vec![uo_change.clone()]))
+        .block_on(db.encumber_outputs(
+            2,
+            MicroTari::from(1000),
+            MicroTari::from(100),
+            outputs_to_encumber,
+            vec![uo_change.clone()],
+        ))
         .unwrap();
     runtime.block_on(db.confirm_encumbered_outputs(2)).unwrap();
 
+    let pending_tx = runtime.block_on(db.fetch_pending_transaction_outputs(2)).unwrap();
+    assert_eq!(pending_tx.amount, MicroTari::from(1000));
+    assert_eq!(pending_tx.fee, MicroTari::from(100));
+    assert_eq!(pending_tx.destination, None);
+    let (_, destination) = CommsPublicKey::random_keypair(&mut OsRng);
+    runtime
+        .block_on(db.set_pending_transaction_destination(2, destination.clone()))
+        .unwrap();
+    let pending_tx = runtime.block_on(db.fetch_pending_transaction_outputs(2)).unwrap();
+    assert_eq!(pending_tx.destination, Some(destination.clone()));
+    assert_eq!(pending_tx.fee, MicroTari::from(100));
+    assert!(runtime
+        .block_on(db.set_pending_transaction_destination(3, destination))
+        .is_err());
+
     available_balance -= total_encumbered;
     pending_incoming_balance += uo_change.clone().value;
     pending_outgoing_balance += total_encumbered;
 
-    let balance = runtime.block_on(db.get_balance()).unwrap();
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari(0),
+        unconfirmed_coinbase_balance: MicroTari(0),
     });
 
     let (_ti, uo_incoming) = make_input(
@@ -207,11 +258,13 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
 
     pending_incoming_balance += uo_incoming.clone().value;
 
-    let balance = runtime.block_on(db.get_balance()).unwrap();
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari(0),
+        unconfirmed_coinbase_balance: MicroTari(0),
     });
 
     runtime
@@ -234,11 +287,13 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
     pending_incoming_balance -= cancelled_incoming;
     pending_outgoing_balance -= cancelled_outgoing;
 
-    let balance = runtime.block_on(db.get_balance()).unwrap();
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari(0),
+        unconfirmed_coinbase_balance: MicroTari(0),
     });
 
     let remaining_p_tx = runtime.block_on(db.fetch_all_pending_transaction_outputs()).unwrap();
@@ -276,12 +331,31 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
     let invalid_outputs = runtime.block_on(db.get_invalid_outputs()).unwrap();
     assert_eq!(invalid_outputs.len(), 0);
     let unspent_outputs = runtime.block_on(db.get_unspent_outputs()).unwrap();
+    let reason = InvalidationReason::NotReturnedByBaseNode {
+        request_key: 1,
+        base_node_public_key: "test_base_node".to_string(),
+        chain_tip_height: Some(10),
+    };
     runtime
-        .block_on(db.invalidate_output(unspent_outputs[0].clone()))
+        .block_on(db.invalidate_output(unspent_outputs[0].clone(), reason.clone()))
         .unwrap();
     let invalid_outputs = runtime.block_on(db.get_invalid_outputs()).unwrap();
     assert_eq!(invalid_outputs.len(), 1);
-    assert_eq!(invalid_outputs[0], unspent_outputs[0]);
+    assert_eq!(invalid_outputs[0].output, unspent_outputs[0]);
+    assert_eq!(invalid_outputs[0].reason, Some(reason));
+    assert!(invalid_outputs[0].timestamp.is_some());
+
+    // Test revalidating the output
+    runtime
+        .block_on(db.revalidate_output(unspent_outputs[0].clone()))
+        .unwrap();
+    let invalid_outputs = runtime.block_on(db.get_invalid_outputs()).unwrap();
+    assert_eq!(invalid_outputs.len(), 0);
+    let restored_outputs = runtime.block_on(db.get_unspent_outputs()).unwrap();
+    assert!(restored_outputs.iter().any(|o| o == &unspent_outputs[0]));
+    assert!(runtime
+        .block_on(db.revalidate_output(unspent_outputs[0].clone()))
+        .is_err());
 }
 
 #[test]
@@ -305,12 +379,16 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
     let db = OutputManagerDatabase::new(backend);
 
     assert_eq!(runtime.block_on(db.get_key_manager_state()).unwrap(), None);
-    assert!(runtime.block_on(db.increment_key_index()).is_err());
+    assert!(runtime
+        .block_on(db.increment_key_index(KeyManagerBranch::Spending))
+        .is_err());
 
     let state1 = KeyManagerState {
         master_seed: PrivateKey::random(&mut OsRng),
         branch_seed: "blah".to_string(),
         primary_key_index: 0,
+        branch_indices: HashMap::new(),
+        bip39_entropy: None,
     };
 
     runtime.block_on(db.set_key_manager_state(state1.clone())).unwrap();
@@ -322,6 +400,8 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
         master_seed: PrivateKey::random(&mut OsRng),
         branch_seed: "blah2".to_string(),
         primary_key_index: 0,
+        branch_indices: HashMap::new(),
+        bip39_entropy: Some(vec![1u8; 16]),
     };
 
     runtime.block_on(db.set_key_manager_state(state2.clone())).unwrap();
@@ -329,11 +409,22 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
     let read_state2 = runtime.block_on(db.get_key_manager_state()).unwrap().unwrap();
     assert_eq!(state2, read_state2);
 
-    runtime.block_on(db.increment_key_index()).unwrap();
-    runtime.block_on(db.increment_key_index()).unwrap();
+    runtime
+        .block_on(db.increment_key_index(KeyManagerBranch::Spending))
+        .unwrap();
+    runtime
+        .block_on(db.increment_key_index(KeyManagerBranch::Spending))
+        .unwrap();
+    runtime
+        .block_on(db.increment_key_index(KeyManagerBranch::Coinbase))
+        .unwrap();
 
+    // Each branch has its own key index
     let read_state3 = runtime.block_on(db.get_key_manager_state()).unwrap().unwrap();
     assert_eq!(read_state3.primary_key_index, 2);
+    assert_eq!(read_state3.key_index(KeyManagerBranch::Spending), 2);
+    assert_eq!(read_state3.key_index(KeyManagerBranch::Coinbase), 1);
+    assert_eq!(read_state3.key_index(KeyManagerBranch::OneSided), 0);
 }
 #[test]
 pub fn test_key_manager_crud_memory_db() {
@@ -350,6 +441,200 @@ pub fn test_key_manager_crud_sqlite_db() {
     test_key_manager_crud(OutputManagerSqliteDatabase::new(connection));
 }
 
+pub fn test_sync_history<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+    let db = OutputManagerDatabase::new(backend);
+
+    assert!(runtime.block_on(db.fetch_sync_history(10)).unwrap().is_empty());
+
+    let (_, base_node_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    let now = Utc::now().naive_utc();
+    let mut entries = Vec::new();
+    for i in 0..4 {
+        let entry = SyncHistoryEntry {
+            request_key: OsRng.next_u64(),
+            base_node_public_key: base_node_public_key.clone(),
+            output_hashes: vec![vec![i as u8; 32], vec![i as u8 + 10; 32]],
+            sent_at: now + ChronoDuration::seconds(i),
+            response_time: None,
+            invalidated_hashes: Vec::new(),
+            outcome: SyncOutcome::Pending,
+        };
+        runtime
+            .block_on(db.record_sync_history_entry(entry.clone(), 3))
+            .unwrap();
+        entries.push(entry);
+    }
+
+    // Only the newest entries are kept, newest first
+    let history = runtime.block_on(db.fetch_sync_history(10)).unwrap();
+    assert_eq!(
+        history.iter().map(|e| e.request_key).collect::<Vec<_>>(),
+        entries.iter().rev().take(3).map(|e| e.request_key).collect::<Vec<_>>()
+    );
+    assert_eq!(history[0].output_hashes, entries[3].output_hashes);
+    assert_eq!(history[0].base_node_public_key, base_node_public_key);
+
+    // Recording an entry again replaces it
+    let mut completed = entries[3].clone();
+    completed.response_time = Some(Duration::from_millis(1500));
+    completed.invalidated_hashes = vec![completed.output_hashes[1].clone()];
+    completed.outcome = SyncOutcome::Completed;
+    runtime
+        .block_on(db.record_sync_history_entry(completed.clone(), 3))
+        .unwrap();
+    let history = runtime.block_on(db.fetch_sync_history(1)).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].request_key, completed.request_key);
+    assert_eq!(history[0].response_time, completed.response_time);
+    assert_eq!(history[0].invalidated_hashes, completed.invalidated_hashes);
+    assert_eq!(history[0].outcome, SyncOutcome::Completed);
+    assert_eq!(runtime.block_on(db.fetch_sync_history(10)).unwrap().len(), 3);
+}
+
+#[test]
+pub fn test_sync_history_memory_db() {
+    test_sync_history(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_sync_history_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+
+    test_sync_history(OutputManagerSqliteDatabase::new(connection));
+}
+
+pub fn test_abandon_pending_transaction<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+    let db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+
+    let (_ti, input) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    let (_ti, change) = make_input(&mut OsRng, MicroTari::from(3_000), &factories.commitment);
+    runtime.block_on(db.add_unspent_output(input.clone())).unwrap();
+    let tx_id = OsRng.next_u64();
+    runtime
+        .block_on(db.encumber_outputs(
+            tx_id,
+            MicroTari::from(6_000),
+            MicroTari::from(1_000),
+            vec![input.clone()],
+            vec![change.clone()],
+        ))
+        .unwrap();
+    runtime.block_on(db.confirm_encumbered_outputs(tx_id)).unwrap();
+    assert!(runtime.block_on(db.fetch_abandoned_transactions()).unwrap().is_empty());
+
+    let reason = AbandonmentReason::RejectedByBaseNode {
+        reason: "Fee too low".to_string(),
+    };
+    runtime
+        .block_on(db.abandon_pending_transaction_outputs(tx_id, reason.clone()))
+        .unwrap();
+
+    // The input is spendable again, but the change output is abandoned rather than returned to the unspent pool
+    assert_eq!(runtime.block_on(db.get_unspent_outputs()).unwrap(), vec![input]);
+    assert!(runtime
+        .block_on(db.fetch_all_pending_transaction_outputs())
+        .unwrap()
+        .is_empty());
+    let balance = runtime.block_on(db.get_balance(None)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(10_000));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    let abandoned = runtime.block_on(db.fetch_abandoned_transactions()).unwrap();
+    assert_eq!(abandoned.len(), 1);
+    assert_eq!(abandoned[0].tx_id, tx_id);
+    assert_eq!(abandoned[0].outputs, vec![change]);
+    assert_eq!(abandoned[0].reason, reason);
+
+    // Abandoning is terminal, so the transaction can be neither abandoned nor cancelled again
+    assert!(runtime
+        .block_on(db.abandon_pending_transaction_outputs(tx_id, AbandonmentReason::CancelledByCounterparty))
+        .is_err());
+    assert!(runtime.block_on(db.cancel_pending_transaction_outputs(tx_id)).is_err());
+    assert_eq!(runtime.block_on(db.fetch_abandoned_transactions()).unwrap().len(), 1);
+}
+
+#[test]
+pub fn test_abandon_pending_transaction_memory_db() {
+    test_abandon_pending_transaction(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_abandon_pending_transaction_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+
+    test_abandon_pending_transaction(OutputManagerSqliteDatabase::new(connection));
+}
+
+pub fn test_add_unspent_outputs_batch<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+    let db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+
+    let mut outputs = Vec::new();
+    for i in 1..=1500 {
+        let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(i), &factories.commitment);
+        outputs.push(uo);
+    }
+    runtime.block_on(db.add_unspent_outputs(outputs.clone())).unwrap();
+
+    let mut unspent_outputs = runtime.block_on(db.get_unspent_outputs()).unwrap();
+    unspent_outputs.sort();
+    outputs.sort();
+    assert_eq!(unspent_outputs, outputs);
+
+    // A batch containing an output that is already stored must not add any of its outputs
+    let (_ti, new_output) = make_input(&mut OsRng, MicroTari::from(5000), &factories.commitment);
+    assert!(runtime
+        .block_on(db.add_unspent_outputs(vec![new_output.clone(), outputs[0].clone()]))
+        .is_err());
+    // As must a batch that contains the same output twice
+    assert!(runtime
+        .block_on(db.add_unspent_outputs(vec![new_output.clone(), new_output.clone()]))
+        .is_err());
+    let unspent_outputs = runtime.block_on(db.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), outputs.len());
+    assert!(!unspent_outputs.contains(&new_output));
+
+    // An output that has been invalidated is still stored, so it must not be added again either
+    let reason = InvalidationReason::NotReturnedByBaseNode {
+        request_key: 1,
+        base_node_public_key: "test_base_node".to_string(),
+        chain_tip_height: Some(10),
+    };
+    runtime
+        .block_on(db.invalidate_output(outputs[1].clone(), reason))
+        .unwrap();
+    assert!(runtime
+        .block_on(db.add_unspent_outputs(vec![new_output.clone(), outputs[1].clone()]))
+        .is_err());
+    let unspent_outputs = runtime.block_on(db.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), outputs.len() - 1);
+    assert!(!unspent_outputs.contains(&new_output));
+}
+
+#[test]
+pub fn test_add_unspent_outputs_batch_memory_db() {
+    test_add_unspent_outputs_batch(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_add_unspent_outputs_batch_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+
+    test_add_unspent_outputs_batch(OutputManagerSqliteDatabase::new(connection));
+}
+
 pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
@@ -362,6 +647,9 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
         outputs_to_be_spent: vec![],
         outputs_to_be_received: vec![],
         timestamp: Utc::now().naive_utc() - ChronoDuration::from_std(Duration::from_millis(120_000_000)).unwrap(),
+        amount: MicroTari::from(5000),
+        fee: MicroTari::from(100),
+        destination: None,
     };
     for i in 1..4 {
         let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(1000 * i), &factories.commitment);
@@ -373,34 +661,42 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
     let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(50), &factories.commitment);
     pending_tx.outputs_to_be_received.push(uo);
 
-    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
-        pending_tx.outputs_to_be_received[0].clone(),
-    ])
+    db.encumber_outputs(
+        pending_tx.tx_id,
+        pending_tx.amount,
+        pending_tx.fee,
+        pending_tx.outputs_to_be_spent.clone(),
+        vec![pending_tx.outputs_to_be_received[0].clone()],
+    )
     .await
     .unwrap();
 
-    let balance = db.get_balance().await.unwrap();
+    let balance = db.get_balance(None).await.unwrap();
     assert_eq!(balance.available_balance, MicroTari(0));
 
     db.clear_short_term_encumberances().await.unwrap();
 
-    let balance = db.get_balance().await.unwrap();
+    let balance = db.get_balance(None).await.unwrap();
     assert_eq!(available_balance, balance.available_balance);
 
     pending_tx.outputs_to_be_received.clear();
     let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(50), &factories.commitment);
     pending_tx.outputs_to_be_received.push(uo);
 
-    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
-        pending_tx.outputs_to_be_received[0].clone(),
-    ])
+    db.encumber_outputs(
+        pending_tx.tx_id,
+        pending_tx.amount,
+        pending_tx.fee,
+        pending_tx.outputs_to_be_spent.clone(),
+        vec![pending_tx.outputs_to_be_received[0].clone()],
+    )
     .await
     .unwrap();
 
     db.confirm_encumbered_outputs(pending_tx.tx_id).await.unwrap();
     db.clear_short_term_encumberances().await.unwrap();
 
-    let balance = db.get_balance().await.unwrap();
+    let balance = db.get_balance(None).await.unwrap();
     assert_eq!(balance.available_balance, MicroTari(0));
 
     pending_tx.outputs_to_be_received.clear();
@@ -409,15 +705,19 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
 
     db.cancel_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
 
-    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
-        pending_tx.outputs_to_be_received[0].clone(),
-    ])
+    db.encumber_outputs(
+        pending_tx.tx_id,
+        pending_tx.amount,
+        pending_tx.fee,
+        pending_tx.outputs_to_be_spent.clone(),
+        vec![pending_tx.outputs_to_be_received[0].clone()],
+    )
     .await
     .unwrap();
 
     db.confirm_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
 
-    let balance = db.get_balance().await.unwrap();
+    let balance = db.get_balance(None).await.unwrap();
     assert_eq!(balance.available_balance, pending_tx.outputs_to_be_received[0].value);
 }
 
@@ -435,3 +735,110 @@ pub async fn test_short_term_encumberance_sqlite_db() {
 
     test_short_term_encumberance(OutputManagerSqliteDatabase::new(connection)).await;
 }
+
+#[tokio_macros::test]
+pub async fn test_sqlite_db_reads_while_writing() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+    let db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection.clone()));
+    let factories = CryptoFactories::default();
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(100), &factories.commitment);
+    db.add_unspent_output(uo.clone()).await.unwrap();
+    assert!(Path::new(&format!("{}-wal", db_path)).exists());
+
+    // A read uses its own pooled connection, so it does not wait for the connection holding the write lock
+    let write_connection = connection.get_write_connection().unwrap();
+    write_connection.execute("BEGIN IMMEDIATE;").unwrap();
+    assert_eq!(db.get_unspent_outputs().await.unwrap(), vec![uo]);
+    write_connection.execute("ROLLBACK;").unwrap();
+}
+
+#[tokio_macros::test]
+pub async fn test_sqlite_db_encryption() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+    let db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection.clone()));
+    let factories = CryptoFactories::default();
+
+    let state = KeyManagerState {
+        master_seed: PrivateKey::random(&mut OsRng),
+        branch_seed: "blah".to_string(),
+        primary_key_index: 0,
+        branch_indices: HashMap::new(),
+        bip39_entropy: Some(vec![7u8; 32]),
+    };
+    db.set_key_manager_state(state.clone()).await.unwrap();
+    let (_ti, uo1) = make_input(&mut OsRng, MicroTari::from(100), &factories.commitment);
+    db.add_unspent_output(uo1.clone()).await.unwrap();
+
+    assert_eq!(
+        db.remove_encryption().await.unwrap_err(),
+        OutputManagerStorageError::NotEncrypted
+    );
+    db.apply_encryption("correct horse battery staple".to_string())
+        .await
+        .unwrap();
+    assert_eq!(
+        db.apply_encryption("correct horse battery staple".to_string())
+            .await
+            .unwrap_err(),
+        OutputManagerStorageError::AlreadyEncrypted
+    );
+
+    // The spending keys are no longer stored in plaintext but outputs can still be read and written
+    let stored_keys = outputs::table
+        .select(outputs::spending_key)
+        .load::<Vec<u8>>(&*connection.get_write_connection().unwrap())
+        .unwrap();
+    assert_eq!(stored_keys.len(), 1);
+    assert_ne!(stored_keys[0], uo1.spending_key.to_vec());
+    let stored_entropy = key_manager_states::table
+        .select(key_manager_states::bip39_entropy)
+        .first::<Option<Vec<u8>>>(&*connection.get_write_connection().unwrap())
+        .unwrap();
+    assert!(stored_entropy.is_some());
+    assert_ne!(stored_entropy, state.bip39_entropy);
+    let (_ti, uo2) = make_input(&mut OsRng, MicroTari::from(200), &factories.commitment);
+    db.add_unspent_output(uo2.clone()).await.unwrap();
+    let mut expected_outputs = vec![uo1.clone(), uo2.clone()];
+    expected_outputs.sort();
+    let mut unspent_outputs = db.get_unspent_outputs().await.unwrap();
+    unspent_outputs.sort();
+    assert_eq!(unspent_outputs, expected_outputs);
+    assert_eq!(db.get_key_manager_state().await.unwrap().unwrap(), state);
+
+    // A new backend on the same database is locked until it is given the correct passphrase
+    let db2 = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection.clone()));
+    assert_eq!(
+        db2.get_unspent_outputs().await.unwrap_err(),
+        OutputManagerStorageError::DatabaseLocked
+    );
+    assert_eq!(
+        db2.apply_encryption("incorrect horse".to_string()).await.unwrap_err(),
+        OutputManagerStorageError::InvalidPassphrase
+    );
+    db2.apply_encryption("correct horse battery staple".to_string())
+        .await
+        .unwrap();
+    let mut unspent_outputs = db2.get_unspent_outputs().await.unwrap();
+    unspent_outputs.sort();
+    assert_eq!(unspent_outputs, expected_outputs);
+
+    db2.remove_encryption().await.unwrap();
+    let stored_keys = outputs::table
+        .select(outputs::spending_key)
+        .load::<Vec<u8>>(&*connection.get_write_connection().unwrap())
+        .unwrap();
+    assert!(stored_keys.contains(&uo1.spending_key.to_vec()));
+    let db3 = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection));
+    let mut unspent_outputs = db3.get_unspent_outputs().await.unwrap();
+    unspent_outputs.sort();
+    assert_eq!(unspent_outputs, expected_outputs);
+    assert_eq!(db3.get_key_manager_state().await.unwrap().unwrap(), state);
+}
@@ -0,0 +1,242 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::service::setup_output_manager_service,
+    support::{
+        comms_and_services::get_next_memory_address,
+        mock_base_node::{MockBaseNode, MockBlock, MockChain},
+        utils::make_input,
+    },
+    transaction_service::service::setup_transaction_service_no_comms,
+};
+use futures::{FutureExt, StreamExt};
+use rand::rngs::OsRng;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tari_comms::peer_manager::{NodeIdentity, PeerFeatures};
+use tari_core::transactions::{tari_amount::*, types::CryptoFactories};
+use tari_wallet::{
+    output_manager_service::{handle::OutputManagerEvent, storage::memory_db::OutputManagerMemoryDatabase},
+    transaction_service::{
+        handle::TransactionEvent,
+        storage::{database::TransactionStatus, memory_db::TransactionMemoryDatabase},
+    },
+};
+use tokio::{runtime::Runtime, time::delay_for};
+
+fn create_base_node_identity() -> NodeIdentity {
+    NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap()
+}
+
+#[test]
+fn output_invalidated_when_reorged_out() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, base_node_response_sender) =
+        setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let mut event_stream = oms.get_event_stream_fused();
+
+    let base_node_identity = create_base_node_identity();
+    let chain = Arc::new(Mutex::new(MockChain::new()));
+    MockBaseNode::new(base_node_identity.public_key().clone(), chain.clone(), outbound_service)
+        .with_base_node_response_sender(base_node_response_sender)
+        .spawn(runtime.handle());
+
+    let (_ti, uo1) = make_input(&mut OsRng, 5_000 * uT, &factories.commitment);
+    let (_ti, uo2) = make_input(&mut OsRng, 7_000 * uT, &factories.commitment);
+    runtime.block_on(oms.add_output(uo1.clone())).unwrap();
+    runtime.block_on(oms.add_output(uo2.clone())).unwrap();
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.add_block(MockBlock::new().with_output(uo1.as_transaction_output(&factories).unwrap()));
+        chain.add_block(MockBlock::new().with_output(uo2.as_transaction_output(&factories).unwrap()));
+    }
+
+    // Setting the base node for the first time triggers a UTXO query
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    let received = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::SyncCompleted { .. } = (*event).clone() {
+                        break true;
+                    }
+                },
+                () = delay => break false,
+            }
+        }
+    });
+    assert!(received, "The UTXO query was not answered");
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 2);
+    assert!(runtime.block_on(oms.get_invalid_outputs()).unwrap().is_empty());
+
+    // The block containing the second output is reorged out
+    chain.lock().unwrap().reorg(1, vec![MockBlock::new(), MockBlock::new()]);
+
+    let request_key = runtime.block_on(oms.sync_with_base_node()).unwrap();
+    let received = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let OutputManagerEvent::SyncCompleted { request_key: key, .. } = (*event).clone() {
+                        if key == request_key {
+                            break true;
+                        }
+                    }
+                },
+                () = delay => break false,
+            }
+        }
+    });
+    assert!(received, "The UTXO query was not answered");
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap(), vec![uo1]);
+    let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    assert_eq!(
+        invalid_outputs.into_iter().map(|io| io.output).collect::<Vec<_>>(),
+        vec![uo2]
+    );
+}
+
+#[test]
+fn transaction_mined_on_orphaned_block_is_not_mined() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut ts, mut oms, outbound_service, _, _, _, mempool_response_sender, base_node_response_sender) =
+        setup_transaction_service_no_comms(
+            &mut runtime,
+            factories.clone(),
+            TransactionMemoryDatabase::new(),
+            Some(Duration::from_secs(1)),
+        );
+    let mut event_stream = ts.get_event_stream_fused();
+
+    let base_node_identity = create_base_node_identity();
+    let chain = Arc::new(Mutex::new(MockChain::new()));
+    MockBaseNode::new(base_node_identity.public_key().clone(), chain.clone(), outbound_service)
+        .with_base_node_response_sender(base_node_response_sender)
+        .with_mempool_response_sender(mempool_response_sender)
+        .spawn(runtime.handle());
+
+    runtime
+        .block_on(ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    let (_ti, uo) = make_input(&mut OsRng, 100_000 * uT, &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+    let (tx_id, tx, fee, amount) = runtime
+        .block_on(oms.create_coin_split(10_000 * uT, 3, 20 * uT, None))
+        .unwrap();
+    runtime
+        .block_on(ts.submit_transaction(tx_id, tx, fee, amount, "Coin split".to_string()))
+        .unwrap();
+
+    let broadcast = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let TransactionEvent::TransactionBroadcast(id) = &*event.unwrap() {
+                        if *id == tx_id {
+                            break true;
+                        }
+                    }
+                },
+                () = delay => break false,
+            }
+        }
+    });
+    assert!(broadcast, "The transaction was not broadcast to the mempool");
+
+    // The transaction is mined on a block that is orphaned before the wallet sees it, which returns it to the mempool
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.mine_mempool();
+        chain.reorg(1, vec![MockBlock::new(), MockBlock::new()]);
+        assert_eq!(chain.height(), 2);
+    }
+
+    let timed_out = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        let mut count = 0;
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    match &*event.unwrap() {
+                        TransactionEvent::TransactionMinedRequestTimedOut(id) if *id == tx_id => {
+                            count += 1;
+                            if count >= 2 {
+                                break true;
+                            }
+                        },
+                        TransactionEvent::TransactionMined(id) if *id == tx_id => break false,
+                        _ => (),
+                    }
+                },
+                () = delay => break false,
+            }
+        }
+    });
+    assert!(timed_out, "The transaction should still be waiting to be mined");
+    let completed_tx = runtime
+        .block_on(ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("Transaction must be in collection");
+    assert_eq!(completed_tx.status, TransactionStatus::Broadcast);
+
+    // Now the transaction is mined on the best chain
+    chain.lock().unwrap().mine_mempool();
+
+    let mined = runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => {
+                    if let TransactionEvent::TransactionMined(id) = &*event.unwrap() {
+                        if *id == tx_id {
+                            break true;
+                        }
+                    }
+                },
+                () = delay => break false,
+            }
+        }
+    });
+    assert!(mined, "The transaction was not detected as mined");
+    let completed_tx = runtime
+        .block_on(ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("Transaction must be in collection");
+    assert_eq!(completed_tx.status, TransactionStatus::Mined);
+    // The split outputs and change are now spendable
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 4);
+}
@@ -0,0 +1,372 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A scripted base node that answers the wallet's base node and mempool queries from a mock chain. Tests can add,
+//! mine and reorg blocks on the chain while the wallet services are running to check how the wallet's validation and
+//! monitoring protocols handle the resulting state transitions.
+
+use crate::support::comms_and_services::create_dummy_message;
+use futures::{channel::mpsc::Sender, SinkExt};
+use prost::Message;
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tari_comms::{message::EnvelopeBody, types::CommsPublicKey};
+use tari_comms_dht::{domain_message::MessageHeader, outbound::mock::OutboundServiceMockState};
+use tari_core::{
+    base_node::proto::{
+        base_node as BaseNodeProto,
+        base_node::{
+            base_node_service_request::Request as BaseNodeRequestProto,
+            base_node_service_response::Response as BaseNodeResponseProto,
+        },
+    },
+    mempool::{
+        proto::mempool as MempoolProto,
+        service::{MempoolRequest, MempoolResponse, MempoolServiceRequest},
+        TxStorageResponse,
+    },
+    transactions::{
+        transaction::{Transaction, TransactionKernel, TransactionOutput},
+        types::{Commitment, Signature},
+    },
+};
+use tari_crypto::tari_utilities::hash::Hashable;
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tokio::{runtime::Handle, time::delay_for};
+
+/// A block in the mock chain. Only the parts of a block that the wallet queries are kept.
+#[derive(Clone, Debug, Default)]
+pub struct MockBlock {
+    pub transactions: Vec<Transaction>,
+    pub outputs: Vec<TransactionOutput>,
+}
+
+impl MockBlock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_transaction(mut self, tx: Transaction) -> Self {
+        self.transactions.push(tx);
+        self
+    }
+
+    pub fn with_output(mut self, output: TransactionOutput) -> Self {
+        self.outputs.push(output);
+        self
+    }
+
+    fn kernels(&self) -> impl Iterator<Item = &TransactionKernel> {
+        self.transactions.iter().flat_map(|tx| tx.body.kernels().iter())
+    }
+
+    fn outputs(&self) -> impl Iterator<Item = &TransactionOutput> {
+        self.transactions
+            .iter()
+            .flat_map(|tx| tx.body.outputs().iter())
+            .chain(self.outputs.iter())
+    }
+
+    fn spent_commitments(&self) -> impl Iterator<Item = &Commitment> {
+        self.transactions
+            .iter()
+            .flat_map(|tx| tx.body.inputs().iter())
+            .map(|i| &i.commitment)
+    }
+}
+
+/// A scripted chain and mempool
+#[derive(Default)]
+pub struct MockChain {
+    blocks: Vec<MockBlock>,
+    mempool: Vec<Transaction>,
+}
+
+impl MockChain {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn height(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Add a block to the tip of the chain. Transactions in the block are removed from the mempool.
+    pub fn add_block(&mut self, block: MockBlock) {
+        for tx in block.transactions.iter() {
+            self.remove_from_mempool(tx);
+        }
+        self.blocks.push(block);
+    }
+
+    /// Add a transaction to the mempool
+    pub fn submit_transaction(&mut self, tx: Transaction) {
+        if !self.mempool_contains(&tx.body.kernels()[0].excess_sig) {
+            self.mempool.push(tx);
+        }
+    }
+
+    /// Mine all the transactions currently in the mempool into a new block
+    pub fn mine_mempool(&mut self) {
+        let transactions = self.mempool.drain(..).collect();
+        self.add_block(MockBlock {
+            transactions,
+            outputs: Vec::new(),
+        });
+    }
+
+    /// Remove the top `depth` blocks and replace them with `new_blocks`. Transactions from the orphaned blocks that are
+    /// not contained in the new blocks are returned to the mempool, as a base node would do.
+    pub fn reorg(&mut self, depth: usize, new_blocks: Vec<MockBlock>) {
+        let fork_height = self.blocks.len().saturating_sub(depth);
+        let orphaned = self.blocks.split_off(fork_height);
+        for block in new_blocks {
+            self.add_block(block);
+        }
+        for tx in orphaned.into_iter().flat_map(|b| b.transactions.into_iter()) {
+            if self.find_kernel(&tx.body.kernels()[0].excess).is_none() {
+                self.submit_transaction(tx);
+            }
+        }
+    }
+
+    /// The unspent outputs of the current chain
+    pub fn utxos(&self) -> Vec<TransactionOutput> {
+        let spent = self
+            .blocks
+            .iter()
+            .flat_map(|b| b.spent_commitments())
+            .collect::<Vec<_>>();
+        self.blocks
+            .iter()
+            .flat_map(|b| b.outputs())
+            .filter(|o| !spent.contains(&&o.commitment))
+            .cloned()
+            .collect()
+    }
+
+    pub fn find_kernel(&self, excess: &Commitment) -> Option<TransactionKernel> {
+        self.blocks
+            .iter()
+            .flat_map(|b| b.kernels())
+            .find(|k| &k.excess == excess)
+            .cloned()
+    }
+
+    fn mempool_contains(&self, excess_sig: &Signature) -> bool {
+        self.mempool
+            .iter()
+            .any(|tx| &tx.body.kernels()[0].excess_sig == excess_sig)
+    }
+
+    fn remove_from_mempool(&mut self, tx: &Transaction) {
+        let excess_sig = &tx.body.kernels()[0].excess_sig;
+        self.mempool.retain(|t| &t.body.kernels()[0].excess_sig != excess_sig);
+    }
+
+    fn is_mined(&self, excess_sig: &Signature) -> bool {
+        self.blocks
+            .iter()
+            .flat_map(|b| b.kernels())
+            .any(|k| &k.excess_sig == excess_sig)
+    }
+
+    /// Produce the response to a base node request, if the request is one that the mock chain supports
+    pub fn handle_base_node_request(
+        &self,
+        request: BaseNodeProto::BaseNodeServiceRequest,
+    ) -> Option<BaseNodeProto::BaseNodeServiceResponse>
+    {
+        let response = match request.request? {
+            BaseNodeRequestProto::FetchUtxos(hashes) => {
+                let outputs = self
+                    .utxos()
+                    .into_iter()
+                    .filter(|o| hashes.outputs.contains(&o.hash()))
+                    .map(Into::into)
+                    .collect();
+                BaseNodeResponseProto::TransactionOutputs(BaseNodeProto::TransactionOutputs { outputs })
+            },
+            BaseNodeRequestProto::FetchKernelByExcess(excess) => {
+                let kernels = Commitment::try_from(excess)
+                    .ok()
+                    .and_then(|excess| self.find_kernel(&excess))
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                BaseNodeResponseProto::TransactionKernels(BaseNodeProto::TransactionKernels { kernels })
+            },
+            _ => return None,
+        };
+
+        Some(BaseNodeProto::BaseNodeServiceResponse {
+            request_key: request.request_key,
+            response: Some(response),
+        })
+    }
+
+    /// Produce the response to a mempool request, if the request is one that the mock chain supports
+    pub fn handle_mempool_request(
+        &mut self,
+        request: MempoolServiceRequest,
+    ) -> Option<MempoolProto::MempoolServiceResponse>
+    {
+        let storage_response = match request.request {
+            MempoolRequest::SubmitTransaction(tx) => {
+                let excess_sig = tx.body.kernels()[0].excess_sig.clone();
+                if self.is_mined(&excess_sig) {
+                    TxStorageResponse::ReorgPool
+                } else {
+                    self.submit_transaction(tx);
+                    TxStorageResponse::UnconfirmedPool
+                }
+            },
+            MempoolRequest::GetTxStateWithExcessSig(excess_sig) => {
+                if self.mempool_contains(&excess_sig) {
+                    TxStorageResponse::UnconfirmedPool
+                } else if self.is_mined(&excess_sig) {
+                    TxStorageResponse::ReorgPool
+                } else {
+                    TxStorageResponse::NotStored
+                }
+            },
+            _ => return None,
+        };
+
+        Some(MempoolProto::MempoolServiceResponse {
+            request_key: request.request_key,
+            response: Some(MempoolResponse::TxStorage(storage_response).into()),
+        })
+    }
+}
+
+/// Answers the base node and mempool requests that the wallet services send through a mock outbound service using the
+/// current state of a shared `MockChain`.
+pub struct MockBaseNode {
+    public_key: CommsPublicKey,
+    chain: Arc<Mutex<MockChain>>,
+    outbound_service: OutboundServiceMockState,
+    base_node_response_senders: Vec<Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>>,
+    mempool_response_senders: Vec<Sender<DomainMessage<MempoolProto::MempoolServiceResponse>>>,
+}
+
+impl MockBaseNode {
+    pub fn new(
+        public_key: CommsPublicKey,
+        chain: Arc<Mutex<MockChain>>,
+        outbound_service: OutboundServiceMockState,
+    ) -> Self
+    {
+        Self {
+            public_key,
+            chain,
+            outbound_service,
+            base_node_response_senders: Vec::new(),
+            mempool_response_senders: Vec::new(),
+        }
+    }
+
+    /// Base node responses are sent to every registered service, services ignore responses to requests they did not
+    /// make.
+    pub fn with_base_node_response_sender(
+        mut self,
+        sender: Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
+    ) -> Self
+    {
+        self.base_node_response_senders.push(sender);
+        self
+    }
+
+    pub fn with_mempool_response_sender(
+        mut self,
+        sender: Sender<DomainMessage<MempoolProto::MempoolServiceResponse>>,
+    ) -> Self
+    {
+        self.mempool_response_senders.push(sender);
+        self
+    }
+
+    /// Answer all the requests that have been sent so far. Messages that are not base node or mempool requests are
+    /// discarded. Returns the number of requests that were answered.
+    pub async fn process_requests(&mut self) -> usize {
+        let mut count = 0;
+        for (_, body) in self.outbound_service.take_calls() {
+            let mut envelope_body = match EnvelopeBody::decode(body.to_vec().as_slice()) {
+                Ok(b) => b,
+                Err(_) => continue,
+            };
+            let message_type = match envelope_body.decode_part::<MessageHeader>(0) {
+                Ok(Some(header)) => header.message_type,
+                _ => continue,
+            };
+            let msg_bytes = match envelope_body.take_part(1) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            if message_type == TariMessageType::BaseNodeRequest as i32 {
+                let response = BaseNodeProto::BaseNodeServiceRequest::decode(msg_bytes.as_slice())
+                    .ok()
+                    .and_then(|request| acquire_lock(&self.chain).handle_base_node_request(request));
+                if let Some(response) = response {
+                    for sender in self.base_node_response_senders.iter_mut() {
+                        let _ = sender
+                            .send(create_dummy_message(response.clone(), &self.public_key))
+                            .await;
+                    }
+                    count += 1;
+                }
+            } else if message_type == TariMessageType::MempoolRequest as i32 {
+                let response = MempoolProto::MempoolServiceRequest::decode(msg_bytes.as_slice())
+                    .ok()
+                    .and_then(|request| MempoolServiceRequest::try_from(request).ok())
+                    .and_then(|request| acquire_lock(&self.chain).handle_mempool_request(request));
+                if let Some(response) = response {
+                    for sender in self.mempool_response_senders.iter_mut() {
+                        let _ = sender
+                            .send(create_dummy_message(response.clone(), &self.public_key))
+                            .await;
+                    }
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Spawn a task that keeps answering requests until the runtime is shut down
+    pub fn spawn(mut self, handle: &Handle) {
+        handle.spawn(async move {
+            loop {
+                self.process_requests().await;
+                delay_for(Duration::from_millis(100)).await;
+            }
+        });
+    }
+}
+
+fn acquire_lock(chain: &Arc<Mutex<MockChain>>) -> std::sync::MutexGuard<'_, MockChain> {
+    chain.lock().expect("MockChain lock must never be poisoned")
+}
@@ -22,4 +22,5 @@
 
 pub mod comms_and_services;
 pub mod data;
+pub mod mock_base_node;
 pub mod utils;
@@ -21,8 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::support::utils::random_string;
-use chrono::Utc;
+use chrono::{Duration as ChronoDuration, Utc};
 use rand::rngs::OsRng;
+use std::time::Duration;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, Transaction, UnblindedOutput},
@@ -40,9 +41,11 @@ use tari_wallet::{
     transaction_service::storage::{
         database::{
             CompletedTransaction,
+            FeePriority,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ScheduledPayment,
             TransactionBackend,
             TransactionDatabase,
             TransactionStatus,
@@ -91,6 +94,7 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
             status: TransactionStatus::Pending,
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: None,
         });
         assert!(
             !runtime.block_on(db.transaction_exists((i + 10) as u64)).unwrap(),
@@ -215,6 +219,7 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
             },
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            fee_priority: None,
         });
         runtime
             .block_on(db.complete_outbound_transaction(outbound_txs[i].tx_id, completed_txs[i].clone()))
@@ -326,3 +331,131 @@ pub fn test_transaction_service_sqlite_db() {
 
     test_db_backend(TransactionServiceSqliteDatabase::new(connection));
 }
+
+pub fn test_processed_messages<T: TransactionBackend + 'static>(backend: T) {
+    let sender = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+    let other_sender = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+    let now = Utc::now().naive_utc();
+    let old = now - ChronoDuration::days(7);
+
+    assert!(!backend.is_message_processed(&sender, &[1u8; 32]).unwrap());
+    backend.mark_message_processed(&sender, &[1u8; 32], old).unwrap();
+    backend.mark_message_processed(&sender, &[2u8; 32], now).unwrap();
+    // Marking the same message again must not fail
+    backend.mark_message_processed(&sender, &[2u8; 32], now).unwrap();
+
+    assert!(backend.is_message_processed(&sender, &[1u8; 32]).unwrap());
+    assert!(backend.is_message_processed(&sender, &[2u8; 32]).unwrap());
+    assert!(!backend.is_message_processed(&other_sender, &[1u8; 32]).unwrap());
+
+    let pruned = backend.prune_processed_messages(now - ChronoDuration::days(1)).unwrap();
+    assert_eq!(pruned, 1);
+    assert!(!backend.is_message_processed(&sender, &[1u8; 32]).unwrap());
+    assert!(backend.is_message_processed(&sender, &[2u8; 32]).unwrap());
+}
+
+pub fn test_replacement_transactions<T: TransactionBackend + 'static>(backend: T) {
+    assert_eq!(backend.get_replacement_transaction(1).unwrap(), None);
+    backend.link_replacement_transaction(1, 2).unwrap();
+    backend.link_replacement_transaction(2, 3).unwrap();
+    assert_eq!(backend.get_replacement_transaction(1).unwrap(), Some(2));
+    assert_eq!(backend.get_replacement_transaction(2).unwrap(), Some(3));
+    assert_eq!(backend.get_replacement_transaction(3).unwrap(), None);
+}
+
+#[test]
+pub fn test_replacement_transactions_memory_db() {
+    test_replacement_transactions(TransactionMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_replacement_transactions_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    test_replacement_transactions(TransactionServiceSqliteDatabase::new(connection));
+}
+
+pub fn test_scheduled_payments<T: TransactionBackend + 'static>(backend: T) {
+    let start = Utc::now().naive_utc();
+    let mut payment = ScheduledPayment {
+        id: 1,
+        destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+        amount: MicroTari::from(5000),
+        fee_priority: FeePriority::Slow,
+        message: "Monthly donation".to_string(),
+        interval: Duration::from_secs(60 * 60),
+        next_payment_at: start,
+        payments_made: 0,
+        last_tx_id: None,
+    };
+    assert!(backend.get_scheduled_payments().unwrap().is_empty());
+    backend.save_scheduled_payment(payment.clone()).unwrap();
+    backend
+        .save_scheduled_payment(ScheduledPayment {
+            id: 2,
+            ..payment.clone()
+        })
+        .unwrap();
+    assert_eq!(backend.get_scheduled_payments().unwrap().len(), 2);
+
+    // Missed periods are skipped so the next payment is due in the future
+    assert!(payment.is_due(start));
+    payment.advance(start + ChronoDuration::minutes(150));
+    assert_eq!(payment.next_payment_at, start + ChronoDuration::hours(3));
+    payment.payments_made = 1;
+    payment.last_tx_id = Some(42);
+    backend.save_scheduled_payment(payment.clone()).unwrap();
+
+    let stored = backend
+        .get_scheduled_payments()
+        .unwrap()
+        .into_iter()
+        .find(|p| p.id == 1)
+        .unwrap();
+    assert_eq!(stored.next_payment_at, payment.next_payment_at);
+    assert_eq!(stored.payments_made, 1);
+    assert_eq!(stored.last_tx_id, Some(42));
+    assert_eq!(stored.interval, payment.interval);
+    assert_eq!(stored.fee_priority, FeePriority::Slow);
+    assert_eq!(stored.destination_public_key, payment.destination_public_key);
+
+    assert_eq!(backend.remove_scheduled_payment(2).unwrap().map(|p| p.id), Some(2));
+    assert_eq!(backend.remove_scheduled_payment(2).unwrap(), None);
+    assert_eq!(backend.get_scheduled_payments().unwrap().len(), 1);
+}
+
+#[test]
+pub fn test_scheduled_payments_memory_db() {
+    test_scheduled_payments(TransactionMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_scheduled_payments_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    test_scheduled_payments(TransactionServiceSqliteDatabase::new(connection));
+}
+
+#[test]
+pub fn test_processed_messages_memory_db() {
+    test_processed_messages(TransactionMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_processed_messages_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    test_processed_messages(TransactionServiceSqliteDatabase::new(connection));
+}
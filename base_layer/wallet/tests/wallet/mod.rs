@@ -30,25 +30,47 @@ use tari_comms::{
 };
 use tari_comms_dht::DhtConfig;
 use tari_core::transactions::{tari_amount::MicroTari, types::CryptoFactories};
-use tari_crypto::keys::PublicKey;
+use tari_crypto::{
+    keys::{PublicKey, SecretKey},
+    tari_utilities::hex::Hex,
+};
 use tari_p2p::initialization::CommsConfig;
 use tari_test_utils::paths::with_temp_dir;
 
 use crate::support::comms_and_services::get_next_memory_address;
 use futures::{FutureExt, StreamExt};
 use std::path::Path;
-use tari_core::transactions::{tari_amount::uT, transaction::UnblindedOutput, types::PrivateKey};
+use tari_core::transactions::{
+    tari_amount::uT,
+    transaction::{OutputFeatures, UnblindedOutput},
+    types::PrivateKey,
+};
 use tari_p2p::transport::TransportType;
 use tari_wallet::{
+    auto_coin_split::{AutoCoinSplitPolicy, AutoCoinSplitter},
+    backup::BackupBundle,
+    clone_detection::{CloneDetectionReason, CloneDetector},
     contacts_service::storage::{database::Contact, memory_db::ContactsServiceMemoryDatabase},
     output_manager_service::storage::memory_db::OutputManagerMemoryDatabase,
-    storage::memory_db::WalletMemoryDatabase,
-    transaction_service::{handle::TransactionEvent, storage::memory_db::TransactionMemoryDatabase},
+    storage::{
+        database::{WalletAuditEvent, WalletEvent},
+        memory_db::WalletMemoryDatabase,
+    },
+    transaction_lifecycle::{
+        coordinator::TransactionLifecycleCoordinator,
+        lifecycle::{TransactionLifecycle, TransactionLifecycleState},
+    },
+    transaction_service::{
+        error::TransactionServiceError,
+        handle::TransactionEvent,
+        storage::memory_db::TransactionMemoryDatabase,
+    },
+    util::faucet::{FaucetClaims, FaucetUtxo},
     wallet::WalletConfig,
     Wallet,
 };
 use tempdir::TempDir;
-use tokio::{runtime::Runtime, time::delay_for};
+use tokio::{runtime::Runtime, sync::broadcast, time::delay_for};
 
 fn create_peer(public_key: CommsPublicKey, net_address: Multiaddr) -> Peer {
     Peer::new(
@@ -66,6 +88,17 @@ fn create_wallet(
     data_path: &Path,
     factories: CryptoFactories,
 ) -> Wallet<WalletMemoryDatabase, TransactionMemoryDatabase, OutputManagerMemoryDatabase, ContactsServiceMemoryDatabase>
+{
+    create_wallet_with_addresses(node_identity, data_path, factories, Vec::new(), Vec::new())
+}
+
+fn create_wallet_with_addresses(
+    node_identity: NodeIdentity,
+    data_path: &Path,
+    factories: CryptoFactories,
+    additional_listener_addresses: Vec<Multiaddr>,
+    additional_public_addresses: Vec<Multiaddr>,
+) -> Wallet<WalletMemoryDatabase, TransactionMemoryDatabase, OutputManagerMemoryDatabase, ContactsServiceMemoryDatabase>
 {
     let comms_config = CommsConfig {
         node_identity: Arc::new(node_identity.clone()),
@@ -83,6 +116,8 @@ fn create_wallet(
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses,
+        additional_public_addresses,
     };
     let config = WalletConfig {
         comms_config,
@@ -199,6 +234,31 @@ fn test_wallet() {
     });
 }
 
+#[test]
+fn test_wallet_additional_addresses() {
+    with_temp_dir(|dir_path| {
+        let factories = CryptoFactories::default();
+        let identity =
+            NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+        let additional_listener_address = get_next_memory_address();
+        let additional_public_address = get_next_memory_address();
+
+        let wallet = create_wallet_with_addresses(
+            identity.clone(),
+            dir_path,
+            factories,
+            vec![additional_listener_address],
+            vec![additional_public_address.clone()],
+        );
+
+        assert_eq!(wallet.comms.node_identity().public_addresses(), vec![
+            identity.public_address(),
+            additional_public_address
+        ]);
+        wallet.shutdown();
+    });
+}
+
 #[test]
 fn test_store_and_forward_send_tx() {
     let factories = CryptoFactories::default();
@@ -329,6 +389,8 @@ fn test_import_utxo() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses: Vec::new(),
+        additional_public_addresses: Vec::new(),
     };
     let config = WalletConfig {
         comms_config,
@@ -374,6 +436,435 @@ fn test_import_utxo() {
     assert_eq!(completed_tx.amount, 20000 * uT);
 }
 
+#[test]
+fn test_import_faucet_utxos() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let claim_key = PrivateKey::random(&mut OsRng);
+    let faucet_public_key = CommsPublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+    let claims = FaucetClaims {
+        network: "rincewind".to_string(),
+        source_public_key: faucet_public_key.to_hex(),
+        utxos: vec![
+            FaucetUtxo::new(
+                10000 * uT,
+                &PrivateKey::random(&mut OsRng),
+                OutputFeatures::default(),
+                &factories,
+            ),
+            FaucetUtxo::new(20000 * uT, &claim_key, OutputFeatures::with_maturity(2), &factories),
+        ],
+    }
+    .to_json()
+    .unwrap();
+
+    let tx_ids = alice_wallet.import_faucet_utxos(&claims, &[claim_key.clone()]).unwrap();
+    assert_eq!(tx_ids.len(), 1);
+
+    let unspent = alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.get_unspent_outputs())
+        .unwrap();
+    assert_eq!(unspent.len(), 1);
+    assert_eq!(unspent[0].value, 20000 * uT);
+    assert_eq!(unspent[0].spending_key, claim_key);
+    assert_eq!(unspent[0].features, OutputFeatures::with_maturity(2));
+
+    let completed_tx = alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_ids[0])
+        .expect("Tx should be in collection");
+    assert_eq!(completed_tx.source_public_key, faucet_public_key);
+
+    // The same claims file is only imported once
+    assert!(alice_wallet
+        .import_faucet_utxos(&claims, &[claim_key])
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_event_journal_replay() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let (replayed, mut journal_receiver) = alice_wallet.subscribe_to_event_journal(0).unwrap();
+    assert!(replayed.is_empty());
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(uo))
+        .unwrap();
+    let (_, bob_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    let tx_id = match alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.send_transaction(
+            bob_public_key,
+            MicroTari::from(1000),
+            MicroTari::from(10),
+            "".to_string(),
+        )) {
+        Ok(tx_id) | Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => tx_id,
+        Err(e) => panic!("Unexpected error sending the transaction: {:?}", e),
+    };
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.cancel_transaction(tx_id))
+        .unwrap();
+
+    // The cancellation is journaled and published to the subscriber with its sequence number
+    let cancelled = alice_wallet.runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(30)).fuse();
+        loop {
+            futures::select! {
+                entry = journal_receiver.recv().fuse() => {
+                    let entry = entry.unwrap();
+                    if entry.event == WalletEvent::TransactionService(TransactionEvent::TransactionCancelled(tx_id)) {
+                        break entry;
+                    }
+                },
+                () = delay => panic!("The cancellation was not journaled"),
+            }
+        }
+    });
+
+    // A subscriber that reconnects replays the events it missed from the journal
+    let (replayed, _) = alice_wallet.subscribe_to_event_journal(0).unwrap();
+    assert_eq!(
+        replayed.iter().map(|e| e.sequence).collect::<Vec<_>>(),
+        (1..=replayed.len() as u64).collect::<Vec<_>>()
+    );
+    assert!(replayed.contains(&*cancelled));
+    let (replayed, _) = alice_wallet.subscribe_to_event_journal(cancelled.sequence).unwrap();
+    assert_eq!(replayed[0], *cancelled);
+
+    let exported = alice_wallet.export_event_journal(cancelled.sequence).unwrap();
+    assert!(exported.contains("TransactionCancelled"));
+}
+
+#[test]
+fn test_wallet_clone_detection_locks_sending() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(uo))
+        .unwrap();
+    assert_eq!(alice_wallet.get_clone_detection().unwrap(), None);
+
+    // Two instances writing heartbeats to the same database
+    let (publisher, mut journal_receiver) = broadcast::channel(10);
+    let mut first = CloneDetector::new(
+        alice_wallet.db.clone(),
+        alice_wallet.transaction_service.clone(),
+        publisher.clone(),
+    );
+    let mut second = CloneDetector::new(
+        alice_wallet.db.clone(),
+        alice_wallet.transaction_service.clone(),
+        publisher,
+    );
+    assert!(!alice_wallet.runtime.block_on(first.heartbeat()).unwrap());
+    assert!(!alice_wallet.runtime.block_on(second.heartbeat()).unwrap());
+    assert!(alice_wallet.runtime.block_on(first.heartbeat()).unwrap());
+
+    let entry = alice_wallet.runtime.block_on(journal_receiver.recv()).unwrap();
+    match &entry.event {
+        WalletEvent::WalletCloneDetected(CloneDetectionReason::ConcurrentInstance { .. }) => {},
+        e => panic!("Unexpected journaled event: {:?}", e),
+    }
+    match alice_wallet.get_clone_detection().unwrap() {
+        Some(CloneDetectionReason::ConcurrentInstance { .. }) => {},
+        r => panic!("Unexpected clone detection: {:?}", r),
+    }
+
+    let (_, bob_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    match alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.send_transaction(
+            bob_public_key.clone(),
+            MicroTari::from(1000),
+            MicroTari::from(10),
+            "".to_string(),
+        )) {
+        Err(TransactionServiceError::SendingLocked) => {},
+        r => panic!("Sending was not locked: {:?}", r),
+    }
+
+    // Sending is unlocked once the detection is acknowledged
+    alice_wallet.acknowledge_clone_detection().unwrap();
+    assert_eq!(alice_wallet.get_clone_detection().unwrap(), None);
+    match alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.send_transaction(
+            bob_public_key,
+            MicroTari::from(1000),
+            MicroTari::from(10),
+            "".to_string(),
+        )) {
+        Ok(_) | Err(TransactionServiceError::OutboundSendDiscoveryInProgress(_)) => {},
+        Err(e) => panic!("Unexpected error sending the transaction: {:?}", e),
+    }
+}
+
+#[test]
+fn test_backup_bundle() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity.clone(), &db_tempdir.path(), factories);
+    let (_, base_node_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    alice_wallet
+        .set_base_node_peer(base_node_public_key.clone(), get_next_memory_address().to_string())
+        .unwrap();
+
+    let encrypted = alice_wallet
+        .create_backup_bundle("correct horse battery staple")
+        .unwrap();
+    let bundle = BackupBundle::decrypt(&encrypted, "correct horse battery staple").unwrap();
+    assert_eq!(bundle.node_identity.public_key(), alice_identity.public_key());
+    assert_eq!(bundle.node_identity.secret_key(), alice_identity.secret_key());
+    assert_eq!(
+        bundle.settings.base_node.map(|(public_key, _)| public_key),
+        Some(base_node_public_key.to_hex())
+    );
+    assert_eq!(
+        alice_wallet
+            .runtime
+            .block_on(alice_wallet.db.get_last_backup_time())
+            .unwrap(),
+        Some(bundle.created_at)
+    );
+    assert!(BackupBundle::decrypt(&encrypted, "incorrect horse").is_err());
+}
+
+#[test]
+fn test_wallet_audit_log() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories);
+
+    let seed_words = alice_wallet.get_seed_words(None).unwrap();
+    assert_eq!(seed_words.len(), 24);
+    alice_wallet
+        .apply_encryption("correct horse battery staple".to_string())
+        .unwrap();
+    alice_wallet.remove_encryption().unwrap();
+    // A failed attempt to toggle encryption is not recorded
+    assert!(alice_wallet.remove_encryption().is_err());
+
+    // The send is recorded as approved before the transaction is created, even if creating it then fails
+    let (_, bob_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    assert!(alice_wallet
+        .send_transaction(
+            bob_public_key.clone(),
+            MicroTari::from(1000),
+            MicroTari::from(25),
+            "".to_string()
+        )
+        .is_err());
+
+    let events = alice_wallet
+        .runtime
+        .block_on(alice_wallet.db.get_audit_log())
+        .unwrap()
+        .into_iter()
+        .map(|e| e.event)
+        .collect::<Vec<_>>();
+    assert_eq!(events, vec![
+        WalletAuditEvent::SeedExported,
+        WalletAuditEvent::EncryptionToggled { enabled: true },
+        WalletAuditEvent::EncryptionToggled { enabled: false },
+        WalletAuditEvent::SendApproved {
+            destination_public_key: bob_public_key.to_hex(),
+            amount: MicroTari::from(1000),
+        },
+    ]);
+}
+
+#[test]
+fn test_transaction_lifecycle_cancellation() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(uo))
+        .unwrap();
+    let (_, bob_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    let tx_id = match alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.send_transaction(
+            bob_public_key,
+            MicroTari::from(1000),
+            MicroTari::from(10),
+            "".to_string(),
+        )) {
+        Ok(tx_id) | Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => tx_id,
+        Err(e) => panic!("Unexpected error sending the transaction: {:?}", e),
+    };
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.cancel_transaction(tx_id))
+        .unwrap();
+
+    // The coordinator records the cancellation and the outputs of the transaction are released
+    let mut state = None;
+    for _ in 0..30 {
+        if let Ok(lifecycle) = alice_wallet.get_transaction_lifecycle(tx_id) {
+            if lifecycle.state == TransactionLifecycleState::Cancelled {
+                state = Some(lifecycle.state);
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    assert_eq!(state, Some(TransactionLifecycleState::Cancelled));
+    let balance = alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.get_balance())
+        .unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(10_000));
+
+    assert!(alice_wallet.get_transaction_lifecycle(tx_id + 1).is_err());
+}
+
+#[test]
+fn test_transaction_lifecycle_refused_transition() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(uo))
+        .unwrap();
+    let stp = alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.prepare_transaction_to_send(
+            MicroTari::from(1000),
+            MicroTari::from(10),
+            None,
+            "".to_string(),
+        ))
+        .unwrap();
+    let tx_id = stp.get_tx_id().unwrap();
+    alice_wallet
+        .runtime
+        .block_on(
+            alice_wallet
+                .db
+                .save_transaction_lifecycle(TransactionLifecycle::new(tx_id, TransactionLifecycleState::Cancelled)),
+        )
+        .unwrap();
+
+    // A late event can't move a transaction out of a final state, and the refused transition leaves the outputs of
+    // the transaction alone
+    let mut coordinator = TransactionLifecycleCoordinator::new(
+        alice_wallet.db.clone(),
+        alice_wallet.transaction_service.clone(),
+        alice_wallet.output_manager_service.clone(),
+    );
+    let lifecycle = alice_wallet
+        .runtime
+        .block_on(coordinator.advance(tx_id, TransactionLifecycleState::Mined))
+        .unwrap();
+    assert_eq!(lifecycle.state, TransactionLifecycleState::Cancelled);
+    assert!(alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.get_pending_transaction(tx_id))
+        .is_ok());
+}
+
+#[test]
+fn test_auto_coin_split() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity, &db_tempdir.path(), factories.clone());
+
+    let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(10_000), &factories.commitment);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(uo))
+        .unwrap();
+    let mut splitter = AutoCoinSplitter::new(
+        alice_wallet.db.clone(),
+        alice_wallet.output_manager_service.clone(),
+        alice_wallet.transaction_service.clone(),
+    );
+
+    // Nothing happens without a policy
+    assert_eq!(alice_wallet.runtime.block_on(splitter.check()).unwrap(), None);
+
+    let policy = AutoCoinSplitPolicy {
+        min_spendable_outputs: 4,
+        target_output_value: MicroTari::from(1000),
+        fee_per_gram: MicroTari::from(10),
+    };
+    alice_wallet.set_auto_coin_split_policy(Some(policy.clone())).unwrap();
+    assert_eq!(alice_wallet.get_auto_coin_split_policy().unwrap(), Some(policy));
+
+    // The single output is split into three outputs of the target value and the change
+    let tx_id = alice_wallet
+        .runtime
+        .block_on(splitter.check())
+        .unwrap()
+        .expect("A coin split should have been made");
+    let pending = alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.get_pending_transaction(tx_id))
+        .unwrap();
+    assert_eq!(pending.outputs_to_be_received.len(), 4);
+    assert_eq!(
+        pending
+            .outputs_to_be_received
+            .iter()
+            .filter(|o| o.value == MicroTari::from(1000))
+            .count(),
+        3
+    );
+    let completed_tx = alice_wallet
+        .runtime
+        .block_on(alice_wallet.transaction_service.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("The coin split should have been submitted");
+    assert_eq!(completed_tx.message, "Automatic coin split");
+
+    // The outputs of the pending split count as spendable, so no second split is made
+    assert_eq!(alice_wallet.runtime.block_on(splitter.check()).unwrap(), None);
+
+    alice_wallet.set_auto_coin_split_policy(None).unwrap();
+    assert_eq!(alice_wallet.get_auto_coin_split_policy().unwrap(), None);
+}
+
 #[cfg(feature = "test_harness")]
 #[test]
 fn test_data_generation() {
@@ -399,6 +890,8 @@ fn test_data_generation() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        additional_listener_addresses: Vec::new(),
+        additional_public_addresses: Vec::new(),
     };
 
     let config = WalletConfig {
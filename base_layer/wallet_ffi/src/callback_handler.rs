@@ -203,7 +203,7 @@ where TBackend: TransactionBackend + 'static
                 msg = self.output_manager_service_event_stream.select_next_some() => {
                     trace!(target: LOG_TARGET, "Output Manager Service Callback Handler event {:?}", msg);
                     match (*msg).clone() {
-                        OutputManagerEvent::ReceiveBaseNodeResponse(request_key) => {
+                        OutputManagerEvent::SyncCompleted { request_key, .. } => {
                             self.receive_sync_process_result(request_key, true);
                         },
                         OutputManagerEvent::BaseNodeSyncRequestTimedOut(request_key) => {
@@ -55,6 +55,8 @@ pub enum InterfaceError {
     DeserializationError(String),
     /// Emoji ID is invalid
     InvalidEmojiId,
+    /// The fee preset is not one of slow (0), normal (1) or fast (2)
+    InvalidFeePriority,
 }
 
 /// This struct is meant to hold an error for use by FFI client applications. The error has an integer code and string
@@ -93,6 +95,10 @@ impl From<InterfaceError> for LibWalletError {
                 code: 6,
                 message: format!("{:?}", v),
             },
+            InterfaceError::InvalidFeePriority => Self {
+                code: 7,
+                message: format!("{:?}", v),
+            },
         }
     }
 }
@@ -174,6 +180,16 @@ impl From<WalletError> for LibWalletError {
                 code: 113,
                 message: format!("{:?}", w),
             },
+            WalletError::OutputManagerError(OutputManagerError::FundsPendingMaturity) => Self {
+                code: 114,
+                message: format!("{:?}", w),
+            },
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
+                OutputManagerError::FundsPendingMaturity,
+            )) => Self {
+                code: 114,
+                message: format!("{:?}", w),
+            },
             // Transaction Service Errors
             WalletError::TransactionServiceError(TransactionServiceError::InvalidStateError) => Self {
                 code: 201,
@@ -130,6 +130,7 @@ use log4rs::{
 use rand::rngs::OsRng;
 use std::{
     boxed::Box,
+    convert::TryFrom,
     ffi::{CStr, CString},
     path::PathBuf,
     slice,
@@ -164,14 +165,16 @@ use tari_wallet::{
         mine_transaction,
         receive_test_transaction,
     },
-    transaction_service::storage::{
-        database::{InboundTransaction, OutboundTransaction, TransactionDatabase, TransactionStatus},
-        sqlite_db::TransactionServiceSqliteDatabase,
+    transaction_service::{
+        handle::FeeEstimator,
+        storage::{
+            database::{FeePriority, InboundTransaction, OutboundTransaction, TransactionDatabase, TransactionStatus},
+            sqlite_db::TransactionServiceSqliteDatabase,
+        },
     },
-    util::emoji::EmojiId,
+    util::{emoji::EmojiId, runtime::RuntimeConfig},
     wallet::WalletConfig,
 };
-use tokio::runtime::Runtime;
 
 const LOG_TARGET: &str = "wallet_ffi";
 
@@ -1280,6 +1283,8 @@ pub unsafe extern "C" fn completed_transaction_get_source_public_key(
 /// |   2 | Mined       |
 /// |   3 | Imported    |
 /// |   4 | Pending     |
+/// |   5 | Cancelled   |
+/// |   6 | Reconstructed |
 ///
 /// # Safety
 /// None
@@ -1308,7 +1313,7 @@ pub unsafe extern "C" fn completed_transaction_get_status(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the amount, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the amount in MicroTari, note that it will be zero if transaction is null
 ///
 /// # Safety
 /// None
@@ -1336,7 +1341,7 @@ pub unsafe extern "C" fn completed_transaction_get_amount(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the fee, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the fee in MicroTari, note that it will be zero if transaction is null
 ///
 /// # Safety
 /// None
@@ -1356,6 +1361,35 @@ pub unsafe extern "C" fn completed_transaction_get_fee(
     c_ulonglong::from((*transaction).fee)
 }
 
+/// Gets the fee preset of a TariCompletedTransaction
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_int` - Returns the fee preset the transaction was sent with: 0 for slow, 1 for normal and 2 for fast. Returns -1
+/// if the transaction was not sent with a fee preset or if transaction is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_get_fee_priority(
+    transaction: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> c_int
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return -1;
+    }
+    (*transaction).fee_priority.map(|p| p as c_int).unwrap_or(-1)
+}
+
 /// Gets the timestamp of a TariCompletedTransaction
 ///
 /// ## Arguments
@@ -1504,7 +1538,7 @@ pub unsafe extern "C" fn pending_outbound_transaction_get_destination_public_key
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the amount, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the amount in MicroTari, note that it will be zero if transaction is null
 ///
 /// # Safety
 /// None
@@ -1532,7 +1566,7 @@ pub unsafe extern "C" fn pending_outbound_transaction_get_amount(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the fee, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the fee in MicroTari, note that it will be zero if transaction is null
 ///
 /// # Safety
 /// None
@@ -1552,6 +1586,35 @@ pub unsafe extern "C" fn pending_outbound_transaction_get_fee(
     c_ulonglong::from((*transaction).fee)
 }
 
+/// Gets the fee preset of a TariPendingOutboundTransaction
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariPendingOutboundTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_int` - Returns the fee preset the transaction was sent with: 0 for slow, 1 for normal and 2 for fast. Returns -1
+/// if the transaction was not sent with a fee preset or if transaction is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn pending_outbound_transaction_get_fee_priority(
+    transaction: *mut TariPendingOutboundTransaction,
+    error_out: *mut c_int,
+) -> c_int
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return -1;
+    }
+    (*transaction).fee_priority.map(|p| p as c_int).unwrap_or(-1)
+}
+
 /// Gets the timestamp of a TariPendingOutboundTransaction
 ///
 /// ## Arguments
@@ -1738,7 +1801,7 @@ pub unsafe extern "C" fn pending_inbound_transaction_get_source_public_key(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - Returns the amount, note that it will be zero if transaction is null
+/// `c_ulonglong` - Returns the amount in MicroTari, note that it will be zero if transaction is null
 ///
 /// # Safety
 /// None
@@ -2214,6 +2277,8 @@ pub unsafe extern "C" fn comms_config_create(
                         allow_test_addresses: true,
                         listener_liveness_whitelist_cidrs: Vec::new(),
                         listener_liveness_max_sessions: 0,
+                        additional_listener_addresses: Vec::new(),
+                        additional_public_addresses: Vec::new(),
                     };
 
                     Box::into_raw(Box::new(config))
@@ -2328,7 +2393,7 @@ pub unsafe extern "C" fn wallet_create(
         debug!(target: LOG_TARGET, "Logging started");
     }
 
-    let runtime = Runtime::new();
+    let runtime = RuntimeConfig::default().build_runtime();
     let factories = CryptoFactories::default();
     let w;
 
@@ -2984,7 +3049,7 @@ pub unsafe extern "C" fn wallet_remove_contact(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The available balance, 0 if wallet is null
+/// `c_ulonglong` - The available balance in MicroTari, 0 if wallet is null
 ///
 /// # Safety
 /// None
@@ -3020,7 +3085,7 @@ pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, e
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The incoming balance, 0 if wallet is null
+/// `c_ulonglong` - The incoming balance in MicroTari, 0 if wallet is null
 ///
 /// # Safety
 /// None
@@ -3060,7 +3125,7 @@ pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The outgoing balance, 0 if wallet is null
+/// `c_ulonglong` - The outgoing balance in MicroTari, 0 if wallet is null
 ///
 /// # Safety
 /// None
@@ -3096,8 +3161,8 @@ pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
 /// `dest_public_key` - The TariPublicKey pointer of the peer
-/// `amount` - The amount
-/// `fee_per_gram` - The transaction fee
+/// `amount_micro_tari` - The amount in MicroTari
+/// `fee_per_gram_micro_tari` - The transaction fee per gram in MicroTari
 /// `message` - The pointer to a char array
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
@@ -3111,8 +3176,8 @@ pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
 pub unsafe extern "C" fn wallet_send_transaction(
     wallet: *mut TariWallet,
     dest_public_key: *mut TariPublicKey,
-    amount: c_ulonglong,
-    fee_per_gram: c_ulonglong,
+    amount_micro_tari: c_ulonglong,
+    fee_per_gram_micro_tari: c_ulonglong,
     message: *const c_char,
     error_out: *mut c_int,
 ) -> c_ulonglong
@@ -3139,23 +3204,265 @@ pub unsafe extern "C" fn wallet_send_transaction(
         CString::new("").unwrap().to_str().unwrap().to_owned()
     };
 
-    match (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.send_transaction(
-            (*dest_public_key).clone(),
-            MicroTari::from(amount),
-            MicroTari::from(fee_per_gram),
-            message_string,
-        )) {
+    match (*wallet).send_transaction(
+        (*dest_public_key).clone(),
+        MicroTari::from(amount_micro_tari),
+        MicroTari::from(fee_per_gram_micro_tari),
+        message_string,
+    ) {
         Ok(tx_id) => tx_id,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Sends a TariPendingOutboundTransaction with the fee per gram resolved from a fee preset. The fee per gram is taken
+/// from the fee estimator callback if one has been set, otherwise the wallet's configured fee for the preset is used.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `dest_public_key` - The TariPublicKey pointer of the peer
+/// `amount_micro_tari` - The amount in MicroTari
+/// `fee_priority` - The fee preset: 0 for slow, 1 for normal and 2 for fast
+/// `message` - The pointer to a char array
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `unsigned long long` - Returns 0 if unsuccessful or the TxId of the sent transaction if successful
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_send_transaction_with_priority(
+    wallet: *mut TariWallet,
+    dest_public_key: *mut TariPublicKey,
+    amount_micro_tari: c_ulonglong,
+    fee_priority: c_uint,
+    message: *const c_char,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    if dest_public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    let fee_priority = match FeePriority::try_from(fee_priority as i32) {
+        Ok(p) => p,
+        Err(_) => {
+            error = LibWalletError::from(InterfaceError::InvalidFeePriority).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return 0;
+        },
+    };
+
+    let message_string = if !message.is_null() {
+        CStr::from_ptr(message).to_str().unwrap().to_owned()
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        CString::new("").unwrap().to_str().unwrap().to_owned()
+    };
+
+    match (*wallet).send_transaction_with_priority(
+        (*dest_public_key).clone(),
+        MicroTari::from(amount_micro_tari),
+        fee_priority,
+        message_string,
+    ) {
+        Ok(tx_id) => tx_id,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             0
         },
     }
 }
 
+/// Sets the callback used to resolve fee presets to a fee per gram when a transaction is sent with
+/// `wallet_send_transaction_with_priority`
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `callback_fee_estimate` - The callback function pointer matching the function signature. It is called with the fee
+/// preset (0 for slow, 1 for normal and 2 for fast) and must return the fee per gram in MicroTari, or 0 to use the
+/// wallet's configured fee for the preset
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+///
+/// # Safety
+/// The callback may be called from any thread
+#[no_mangle]
+pub unsafe extern "C" fn wallet_set_fee_estimator_callback(
+    wallet: *mut TariWallet,
+    callback_fee_estimate: unsafe extern "C" fn(c_uint) -> c_ulonglong,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let estimator = FeeEstimator::new(move |priority| match callback_fee_estimate(priority as c_uint) {
+        0 => None,
+        fee_per_gram => Some(MicroTari::from(fee_per_gram)),
+    });
+
+    match (*wallet).set_fee_estimator(Some(estimator)) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Exports the wallet audit log, a record of security-relevant actions such as sends and base node changes
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the audit log as a JSON array in a pointer to a char array, array will be empty on error
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_export_audit_log(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut log = CString::new("").unwrap();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return log.into_raw();
+    }
+
+    match (*wallet).export_audit_log() {
+        Ok(json) => log = CString::new(json).unwrap(),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+
+    log.into_raw()
+}
+
+/// Exports the wallet event journal, a record of every transaction and output manager event emitted by the wallet, so
+/// that a client that was disconnected or restarted can catch up on the notifications it missed
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `from_sequence` - The sequence number of the first journaled event to export, events are numbered from 1
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the journaled events as a JSON array of entries with a `sequence` number, a `timestamp` and
+/// the `event`, in a pointer to a char array, array will be empty on error
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with a string from rust to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_export_event_journal(
+    wallet: *mut TariWallet,
+    from_sequence: c_ulonglong,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut journal = CString::new("").unwrap();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return journal.into_raw();
+    }
+
+    match (*wallet).export_event_journal(from_sequence) {
+        Ok(json) => journal = CString::new(json).unwrap(),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+
+    journal.into_raw()
+}
+
+/// Gets the lifecycle state of a transaction. The lifecycle spans the negotiation, the encumbering of its outputs, the
+/// broadcast and the mining of the transaction and is the canonical state of the transaction across the wallet
+/// services.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `transaction_id` - The TransactionId
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_int` - Returns the lifecycle state which corresponds to:
+/// | Value | Interpretation |
+/// |---|---|
+/// |  -1 | Error, e.g. the transaction is unknown |
+/// |   0 | Negotiating |
+/// |   1 | Encumbered  |
+/// |   2 | Broadcast   |
+/// |   3 | Mined       |
+/// |   4 | Confirmed   |
+/// |   5 | Cancelled   |
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_transaction_lifecycle_state(
+    wallet: *mut TariWallet,
+    transaction_id: c_ulonglong,
+    error_out: *mut c_int,
+) -> c_int
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return -1;
+    }
+
+    match (*wallet).get_transaction_lifecycle(transaction_id) {
+        Ok(lifecycle) => lifecycle.state as c_int,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            -1
+        },
+    }
+}
+
 /// Get the TariContacts from a TariWallet
 ///
 /// ## Arguments
@@ -3613,7 +3920,7 @@ pub unsafe extern "C" fn wallet_get_public_key(wallet: *mut TariWallet, error_ou
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `amount` - The value of the UTXO in MicroTari
+/// `amount_micro_tari` - The value of the UTXO in MicroTari
 /// `spending_key` - The private spending key  
 /// `source_public_key` - The public key of the source of the transaction
 /// `message` - The message that the transaction will have
@@ -3629,7 +3936,7 @@ pub unsafe extern "C" fn wallet_get_public_key(wallet: *mut TariWallet, error_ou
 #[no_mangle]
 pub unsafe extern "C" fn wallet_import_utxo(
     wallet: *mut TariWallet,
-    amount: c_ulonglong,
+    amount_micro_tari: c_ulonglong,
     spending_key: *mut TariPrivateKey,
     source_public_key: *mut TariPublicKey,
     message: *const c_char,
@@ -3665,7 +3972,7 @@ pub unsafe extern "C" fn wallet_import_utxo(
     };
 
     match (*wallet).import_utxo(
-        MicroTari::from(amount),
+        MicroTari::from(amount_micro_tari),
         &(*spending_key).clone(),
         &(*source_public_key).clone(),
         message_string,
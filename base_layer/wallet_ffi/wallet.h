@@ -190,12 +190,15 @@ struct TariPublicKey *completed_transaction_get_destination_public_key(struct Ta
 // Gets the source TariPublicKey of a TariCompletedTransaction
 struct TariPublicKey *completed_transaction_get_source_public_key(struct TariCompletedTransaction *transaction,int* error_out);
 
-// Gets the amount of a TariCompletedTransaction
+// Gets the amount in MicroTari of a TariCompletedTransaction
 unsigned long long completed_transaction_get_amount(struct TariCompletedTransaction *transaction,int* error_out);
 
-// Gets the fee of a TariCompletedTransaction
+// Gets the fee in MicroTari of a TariCompletedTransaction
 unsigned long long completed_transaction_get_fee(struct TariCompletedTransaction *transaction,int* error_out);
 
+// Gets the fee preset of a TariCompletedTransaction, -1 if it was not sent with a fee preset
+int completed_transaction_get_fee_priority(struct TariCompletedTransaction *transaction,int* error_out);
+
 // Gets the message of a TariCompletedTransaction
 const char *completed_transaction_get_message(struct TariCompletedTransaction *transaction,int* error_out);
 
@@ -208,6 +211,8 @@ const char *completed_transaction_get_message(struct TariCompletedTransaction *t
 // |   2 | Mined       |
 // |   3 | Imported    |
 // |   4 | Pending     |
+// |   5 | Cancelled   |
+// |   6 | Reconstructed |
 int completed_transaction_get_status(struct TariCompletedTransaction *transaction,int* error_out);
 
 // Gets the TransactionID of a TariCompletedTransaction
@@ -238,12 +243,15 @@ unsigned long long pending_outbound_transaction_get_transaction_id(struct TariPe
 // Gets the destination TariPublicKey of a TariPendingOutboundTransaction
 struct TariPublicKey *pending_outbound_transaction_get_destination_public_key(struct TariPendingOutboundTransaction *transaction,int* error_out);
 
-// Gets the amount of a TariPendingOutboundTransaction
+// Gets the amount in MicroTari of a TariPendingOutboundTransaction
 unsigned long long pending_outbound_transaction_get_amount(struct TariPendingOutboundTransaction *transaction,int* error_out);
 
-// Gets the fee of a TariPendingOutboundTransaction
+// Gets the fee in MicroTari of a TariPendingOutboundTransaction
 unsigned long long pending_outbound_transaction_get_fee(struct TariPendingOutboundTransaction *transaction,int* error_out);
 
+// Gets the fee preset of a TariPendingOutboundTransaction, -1 if it was not sent with a fee preset
+int pending_outbound_transaction_get_fee_priority(struct TariPendingOutboundTransaction *transaction,int* error_out);
+
 // Gets the message of a TariPendingOutboundTransaction
 const char *pending_outbound_transaction_get_message(struct TariPendingOutboundTransaction *transaction,int* error_out);
 
@@ -286,7 +294,7 @@ struct TariPublicKey *pending_inbound_transaction_get_source_public_key(struct T
 // Gets the message of a TariPendingInboundTransaction
 const char *pending_inbound_transaction_get_message(struct TariPendingInboundTransaction *transaction,int* error_out);
 
-// Gets the amount of a TariPendingInboundTransaction
+// Gets the amount in MicroTari of a TariPendingInboundTransaction
 unsigned long long pending_inbound_transaction_get_amount(struct TariPendingInboundTransaction *transaction,int* error_out);
 
 // Gets the timestamp of a TariPendingInboundTransaction
@@ -364,17 +372,32 @@ bool wallet_upsert_contact(struct TariWallet *wallet, struct TariContact *contac
 // Removes a TariContact form the TariWallet
 bool wallet_remove_contact(struct TariWallet *wallet, struct TariContact *contact,int* error_out);
 
-// Gets the available balance from a TariWallet
+// Gets the available balance in MicroTari from a TariWallet
 unsigned long long wallet_get_available_balance(struct TariWallet *wallet,int* error_out);
 
-// Gets the incoming balance from a TariWallet
+// Gets the incoming balance in MicroTari from a TariWallet
 unsigned long long wallet_get_pending_incoming_balance(struct TariWallet *wallet,int* error_out);
 
-// Gets the outgoing balance from a TariWallet
+// Gets the outgoing balance in MicroTari from a TariWallet
 unsigned long long wallet_get_pending_outgoing_balance(struct TariWallet *wallet,int* error_out);
 
 // Sends a TariPendingOutboundTransaction
-unsigned long long wallet_send_transaction(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount, unsigned long long fee_per_gram,const char *message,int* error_out);
+unsigned long long wallet_send_transaction(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount_micro_tari, unsigned long long fee_per_gram_micro_tari,const char *message,int* error_out);
+
+// Sends a TariPendingOutboundTransaction with the fee resolved from a fee preset (0 slow, 1 normal, 2 fast)
+unsigned long long wallet_send_transaction_with_priority(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount_micro_tari, unsigned int fee_priority,const char *message,int* error_out);
+
+// Sets the callback that resolves a fee preset to a fee per gram in MicroTari, returning 0 uses the configured fee
+bool wallet_set_fee_estimator_callback(struct TariWallet *wallet, unsigned long long (*callback_fee_estimate)(unsigned int),int* error_out);
+
+// Exports the wallet audit log as a JSON array, must be freed with string_destroy
+char *wallet_export_audit_log(struct TariWallet *wallet,int* error_out);
+
+// Exports the wallet event journal from a sequence number on as a JSON array, must be freed with string_destroy
+char *wallet_export_event_journal(struct TariWallet *wallet,unsigned long long from_sequence,int* error_out);
+
+// Gets the lifecycle state of a transaction, the single source of truth for where the transaction is
+int wallet_get_transaction_lifecycle_state(struct TariWallet *wallet,unsigned long long transaction_id,int* error_out);
 
 // Get the TariContacts from a TariWallet
 struct TariContacts *wallet_get_contacts(struct TariWallet *wallet,int* error_out);
@@ -409,7 +432,7 @@ bool wallet_is_completed_transaction_outbound(struct TariWallet *wallet, struct
 
 // Import a UTXO into the wallet. This will add a spendable UTXO and create a faux completed transaction to record the
 // event.
-unsigned long long wallet_import_utxo(struct TariWallet *wallet, unsigned long long amount, struct TariPrivateKey *spending_key, struct TariPublicKey *source_public_key, const char *message, int* error_out);
+unsigned long long wallet_import_utxo(struct TariWallet *wallet, unsigned long long amount_micro_tari, struct TariPrivateKey *spending_key, struct TariPublicKey *source_public_key, const char *message, int* error_out);
 
 // This function will tell the wallet to query the set base node to confirm the status of wallet data.
 unsigned long long wallet_sync_with_base_node(struct TariWallet *wallet, int* error_out);
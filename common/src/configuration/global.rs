@@ -23,7 +23,7 @@
 //! # Global configuration of tari base layer system
 
 use super::ConfigurationError;
-use config::{Config, Environment};
+use config::{Config, ConfigError, Environment};
 use multiaddr::Multiaddr;
 use std::{
     convert::TryInto,
@@ -47,9 +47,15 @@ pub struct GlobalConfig {
     pub blocking_threads: usize,
     pub identity_file: PathBuf,
     pub public_address: Multiaddr,
+    pub additional_public_addresses: Vec<Multiaddr>,
+    pub additional_listener_addresses: Vec<Multiaddr>,
     pub peer_seeds: Vec<String>,
     pub peer_db_path: PathBuf,
     pub block_sync_strategy: String,
+    pub preferred_sync_peers: Vec<String>,
+    pub max_allowed_reorg_depth: Option<u64>,
+    pub min_free_disk_space_mb: Option<u64>,
+    pub max_orphan_age_secs: Option<u64>,
     pub enable_mining: bool,
     pub num_mining_threads: usize,
     pub tor_identity_file: PathBuf,
@@ -57,6 +63,7 @@ pub struct GlobalConfig {
     pub wallet_identity_file: PathBuf,
     pub wallet_tor_identity_file: PathBuf,
     pub wallet_peer_db_path: PathBuf,
+    pub wallet_recovery_scan_values: Vec<u64>,
 }
 
 impl GlobalConfig {
@@ -149,6 +156,12 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
                 .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
         })?;
 
+    // Additional public and listener addresses
+    let key = config_string(&net_str, "additional_public_addresses");
+    let additional_public_addresses = multiaddr_array(&cfg, &key)?;
+    let key = config_string(&net_str, "additional_listener_addresses");
+    let additional_listener_addresses = multiaddr_array(&cfg, &key)?;
+
     // Peer seeds
     let key = config_string(&net_str, "peer_seeds");
     let peer_seeds = cfg
@@ -165,6 +178,49 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         .get_str(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
 
+    // Preferred sync peers
+    let key = config_string(&net_str, "preferred_sync_peers");
+    let preferred_sync_peers = cfg
+        .get_array(&key)
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+    let preferred_sync_peers = preferred_sync_peers
+        .into_iter()
+        .map(|v| v.into_str().map_err(|e| ConfigurationError::new(&key, &e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Deep reorg protection, a value of 0 disables the check
+    let key = config_string(&net_str, "max_allowed_reorg_depth");
+    let max_allowed_reorg_depth = cfg
+        .get_int(&key)
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+    let max_allowed_reorg_depth = if max_allowed_reorg_depth > 0 {
+        Some(max_allowed_reorg_depth as u64)
+    } else {
+        None
+    };
+
+    // Low disk space protection, a value of 0 disables the check
+    let key = config_string(&net_str, "min_free_disk_space_mb");
+    let min_free_disk_space_mb = cfg
+        .get_int(&key)
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+    let min_free_disk_space_mb = if min_free_disk_space_mb > 0 {
+        Some(min_free_disk_space_mb as u64)
+    } else {
+        None
+    };
+
+    // Missed orphan parent recovery, a value of 0 disables the check
+    let key = config_string(&net_str, "max_orphan_age_secs");
+    let max_orphan_age_secs = cfg
+        .get_int(&key)
+        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+    let max_orphan_age_secs = if max_orphan_age_secs > 0 {
+        Some(max_orphan_age_secs as u64)
+    } else {
+        None
+    };
+
     // set base node mining
     let key = config_string(&net_str, "enable_mining");
     let enable_mining = cfg
@@ -176,12 +232,30 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         .get_int(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as usize;
 
-    // set wallet_file
+    // set wallet_file, which is kept in the data directory of the network unless configured otherwise so that
+    // wallets of different networks are not mixed up
     let key = "wallet.wallet_file".to_string();
-    let wallet_db_file = cfg
-        .get_str(&key)
-        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
-        .into();
+    let wallet_db_file = match cfg.get_str(&key) {
+        Ok(path) => path.into(),
+        Err(ConfigError::NotFound(_)) => data_dir.join("wallet").join("wallet.dat"),
+        Err(e) => return Err(ConfigurationError::new(&key, &e.to_string())),
+    };
+
+    // The output values, in MicroTari, that a wallet recovery scan looks for
+    let key = "wallet.recovery_scan_values";
+    let wallet_recovery_scan_values = match cfg.get_array(key) {
+        Ok(values) => values
+            .into_iter()
+            .map(|v| {
+                v.into_int()
+                    .map_err(|e| ConfigurationError::new(key, &e.to_string()))?
+                    .try_into()
+                    .map_err(|e: TryFromIntError| ConfigurationError::new(key, &e.to_string()))
+            })
+            .collect::<Result<Vec<u64>, _>>()?,
+        Err(ConfigError::NotFound(_)) => Vec::new(),
+        Err(e) => return Err(ConfigurationError::new(key, &e.to_string())),
+    };
 
     let key = "common.liveness_max_sessions";
     let liveness_max_sessions = cfg
@@ -207,9 +281,15 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         blocking_threads,
         identity_file,
         public_address,
+        additional_public_addresses,
+        additional_listener_addresses,
         peer_seeds,
         peer_db_path,
         block_sync_strategy,
+        preferred_sync_peers,
+        max_allowed_reorg_depth,
+        min_free_disk_space_mb,
+        max_orphan_age_secs,
         enable_mining,
         num_mining_threads,
         tor_identity_file,
@@ -217,6 +297,7 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         wallet_db_file,
         wallet_tor_identity_file,
         wallet_peer_db_path,
+        wallet_recovery_scan_values,
     })
 }
 
@@ -315,6 +396,19 @@ fn config_string(network: &str, key: &str) -> String {
     format!("base_node.{}.{}", network, key)
 }
 
+fn multiaddr_array(cfg: &Config, key: &str) -> Result<Vec<Multiaddr>, ConfigurationError> {
+    cfg.get_array(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?
+        .into_iter()
+        .map(|v| {
+            v.into_str()
+                .map_err(|e| ConfigurationError::new(key, &e.to_string()))?
+                .parse::<Multiaddr>()
+                .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        })
+        .collect()
+}
+
 //---------------------------------------------       Network type        ------------------------------------------//
 #[derive(Clone, Debug, PartialEq)]
 pub enum Network {
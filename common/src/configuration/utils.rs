@@ -62,11 +62,6 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
     // Wallet settings
     cfg.set_default("wallet.grpc_enabled", false).unwrap();
     cfg.set_default("wallet.grpc_address", "tcp://127.0.0.1:18040").unwrap();
-    cfg.set_default(
-        "wallet.wallet_file",
-        default_subdir("wallet/wallet.dat", Some(&bootstrap.base_path)),
-    )
-    .unwrap();
 
     //---------------------------------- Mainnet Defaults --------------------------------------------//
 
@@ -78,6 +73,13 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
         .unwrap();
     cfg.set_default("base_node.mainnet.block_sync_strategy", "ViaBestChainMetadata")
         .unwrap();
+    cfg.set_default("base_node.mainnet.preferred_sync_peers", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default("base_node.mainnet.max_allowed_reorg_depth", 100)
+        .unwrap();
+    cfg.set_default("base_node.mainnet.min_free_disk_space_mb", 1024)
+        .unwrap();
+    cfg.set_default("base_node.mainnet.max_orphan_age_secs", 600).unwrap();
     cfg.set_default("base_node.mainnet.blocking_threads", 4).unwrap();
     cfg.set_default("base_node.mainnet.core_threads", 6).unwrap();
     cfg.set_default(
@@ -110,6 +112,10 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
         format!("{}/tcp/18041", local_ip_addr),
     )
     .unwrap();
+    cfg.set_default("base_node.mainnet.additional_public_addresses", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default("base_node.mainnet.additional_listener_addresses", Vec::<String>::new())
+        .unwrap();
     cfg.set_default("base_node.mainnet.grpc_enabled", false).unwrap();
     cfg.set_default("base_node.mainnet.grpc_address", "tcp://127.0.0.1:18041")
         .unwrap();
@@ -123,6 +129,13 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
         .unwrap();
     cfg.set_default("base_node.rincewind.block_sync_strategy", "ViaBestChainMetadata")
         .unwrap();
+    cfg.set_default("base_node.rincewind.preferred_sync_peers", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default("base_node.rincewind.max_allowed_reorg_depth", 0)
+        .unwrap();
+    cfg.set_default("base_node.rincewind.min_free_disk_space_mb", 1024)
+        .unwrap();
+    cfg.set_default("base_node.rincewind.max_orphan_age_secs", 600).unwrap();
     cfg.set_default("base_node.rincewind.blocking_threads", 4).unwrap();
     cfg.set_default("base_node.rincewind.core_threads", 4).unwrap();
     cfg.set_default(
@@ -155,6 +168,13 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
         format!("{}/tcp/18141", local_ip_addr),
     )
     .unwrap();
+    cfg.set_default("base_node.rincewind.additional_public_addresses", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default(
+        "base_node.rincewind.additional_listener_addresses",
+        Vec::<String>::new(),
+    )
+    .unwrap();
     cfg.set_default("base_node.rincewind.grpc_enabled", false).unwrap();
     cfg.set_default("base_node.rincewind.grpc_address", "tcp://127.0.0.1:18141")
         .unwrap();
@@ -156,6 +156,12 @@ impl SendMessageParams {
         self
     }
 
+    /// Set broadcast_strategy to Random, never selecting any of the excluded peers.
+    pub fn random_excluding(&mut self, n: usize, excluded_peers: Vec<NodeId>) -> &mut Self {
+        self.params_mut().broadcast_strategy = BroadcastStrategy::Random(n, excluded_peers);
+        self
+    }
+
     /// Set destination field in message header.
     pub fn with_destination(&mut self, destination: NodeDestination) -> &mut Self {
         self.params_mut().destination = destination;
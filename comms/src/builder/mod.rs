@@ -145,6 +145,13 @@ where
         self
     }
 
+    /// Listen on the given addresses in addition to the listener address. This allows a node to be reachable on more
+    /// than one address, for example both a clearnet address and a tor hidden service.
+    pub fn with_additional_listener_addresses(mut self, addresses: Vec<Multiaddr>) -> Self {
+        self.connection_manager_config.additional_listener_addresses = addresses;
+        self
+    }
+
     pub fn with_listener_liveness_max_sessions(mut self, max_sessions: usize) -> Self {
         self.connection_manager_config.liveness_max_sessions = max_sessions;
         self
@@ -25,6 +25,7 @@ use crate::{
     peer_manager::Peer,
 };
 use futures::channel::oneshot;
+use multiaddr::Multiaddr;
 use tari_shutdown::ShutdownSignal;
 
 /// The state of the dial request
@@ -37,6 +38,8 @@ pub struct DialState {
     cancel_signal: ShutdownSignal,
     /// Reply channel for a connection result
     pub reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
+    /// Addresses that could not be dialed, in the order they were attempted
+    failed_addresses: Vec<Multiaddr>,
 }
 
 impl DialState {
@@ -52,6 +55,7 @@ impl DialState {
             attempts: 0,
             reply_tx,
             cancel_signal,
+            failed_addresses: Vec::new(),
         }
     }
 
@@ -69,4 +73,14 @@ impl DialState {
     pub fn num_attempts(&self) -> usize {
         self.attempts
     }
+
+    /// Record that the given address could not be dialed
+    pub fn add_failed_address(&mut self, address: Multiaddr) -> &mut Self {
+        self.failed_addresses.push(address);
+        self
+    }
+
+    pub fn failed_addresses(&self) -> &[Multiaddr] {
+        &self.failed_addresses
+    }
 }
@@ -289,6 +289,23 @@ where
 
             let cancel_signal = dial_state.get_cancel_signal();
 
+            let successful_address = dial_result.as_ref().ok().map(|(_, addr)| addr);
+            if let Err(err) = peer_manager
+                .record_dial_result(
+                    &dial_state.peer.node_id,
+                    dial_state.failed_addresses(),
+                    successful_address,
+                )
+                .await
+            {
+                debug!(
+                    target: LOG_TARGET,
+                    "Unable to record dial result for peer '{}' because '{:?}'",
+                    dial_state.peer.node_id.short_str(),
+                    err
+                );
+            }
+
             match dial_result {
                 Ok((socket, addr)) => {
                     let authenticated_public_key =
@@ -464,7 +481,7 @@ where
     /// Returns ownership of the given `DialState` and a success or failure result for the dial,
     /// or None if the dial was cancelled inflight
     async fn dial_peer(
-        dial_state: DialState,
+        mut dial_state: DialState,
         noise_config: &NoiseConfig,
         transport: &TTransport,
     ) -> (
@@ -472,7 +489,10 @@ where
         Result<(NoiseSocket<TTransport::Output>, Multiaddr), ConnectionManagerError>,
     )
     {
-        let mut addr_iter = dial_state.peer.addresses.address_iter();
+        // Addresses are ordered by their connection stats, so addresses that have been dialed successfully are tried
+        // first
+        let addresses = dial_state.peer.addresses.address_iter().cloned().collect::<Vec<_>>();
+        let mut addr_iter = addresses.iter();
         let cancel_signal = dial_state.get_cancel_signal();
         loop {
             let result = match addr_iter.next() {
@@ -518,6 +538,7 @@ where
                                 dial_state.peer.node_id.short_str(),
                                 err,
                             );
+                            dial_state.add_failed_address(address.clone());
                             // Try the next address
                             continue;
                         },
@@ -47,7 +47,7 @@ use std::{
     convert::TryInto,
     mem,
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -68,8 +68,10 @@ pub struct PeerListener<TTransport> {
     peer_manager: Arc<PeerManager>,
     node_identity: Arc<NodeIdentity>,
     listening_address: Option<Multiaddr>,
+    is_additional: bool,
     our_supported_protocols: Vec<ProtocolId>,
     liveness_session_count: Arc<AtomicUsize>,
+    inbound_paused: Arc<AtomicBool>,
 }
 
 impl<TTransport> PeerListener<TTransport>
@@ -97,13 +99,28 @@ where
             node_identity,
             shutdown_signal,
             listening_address: None,
+            is_additional: false,
             our_supported_protocols: supported_protocols,
             bounded_executor: BoundedExecutor::from_current(config.max_simultaneous_inbound_connects),
             liveness_session_count: Arc::new(AtomicUsize::new(config.liveness_max_sessions)),
+            inbound_paused: Arc::new(AtomicBool::new(false)),
             config,
         }
     }
 
+    /// Mark this listener as one of the additional listeners of the connection manager. Additional listeners report
+    /// that they are listening with `ConnectionManagerEvent::AdditionalListening`.
+    pub fn into_additional_listener(mut self) -> Self {
+        self.is_additional = true;
+        self
+    }
+
+    /// Share a flag with the connection manager that, while set, makes this listener drop inbound connections
+    pub fn with_inbound_paused(mut self, inbound_paused: Arc<AtomicBool>) -> Self {
+        self.inbound_paused = inbound_paused;
+        self
+    }
+
     pub async fn run(mut self) {
         let mut shutdown_signal = self.shutdown_signal.clone();
 
@@ -115,13 +132,22 @@ where
                 info!(target: LOG_TARGET, "Listening for peer connections on '{}'", address);
                 self.listening_address = Some(address.clone());
 
-                self.send_event(ConnectionManagerEvent::Listening(address)).await;
+                if self.is_additional {
+                    self.send_event(ConnectionManagerEvent::AdditionalListening(address))
+                        .await;
+                } else {
+                    self.send_event(ConnectionManagerEvent::Listening(address)).await;
+                }
 
                 loop {
                     futures::select! {
                         inbound_result = inbound.select_next_some() => {
                             if let Some((inbound_future, peer_addr)) = log_if_error!(target: LOG_TARGET, inbound_result, "Inbound connection failed because '{error}'",) {
                                 if let Some(socket) = log_if_error!(target: LOG_TARGET, inbound_future.await,  "Inbound connection failed because '{error}'",) {
+                                    if self.inbound_paused.load(Ordering::SeqCst) {
+                                        debug!(target: LOG_TARGET, "Dropping inbound connection from '{}' because inbound connections are paused", peer_addr);
+                                        continue;
+                                    }
                                     self.spawn_listen_task(socket, peer_addr).await;
                                 }
                             }
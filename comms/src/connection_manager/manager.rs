@@ -48,7 +48,14 @@ use futures::{
 };
 use log::*;
 use multiaddr::Multiaddr;
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use time::Duration;
 use tokio::{sync::broadcast, task, time};
@@ -69,6 +76,7 @@ pub enum ConnectionManagerEvent {
 
     // Listener
     Listening(Multiaddr),
+    AdditionalListening(Multiaddr),
     ListenFailed(ConnectionManagerError),
 
     // Substreams
@@ -91,6 +99,7 @@ impl fmt::Display for ConnectionManagerEvent {
             ),
             PeerInboundConnectFailed(err) => write!(f, "PeerInboundConnectFailed({:?})", err),
             Listening(addr) => write!(f, "Listening({})", addr),
+            AdditionalListening(addr) => write!(f, "AdditionalListening({})", addr),
             ListenFailed(err) => write!(f, "ListenFailed({:?})", err),
             NewInboundSubstream(node_id, protocol, _) => write!(
                 f,
@@ -107,6 +116,9 @@ pub struct ConnectionManagerConfig {
     /// The address to listen on for incoming connections. This address must be supported by the transport.
     /// Default: DEFAULT_LISTENER_ADDRESS constant
     pub listener_address: Multiaddr,
+    /// Further addresses to listen on concurrently with `listener_address`, for example a clearnet address alongside
+    /// the address a tor hidden service forwards to. Each address must be supported by the transport. Default: None
+    pub additional_listener_addresses: Vec<Multiaddr>,
     /// The number of dial attempts to make before giving up. Default: 3
     pub max_dial_attempts: usize,
     /// The maximum number of connection tasks that will be spawned at the same time. Once this limit is reached, peers
@@ -131,6 +143,7 @@ impl Default for ConnectionManagerConfig {
             listener_address: DEFAULT_LISTENER_ADDRESS
                 .parse()
                 .expect("DEFAULT_LISTENER_ADDRESS is malformed"),
+            additional_listener_addresses: Vec::new(),
             max_dial_attempts: 3,
             max_simultaneous_inbound_connects: 20,
             disconnect_linger: Duration::from_secs(3),
@@ -152,16 +165,19 @@ pub struct ConnectionManager<TTransport, TBackoff> {
     internal_event_rx: Fuse<mpsc::Receiver<ConnectionManagerEvent>>,
     dialer_tx: mpsc::Sender<DialerRequest>,
     dialer: Option<Dialer<TTransport, TBackoff>>,
-    listener: Option<PeerListener<TTransport>>,
+    listeners: Vec<PeerListener<TTransport>>,
     peer_manager: Arc<PeerManager>,
     node_identity: Arc<NodeIdentity>,
     active_connections: HashMap<NodeId, PeerConnection>,
     shutdown_signal: Option<ShutdownSignal>,
     protocols: Protocols<yamux::Stream>,
     listener_address: Option<Multiaddr>,
+    additional_listener_addresses: Vec<Multiaddr>,
     listening_notifiers: Vec<oneshot::Sender<Multiaddr>>,
     connection_manager_events_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
     complete_trigger: Shutdown,
+    // Shared with the listeners, which drop inbound connections while this is set
+    inbound_paused: Arc<AtomicBool>,
 }
 
 impl<TTransport, TBackoff> ConnectionManager<TTransport, TBackoff>
@@ -190,7 +206,8 @@ where
 
         let supported_protocols = protocols.get_supported_protocols();
 
-        let listener = PeerListener::new(
+        let inbound_paused = Arc::new(AtomicBool::new(false));
+        let mut listeners = vec![PeerListener::new(
             config.clone(),
             transport.clone(),
             noise_config.clone(),
@@ -199,7 +216,29 @@ where
             Arc::clone(&node_identity),
             supported_protocols.clone(),
             shutdown_signal.clone(),
-        );
+        )
+        .with_inbound_paused(inbound_paused.clone())];
+        for address in &config.additional_listener_addresses {
+            let listener_config = ConnectionManagerConfig {
+                listener_address: address.clone(),
+                additional_listener_addresses: Vec::new(),
+                ..config.clone()
+            };
+            listeners.push(
+                PeerListener::new(
+                    listener_config,
+                    transport.clone(),
+                    noise_config.clone(),
+                    internal_event_tx.clone(),
+                    peer_manager.clone(),
+                    Arc::clone(&node_identity),
+                    supported_protocols.clone(),
+                    shutdown_signal.clone(),
+                )
+                .into_additional_listener()
+                .with_inbound_paused(inbound_paused.clone()),
+            );
+        }
 
         let dialer = Dialer::new(
             config.clone(),
@@ -224,12 +263,14 @@ where
             internal_event_rx: internal_event_rx.fuse(),
             dialer_tx,
             dialer: Some(dialer),
-            listener: Some(listener),
+            listeners,
             active_connections: Default::default(),
             listener_address: None,
+            additional_listener_addresses: Vec::new(),
             listening_notifiers: Vec::new(),
             connection_manager_events_tx,
             complete_trigger: Shutdown::new(),
+            inbound_paused,
         }
     }
 
@@ -243,7 +284,7 @@ where
             .take()
             .expect("ConnectionManager initialized without a shutdown");
 
-        self.run_listener();
+        self.run_listeners();
         self.run_dialer();
 
         debug!(target: LOG_TARGET, "Connection manager started");
@@ -293,13 +334,15 @@ where
         }
     }
 
-    fn run_listener(&mut self) {
-        let listener = self
-            .listener
-            .take()
-            .expect("ConnectionManager initialized without a listener");
-
-        runtime::current_executor().spawn(listener.run());
+    fn run_listeners(&mut self) {
+        assert!(
+            !self.listeners.is_empty(),
+            "ConnectionManager initialized without a listener"
+        );
+        let executor = runtime::current_executor();
+        for listener in self.listeners.drain(..) {
+            executor.spawn(listener.run());
+        }
     }
 
     fn run_dialer(&mut self) {
@@ -358,6 +401,15 @@ where
                     let _ = reply_tx.send(Ok(()));
                 },
             },
+            SetInboundConnectionsPaused(paused, reply_tx) => {
+                if paused {
+                    info!(target: LOG_TARGET, "Inbound peer connections are paused");
+                } else {
+                    info!(target: LOG_TARGET, "Inbound peer connections are resumed");
+                }
+                self.inbound_paused.store(paused, Ordering::SeqCst);
+                let _ = reply_tx.send(());
+            },
         }
     }
 
@@ -379,6 +431,10 @@ where
                     let _ = notifier.send(addr.clone());
                 }
             },
+            AdditionalListening(addr) => {
+                self.additional_listener_addresses.push(addr.clone());
+                self.publish_event(ConnectionManagerEvent::AdditionalListening(addr));
+            },
             NewInboundSubstream(node_id, protocol, stream) => {
                 let proto_str = String::from_utf8_lossy(&protocol);
                 debug!(
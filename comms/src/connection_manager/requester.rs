@@ -44,6 +44,8 @@ pub enum ConnectionManagerRequest {
     GetActiveConnections(oneshot::Sender<Vec<PeerConnection>>),
     /// Disconnect a peer
     DisconnectPeer(NodeId, oneshot::Sender<Result<(), ConnectionManagerError>>),
+    /// Stop or resume accepting inbound peer connections. Existing connections and outbound dials are not affected.
+    SetInboundConnectionsPaused(bool, oneshot::Sender<()>),
 }
 
 /// Responsible for constructing requests to the ConnectionManagerService
@@ -99,6 +101,8 @@ impl ConnectionManagerRequester {
 
     request_fn!(disconnect_peer(node_id: NodeId) -> Result<(), ConnectionManagerError>, request = ConnectionManagerRequest::DisconnectPeer);
 
+    request_fn!(set_inbound_connections_paused(paused: bool) -> (), request = ConnectionManagerRequest::SetInboundConnectionsPaused);
+
     /// Returns a ConnectionManagerEvent stream
     pub fn get_event_subscription(&self) -> broadcast::Receiver<Arc<ConnectionManagerEvent>> {
         self.event_tx.subscribe()
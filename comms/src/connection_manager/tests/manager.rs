@@ -29,6 +29,7 @@ use crate::{
         ConnectionManagerRequester,
         PeerConnectionError,
     },
+    multiaddr::Multiaddr,
     noise::NoiseConfig,
     peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags, PeerManagerError},
     protocol::{ProtocolEvent, ProtocolId, Protocols, IDENTITY_PROTOCOL},
@@ -172,6 +173,68 @@ async fn dial_success() {
     assert_eq!(buf, MSG);
 }
 
+#[tokio_macros::test_basic]
+async fn dial_additional_listener_address() {
+    let shutdown = Shutdown::new();
+
+    let node_identity1 = build_node_identity(PeerFeatures::empty());
+    let node_identity2 = build_node_identity(PeerFeatures::empty());
+
+    let peer_manager1 = build_peer_manager();
+    let mut conn_man1 = build_connection_manager(
+        TestNodeConfig {
+            node_identity: node_identity1.clone(),
+            ..Default::default()
+        },
+        peer_manager1.clone(),
+        Protocols::new(),
+        shutdown.to_signal(),
+    );
+    conn_man1.wait_until_listening().await.unwrap();
+
+    let additional_address: Multiaddr = format!("/memory/{}", MemoryTransport::acquire_next_memsocket_port())
+        .parse()
+        .unwrap();
+    let mut config = TestNodeConfig {
+        node_identity: node_identity2.clone(),
+        ..Default::default()
+    };
+    config.connection_manager_config.additional_listener_addresses = vec![additional_address.clone()];
+    let peer_manager2 = build_peer_manager();
+    let mut conn_man2 = build_connection_manager(config, peer_manager2.clone(), Protocols::new(), shutdown.to_signal());
+    let mut subscription2 = conn_man2.get_event_subscription();
+    conn_man2.wait_until_listening().await.unwrap();
+    let events = collect_stream!(subscription2, take = 2, timeout = Duration::from_secs(10));
+    assert!(events.iter().any(|event| match &**event.as_ref().unwrap() {
+        ConnectionManagerEvent::AdditionalListening(addr) => addr == &additional_address,
+        _ => false,
+    }));
+
+    // Nothing is listening on the first address so the dial only succeeds on the additional listener address
+    let unreachable_address: Multiaddr = format!("/memory/{}", MemoryTransport::acquire_next_memsocket_port())
+        .parse()
+        .unwrap();
+    peer_manager1
+        .add_peer(Peer::new(
+            node_identity2.public_key().clone(),
+            node_identity2.node_id().clone(),
+            vec![unreachable_address.clone(), additional_address.clone()].into(),
+            PeerFlags::empty(),
+            PeerFeatures::COMMUNICATION_CLIENT,
+            &[],
+        ))
+        .await
+        .unwrap();
+
+    let conn_out = conn_man1.dial_peer(node_identity2.node_id().clone()).await.unwrap();
+    assert_eq!(conn_out.address(), &additional_address);
+
+    // The address that was dialed successfully is now preferred
+    let peer2 = peer_manager1.find_by_node_id(node_identity2.node_id()).await.unwrap();
+    assert_eq!(peer2.addresses.addresses[0].address, additional_address);
+    assert!(peer2.addresses.addresses[0].last_seen.is_some());
+}
+
 fn count_string_occurrences<T, U>(events: &[T], expected: &[&str]) -> usize
 where
     T: AsRef<U>,
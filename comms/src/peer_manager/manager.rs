@@ -264,6 +264,20 @@ impl PeerManager {
         self.peer_storage.write().await.add_net_address(node_id, net_address)
     }
 
+    /// Update the connection stats of the peer's net addresses with the outcome of a dial
+    pub async fn record_dial_result(
+        &self,
+        node_id: &NodeId,
+        failed_addresses: &[Multiaddr],
+        successful_address: Option<&Multiaddr>,
+    ) -> Result<(), PeerManagerError>
+    {
+        self.peer_storage
+            .write()
+            .await
+            .record_dial_result(node_id, failed_addresses, successful_address)
+    }
+
     pub async fn update_each<F>(&self, mut f: F) -> Result<usize, PeerManagerError>
     where F: FnMut(Peer) -> Option<Peer> {
         let mut lock = self.peer_storage.write().await;
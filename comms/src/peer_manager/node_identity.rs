@@ -57,6 +57,9 @@ pub struct NodeIdentity {
     features: PeerFeatures,
     secret_key: CommsSecretKey,
     public_address: RwLock<Multiaddr>,
+    /// Further addresses on which this node can be reached (e.g. an onion address in addition to a clearnet address)
+    #[serde(default)]
+    additional_public_addresses: RwLock<Vec<Multiaddr>>,
 }
 
 impl NodeIdentity {
@@ -76,6 +79,7 @@ impl NodeIdentity {
             features,
             secret_key,
             public_address: RwLock::new(public_address),
+            additional_public_addresses: RwLock::new(Vec::new()),
         })
     }
 
@@ -98,6 +102,7 @@ impl NodeIdentity {
             features,
             secret_key,
             public_address: RwLock::new(public_address),
+            additional_public_addresses: RwLock::new(Vec::new()),
         })
     }
 
@@ -115,6 +120,28 @@ impl NodeIdentity {
         Ok(())
     }
 
+    /// Retrieve all publicly accessible addresses for this node. The primary public address is always first.
+    pub fn public_addresses(&self) -> Vec<Multiaddr> {
+        let mut addresses = vec![self.public_address()];
+        addresses.extend(acquire_read_lock!(self.additional_public_addresses).iter().cloned());
+        addresses
+    }
+
+    /// Add a further publicly accessible address for this node. Adding an address that is already known has no effect.
+    pub fn add_public_address(&self, address: Multiaddr) -> Result<(), NodeIdentityError> {
+        if self.public_address() == address {
+            return Ok(());
+        }
+        let mut addresses = self
+            .additional_public_addresses
+            .write()
+            .map_err(|_| NodeIdentityError::PoisonedAccess)?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+        Ok(())
+    }
+
     /// This returns a random NodeIdentity for testing purposes. This function can panic. If public_address
     /// is None, 127.0.0.1:9000 will be used (i.e. the caller doesn't care what the control_service_address is).
     #[cfg(test)]
@@ -152,13 +179,13 @@ impl NodeIdentity {
         self.features().contains(peer_features)
     }
 
-    /// Returns a Peer with the same public key, node id, public address and features as represented in this
+    /// Returns a Peer with the same public key, node id, public addresses and features as represented in this
     /// NodeIdentity. _NOTE: PeerFlags and supported_protocols are empty._
     pub fn to_peer(&self) -> Peer {
         Peer::new(
             self.public_key().clone(),
             self.node_id().clone(),
-            self.public_address().into(),
+            self.public_addresses().into(),
             PeerFlags::empty(),
             self.features(),
             &[],
@@ -174,6 +201,7 @@ impl Clone for NodeIdentity {
             features: self.features,
             secret_key: self.secret_key.clone(),
             public_address: RwLock::new(self.public_address()),
+            additional_public_addresses: RwLock::new(acquire_read_lock!(self.additional_public_addresses).clone()),
         }
     }
 }
@@ -183,6 +211,9 @@ impl fmt::Display for NodeIdentity {
         writeln!(f, "Public Key: {}", self.public_key)?;
         writeln!(f, "Node ID: {}", self.node_id)?;
         writeln!(f, "Public Address: {}", acquire_read_lock!(self.public_address))?;
+        for address in acquire_read_lock!(self.additional_public_addresses).iter() {
+            writeln!(f, "Additional Public Address: {}", address)?;
+        }
         writeln!(f, "Features: {:?}", self.features)?;
 
         Ok(())
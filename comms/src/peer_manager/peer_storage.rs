@@ -496,6 +496,35 @@ where DS: KeyValueStore<PeerId, Peer>
             .map_err(PeerManagerError::DatabaseError)
     }
 
+    /// Enables Thread safe access - Updates the connection stats of the peer's net addresses after a dial. Addresses
+    /// are scored by these stats so that addresses which can be dialed successfully are tried first.
+    pub fn record_dial_result(
+        &mut self,
+        node_id: &NodeId,
+        failed_addresses: &[Multiaddr],
+        successful_address: Option<&Multiaddr>,
+    ) -> Result<(), PeerManagerError>
+    {
+        let peer_key = *self
+            .node_id_index
+            .get(&node_id)
+            .ok_or_else(|| PeerManagerError::PeerNotFoundError)?;
+        let mut peer: Peer = self
+            .peer_db
+            .get(&peer_key)
+            .map_err(PeerManagerError::DatabaseError)?
+            .ok_or_else(|| PeerManagerError::PeerNotFoundError)?;
+        for address in failed_addresses {
+            peer.addresses.mark_failed_connection_attempt(address);
+        }
+        if let Some(address) = successful_address {
+            peer.addresses.mark_successful_connection_attempt(address);
+        }
+        self.peer_db
+            .insert(peer_key, peer)
+            .map_err(PeerManagerError::DatabaseError)
+    }
+
     /// Return some basic stats for the region surrounding the region_node_id
     pub fn get_region_stats<'a>(
         &self,
@@ -84,7 +84,11 @@ where
     // Send this node's identity
     let msg_bytes = PeerIdentityMsg {
         node_id: node_identity.node_id().to_vec(),
-        addresses: vec![node_identity.public_address().to_string()],
+        addresses: node_identity
+            .public_addresses()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
         features: node_identity.features().bits(),
         supported_protocols,
     }
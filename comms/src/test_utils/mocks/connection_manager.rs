@@ -156,6 +156,9 @@ impl ConnectionManagerMock {
                 let _ = self.state.active_conns.lock().await.remove(&node_id);
                 reply_tx.send(Ok(())).unwrap();
             },
+            SetInboundConnectionsPaused(_, reply_tx) => {
+                reply_tx.send(()).unwrap();
+            },
         }
     }
 }